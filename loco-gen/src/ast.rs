@@ -0,0 +1,237 @@
+use std::path::Path;
+
+use chrono::Utc;
+use cruet::Inflector;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    infer,
+    model::{self, get_columns_and_references},
+    render_template, AppInfo, GenerateOptions, GenerateResults, IndexSpec, Result,
+    TemplateRenderer,
+};
+
+/// A statement of a migration's `up`, see [`build_migration`]. Its `down`
+/// reverts the statements in reverse order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind")]
+pub enum MigrationStatement {
+    /// Creates `table` with its `columns` as `(name, ColType)` and its
+    /// `references` as `(field, custom column)`, along with `created_at` and
+    /// `updated_at` when `timestamps`. An `id` primary key of `pk_col_type`
+    /// leads the columns, unless a join table's references or a shared
+    /// primary key make its key.
+    CreateTable {
+        table: String,
+        columns: Vec<(String, String)>,
+        references: Vec<(String, String)>,
+        pk_col_type: Option<String>,
+        timestamps: bool,
+        join_table: bool,
+    },
+    AddColumn {
+        table: String,
+        column: String,
+        col_type: String,
+    },
+    /// Removes `column`, whose `col_type` adds it back on the way down
+    RemoveColumn {
+        table: String,
+        column: String,
+        col_type: String,
+    },
+    /// Adds a reference `field`, with an optional custom column, or only its
+    /// foreign key when the column exists already
+    AddForeignKey {
+        table: String,
+        field: String,
+        column: String,
+        column_exists: bool,
+    },
+    CreateIndex {
+        table: String,
+        name: String,
+        index: IndexSpec,
+    },
+    AddCheck {
+        table: String,
+        name: String,
+        expr: String,
+    },
+}
+
+/// A migration as the statements it runs, which [`render_migration`] renders.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Migration {
+    /// The migration's name, e.g. `CreatePosts` or `AddTitleToPosts`
+    pub name: String,
+    pub statements: Vec<MigrationStatement>,
+}
+
+/// Builds the statements of the migration `name` over `fields`, inferring
+/// what it does from its name like [`crate::Component::Migration`]. A new
+/// table gets timestamps, and the indexes and checks of `options`; its other
+/// options are not represented.
+///
+/// # Errors
+///
+/// When a field, an index or a check cannot be resolved
+pub fn build_migration(
+    name: &str,
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Migration> {
+    let mut statements = Vec::new();
+    match infer::guess_migration_type(name) {
+        infer::MigrationType::CreateTable { table } => {
+            let fields = &model::with_tenant(&table, fields, options);
+            let (mut columns, references) = get_columns_and_references(fields, options)?;
+            let requested = model::requested_indexes(fields, options)?;
+            let mut indexes = model::resolve_indexes(
+                &requested,
+                &columns,
+                &references,
+                true,
+                options.tenant.as_deref(),
+            )?;
+            // as given rather than as a Rust string literal
+            for (index, requested) in indexes.iter_mut().zip(&requested) {
+                index.comment.clone_from(&requested.comment);
+            }
+            model::soft_delete_unique(&mut columns, &mut indexes, options)?;
+            let checks =
+                model::resolve_checks(&table, &options.checks, true, &columns, &references)?;
+            let table = table.to_plural().to_snake_case();
+            let pk_col_type = if columns.is_empty() || model::shared_primary_key(&columns).is_some()
+            {
+                None
+            } else {
+                Some(options.pk_col_type()?)
+            };
+            statements.push(MigrationStatement::CreateTable {
+                table: table.clone(),
+                columns,
+                references,
+                pk_col_type,
+                timestamps: true,
+                join_table: false,
+            });
+            statements.extend(
+                indexes
+                    .into_iter()
+                    .map(|index| MigrationStatement::CreateIndex {
+                        table: table.clone(),
+                        name: index_name(&table, &index),
+                        index,
+                    }),
+            );
+            statements.extend(
+                checks
+                    .into_iter()
+                    .map(|check| MigrationStatement::AddCheck {
+                        table: table.clone(),
+                        name: check.name,
+                        expr: check.sql,
+                    }),
+            );
+        }
+        infer::MigrationType::AddColumns { table }
+        | infer::MigrationType::AddReference { table } => {
+            let (columns, references) = get_columns_and_references(fields, options)?;
+            let table = table.to_plural().to_snake_case();
+            statements.extend(columns.into_iter().map(|(column, col_type)| {
+                MigrationStatement::AddColumn {
+                    table: table.clone(),
+                    column,
+                    col_type,
+                }
+            }));
+            statements.extend(references.into_iter().map(|(field, column)| {
+                MigrationStatement::AddForeignKey {
+                    table: table.clone(),
+                    field,
+                    column,
+                    column_exists: options.column_exists,
+                }
+            }));
+        }
+        infer::MigrationType::RemoveColumns { table } => {
+            let (columns, _references) = get_columns_and_references(fields, options)?;
+            let table = table.to_plural().to_snake_case();
+            statements.extend(columns.into_iter().map(|(column, col_type)| {
+                MigrationStatement::RemoveColumn {
+                    table: table.clone(),
+                    column,
+                    col_type,
+                }
+            }));
+        }
+        infer::MigrationType::CreateJoinTable { table_a, table_b } => {
+            let (columns, extra_references) = get_columns_and_references(fields, options)?;
+            statements.push(MigrationStatement::CreateTable {
+                table: format!("{table_a}_{table_b}").to_plural().to_snake_case(),
+                columns,
+                references: model::many_to_many_references(&table_a, &table_b)
+                    .into_iter()
+                    .chain(extra_references)
+                    .collect(),
+                pk_col_type: None,
+                timestamps: true,
+                join_table: true,
+            });
+        }
+        infer::MigrationType::Empty => {}
+    }
+    Ok(Migration {
+        name: name.to_string(),
+        statements,
+    })
+}
+
+/// Renders the migration file running the statements of `migration`, e.g. as
+/// built by [`build_migration`] and transformed since.
+///
+/// # Errors
+///
+/// When the migration fails to render
+pub fn render_migration(
+    renderer: &dyn TemplateRenderer,
+    migration: &Migration,
+    options: &GenerateOptions,
+    appinfo: &AppInfo,
+) -> Result<GenerateResults> {
+    let mut statements = json!(migration.statements);
+    // the strings the migration holds as Rust string literals
+    for statement in statements.as_array_mut().into_iter().flatten() {
+        if let Some(expr) = statement["expr"].as_str().map(ToString::to_string) {
+            statement["expr"] = json!(format!("{expr:?}"));
+        }
+        if let Some(comment) = statement["index"]["comment"]
+            .as_str()
+            .map(ToString::to_string)
+        {
+            statement["index"]["comment"] = json!(format!("{comment:?}"));
+        }
+    }
+    let vars = json!({"name": migration.name, "ts": Utc::now(), "pkg_name": appinfo.app_name, "statements": statements, "migration_dir": options.migration_dir(), "metadata": options.migration_metadata, "if_exists": options.drop_if_exists});
+    render_template(renderer, Path::new("migration/statements.t"), &vars)
+}
+
+/// The name the model migration gives an index, e.g. `idx-posts-title`.
+fn index_name(table: &str, index: &IndexSpec) -> String {
+    let mut name = format!("idx-{table}");
+    for column in &index.columns {
+        name.push('-');
+        if column.contains('(') {
+            name.push_str(&column.to_snake_case());
+        } else {
+            name.push_str(column);
+        }
+    }
+    if let Some(flag) = &index.when {
+        name.push('-');
+        name.push_str(flag);
+    }
+    name
+}
@@ -1,38 +1,69 @@
-use super::{AppInfo, GenerateResults, Result};
+use super::{AppInfo, Error, GenerateOptions, GenerateResults, Result, TemplateRenderer};
 use crate as gen;
-use rrgen::RRgen;
 use serde_json::json;
 use std::path::Path;
 
+/// Renders the app's `api_docs` controller, which serves the OpenAPI spec
+/// the documented controllers merge theirs into, unless it exists already.
+///
+/// # Errors
+///
+/// When the controller fails to render
+pub(crate) fn generate_api_docs(
+    renderer: &dyn TemplateRenderer,
+    appinfo: &AppInfo,
+) -> Result<GenerateResults> {
+    let vars = json!({"pkg_name": appinfo.app_name});
+    gen::render_template(renderer, Path::new("api_docs"), &vars)
+}
+
 pub fn generate(
-    rrgen: &RRgen,
+    renderer: &dyn TemplateRenderer,
     name: &str,
     actions: &[String],
     kind: &gen::ScaffoldKind,
+    options: &GenerateOptions,
     appinfo: &AppInfo,
 ) -> Result<GenerateResults> {
-    let vars = json!({"name": name, "actions": actions, "pkg_name": appinfo.app_name});
+    if options.api_docs && !matches!(kind, gen::ScaffoldKind::Api) {
+        return Err(Error::Message(
+            "api docs are only supported by api controllers and scaffolds".to_string(),
+        ));
+    }
+    let vars = json!({"name": name, "actions": actions, "pkg_name": appinfo.app_name, "api_docs": options.api_docs});
     match kind {
-        gen::ScaffoldKind::Api => gen::render_template(rrgen, Path::new("controller/api"), &vars),
+        gen::ScaffoldKind::Api => {
+            let mut gen_result = if options.api_docs {
+                generate_api_docs(renderer, appinfo)?
+            } else {
+                GenerateResults::default()
+            };
+            gen_result.merge(gen::render_template(
+                renderer,
+                Path::new("controller/api"),
+                &vars,
+            )?);
+            Ok(gen_result)
+        }
         gen::ScaffoldKind::Html => {
             let mut gen_result =
-                gen::render_template(rrgen, Path::new("controller/html/controller.t"), &vars)?;
+                gen::render_template(renderer, Path::new("controller/html/controller.t"), &vars)?;
             for action in actions {
                 let vars = json!({"name": name, "action": action, "pkg_name": appinfo.app_name});
-                let res = gen::render_template(rrgen, Path::new("controller/html/view.t"), &vars)?;
-                gen_result.rrgen.extend(res.rrgen);
-                gen_result.local_templates.extend(res.local_templates);
+                let res =
+                    gen::render_template(renderer, Path::new("controller/html/view.t"), &vars)?;
+                gen_result.merge(res);
             }
             Ok(gen_result)
         }
         gen::ScaffoldKind::Htmx => {
             let mut gen_result =
-                gen::render_template(rrgen, Path::new("controller/htmx/controller.t"), &vars)?;
+                gen::render_template(renderer, Path::new("controller/htmx/controller.t"), &vars)?;
             for action in actions {
                 let vars = json!({"name": name, "action": action, "pkg_name": appinfo.app_name});
-                let res = gen::render_template(rrgen, Path::new("controller/htmx/view.t"), &vars)?;
-                gen_result.rrgen.extend(res.rrgen);
-                gen_result.local_templates.extend(res.local_templates);
+                let res =
+                    gen::render_template(renderer, Path::new("controller/htmx/view.t"), &vars)?;
+                gen_result.merge(res);
             }
             Ok(gen_result)
         }
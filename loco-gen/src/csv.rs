@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use heck::ToSnakeCase;
+
+use crate::{Error, Result};
+
+/// Number of rows after the header looked at to infer the column types
+pub const SAMPLE_ROWS: usize = 20;
+
+/// Infers model fields from a CSV: the header row names the fields, the first
+/// [`SAMPLE_ROWS`] rows give their types. A column is only typed `int`,
+/// `big_int`, `float`, `bool` or `date` when all of its sampled values are,
+/// anything else is a `string`. Fields are nullable, an `id` column is left
+/// to the primary key.
+///
+/// # Errors
+///
+/// When there is no header, or it has an empty or a repeated column name
+pub fn fields(content: &str) -> Result<Vec<(String, String)>> {
+    let mut rows = records(content, SAMPLE_ROWS + 1).into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| Error::Message("the CSV has no header row".to_string()))?;
+    let rows = rows.collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    let mut fields = Vec::new();
+    for (i, column) in header.iter().enumerate() {
+        let name = column.trim().to_snake_case();
+        if name.is_empty() {
+            return Err(Error::Message(format!(
+                "column {} of the CSV header has no name",
+                i + 1
+            )));
+        }
+        if !seen.insert(name.clone()) {
+            return Err(Error::Message(format!(
+                "column `{name}` appears more than once in the CSV header"
+            )));
+        }
+        if name == "id" {
+            continue;
+        }
+        let values = rows
+            .iter()
+            .filter_map(|row| row.get(i))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>();
+        fields.push((name, infer_type(&values).to_string()));
+    }
+    Ok(fields)
+}
+
+fn infer_type(values: &[&str]) -> &'static str {
+    if values.is_empty() {
+        "string"
+    } else if values.iter().all(|v| v.parse::<i32>().is_ok()) {
+        "int"
+    } else if values.iter().all(|v| v.parse::<i64>().is_ok()) {
+        "big_int"
+    } else if values.iter().all(|v| is_float(v)) {
+        "float"
+    } else if values
+        .iter()
+        .all(|v| v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("false"))
+    {
+        "bool"
+    } else if values
+        .iter()
+        .all(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").is_ok())
+    {
+        "date"
+    } else {
+        "string"
+    }
+}
+
+/// Plain decimal numbers only, `f64` would also take `inf` or `NaN`
+fn is_float(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value.parse::<f64>().is_ok()
+}
+
+/// Splits up to `limit` records off a CSV, quoted values can hold commas,
+/// newlines and `""` escaped quotes.
+fn records(content: &str, limit: usize) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut value)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut value));
+                if record.iter().any(|v| !v.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                    if records.len() == limit {
+                        return records;
+                    }
+                }
+                record.clear();
+            }
+            c => value.push(c),
+        }
+    }
+    record.push(value);
+    if records.len() < limit && record.iter().any(|v| !v.is_empty()) {
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_split_records() {
+        assert_eq!(
+            records("a,b\r\n\"x, \"\"y\"\"\",\"2\n3\"\n\n4,5", 10),
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["x, \"y\"".to_string(), "2\n3".to_string()],
+                vec!["4".to_string(), "5".to_string()],
+            ]
+        );
+        assert_eq!(records("a\n1\n2\n", 2).len(), 2);
+    }
+
+    #[test]
+    fn can_infer_types() {
+        assert_eq!(infer_type(&["1", "-2"]), "int");
+        assert_eq!(infer_type(&["1", "3000000000"]), "big_int");
+        assert_eq!(infer_type(&["1", "2.5"]), "float");
+        assert_eq!(infer_type(&["inf"]), "string");
+        assert_eq!(infer_type(&["true", "FALSE"]), "bool");
+        assert_eq!(infer_type(&["1", "true"]), "string");
+        assert_eq!(infer_type(&["2024-02-29"]), "date");
+        assert_eq!(infer_type(&["2023-02-29"]), "string");
+        assert_eq!(infer_type(&[]), "string");
+    }
+
+    #[test]
+    fn fail_bad_header() {
+        assert_eq!(
+            fields("").unwrap_err().to_string(),
+            "the CSV has no header row"
+        );
+        assert_eq!(
+            fields("name,,age").unwrap_err().to_string(),
+            "column 2 of the CSV header has no name"
+        );
+        assert_eq!(
+            fields("Name,name").unwrap_err().to_string(),
+            "column `name` appears more than once in the CSV header"
+        );
+    }
+}
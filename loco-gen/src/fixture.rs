@@ -0,0 +1,100 @@
+use std::{path::Path, sync::OnceLock};
+
+use regex::Regex;
+use serde_json::json;
+
+use crate::{render_template, GenerateResults, Result, TemplateRenderer};
+
+/// Columns the database fills in, left out of a fixture
+const SKIPPED_COLUMNS: &[&str] = &["id", "created_at", "updated_at"];
+
+fn field_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s*pub\s+(\w+)\s*:\s*(.+?),?\s*$").unwrap())
+}
+
+/// The columns of a `SeaORM` entity, by name and Rust type, read from the
+/// fields of its `Model` struct, e.g. `src/models/_entities/posts.rs`.
+#[must_use]
+pub fn entity_columns(source: &str) -> Vec<(String, String)> {
+    source
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("pub struct Model"))
+        .skip(1)
+        .take_while(|line| line.trim() != "}")
+        .filter_map(|line| field_regex().captures(line))
+        .map(|caps| (caps[1].to_string(), caps[2].to_string()))
+        .collect()
+}
+
+pub fn generate(
+    renderer: &dyn TemplateRenderer,
+    name: &str,
+    columns: &[(String, String)],
+) -> Result<GenerateResults> {
+    let fields = columns
+        .iter()
+        .filter(|(column, _)| !SKIPPED_COLUMNS.contains(&column.as_str()))
+        .map(|(column, ty)| match column.strip_suffix("_id") {
+            // a reference, to the `one` record of the referenced table
+            Some(reference) => json!({ "name": reference, "value": "one" }),
+            None => json!({ "name": column, "value": placeholder(ty) }),
+        })
+        .collect::<Vec<_>>();
+    let vars = json!({ "name": name, "fields": fields });
+    render_template(renderer, Path::new("fixture"), &vars)
+}
+
+/// A YAML value of a column's Rust type, to fill in.
+fn placeholder(ty: &str) -> &'static str {
+    if ty.starts_with("Option<") {
+        return "null";
+    }
+    match ty {
+        "String" => "\"\"",
+        "bool" => "false",
+        "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" => "0",
+        "f32" | "f64" | "Decimal" => "0.0",
+        "Uuid" => "00000000-0000-0000-0000-000000000000",
+        "Date" => "\"2024-01-01\"",
+        "DateTime" | "DateTimeWithTimeZone" | "DateTimeUtc" => "\"2024-01-01T00:00:00Z\"",
+        "Json" => "{}",
+        ty if ty.starts_with("Vec<") => "[]",
+        _ => "\"\"",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_read_entity_columns() {
+        let source = r#"
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "posts")]
+pub struct Model {
+    pub created_at: DateTimeWithTimeZone,
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub title: Option<String>,
+    pub user_id: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    User,
+}
+"#;
+        assert_eq!(
+            entity_columns(source),
+            [
+                ("created_at", "DateTimeWithTimeZone"),
+                ("id", "i32"),
+                ("title", "Option<String>"),
+                ("user_id", "i32"),
+            ]
+            .map(|(name, ty)| (name.to_string(), ty.to_string()))
+        );
+    }
+}
@@ -17,21 +17,197 @@ pub enum FieldType {
     ReferenceWithCustomField(String),
     NullableReference,
     NullableReferenceWithCustomField(String),
+    /// a reference to a table other than the field's, `references:to=<table>`,
+    /// or in another schema, `references:<schema>.<table>`
+    ReferenceToTable(String),
+    NullableReferenceToTable(String),
+    /// a reference whose column is also the primary key, `references:shared_pk`
+    SharedPrimaryKeyReference,
+    /// a column of an enum type named after the field, from an inline list of
+    /// variants, `enum!:pending,shipped`
+    Enum(Vec<String>),
+    NullableEnum(Vec<String>),
+    /// a many-to-many relation through a join table rather than a column,
+    /// to the table named after the field, `tags:many_to_many`, or to another
+    /// one, `labels:many_to_many:tags`
+    ManyToMany(Option<String>),
     Type(String),
     TypeWithParameters(String, Vec<String>),
+    /// a type with a database generated default, e.g. `uuid^=gen_random_uuid()`
+    TypeWithDefault(String, String),
+}
+
+/// Modifiers that can follow a field type, e.g. `ssn:string@encrypted`, and
+/// whether they take a value, e.g. `name:string@collate=C`,
+/// `owner:references?@default=1` or `owner:references@comment=the owner`
+pub const FIELD_MODIFIERS: &[(&str, bool)] = &[
+    ("encrypted", false),
+    ("collate", true),
+    ("default", true),
+    ("comment", true),
+];
+
+/// A field modifier's name and value
+pub type FieldModifier<'a> = (&'a str, Option<&'a str>);
+
+/// Splits the `@` modifiers off a field type, e.g. `string@encrypted`, into
+/// their name and value.
+///
+/// # Errors
+///
+/// When a modifier is not one of [`FIELD_MODIFIERS`], or its value is missing
+/// or unexpected
+pub fn split_modifiers(ftype: &str) -> Result<(&str, Vec<FieldModifier<'_>>)> {
+    let mut parts = ftype.split('@');
+    let ftype = parts.next().unwrap_or_default();
+    let mut modifiers = Vec::new();
+    for part in parts {
+        let (name, value) = part
+            .split_once('=')
+            .map_or((part, None), |(name, value)| (name, Some(value)));
+        match FIELD_MODIFIERS.iter().find(|(m, _)| *m == name) {
+            Some((_, true)) if value.map_or(true, str::is_empty) => {
+                return Err(Error::Message(format!(
+                    "field modifier `{name}` in `{ftype}` requires a value, e.g. `@{name}=<value>`"
+                )));
+            }
+            Some((_, false)) if value.is_some() => {
+                return Err(Error::Message(format!(
+                    "field modifier `{name}` in `{ftype}` does not take a value"
+                )));
+            }
+            Some(_) => modifiers.push((name, value)),
+            None => {
+                return Err(Error::Message(format!(
+                    "unknown field modifier: `{name}` in `{ftype}`, expected one of: {}",
+                    FIELD_MODIFIERS
+                        .iter()
+                        .map(|(m, _)| *m)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        }
+    }
+    Ok((ftype, modifiers))
+}
+
+/// The value of a modifier, e.g. `C` for `@collate=C`
+#[must_use]
+pub fn modifier_value<'a>(modifiers: &[FieldModifier<'a>], name: &str) -> Option<&'a str> {
+    modifiers
+        .iter()
+        .find_map(|(m, value)| if *m == name { *value } else { None })
+}
+
+/// Splits the literal default off a field type, e.g. `string!:default=draft`.
+/// Everything after `default=` is the value, which can be quoted to keep its
+/// spaces or for an empty string: `string:default="in review"`. An inline
+/// enum can also give its default variant after the list, e.g.
+/// `enum:draft,published=draft`.
+///
+/// # Errors
+///
+/// When the default is empty, or a quoted default is not closed
+pub fn split_default(ftype: &str) -> Result<(&str, Option<String>)> {
+    let Some((ftype, value)) = ftype.split_once(":default=") else {
+        if ftype.starts_with("enum:") || ftype.starts_with("enum!:") {
+            if let Some((ftype, variant)) = ftype.split_once('=') {
+                return Ok((ftype, Some(variant.to_string())));
+            }
+        }
+        return Ok((ftype, None));
+    };
+    let value = match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => unquote(value, quote).ok_or_else(|| {
+            Error::Message(format!(
+                "cannot parse default: `{value}`, expected a value between matching quotes"
+            ))
+        })?,
+        Some(_) => value.to_string(),
+        None => {
+            return Err(Error::Message(format!(
+                "default of `{ftype}` is empty, quote it for an empty string: `default=\"\"`"
+            )))
+        }
+    };
+    Ok((ftype, Some(value)))
+}
+
+/// `"in \"review\""` -> `in "review"`, a backslash escapes the next character
+fn unquote(value: &str, quote: char) -> Option<String> {
+    let mut unquoted = String::new();
+    let mut chars = value.chars().skip(1);
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.push(chars.next()?),
+            c if c == quote => return chars.next().is_none().then_some(unquoted),
+            c => unquoted.push(c),
+        }
+    }
+    None
+}
+
+/// Splits the `:index` flag off a field type, e.g. `string!:index`, which
+/// indexes the field's column. It follows the type and its parameters,
+/// before a `:default=`.
+#[must_use]
+pub fn split_index(ftype: &str) -> (&str, bool) {
+    ftype
+        .strip_suffix(":index")
+        .map_or((ftype, false), |ftype| (ftype, true))
 }
 
 pub fn parse_field_type(ftype: &str) -> Result<FieldType> {
+    let (ftype, _modifiers) = split_modifiers(ftype)?;
+    let (ftype, _default) = split_default(ftype)?;
+    let (ftype, _index) = split_index(ftype);
     let parts: Vec<&str> = ftype.split(':').collect();
 
     match parts.as_slice() {
         ["references?"] => Ok(FieldType::NullableReference),
+        ["references?", f] if f.starts_with("to=") => Ok(FieldType::NullableReferenceToTable(
+            parse_target_table(&f["to=".len()..])?,
+        )),
+        ["references?", f] if f.contains('.') => Ok(FieldType::NullableReferenceToTable(
+            parse_qualified_table(f)?,
+        )),
         ["references?", f] => Ok(FieldType::NullableReferenceWithCustomField(
             (*f).to_string(),
         )),
         ["references"] => Ok(FieldType::Reference),
+        ["references", f] if f.starts_with("to=") => Ok(FieldType::ReferenceToTable(
+            parse_target_table(&f["to=".len()..])?,
+        )),
+        ["references", f] if f.contains('.') => {
+            Ok(FieldType::ReferenceToTable(parse_qualified_table(f)?))
+        }
+        ["references", "shared_pk"] => Ok(FieldType::SharedPrimaryKeyReference),
         ["references", f] => Ok(FieldType::ReferenceWithCustomField((*f).to_string())),
-        [t] => Ok(FieldType::Type((*t).to_string())),
+        ["enum" | "enum!"] | ["enum" | "enum!", ""] => Err(Error::Message(format!(
+            "type: `{}` requires a list of variants, e.g. `{}:pending,shipped`",
+            parts[0], parts[0]
+        ))),
+        ["many_to_many"] => Ok(FieldType::ManyToMany(None)),
+        ["many_to_many", table] if is_ident(table) => {
+            Ok(FieldType::ManyToMany(Some((*table).to_string())))
+        }
+        ["many_to_many", table] => Err(Error::Message(format!(
+            "cannot parse many-to-many target: `{table}`, expected a table such as `tags`"
+        ))),
+        ["enum", variants] => Ok(FieldType::NullableEnum(
+            variants.split(',').map(ToString::to_string).collect(),
+        )),
+        ["enum!", variants] => Ok(FieldType::Enum(
+            variants.split(',').map(ToString::to_string).collect(),
+        )),
+        [t] => match t.split_once('=') {
+            Some((t, default)) => Ok(FieldType::TypeWithDefault(
+                t.to_string(),
+                parse_function_default(default)?,
+            )),
+            None => Ok(FieldType::Type((*t).to_string())),
+        },
         [t, params @ ..] => Ok(FieldType::TypeWithParameters(
             (*t).to_string(),
             params.iter().map(ToString::to_string).collect::<Vec<_>>(),
@@ -39,6 +215,89 @@ pub fn parse_field_type(ftype: &str) -> Result<FieldType> {
         [] => Err(Error::Message(format!("cannot parse type: `{ftype}`"))),
     }
 }
+
+/// Validates a default that is a database function call, e.g.
+/// `gen_random_uuid()`, which ends up verbatim in the column definition. The
+/// one call taking a quoted argument is `nextval('<sequence>')`.
+fn parse_function_default(default: &str) -> Result<String> {
+    if let Some(sequence) = default_sequence(default) {
+        return parse_sequence_name(sequence).map(|_| default.to_string());
+    }
+    let is_call = default
+        .strip_suffix(')')
+        .and_then(|call| call.split_once('('))
+        .is_some_and(|(func, args)| {
+            !func.is_empty()
+                && func.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !args.contains(['\'', '"', ';', '(', ')'])
+        });
+    if is_call {
+        Ok(default.to_string())
+    } else {
+        Err(Error::Message(format!(
+            "cannot parse default: `{default}`, expected an unquoted function call such as \
+             `gen_random_uuid()`"
+        )))
+    }
+}
+
+/// The sequence a `nextval('<sequence>')` default draws from
+pub fn default_sequence(default: &str) -> Option<&str> {
+    default
+        .strip_prefix("nextval('")
+        .and_then(|rest| rest.strip_suffix("')"))
+}
+
+/// Validates a sequence name, optionally in a schema, e.g. `invoice_numbers`
+/// or `billing.invoice_numbers`.
+fn parse_sequence_name(sequence: &str) -> Result<&str> {
+    let mut parts = sequence.split('.');
+    let valid = match (parts.next(), parts.next(), parts.next()) {
+        (Some(name), None, _) => is_ident(name),
+        (Some(schema), Some(name), None) => is_ident(schema) && is_ident(name),
+        _ => false,
+    };
+    if valid && sequence.len() <= 63 {
+        Ok(sequence)
+    } else {
+        Err(Error::Message(format!(
+            "invalid sequence name: `{sequence}`, expected an identifier such as \
+             `invoice_numbers`, optionally in a schema"
+        )))
+    }
+}
+
+fn is_ident(s: &str) -> bool {
+    s.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Validates the `<table>` of a `to=<table>` reference target, which can be in
+/// another schema too.
+fn parse_target_table(target: &str) -> Result<String> {
+    if target.contains('.') {
+        parse_qualified_table(target)
+    } else if is_ident(target) {
+        Ok(target.to_string())
+    } else {
+        Err(Error::Message(format!(
+            "cannot parse reference target: `{target}`, expected a table such as `users`"
+        )))
+    }
+}
+
+/// Validates a `<schema>.<table>` reference target.
+fn parse_qualified_table(target: &str) -> Result<String> {
+    match target.split_once('.') {
+        Some((schema, table)) if is_ident(schema) && is_ident(table) => Ok(target.to_string()),
+        _ => Err(Error::Message(format!(
+            "cannot parse reference target: `{target}`, expected `<schema>.<table>`"
+        ))),
+    }
+}
+
 pub fn guess_migration_type(migration_name: &str) -> MigrationType {
     let normalized_name = to_snake_case(migration_name);
     let parts: Vec<&str> = normalized_name.split('_').collect();
@@ -215,4 +474,177 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_parse_type_with_default() {
+        assert!(matches!(
+            parse_field_type("uuid^=gen_random_uuid()"),
+            Ok(FieldType::TypeWithDefault(ftype, default))
+                if ftype == "uuid^" && default == "gen_random_uuid()"
+        ));
+        for default in [
+            "'gen_random_uuid()'",
+            "\"gen_random_uuid()\"",
+            "gen_random_uuid",
+            "()",
+            "gen_random_uuid(); DROP TABLE users; --()",
+        ] {
+            assert!(
+                parse_field_type(&format!("uuid^={default}")).is_err(),
+                "{default} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_default() {
+        assert_eq!(split_default("int").unwrap(), ("int", None));
+        assert_eq!(
+            split_default("string!:default=draft").unwrap(),
+            ("string!", Some("draft".to_string()))
+        );
+        assert_eq!(
+            split_default(r#"string:255:default="in \"review\", later""#).unwrap(),
+            ("string:255", Some(r#"in "review", later"#.to_string()))
+        );
+        assert_eq!(
+            split_default("string:default=''").unwrap(),
+            ("string", Some(String::new()))
+        );
+        assert_eq!(
+            split_default("enum!:draft,published=draft").unwrap(),
+            ("enum!:draft,published", Some("draft".to_string()))
+        );
+        assert!(split_default("string:default=").is_err());
+        assert!(split_default(r#"string:default="draft"#).is_err());
+        assert!(split_default(r#"string:default="draft"s"#).is_err());
+        assert!(matches!(
+            parse_field_type("int!:default=0"),
+            Ok(FieldType::Type(ftype)) if ftype == "int!"
+        ));
+    }
+
+    #[test]
+    fn test_split_index() {
+        assert_eq!(split_index("string"), ("string", false));
+        assert_eq!(split_index("string!:255:index"), ("string!:255", true));
+        assert!(matches!(
+            parse_field_type("references:index"),
+            Ok(FieldType::Reference)
+        ));
+        assert!(matches!(
+            parse_field_type("string:index:default=draft"),
+            Ok(FieldType::Type(ftype)) if ftype == "string"
+        ));
+    }
+
+    #[test]
+    fn test_parse_sequence_default() {
+        for (ftype, sequence) in [
+            ("int!=nextval('invoice_numbers')", "invoice_numbers"),
+            (
+                "big_int^=nextval('billing.invoice_numbers')",
+                "billing.invoice_numbers",
+            ),
+        ] {
+            let Ok(FieldType::TypeWithDefault(_, default)) = parse_field_type(ftype) else {
+                panic!("{ftype} should parse");
+            };
+            assert_eq!(default_sequence(&default), Some(sequence));
+        }
+        for sequence in ["", "1st", "a.b.c", "seq'); DROP TABLE users; --", "my-seq"] {
+            let Err(err) = parse_field_type(&format!("int!=nextval('{sequence}')")) else {
+                panic!("{sequence} should be rejected");
+            };
+            assert!(
+                err.to_string()
+                    .starts_with(&format!("invalid sequence name: `{sequence}`")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_qualified_reference() {
+        assert!(matches!(
+            parse_field_type("references:tenant.organizations"),
+            Ok(FieldType::ReferenceToTable(target)) if target == "tenant.organizations"
+        ));
+        assert!(matches!(
+            parse_field_type("references?:tenant.organizations"),
+            Ok(FieldType::NullableReferenceToTable(target)) if target == "tenant.organizations"
+        ));
+        for target in [
+            "tenant.",
+            ".organizations",
+            "ten-ant.organizations",
+            "a.b.c",
+        ] {
+            assert!(
+                parse_field_type(&format!("references:{target}")).is_err(),
+                "{target} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_reference_to_table() {
+        assert!(matches!(
+            parse_field_type("references:to=categories"),
+            Ok(FieldType::ReferenceToTable(target)) if target == "categories"
+        ));
+        assert!(matches!(
+            parse_field_type("references?:to=tenant.organizations"),
+            Ok(FieldType::NullableReferenceToTable(target)) if target == "tenant.organizations"
+        ));
+        // a custom column as before
+        assert!(matches!(
+            parse_field_type("references:authored_by"),
+            Ok(FieldType::ReferenceWithCustomField(column)) if column == "authored_by"
+        ));
+        for target in ["", "user-groups", "a.b.c"] {
+            assert!(
+                parse_field_type(&format!("references:to={target}")).is_err(),
+                "{target} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_many_to_many() {
+        assert!(matches!(
+            parse_field_type("many_to_many"),
+            Ok(FieldType::ManyToMany(None))
+        ));
+        assert!(matches!(
+            parse_field_type("many_to_many:tags"),
+            Ok(FieldType::ManyToMany(Some(table))) if table == "tags"
+        ));
+        for target in ["", "tenant.tags"] {
+            assert!(
+                parse_field_type(&format!("many_to_many:{target}")).is_err(),
+                "{target} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_field_modifiers() {
+        assert_eq!(
+            split_modifiers("string!@encrypted").unwrap(),
+            ("string!", vec![("encrypted", None)])
+        );
+        assert_eq!(
+            split_modifiers("string@collate=C@encrypted").unwrap(),
+            ("string", vec![("collate", Some("C")), ("encrypted", None)])
+        );
+        assert!(split_modifiers("string@collate").is_err());
+        assert!(split_modifiers("string@encrypted=yes").is_err());
+        assert_eq!(split_modifiers("string").unwrap(), ("string", vec![]));
+        assert!(matches!(
+            parse_field_type("string@encrypted"),
+            Ok(FieldType::Type(ftype)) if ftype == "string"
+        ));
+        assert!(split_modifiers("string@compressed").is_err());
+    }
 }
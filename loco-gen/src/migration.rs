@@ -1,11 +1,16 @@
-use std::path::Path;
+use std::{fs, path::Path};
 
 use chrono::Utc;
-use rrgen::RRgen;
-use serde_json::json;
+use cruet::Inflector;
+use regex::Regex;
+use rrgen::GenResult;
+use serde_json::{json, Value};
 
 use crate::{
-    infer, model::get_columns_and_references, render_template, AppInfo, GenerateResults, Result,
+    infer,
+    model::{self, get_columns_and_references, resolve_indexes},
+    render_template, template, template_content, undo, AppInfo, Diff, Error, GenerateOptions,
+    GenerateResults, Result, TemplateRenderer,
 };
 
 /// skipping some fields from the generated models.
@@ -14,53 +19,436 @@ use crate::{
 pub const IGNORE_FIELDS: &[&str] = &["created_at", "updated_at", "create_at", "update_at"];
 
 pub fn generate(
-    rrgen: &RRgen,
+    renderer: &dyn TemplateRenderer,
     name: &str,
     with_tz: bool,
     fields: &[(String, String)],
+    options: &GenerateOptions,
     appinfo: &AppInfo,
 ) -> Result<GenerateResults> {
     let pkg_name: &str = &appinfo.app_name;
     let ts = Utc::now();
+    let migration_dir = options.migration_dir();
 
     let res = infer::guess_migration_type(name);
-    match res {
+    let mut identifier_warnings = Vec::new();
+    let (template, mut vars) = match res {
         // NOTE: re-uses the 'new model' migration template!
         infer::MigrationType::CreateTable { table } => {
-            let (columns, references) = get_columns_and_references(fields)?;
-            let vars = json!({"name": table, "ts": ts, "with_tz": with_tz,"pkg_name": pkg_name, "is_link": false, "columns": columns, "references": references});
-            render_template(rrgen, Path::new("model/model.t"), &vars)
+            options.check_backend()?;
+            let fields = &model::with_tenant(&table, fields, options);
+            let (mut columns, references) = get_columns_and_references(fields, options)?;
+            model::check_primary_key(&table, &columns, options)?;
+            identifier_warnings =
+                model::check_identifiers(&table, true, &columns, &references, options)?;
+            columns.extend(model::db_created_at(with_tz, options));
+            if let Some(column) = model::history_column(&columns, options)? {
+                columns.push(column);
+            }
+            let mut indexes = resolve_indexes(
+                &model::requested_indexes(fields, options)?,
+                &columns,
+                &references,
+                with_tz,
+                options.tenant.as_deref(),
+            )?;
+            if let Some(column) = options.content_hash.as_deref() {
+                model::content_hash(fields, column, options)?.add_to(&mut columns, &mut indexes);
+            }
+            model::soft_delete_unique(&mut columns, &mut indexes, options)?;
+            let counter_caches =
+                model::resolve_counter_caches(&table, &options.counter_caches, &references)?;
+            let collated_indexes =
+                model::resolve_collated_indexes(&options.collated_indexes, &columns)?;
+            let column_storages =
+                model::resolve_column_storages(&options.column_storages, &columns)?;
+            let denormalized = model::resolve_denormalized(&options.denormalized, &columns)?;
+            let composite_types =
+                model::resolve_composite_types(&options.composite_types, &columns)?;
+            let checks =
+                model::resolve_checks(&table, &options.checks, with_tz, &columns, &references)?;
+            let no_overlaps =
+                model::resolve_no_overlaps(&table, &options.no_overlaps, &columns, &references)?;
+            let vars = json!({"name": table, "ts": ts, "with_tz": with_tz,"pkg_name": pkg_name, "is_link": false, "columns": columns, "references": references, "pk_col_type": options.pk_col_type()?, "indexes": indexes, "collated_indexes": collated_indexes, "column_storages": column_storages, "checks": checks, "no_overlaps": no_overlaps, "counter_caches": counter_caches, "denormalized": denormalized, "composite_types": composite_types, "concurrently": options.concurrently, "in_transaction": options.migration_in_transaction(&indexes)?, "unlogged": options.unlogged, "without_rowid": options.without_rowid, "sqlite_strict": options.sqlite_strict, "history": options.history, "sequences": model::sequences(fields, options)?, "enum_types": model::new_enum_types(&columns, options)?, "shared_pk": model::shared_primary_key(&columns), "fk_comments": model::foreign_key_comments(fields)?, "migration_dir": migration_dir});
+            ("model/model.t", vars)
         }
         infer::MigrationType::AddColumns { table } => {
-            let (columns, references) = get_columns_and_references(fields)?;
-            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "is_link": false, "columns": columns, "references": references});
-            render_template(rrgen, Path::new("migration/add_columns.t"), &vars)
+            let (columns, references) = get_columns_and_references(fields, options)?;
+            identifier_warnings =
+                model::check_identifiers(&table, false, &columns, &references, options)?;
+            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "is_link": false, "columns": columns, "references": references, "migration_dir": migration_dir});
+            ("migration/add_columns.t", vars)
         }
         infer::MigrationType::RemoveColumns { table } => {
-            let (columns, _references) = get_columns_and_references(fields)?;
-            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "columns": columns});
-            render_template(rrgen, Path::new("migration/remove_columns.t"), &vars)
+            let (columns, _references) = get_columns_and_references(fields, options)?;
+            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "columns": columns, "migration_dir": migration_dir});
+            ("migration/remove_columns.t", vars)
         }
         infer::MigrationType::AddReference { table } => {
-            let (columns, references) = get_columns_and_references(fields)?;
-            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "columns": columns, "references": references});
-            render_template(rrgen, Path::new("migration/add_references.t"), &vars)
+            let (columns, references) = get_columns_and_references(fields, options)?;
+            identifier_warnings =
+                model::check_identifiers(&table, false, &columns, &references, options)?;
+            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "columns": columns, "references": references, "column_exists": options.column_exists, "migration_dir": migration_dir});
+            ("migration/add_references.t", vars)
         }
         infer::MigrationType::CreateJoinTable { table_a, table_b } => {
             let table = format!("{table_a}_{table_b}");
-            let (columns, extra_references) = get_columns_and_references(fields)?;
+            let (columns, extra_references) = get_columns_and_references(fields, options)?;
 
-            let references = [(table_a, String::new()), (table_b, String::new())]
+            let references = model::many_to_many_references(&table_a, &table_b)
                 .into_iter()
                 .chain(extra_references)
                 .collect::<Vec<_>>();
+            identifier_warnings =
+                model::check_identifiers(&table.to_plural(), true, &columns, &references, options)?;
 
-            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "columns": columns, "references": references});
-            render_template(rrgen, Path::new("migration/join_table.t"), &vars)
+            let vars = json!({"name": name, "table": table, "ts": ts, "pkg_name": pkg_name, "columns": columns, "references": references, "migration_dir": migration_dir});
+            ("migration/join_table.t", vars)
         }
         infer::MigrationType::Empty => {
-            let vars = json!({"name": name, "ts": ts, "pkg_name": pkg_name});
-            render_template(rrgen, Path::new("migration/empty.t"), &vars)
+            let vars = json!({"name": name, "ts": ts, "pkg_name": pkg_name, "migration_dir": migration_dir});
+            ("migration/empty.t", vars)
         }
+    };
+    vars["metadata"] = json!(options.migration_metadata);
+    vars["if_exists"] = json!(options.drop_if_exists);
+    let mut gen_result = if options.append_to_latest {
+        append_to_latest(Path::new(template), &vars, &migration_dir)?
+    } else {
+        render_template(renderer, Path::new(template), &vars)?
+    };
+    gen_result
+        .warnings
+        .extend(model::redundant_field_warnings(fields, options));
+    gen_result.warnings.extend(identifier_warnings);
+    Ok(gen_result)
+}
+
+/// Renders a migration template and appends its statements to the latest
+/// migration in `migration_dir` instead of writing a new migration: `up` runs
+/// the new statements last and `down` reverts them first.
+///
+/// Like the `skip_glob` of the templates, the migration is looked up relative
+/// to the current directory.
+pub(crate) fn append_to_latest(
+    template: &Path,
+    vars: &Value,
+    migration_dir: &str,
+) -> Result<GenerateResults> {
+    let mut local_templates = vec![];
+    let mut additions = vec![];
+    for template in template::collect_files_from_path(template)? {
+        let (content, custom_template) = template_content(template)?;
+        local_templates.extend(custom_template);
+        additions.push(undo::describe(&content, vars)?.content);
+    }
+
+    let src = Path::new(migration_dir).join("src");
+    let latest = fs::read_dir(&src)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| crate::migration_file_regex().is_match(name))
+        .max()
+        .ok_or_else(|| {
+            Error::Message(format!(
+                "there is no migration to append to in {}",
+                src.display()
+            ))
+        })?;
+    let path = src.join(&latest);
+
+    let mut migration = fs::read_to_string(&path)?;
+    for addition in &additions {
+        migration = merge_migrations(&migration, addition)
+            .map_err(|err| Error::Message(format!("cannot append to {latest}: {err}")))?;
+    }
+    fs::write(&path, migration)?;
+
+    Ok(GenerateResults {
+        rrgen: vec![GenResult::Generated {
+            message: Some(format!(
+                "Appended to the migration `{latest}`, roll it back first if it was applied already."
+            )),
+        }],
+        local_templates,
+        // the migration existed before, there is no file to take back
+        artifacts: vec![],
+        warnings: vec![],
+    })
+}
+
+/// Merges the statements of the `addition` migration into `migration`, along
+/// with the imports it is missing.
+fn merge_migrations(migration: &str, addition: &str) -> Result<String> {
+    let (up_start, up_end) = fn_body(migration, "up")?;
+    let (down_start, down_end) = fn_body(migration, "down")?;
+    let (new_up_start, new_up_end) = fn_body(addition, "up")?;
+    let (new_down_start, new_down_end) = fn_body(addition, "down")?;
+
+    let up = merged_body(&[
+        &migration[up_start..up_end],
+        &addition[new_up_start..new_up_end],
+    ]);
+    let down = merged_body(&[
+        &addition[new_down_start..new_down_end],
+        &migration[down_start..down_end],
+    ]);
+
+    // replace the later body first so the offsets of the other one hold
+    let mut merged = migration.to_string();
+    if up_start < down_start {
+        merged.replace_range(down_start..down_end, &down);
+        merged.replace_range(up_start..up_end, &up);
+    } else {
+        merged.replace_range(up_start..up_end, &up);
+        merged.replace_range(down_start..down_end, &down);
+    }
+
+    let missing_imports = addition
+        .lines()
+        .filter(|line| line.starts_with("use ") && !merged.lines().any(|l| l == *line))
+        .collect::<Vec<_>>();
+    if !missing_imports.is_empty() {
+        let at = merged
+            .lines()
+            .take_while(|line| line.starts_with("use ") || line.trim().is_empty())
+            .map(|line| line.len() + 1)
+            .sum::<usize>();
+        let at = merged[..at.min(merged.len())].trim_end().len();
+        merged.insert_str(at, &format!("\n{}", missing_imports.join("\n")));
+    }
+    Ok(merged)
+}
+
+/// Joins function bodies into one running their statements in order.
+fn merged_body(bodies: &[&str]) -> String {
+    let mut out = String::new();
+    for body in bodies {
+        let body = body.trim();
+        let body = body.strip_suffix("Ok(())").unwrap_or(body).trim_end();
+        if body.is_empty() {
+            continue;
+        }
+        out.push_str("\n        ");
+        out.push_str(body);
+        if body.ends_with(".await") {
+            out.push_str("?;");
+        } else if !body.ends_with(';') && !body.ends_with('}') {
+            out.push(';');
+        }
+        out.push('\n');
+    }
+    out.push_str("\n        Ok(())\n    ");
+    out
+}
+
+/// Whether a raw string literal, `r"..."` or `r#"..."#`, starts at `at`.
+fn is_raw_string(source: &str, at: usize) -> bool {
+    let starts_token = source[..at]
+        .chars()
+        .next_back()
+        .map_or(true, |c| !c.is_alphanumeric() && c != '_');
+    starts_token && source[at + 1..].trim_start_matches('#').starts_with('"')
+}
+
+/// Finds the body of `fn {name}`, between its braces.
+fn fn_body(source: &str, name: &str) -> Result<(usize, usize)> {
+    let signature = format!("fn {name}(");
+    let not_found = || Error::Message(format!("no `{name}` function found"));
+    let at = source.find(&signature).ok_or_else(not_found)?;
+    let open = at + source[at..].find('{').ok_or_else(not_found)?;
+
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok((open + 1, i));
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i += source[i..].find('\n').unwrap_or(source.len() - i);
+            }
+            b'r' if is_raw_string(source, i) => {
+                let hashes = source[i + 1..].chars().take_while(|c| *c == '#').count();
+                let closing = format!("\"{}", "#".repeat(hashes));
+                let start = i + 2 + hashes;
+                i = start + source[start..].find(&closing).ok_or_else(not_found)? + closing.len()
+                    - 1;
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Err(not_found())
+}
+
+/// Compares a model's fields with the table its existing migration creates,
+/// e.g. in CI to catch a field spec that drifted from the schema. The `id`
+/// and the timestamps are left aside.
+///
+/// # Errors
+///
+/// When the fields cannot be parsed, or the migration cannot be read or does
+/// not create the model's table
+pub fn check_drift(
+    name: &str,
+    fields: &[(String, String)],
+    migration_path: &Path,
+) -> Result<Vec<Diff>> {
+    let table = name.to_plural().to_snake_case();
+    let source = fs::read_to_string(migration_path)?;
+    let (start, end) = fn_body(&source, "up")?;
+    let migrated = created_columns(&source[start..end], &table).ok_or_else(|| {
+        Error::Message(format!(
+            "migration `{}` does not create `{table}`",
+            migration_path.display()
+        ))
+    })?;
+
+    let (columns, references) = get_columns_and_references(fields, &GenerateOptions::default())?;
+    let declared = columns
+        .into_iter()
+        .chain(references.iter().map(|(fname, custom)| {
+            (
+                model::reference_column(fname, custom),
+                format!("references:{fname}"),
+            )
+        }))
+        .collect::<Vec<_>>();
+
+    let mut diffs = Vec::new();
+    for (column, col_type) in &migrated {
+        match declared.iter().find(|(name, _)| name == column) {
+            None => diffs.push(Diff::Removed {
+                column: column.clone(),
+                col_type: col_type.clone(),
+            }),
+            Some((_, declared_type)) if declared_type != col_type => diffs.push(Diff::Changed {
+                column: column.clone(),
+                migration: col_type.clone(),
+                fields: declared_type.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (column, col_type) in declared {
+        if !migrated.iter().any(|(name, _)| *name == column) {
+            diffs.push(Diff::Added { column, col_type });
+        }
+    }
+    Ok(diffs)
+}
+
+/// Reads the columns and references a `create_table` of `table` declares,
+/// as generated: one `("name", ColType::Type),` or `("user", ""),` a line.
+/// References are keyed by their foreign key column.
+fn created_columns(up: &str, table: &str) -> Option<Vec<(String, String)>> {
+    let create = Regex::new(&format!(r#"create_\w*table\w*\(\s*m,\s*"{table}""#)).ok()?;
+    let call = &up[create.find(up)?.end()..];
+    let call = &call[..call.find(").await").unwrap_or(call.len())];
+
+    let mut columns = Vec::new();
+    for line in call.lines() {
+        let Some(tuple) = line
+            .trim()
+            .strip_prefix("(\"")
+            .and_then(|line| line.strip_suffix("),"))
+        else {
+            continue;
+        };
+        if let Some((column, col_type)) = tuple.split_once("\", ColType::") {
+            if column != "id" && !IGNORE_FIELDS.contains(&column) {
+                columns.push((column.to_string(), col_type.to_string()));
+            }
+        } else if let Some((fname, custom)) = tuple.split_once("\", \"") {
+            let custom = custom.trim_end_matches('"');
+            columns.push((
+                model::reference_column(fname, custom),
+                format!("references:{fname}"),
+            ));
+        }
+    }
+    Some(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY: &str = r#"use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        let _ = "{";
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+"#;
+
+    const ADD_COLUMN: &str = r#"use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "users", "age", ColType::Integer).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "users", "age").await?;
+        Ok(())
+    }
+}
+"#;
+
+    #[test]
+    fn test_merge_migrations() {
+        let merged = merge_migrations(EMPTY, ADD_COLUMN).expect("Failed to merge");
+        assert!(merged.starts_with(
+            "use sea_orm_migration::{prelude::*, schema::*};\nuse loco_rs::schema::*;\nuse sea_orm_migration::prelude::*;\n\n"
+        ));
+        assert!(merged.contains(
+            "        let _ = \"{\";\n\n        add_column(m, \"users\", \"age\", ColType::Integer).await?;\n\n        Ok(())\n    }"
+        ));
+        assert!(merged.contains(
+            "        remove_column(m, \"users\", \"age\").await?;\n\n        Ok(())\n    }"
+        ));
+    }
+
+    #[test]
+    fn test_merge_migrations_without_down() {
+        let existing = EMPTY.replace("    async fn down", "    async fn other");
+        assert_eq!(
+            merge_migrations(&existing, ADD_COLUMN)
+                .expect_err("Expected an error")
+                .to_string(),
+            "no `down` function found"
+        );
     }
 }
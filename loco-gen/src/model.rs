@@ -1,13 +1,22 @@
-use std::{collections::HashMap, env::current_dir, path::Path};
+use std::{collections::HashMap, env::current_dir, fs, path::Path};
 
 use chrono::Utc;
+use cruet::Inflector;
 use duct::cmd;
 use heck::ToUpperCamelCase;
-use rrgen::RRgen;
-use serde_json::json;
+use rrgen::GenResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
 use crate::{
-    get_mappings, infer::parse_field_type, render_template, AppInfo, Error, GenerateResults, Result,
+    get_mappings,
+    infer::{
+        default_sequence, modifier_value, parse_field_type, split_default, split_index,
+        split_modifiers, FieldModifier,
+    },
+    migration, render_template, AppInfo, CollatedIndex, ColumnStorage, CompositeType, CounterCache,
+    DbBackend, Denormalized, Diagnostic, Error, GenerateMode, GenerateOptions, GenerateResults,
+    IndexSpec, ModelDiagnostic, NoOverlap, Result, TemplateRenderer,
 };
 
 /// skipping some fields from the generated models.
@@ -15,6 +24,17 @@ use crate::{
 /// generated by the Loco app and should be given
 pub const IGNORE_FIELDS: &[&str] = &["created_at", "updated_at", "create_at", "update_at"];
 
+/// The fields of the `api_keys` table, as `loco_rs::auth::apikey` reads and
+/// writes it: the key is only kept hashed, its scopes space separated.
+pub const API_KEY_FIELDS: &[(&str, &str)] = &[
+    ("name", "string!"),
+    ("prefix", "string!"),
+    ("key_hash", "string^"),
+    ("scopes", "text"),
+    ("expires_at", "tstz"),
+    ("revoked_at", "tstz"),
+];
+
 /// columns are <name>, <dbtype>: ("content", "string")
 /// references are <to table, id col in from table>: ("user", `user_id`)
 ///  parsed from e.g.: model article content:string user:references
@@ -22,18 +42,48 @@ pub const IGNORE_FIELDS: &[&str] = &["created_at", "updated_at", "create_at", "u
 #[allow(clippy::type_complexity)]
 pub fn get_columns_and_references(
     fields: &[(String, String)],
+    options: &GenerateOptions,
 ) -> Result<(Vec<(String, String)>, Vec<(String, String)>)> {
     let mut columns = Vec::new();
     let mut references = Vec::new();
     for (fname, ftype) in fields {
-        if IGNORE_FIELDS.contains(&fname.as_str()) {
+        if options.ignores_field(fname) {
             tracing::warn!(
                 field = fname,
                 "note that a redundant field was specified, it is already generated automatically"
             );
             continue;
         }
+        let (base, modifiers) = split_modifiers(ftype)?;
+        let (_, default) = split_default(base)?;
         let field_type = parse_field_type(ftype)?;
+        if default.is_some()
+            && !matches!(
+                field_type,
+                crate::infer::FieldType::Type(_)
+                    | crate::infer::FieldType::TypeWithParameters(..)
+                    | crate::infer::FieldType::Enum(_)
+                    | crate::infer::FieldType::NullableEnum(_)
+            )
+        {
+            return Err(Error::Message(format!(
+                "field `{fname}` cannot have a `default=`, only value fields can, references \
+                 take `@default=<id>`"
+            )));
+        }
+        if modifier_value(&modifiers, "comment").is_some()
+            && field_reference_column(fname, &field_type).is_none()
+        {
+            return Err(Error::Message(format!(
+                "field `{fname}` is not a reference, `@comment` only applies to the foreign key \
+                 of a reference"
+            )));
+        }
+        if let Some(default) = modifier_value(&modifiers, "default") {
+            // declaring the foreign key column up front gives it the default,
+            // `create_table` then only adds the constraint
+            columns.push(reference_with_default(fname, &field_type, default)?);
+        }
         match field_type {
             crate::infer::FieldType::Reference => {
                 // (users, "")
@@ -42,20 +92,180 @@ pub fn get_columns_and_references(
             crate::infer::FieldType::ReferenceWithCustomField(refname) => {
                 references.push((fname.clone(), refname.clone()));
             }
+            crate::infer::FieldType::SharedPrimaryKeyReference => {
+                // the foreign key column stands in for `id`, `create_table`
+                // then only adds the constraint
+                if let Some(shared) = shared_primary_key(&columns) {
+                    return Err(Error::Message(format!(
+                        "field `{fname}` cannot share the primary key, `{shared}` already does"
+                    )));
+                }
+                columns.insert(0, (reference_column(fname, ""), "PkInteger".to_string()));
+                references.push((fname.clone(), String::new()));
+            }
             crate::infer::FieldType::NullableReference => {
                 references.push((format!("{fname}?"), String::new()));
             }
             crate::infer::FieldType::NullableReferenceWithCustomField(refname) => {
                 references.push((format!("{fname}?"), refname.clone()));
             }
+            crate::infer::FieldType::ReferenceToTable(target) => {
+                // (categories, parent_id) or (tenant.organizations, org_id)
+                references.push((target, reference_column(fname, "")));
+            }
+            crate::infer::FieldType::NullableReferenceToTable(target) => {
+                references.push((format!("{target}?"), reference_column(fname, "")));
+            }
+            // no column, the relation's join table is a migration of its own
+            crate::infer::FieldType::ManyToMany(_) => {}
+            crate::infer::FieldType::Enum(variants) => {
+                let col_type =
+                    inline_enum_col_type("enum!", fname, &variants, default.as_deref(), options)?;
+                columns.push((fname.clone(), col_type));
+            }
+            crate::infer::FieldType::NullableEnum(variants) => {
+                let col_type =
+                    inline_enum_col_type("enum", fname, &variants, default.as_deref(), options)?;
+                columns.push((fname.clone(), col_type));
+            }
+            crate::infer::FieldType::Type(ftype) if ftype.starts_with("composite") => {
+                return Err(Error::Message(format!(
+                    "type: `{ftype}` takes the name of a composite type, e.g. `{ftype}:address`"
+                )));
+            }
             crate::infer::FieldType::Type(ftype) => {
                 let mappings = get_mappings();
                 let col_type = mappings.col_type_field(ftype.as_str())?;
-                columns.push((fname.clone(), col_type.to_string()));
+                if let Some(backend) = options.backend {
+                    mappings.check_backend(ftype.as_str(), backend)?;
+                }
+                let col_type = match options.default_string_len {
+                    Some(len) => string_len_col_type(&ftype, len)?,
+                    None => None,
+                }
+                .unwrap_or_else(|| col_type.to_string());
+                let col_type = with_collation(col_type, &ftype, &modifiers, options)?;
+                let col_type = with_phone_check(col_type, fname, &ftype, options);
+                let col_type = with_color_check(col_type, fname, &ftype, false, options);
+                let col_type = with_default(
+                    col_type,
+                    fname,
+                    mappings.rust_field(&ftype)?,
+                    default.as_deref(),
+                )?;
+                columns.push((fname.clone(), col_type));
+            }
+            crate::infer::FieldType::TypeWithDefault(ftype, default) => {
+                let mappings = get_mappings();
+                let col_type = mappings.col_type_with_default_field(ftype.as_str())?;
+                if let Some(backend) = options.backend {
+                    mappings.check_backend(ftype.as_str(), backend)?;
+                    if backend != DbBackend::Postgres && default_sequence(&default).is_some() {
+                        return Err(Error::Message(format!(
+                            "field `{fname}`: sequence defaults are only supported on postgres, \
+                             but the target backend is {backend}"
+                        )));
+                    }
+                }
+                // uuid^=gen_random_uuid() -> UuidUniqWithDefault("gen_random_uuid()".to_string())
+                let col_type = format!("{col_type}(\"{default}\".to_string())");
+                columns.push((fname.clone(), col_type));
+            }
+            crate::infer::FieldType::TypeWithParameters(ftype, params)
+                if ftype.trim_end_matches(['!', '^']) == "string" =>
+            {
+                // string:255 -> StringLenNull(255)
+                let len = match params.as_slice() {
+                    [len] => len.parse::<u32>().ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    Error::Message(format!(
+                        "type: `{ftype}` takes a single length parameter, e.g. `{ftype}:255`"
+                    ))
+                })?;
+                let col_type = string_len_col_type(&ftype, len)?.unwrap_or_default();
+                let col_type = with_collation(col_type, &ftype, &modifiers, options)?;
+                let col_type = with_default(col_type, fname, "String", default.as_deref())?;
+                columns.push((fname.clone(), col_type));
+            }
+            crate::infer::FieldType::TypeWithParameters(ftype, params)
+                if matches!(ftype.as_str(), "composite" | "composite!") =>
+            {
+                // composite:address -> CompositeNull("address".to_string())
+                let mappings = get_mappings();
+                if let Some(backend) = options.backend {
+                    mappings.check_backend(ftype.as_str(), backend)?;
+                }
+                let type_name = match params.as_slice() {
+                    [type_name] => type_name,
+                    _ => {
+                        return Err(Error::Message(format!(
+                            "type: `{ftype}` takes the name of a composite type, e.g. \
+                             `{ftype}:address`"
+                        )))
+                    }
+                };
+                if !is_composite_type_name(type_name) {
+                    return Err(Error::Message(format!(
+                        "field `{fname}`: `{type_name}` is not a composite type name, expected a \
+                         snake_case identifier"
+                    )));
+                }
+                let col_type = format!(
+                    "{}(\"{type_name}\".to_string())",
+                    mappings.col_type_field(&ftype)?
+                );
+                let col_type = with_default(
+                    col_type,
+                    fname,
+                    mappings.rust_field(&ftype)?,
+                    default.as_deref(),
+                )?;
+                columns.push((fname.clone(), col_type));
+            }
+            crate::infer::FieldType::TypeWithParameters(ftype, params)
+                if ftype.trim_end_matches(['!', '^']) == "color" =>
+            {
+                // color:alpha -> CharLenNull(9), #RRGGBBAA
+                if params.as_slice() != ["alpha"] {
+                    return Err(Error::Message(format!(
+                        "type: `{ftype}` only takes the `alpha` parameter, e.g. `{ftype}:alpha`, \
+                         not `{}`",
+                        params.join(",")
+                    )));
+                }
+                let col_type = get_mappings().col_type_field(&ftype)?.replace("(7)", "(9)");
+                let col_type = with_color_check(col_type, fname, &ftype, true, options);
+                let col_type = with_default(col_type, fname, "String", default.as_deref())?;
+                columns.push((fname.clone(), col_type));
+            }
+            crate::infer::FieldType::TypeWithParameters(ftype, params)
+                if matches!(
+                    ftype.trim_end_matches(['!', '^']),
+                    "decimal" | "decimal_len"
+                ) =>
+            {
+                // decimal:10,2 -> DecimalLenNull(10,2)
+                let mappings = get_mappings();
+                if let Some(backend) = options.backend {
+                    mappings.check_backend(ftype.as_str(), backend)?;
+                }
+                let col_type = decimal_col_type(&ftype, &params)?;
+                let col_type = with_default(
+                    col_type,
+                    fname,
+                    mappings.rust_field(&ftype)?,
+                    default.as_deref(),
+                )?;
+                columns.push((fname.clone(), col_type));
             }
             crate::infer::FieldType::TypeWithParameters(ftype, params) => {
                 let mappings = get_mappings();
                 let col_type = mappings.col_type_field(ftype.as_str())?;
+                if let Some(backend) = options.backend {
+                    mappings.check_backend(ftype.as_str(), backend)?;
+                }
                 let arity = mappings.col_type_arity(ftype.as_str()).unwrap_or_default();
                 if params.len() != arity {
                     return Err(Error::Message(format!(
@@ -77,73 +287,3075 @@ pub fn get_columns_and_references(
                                     params.join(",")
                                 ))),
                         }?;
+                        mappings.rust_field_with_params(&ftype, &params)?;
+
+                        // elements of a postgres array are nullable either way,
+                        // `string?` only tells the rust type apart
+                        format!(
+                            r"{}(ArrayColType::{})",
+                            col_type,
+                            array_kind.trim_end_matches('?').to_upper_camel_case()
+                        )
+                    }
+                    "state_machine" if default.is_some() => {
+                        return Err(Error::Message(format!(
+                            "state machine `{fname}` starts out in its first state, it cannot \
+                             have a `default=`"
+                        )));
+                    }
+                    "state_machine" => {
+                        // starts out in the first state
+                        let machine = parse_state_machine(fname, &params[0])?;
+                        format!("{}(\"{}\".to_string())", col_type, machine.states[0])
+                    }
+                    "enum" | "enum!" => {
+                        if let Some(default) = default
+                            .as_deref()
+                            .filter(|default| !params[1].split(',').any(|v| v == *default))
+                        {
+                            return Err(Error::Message(format!(
+                                "field `{fname}`: default `{default}` is not one of the variants \
+                                 `{}`",
+                                params[1]
+                            )));
+                        }
+                        enum_col_type(col_type, fname, &params[0], &params[1])?
+                    }
+                    &_ => {
+                        format!("{}({})", col_type, params.join(","))
+                    }
+                };
+
+                let col = with_collation(col, &ftype, &modifiers, options)?;
+                let col = with_default(
+                    col,
+                    fname,
+                    mappings.rust_field_with_params(&ftype, &params)?,
+                    default.as_deref(),
+                )?;
+                columns.push((fname.clone(), col));
+            }
+        }
+    }
+    validate_field_names(fields, options)?;
+    if options.sort_references {
+        references.sort();
+    }
+    Ok((columns, references))
+}
+
+/// Adds the literal default of a `:default=` field to its column, as a Rust
+/// literal of the field's type.
+fn with_default(
+    col_type: String,
+    fname: &str,
+    rust_type: &str,
+    default: Option<&str>,
+) -> Result<String> {
+    fn literal<T: std::str::FromStr + std::fmt::Display>(
+        value: &str,
+        suffix: &str,
+    ) -> Option<String> {
+        value.parse::<T>().ok().map(|v| format!("{v}{suffix}"))
+    }
+
+    let Some(default) = default else {
+        return Ok(col_type);
+    };
+    let rust_type = rust_type
+        .strip_prefix("Option<")
+        .and_then(|inner| inner.strip_suffix('>'))
+        .unwrap_or(rust_type);
+    let value = match rust_type {
+        "i16" => literal::<i16>(default, "i16"),
+        "i32" => literal::<i32>(default, ""),
+        "i64" => literal::<i64>(default, "i64"),
+        // `{:?}` keeps the `.0` of whole numbers, a float literal
+        "f32" | "f64" | "Decimal" => default
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(|v| format!("{v:?}")),
+        "bool" => literal::<bool>(default, ""),
+        _ if rust_type.starts_with("Vec<") => {
+            return Err(Error::Message(format!(
+                "field `{fname}` of type `{rust_type}` cannot have a `default=`"
+            )))
+        }
+        // as a Rust string literal, quotes and all escaped
+        _ => Some(format!("{default:?}")),
+    }
+    .ok_or_else(|| {
+        Error::Message(format!(
+            "default of field `{fname}` is not a valid `{rust_type}`: `{default}`"
+        ))
+    })?;
+    Ok(format!("{col_type}.default({value})"))
+}
+
+/// The foreign key column of a reference with a default id, nullable if the
+/// reference is.
+fn reference_with_default(
+    fname: &str,
+    field_type: &crate::infer::FieldType,
+    default: &str,
+) -> Result<(String, String)> {
+    use crate::infer::FieldType;
+
+    let (column, nullable) = match field_type {
+        FieldType::Reference | FieldType::ReferenceToTable(_) => {
+            (reference_column(fname, ""), false)
+        }
+        FieldType::NullableReference | FieldType::NullableReferenceToTable(_) => {
+            (reference_column(fname, ""), true)
+        }
+        FieldType::ReferenceWithCustomField(column) => (column.clone(), false),
+        FieldType::NullableReferenceWithCustomField(column) => (column.clone(), true),
+        FieldType::SharedPrimaryKeyReference => {
+            return Err(Error::Message(format!(
+                "reference `{fname}` is the primary key, it cannot have a `@default`"
+            )))
+        }
+        _ => {
+            return Err(Error::Message(format!(
+                "field `{fname}` is not a reference, `@default` only applies to references"
+            )))
+        }
+    };
+    let id = default.parse::<i32>().map_err(|_| {
+        Error::Message(format!(
+            "default of reference `{fname}` is not an id: `{default}`"
+        ))
+    })?;
+    let col_type = if nullable {
+        format!("IntegerNullWithDefault({id})")
+    } else {
+        format!("IntegerWithDefault({id})")
+    };
+    Ok((column, col_type))
+}
+
+/// The fields led by the tenant reference of [`GenerateOptions::tenant`],
+/// unless they already have it or the table is the tenants' own.
+pub fn with_tenant(
+    table: &str,
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Vec<(String, String)> {
+    let Some(tenant) = options.tenant.as_deref() else {
+        return fields.to_vec();
+    };
+    let tenants = tenant.to_plural().to_snake_case();
+    if table.to_plural().to_snake_case() == tenants
+        || fields.iter().any(|(fname, _)| fname == tenant)
+    {
+        return fields.to_vec();
+    }
+    std::iter::once((tenant.to_string(), "references".to_string()))
+        .chain(fields.iter().cloned())
+        .collect()
+}
+
+/// The fields followed by the `deleted_at` column of
+/// [`GenerateOptions::soft_delete`], unless they already have it.
+pub fn with_soft_delete(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Vec<(String, String)> {
+    let mut fields = fields.to_vec();
+    if options.soft_delete && !fields.iter().any(|(fname, _)| fname == SOFT_DELETE_COLUMN) {
+        fields.push((SOFT_DELETE_COLUMN.to_string(), "tstz".to_string()));
+    }
+    fields
+}
+
+/// A `password` field, stored as the hash of the password in
+/// `<field>_hash`, see `loco_rs::hash`.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PasswordColumn {
+    pub field: String,
+    /// The column holding the hash
+    pub name: String,
+    pub nullable: bool,
+}
+
+/// Collects the `password` fields.
+///
+/// # Errors
+///
+/// When a password is unique, which its salted hash cannot tell
+pub fn password_columns(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<PasswordColumn>> {
+    typed_columns(fields, "password", options)
+        .into_iter()
+        .map(|col| {
+            if col.unique {
+                return Err(Error::Message(format!(
+                    "password `{}` cannot be unique, each of its hashes is salted",
+                    col.name
+                )));
+            }
+            Ok(PasswordColumn {
+                name: format!("{}_hash", col.name),
+                field: col.name,
+                nullable: col.nullable,
+            })
+        })
+        .collect()
+}
+
+/// The fields with each `password` field in place of the string column
+/// holding its hash, see [`password_columns`].
+pub fn with_password_hashes(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Vec<(String, String)> {
+    fields
+        .iter()
+        .map(|(fname, ftype)| match ftype.strip_prefix("password") {
+            Some(flavor)
+                if !options.ignores_field(fname)
+                    && ["", "!", "^"].iter().any(|f| {
+                        flavor
+                            .strip_prefix(f)
+                            .is_some_and(|rest| rest.is_empty() || rest.starts_with([':', '@']))
+                    }) =>
+            {
+                (format!("{fname}_hash"), format!("string{flavor}"))
+            }
+            _ => (fname.clone(), ftype.clone()),
+        })
+        .collect()
+}
+
+/// The `created_at` column defaulted by the database, for a model without
+/// timestamps, see [`GenerateOptions::db_created_at`].
+pub fn db_created_at(with_tz: bool, options: &GenerateOptions) -> Option<(String, String)> {
+    if !options.db_created_at || with_tz {
+        return None;
+    }
+    Some(("created_at".to_string(), default_now(options)))
+}
+
+/// The `valid_from` column of a system-versioned model, see
+/// [`GenerateOptions::history`].
+///
+/// # Errors
+///
+/// When the model already has a column of the validity period
+pub fn history_column(
+    columns: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Option<(String, String)>> {
+    if !options.history {
+        return Ok(None);
+    }
+    if let Some((name, _)) = columns
+        .iter()
+        .find(|(name, _)| name == "valid_from" || name == "valid_to")
+    {
+        return Err(Error::Message(format!(
+            "`{name}` is a column of the validity period of a versioned model, it cannot be a field"
+        )));
+    }
+    if let Some(column) = shared_primary_key(columns) {
+        return Err(Error::Message(format!(
+            "the history of a versioned model is kept by `id`, but `{column}` is the primary key"
+        )));
+    }
+    Ok(Some(("valid_from".to_string(), default_now(options))))
+}
+
+/// The references of a many-to-many join table between the models `a` and
+/// `b`, whose foreign keys make up its composite primary key. When both sides
+/// are the same model, e.g. `user` following `user`, the second foreign key
+/// is told apart as `related_user_id`.
+#[must_use]
+pub fn many_to_many_references(a: &str, b: &str) -> Vec<(String, String)> {
+    let a = a.to_singular().to_snake_case();
+    let b = b.to_singular().to_snake_case();
+    let related = if a == b {
+        format!("related_{}", reference_column(&b, ""))
+    } else {
+        String::new()
+    };
+    vec![(a, String::new()), (b, related)]
+}
+
+/// The tables a model's `many_to_many` fields relate it to, e.g. `tags` for
+/// `tags:many_to_many`, each through a join table created after the model's.
+///
+/// # Errors
+///
+/// When a field cannot be parsed, or the model is a join table itself
+pub fn many_to_many_tables(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<String>> {
+    let mut tables = Vec::new();
+    for (fname, ftype) in fields {
+        if let crate::infer::FieldType::ManyToMany(table) = parse_field_type(ftype)? {
+            if options.many_to_many.is_some() {
+                return Err(Error::Message(format!(
+                    "field `{fname}`: a many-to-many join table cannot have many-to-many \
+                     relations of its own"
+                )));
+            }
+            tables.push(table.unwrap_or_else(|| fname.clone()));
+        }
+    }
+    Ok(tables)
+}
+
+/// The reference column standing in for `id`, of a `references:shared_pk`
+/// field
+#[must_use]
+pub fn shared_primary_key(columns: &[(String, String)]) -> Option<&str> {
+    columns
+        .iter()
+        .find(|(_, col_type)| col_type == "PkInteger")
+        .map(|(name, _)| name.as_str())
+}
+
+/// A timestamp column defaulting to the current time in the database
+fn default_now(options: &GenerateOptions) -> String {
+    let now = match options.backend {
+        Some(DbBackend::Postgres) => "now()",
+        Some(DbBackend::MySql | DbBackend::Sqlite) | None => "CURRENT_TIMESTAMP",
+    };
+    format!("TimestampWithTimeZoneWithDefaultExpr(\"{now}\".to_string())")
+}
+
+/// The `varchar(len)` column type of a `string` field, `None` for the
+/// other types.
+fn string_len_col_type(ftype: &str, len: u32) -> Result<Option<String>> {
+    let col_type = match ftype {
+        "string" => "StringLenNull",
+        "string!" => "StringLen",
+        "string^" => "StringLenUniq",
+        _ => return Ok(None),
+    };
+    if len == 0 {
+        return Err(Error::Message(format!(
+            "type: `{ftype}` needs a length above 0"
+        )));
+    }
+    Ok(Some(format!("{col_type}({len})")))
+}
+
+/// The column type of a decimal with a precision and scale, e.g.
+/// `decimal:10,2` or `decimal_len:10:2`, which `decimal` and `decimal_len`
+/// both take.
+fn decimal_col_type(ftype: &str, params: &[String]) -> Result<String> {
+    let params = params
+        .iter()
+        .flat_map(|param| param.split(','))
+        .collect::<Vec<_>>();
+    let (precision, scale) = match params.as_slice() {
+        [precision, scale] => (precision.parse::<u32>().ok(), scale.parse::<u32>().ok()),
+        _ => (None, None),
+    };
+    let (Some(precision), Some(scale)) = (precision, scale) else {
+        return Err(Error::Message(format!(
+            "type: `{ftype}` takes a precision and a scale as non-negative integers, e.g. \
+             `{ftype}:10,2`, not `{}`",
+            params.join(",")
+        )));
+    };
+    if precision == 0 {
+        return Err(Error::Message(format!(
+            "type: `{ftype}` needs a precision above 0"
+        )));
+    }
+    if scale > precision {
+        return Err(Error::Message(format!(
+            "type: `{ftype}` has a scale of {scale}, which exceeds its precision of {precision}"
+        )));
+    }
+    let col_type = match ftype
+        .trim_start_matches("decimal")
+        .trim_start_matches("_len")
+    {
+        "!" => "DecimalLen",
+        "^" => "DecimalLenUniq",
+        _ => "DecimalLenNull",
+    };
+    Ok(format!("{col_type}({precision},{scale})"))
+}
+
+/// Adds an E.164 format CHECK to phone columns, on the backends with regular
+/// expressions. `SQLite` has none, it only gets the normalization helper.
+fn with_phone_check(
+    col_type: String,
+    fname: &str,
+    ftype: &str,
+    options: &GenerateOptions,
+) -> String {
+    if ftype.trim_end_matches(['!', '^']) != "phone" {
+        return col_type;
+    }
+    match options.backend {
+        Some(DbBackend::Postgres) => {
+            format!(r##"{col_type}.check(r#""{fname}" ~ '^\+[1-9][0-9]{{1,14}}$'"#)"##)
+        }
+        Some(DbBackend::MySql) => {
+            format!(r##"{col_type}.check(r#"`{fname}` REGEXP '^\\+[1-9][0-9]{{1,14}}$'"#)"##)
+        }
+        Some(DbBackend::Sqlite) | None => {
+            tracing::warn!(
+                field = fname,
+                "skipping the E.164 CHECK of the phone column, the backend has no regular expressions"
+            );
+            col_type
+        }
+    }
+}
+
+/// Adds a hex format CHECK to color columns, `#RRGGBB` or with `alpha`
+/// `#RRGGBBAA`. `SQLite` has no regular expressions, it matches a `GLOB`.
+fn with_color_check(
+    col_type: String,
+    fname: &str,
+    ftype: &str,
+    alpha: bool,
+    options: &GenerateOptions,
+) -> String {
+    if ftype.trim_end_matches(['!', '^']) != "color" {
+        return col_type;
+    }
+    let digits = if alpha { 8 } else { 6 };
+    match options.backend {
+        Some(DbBackend::Postgres) => {
+            format!(r##"{col_type}.check(r#""{fname}" ~ '^#[0-9A-Fa-f]{{{digits}}}$'"#)"##)
+        }
+        Some(DbBackend::MySql) => {
+            format!(r##"{col_type}.check(r#"`{fname}` REGEXP '^#[0-9A-Fa-f]{{{digits}}}$'"#)"##)
+        }
+        Some(DbBackend::Sqlite) => {
+            let pattern = "[0-9A-Fa-f]".repeat(digits);
+            format!(r##"{col_type}.check(r#""{fname}" GLOB '#{pattern}'"#)"##)
+        }
+        None => {
+            tracing::warn!(
+                field = fname,
+                "skipping the hex format CHECK of the color column, the backend is unknown"
+            );
+            col_type
+        }
+    }
+}
+
+/// Adds the column's collation to its type: the one given with
+/// `@collate=<name>`, or else the table default for string and text columns.
+fn with_collation(
+    col_type: String,
+    ftype: &str,
+    modifiers: &[FieldModifier<'_>],
+    options: &GenerateOptions,
+) -> Result<String> {
+    let is_text = matches!(ftype.trim_end_matches(['!', '^']), "string" | "text");
+    let collation = modifier_value(modifiers, "collate")
+        .or_else(|| options.collation.as_deref().filter(|_| is_text));
+    let Some(collation) = collation else {
+        return Ok(col_type);
+    };
+    if collation.is_empty()
+        || !collation
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(Error::Message(format!("invalid collation: `{collation}`")));
+    }
+    Ok(format!("{col_type}.collate(\"{collation}\")"))
+}
+
+/// The sequences the fields default to with `nextval('<sequence>')`, created
+/// along with the table under [`GenerateOptions::create_sequences`].
+///
+/// # Errors
+///
+/// When a field type cannot be parsed
+pub fn sequences(fields: &[(String, String)], options: &GenerateOptions) -> Result<Vec<String>> {
+    let mut sequences = Vec::new();
+    if !options.create_sequences {
+        return Ok(sequences);
+    }
+    for (_, ftype) in fields {
+        if let crate::infer::FieldType::TypeWithDefault(_, default) = parse_field_type(ftype)? {
+            if let Some(sequence) = default_sequence(&default) {
+                if !sequences.iter().any(|s| s == sequence) {
+                    sequences.push(sequence.to_string());
+                }
+            }
+        }
+    }
+    Ok(sequences)
+}
+
+/// `status:enum:order_status:draft,published` ->
+/// `EnumNull("order_status".to_string(), vec!["draft".to_string(), ...])`
+fn enum_col_type(col_type: &str, fname: &str, enum_name: &str, variants: &str) -> Result<String> {
+    let is_name = |s: &str| {
+        s.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if !is_name(enum_name) {
+        return Err(Error::Message(format!(
+            "field `{fname}`: invalid enum type name `{enum_name}`, expected an identifier such \
+             as `order_status`"
+        )));
+    }
+    let mut quoted: Vec<String> = Vec::new();
+    for variant in variants.split(',') {
+        if !is_name(variant) {
+            return Err(Error::Message(format!(
+                "field `{fname}`: invalid enum variant `{variant}`, expected an identifier such \
+                 as `draft`"
+            )));
+        }
+        let variant = format!("\"{variant}\".to_string()");
+        if quoted.contains(&variant) {
+            return Err(Error::Message(format!(
+                "field `{fname}`: enum variant {variant} is given more than once"
+            )));
+        }
+        quoted.push(variant);
+    }
+    Ok(format!(
+        "{col_type}(\"{enum_name}\".to_string(), vec![{}])",
+        quoted.join(", ")
+    ))
+}
+
+/// `status:enum:pending,shipped` -> an enum type named after the field,
+/// `EnumNull("status".to_string(), vec!["pending".to_string(), ...])`, whose
+/// default has to be one of the variants
+fn inline_enum_col_type(
+    ftype: &str,
+    fname: &str,
+    variants: &[String],
+    default: Option<&str>,
+    options: &GenerateOptions,
+) -> Result<String> {
+    let mappings = get_mappings();
+    let col_type = mappings.col_type_field(ftype)?;
+    if let Some(backend) = options.backend {
+        mappings.check_backend(ftype, backend)?;
+    }
+    if let Some(default) = default.filter(|default| !variants.iter().any(|v| v == default)) {
+        return Err(Error::Message(format!(
+            "field `{fname}`: default `{default}` is not one of the variants `{}`",
+            variants.join(",")
+        )));
+    }
+    let col_type = enum_col_type(col_type, fname, fname, &variants.join(","))?;
+    with_default(col_type, fname, mappings.rust_field(ftype)?, default)
+}
+
+/// The enum columns of a model, as the enum type and its variants, for the
+/// model to have a Rust enum of each.
+#[must_use]
+pub fn enum_columns(columns: &[(String, String)]) -> Vec<EnumColumn> {
+    columns
+        .iter()
+        .filter_map(|(_, col_type)| parse_enum_col_type(col_type))
+        .map(|(name, variants)| EnumColumn { name, variants })
+        .collect()
+}
+
+/// The enum type of an enum column and its variants
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct EnumColumn {
+    pub name: String,
+    pub variants: Vec<String>,
+}
+
+/// The enum type a column declares and its variants, from a generated
+/// `EnumNull("order_status".to_string(), vec!["draft".to_string(), ...])`.
+fn parse_enum_col_type(col_type: &str) -> Option<(String, Vec<String>)> {
+    let rest = [
+        "EnumNullWithDefault(",
+        "EnumWithDefault(",
+        "EnumNull(",
+        "Enum(",
+    ]
+    .iter()
+    .find_map(|prefix| col_type.strip_prefix(prefix))?;
+    let (name, rest) = rest.trim_start().strip_prefix('"')?.split_once('"')?;
+    let (_, rest) = rest.split_once("vec![")?;
+    let (variants, _) = rest.split_once(']')?;
+    let variants = variants
+        .split(',')
+        .map(str::trim)
+        .filter(|variant| !variant.is_empty())
+        .map(|variant| {
+            variant
+                .trim_end_matches(".to_string()")
+                .trim_matches('"')
+                .to_string()
+        })
+        .collect();
+    Some((name.to_string(), variants))
+}
+
+/// The enum types of the columns the model's migration creates, and drops on
+/// the way down. An enum type that a migration in
+/// [`GenerateOptions::migration_dir`] already declares is reused instead, which
+/// the migration's table creation leaves as it is.
+///
+/// # Errors
+///
+/// When an enum type is declared with other variants than before, or the
+/// migrations cannot be read
+pub fn new_enum_types(
+    columns: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<String>> {
+    let mut declared = Vec::new();
+    let src = Path::new(&options.migration_dir()).join("src");
+    if src.is_dir()
+        && columns
+            .iter()
+            .any(|(_, c)| parse_enum_col_type(c).is_some())
+    {
+        for entry in fs::read_dir(&src)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "rs") {
+                let source = fs::read_to_string(&path)?;
+                declared.extend(
+                    source
+                        .split("ColType::")
+                        .skip(1)
+                        .filter_map(parse_enum_col_type),
+                );
+            }
+        }
+    }
+
+    let mut enum_types = Vec::new();
+    for (fname, col_type) in columns {
+        let Some((name, variants)) = parse_enum_col_type(col_type) else {
+            continue;
+        };
+        match declared.iter().find(|(declared, _)| *declared == name) {
+            Some((_, existing)) if *existing != variants => {
+                return Err(Error::Message(format!(
+                    "field `{fname}`: enum type `{name}` is already declared with the variants \
+                     `{}`, not `{}`",
+                    existing.join(","),
+                    variants.join(",")
+                )));
+            }
+            Some(_) => {}
+            None => {
+                enum_types.push(name.clone());
+                declared.push((name, variants));
+            }
+        }
+    }
+    Ok(enum_types)
+}
+
+/// Warns about the fields that are generated automatically anyway.
+pub fn redundant_field_warnings(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|(fname, _)| options.ignores_field(fname))
+        .map(|(fname, _)| {
+            format!("field `{fname}` is redundant, it is already generated automatically")
+        })
+        .collect()
+}
+
+/// Words reserved in SQL by postgres, mysql or sqlite, which have to be
+/// quoted wherever the name is used in hand written queries.
+const SQL_RESERVED_WORDS: &[&str] = &[
+    "all",
+    "analyse",
+    "analyze",
+    "and",
+    "any",
+    "as",
+    "asc",
+    "between",
+    "both",
+    "by",
+    "case",
+    "cast",
+    "check",
+    "collate",
+    "column",
+    "constraint",
+    "create",
+    "cross",
+    "current_date",
+    "current_time",
+    "current_timestamp",
+    "current_user",
+    "default",
+    "delete",
+    "desc",
+    "distinct",
+    "do",
+    "else",
+    "end",
+    "except",
+    "exists",
+    "false",
+    "fetch",
+    "for",
+    "foreign",
+    "from",
+    "full",
+    "grant",
+    "group",
+    "having",
+    "in",
+    "index",
+    "inner",
+    "insert",
+    "intersect",
+    "into",
+    "is",
+    "join",
+    "key",
+    "leading",
+    "left",
+    "like",
+    "limit",
+    "natural",
+    "not",
+    "null",
+    "offset",
+    "on",
+    "only",
+    "or",
+    "order",
+    "outer",
+    "primary",
+    "references",
+    "right",
+    "select",
+    "session_user",
+    "set",
+    "some",
+    "table",
+    "then",
+    "to",
+    "trailing",
+    "true",
+    "union",
+    "unique",
+    "update",
+    "user",
+    "using",
+    "values",
+    "when",
+    "where",
+    "window",
+    "with",
+];
+
+/// Rust keywords, entity fields named after them are raw identifiers
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Methods SeaORM has on entity models, which a field of the same name reads
+/// as, `order.save` rather than `order.save(db)`
+const SEAORM_METHODS: &[&str] = &[
+    "delete",
+    "find_related",
+    "get",
+    "insert",
+    "into_active_model",
+    "into_json",
+    "reset",
+    "save",
+    "set",
+    "update",
+];
+
+/// The longest identifier postgres keeps, it truncates longer ones
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Warns about the table and column names that make for an awkward schema:
+/// SQL reserved words, names too long for postgres, names that are not
+/// snake_case and Rust keywords. The table is only checked when it is
+/// `created`, not when columns are added to it. Under
+/// [`GenerateOptions::check_reserved`] these are errors instead.
+///
+/// # Errors
+///
+/// When there are warnings in strict mode
+pub fn check_identifiers(
+    table: &str,
+    created: bool,
+    columns: &[(String, String)],
+    references: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<String>> {
+    let columns = columns.iter().map(|(name, _)| name.clone()).chain(
+        references
+            .iter()
+            .map(|(name, refname)| reference_column(name, refname)),
+    );
+    let mut warnings = Vec::new();
+    if created {
+        warnings.extend(identifier_warnings(&format!("table `{table}`"), table));
+    }
+    for column in columns {
+        warnings.extend(identifier_warnings(
+            &format!("column `{column}` of `{table}`"),
+            &column,
+        ));
+    }
+    if options.check_reserved && !warnings.is_empty() {
+        return Err(Error::Message(warnings.join("\n")));
+    }
+    for warning in &warnings {
+        tracing::warn!("{warning}");
+    }
+    Ok(warnings)
+}
+
+/// Rejects the field names that make for entities that do not compile, or
+/// read wrong: anything but a snake_case identifier, Rust keywords, raw
+/// identifiers and the names of SeaORM's model methods.
+///
+/// # Errors
+///
+/// Naming the first offending field
+pub fn validate_field_names(fields: &[(String, String)], options: &GenerateOptions) -> Result<()> {
+    for (fname, _) in fields {
+        if !options.ignores_field(fname) {
+            validate_name(&format!("field `{fname}`"), fname, true)?;
+            if SEAORM_METHODS.contains(&fname.as_str()) {
+                return Err(Error::Message(format!(
+                    "field `{fname}` is named like the `{fname}` method SeaORM has on models"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rejects a model name whose table, and entity module, would not be a
+/// snake_case identifier or would be a Rust keyword.
+///
+/// # Errors
+///
+/// When the model name is not valid
+pub fn validate_model_name(name: &str) -> Result<()> {
+    validate_name(&format!("model `{name}`"), name, false)?;
+    let table = name.to_plural().to_snake_case();
+    validate_name(&format!("table `{table}` of model `{name}`"), &table, true)
+}
+
+/// Errors about a name which is not an identifier, not snake_case when it has
+/// to be, or which is a Rust keyword.
+fn validate_name(what: &str, name: &str, snake_case: bool) -> Result<()> {
+    if let Some(raw) = name.strip_prefix("r#") {
+        return Err(Error::Message(format!(
+            "{what} is a raw identifier, name it `{raw}` without the `r#` if it is not a keyword"
+        )));
+    }
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier {
+        return Err(Error::Message(format!(
+            "{what} is not an identifier, it has to start with a letter or `_` followed by \
+             letters, digits and `_`"
+        )));
+    }
+    if snake_case && name.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err(Error::Message(format!(
+            "{what} is not snake_case, e.g. `{}`",
+            name.to_snake_case()
+        )));
+    }
+    if RUST_KEYWORDS.contains(&name) {
+        return Err(Error::Message(format!("{what} is a Rust keyword")));
+    }
+    Ok(())
+}
+
+fn identifier_warnings(what: &str, name: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let lowercase = name.to_lowercase();
+    if SQL_RESERVED_WORDS.contains(&lowercase.as_str()) {
+        warnings.push(format!("{what} is a reserved word in SQL"));
+    }
+    if name.len() > MAX_IDENTIFIER_LEN {
+        warnings.push(format!(
+            "{what} is longer than {MAX_IDENTIFIER_LEN} characters, postgres truncates it"
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        warnings.push(format!("{what} is not snake_case"));
+    }
+    if RUST_KEYWORDS.contains(&name) {
+        warnings.push(format!("{what} is a Rust keyword"));
+    }
+    warnings
+}
+
+/// A column of a given field type, e.g. all the `money_minor` columns.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct TypedColumn {
+    pub name: String,
+    pub nullable: bool,
+    pub unique: bool,
+}
+
+/// Collects the fields of the given type in any of its nullable (`type`),
+/// not null (`type!`) or unique (`type^`) flavors.
+pub fn typed_columns(
+    fields: &[(String, String)],
+    type_name: &str,
+    options: &GenerateOptions,
+) -> Vec<TypedColumn> {
+    fields
+        .iter()
+        .filter(|(fname, _)| !options.ignores_field(fname))
+        .filter_map(|(fname, ftype)| {
+            let ftype = ftype.split([':', '@']).next().unwrap_or_default();
+            let flavor = ftype.strip_prefix(type_name)?;
+            let (nullable, unique) = match flavor {
+                "" => (true, false),
+                "!" => (false, false),
+                "^" => (false, true),
+                _ => return None,
+            };
+            Some(TypedColumn {
+                name: fname.clone(),
+                nullable,
+                unique,
+            })
+        })
+        .collect()
+}
+
+/// A column the application encrypts, see `loco_rs::model::Encrypted`.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct EncryptedColumn {
+    pub name: String,
+    /// The rust type of the column, without the `Option`
+    pub rust_type: String,
+    pub nullable: bool,
+}
+
+/// The column shown by the model's `Display`.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct DisplayColumn {
+    pub name: String,
+    pub nullable: bool,
+}
+
+/// Looks up the column the model's `Display` shows.
+///
+/// # Errors
+///
+/// When the column is not a value field of the model
+pub fn display_column(fields: &[(String, String)], name: &str) -> Result<DisplayColumn> {
+    let mappings = get_mappings();
+    let not_found = || {
+        Error::Message(format!(
+            "display column `{name}` is not a column of the model"
+        ))
+    };
+    let (_, ftype) = fields
+        .iter()
+        .find(|(fname, _)| fname == name)
+        .ok_or_else(not_found)?;
+    let rust_type = match parse_field_type(ftype)? {
+        crate::infer::FieldType::Type(ftype)
+        | crate::infer::FieldType::TypeWithDefault(ftype, _) => mappings.rust_field(&ftype)?,
+        crate::infer::FieldType::TypeWithParameters(ftype, params) => {
+            mappings.rust_field_with_params(&ftype, &params)?
+        }
+        _ => return Err(not_found()),
+    };
+    Ok(DisplayColumn {
+        name: name.to_string(),
+        nullable: rust_type.starts_with("Option<"),
+    })
+}
+
+/// A column set through the model's `ActiveModelBuilder`, see
+/// [`GenerateOptions::builder`].
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct BuilderColumn {
+    pub name: String,
+    /// The rust type of the column, without the `Option`
+    pub rust_type: String,
+    pub nullable: bool,
+}
+
+/// Collects the columns of the `ActiveModelBuilder`, value fields and the
+/// foreign keys of references, in the order of the fields. The not null
+/// ones are taken by `new`, the nullable ones get a setter.
+///
+/// # Errors
+///
+/// When a field type is not known, or a setter is named like one of the
+/// builder's own methods
+pub fn builder_columns(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<BuilderColumn>> {
+    use crate::infer::FieldType;
+
+    let mappings = get_mappings();
+    let mut columns = Vec::new();
+    for (fname, ftype) in fields {
+        if options.ignores_field(fname) {
+            continue;
+        }
+        let field_type = parse_field_type(ftype)?;
+        let (name, rust_type) = match &field_type {
+            FieldType::NullableReference
+            | FieldType::NullableReferenceWithCustomField(_)
+            | FieldType::NullableReferenceToTable(_) => (
+                field_reference_column(fname, &field_type).unwrap_or_default(),
+                "Option<i32>",
+            ),
+            FieldType::Reference
+            | FieldType::ReferenceWithCustomField(_)
+            | FieldType::ReferenceToTable(_)
+            | FieldType::SharedPrimaryKeyReference => (
+                field_reference_column(fname, &field_type).unwrap_or_default(),
+                "i32",
+            ),
+            FieldType::ManyToMany(_) => continue,
+            FieldType::Enum(_) => (fname.clone(), mappings.rust_field("enum!")?),
+            FieldType::NullableEnum(_) => (fname.clone(), mappings.rust_field("enum")?),
+            FieldType::Type(ftype) | FieldType::TypeWithDefault(ftype, _) => {
+                (fname.clone(), mappings.rust_field(ftype)?)
+            }
+            FieldType::TypeWithParameters(ftype, params) => (
+                fname.clone(),
+                mappings.rust_field_with_params(ftype, params)?,
+            ),
+        };
+        let (rust_type, nullable) = rust_type
+            .strip_prefix("Option<")
+            .and_then(|inner| inner.strip_suffix('>'))
+            .map_or((rust_type, false), |inner| (inner, true));
+        if nullable && ["new", "build"].contains(&name.as_str()) {
+            return Err(Error::Message(format!(
+                "field `{name}` would have a setter named like the builder's own `{name}` method"
+            )));
+        }
+        columns.push(BuilderColumn {
+            name,
+            rust_type: rust_type.to_string(),
+            nullable,
+        });
+    }
+    Ok(columns)
+}
+
+/// A method of the model loading the row a reference field refers to, see
+/// [`GenerateOptions::reference_accessors`].
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ReferenceAccessor {
+    /// The method, named after the field
+    pub name: String,
+    /// The referenced table, whose entity loads the row
+    pub table: String,
+    /// The model of the referenced table, e.g. `Post` for `posts`
+    pub model: String,
+    /// The foreign key column
+    pub column: String,
+    pub nullable: bool,
+}
+
+/// Collects the accessors of the reference fields, in the order of the
+/// fields.
+///
+/// # Errors
+///
+/// When a field cannot be parsed
+pub fn reference_accessors(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<ReferenceAccessor>> {
+    use crate::infer::FieldType;
+
+    let mut accessors = Vec::new();
+    for (fname, ftype) in fields {
+        if options.ignores_field(fname) {
+            continue;
+        }
+        let field_type = parse_field_type(ftype)?;
+        let Some(column) = field_reference_column(fname, &field_type) else {
+            continue;
+        };
+        let (table, nullable) = match &field_type {
+            // a schema qualified `tenant.organizations` has its entity named
+            // after the table
+            FieldType::ReferenceToTable(target) => (
+                target.rsplit('.').next().unwrap_or(target).to_string(),
+                false,
+            ),
+            FieldType::NullableReferenceToTable(target) => (
+                target.rsplit('.').next().unwrap_or(target).to_string(),
+                true,
+            ),
+            FieldType::NullableReference | FieldType::NullableReferenceWithCustomField(_) => {
+                (fname.to_plural().to_snake_case(), true)
+            }
+            _ => (fname.to_plural().to_snake_case(), false),
+        };
+        accessors.push(ReferenceAccessor {
+            name: fname.trim_end_matches('?').to_snake_case(),
+            model: table.to_singular().to_pascal_case(),
+            table,
+            column,
+            nullable,
+        });
+    }
+    Ok(accessors)
+}
+
+/// Looks up the `tstzrange` column of the model's `is_active_at`, see
+/// [`GenerateOptions::active_period`].
+///
+/// # Errors
+///
+/// When the column is not a `tstzrange` field of the model
+pub fn active_period_column(
+    fields: &[(String, String)],
+    name: &str,
+    options: &GenerateOptions,
+) -> Result<TypedColumn> {
+    if let Some(column) = typed_columns(fields, "tstzrange", options)
+        .into_iter()
+        .find(|column| column.name == name)
+    {
+        return Ok(column);
+    }
+    Err(Error::Message(
+        if fields.iter().any(|(fname, _)| fname == name) {
+            format!(
+                "active period `{name}` is not a range, `is_active_at` needs a `tstzrange` column"
+            )
+        } else {
+            format!("active period `{name}` is not a column of the model")
+        },
+    ))
+}
+
+/// A blob (`bytea`) column addressed by its content, see
+/// [`GenerateOptions::content_hash`].
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ContentHash {
+    pub column: String,
+    pub hash_column: String,
+    pub nullable: bool,
+}
+
+impl ContentHash {
+    /// Adds the hash column and its unique index, which the dedup helper
+    /// conflicts on.
+    pub fn add_to(&self, columns: &mut Vec<(String, String)>, indexes: &mut Vec<IndexSpec>) {
+        let col_type = if self.nullable {
+            "StringLenNull(64)"
+        } else {
+            "StringLen(64)"
+        };
+        columns.push((self.hash_column.clone(), col_type.to_string()));
+        indexes.push(IndexSpec {
+            columns: vec![self.hash_column.clone()],
+            unique: true,
+            ..Default::default()
+        });
+    }
+}
+
+/// The column soft deleted rows are marked by
+const SOFT_DELETE_COLUMN: &str = "deleted_at";
+
+/// Makes the unique columns and indexes of a model with soft deletes, see
+/// [`GenerateOptions::soft_delete_unique`], partial over the rows not
+/// deleted. A unique (`^`) column loses its `UNIQUE` constraint for a partial
+/// unique index.
+///
+/// # Errors
+///
+/// When a unique index is deferrable, or the entity gets helpers conflicting
+/// on the unique columns, which `ON CONFLICT` cannot target once partial
+pub fn soft_delete_unique(
+    columns: &mut [(String, String)],
+    indexes: &mut Vec<IndexSpec>,
+    options: &GenerateOptions,
+) -> Result<()> {
+    if !options.soft_delete_unique
+        || !columns
+            .iter()
+            .any(|(column, _)| column == SOFT_DELETE_COLUMN)
+    {
+        return Ok(());
+    }
+    if options.upsert || options.seed_loader || options.content_hash.is_some() {
+        return Err(Error::Message(
+            "`ON CONFLICT` cannot target the unique indexes made partial for soft deletes, \
+             which upserts, seed loaders and content hashes conflict on"
+                .to_string(),
+        ));
+    }
+    for index in indexes.iter_mut().filter(|index| index.unique) {
+        if index.deferrable {
+            return Err(Error::Message(format!(
+                "index over `{}` is deferrable, which cannot leave out the soft deleted rows",
+                index.columns.join("`, `")
+            )));
+        }
+        index.when_null = Some(SOFT_DELETE_COLUMN.to_string());
+    }
+    for (column, col_type) in columns.iter_mut() {
+        if col_type.contains("Uniq") || col_type.starts_with("array_uniq") {
+            *col_type = col_type
+                .replacen("Uniq", "", 1)
+                .replacen("array_uniq", "array", 1);
+            indexes.push(IndexSpec {
+                columns: vec![column.clone()],
+                unique: true,
+                when_null: Some(SOFT_DELETE_COLUMN.to_string()),
+                ..Default::default()
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Looks up the blob column deduplicated by its content.
+///
+/// # Errors
+///
+/// When the column is not a `blob` field of the model
+pub fn content_hash(
+    fields: &[(String, String)],
+    name: &str,
+    options: &GenerateOptions,
+) -> Result<ContentHash> {
+    let column = typed_columns(fields, "blob", options)
+        .into_iter()
+        .find(|column| column.name == name)
+        .ok_or_else(|| {
+            Error::Message(format!(
+                "content hash column `{name}` is not a `blob` column of the model"
+            ))
+        })?;
+    Ok(ContentHash {
+        hash_column: format!("{name}_sha256"),
+        column: column.name,
+        nullable: column.nullable,
+    })
+}
+
+/// Collects the fields flagged with the `@encrypted` modifier.
+///
+/// # Errors
+///
+/// When a field type is not known, or a reference is flagged
+pub fn encrypted_columns(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<EncryptedColumn>> {
+    let mappings = get_mappings();
+    let mut columns = Vec::new();
+    for (fname, ftype) in fields {
+        if options.ignores_field(fname) {
+            continue;
+        }
+        let (_, modifiers) = split_modifiers(ftype)?;
+        if !modifiers.iter().any(|(m, _)| *m == "encrypted") {
+            continue;
+        }
+        let rust_type = match parse_field_type(ftype)? {
+            crate::infer::FieldType::Type(ftype)
+            | crate::infer::FieldType::TypeWithDefault(ftype, _) => mappings.rust_field(&ftype)?,
+            crate::infer::FieldType::TypeWithParameters(ftype, params) => {
+                mappings.rust_field_with_params(&ftype, &params)?
+            }
+            _ => {
+                return Err(Error::Message(format!(
+                    "field `{fname}` is a reference, only value fields can be encrypted"
+                )))
+            }
+        };
+        let (rust_type, nullable) = rust_type
+            .strip_prefix("Option<")
+            .and_then(|inner| inner.strip_suffix('>'))
+            .map_or((rust_type, false), |inner| (inner, true));
+        columns.push(EncryptedColumn {
+            name: fname.clone(),
+            rust_type: rust_type.to_string(),
+            nullable,
+        });
+    }
+    Ok(columns)
+}
+
+/// A `state_machine` column: its states, in order, and the allowed
+/// transitions between them.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct StateMachineColumn {
+    pub name: String,
+    pub states: Vec<String>,
+    pub transitions: Vec<(String, String)>,
+}
+
+/// Parses the states of a `state_machine` field, either a list of states
+/// moving linearly from one to the next (`draft,review,published`), or a list
+/// of transitions (`draft>review,review>draft,review>published`).
+///
+/// # Errors
+///
+/// When a state is not a valid name, or there are less than two states
+pub fn parse_state_machine(name: &str, spec: &str) -> Result<StateMachineColumn> {
+    let is_state = |s: &str| {
+        s.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+            && s.chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    };
+    let mut states: Vec<String> = Vec::new();
+    let mut transitions = Vec::new();
+    let mut add_state = |state: &str| -> Result<String> {
+        if !is_state(state) {
+            return Err(Error::Message(format!(
+                "state machine `{name}`: invalid state `{state}`, expected a snake_case name"
+            )));
+        }
+        if !states.iter().any(|s| s == state) {
+            states.push(state.to_string());
+        }
+        Ok(state.to_string())
+    };
+
+    if spec.contains('>') {
+        for transition in spec.split(',') {
+            let Some((from, to)) = transition.split_once('>') else {
+                return Err(Error::Message(format!(
+                    "state machine `{name}`: cannot parse transition `{transition}`, expected \
+                     `<from>><to>`"
+                )));
+            };
+            transitions.push((add_state(from)?, add_state(to)?));
+        }
+    } else {
+        let linear = spec
+            .split(',')
+            .map(&mut add_state)
+            .collect::<Result<Vec<_>>>()?;
+        transitions = linear
+            .windows(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+    }
+
+    if states.len() < 2 {
+        return Err(Error::Message(format!(
+            "state machine `{name}` needs at least two states"
+        )));
+    }
+    Ok(StateMachineColumn {
+        name: name.to_string(),
+        states,
+        transitions,
+    })
+}
+
+/// Collects the `state_machine` fields.
+///
+/// # Errors
+///
+/// When the states of a field cannot be parsed
+pub fn state_machine_columns(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<StateMachineColumn>> {
+    let mut columns = Vec::new();
+    for (fname, ftype) in fields {
+        if options.ignores_field(fname) {
+            continue;
+        }
+        if let crate::infer::FieldType::TypeWithParameters(ftype, params) = parse_field_type(ftype)?
+        {
+            if let ("state_machine", [spec]) = (ftype.as_str(), params.as_slice()) {
+                columns.push(parse_state_machine(fname, spec)?);
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// The foreign key column a reference creates, `user` -> `user_id`
+pub fn reference_column(name: &str, custom_field: &str) -> String {
+    if custom_field.is_empty() {
+        format!(
+            "{}_id",
+            name.trim_end_matches('?').to_singular().to_snake_case()
+        )
+    } else {
+        custom_field.to_string()
+    }
+}
+
+/// The foreign key column of a reference field, `None` for a value field
+fn field_reference_column(fname: &str, field_type: &crate::infer::FieldType) -> Option<String> {
+    use crate::infer::FieldType;
+
+    match field_type {
+        FieldType::Reference
+        | FieldType::NullableReference
+        | FieldType::ReferenceToTable(_)
+        | FieldType::NullableReferenceToTable(_)
+        | FieldType::SharedPrimaryKeyReference => Some(reference_column(fname, "")),
+        FieldType::ReferenceWithCustomField(column)
+        | FieldType::NullableReferenceWithCustomField(column) => Some(column.clone()),
+        FieldType::Enum(_)
+        | FieldType::NullableEnum(_)
+        | FieldType::ManyToMany(_)
+        | FieldType::Type(_)
+        | FieldType::TypeWithParameters(..)
+        | FieldType::TypeWithDefault(..) => None,
+    }
+}
+
+/// The comments on the foreign keys of the reference fields with a
+/// `@comment`, as `(column, comment)`, the comment a Rust string literal for
+/// the migration.
+///
+/// # Errors
+///
+/// When a field cannot be parsed
+pub fn foreign_key_comments(fields: &[(String, String)]) -> Result<Vec<(String, String)>> {
+    let mut comments = Vec::new();
+    for (fname, ftype) in fields {
+        let (_, modifiers) = split_modifiers(ftype)?;
+        if let Some(comment) = modifier_value(&modifiers, "comment") {
+            if let Some(column) = field_reference_column(fname, &parse_field_type(ftype)?) {
+                comments.push((column, format!("{comment:?}")));
+            }
+        }
+    }
+    Ok(comments)
+}
+
+/// The requested indexes along with those of the fields flagged `:index`,
+/// e.g. `email:string:index`, unless one is already requested over the
+/// field alone.
+///
+/// # Errors
+///
+/// When a field cannot be parsed
+pub fn requested_indexes(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<IndexSpec>> {
+    let mut indexes = options.indexes.clone();
+    for (fname, ftype) in fields {
+        let (base, _) = split_modifiers(ftype)?;
+        let (base, _) = split_default(base)?;
+        let already = indexes.iter().any(|index| index.columns == [fname.clone()]);
+        if split_index(base).1 && !already {
+            indexes.push(IndexSpec {
+                columns: vec![fname.clone()],
+                ..Default::default()
+            });
+        }
+    }
+    Ok(indexes)
+}
+
+/// Resolves the columns of the requested indexes against the parsed columns
+/// and references, the latter by field name or by their foreign key column,
+/// and the timestamps of a table that has them. An expression, e.g. `lower(email)`, can only refer to columns by name.
+///
+/// # Errors
+///
+/// When an index names a column which is not part of the model, or its
+/// condition is not a boolean column
+pub fn resolve_indexes(
+    indexes: &[IndexSpec],
+    columns: &[(String, String)],
+    references: &[(String, String)],
+    with_tz: bool,
+    tenant: Option<&str>,
+) -> Result<Vec<IndexSpec>> {
+    indexes
+        .iter()
+        .map(|index| {
+            let tenant = if index.tenant_scoped {
+                let Some(tenant) = tenant else {
+                    return Err(Error::Message(format!(
+                        "index over `{}` is scoped to the tenant, but there is no tenant reference",
+                        index.columns.join("`, `")
+                    )));
+                };
+                Some(tenant.to_string())
+            } else {
+                None
+            };
+            if index.deferrable
+                && (!index.unique
+                    || index.not_null
+                    || index.when.is_some()
+                    || index.when_null.is_some()
+                    || index.columns.iter().any(|column| column.contains('(')))
+            {
+                return Err(Error::Message(format!(
+                    "index over `{}` is deferrable, which only a unique constraint over plain \
+                     columns of all the rows can be",
+                    index.columns.join("`, `")
+                )));
+            }
+            if index.gin {
+                gin_index_columns(index, columns)?;
+            }
+            if let Some(tablespace) = &index.tablespace {
+                let is_name = tablespace
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && tablespace
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !is_name {
+                    return Err(Error::Message(format!(
+                        "`{tablespace}` is not a tablespace name"
+                    )));
+                }
+                if index.deferrable {
+                    return Err(Error::Message(format!(
+                        "index over `{}` is deferrable, which cannot be placed in a tablespace",
+                        index.columns.join("`, `")
+                    )));
+                }
+            }
+            if let Some(column) = &index.when_null {
+                if !columns.iter().any(|(name, _)| name == column) {
+                    return Err(Error::Message(format!(
+                        "index condition `{column}` is not a column of the model"
+                    )));
+                }
+            }
+            if let Some(flag) = &index.when {
+                let is_boolean = columns
+                    .iter()
+                    .find(|(column, _)| column == flag)
+                    .is_some_and(|(_, col_type)| col_type.starts_with("Boolean"));
+                if !is_boolean {
+                    return Err(Error::Message(format!(
+                        "index condition `{flag}` is not a boolean column of the model"
+                    )));
+                }
+            }
+            let columns = tenant
+                .iter()
+                .chain(
+                    index
+                        .columns
+                        .iter()
+                        .filter(|name| tenant.as_ref() != Some(name)),
+                )
+                .map(|name| {
+                    if name.contains('(') {
+                        return index_expression(name, columns, references);
+                    }
+                    if columns.iter().any(|(column, _)| column == name)
+                        || (with_tz && IGNORE_FIELDS[..2].contains(&name.as_str()))
+                    {
+                        return Ok(name.clone());
+                    }
+                    references
+                        .iter()
+                        .map(|(fname, custom)| {
+                            (fname.trim_end_matches('?'), reference_column(fname, custom))
+                        })
+                        .find(|(fname, column)| fname == name || column == name)
+                        .map(|(_, column)| column)
+                        .ok_or_else(|| {
+                            Error::Message(format!(
+                                "index column `{name}` is not a column or reference of the model"
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(IndexSpec {
+                columns,
+                unique: index.unique,
+                not_null: index.not_null,
+                tenant_scoped: false,
+                when: index.when.clone(),
+                when_null: index.when_null.clone(),
+                deferrable: index.deferrable,
+                gin: index.gin,
+                tablespace: index.tablespace.clone(),
+                // as a Rust string literal for the migration
+                comment: index.comment.as_ref().map(|comment| format!("{comment:?}")),
+            })
+        })
+        .collect()
+}
+
+/// Checks a GIN index is not unique, and is over jsonb columns or paths of
+/// them, e.g. `(payload -> 'tags')`.
+fn gin_index_columns(index: &IndexSpec, columns: &[(String, String)]) -> Result<()> {
+    if index.unique {
+        return Err(Error::Message(format!(
+            "GIN index over `{}` cannot be unique",
+            index.columns.join("`, `")
+        )));
+    }
+    let is_jsonb = |name: &str| {
+        columns
+            .iter()
+            .any(|(column, col_type)| column == name && col_type.starts_with("JsonBinary"))
+    };
+    for name in &index.columns {
+        let refers_to = if name.contains('(') {
+            expression_columns(name)
+        } else {
+            vec![name.clone()]
+        };
+        if let Some(column) = refers_to.iter().find(|column| !is_jsonb(column)) {
+            return Err(Error::Message(format!(
+                "GIN index over `{name}` refers to `{column}`, which is not a jsonb column of the \
+                 model"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks an index expression, e.g. `lower(email)`, only refers to columns of
+/// the model.
+fn index_expression(
+    expr: &str,
+    columns: &[(String, String)],
+    references: &[(String, String)],
+) -> Result<String> {
+    let expr = expr.trim();
+    if expr.contains(';') || expr.contains("\"#") {
+        return Err(Error::Message(format!(
+            "index expression `{expr}` is not a single expression"
+        )));
+    }
+    let refers_to = expression_columns(expr);
+    let unknown = refers_to.iter().find(|name| {
+        !columns.iter().any(|(column, _)| column == *name)
+            && !references
+                .iter()
+                .any(|(fname, custom)| reference_column(fname, custom) == **name)
+    });
+    if let Some(name) = unknown {
+        return Err(Error::Message(format!(
+            "index expression `{expr}` refers to `{name}`, which is not a column of the model"
+        )));
+    }
+    if refers_to.is_empty() {
+        return Err(Error::Message(format!(
+            "index expression `{expr}` does not refer to any column of the model"
+        )));
+    }
+    Ok(expr.to_string())
+}
+
+/// Checks the collated indexes are over columns of the model, with
+/// collations that can be quoted as identifiers.
+///
+/// # Errors
+///
+/// When a column is not a column of the model, or a collation is not a name
+pub fn resolve_collated_indexes(
+    indexes: &[CollatedIndex],
+    columns: &[(String, String)],
+) -> Result<Vec<CollatedIndex>> {
+    for index in indexes {
+        if !columns.iter().any(|(column, _)| *column == index.column) {
+            return Err(Error::Message(format!(
+                "collated index column `{}` is not a column of the model",
+                index.column
+            )));
+        }
+        if index.collation.is_empty()
+            || !index
+                .collation
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(Error::Message(format!(
+                "`{}` is not a collation name",
+                index.collation
+            )));
+        }
+    }
+    Ok(indexes.to_vec())
+}
+
+/// The column types postgres stores as variable-length values, the only
+/// ones it can TOAST.
+const TOASTABLE_COL_TYPES: &[&str] = &[
+    "String",
+    "Text",
+    "Blob",
+    "VarBinary",
+    "BinaryLen",
+    "Json",
+    "TsVector",
+    "array",
+];
+
+/// Checks the storage modes are set on variable-length columns of the model,
+/// normalizing the modes to upper case.
+///
+/// # Errors
+///
+/// When a column is not a variable-length column of the model, or a mode is
+/// not one of `PLAIN`, `EXTERNAL`, `EXTENDED` or `MAIN`
+pub fn resolve_column_storages(
+    storages: &[ColumnStorage],
+    columns: &[(String, String)],
+) -> Result<Vec<ColumnStorage>> {
+    storages
+        .iter()
+        .map(|item| {
+            let (_, col_type) = columns
+                .iter()
+                .find(|(column, _)| *column == item.column)
+                .ok_or_else(|| {
+                    Error::Message(format!(
+                        "storage column `{}` is not a column of the model",
+                        item.column
+                    ))
+                })?;
+            let storage = item.storage.to_uppercase();
+            if !["PLAIN", "EXTERNAL", "EXTENDED", "MAIN"].contains(&storage.as_str()) {
+                return Err(Error::Message(format!(
+                    "`{}` is not a storage mode. try any of: `PLAIN`, `EXTERNAL`, `EXTENDED`, \
+                     `MAIN`",
+                    item.storage
+                )));
+            }
+            if !TOASTABLE_COL_TYPES
+                .iter()
+                .any(|toastable| col_type.starts_with(toastable))
+            {
+                return Err(Error::Message(format!(
+                    "storage column `{}` is `{col_type}`, which is not variable-length",
+                    item.column
+                )));
+            }
+            Ok(ColumnStorage {
+                column: item.column.clone(),
+                storage,
+            })
+        })
+        .collect()
+}
+
+/// A `CHECK` constraint added to the table, named after the columns it refers
+/// to.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ResolvedCheck {
+    pub name: String,
+    /// The expression, as a Rust string literal for the migration
+    pub expr: String,
+    /// The expression as SQL, with the references it names resolved
+    pub sql: String,
+}
+
+/// Checks the `CHECK` expressions only refer to columns of the table, which
+/// name them, e.g. `chk-bookings-start_date-end_date` for
+/// `start_date <= end_date`. A reference can be referred to by its field
+/// name, e.g. `user` for `user_id`.
+///
+/// # Errors
+///
+/// When an expression refers to a column or reference the table does not
+/// have, or to none at all
+pub fn resolve_checks(
+    table: &str,
+    checks: &[String],
+    with_tz: bool,
+    columns: &[(String, String)],
+    references: &[(String, String)],
+) -> Result<Vec<ResolvedCheck>> {
+    let mut known = columns
+        .iter()
+        .map(|(column, _)| column.clone())
+        .chain(
+            references
+                .iter()
+                .map(|(fname, custom)| reference_column(fname, custom)),
+        )
+        .collect::<Vec<_>>();
+    if shared_primary_key(columns).is_none() {
+        known.push("id".to_string());
+    }
+    if with_tz {
+        known.extend(IGNORE_FIELDS[..2].iter().map(ToString::to_string));
+    }
+
+    let mut resolved = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for expr in checks {
+        let expr = expr.trim();
+        if expr.is_empty() || expr.contains(';') {
+            return Err(Error::Message(format!(
+                "check `{expr}` is not a single expression"
+            )));
+        }
+        let expr = &resolve_reference_names(expr, &known, references);
+        let refers_to = expression_columns(expr);
+        if let Some(unknown) = refers_to.iter().find(|column| !known.contains(column)) {
+            return Err(Error::Message(format!(
+                "check `{expr}` refers to `{unknown}`, which is not a column of `{table}`"
+            )));
+        }
+        if refers_to.is_empty() {
+            return Err(Error::Message(format!(
+                "check `{expr}` does not refer to any column of `{table}`"
+            )));
+        }
+        let name = format!("chk-{table}-{}", refers_to.join("-"));
+        // checks over the same columns are numbered
+        let taken = names.iter().filter(|taken| **taken == name).count();
+        names.push(name.clone());
+        resolved.push(ResolvedCheck {
+            name: if taken == 0 {
+                name
+            } else {
+                format!("{name}-{}", taken + 1)
+            },
+            expr: format!("{expr:?}"),
+            sql: expr.to_string(),
+        });
+    }
+    Ok(resolved)
+}
+
+/// An exclusion constraint added to the table, named after its columns, e.g.
+/// `excl-bookings-room_id-during`.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ResolvedNoOverlap {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub range: String,
+}
+
+/// Resolves the columns of the exclusion constraints, a reference's field
+/// name to its foreign key column, e.g. `room` to `room_id`, and checks the
+/// range column is one.
+///
+/// # Errors
+///
+/// When a constraint refers to a column the table does not have, or its range
+/// column is not of a range type
+pub fn resolve_no_overlaps(
+    table: &str,
+    no_overlaps: &[NoOverlap],
+    columns: &[(String, String)],
+    references: &[(String, String)],
+) -> Result<Vec<ResolvedNoOverlap>> {
+    let resolve = |name: &str| {
+        if columns.iter().any(|(column, _)| column == name) {
+            return Some(name.to_string());
+        }
+        references
+            .iter()
+            .map(|(fname, custom)| (fname.trim_end_matches('?'), reference_column(fname, custom)))
+            .find(|(fname, column)| *fname == name || column == name)
+            .map(|(_, column)| column)
+    };
+    no_overlaps
+        .iter()
+        .map(|no_overlap| {
+            let equal = no_overlap
+                .columns
+                .iter()
+                .map(|name| {
+                    resolve(name).ok_or_else(|| {
+                        Error::Message(format!(
+                            "no overlap constraint `{no_overlap}` refers to `{name}`, which is \
+                             not a column of `{table}`"
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let is_range = columns.iter().any(|(column, col_type)| {
+                *column == no_overlap.range && col_type.starts_with("TstzRange")
+            });
+            if !is_range {
+                return Err(Error::Message(format!(
+                    "no overlap constraint `{no_overlap}` needs a range column, `{}` is not a \
+                     range column of `{table}`",
+                    no_overlap.range
+                )));
+            }
+            Ok(ResolvedNoOverlap {
+                name: format!("excl-{table}-{}-{}", equal.join("-"), no_overlap.range),
+                columns: equal,
+                range: no_overlap.range.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Rewrites the references an SQL expression refers to by field name, e.g.
+/// `user IS NOT NULL`, to their foreign key column, `user_id IS NOT NULL`.
+/// Names of `columns` are left as they are.
+fn resolve_reference_names(
+    expr: &str,
+    columns: &[String],
+    references: &[(String, String)],
+) -> String {
+    let mut resolved = expr.to_string();
+    // from the end, so the spans before stay in place
+    for (span, name) in expression_identifiers(expr).into_iter().rev() {
+        if columns.contains(&name) {
+            continue;
+        }
+        let column = references
+            .iter()
+            .find(|(fname, _)| fname.trim_end_matches('?') == name)
+            .map(|(fname, custom)| reference_column(fname, custom));
+        if let Some(column) = column {
+            let column = if expr[span.clone()].starts_with('"') {
+                format!("\"{column}\"")
+            } else {
+                column
+            };
+            resolved.replace_range(span, &column);
+        }
+    }
+    resolved
+}
+
+/// The columns an SQL expression refers to, in order: its identifiers,
+/// bare or double quoted, other than SQL keywords, function names and the
+/// types of `::` casts.
+fn expression_columns(expr: &str) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for (_, column) in expression_identifiers(expr) {
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    columns
+}
+
+/// Each identifier of an SQL expression referring to a column, see
+/// [`expression_columns`], along with its span, quotes included.
+fn expression_identifiers(expr: &str) -> Vec<(std::ops::Range<usize>, String)> {
+    const KEYWORDS: &[&str] = &[
+        "and",
+        "or",
+        "not",
+        "null",
+        "is",
+        "in",
+        "between",
+        "like",
+        "ilike",
+        "true",
+        "false",
+        "case",
+        "when",
+        "then",
+        "else",
+        "end",
+        "distinct",
+        "from",
+        "escape",
+        "current_date",
+        "current_time",
+        "current_timestamp",
+    ];
+    let mut identifiers = Vec::new();
+    let mut cast = false;
+    let mut chars = expr.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\'' => {
+                chars.by_ref().find(|&(_, c)| c == '\'');
+            }
+            '"' => {
+                let end = chars
+                    .by_ref()
+                    .find(|&(_, c)| c == '"')
+                    .map_or(expr.len(), |(i, _)| i);
+                identifiers.push((
+                    start..(end + 1).min(expr.len()),
+                    expr[start + 1..end].to_string(),
+                ));
+            }
+            ':' if chars.peek().is_some_and(|&(_, c)| c == ':') => {
+                chars.next();
+                cast = true;
+                continue;
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let word_is_number = c.is_ascii_digit();
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    // the fraction of a number
+                    let is_fraction = c == '.' && word_is_number;
+                    if !(c.is_ascii_alphanumeric() || c == '_' || is_fraction) {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                let word = &expr[start..end];
+                let is_call = expr[end..].trim_start().starts_with('(');
+                if !(cast
+                    || is_call
+                    || word_is_number
+                    || KEYWORDS.contains(&word.to_ascii_lowercase().as_str()))
+                {
+                    identifiers.push((start..end, word.to_string()));
+                }
+            }
+            c if c.is_whitespace() => continue,
+            _ => {}
+        }
+        cast = false;
+    }
+    identifiers
+}
+
+/// A counter cache along with the foreign key its triggers follow.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ResolvedCounterCache {
+    pub parent: String,
+    pub column: String,
+    pub child: String,
+    pub fk: String,
+}
+
+/// Resolves the counter caches maintained on `table` against its references.
+///
+/// # Errors
+///
+/// When a counter cache counts another table, or `table` does not reference
+/// its parent
+pub fn resolve_counter_caches(
+    table: &str,
+    counter_caches: &[CounterCache],
+    references: &[(String, String)],
+) -> Result<Vec<ResolvedCounterCache>> {
+    let table = table.to_plural().to_snake_case();
+    counter_caches
+        .iter()
+        .map(|cache| {
+            let child = cache.child.to_plural().to_snake_case();
+            if child != table {
+                return Err(Error::Message(format!(
+                    "counter cache `{cache}` counts `{child}`, but the generated table is `{table}`"
+                )));
+            }
+            let parent = cache.parent.to_plural().to_snake_case();
+            let fk = references
+                .iter()
+                .find(|(fname, _)| {
+                    fname.trim_end_matches('?').to_plural().to_snake_case() == parent
+                })
+                .map(|(fname, custom)| reference_column(fname, custom))
+                .ok_or_else(|| {
+                    Error::Message(format!(
+                        "counter cache `{cache}` needs a reference from `{table}` to `{parent}`"
+                    ))
+                })?;
+            Ok(ResolvedCounterCache {
+                parent,
+                column: cache.column.clone(),
+                child,
+                fk,
+            })
+        })
+        .collect()
+}
+
+/// Checks the denormalized columns are new to the model and built from its
+/// columns.
+///
+/// # Errors
+///
+/// When a denormalized column is already a column of the model, or one of
+/// its sources is not
+pub fn resolve_denormalized(
+    denormalized: &[Denormalized],
+    columns: &[(String, String)],
+) -> Result<Vec<Denormalized>> {
+    for item in denormalized {
+        if columns.iter().any(|(column, _)| *column == item.column) {
+            return Err(Error::Message(format!(
+                "denormalized column `{}` is already a column of the model",
+                item.column
+            )));
+        }
+        if let Some(source) = item
+            .sources
+            .iter()
+            .find(|source| !columns.iter().any(|(column, _)| column == *source))
+        {
+            return Err(Error::Message(format!(
+                "denormalized column `{}` is built from `{source}`, which is not a column of the \
+                 model",
+                item.column
+            )));
+        }
+    }
+    Ok(denormalized.to_vec())
+}
+
+/// Whether `name` can name a composite type: a snake case identifier, which
+/// postgres takes as it is.
+fn is_composite_type_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The composite type of a column, from a generated
+/// `CompositeNull("address".to_string())`.
+fn composite_col_type_name(col_type: &str) -> Option<&str> {
+    let rest = ["CompositeNull(", "Composite("]
+        .iter()
+        .find_map(|prefix| col_type.strip_prefix(prefix))?;
+    let (name, _) = rest.strip_prefix('"')?.split_once('"')?;
+    Some(name)
+}
+
+/// The Rust type of a composite type field, by the base of its SQL type.
+/// Fields of a composite type are nullable, which the caller adds.
+fn composite_field_rust_type(sql_type: &str) -> Option<&'static str> {
+    let base = sql_type
+        .split('(')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let rust_type = match base.as_str() {
+        "text" | "varchar" | "character varying" | "char" | "character" => "String",
+        "smallint" | "int2" => "i16",
+        "integer" | "int" | "int4" => "i32",
+        "bigint" | "int8" => "i64",
+        "boolean" | "bool" => "bool",
+        "real" | "float4" => "f32",
+        "double precision" | "float8" => "f64",
+        "numeric" | "decimal" => "Decimal",
+        "date" => "Date",
+        "timestamp" => "DateTime",
+        "timestamptz" | "timestamp with time zone" => "DateTimeWithTimeZone",
+        "uuid" => "Uuid",
+        "json" | "jsonb" => "Json",
+        _ => return None,
+    };
+    Some(rust_type)
+}
+
+/// A composite type created by the migration, with the Rust types of its
+/// fields for the struct of the model file.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ResolvedCompositeType {
+    pub name: String,
+    pub fields: Vec<CompositeField>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct CompositeField {
+    pub name: String,
+    pub sql_type: String,
+    pub rust_type: String,
+}
+
+/// Checks the composite types to create are each the type of a column of the
+/// model, and gets the Rust types of their fields.
+///
+/// # Errors
+///
+/// When a composite type is given twice, is not the type of any column, or
+/// has a field of a SQL type without a Rust type
+pub fn resolve_composite_types(
+    composite_types: &[CompositeType],
+    columns: &[(String, String)],
+) -> Result<Vec<ResolvedCompositeType>> {
+    let mut resolved: Vec<ResolvedCompositeType> = Vec::new();
+    for composite in composite_types {
+        if resolved.iter().any(|other| other.name == composite.name) {
+            return Err(Error::Message(format!(
+                "composite type `{}` is given twice",
+                composite.name
+            )));
+        }
+        if !columns
+            .iter()
+            .any(|(_, col_type)| composite_col_type_name(col_type) == Some(&composite.name))
+        {
+            return Err(Error::Message(format!(
+                "composite type `{}` is not the type of any column of the model",
+                composite.name
+            )));
+        }
+        let fields = composite
+            .fields
+            .iter()
+            .map(|(name, sql_type)| {
+                let rust_type = composite_field_rust_type(sql_type).ok_or_else(|| {
+                    Error::Message(format!(
+                        "composite type `{}` has `{name}` of SQL type `{sql_type}`, which has no \
+                         Rust type",
+                        composite.name
+                    ))
+                })?;
+                Ok(CompositeField {
+                    name: name.clone(),
+                    sql_type: sql_type.clone(),
+                    rust_type: rust_type.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        resolved.push(ResolvedCompositeType {
+            name: composite.name.clone(),
+            fields,
+        });
+    }
+    Ok(resolved)
+}
+
+/// Checks the table gets the primary key a `WITHOUT ROWID` table requires,
+/// which is only added along with other columns, and that a many-to-many
+/// join table gets its composite one.
+///
+/// # Errors
+///
+/// When the table is `WITHOUT ROWID` but has no columns, or a join table
+/// shares its primary key or cannot be created as requested
+pub fn check_primary_key(
+    name: &str,
+    columns: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<()> {
+    if options.many_to_many.is_some() {
+        if let Some(shared) = shared_primary_key(columns) {
+            return Err(Error::Message(format!(
+                "field `{shared}` cannot share the primary key, a many-to-many join table's is \
+                 made up of its two references"
+            )));
+        }
+        if options.unlogged || options.without_rowid || options.sqlite_strict {
+            return Err(Error::Message(
+                "a many-to-many join table is created with `create_join_table`, which cannot make \
+                 it unlogged, WITHOUT ROWID or STRICT"
+                    .to_string(),
+            ));
+        }
+    }
+    if options.without_rowid && columns.is_empty() {
+        return Err(Error::Message(format!(
+            "a WITHOUT ROWID table needs a primary key, but `{name}` has no columns to add one \
+             with"
+        )));
+    }
+    Ok(())
+}
+
+/// The unique (`^`) fields of the model.
+fn unique_fields(fields: &[(String, String)], options: &GenerateOptions) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|(fname, ftype)| {
+            !options.ignores_field(fname)
+                && ftype
+                    .split([':', '@'])
+                    .next()
+                    .unwrap_or_default()
+                    .ends_with('^')
+        })
+        .map(|(fname, _)| fname.clone())
+        .collect()
+}
+
+/// The conflict target of the seed loader, the unique (`^`) columns of the
+/// model.
+///
+/// # Errors
+///
+/// When the model has no unique column
+pub fn seed_conflict_columns(
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+) -> Result<Vec<String>> {
+    let conflict = unique_fields(fields, options);
+    if conflict.is_empty() {
+        return Err(Error::Message(
+            "a seed loader skips the rows conflicting on the unique (`^`) columns, but the model \
+             has none"
+                .to_string(),
+        ));
+    }
+    Ok(conflict)
+}
+
+/// The columns of an `upsert`: the conflict target and the columns updated
+/// on a conflict.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Upsert {
+    pub conflict: Vec<String>,
+    pub update: Vec<String>,
+}
+
+/// Derives the `upsert` columns, the conflict target defaulting to the unique
+/// (`^`) columns.
+///
+/// # Errors
+///
+/// When there is no conflict target, or it names an unknown column
+pub fn upsert_columns(
+    fields: &[(String, String)],
+    columns: &[(String, String)],
+    references: &[(String, String)],
+    with_tz: bool,
+    options: &GenerateOptions,
+) -> Result<Upsert> {
+    let mut all = columns
+        .iter()
+        .map(|(name, _)| name.clone())
+        .chain(
+            references
+                .iter()
+                .map(|(fname, custom)| reference_column(fname, custom)),
+        )
+        .collect::<Vec<_>>();
+    if with_tz {
+        all.push("updated_at".to_string());
+    }
+
+    let conflict = if options.upsert_conflict.is_empty() {
+        unique_fields(fields, options)
+    } else {
+        options.upsert_conflict.clone()
+    };
+    if conflict.is_empty() {
+        return Err(Error::Message(
+            "an upsert needs a unique (`^`) column or an explicit conflict target".to_string(),
+        ));
+    }
+    if let Some(unknown) = conflict.iter().find(|name| !all.contains(name)) {
+        return Err(Error::Message(format!(
+            "upsert conflict column `{unknown}` is not a column of the model"
+        )));
+    }
+
+    let mut update = all
+        .into_iter()
+        .filter(|name| !conflict.contains(name))
+        .collect::<Vec<_>>();
+    if update.is_empty() {
+        // `DO NOTHING` would not return the row, update it to itself instead
+        update.clone_from(&conflict);
+    }
+    Ok(Upsert { conflict, update })
+}
+
+/// Orders a batch of models so that each comes after the models it
+/// references, e.g. to name their migrations in the order to apply them.
+/// References outside of the batch, and of a model to itself, do not order
+/// anything. Models otherwise keep their order.
+///
+/// # Errors
+///
+/// When a field cannot be parsed, or the models reference each other in a
+/// cycle
+pub fn plan(models: &[(String, Vec<(String, String)>)]) -> Result<Vec<String>> {
+    let tables = models
+        .iter()
+        .map(|(name, _)| name.to_plural().to_snake_case())
+        .collect::<Vec<_>>();
+    let mut depends_on = Vec::with_capacity(models.len());
+    for (i, (_, fields)) in models.iter().enumerate() {
+        let mut parents = Vec::new();
+        for (fname, ftype) in fields {
+            let Some(table) = referenced_table(fname, &parse_field_type(ftype)?) else {
+                continue;
+            };
+            let table = table.rsplit('.').next().unwrap_or_default();
+            if let Some(parent) = tables.iter().position(|t| *t == table) {
+                if parent != i && !parents.contains(&parent) {
+                    parents.push(parent);
+                }
+            }
+        }
+        depends_on.push(parents);
+    }
+
+    let mut order = Vec::with_capacity(models.len());
+    let mut placed = vec![false; models.len()];
+    while order.len() < models.len() {
+        let Some(next) = (0..models.len())
+            .find(|&i| !placed[i] && depends_on[i].iter().all(|&parent| placed[parent]))
+        else {
+            let cycle = (0..models.len())
+                .filter(|&i| !placed[i])
+                .map(|i| models[i].0.as_str())
+                .collect::<Vec<_>>();
+            return Err(Error::Message(format!(
+                "models `{}` reference each other in a cycle, there is no order to migrate them in",
+                cycle.join("`, `")
+            )));
+        };
+        placed[next] = true;
+        order.push(models[next].0.clone());
+    }
+    Ok(order)
+}
+
+/// The table a reference field points at, `<schema>.<table>` for a table of
+/// another schema, `None` for a value field.
+fn referenced_table(fname: &str, field_type: &crate::infer::FieldType) -> Option<String> {
+    use crate::infer::FieldType;
+
+    match field_type {
+        FieldType::Reference
+        | FieldType::ReferenceWithCustomField(_)
+        | FieldType::SharedPrimaryKeyReference
+        | FieldType::NullableReference
+        | FieldType::NullableReferenceWithCustomField(_) => Some(fname.to_plural().to_snake_case()),
+        FieldType::ReferenceToTable(table) | FieldType::NullableReferenceToTable(table) => {
+            Some(match table.rsplit_once('.') {
+                Some((schema, table)) => {
+                    format!("{schema}.{}", table.to_plural().to_snake_case())
+                }
+                None => table.to_plural().to_snake_case(),
+            })
+        }
+        FieldType::Enum(_)
+        | FieldType::NullableEnum(_)
+        | FieldType::ManyToMany(_)
+        | FieldType::Type(_)
+        | FieldType::TypeWithParameters(_, _)
+        | FieldType::TypeWithDefault(_, _) => None,
+    }
+}
+
+/// Validates each field of a spec on its own, for editors: unlike generating,
+/// which stops at the first error, every problem is reported along with the
+/// token of the field causing it.
+#[must_use]
+pub fn validate_spec(fields: &[(String, String)], options: &GenerateOptions) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (index, (fname, ftype)) in fields.iter().enumerate() {
+        let field = format!("{fname}:{ftype}");
+        let diagnostic = |span: std::ops::Range<usize>, message: String| Diagnostic {
+            index,
+            field: field.clone(),
+            token: field[span.clone()].to_string(),
+            span,
+            message,
+        };
+        let name_span = 0..fname.len();
+        if fname.is_empty() {
+            diagnostics.push(diagnostic(name_span, "the field has no name".to_string()));
+        } else if fields[..index].iter().any(|(other, _)| other == fname) {
+            diagnostics.push(diagnostic(
+                name_span,
+                format!("field `{fname}` is given more than once"),
+            ));
+        }
+        let columns = with_password_hashes(&[(fname.clone(), ftype.clone())], options);
+        if let Err(err) = get_columns_and_references(&columns, options) {
+            diagnostics.push(type_diagnostic(index, fname, ftype, err.to_string()));
+        }
+    }
+    diagnostics
+}
+
+/// A diagnostic of a field's type, narrowed down to the first quoted part of
+/// the message found in the type.
+fn type_diagnostic(index: usize, fname: &str, ftype: &str, message: String) -> Diagnostic {
+    let field = format!("{fname}:{ftype}");
+    let type_start = fname.len() + 1;
+    let span = message
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .filter(|quoted| !quoted.is_empty())
+        .find_map(|quoted| ftype.find(quoted).map(|at| at..at + quoted.len()))
+        .map_or(type_start..field.len(), |span| {
+            type_start + span.start..type_start + span.end
+        });
+    Diagnostic {
+        index,
+        token: field[span.clone()].to_string(),
+        field,
+        span,
+        message,
+    }
+}
 
-                        format!(
-                            r"{}(ArrayColType::{})",
-                            col_type,
-                            array_kind.to_upper_camel_case()
-                        )
-                    }
-                    &_ => {
-                        format!("{}({})", col_type, params.join(","))
-                    }
-                };
+/// Reads a directory of model specs, one file per model named after it, e.g.
+/// `post.fields`, holding a `<name>:<type>` field a line as given to `cargo
+/// loco generate model`. Blank lines and `#` comments are skipped, the models
+/// come sorted by name.
+///
+/// # Errors
+///
+/// When the directory or a spec cannot be read
+#[allow(clippy::type_complexity)]
+pub fn read_model_specs(dir: &Path) -> Result<Vec<(String, Vec<(String, String)>)>> {
+    let mut models = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|_| path.extension().is_some_and(|ext| ext == "fields"))
+        else {
+            continue;
+        };
+        let fields = fs::read_to_string(&path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (fname, ftype) = line.split_once(':').unwrap_or((line, ""));
+                (fname.to_string(), ftype.to_string())
+            })
+            .collect();
+        models.push((name.to_string(), fields));
+    }
+    models.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(models)
+}
 
-                columns.push((fname.clone(), col));
+/// Validates a whole directory of model specs, see [`read_model_specs`]: each
+/// model with [`validate_spec`], and across models, that every reference is
+/// to the table of a model of the directory or of a migration in
+/// [`GenerateOptions::migration_dir`]. References to tables of another schema
+/// are not checked.
+///
+/// # Errors
+///
+/// When the specs or the migrations cannot be read
+pub fn validate_models(dir: &Path, options: &GenerateOptions) -> Result<Vec<ModelDiagnostic>> {
+    let models = read_model_specs(dir)?;
+    let mut tables = models
+        .iter()
+        .map(|(name, _)| name.to_plural().to_snake_case())
+        .collect::<Vec<_>>();
+    tables.extend(migrated_tables(options)?);
+
+    let mut diagnostics = Vec::new();
+    for (model, fields) in &models {
+        let mut found = validate_spec(fields, options);
+        for (index, (fname, ftype)) in fields.iter().enumerate() {
+            // a type that does not parse is reported by `validate_spec`
+            let Some(table) = parse_field_type(ftype)
+                .ok()
+                .and_then(|field_type| referenced_table(fname, &field_type))
+            else {
+                continue;
+            };
+            if !table.contains('.') && !tables.contains(&table) {
+                found.push(type_diagnostic(
+                    index,
+                    fname,
+                    ftype,
+                    format!(
+                        "field `{fname}` references `{table}`, which no model or migration creates"
+                    ),
+                ));
             }
         }
+        found.sort_by_key(|diagnostic| diagnostic.index);
+        diagnostics.extend(found.into_iter().map(|diagnostic| ModelDiagnostic {
+            model: model.clone(),
+            diagnostic,
+        }));
     }
-    Ok((columns, references))
+    Ok(diagnostics)
+}
+
+/// The tables the migrations in [`GenerateOptions::migration_dir`] create.
+fn migrated_tables(options: &GenerateOptions) -> Result<Vec<String>> {
+    let src = Path::new(&options.migration_dir()).join("src");
+    let mut tables = Vec::new();
+    if !src.is_dir() {
+        return Ok(tables);
+    }
+    let create = regex::Regex::new(r#"create_\w*table\w*\(\s*m,\s*"([^"]+)""#)
+        .map_err(|err| Error::Message(err.to_string()))?;
+    for entry in fs::read_dir(&src)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            let source = fs::read_to_string(&path)?;
+            tables.extend(create.captures_iter(&source).map(|c| c[1].to_string()));
+        }
+    }
+    Ok(tables)
 }
 
 pub fn generate(
-    rrgen: &RRgen,
+    renderer: &dyn TemplateRenderer,
     name: &str,
     with_tz: bool,
     fields: &[(String, String)],
+    options: &GenerateOptions,
     appinfo: &AppInfo,
 ) -> Result<GenerateResults> {
     let pkg_name: &str = &appinfo.app_name;
     let ts = Utc::now();
+    let fields = &with_soft_delete(&with_tenant(name, fields, options), options);
+    let passwords = password_columns(fields, options)?;
+    let fields = &with_password_hashes(fields, options);
+
+    options.check_backend()?;
+    validate_model_name(name)?;
+    let join_tables = many_to_many_tables(fields, options)?;
+    let (mut columns, mut references) = get_columns_and_references(fields, options)?;
+    if let Some((a, b)) = &options.many_to_many {
+        references.splice(0..0, many_to_many_references(a, b));
+    }
+    check_primary_key(name, &columns, options)?;
+    let identifier_warnings = check_identifiers(
+        &name.to_plural().to_snake_case(),
+        true,
+        &columns,
+        &references,
+        options,
+    )?;
+    let mut indexes = resolve_indexes(
+        &requested_indexes(fields, options)?,
+        &columns,
+        &references,
+        with_tz,
+        options.tenant.as_deref(),
+    )?;
+    let content_hash = options
+        .content_hash
+        .as_deref()
+        .map(|column| content_hash(fields, column, options))
+        .transpose()?;
+    if let Some(content_hash) = &content_hash {
+        content_hash.add_to(&mut columns, &mut indexes);
+    }
+    soft_delete_unique(&mut columns, &mut indexes, options)?;
+    let counter_caches = resolve_counter_caches(name, &options.counter_caches, &references)?;
+    let collated_indexes = resolve_collated_indexes(&options.collated_indexes, &columns)?;
+    let column_storages = resolve_column_storages(&options.column_storages, &columns)?;
+    let denormalized = resolve_denormalized(&options.denormalized, &columns)?;
+    let composite_types = resolve_composite_types(&options.composite_types, &columns)?;
+    let checks = resolve_checks(
+        &name.to_plural().to_snake_case(),
+        &options.checks,
+        with_tz,
+        &columns,
+        &references,
+    )?;
+    let no_overlaps = resolve_no_overlaps(
+        &name.to_plural().to_snake_case(),
+        &options.no_overlaps,
+        &columns,
+        &references,
+    )?;
+    let money_minor = typed_columns(fields, "money_minor", options);
+    let phone = typed_columns(fields, "phone", options);
+    let cache_key = options.cache_key && with_tz;
+    let encrypted = encrypted_columns(fields, options)?;
+    let state_machines = state_machine_columns(fields, options)?;
+    let enums = enum_columns(&columns);
+    let builder = if options.builder {
+        builder_columns(fields, options)?
+    } else {
+        Vec::new()
+    };
+    let reference_accessors = if options.reference_accessors {
+        reference_accessors(fields, options)?
+    } else {
+        Vec::new()
+    };
+    let delete_guard = options
+        .delete_guard
+        .iter()
+        .map(|table| table.to_plural().to_snake_case())
+        .collect::<Vec<_>>();
+    let search_column = if options.search_rank {
+        let tsvectors = typed_columns(fields, "tsvector", options);
+        let Some(column) = tsvectors.into_iter().next() else {
+            return Err(Error::Message(
+                "a search helper needs a `tsvector` column in the model".to_string(),
+            ));
+        };
+        Some(column.name)
+    } else {
+        None
+    };
+
+    let active_period = options
+        .active_period
+        .as_deref()
+        .map(|column| active_period_column(fields, column, options))
+        .transpose()?;
+
+    let display_column = options
+        .display_column
+        .as_deref()
+        .map(|column| display_column(fields, column))
+        .transpose()?;
+
+    let upsert = if options.upsert {
+        Some(upsert_columns(
+            fields,
+            &columns,
+            &references,
+            with_tz,
+            options,
+        )?)
+    } else {
+        None
+    };
+    let seed_conflict = if options.seed_loader {
+        seed_conflict_columns(fields, options)?
+    } else {
+        vec![]
+    };
+    // after the upsert, which should not overwrite it
+    columns.extend(db_created_at(with_tz, options));
+    if let Some(column) = history_column(&columns, options)? {
+        columns.push(column);
+    }
+
+    let enum_types = new_enum_types(&columns, options)?;
+    let guarded_enum_types = if options.enum_if_not_exists {
+        enum_types
+            .iter()
+            .filter_map(|enum_type| {
+                columns
+                    .iter()
+                    .filter_map(|(_, col_type)| parse_enum_col_type(col_type))
+                    .find(|(name, _)| name == enum_type)
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    // the key of an `id` generated on insert, the migration creates none for
+    // a shared primary key
+    let uuid_pk = options
+        .pk_kind
+        .uuid_constructor()
+        .filter(|_| !columns.is_empty() && shared_primary_key(&columns).is_none());
+    let vars = json!({"name": name, "ts": ts, "with_tz": with_tz,"pkg_name": pkg_name, "columns": columns, "references": references, "before_save": options.before_save, "pk_col_type": options.pk_col_type()?, "uuid_pk": uuid_pk, "indexes": indexes, "collated_indexes": collated_indexes, "column_storages": column_storages, "checks": checks, "no_overlaps": no_overlaps, "counter_caches": counter_caches, "denormalized": denormalized, "composite_types": composite_types, "money_minor": money_minor, "phone": phone, "passwords": passwords, "cache_key": cache_key, "encrypted": encrypted, "state_machines": state_machines, "search_column": search_column, "upsert": upsert, "seed_conflict": seed_conflict, "display": options.display || display_column.is_some(), "display_column": display_column, "concurrently": options.concurrently, "in_transaction": options.migration_in_transaction(&indexes)?, "delete_guard": delete_guard, "delete_guard_column": reference_column(name, ""), "migration_dir": options.migration_dir(), "unlogged": options.unlogged, "without_rowid": options.without_rowid, "sqlite_strict": options.sqlite_strict, "metadata": options.migration_metadata, "content_hash": content_hash, "if_exists": options.drop_if_exists, "history": options.history, "sequences": sequences(fields, options)?, "enum_types": enum_types, "guarded_enum_types": guarded_enum_types, "enums": enums, "builder": builder, "reference_accessors": reference_accessors, "active_period": active_period, "many_to_many": options.many_to_many.is_some(), "shared_pk": shared_primary_key(&columns), "fk_comments": foreign_key_comments(fields)?, "soft_delete": options.soft_delete});
+    let mut gen_result = if options.append_to_latest {
+        let mut res = render_template(renderer, Path::new("model/test.t"), &vars)?;
+        res.merge(migration::append_to_latest(
+            Path::new("model/model.t"),
+            &vars,
+            &options.migration_dir(),
+        )?);
+        res
+    } else {
+        render_template(renderer, Path::new("model"), &vars)?
+    };
+    gen_result
+        .warnings
+        .extend(redundant_field_warnings(fields, options));
+    gen_result.warnings.extend(identifier_warnings);
+    if options.db_created_at && with_tz {
+        let warning =
+            "`created_at` is already defaulted by the database on a model with timestamps";
+        tracing::warn!(model = name, "{warning}");
+        gen_result.warnings.push(warning.to_string());
+    }
+    if options.cache_key && !with_tz {
+        let warning = "a cache key needs `updated_at`, skipping it for a model without timestamps";
+        tracing::warn!(model = name, "{warning}");
+        gen_result.warnings.push(warning.to_string());
+    }
+
+    // the model file is otherwise created empty by `db entities`, only take it
+    // over when there is something to put in it
+    if options.needs_model_file()
+        || !money_minor.is_empty()
+        || !phone.is_empty()
+        || !passwords.is_empty()
+        || cache_key
+        || !encrypted.is_empty()
+        || search_column.is_some()
+        || upsert.is_some()
+        || !seed_conflict.is_empty()
+        || content_hash.is_some()
+        || !delete_guard.is_empty()
+        || !state_machines.is_empty()
+        || !enums.is_empty()
+        || !composite_types.is_empty()
+        || !builder.is_empty()
+        || !reference_accessors.is_empty()
+        || active_period.is_some()
+    {
+        let res = render_template(renderer, Path::new("model_ext"), &vars)?;
+        gen_result.merge(res);
+    }
+
+    // after the model's table, which the join tables refer to
+    for table in join_tables {
+        let join_table = format!(
+            "CreateJoinTable{}And{}",
+            name.to_plural().to_pascal_case(),
+            table.to_plural().to_pascal_case()
+        );
+        let res = migration::generate(renderer, &join_table, with_tz, &[], options, appinfo)?;
+        gen_result.merge(res);
+    }
+
+    if options.effective_mode() == GenerateMode::Full {
+        migrate_and_generate_entities()?;
+    }
+
+    Ok(gen_result)
+}
+
+/// Generates the model files by migrating and re-running seaorm.
+fn migrate_and_generate_entities() -> Result<()> {
+    let cwd = current_dir()?;
+    let env_map: HashMap<_, _> = std::env::vars().collect();
+
+    let _ = cmd!("cargo", "loco-tool", "db", "migrate",)
+        .stderr_to_stdout()
+        .dir(cwd.as_path())
+        .full_env(&env_map)
+        .run()
+        .map_err(|err| {
+            Error::Message(format!(
+                "failed to run loco db migration. error details: `{err}`",
+            ))
+        })?;
+    let _ = cmd!("cargo", "loco-tool", "db", "entities",)
+        .stderr_to_stdout()
+        .dir(cwd.as_path())
+        .full_env(&env_map)
+        .run()
+        .map_err(|err| {
+            Error::Message(format!(
+                "failed to run loco db entities. error details: `{err}`",
+            ))
+        })?;
+    Ok(())
+}
+
+/// A schema file describing several models, see [`generate_from_schema`].
+#[derive(Debug, Deserialize)]
+struct SchemaFile {
+    models: Vec<SchemaModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SchemaModel {
+    name: String,
+    /// `name:type` specs, e.g. `title:string!`
+    #[serde(default)]
+    fields: Vec<String>,
+    #[serde(default)]
+    without_tz: bool,
+}
+
+/// Renders nothing, to check that the models generate before any is rendered.
+struct DryRun;
 
-    let (columns, references) = get_columns_and_references(fields)?;
-
-    let vars = json!({"name": name, "ts": ts, "with_tz": with_tz,"pkg_name": pkg_name, "columns": columns, "references": references});
-    let gen_result = render_template(rrgen, Path::new("model"), &vars)?;
-
-    if std::env::var("SKIP_MIGRATION").is_err() {
-        // generate the model files by migrating and re-running seaorm
-        let cwd = current_dir()?;
-        let env_map: HashMap<_, _> = std::env::vars().collect();
-
-        let _ = cmd!("cargo", "loco-tool", "db", "migrate",)
-            .stderr_to_stdout()
-            .dir(cwd.as_path())
-            .full_env(&env_map)
-            .run()
-            .map_err(|err| {
-                Error::Message(format!(
-                    "failed to run loco db migration. error details: `{err}`",
-                ))
-            })?;
-        let _ = cmd!("cargo", "loco-tool", "db", "entities",)
-            .stderr_to_stdout()
-            .dir(cwd.as_path())
-            .full_env(&env_map)
-            .run()
-            .map_err(|err| {
-                Error::Message(format!(
-                    "failed to run loco db entities. error details: `{err}`",
-                ))
-            })?;
+impl TemplateRenderer for DryRun {
+    fn render(&self, _path: &Path, _template: &str, _vars: &Value) -> Result<GenResult> {
+        Ok(GenResult::Skipped)
     }
+}
+
+/// Generates the models a YAML or JSON schema file describes, in the order of
+/// the file so that the tables a model references are created first, then
+/// migrates and regenerates the entities once for all of them.
+///
+/// ```yaml
+/// models:
+///   - name: user
+///     fields: ["name:string!"]
+///   - name: post
+///     fields: ["title:string!", "user:references"]
+///     without_tz: true
+/// ```
+///
+/// # Errors
+///
+/// When the file cannot be read or parsed, or a model does not generate, in
+/// which case none of them are rendered
+pub fn generate_from_schema(
+    renderer: &dyn TemplateRenderer,
+    path: &Path,
+    options: &GenerateOptions,
+    appinfo: &AppInfo,
+) -> Result<GenerateResults> {
+    let content = fs::read_to_string(path)?;
+    let schema: SchemaFile = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&content).map_err(|err| err.to_string()),
+        Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|err| err.to_string()),
+        _ => Err("expected a `.yaml`, `.yml` or `.json` file".to_string()),
+    }
+    .map_err(|err| {
+        Error::Message(format!(
+            "cannot read the schema `{}`: {err}",
+            path.display()
+        ))
+    })?;
+
+    let mut models = Vec::new();
+    for model in schema.models {
+        let fields = model
+            .fields
+            .iter()
+            .map(|spec| {
+                spec.split_once(':')
+                    .map(|(fname, ftype)| (fname.to_string(), ftype.to_string()))
+                    .ok_or_else(|| {
+                        Error::Message(format!(
+                            "model `{}`: field `{spec}` is not a `name:type` spec",
+                            model.name
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        models.push((model.name, !model.without_tz, fields));
+    }
+    generate_batch(renderer, &models, options, appinfo)
+}
 
+/// Renders a batch of models, `(name, with_tz, fields)`, in their order,
+/// after checking that every one of them generates, and migrates them at
+/// once.
+#[allow(clippy::type_complexity)]
+fn generate_batch(
+    renderer: &dyn TemplateRenderer,
+    models: &[(String, bool, Vec<(String, String)>)],
+    options: &GenerateOptions,
+    appinfo: &AppInfo,
+) -> Result<GenerateResults> {
+    // migrating in between would only get in the way of the models that
+    // reference each other
+    let template_only = GenerateOptions {
+        mode: GenerateMode::TemplateOnly,
+        ..options.clone()
+    };
+    let in_model = |name: &str, err: Error| Error::Message(format!("model `{name}`: {err}"));
+    for (name, with_tz, fields) in models {
+        generate(&DryRun, name, *with_tz, fields, &template_only, appinfo)
+            .map_err(|err| in_model(name, err))?;
+    }
+    let mut gen_result = GenerateResults::default();
+    for (name, with_tz, fields) in models {
+        let res = generate(renderer, name, *with_tz, fields, &template_only, appinfo)
+            .map_err(|err| in_model(name, err))?;
+        gen_result.merge(res);
+    }
+
+    if options.effective_mode() == GenerateMode::Full {
+        migrate_and_generate_entities()?;
+    }
     Ok(gen_result)
 }
 
+/// A hash of a model's spec, its name and fields in order, to tell whether
+/// the spec changed since the model was generated. It is stable across
+/// builds, so that it can be stored alongside the specs.
+#[must_use]
+pub fn spec_hash(name: &str, fields: &[(String, String)]) -> String {
+    // FNV-1a, the hashers of std are free to change between releases
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let spec = fields
+        .iter()
+        .fold(format!("{name}\n"), |spec, (fname, ftype)| {
+            format!("{spec}{fname}:{ftype}\n")
+        });
+    for byte in spec.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+/// The outcome of [`generate_changed`].
+#[derive(Debug)]
+pub struct ChangedModels {
+    /// The models whose spec changed, in the order they were generated
+    pub regenerated: Vec<String>,
+    /// The [`spec_hash`] of every model, to pass on the next run
+    pub hashes: HashMap<String, String>,
+    pub results: GenerateResults,
+}
+
+/// Regenerates the models whose spec changed since `prior_hashes`, as
+/// returned by an earlier run, skipping the others. A model without a prior
+/// hash is generated. The changed models are generated in the order of
+/// [`plan`], and migrated at once.
+///
+/// # Errors
+///
+/// When a changed model cannot be generated, in which case none is rendered
+pub fn generate_changed(
+    renderer: &dyn TemplateRenderer,
+    models: &[(String, Vec<(String, String)>)],
+    prior_hashes: &HashMap<String, String>,
+    options: &GenerateOptions,
+    appinfo: &AppInfo,
+) -> Result<ChangedModels> {
+    let hashes = models
+        .iter()
+        .map(|(name, fields)| (name.clone(), spec_hash(name, fields)))
+        .collect::<HashMap<_, _>>();
+    let changed = models
+        .iter()
+        .filter(|(name, _)| prior_hashes.get(name) != hashes.get(name))
+        .cloned()
+        .collect::<Vec<_>>();
+    let regenerated = plan(&changed)?;
+    let batch = regenerated
+        .iter()
+        .filter_map(|name| changed.iter().find(|(model, _)| model == name))
+        .map(|(name, fields)| (name.clone(), true, fields.clone()))
+        .collect::<Vec<_>>();
+    let results = if batch.is_empty() {
+        GenerateResults::default()
+    } else {
+        generate_batch(renderer, &batch, options, appinfo)?
+    };
+    Ok(ChangedModels {
+        regenerated,
+        hashes,
+        results,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::DbBackend;
 
     fn to_field(name: &str, field_type: &str) -> (String, String) {
         (name.to_string(), field_type.to_string())
@@ -156,7 +3368,8 @@ mod tests {
             to_field("expect_string", "string!"),
             to_field("expect_unique", "string^"),
         ];
-        let res = get_columns_and_references(&fields).expect("Failed to parse fields");
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
 
         let expected_columns = vec![
             to_field("expect_string_null", "StringNull"),
@@ -174,16 +3387,207 @@ mod tests {
             to_field("expect_array", "array!:string"),
             to_field("expect_array_uniq", "array^:string"),
         ];
-        let res = get_columns_and_references(&fields).expect("Failed to parse fields");
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
 
         let expected_columns = vec![
             to_field("expect_array_null", "array_null(ArrayColType::String)"),
-            to_field("expect_array", "array(ArrayColType::String)"),
+            to_field(
+                "expect_array",
+                "array_with_empty_default(ArrayColType::String)",
+            ),
             to_field("expect_array_uniq", "array_uniq(ArrayColType::String)"),
         ];
         let expected_references: Vec<(String, String)> = vec![];
 
         assert_eq!(res, (expected_columns, expected_references));
+
+        // a missing array is told apart from an empty one only when nullable
+        let mappings = get_mappings();
+        let rust_type = |ftype: &str| {
+            mappings
+                .rust_field_with_params(ftype, &vec!["string".to_string()])
+                .unwrap()
+        };
+        assert_eq!(rust_type("array"), "Option<Vec<String>>");
+        assert_eq!(rust_type("array!"), "Vec<String>");
+        assert_eq!(rust_type("array^"), "Vec<String>");
+    }
+
+    #[test]
+    fn test_get_columns_with_nullable_array_elements() {
+        let fields = [
+            to_field("tags", "array:string?"),
+            to_field("scores", "array!:int?"),
+        ];
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
+        assert_eq!(
+            res.0,
+            vec![
+                to_field("tags", "array_null(ArrayColType::String)"),
+                to_field("scores", "array_with_empty_default(ArrayColType::Int)"),
+            ]
+        );
+
+        let mappings = get_mappings();
+        assert_eq!(
+            mappings
+                .rust_field_with_params("array", &vec!["string?".to_string()])
+                .unwrap(),
+            "Option<Vec<Option<String>>>"
+        );
+        assert_eq!(
+            mappings
+                .rust_field_with_params("array!", &vec!["int?".to_string()])
+                .unwrap(),
+            "Vec<Option<i32>>"
+        );
+    }
+
+    #[test]
+    fn test_unknown_array_element_kind() {
+        for kind in ["strng", "references"] {
+            let err = get_columns_and_references(
+                &[to_field("tags", &format!("array:{kind}"))],
+                &GenerateOptions::default(),
+            )
+            .expect_err("Expected Err");
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "type: `array` has no element kind `{kind}`. try any of: \
+                     `big_int,big_int?,bool,bool?,double,double?,float,float?,int,int?,string,\
+                     string?`"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_columns_with_enum_types() {
+        let fields = [
+            to_field("expect_enum_null", "enum:pending,shipped"),
+            to_field("expect_enum", "enum!:pending,shipped:default=shipped"),
+            to_field("expect_enum_short", "enum:pending,shipped=pending"),
+        ];
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
+
+        let expected_columns = vec![
+            to_field(
+                "expect_enum_null",
+                r#"EnumNull("expect_enum_null".to_string(), vec!["pending".to_string(), "shipped".to_string()])"#,
+            ),
+            to_field(
+                "expect_enum",
+                r#"Enum("expect_enum".to_string(), vec!["pending".to_string(), "shipped".to_string()]).default("shipped")"#,
+            ),
+            to_field(
+                "expect_enum_short",
+                r#"EnumNull("expect_enum_short".to_string(), vec!["pending".to_string(), "shipped".to_string()]).default("pending")"#,
+            ),
+        ];
+        let expected_references: Vec<(String, String)> = vec![];
+
+        assert_eq!(res, (expected_columns, expected_references));
+        assert_eq!(
+            enum_columns(&res.0)[1],
+            EnumColumn {
+                name: "expect_enum".to_string(),
+                variants: vec!["pending".to_string(), "shipped".to_string()],
+            }
+        );
+
+        let err = |ftype: &str| {
+            get_columns_and_references(&[to_field("status", ftype)], &GenerateOptions::default())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err("enum!:"),
+            "type: `enum!` requires a list of variants, e.g. `enum!:pending,shipped`"
+        );
+        assert_eq!(
+            err("enum"),
+            "type: `enum` requires a list of variants, e.g. `enum:pending,shipped`"
+        );
+        assert_eq!(
+            err("enum:pending,pending"),
+            "field `status`: enum variant \"pending\".to_string() is given more than once"
+        );
+        assert_eq!(
+            err("enum:pending,in transit"),
+            "field `status`: invalid enum variant `in transit`, expected an identifier such as \
+             `draft`"
+        );
+        assert_eq!(
+            err("enum:pending,shipped:default=lost"),
+            "field `status`: default `lost` is not one of the variants `pending,shipped`"
+        );
+        assert_eq!(
+            err("enum:order_status:pending,shipped=lost"),
+            "field `status`: default `lost` is not one of the variants `pending,shipped`"
+        );
+        assert_eq!(
+            err("enum!:pending,shipped=lost"),
+            "field `status`: default `lost` is not one of the variants `pending,shipped`"
+        );
+    }
+
+    #[test]
+    fn test_resolve_checks() {
+        assert_eq!(
+            expression_columns(r#"start_date <= end_date AND "end_date"::date < '2100-01-01'"#),
+            vec!["start_date", "end_date"]
+        );
+        assert_eq!(
+            expression_columns("price * 1.5 >= cost OR notes IS NULL AND length(notes) > 0"),
+            vec!["price", "cost", "notes"]
+        );
+
+        let columns = [to_field("start_date", "Date"), to_field("end_date", "Date")];
+        let checks = |exprs: &[&str]| {
+            resolve_checks(
+                "bookings",
+                &exprs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                true,
+                &columns,
+                &[to_field("room", "")],
+            )
+        };
+        let resolved = checks(&[
+            "start_date <= end_date",
+            "start_date < end_date + 30",
+            "room_id > 0",
+        ])
+        .unwrap();
+        assert_eq!(resolved[0].name, "chk-bookings-start_date-end_date");
+        assert_eq!(resolved[0].expr, r#""start_date <= end_date""#);
+        assert_eq!(resolved[1].name, "chk-bookings-start_date-end_date-2");
+        assert_eq!(resolved[2].name, "chk-bookings-room_id");
+
+        // references by field name
+        let resolved = checks(&["(start_date IS NULL) = (room IS NULL)", r#""room" > 0"#]).unwrap();
+        assert_eq!(resolved[0].name, "chk-bookings-start_date-room_id");
+        assert_eq!(
+            resolved[0].expr,
+            r#""(start_date IS NULL) = (room_id IS NULL)""#
+        );
+        assert_eq!(resolved[1].expr, r#""\"room_id\" > 0""#);
+
+        assert_eq!(
+            checks(&["start_date <= finish_date"])
+                .unwrap_err()
+                .to_string(),
+            "check `start_date <= finish_date` refers to `finish_date`, which is not a column of \
+             `bookings`"
+        );
+        assert_eq!(
+            checks(&["1 = 1"]).unwrap_err().to_string(),
+            "check `1 = 1` does not refer to any column of `bookings`"
+        );
+        assert!(checks(&["id > 0; DROP TABLE bookings"]).is_err());
     }
 
     #[test]
@@ -192,7 +3596,8 @@ mod tests {
             to_field("user", "references"),
             to_field("post", "references"),
         ];
-        let res = get_columns_and_references(&fields).expect("Failed to parse fields");
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
 
         let expected_columns: Vec<(String, String)> = vec![];
         let expected_references = vec![to_field("user", ""), to_field("post", "")];
@@ -208,7 +3613,8 @@ mod tests {
             fields.push(to_field(ignore_field, "string"));
         }
 
-        let res = get_columns_and_references(&fields).expect("Failed to parse fields");
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
 
         let expected_columns = vec![to_field("name", "StringNull")];
         let expected_references: Vec<(String, String)> = vec![];
@@ -216,15 +3622,810 @@ mod tests {
         assert_eq!(res, (expected_columns, expected_references));
     }
 
+    #[test]
+    fn test_decimal_precision_and_scale() {
+        let parse = |ftype: &str| {
+            get_columns_and_references(&[to_field("price", ftype)], &GenerateOptions::default())
+                .map(|(columns, _)| columns[0].1.clone())
+                .map_err(|err| err.to_string())
+        };
+        assert_eq!(
+            parse("decimal:10,2"),
+            Ok("DecimalLenNull(10,2)".to_string())
+        );
+        assert_eq!(parse("decimal!:10,2"), Ok("DecimalLen(10,2)".to_string()));
+        assert_eq!(
+            parse("decimal_len^:12,0"),
+            Ok("DecimalLenUniq(12,0)".to_string())
+        );
+        assert_eq!(parse("decimal!:10,10"), Ok("DecimalLen(10,10)".to_string()));
+
+        assert_eq!(
+            parse("decimal:10,two"),
+            Err(
+                "type: `decimal` takes a precision and a scale as non-negative integers, e.g. \
+                 `decimal:10,2`, not `10,two`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse("decimal_len!:10,-2"),
+            Err(
+                "type: `decimal_len!` takes a precision and a scale as non-negative integers, \
+                 e.g. `decimal_len!:10,2`, not `10,-2`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse("decimal:10"),
+            Err(
+                "type: `decimal` takes a precision and a scale as non-negative integers, e.g. \
+                 `decimal:10,2`, not `10`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse("decimal:2,10"),
+            Err("type: `decimal` has a scale of 10, which exceeds its precision of 2".to_string())
+        );
+        assert_eq!(
+            parse("decimal:0,0"),
+            Err("type: `decimal` needs a precision above 0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_many_to_many_references() {
+        assert_eq!(
+            many_to_many_references("articles", "tag"),
+            vec![
+                ("article".to_string(), String::new()),
+                ("tag".to_string(), String::new())
+            ]
+        );
+        assert_eq!(
+            many_to_many_references("user", "user"),
+            vec![
+                ("user".to_string(), String::new()),
+                ("user".to_string(), "related_user_id".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extended_ignore_fields() {
+        let options = GenerateOptions {
+            ignore_fields: Some(vec![
+                "created_at".to_string(),
+                "updated_at".to_string(),
+                "deleted_at".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let fields = vec![
+            to_field("name", "string"),
+            to_field("deleted_at", "tstz"),
+            to_field("updated_at", "tstz"),
+        ];
+
+        let res = get_columns_and_references(&fields, &options).expect("Failed to parse fields");
+        assert_eq!(res, (vec![to_field("name", "StringNull")], vec![]));
+        assert_eq!(
+            redundant_field_warnings(&fields, &options),
+            vec![
+                "field `deleted_at` is redundant, it is already generated automatically",
+                "field `updated_at` is redundant, it is already generated automatically",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emptied_ignore_fields() {
+        let options = GenerateOptions {
+            ignore_fields: Some(vec![]),
+            ..Default::default()
+        };
+        let fields = vec![to_field("name", "string"), to_field("updated_at", "tstz!")];
+
+        let res = get_columns_and_references(&fields, &options).expect("Failed to parse fields");
+        assert_eq!(
+            res,
+            (
+                vec![
+                    to_field("name", "StringNull"),
+                    to_field("updated_at", "TimestampWithTimeZone"),
+                ],
+                vec![]
+            )
+        );
+        assert!(redundant_field_warnings(&fields, &options).is_empty());
+    }
+
+    #[test]
+    fn test_get_columns_with_network_types() {
+        let fields = [
+            to_field("ip", "inet"),
+            to_field("network", "cidr!"),
+            to_field("mac", "macaddr^"),
+            to_field("eui64", "macaddr8"),
+            to_field("eui64_nonull", "macaddr8!"),
+            to_field("eui64_uniq", "macaddr8^"),
+        ];
+        let options = GenerateOptions {
+            backend: Some(DbBackend::Postgres),
+            ..Default::default()
+        };
+        let res = get_columns_and_references(&fields, &options).expect("Failed to parse fields");
+
+        let expected_columns = vec![
+            to_field("ip", "InetNull"),
+            to_field("network", "Cidr"),
+            to_field("mac", "MacAddrUniq"),
+            to_field("eui64", "MacAddr8Null"),
+            to_field("eui64_nonull", "MacAddr8"),
+            to_field("eui64_uniq", "MacAddr8Uniq"),
+        ];
+        let expected_references: Vec<(String, String)> = vec![];
+
+        assert_eq!(res, (expected_columns, expected_references));
+    }
+
+    #[test]
+    fn test_get_columns_with_default_string_len() {
+        let fields = [
+            to_field("title", "string"),
+            to_field("code", "string!"),
+            to_field("slug", "string^"),
+            to_field("sku", "string!:32"),
+            to_field("body", "text"),
+        ];
+        let options = GenerateOptions {
+            default_string_len: Some(255),
+            ..Default::default()
+        };
+        let (columns, _) =
+            get_columns_and_references(&fields, &options).expect("Failed to parse fields");
+
+        assert_eq!(
+            columns,
+            vec![
+                to_field("title", "StringLenNull(255)"),
+                to_field("code", "StringLen(255)"),
+                to_field("slug", "StringLenUniq(255)"),
+                to_field("sku", "StringLen(32)"),
+                to_field("body", "TextNull"),
+            ]
+        );
+
+        for (ftype, message) in [
+            (
+                "string:long",
+                "type: `string` takes a single length parameter, e.g. `string:255`",
+            ),
+            ("string:0", "type: `string` needs a length above 0"),
+        ] {
+            let err = get_columns_and_references(&[to_field("title", ftype)], &options)
+                .expect_err("Expected Err");
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_enum_col_type() {
+        let col_type = enum_col_type("EnumNull", "status", "order_status", "draft,paid").unwrap();
+        assert_eq!(
+            col_type,
+            "EnumNull(\"order_status\".to_string(), vec![\"draft\".to_string(), \"paid\".to_string()])"
+        );
+        assert_eq!(
+            parse_enum_col_type(&col_type),
+            Some((
+                "order_status".to_string(),
+                vec!["draft".to_string(), "paid".to_string()]
+            ))
+        );
+        assert!(enum_col_type("Enum", "status", "order status", "draft").is_err());
+        assert!(enum_col_type("Enum", "status", "order_status", "draft,").is_err());
+        assert!(enum_col_type("Enum", "status", "order_status", "draft,draft").is_err());
+    }
+
+    #[test]
+    fn test_validate_spec() {
+        let options = GenerateOptions::default();
+        let fields = [
+            to_field("title", "string"),
+            to_field("price", "strin"),
+            to_field("title", "text"),
+        ];
+        let diagnostics = validate_spec(&fields, &options);
+        assert_eq!(diagnostics.len(), 2);
+
+        assert_eq!(diagnostics[0].index, 1);
+        assert_eq!(diagnostics[0].field, "price:strin");
+        assert_eq!(diagnostics[0].token, "strin");
+        assert_eq!(diagnostics[0].span, 6..11);
+
+        assert_eq!(diagnostics[1].index, 2);
+        assert_eq!(diagnostics[1].token, "title");
+        assert_eq!(diagnostics[1].span, 0..5);
+        assert_eq!(
+            diagnostics[1].message,
+            "field `title` is given more than once"
+        );
+
+        assert!(validate_spec(&fields[..2], &options)[0]
+            .message
+            .contains("strin"));
+        assert!(validate_spec(&[to_field("title", "string")], &options).is_empty());
+    }
+
+    #[test]
+    fn test_validate_field_names() {
+        let err = |fname: &str| {
+            get_columns_and_references(&[to_field(fname, "string")], &GenerateOptions::default())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(err("match"), "field `match` is a Rust keyword");
+        assert_eq!(
+            err("r#type"),
+            "field `r#type` is a raw identifier, name it `type` without the `r#` if it is not a \
+             keyword"
+        );
+        assert_eq!(
+            err("1st_place"),
+            "field `1st_place` is not an identifier, it has to start with a letter or `_` \
+             followed by letters, digits and `_`"
+        );
+        assert_eq!(
+            err("firstName"),
+            "field `firstName` is not snake_case, e.g. `first_name`"
+        );
+        assert_eq!(
+            err("save"),
+            "field `save` is named like the `save` method SeaORM has on models"
+        );
+        // automatically generated fields are skipped with a warning
+        assert!(get_columns_and_references(
+            &[to_field("created_at", "date")],
+            &GenerateOptions::default()
+        )
+        .is_ok());
+
+        assert_eq!(
+            validate_model_name("type").unwrap_err().to_string(),
+            "model `type` is a Rust keyword"
+        );
+        assert_eq!(
+            validate_model_name("2fa_codes").unwrap_err().to_string(),
+            "model `2fa_codes` is not an identifier, it has to start with a letter or `_` \
+             followed by letters, digits and `_`"
+        );
+        assert!(validate_model_name("BlogPost").is_ok());
+    }
+
+    #[test]
+    fn test_check_identifiers() {
+        let columns = [
+            to_field("order", "int"),
+            to_field("firstName", "string"),
+            to_field("type", "string"),
+            to_field(&"x".repeat(64), "string"),
+            to_field("address2", "string"),
+        ];
+        let references = [to_field("user", "")];
+        let warnings = check_identifiers(
+            "users",
+            true,
+            &columns,
+            &references,
+            &GenerateOptions::default(),
+        )
+        .expect("Expected warnings");
+        assert_eq!(
+            warnings,
+            vec![
+                "column `order` of `users` is a reserved word in SQL".to_string(),
+                "column `firstName` of `users` is not snake_case".to_string(),
+                "column `type` of `users` is a Rust keyword".to_string(),
+                format!(
+                    "column `{}` of `users` is longer than 63 characters, postgres truncates it",
+                    "x".repeat(64)
+                ),
+            ]
+        );
+
+        assert_eq!(
+            check_identifiers("user", true, &[], &[], &GenerateOptions::default()).unwrap(),
+            vec!["table `user` is a reserved word in SQL".to_string()]
+        );
+        assert!(
+            check_identifiers("user", false, &[], &[], &GenerateOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_network_types_are_postgres_only() {
+        let fields = [to_field("eui64", "macaddr8")];
+        let options = GenerateOptions {
+            backend: Some(DbBackend::Sqlite),
+            ..Default::default()
+        };
+        let err = get_columns_and_references(&fields, &options).expect_err("Expected Err");
+
+        assert_eq!(
+            err.to_string(),
+            "type: `macaddr8` is only supported on postgres, but the target backend is sqlite"
+        );
+    }
+
+    #[test]
+    fn test_resolve_indexes_by_reference_name() {
+        let fields = [
+            to_field("user", "references"),
+            to_field("role", "references"),
+            to_field("owner", "references:admin_id"),
+            to_field("name", "string"),
+        ];
+        let (columns, references) =
+            get_columns_and_references(&fields, &GenerateOptions::default()).unwrap();
+
+        let indexes = resolve_indexes(
+            &[
+                IndexSpec {
+                    columns: vec!["user".to_string(), "role".to_string()],
+                    unique: true,
+                    not_null: false,
+                    tenant_scoped: false,
+                    when: None,
+                    when_null: None,
+                    comment: None,
+                    deferrable: false,
+                    gin: false,
+                    tablespace: None,
+                },
+                IndexSpec {
+                    columns: vec!["owner".to_string(), "name".to_string()],
+                    unique: false,
+                    not_null: false,
+                    tenant_scoped: false,
+                    when: None,
+                    when_null: None,
+                    comment: None,
+                    deferrable: false,
+                    gin: false,
+                    tablespace: None,
+                },
+            ],
+            &columns,
+            &references,
+            true,
+            None,
+        )
+        .expect("Failed to resolve indexes");
+
+        assert_eq!(
+            indexes,
+            vec![
+                IndexSpec {
+                    columns: vec!["user_id".to_string(), "role_id".to_string()],
+                    unique: true,
+                    not_null: false,
+                    tenant_scoped: false,
+                    when: None,
+                    when_null: None,
+                    comment: None,
+                    deferrable: false,
+                    gin: false,
+                    tablespace: None,
+                },
+                IndexSpec {
+                    columns: vec!["admin_id".to_string(), "name".to_string()],
+                    unique: false,
+                    not_null: false,
+                    tenant_scoped: false,
+                    when: None,
+                    when_null: None,
+                    comment: None,
+                    deferrable: false,
+                    gin: false,
+                    tablespace: None,
+                },
+            ]
+        );
+
+        let err = resolve_indexes(
+            &[IndexSpec {
+                columns: vec!["group".to_string()],
+                unique: true,
+                not_null: false,
+                tenant_scoped: false,
+                when: None,
+                when_null: None,
+                comment: None,
+                deferrable: false,
+                gin: false,
+                tablespace: None,
+            }],
+            &columns,
+            &references,
+            true,
+            None,
+        )
+        .expect_err("Expected Err");
+        assert_eq!(
+            err.to_string(),
+            "index column `group` is not a column or reference of the model"
+        );
+    }
+
+    #[test]
+    fn test_resolve_deferrable_index() {
+        let columns = [to_field("position", "Integer")];
+        let resolve = |unique: bool, when: Option<&str>| {
+            resolve_indexes(
+                &[IndexSpec {
+                    columns: vec!["position".to_string()],
+                    unique,
+                    when: when.map(ToString::to_string),
+                    deferrable: true,
+                    ..Default::default()
+                }],
+                &columns,
+                &[],
+                true,
+                None,
+            )
+        };
+        assert!(resolve(true, None).unwrap()[0].deferrable);
+        for err in [resolve(false, None), resolve(true, Some("active"))] {
+            assert_eq!(
+                err.unwrap_err().to_string(),
+                "index over `position` is deferrable, which only a unique constraint over plain \
+                 columns of all the rows can be"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_state_machine() {
+        assert_eq!(
+            parse_state_machine("status", "draft>review,review>draft,review>published")
+                .expect("Failed to parse the state machine"),
+            StateMachineColumn {
+                name: "status".to_string(),
+                states: vec![
+                    "draft".to_string(),
+                    "review".to_string(),
+                    "published".to_string()
+                ],
+                transitions: vec![
+                    ("draft".to_string(), "review".to_string()),
+                    ("review".to_string(), "draft".to_string()),
+                    ("review".to_string(), "published".to_string()),
+                ],
+            }
+        );
+        assert!(parse_state_machine("status", "draft").is_err());
+        assert!(parse_state_machine("status", "draft,In Review").is_err());
+        assert!(parse_state_machine("status", "draft>").is_err());
+    }
+
+    #[test]
+    fn test_get_columns_with_phone_check() {
+        let fields = [to_field("phone", "phone^")];
+        let check = |backend| {
+            let options = GenerateOptions {
+                backend: Some(backend),
+                ..Default::default()
+            };
+            let (columns, _) =
+                get_columns_and_references(&fields, &options).expect("Failed to parse fields");
+            columns[0].1.clone()
+        };
+
+        assert_eq!(
+            check(DbBackend::Postgres),
+            r##"PhoneUniq.check(r#""phone" ~ '^\+[1-9][0-9]{1,14}$'"#)"##
+        );
+        assert_eq!(
+            check(DbBackend::MySql),
+            r##"PhoneUniq.check(r#"`phone` REGEXP '^\\+[1-9][0-9]{1,14}$'"#)"##
+        );
+        assert_eq!(check(DbBackend::Sqlite), "PhoneUniq");
+    }
+
+    #[test]
+    fn test_get_columns_with_color_check() {
+        let fields = [to_field("color", "color^"), to_field("tint", "color:alpha")];
+        let columns = |backend| {
+            let options = GenerateOptions {
+                backend,
+                ..Default::default()
+            };
+            get_columns_and_references(&fields, &options)
+                .expect("Failed to parse fields")
+                .0
+                .into_iter()
+                .map(|(_, col_type)| col_type)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            columns(Some(DbBackend::MySql)),
+            [
+                r##"CharLenUniq(7).check(r#"`color` REGEXP '^#[0-9A-Fa-f]{6}$'"#)"##,
+                r##"CharLenNull(9).check(r#"`tint` REGEXP '^#[0-9A-Fa-f]{8}$'"#)"##,
+            ]
+        );
+        assert_eq!(
+            columns(Some(DbBackend::Sqlite))[0],
+            r##"CharLenUniq(7).check(r#""color" GLOB '#[0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f][0-9A-Fa-f]'"#)"##
+        );
+        assert_eq!(columns(None), ["CharLenUniq(7)", "CharLenNull(9)"]);
+
+        let fields = [to_field("tint", "color:beta")];
+        let err = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect_err("Expected Err");
+        assert_eq!(
+            err.to_string(),
+            "type: `color` only takes the `alpha` parameter, e.g. `color:alpha`, not `beta`"
+        );
+    }
+
+    #[test]
+    fn test_upsert_columns() {
+        let fields = [
+            to_field("email", "string^"),
+            to_field("name", "string"),
+            to_field("team", "references"),
+        ];
+        let (columns, references) =
+            get_columns_and_references(&fields, &GenerateOptions::default())
+                .expect("Failed to parse fields");
+        let upsert = |options: &GenerateOptions| {
+            upsert_columns(&fields, &columns, &references, true, options)
+        };
+
+        assert_eq!(
+            upsert(&GenerateOptions::default()).expect("Failed to derive the upsert"),
+            Upsert {
+                conflict: vec!["email".to_string()],
+                update: vec![
+                    "name".to_string(),
+                    "team_id".to_string(),
+                    "updated_at".to_string()
+                ],
+            }
+        );
+        let explicit = GenerateOptions {
+            upsert_conflict: vec!["name".to_string(), "team_id".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            upsert(&explicit)
+                .expect("Failed to derive the upsert")
+                .conflict,
+            vec!["name".to_string(), "team_id".to_string()]
+        );
+        let unknown = GenerateOptions {
+            upsert_conflict: vec!["slug".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            upsert(&unknown).expect_err("Expected an error").to_string(),
+            "upsert conflict column `slug` is not a column of the model"
+        );
+    }
+
+    #[test]
+    fn test_get_columns_with_reference_default() {
+        let fields = [
+            to_field("user", "references?@default=1"),
+            to_field("team", "references@default=2"),
+        ];
+        let (columns, references) =
+            get_columns_and_references(&fields, &GenerateOptions::default())
+                .expect("Failed to parse fields");
+        assert_eq!(
+            columns,
+            vec![
+                to_field("user_id", "IntegerNullWithDefault(1)"),
+                to_field("team_id", "IntegerWithDefault(2)"),
+            ]
+        );
+        assert_eq!(
+            references,
+            vec![to_field("user?", ""), to_field("team", "")]
+        );
+
+        for (ftype, expected) in [
+            (
+                "references@default=admin",
+                "default of reference `user` is not an id: `admin`",
+            ),
+            (
+                "int@default=1",
+                "field `user` is not a reference, `@default` only applies to references",
+            ),
+        ] {
+            let err =
+                get_columns_and_references(&[to_field("user", ftype)], &GenerateOptions::default())
+                    .expect_err("Expected an error");
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_get_columns_with_sorted_references() {
+        let fields = [
+            to_field("user", "references"),
+            to_field("title", "string"),
+            to_field("category", "references"),
+            to_field("author", "references:written_by"),
+        ];
+
+        let (_, references) = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
+        assert_eq!(
+            references,
+            vec![
+                to_field("user", ""),
+                to_field("category", ""),
+                to_field("author", "written_by"),
+            ]
+        );
+
+        let options = GenerateOptions {
+            sort_references: true,
+            ..Default::default()
+        };
+        let (_, references) =
+            get_columns_and_references(&fields, &options).expect("Failed to parse fields");
+        assert_eq!(
+            references,
+            vec![
+                to_field("author", "written_by"),
+                to_field("category", ""),
+                to_field("user", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_columns_with_function_default() {
+        let fields = [
+            to_field("external_id", "uuid^=gen_random_uuid()"),
+            to_field("token", "uuid!=gen_random_uuid()"),
+        ];
+        let (columns, _) = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
+
+        assert_eq!(
+            columns,
+            vec![
+                to_field(
+                    "external_id",
+                    r#"UuidUniqWithDefault("gen_random_uuid()".to_string())"#
+                ),
+                to_field(
+                    "token",
+                    r#"UuidWithDefault("gen_random_uuid()".to_string())"#
+                ),
+            ]
+        );
+
+        let fields = [to_field("name", "string=gen_random_uuid()")];
+        assert_eq!(
+            get_columns_and_references(&fields, &GenerateOptions::default())
+                .expect_err("string takes no default")
+                .to_string(),
+            "type: `string` does not take a default value"
+        );
+    }
+
+    #[test]
+    fn test_get_columns_with_table_collation() {
+        let fields = [
+            to_field("name", "string!"),
+            to_field("bio", "text"),
+            to_field("code", "string^@collate=C"),
+            to_field("age", "int"),
+        ];
+        let options = GenerateOptions {
+            collation: Some("und-x-icu".to_string()),
+            ..Default::default()
+        };
+        let (columns, _) =
+            get_columns_and_references(&fields, &options).expect("Failed to parse fields");
+
+        assert_eq!(
+            columns,
+            vec![
+                to_field("name", r#"String.collate("und-x-icu")"#),
+                to_field("bio", r#"TextNull.collate("und-x-icu")"#),
+                to_field("code", r#"StringUniq.collate("C")"#),
+                to_field("age", "IntegerNull"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_columns_with_invalid_collation() {
+        let fields = [to_field("name", "string@collate=C\" OR")];
+        assert!(get_columns_and_references(&fields, &GenerateOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_get_columns_with_qualified_reference() {
+        let fields = [
+            to_field("org", "references:tenant.organizations"),
+            to_field("parent", "references?:tenant.organizations"),
+        ];
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
+
+        let expected_columns: Vec<(String, String)> = vec![];
+        let expected_references = vec![
+            to_field("tenant.organizations", "org_id"),
+            to_field("tenant.organizations?", "parent_id"),
+        ];
+        assert_eq!(res, (expected_columns, expected_references));
+    }
+
+    #[test]
+    fn test_get_columns_with_money_minor() {
+        let fields = [
+            to_field("price", "money_minor"),
+            to_field("total", "money_minor!"),
+            to_field("fee", "money_minor^"),
+            to_field("money", "money"),
+        ];
+        let res = get_columns_and_references(&fields, &GenerateOptions::default())
+            .expect("Failed to parse fields");
+
+        let expected_columns = vec![
+            to_field("price", "BigIntegerNull"),
+            to_field("total", "BigInteger"),
+            to_field("fee", "BigIntegerUniq"),
+            to_field("money", "MoneyNull"),
+        ];
+        let expected_references: Vec<(String, String)> = vec![];
+        assert_eq!(res, (expected_columns, expected_references));
+
+        assert_eq!(
+            typed_columns(&fields, "money_minor", &GenerateOptions::default()),
+            vec![
+                TypedColumn {
+                    name: "price".to_string(),
+                    nullable: true,
+                    unique: false,
+                },
+                TypedColumn {
+                    name: "total".to_string(),
+                    nullable: false,
+                    unique: false,
+                },
+                TypedColumn {
+                    name: "fee".to_string(),
+                    nullable: false,
+                    unique: true,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn validate_arity() {
         // field not expected arity, but given 2
-        let fields = vec![to_field("name", "string:2")];
-        let res = get_columns_and_references(&fields);
+        let fields = vec![to_field("name", "text:2")];
+        let res = get_columns_and_references(&fields, &GenerateOptions::default());
         if let Err(err) = res {
             assert_eq!(
                 err.to_string(),
-                "type: `string` requires specifying 0 parameters, but only 1 were given (`2`)."
+                "type: `text` requires specifying 0 parameters, but only 1 were given (`2`)."
             );
         } else {
             panic!("Expected Err, but got Ok: {res:?}");
@@ -232,7 +4433,7 @@ mod tests {
 
         // references not expected arity, but given 2
         let references = vec![to_field("post:2", "")];
-        let res = get_columns_and_references(&references);
+        let res = get_columns_and_references(&references, &GenerateOptions::default());
         if let Err(err) = res {
             let mappings = get_mappings();
             assert_eq!(
@@ -243,4 +4444,44 @@ mod tests {
             panic!("Expected Err, but got Ok: {res:?}");
         }
     }
+
+    #[test]
+    fn can_plan_batch_order() {
+        let models = vec![
+            (
+                "comment".to_string(),
+                vec![
+                    to_field("post", "references"),
+                    to_field("author", "references:user_id"),
+                    to_field("parent", "references?"),
+                ],
+            ),
+            (
+                "post".to_string(),
+                vec![to_field("title", "string"), to_field("user", "references")],
+            ),
+            ("user".to_string(), vec![to_field("name", "string")]),
+            ("tag".to_string(), vec![to_field("name", "string")]),
+        ];
+
+        assert_eq!(
+            plan(&models).expect("Failed to plan"),
+            vec!["user", "post", "comment", "tag"]
+        );
+
+        let cycle = vec![
+            ("user".to_string(), vec![to_field("team", "references?")]),
+            (
+                "team".to_string(),
+                vec![to_field("owner", "references:user_id")],
+            ),
+            ("owner".to_string(), vec![to_field("user", "references")]),
+            ("tag".to_string(), vec![to_field("name", "string")]),
+        ];
+        assert_eq!(
+            plan(&cycle).expect_err("Expected Err").to_string(),
+            "models `user`, `team`, `owner` reference each other in a cycle, there is no order \
+             to migrate them in"
+        );
+    }
 }
@@ -0,0 +1,100 @@
+//! Rendering the generator templates.
+//!
+//! The generators parse the component into template vars and pick the
+//! templates, a [`TemplateRenderer`] renders them. [`RRgen`] is the default,
+//! see [`crate::generate_with_renderer`] for plugging in another engine.
+use std::path::Path;
+
+use rrgen::{GenResult, RRgen};
+use serde_json::Value;
+
+use crate::Result;
+
+/// Renders a generator template and writes out what it describes.
+pub trait TemplateRenderer {
+    /// Renders `template`, the content of the embedded (or app local)
+    /// template at `path`, e.g. `model/model.t`, with the generator's `vars`.
+    ///
+    /// # Errors
+    ///
+    /// When the template cannot be rendered or its files written
+    fn render(&self, path: &Path, template: &str, vars: &Value) -> Result<GenResult>;
+}
+
+impl TemplateRenderer for RRgen {
+    fn render(&self, _path: &Path, template: &str, vars: &Value) -> Result<GenResult> {
+        Ok(self.generate(template, vars)?)
+    }
+}
+
+/// Renders with another [`TemplateRenderer`], with a header commented out
+/// atop each generated file, see [`crate::GenerateOptions::file_header`].
+pub struct WithHeader<'a> {
+    pub inner: &'a dyn TemplateRenderer,
+    pub header: &'a str,
+}
+
+impl TemplateRenderer for WithHeader<'_> {
+    fn render(&self, path: &Path, template: &str, vars: &Value) -> Result<GenResult> {
+        self.inner
+            .render(path, &with_header(template, self.header), vars)
+    }
+}
+
+/// Inserts `header` at the top of the body of `template`, commented out in
+/// the syntax of the file it generates. Files without a line comment syntax,
+/// e.g. html views or json, are left as they are.
+fn with_header(template: &str, header: &str) -> String {
+    let Some((frontmatter, body)) = template.split_once("\n---\n") else {
+        return template.to_string();
+    };
+    let to = frontmatter
+        .lines()
+        .find_map(|line| line.strip_prefix("to:"))
+        .map(|to| Path::new(to.trim().trim_matches('"')));
+    let extension = to.and_then(Path::extension).and_then(|ext| ext.to_str());
+    let file_name = to.and_then(Path::file_name).and_then(|name| name.to_str());
+    let prefix = match (extension, file_name) {
+        (Some("rs"), _) => "//",
+        (Some("sql"), _) => "--",
+        (Some("yaml" | "yml" | "toml" | "conf"), _) | (_, Some("Dockerfile" | ".dockerignore")) => {
+            "#"
+        }
+        _ => return template.to_string(),
+    };
+    let comment = header
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                prefix.to_string()
+            } else {
+                format!("{prefix} {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("{frontmatter}\n---\n{comment}\n\n{body}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_header() {
+        assert_eq!(
+            with_header(
+                "to: \"db/{{name}}.sql\"\n---\nSELECT 1;\n",
+                "License\n\nMIT"
+            ),
+            "to: \"db/{{name}}.sql\"\n---\n-- License\n--\n-- MIT\n\nSELECT 1;\n"
+        );
+        assert_eq!(
+            with_header("to: config/scheduler.yaml\n---\njobs:\n", "MIT"),
+            "to: config/scheduler.yaml\n---\n# MIT\n\njobs:\n"
+        );
+        // no line comments in html
+        let view = "to: assets/views/{{name}}/show.html\n---\n{% extends \"base.html\" %}\n";
+        assert_eq!(with_header(view, "MIT"), view);
+    }
+}
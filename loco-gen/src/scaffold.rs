@@ -1,29 +1,88 @@
 use std::path::Path;
 
-use rrgen::RRgen;
 use serde_json::json;
 
 use crate::{
-    get_mappings, infer::parse_field_type, model, render_template, AppInfo, Error, GenerateResults,
-    Result, ScaffoldKind,
+    controller, get_mappings,
+    infer::{parse_field_type, split_modifiers},
+    model, render_template, AppInfo, Error, GenerateOptions, GenerateResults, RenameAll, Result,
+    ScaffoldKind, TemplateRenderer,
 };
 
+/// Whether a field is left out of the request `Params` unless included
+/// explicitly: computed (`tsvector`), managed (`state_machine`, moved by its
+/// transitions) or hidden (`@encrypted`) columns.
+fn is_param_by_default(ftype: &str) -> Result<bool> {
+    let (ftype, modifiers) = split_modifiers(ftype)?;
+    let base = ftype
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(['!', '^']);
+    Ok(!matches!(base, "tsvector" | "state_machine")
+        && !modifiers.iter().any(|(name, _)| *name == "encrypted"))
+}
+
+/// The Rust types of the columns a scaffolded `list` can filter on, which
+/// parse from a query string and compare by equality.
+const FILTER_TYPES: &[&str] = &["String", "i16", "i32", "i64", "bool", "Uuid"];
+
 pub fn generate(
-    rrgen: &RRgen,
+    renderer: &dyn TemplateRenderer,
     name: &str,
     with_tz: bool,
     fields: &[(String, String)],
     kind: &ScaffoldKind,
+    options: &GenerateOptions,
     appinfo: &AppInfo,
 ) -> Result<GenerateResults> {
+    if options.serde_rename_all.is_some() && !matches!(kind, ScaffoldKind::Api) {
+        return Err(Error::Message(
+            "serde rename_all is only supported by the api scaffold, html forms post the field \
+             names as they are"
+                .to_string(),
+        ));
+    }
+
+    if options.tenant.is_some() && !matches!(kind, ScaffoldKind::Api) {
+        return Err(Error::Message(
+            "tenant scoped scaffolds are only supported by the api scaffold".to_string(),
+        ));
+    }
+
+    if options.api_docs && !matches!(kind, ScaffoldKind::Api) {
+        return Err(Error::Message(
+            "api docs are only supported by api controllers and scaffolds".to_string(),
+        ));
+    }
+
     // - scaffold is never a link table
     // - never run with migration_only, because the controllers will refer to the
     //   models. the models only arrive after migration and entities sync.
-    let mut gen_result = model::generate(rrgen, name, with_tz, fields, appinfo)?;
+    let mut gen_result = model::generate(renderer, name, with_tz, fields, options, appinfo)?;
+    // the column stamping rows with their tenant, which the app sets from the
+    // request's tenant instead of taking it in the request
+    let tenant = options
+        .tenant
+        .as_deref()
+        .filter(|tenant| {
+            model::with_tenant(name, fields, options)
+                .iter()
+                .any(|(fname, _)| fname == tenant)
+        })
+        .map(|tenant| format!("{tenant}_id"));
+    // password hashes are neither taken in the request nor shown
+    let hidden = model::password_columns(fields, options)?
+        .into_iter()
+        .map(|password| password.name)
+        .collect::<Vec<_>>();
+    let fields = &model::with_password_hashes(fields, options);
 
     let mut columns = Vec::new();
+    // whether each column is in the request `Params` by default
+    let mut defaults = Vec::new();
     for (fname, ftype) in fields {
-        if model::IGNORE_FIELDS.contains(&fname.as_str()) {
+        if options.ignores_field(fname) || (options.soft_delete && fname == "deleted_at") {
             tracing::warn!(
                 field = fname,
                 "note that a redundant field was specified, it is already generated automatically"
@@ -33,13 +92,22 @@ pub fn generate(
 
         let field_type = parse_field_type(ftype)?;
         match field_type {
-            crate::infer::FieldType::Reference => {
+            crate::infer::FieldType::Reference
+            | crate::infer::FieldType::SharedPrimaryKeyReference => {
                 let col_name = format!("{fname}_id");
                 columns.push((col_name, "i32".to_string(), "Integer".to_string()));
             }
             crate::infer::FieldType::ReferenceWithCustomField(refname) => {
                 columns.push((refname.clone(), "i32".to_string(), "Integer".to_string()));
             }
+            crate::infer::FieldType::ReferenceToTable(_) => {
+                let col_name = format!("{fname}_id");
+                columns.push((col_name, "i32".to_string(), "Integer".to_string()));
+            }
+            crate::infer::FieldType::NullableReferenceToTable(_) => {
+                let col_name = format!("{fname}_id");
+                columns.push((col_name, "i32".to_string(), "IntegerNull".to_string()));
+            }
             crate::infer::FieldType::NullableReference => {
                 let col_name = format!("{fname}_id");
                 columns.push((col_name, "i32".to_string(), "IntegerNull".to_string()));
@@ -51,7 +119,19 @@ pub fn generate(
                     "IntegerNull".to_string(),
                 ));
             }
-            crate::infer::FieldType::Type(ftype) => {
+            // not a column of the scaffolded model, its join table's
+            crate::infer::FieldType::ManyToMany(_) => continue,
+            crate::infer::FieldType::Enum(_) | crate::infer::FieldType::NullableEnum(_) => {
+                let ftype = if matches!(field_type, crate::infer::FieldType::Enum(_)) {
+                    "enum!"
+                } else {
+                    "enum"
+                };
+                let rust_type = get_mappings().rust_field(ftype)?;
+                columns.push((fname.clone(), rust_type.to_string(), ftype.to_string()));
+            }
+            crate::infer::FieldType::Type(ftype)
+            | crate::infer::FieldType::TypeWithDefault(ftype, _) => {
                 let mappings = get_mappings();
                 let rust_type = mappings.rust_field(ftype.as_str())?;
                 columns.push((fname.clone(), rust_type.to_string(), ftype));
@@ -72,24 +152,61 @@ pub fn generate(
                 columns.push((fname.clone(), rust_type.to_string(), ftype));
             }
         }
+        defaults.push(is_param_by_default(ftype)? && !hidden.contains(fname));
     }
 
-    let vars = json!({"name": name, "columns": columns, "pkg_name": appinfo.app_name});
+    for column in options.params_include.iter().chain(&options.params_exclude) {
+        if !columns.iter().any(|(name, _, _)| name == column) {
+            return Err(Error::Message(format!(
+                "request field `{column}` is not a column of `{name}`"
+            )));
+        }
+    }
+    let params = columns
+        .iter()
+        .zip(&defaults)
+        .filter(|((name, _, _), by_default)| {
+            tenant.as_ref() != Some(name)
+                && !options.params_exclude.contains(name)
+                && (**by_default || options.params_include.contains(name))
+        })
+        .map(|(column, _)| column)
+        .collect::<Vec<_>>();
+
+    let shown = columns
+        .iter()
+        .filter(|(name, _, _)| !hidden.contains(name))
+        .collect::<Vec<_>>();
+    // the shown columns `list` filters on by equality, with the type of a filter
+    let filters = shown
+        .iter()
+        .filter(|(name, _, _)| tenant.as_ref() != Some(name))
+        .filter_map(|(name, rust_type, _)| {
+            let rust_type = rust_type
+                .strip_prefix("Option<")
+                .and_then(|inner| inner.strip_suffix('>'))
+                .unwrap_or(rust_type);
+            FILTER_TYPES
+                .contains(&rust_type)
+                .then(|| (name.clone(), rust_type.to_string()))
+        })
+        .collect::<Vec<_>>();
+    let vars = json!({"name": name, "columns": shown, "hidden": hidden, "params": params, "filters": filters, "pkg_name": appinfo.app_name, "rename_all": options.serde_rename_all.map(RenameAll::as_str), "api_docs": options.api_docs, "tenant": tenant, "soft_delete": options.soft_delete});
     match kind {
         ScaffoldKind::Api => {
-            let res = render_template(rrgen, Path::new("scaffold/api"), &vars)?;
-            gen_result.rrgen.extend(res.rrgen);
-            gen_result.local_templates.extend(res.local_templates);
+            if options.api_docs {
+                gen_result.merge(controller::generate_api_docs(renderer, appinfo)?);
+            }
+            let res = render_template(renderer, Path::new("scaffold/api"), &vars)?;
+            gen_result.merge(res);
         }
         ScaffoldKind::Html => {
-            let res = render_template(rrgen, Path::new("scaffold/html"), &vars)?;
-            gen_result.rrgen.extend(res.rrgen);
-            gen_result.local_templates.extend(res.local_templates);
+            let res = render_template(renderer, Path::new("scaffold/html"), &vars)?;
+            gen_result.merge(res);
         }
         ScaffoldKind::Htmx => {
-            let res = render_template(rrgen, Path::new("scaffold/htmx"), &vars)?;
-            gen_result.rrgen.extend(res.rrgen);
-            gen_result.local_templates.extend(res.local_templates);
+            let res = render_template(renderer, Path::new("scaffold/htmx"), &vars)?;
+            gen_result.merge(res);
         }
     }
     Ok(gen_result)
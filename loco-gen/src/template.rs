@@ -17,6 +17,10 @@ pub fn get_ignored_paths() -> Vec<&'static Path> {
         Path::new("migration"),
         #[cfg(not(feature = "with-db"))]
         Path::new("model"),
+        #[cfg(not(feature = "with-db"))]
+        Path::new("model_ext"),
+        #[cfg(not(feature = "with-db"))]
+        Path::new("materialized_view"),
     ]
 }
 
@@ -158,6 +162,8 @@ pub mod tests {
             assert!(ignored_paths.contains(&Path::new("scaffold")));
             assert!(ignored_paths.contains(&Path::new("migration")));
             assert!(ignored_paths.contains(&Path::new("model")));
+            assert!(ignored_paths.contains(&Path::new("model_ext")));
+            assert!(ignored_paths.contains(&Path::new("materialized_view")));
         }
         #[cfg(feature = "with-db")]
         {
@@ -0,0 +1,66 @@
+to: src/controllers/api_docs.rs
+skip_exists: true
+message: "The OpenAPI spec is served at `/api-docs/openapi.json`, and browsed at `/api-docs`."
+injections:
+- into: src/controllers/mod.rs
+  append: true
+  content: "pub mod api_docs;"
+- into: src/app.rs
+  after: "AppRoutes::"
+  content: "            .add_route(controllers::api_docs::routes())"
+- into: Cargo.toml
+  after: "^\\[dependencies\\]"
+  skip_if: "(?m)^utoipa ="
+  content: "utoipa = { version = \"5\", features = [\"chrono\", \"uuid\", \"decimal\"] }"
+---
+#![allow(clippy::unused_async)]
+use loco_rs::prelude::*;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(info(title = "{{pkg_name}}"))]
+struct ApiDoc;
+
+/// The OpenAPI spec of the app, merging the spec of each documented
+/// controller
+#[must_use]
+pub fn spec() -> utoipa::openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut spec = ApiDoc::openapi();
+    // api-docs-inject-above (do not remove)
+    spec
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <title>{{pkg_name}} API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+    <script>
+      SwaggerUIBundle({ url: "/api-docs/openapi.json", dom_id: "#swagger-ui" });
+    </script>
+  </body>
+</html>
+"##;
+
+#[debug_handler]
+pub async fn openapi_json() -> Result<Response> {
+    format::json(spec())
+}
+
+#[debug_handler]
+pub async fn swagger_ui() -> Result<Response> {
+    format::html(SWAGGER_UI)
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("api-docs/")
+        .add("/", get(swagger_ui))
+        .add("openapi.json", get(openapi_json))
+}
@@ -0,0 +1,78 @@
+{% set file_name = name | snake_case -%}
+to: "src/channels/{{file_name}}.rs"
+skip_exists: true
+message: "Channel `{{file_name}}` was added successfully. Subscribe with a WebSocket at `/channels/{{file_name}}`."
+injections:
+- into: src/channels/mod.rs
+  append: true
+  content: "pub mod {{file_name}};"
+- into: src/app.rs
+  before: "// channels-inject"
+  content: "        channels.register(crate::channels::{{file_name}}::NAME);"
+- into: src/app.rs
+  after: "AppRoutes::"
+  content: "            .add_route(crate::channels::{{file_name}}::routes())"
+- into: Cargo.toml
+  before: '^axum = \{ version = "0\.8[.0-9]*" \}$'
+  skip_if: '(?m)^axum = .*"ws"'
+  content: 'axum = { version = "0.8", features = ["ws"] }'
+- into: Cargo.toml
+  remove_lines: '^axum = \{ version = "0\.8[.0-9]*" \}$'
+  content: ""
+---
+use axum::extract::ws::{Message as Frame, WebSocket, WebSocketUpgrade};
+use loco_rs::{channels::Message, prelude::*};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// The channel's name
+pub const NAME: &str = "{{file_name}}";
+
+/// Broadcasts `event` with its `data` to the channel's subscribers, e.g. from
+/// a worker, returning how many there are.
+///
+/// # Errors
+///
+/// When the channel is not registered, or `data` fails to serialize
+pub fn broadcast<T: Serialize>(ctx: &AppContext, event: &str, data: &T) -> Result<usize> {
+    ctx.channels.broadcast(NAME, event, data)
+}
+
+/// Subscribes a WebSocket client to the channel, sending it each message as a
+/// JSON text frame: `{"event": ..., "data": ...}`. Authorize the subscriber
+/// here, e.g. by taking `auth::JWT`.
+pub async fn subscribe(State(ctx): State<AppContext>, ws: WebSocketUpgrade) -> Result<Response> {
+    let receiver = ctx.channels.subscribe(NAME)?;
+    Ok(ws.on_upgrade(move |socket| forward(socket, receiver)))
+}
+
+/// Forwards the channel's messages to the client until either side closes,
+/// skipping the ones it lags behind on.
+async fn forward(mut socket: WebSocket, mut receiver: broadcast::Receiver<Message>) {
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Ok(message) => {
+                    let Ok(text) = serde_json::to_string(&message) else {
+                        continue;
+                    };
+                    if socket.send(Frame::Text(text.into())).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            frame = socket.recv() => match frame {
+                Some(Ok(Frame::Close(_)) | Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("channels")
+        .add("/{{file_name}}", get(subscribe))
+}
@@ -0,0 +1,11 @@
+to: src/channels/mod.rs
+skip_exists: true
+message: "Channels are served to their subscribers by the routes mounted in `src/app.rs`."
+injections:
+- into: src/lib.rs
+  append: true
+  content: "pub mod channels;"
+- into: src/app.rs
+  after: "impl Hooks for App"
+  content: "    fn register_channels(channels: &mut loco_rs::channels::Channels) {\n        // channels-inject (do not remove)\n    }\n"
+---
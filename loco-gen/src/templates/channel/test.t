@@ -0,0 +1,66 @@
+{% set file_name = name | snake_case -%}
+to: "tests/channels/{{file_name}}.rs"
+skip_exists: true
+message: "Tests for channel `{{file_name}}` were added successfully. Run `cargo test`."
+injections:
+- into: tests/channels/mod.rs
+  append: true
+  content: "pub mod {{file_name}};"
+- into: Cargo.toml
+  after: '^\[dev-dependencies\]'
+  skip_if: '(?m)^tokio-tungstenite ='
+  content: 'tokio-tungstenite = { version = "0.26" }'
+- into: Cargo.toml
+  after: '^\[dev-dependencies\]'
+  skip_if: '(?m)^futures-util ='
+  content: 'futures-util = { version = "0.3" }'
+---
+use {{pkg_name}}::{app::App, channels::{{file_name}}};
+use futures_util::StreamExt;
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn can_broadcast_on_{{file_name}}() {
+    let boot = boot_test::<App>().await.unwrap();
+    let mut subscriber = boot
+        .app_context
+        .channels
+        .subscribe({{file_name}}::NAME)
+        .unwrap();
+
+    let sent = {{file_name}}::broadcast(&boot.app_context, "ping", &"hello").unwrap();
+    assert_eq!(sent, 1);
+
+    let message = subscriber.recv().await.unwrap();
+    assert_eq!(message.event, "ping");
+    assert_eq!(message.data, "hello");
+}
+
+#[tokio::test]
+#[serial]
+async fn can_subscribe_to_{{file_name}}_with_websocket() {
+    let boot = boot_test::<App>().await.unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = boot.router.unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+    });
+
+    let (mut socket, _) =
+        tokio_tungstenite::connect_async(format!("ws://{addr}/channels/{{file_name}}"))
+            .await
+            .unwrap();
+    let sent = {{file_name}}::broadcast(&boot.app_context, "ping", &"hello").unwrap();
+    assert_eq!(sent, 1);
+
+    let frame = socket.next().await.unwrap().unwrap();
+    let message: serde_json::Value = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+    assert_eq!(message, serde_json::json!({"event": "ping", "data": "hello"}));
+}
@@ -0,0 +1,8 @@
+to: tests/channels/mod.rs
+skip_exists: true
+message: "Channel tests are in `tests/channels`."
+injections:
+- into: tests/mod.rs
+  append: true
+  content: "mod channels;"
+---
@@ -10,18 +10,36 @@ injections:
 - into: src/app.rs
   after: "AppRoutes::"
   content: "            .add_route(controllers::{{ file_name }}::routes())"
+{% if api_docs -%}
+- into: src/controllers/api_docs.rs
+  before: "api-docs-inject-above"
+  content: "    spec.merge(super::{{ file_name }}::ApiDoc::openapi());"
+{% endif -%}
 ---
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
 use loco_rs::prelude::*;
+{%- if api_docs %}
+use utoipa::OpenApi;
 
+#[derive(OpenApi)]
+#[openapi(paths(index{% for action in actions %}, {{action}}{% endfor %}))]
+pub struct ApiDoc;
+{%- endif %}
+
+{% if api_docs -%}
+#[utoipa::path(get, path = "/api/{{file_name | plural}}/", responses((status = 200)))]
+{% endif -%}
 #[debug_handler]
 pub async fn index(State(_ctx): State<AppContext>) -> Result<Response> {
     format::empty()
 }
 
 {% for action in actions -%}
+{% if api_docs -%}
+#[utoipa::path(get, path = "/api/{{file_name | plural}}/{{action}}", responses((status = 200)))]
+{% endif -%}
 #[debug_handler]
 pub async fn {{action}}(State(_ctx): State<AppContext>) -> Result<Response> {
     format::empty()
@@ -0,0 +1,9 @@
+{% set file_name = name | plural | snake_case -%}
+to: "src/fixtures/{{file_name}}.yaml"
+skip_exists: true
+message: "Fixtures `src/fixtures/{{file_name}}.yaml` added. Load them with `cargo loco db seed`."
+---
+one:
+{%- for field in fields %}
+  {{field.name}}: {{field.value}}
+{%- endfor %}
@@ -0,0 +1,94 @@
+{% set migration_dir = migration_dir | default(value="migration") -%}
+{% set mig_ts = ts | date(format="%Y%m%d_%H%M%S") -%}
+{% set plural_snake = name | plural | snake_case -%}
+{% set module_name = "m" ~  mig_ts ~ "_" ~ plural_snake -%}
+{% if if_exists | default(value=false) %}{% set drop_suffix = "_if_exists" %}{% else %}{% set drop_suffix = "" %}{% endif -%}
+to: "{{migration_dir}}/src/{{module_name}}.rs"
+skip_glob: "{{migration_dir}}/src/m????????_??????_{{plural_snake}}.rs"
+message: "Migration for the materialized view `{{plural_snake}}` added! You can now apply it with `$ cargo loco db migrate`."
+injections:
+- into: "{{migration_dir}}/src/lib.rs"
+  before: "inject-above"
+  content: "            Box::new({{module_name}}::Migration),"
+- into: "{{migration_dir}}/src/lib.rs"
+  before: "pub struct Migrator"
+  content: "mod {{module_name}};"
+---
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+{% if metadata.author -%}
+/// Author: {{metadata.author}}
+{% endif -%}
+{% if metadata.ticket -%}
+/// Ticket: {{metadata.ticket}}
+{% endif -%}
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_materialized_view(
+            m,
+            "{{plural_snake}}",
+            r#"{{query}}"#,
+        )
+        .await?;
+        {%- for index in indexes %}
+        {%- set_global index_name = "idx-" ~ plural_snake -%}
+        {%- for col in index.columns -%}
+        {%- if "(" in col %}{% set part = col | snake_case %}{% else %}{% set part = col %}{% endif -%}
+        {%- set_global index_name = index_name ~ "-" ~ part -%}
+        {%- endfor -%}
+        {%- if index.when %}{% set_global index_name = index_name ~ "-" ~ index.when %}{% endif %}
+        {% if index.tablespace -%}
+        create_index_in_tablespace(m,
+        {%- else -%}
+        m.create_index(
+        {%- endif %}
+            Index::create()
+                .name("{{index_name}}")
+                .table(Alias::new("{{plural_snake}}"))
+                {%- for col in index.columns %}
+                {%- if "(" in col %}
+                .col(Expr::cust(r#"{{col}}"#))
+                {%- else %}
+                .col(Alias::new("{{col}}"))
+                {%- endif %}
+                {%- endfor %}
+                {%- if index.unique %}
+                .unique()
+                {%- endif %}
+                {%- if index.gin %}
+                .full_text()
+                {%- endif %}
+                {%- if index.not_null %}
+                {%- for col in index.columns %}
+                {%- if "(" in col %}
+                .and_where(Expr::cust(r#"({{col}}) IS NOT NULL"#))
+                {%- else %}
+                .and_where(Expr::col(Alias::new("{{col}}")).is_not_null())
+                {%- endif %}
+                {%- endfor %}
+                {%- endif %}
+                {%- if index.when %}
+                .and_where(Expr::col(Alias::new("{{index.when}}")).eq(true))
+                {%- endif %}
+                {%- if index.when_null %}
+                .and_where(Expr::col(Alias::new("{{index.when_null}}")).is_null())
+                {%- endif %}
+                .to_owned(),
+            {%- if index.tablespace %}
+            "{{index.tablespace}}",
+            {%- endif %}
+        )
+        .await?;
+        {%- endfor %}
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_materialized_view{{drop_suffix}}(m, "{{plural_snake}}").await
+    }
+}
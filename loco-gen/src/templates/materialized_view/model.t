@@ -0,0 +1,77 @@
+{% set plural_snake = name | plural | snake_case -%}
+{% set model = plural_snake | pascal_case -%}
+to: "src/models/{{plural_snake}}.rs"
+skip_exists: true
+message: "Read-only model `{{model}}` of the materialized view `{{plural_snake}}` was added."
+injections:
+- into: "src/models/mod.rs"
+  append: true
+  content: "pub mod {{plural_snake}};"
+---
+//! The read-only entity of the materialized view `{{plural_snake}}`. `db
+//! entities` only generates the entities of tables, keep its columns in line
+//! with the view's query.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "{{plural_snake}}")]
+pub struct Model {
+    {%- for col in columns %}
+    {%- if col.primary_key %}
+    #[sea_orm(primary_key, auto_increment = false)]
+    {%- endif %}
+    {%- if col.column_name %}
+    #[sea_orm(column_name = "{{col.column_name}}")]
+    {%- endif %}
+    pub {{col.name}}: {{col.rust_type}},
+    {%- endfor %}
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+pub type {{model}} = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, _insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Err(DbErr::Custom(
+            "`{{plural_snake}}` is a materialized view, its rows are read-only".to_string(),
+        ))
+    }
+
+    async fn before_delete<C>(self, _db: &C) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Err(DbErr::Custom(
+            "`{{plural_snake}}` is a materialized view, its rows are read-only".to_string(),
+        ))
+    }
+}
+
+impl Entity {
+    /// Recomputes the rows of the view from its query
+    {%- if refresh_concurrently %}, without locking out
+    /// its readers
+    {%- endif %}
+    ///
+    /// # Errors
+    ///
+    /// When the query could not be executed
+    pub async fn refresh<C>(db: &C) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        db.execute_unprepared(
+            r#"REFRESH MATERIALIZED VIEW {% if refresh_concurrently %}CONCURRENTLY {% endif %}"{{plural_snake}}""#,
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -1,21 +1,29 @@
+{% set migration_dir = migration_dir | default(value="migration") -%}
 {% set mig_ts = ts | date(format="%Y%m%d_%H%M%S") -%}
 {% set mig_name = name | snake_case -%}
 {% set plural_snake = table | plural | snake_case -%}
 {% set module_name = "m" ~  mig_ts ~ "_" ~ mig_name -%}
-to: "migration/src/{{module_name}}.rs"
-skip_glob: "migration/src/m????????_??????_{{mig_name}}.rs"
+{% if if_exists | default(value=false) %}{% set drop_suffix = "_if_exists" %}{% else %}{% set drop_suffix = "" %}{% endif %}
+to: "{{migration_dir}}/src/{{module_name}}.rs"
+skip_glob: "{{migration_dir}}/src/m????????_??????_{{mig_name}}.rs"
 message: "Migration `{{mig_name}}` added! You can now apply it with `$ cargo loco db migrate && cargo loco db entities`."
 injections:
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "inject-above"
   content: "            Box::new({{module_name}}::Migration),"
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "pub struct Migrator"
   content: "mod {{module_name}};"
 ---
 use loco_rs::schema::*;
 use sea_orm_migration::prelude::*;
 
+{% if metadata.author -%}
+/// Author: {{metadata.author}}
+{% endif -%}
+{% if metadata.ticket -%}
+/// Ticket: {{metadata.ticket}}
+{% endif -%}
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -27,7 +35,11 @@ impl MigrationTrait for Migration {
         {% endfor -%}
 
         {% for ref in references -%}
+        {% if column_exists | default(value=false) -%}
+        add_foreign_key(m, "{{plural_snake}}", "{{ref.0}}", "{{ref.1}}").await?;
+        {% else -%}
         add_reference(m, "{{plural_snake}}", "{{ref.0}}", "{{ref.1}}").await?;
+        {% endif -%}
         {% endfor -%}
 
         Ok(())
@@ -35,11 +47,11 @@ impl MigrationTrait for Migration {
 
     async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
         {% for ref in references -%}
-        remove_reference(m, "{{plural_snake}}", "{{ref.0}}", "{{ref.1}}").await?;
+        remove_reference{{drop_suffix}}(m, "{{plural_snake}}", "{{ref.0}}", "{{ref.1}}").await?;
         {% endfor -%}
 
         {% for column in columns -%}
-        remove_column(m, "{{plural_snake}}", "{{column.0}}").await?;
+        remove_column{{drop_suffix}}(m, "{{plural_snake}}", "{{column.0}}").await?;
         {% endfor -%}
         
         Ok(())
@@ -1,19 +1,26 @@
+{% set migration_dir = migration_dir | default(value="migration") -%}
 {% set mig_ts = ts | date(format="%Y%m%d_%H%M%S") -%}
 {% set mig_name = name | snake_case -%}
 {% set module_name = "m" ~  mig_ts ~ "_" ~ mig_name -%}
-to: "migration/src/{{module_name}}.rs"
-skip_glob: "migration/src/*_{{mig_name}}.rs"
+to: "{{migration_dir}}/src/{{module_name}}.rs"
+skip_glob: "{{migration_dir}}/src/*_{{mig_name}}.rs"
 message: "Migration for `{{name}}` added! You can now apply it with `$ cargo loco db migrate && cargo loco db entities`."
 injections:
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "inject-above"
   content: "            Box::new({{module_name}}::Migration),"
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "pub struct Migrator"
   content: "mod {{module_name}};"
 ---
 use sea_orm_migration::{prelude::*, schema::*};
 
+{% if metadata.author -%}
+/// Author: {{metadata.author}}
+{% endif -%}
+{% if metadata.ticket -%}
+/// Ticket: {{metadata.ticket}}
+{% endif -%}
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -1,3 +1,4 @@
+{% set migration_dir = migration_dir | default(value="migration") -%}
 {% set mig_ts = ts | date(format="%Y%m%d_%H%M%S") -%}
 {% set plural_snake = name | plural | snake_case -%}
 {% set module_name = "m" ~  mig_ts ~ "_" ~ plural_snake -%}
@@ -7,20 +8,27 @@
 {% else %}
 {% set join_table_func = "create_join_table_without_timestamps" %}
 {% endif %}
-to: "migration/src/{{module_name}}.rs"
-skip_glob: "migration/src/m????????_??????_{{plural_snake}}.rs"
+{% if if_exists | default(value=false) %}{% set drop_suffix = "_if_exists" %}{% else %}{% set drop_suffix = "" %}{% endif %}
+to: "{{migration_dir}}/src/{{module_name}}.rs"
+skip_glob: "{{migration_dir}}/src/m????????_??????_{{plural_snake}}.rs"
 message: "Migration for `{{name}}` added! You can now apply it with `$ cargo loco db migrate && cargo loco db entities`."
 injections:
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "inject-above"
   content: "            Box::new({{module_name}}::Migration),"
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "pub struct Migrator"
   content: "mod {{module_name}};"
 ---
 use loco_rs::schema::*;
 use sea_orm_migration::prelude::*;
 
+{% if metadata.author -%}
+/// Author: {{metadata.author}}
+{% endif -%}
+{% if metadata.ticket -%}
+/// Ticket: {{metadata.ticket}}
+{% endif -%}
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -42,6 +50,6 @@ impl MigrationTrait for Migration {
     }
 
     async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
-        drop_table(m, "{{plural_snake}}").await
+        drop_table{{drop_suffix}}(m, "{{plural_snake}}").await
     }
 }
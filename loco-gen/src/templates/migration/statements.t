@@ -0,0 +1,150 @@
+{% set migration_dir = migration_dir | default(value="migration") -%}
+{% set mig_ts = ts | date(format="%Y%m%d_%H%M%S") -%}
+{% set mig_name = name | snake_case -%}
+{% set module_name = "m" ~  mig_ts ~ "_" ~ mig_name -%}
+{% if if_exists | default(value=false) %}{% set drop_suffix = "_if_exists" %}{% else %}{% set drop_suffix = "" %}{% endif %}
+to: "{{migration_dir}}/src/{{module_name}}.rs"
+skip_glob: "{{migration_dir}}/src/m????????_??????_{{mig_name}}.rs"
+message: "Migration `{{mig_name}}` added! You can now apply it with `$ cargo loco db migrate && cargo loco db entities`."
+injections:
+- into: "{{migration_dir}}/src/lib.rs"
+  before: "inject-above"
+  content: "            Box::new({{module_name}}::Migration),"
+- into: "{{migration_dir}}/src/lib.rs"
+  before: "pub struct Migrator"
+  content: "mod {{module_name}};"
+---
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+{% if metadata.author -%}
+/// Author: {{metadata.author}}
+{% endif -%}
+{% if metadata.ticket -%}
+/// Ticket: {{metadata.ticket}}
+{% endif -%}
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        {%- for s in statements %}
+        {%- if s.kind == "CreateTable" %}
+        {%- if s.join_table %}{% set func = "create_join_table" %}{% else %}{% set func = "create_table" %}{% endif %}
+        {%- if not s.timestamps %}{% set func = func ~ "_without_timestamps" %}{% endif %}
+        {{func}}(m, "{{s.table}}",
+            &[
+            {%- if s.pk_col_type %}
+            ("id", ColType::{{s.pk_col_type}}),
+            {%- endif %}
+            {%- for column in s.columns %}
+            ("{{column.0}}", ColType::{{column.1}}),
+            {%- endfor %}
+            ],
+            &[
+            {%- for ref in s.references %}
+            ("{{ref.0}}", "{{ref.1}}"),
+            {%- endfor %}
+            ]
+        ).await?;
+        {%- elif s.kind == "AddColumn" %}
+        add_column(m, "{{s.table}}", "{{s.column}}", ColType::{{s.col_type}}).await?;
+        {%- elif s.kind == "RemoveColumn" %}
+        remove_column(m, "{{s.table}}", "{{s.column}}").await?;
+        {%- elif s.kind == "AddForeignKey" %}
+        {%- if s.column_exists %}
+        add_foreign_key(m, "{{s.table}}", "{{s.field}}", "{{s.column}}").await?;
+        {%- else %}
+        add_reference(m, "{{s.table}}", "{{s.field}}", "{{s.column}}").await?;
+        {%- endif %}
+        {%- elif s.kind == "CreateIndex" %}
+        {%- set index = s.index %}
+        {%- if index.deferrable %}
+        add_deferrable_unique(m, "{{s.table}}", "{{s.name}}", &[{% for col in index.columns %}"{{col}}"{% if not loop.last %}, {% endif %}{% endfor %}]).await?;
+        {%- else %}
+        {% if index.tablespace -%}
+        create_index_in_tablespace(m,
+        {%- else -%}
+        m.create_index(
+        {%- endif %}
+            Index::create()
+                .name("{{s.name}}")
+                .table(Alias::new("{{s.table}}"))
+                {%- for col in index.columns %}
+                {%- if "(" in col %}
+                .col(Expr::cust(r#"{{col}}"#))
+                {%- else %}
+                .col(Alias::new("{{col}}"))
+                {%- endif %}
+                {%- endfor %}
+                {%- if index.unique %}
+                .unique()
+                {%- endif %}
+                {%- if index.gin %}
+                .full_text()
+                {%- endif %}
+                {%- if index.not_null %}
+                {%- for col in index.columns %}
+                {%- if "(" in col %}
+                .and_where(Expr::cust(r#"({{col}}) IS NOT NULL"#))
+                {%- else %}
+                .and_where(Expr::col(Alias::new("{{col}}")).is_not_null())
+                {%- endif %}
+                {%- endfor %}
+                {%- endif %}
+                {%- if index.when %}
+                .and_where(Expr::col(Alias::new("{{index.when}}")).eq(true))
+                {%- endif %}
+                {%- if index.when_null %}
+                .and_where(Expr::col(Alias::new("{{index.when_null}}")).is_null())
+                {%- endif %}
+                .to_owned(),
+            {%- if index.tablespace %}
+            "{{index.tablespace}}",
+            {%- endif %}
+        )
+        .await?;
+        {%- endif %}
+        {%- if index.comment %}
+        comment_on_index(m, "{{s.name}}", {{index.comment}}).await?;
+        {%- endif %}
+        {%- elif s.kind == "AddCheck" %}
+        add_check(m, "{{s.table}}", "{{s.name}}", {{s.expr}}).await?;
+        {%- endif %}
+        {%- endfor %}
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        {%- for s in statements | reverse %}
+        {%- if s.kind == "CreateTable" %}
+        drop_table{{drop_suffix}}(m, "{{s.table}}").await?;
+        {%- elif s.kind == "AddColumn" %}
+        remove_column{{drop_suffix}}(m, "{{s.table}}", "{{s.column}}").await?;
+        {%- elif s.kind == "RemoveColumn" %}
+        add_column(m, "{{s.table}}", "{{s.column}}", ColType::{{s.col_type}}).await?;
+        {%- elif s.kind == "AddForeignKey" %}
+        remove_reference{{drop_suffix}}(m, "{{s.table}}", "{{s.field}}", "{{s.column}}").await?;
+        {%- elif s.kind == "CreateIndex" %}
+        {%- if s.index.deferrable %}
+        drop_check{{drop_suffix}}(m, "{{s.table}}", "{{s.name}}").await?;
+        {%- else %}
+        m.drop_index(
+            Index::drop()
+                .name("{{s.name}}")
+                .table(Alias::new("{{s.table}}"))
+                {%- if if_exists | default(value=false) %}
+                .if_exists()
+                {%- endif %}
+                .to_owned(),
+        )
+        .await?;
+        {%- endif %}
+        {%- elif s.kind == "AddCheck" %}
+        drop_check{{drop_suffix}}(m, "{{s.table}}", "{{s.name}}").await?;
+        {%- endif %}
+        {%- endfor %}
+        Ok(())
+    }
+}
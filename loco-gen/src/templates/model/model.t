@@ -1,36 +1,87 @@
+{% set migration_dir = migration_dir | default(value="migration") -%}
 {% set mig_ts = ts | date(format="%Y%m%d_%H%M%S") -%}
 {% set plural_snake = name | plural | snake_case -%}
 {% set module_name = "m" ~  mig_ts ~ "_" ~ plural_snake -%}
 {% set model = name | plural | pascal_case -%}
-{% if with_tz %}
-{% set create_table_func = "create_table" %}
+{% set indexes = indexes | default(value=[]) -%}
+{% set collated_indexes = collated_indexes | default(value=[]) -%}
+{% set column_storages = column_storages | default(value=[]) -%}
+{% set counter_caches = counter_caches | default(value=[]) -%}
+{% set denormalized = denormalized | default(value=[]) -%}
+{% set composite_types = composite_types | default(value=[]) -%}
+{% set sequences = sequences | default(value=[]) -%}
+{% set enum_types = enum_types | default(value=[]) -%}
+{% set fk_comments = fk_comments | default(value=[]) -%}
+{% set checks = checks | default(value=[]) -%}
+{% set no_overlaps = no_overlaps | default(value=[]) -%}
+{% if many_to_many | default(value=false) %}
+{% set create_table_func = "create_join_table" %}
+{% elif unlogged | default(value=false) %}
+{% set create_table_func = "create_unlogged_table" %}
+{% elif without_rowid | default(value=false) and sqlite_strict | default(value=false) %}
+{% set create_table_func = "create_strict_without_rowid_table" %}
+{% elif without_rowid | default(value=false) %}
+{% set create_table_func = "create_without_rowid_table" %}
+{% elif sqlite_strict | default(value=false) %}
+{% set create_table_func = "create_strict_table" %}
 {% else %}
-{% set create_table_func = "create_table_without_timestamps" %}
+{% set create_table_func = "create_table" %}
 {% endif %}
-to: "migration/src/{{module_name}}.rs"
-skip_glob: "migration/src/m????????_??????_{{plural_snake}}.rs"
+{% if not with_tz %}
+{% set create_table_func = create_table_func ~ "_without_timestamps" %}
+{% endif %}
+{% if if_exists | default(value=false) %}{% set drop_suffix = "_if_exists" %}{% else %}{% set drop_suffix = "" %}{% endif %}
+{% set in_transaction = in_transaction | default(value=true) %}
+to: "{{migration_dir}}/src/{{module_name}}.rs"
+skip_glob: "{{migration_dir}}/src/m????????_??????_{{plural_snake}}.rs"
 message: "Migration for `{{name}}` added! You can now apply it with `$ cargo loco db migrate && cargo loco db entities`."
 injections:
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "inject-above"
   content: "            Box::new({{module_name}}::Migration),"
-- into: "migration/src/lib.rs"
+- into: "{{migration_dir}}/src/lib.rs"
   before: "pub struct Migrator"
   content: "mod {{module_name}};"
 ---
 use loco_rs::schema::*;
 use sea_orm_migration::prelude::*;
 
+{% if metadata.author -%}
+/// Author: {{metadata.author}}
+{% endif -%}
+{% if metadata.ticket -%}
+/// Ticket: {{metadata.ticket}}
+{% endif -%}
+{% if not in_transaction -%}
+/// Non-transactional: runs outside of the migrator's transaction, see
+/// `leave_transaction`.
+{% if concurrently | default(value=false) and indexes | default(value=[]) | length > 0 -%}
+/// The indexes are built concurrently, which Postgres only allows outside of
+/// a transaction.
+{% endif -%}
+{% endif -%}
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        {% if not in_transaction -%}
+        leave_transaction(m).await?;
+        {% endif -%}
+        {% for sequence in sequences -%}
+        create_sequence(m, "{{sequence}}").await?;
+        {% endfor -%}
+        {% for composite in composite_types -%}
+        create_composite_type(m, "{{composite.name}}", &[{% for field in composite.fields %}("{{field.name}}", "{{field.sql_type}}"){% if not loop.last %}, {% endif %}{% endfor %}]).await?;
+        {% endfor -%}
+        {% for enum_type in guarded_enum_types | default(value=[]) -%}
+        create_enum_type_if_not_exists(m, "{{enum_type.0}}", &[{% for variant in enum_type.1 %}"{{variant}}"{% if not loop.last %}, {% endif %}{% endfor %}]).await?;
+        {% endfor -%}
         {{create_table_func}}(m, "{{plural_snake}}",
             &[
-            {% if columns | length > 0 %}
-            ("id", ColType::PkAuto),
+            {% if columns | length > 0 and not shared_pk | default(value=false) and not many_to_many | default(value=false) %}
+            ("id", ColType::{{ pk_col_type | default(value="PkAuto") }}),
             {% endif %}
             {% for column in columns -%}
             ("{{column.0}}", ColType::{{column.1}}),
@@ -41,10 +92,138 @@ impl MigrationTrait for Migration {
             ("{{ref.0}}", "{{ref.1}}"),
             {% endfor -%}
             ]
-        ).await
+        ).await{% if indexes | length > 0 or collated_indexes | length > 0 or column_storages | length > 0 or counter_caches | length > 0 or denormalized | length > 0 or history | default(value=false) or fk_comments | length > 0 or checks | length > 0 or no_overlaps | length > 0 or not in_transaction %}?;
+        {% for index in indexes %}
+        {%- set_global index_name = "idx-" ~ plural_snake -%}
+        {%- for col in index.columns -%}
+        {%- if "(" in col %}{% set part = col | snake_case %}{% else %}{% set part = col %}{% endif -%}
+        {%- set_global index_name = index_name ~ "-" ~ part -%}
+        {%- endfor -%}
+        {%- if index.when %}{% set_global index_name = index_name ~ "-" ~ index.when %}{% endif %}
+        {% if index.deferrable -%}
+        add_deferrable_unique(m, "{{plural_snake}}", "{{index_name}}", &[{% for col in index.columns %}"{{col}}"{% if not loop.last %}, {% endif %}{% endfor %}]).await?;
+        {%- else -%}
+        {% if index.tablespace and concurrently | default(value=false) -%}
+        create_index_concurrently_in_tablespace_without_transaction(m,
+        {%- elif index.tablespace -%}
+        create_index_in_tablespace(m,
+        {%- elif concurrently | default(value=false) -%}
+        create_index_concurrently_without_transaction(m,
+        {%- else -%}
+        m.create_index(
+        {%- endif %}
+            Index::create()
+                .name("{{index_name}}")
+                .table(Alias::new("{{plural_snake}}"))
+                {%- for col in index.columns %}
+                {%- if "(" in col %}
+                .col(Expr::cust(r#"{{col}}"#))
+                {%- else %}
+                .col(Alias::new("{{col}}"))
+                {%- endif %}
+                {%- endfor %}
+                {%- if index.unique %}
+                .unique()
+                {%- endif %}
+                {%- if index.gin %}
+                .full_text()
+                {%- endif %}
+                {%- if index.not_null %}
+                {%- for col in index.columns %}
+                {%- if "(" in col %}
+                .and_where(Expr::cust(r#"({{col}}) IS NOT NULL"#))
+                {%- else %}
+                .and_where(Expr::col(Alias::new("{{col}}")).is_not_null())
+                {%- endif %}
+                {%- endfor %}
+                {%- endif %}
+                {%- if index.when %}
+                .and_where(Expr::col(Alias::new("{{index.when}}")).eq(true))
+                {%- endif %}
+                {%- if index.when_null %}
+                .and_where(Expr::col(Alias::new("{{index.when_null}}")).is_null())
+                {%- endif %}
+                .to_owned(),
+            {%- if index.tablespace %}
+            "{{index.tablespace}}",
+            {%- endif %}
+        )
+        .await?;
+        {%- endif %}
+        {%- if index.comment %}
+        comment_on_index(m, "{{index_name}}", {{index.comment}}).await?;
+        {%- endif %}
+        {% endfor %}
+        {%- for index in collated_indexes %}
+        create_collated_index(m, "{{plural_snake}}", "{{index.column}}", "{{index.collation}}").await?;
+        {%- endfor %}
+        {%- for item in column_storages %}
+        set_column_storage(m, "{{plural_snake}}", "{{item.column}}", "{{item.storage}}").await?;
+        {%- endfor %}
+        {%- for comment in fk_comments %}
+        comment_on_foreign_key(m, "{{plural_snake}}", "{{comment.0}}", {{comment.1}}).await?;
+        {%- endfor %}
+        {%- for check in checks %}
+        add_check(m, "{{plural_snake}}", "{{check.name}}", {{check.expr}}).await?;
+        {%- endfor %}
+        {%- for no_overlap in no_overlaps %}
+        add_no_overlap(m, "{{plural_snake}}", "{{no_overlap.name}}", &[{% for col in no_overlap.columns %}"{{col}}"{% if not loop.last %}, {% endif %}{% endfor %}], "{{no_overlap.range}}").await?;
+        {%- endfor %}
+        {%- for item in denormalized %}
+        add_denormalized_column(m, "{{plural_snake}}", "{{item.column}}", &[{% for source in item.sources %}"{{source}}"{% if not loop.last %}, {% endif %}{% endfor %}]).await?;
+        {%- endfor %}
+        {%- for cache in counter_caches %}
+        add_counter_cache(m, "{{cache.parent}}", "{{cache.column}}", "{{cache.child}}", "{{cache.fk}}").await?;
+        {%- endfor %}
+        {%- if history | default(value=false) %}
+        create_history_table{% if not with_tz %}_without_timestamps{% endif %}(m, "{{plural_snake}}",
+            &[
+            {% for column in columns -%}
+            ("{{column.0}}", ColType::{{column.1}}),
+            {% endfor -%}
+            ],
+            &[
+            {% for ref in references -%}
+            ("{{ref.0}}", "{{ref.1}}"),
+            {% endfor -%}
+            ]
+        ).await?;
+        {%- endif %}
+        {%- if not in_transaction %}
+        resume_transaction(m).await?;
+        {%- endif %}
+        Ok(()){% endif %}
     }
 
     async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
-        drop_table(m, "{{plural_snake}}").await
+        {%- if not in_transaction %}
+        leave_transaction(m).await?;
+        {%- endif %}
+        {%- for item in denormalized %}
+        remove_denormalized_column{{drop_suffix}}(m, "{{plural_snake}}", "{{item.column}}").await?;
+        {%- endfor %}
+        {%- for cache in counter_caches %}
+        remove_counter_cache{{drop_suffix}}(m, "{{cache.parent}}", "{{cache.column}}", "{{cache.child}}").await?;
+        {%- endfor %}
+        {%- if history | default(value=false) %}
+        drop_history_table{{drop_suffix}}(m, "{{plural_snake}}").await?;
+        {%- endif %}
+        {%- for check in checks %}
+        drop_check{{drop_suffix}}(m, "{{plural_snake}}", "{{check.name}}").await?;
+        {%- endfor %}
+        drop_table{{drop_suffix}}(m, "{{plural_snake}}").await{% if sequences | length > 0 or enum_types | length > 0 or composite_types | length > 0 or not in_transaction %}?;
+        {%- for composite in composite_types %}
+        drop_composite_type{{drop_suffix}}(m, "{{composite.name}}").await?;
+        {%- endfor %}
+        {%- for sequence in sequences %}
+        drop_sequence(m, "{{sequence}}").await?;
+        {%- endfor %}
+        {%- for enum_type in enum_types %}
+        drop_enum_type(m, "{{enum_type}}").await?;
+        {%- endfor %}
+        {%- if not in_transaction %}
+        resume_transaction(m).await?;
+        {%- endif %}
+        Ok(()){% endif %}
     }
 }
@@ -0,0 +1,600 @@
+{% set plural_snake = name | plural | snake_case -%}
+{% set model = plural_snake | pascal_case -%}
+{% set encrypted = encrypted | default(value=[]) -%}
+{% set money_minor = money_minor | default(value=[]) -%}
+{% set state_machines = state_machines | default(value=[]) -%}
+{% set enums = enums | default(value=[]) -%}
+{% set composite_types = composite_types | default(value=[]) -%}
+{% set phone = phone | default(value=[]) -%}
+{% set passwords = passwords | default(value=[]) -%}
+{% set content_hash = content_hash | default(value="") -%}
+{% set builder = builder | default(value=[]) -%}
+{% set reference_accessors = reference_accessors | default(value=[]) -%}
+{% set seed_conflict = seed_conflict | default(value=[]) -%}
+{% if shared_pk | default(value=false) %}{% set pk = shared_pk %}{% else %}{% set pk = "id" %}{% endif -%}
+{% set active_period = active_period | default(value="") -%}
+{% set has_model_methods = money_minor | length > 0 or cache_key or encrypted | length > 0 or state_machines | length > 0 or passwords | length > 0 or active_period or reference_accessors | length > 0 -%}
+{% set has_active_model_methods = money_minor | length > 0 or encrypted | length > 0 or state_machines | length > 0 or phone | length > 0 or passwords | length > 0 or content_hash -%}
+to: "src/models/{{plural_snake}}.rs"
+skip_exists: true
+message: "Model `{{model}}` was added with an `ActiveModelBehavior` hook."
+injections:
+- into: "src/models/mod.rs"
+  append: true
+  skip_if: "pub mod {{plural_snake}};"
+  content: "pub mod {{plural_snake}};"
+---
+{% if encrypted | length > 0 -%}
+use loco_rs::model::Encrypted;
+{% endif -%}
+{% if active_period -%}
+use loco_rs::model::TstzRange;
+{% endif -%}
+use sea_orm::entity::prelude::*;
+pub use super::_entities::{{plural_snake}}::{ActiveModel, Model, Entity};
+pub type {{model}} = Entity;
+{%- for accessor in reference_accessors | unique(attribute="table") %}
+use super::_entities::{{accessor.table}}::Model as {{accessor.model}};
+{%- endfor %}
+{%- for machine in state_machines %}
+{%- set enum_name = machine.name | pascal_case %}
+
+/// The states of `{{machine.name}}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum {{enum_name}} {
+    {%- for state in machine.states %}
+    {{state | pascal_case}},
+    {%- endfor %}
+}
+
+impl {{enum_name}} {
+    /// The state as stored in `{{machine.name}}`
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            {%- for state in machine.states %}
+            Self::{{state | pascal_case}} => "{{state}}",
+            {%- endfor %}
+        }
+    }
+
+    /// Whether moving from this state to `next` is allowed
+    #[must_use]
+    pub const fn can_transition_to(self, next: Self) -> bool {
+        matches!(
+            (self, next),
+            {%- for transition in machine.transitions %}
+            {% if not loop.first %}| {% endif %}(Self::{{transition.0 | pascal_case}}, Self::{{transition.1 | pascal_case}})
+            {%- endfor %}
+        )
+    }
+}
+
+impl std::str::FromStr for {{enum_name}} {
+    type Err = DbErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            {%- for state in machine.states %}
+            "{{state}}" => Ok(Self::{{state | pascal_case}}),
+            {%- endfor %}
+            _ => Err(DbErr::Custom(format!("unknown {{machine.name}}: `{s}`"))),
+        }
+    }
+}
+{%- endfor %}
+{%- for enum_type in enums %}
+{%- set enum_name = enum_type.name | pascal_case %}
+
+/// The variants of the `{{enum_type.name}}` enum type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "{{enum_type.name}}")]
+pub enum {{enum_name}} {
+    {%- for variant in enum_type.variants %}
+    #[sea_orm(string_value = "{{variant}}")]
+    {{variant | pascal_case}},
+    {%- endfor %}
+}
+
+impl {{enum_name}} {
+    /// The variant as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            {%- for variant in enum_type.variants %}
+            Self::{{variant | pascal_case}} => "{{variant}}",
+            {%- endfor %}
+        }
+    }
+}
+
+impl std::str::FromStr for {{enum_name}} {
+    type Err = DbErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            {%- for variant in enum_type.variants %}
+            "{{variant}}" => Ok(Self::{{variant | pascal_case}}),
+            {%- endfor %}
+            _ => Err(DbErr::Custom(format!("unknown {{enum_type.name}}: `{s}`"))),
+        }
+    }
+}
+{%- endfor %}
+{%- for composite in composite_types %}
+
+/// The fields of the `{{composite.name}}` composite type, e.g. deserialized from
+/// a column of that type selected as `to_jsonb(<column>)`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct {{composite.name | pascal_case}} {
+    {%- for field in composite.fields %}
+    pub {{field.name}}: Option<{{field.rust_type}}>,
+    {%- endfor %}
+}
+{%- endfor %}
+{%- if phone | length > 0 %}
+
+/// Normalizes a phone number to E.164, e.g. `+1 (555) 010-9999` to
+/// `+15550109999`, or `None` when it is not one
+#[must_use]
+pub fn normalize_phone(input: &str) -> Option<String> {
+    let input = input.trim();
+    let number = input.strip_prefix('+').or_else(|| input.strip_prefix("00"))?;
+    let digits = number
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect::<String>();
+    let valid = (2..=15).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && !digits.starts_with('0');
+    valid.then(|| format!("+{digits}"))
+}
+{%- endif %}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        {%- if uuid_pk | default(value="") %}
+        if insert && self.id.is_not_set() {
+            let mut this = self;
+            this.id = sea_orm::ActiveValue::Set(Uuid::{{uuid_pk}}());
+            return Ok(this);
+        }
+        {%- endif %}
+        {%- if with_tz %}
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            return Ok(this);
+        }
+        {%- else %}
+        let _ = insert;
+        {%- endif %}
+        // validate or adjust the model before it is written
+        Ok(self)
+    }
+    {%- if delete_guard | default(value=[]) | length > 0 %}
+
+    async fn before_delete<C>(self, db: &C) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if let Some(id) = self.{{pk}}.try_as_ref() {
+            {%- for table in delete_guard %}
+            let dependents = super::_entities::{{table}}::Entity::find()
+                .filter(super::_entities::{{table}}::Column::{{delete_guard_column | pascal_case}}.eq(*id))
+                .count(db)
+                .await?;
+            if dependents > 0 {
+                return Err(DbErr::Custom(format!(
+                    "cannot delete from {{plural_snake}}, the row is still referenced by {dependents} {{table}}"
+                )));
+            }
+            {%- endfor %}
+        }
+        Ok(self)
+    }
+    {%- endif %}
+}
+
+{%- if display | default(value=false) %}
+
+impl std::fmt::Display for Model {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        {%- if display_column and display_column.nullable %}
+        match &self.{{display_column.name}} {
+            Some({{display_column.name}}) => write!(f, "{}", {{display_column.name}}),
+            None => write!(f, "{}", self.{{pk}}),
+        }
+        {%- elif display_column %}
+        write!(f, "{}", self.{{display_column.name}})
+        {%- else %}
+        write!(f, "{}", self.{{pk}})
+        {%- endif %}
+    }
+}
+{%- endif %}
+
+// implement your read-oriented logic here
+{% if has_model_methods -%}
+impl Model {
+    {%- if cache_key %}
+
+    /// A key identifying this version of the row, e.g. for an `ETag`
+    #[must_use]
+    pub fn cache_key(&self) -> String {
+        format!("{{plural_snake}}/{}-{}", self.{{pk}}, self.updated_at.timestamp_micros())
+    }
+    {%- endif %}
+    {%- for col in money_minor %}
+
+    /// `{{col.name}}` in major units, stored as minor units (cents)
+    #[must_use]
+    {%- if col.nullable %}
+    pub fn {{col.name}}_major(&self) -> Option<Decimal> {
+        self.{{col.name}}.map(|minor| Decimal::new(minor, 2))
+    }
+    {%- else %}
+    pub fn {{col.name}}_major(&self) -> Decimal {
+        Decimal::new(self.{{col.name}}, 2)
+    }
+    {%- endif %}
+    {%- endfor %}
+    {%- for col in encrypted %}
+
+    /// `{{col.name}}` as stored, encrypted by the application
+    #[must_use]
+    {%- if col.nullable %}
+    pub fn {{col.name}}_encrypted(&self) -> Option<Encrypted<&{{col.rust_type}}>> {
+        self.{{col.name}}.as_ref().map(Encrypted::new)
+    }
+    {%- else %}
+    pub fn {{col.name}}_encrypted(&self) -> Encrypted<&{{col.rust_type}}> {
+        Encrypted::new(&self.{{col.name}})
+    }
+    {%- endif %}
+    {%- endfor %}
+    {%- if active_period %}
+
+    /// Whether `t` falls within `{{active_period.name}}`
+    #[must_use]
+    pub fn is_active_at(&self, t: DateTimeWithTimeZone) -> bool {
+        {%- if active_period.nullable %}
+        self.{{active_period.name}}
+            .as_deref()
+            .and_then(|period| period.parse::<TstzRange>().ok())
+            .is_some_and(|period| period.contains(t))
+        {%- else %}
+        self.{{active_period.name}}
+            .parse::<TstzRange>()
+            .is_ok_and(|period| period.contains(t))
+        {%- endif %}
+    }
+    {%- endif %}
+    {%- for col in passwords %}
+
+    /// Whether `password` is the one hashed in `{{col.name}}`
+    #[must_use]
+    pub fn verify_{{col.field}}(&self, password: &str) -> bool {
+        {%- if col.nullable %}
+        self.{{col.name}}
+            .as_deref()
+            .is_some_and(|hash| loco_rs::hash::verify_password(password, hash))
+        {%- else %}
+        loco_rs::hash::verify_password(password, &self.{{col.name}})
+        {%- endif %}
+    }
+    {%- endfor %}
+    {%- for accessor in reference_accessors %}
+
+    /// The `{{accessor.table}}` row `{{accessor.column}}` refers to
+    ///
+    /// # Errors
+    ///
+    /// When the query could not be executed{% if not accessor.nullable %}, or the row does not exist{% endif %}
+    {% if accessor.nullable -%}
+    pub async fn {{accessor.name}}<C>(&self, db: &C) -> Result<Option<{{accessor.model}}>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        match self.{{accessor.column}} {
+            Some(id) => super::_entities::{{accessor.table}}::Entity::find_by_id(id).one(db).await,
+            None => Ok(None),
+        }
+    }
+    {%- else -%}
+    pub async fn {{accessor.name}}<C>(&self, db: &C) -> Result<{{accessor.model}}, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        super::_entities::{{accessor.table}}::Entity::find_by_id(self.{{accessor.column}})
+            .one(db)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("{{accessor.table}} {}", self.{{accessor.column}})))
+    }
+    {%- endif %}
+    {%- endfor %}
+    {%- for machine in state_machines %}
+
+    /// The current state of `{{machine.name}}`
+    ///
+    /// # Errors
+    ///
+    /// When the stored value is not a known state
+    pub fn {{machine.name}}_state(&self) -> Result<{{machine.name | pascal_case}}, DbErr> {
+        self.{{machine.name}}.parse()
+    }
+    {%- endfor %}
+}
+{%- else -%}
+impl Model {}
+{%- endif %}
+
+// implement your write-oriented logic here
+{% if has_active_model_methods -%}
+impl ActiveModel {
+    {%- for col in money_minor %}
+
+    /// Sets `{{col.name}}` from major units, rounded to minor units (cents)
+    ///
+    /// # Errors
+    ///
+    /// When the amount does not fit in minor units
+    pub fn set_{{col.name}}_major(&mut self, major: Decimal) -> Result<(), DbErr> {
+        let minor = i64::try_from((major * Decimal::new(100, 0)).round())
+            .map_err(|err| DbErr::Custom(err.to_string()))?;
+        {%- if col.nullable %}
+        self.{{col.name}} = sea_orm::ActiveValue::Set(Some(minor));
+        {%- else %}
+        self.{{col.name}} = sea_orm::ActiveValue::Set(minor);
+        {%- endif %}
+        Ok(())
+    }
+    {%- endfor %}
+    {%- for col in encrypted %}
+
+    /// Sets `{{col.name}}` from a value encrypted by the application
+    {%- if col.nullable %}
+    pub fn set_{{col.name}}_encrypted(&mut self, value: Option<Encrypted<{{col.rust_type}}>>) {
+        self.{{col.name}} = sea_orm::ActiveValue::Set(value.map(Encrypted::into_inner));
+    }
+    {%- else %}
+    pub fn set_{{col.name}}_encrypted(&mut self, value: Encrypted<{{col.rust_type}}>) {
+        self.{{col.name}} = sea_orm::ActiveValue::Set(value.into_inner());
+    }
+    {%- endif %}
+    {%- endfor %}
+    {%- for col in phone %}
+
+    /// Sets `{{col.name}}` from a phone number, normalized to E.164
+    ///
+    /// # Errors
+    ///
+    /// When `input` is not a phone number
+    pub fn set_{{col.name}}_normalized(&mut self, input: &str) -> Result<(), DbErr> {
+        let number = normalize_phone(input)
+            .ok_or_else(|| DbErr::Custom(format!("`{input}` is not an E.164 phone number")))?;
+        {%- if col.nullable %}
+        self.{{col.name}} = sea_orm::ActiveValue::Set(Some(number));
+        {%- else %}
+        self.{{col.name}} = sea_orm::ActiveValue::Set(number);
+        {%- endif %}
+        Ok(())
+    }
+    {%- endfor %}
+    {%- for col in passwords %}
+
+    /// Sets `{{col.name}}` to the hash of `password`, the password itself is never stored
+    ///
+    /// # Errors
+    ///
+    /// When the password could not be hashed
+    pub fn set_{{col.field}}(&mut self, password: &str) -> loco_rs::Result<()> {
+        let hash = loco_rs::hash::hash_password(password)?;
+        {%- if col.nullable %}
+        self.{{col.name}} = sea_orm::ActiveValue::Set(Some(hash));
+        {%- else %}
+        self.{{col.name}} = sea_orm::ActiveValue::Set(hash);
+        {%- endif %}
+        Ok(())
+    }
+    {%- endfor %}
+    {%- if content_hash %}
+
+    /// Sets `{{content_hash.column}}` along with its SHA-256 in `{{content_hash.hash_column}}`
+    {%- if content_hash.nullable %}
+    pub fn set_{{content_hash.column}}_hashed(&mut self, {{content_hash.column}}: Option<Vec<u8>>) {
+        self.{{content_hash.hash_column}} =
+            sea_orm::ActiveValue::Set({{content_hash.column}}.as_deref().map(loco_rs::hash::sha256_hex));
+        self.{{content_hash.column}} = sea_orm::ActiveValue::Set({{content_hash.column}});
+    }
+    {%- else %}
+    pub fn set_{{content_hash.column}}_hashed(&mut self, {{content_hash.column}}: Vec<u8>) {
+        self.{{content_hash.hash_column}} =
+            sea_orm::ActiveValue::Set(loco_rs::hash::sha256_hex(&{{content_hash.column}}));
+        self.{{content_hash.column}} = sea_orm::ActiveValue::Set({{content_hash.column}});
+    }
+    {%- endif %}
+    {%- endif %}
+    {%- for machine in state_machines %}
+    {%- set enum_name = machine.name | pascal_case %}
+
+    /// Moves `{{machine.name}}` to `next`
+    ///
+    /// # Errors
+    ///
+    /// When `{{machine.name}}` is not set, or cannot move to `next`
+    pub fn transition_{{machine.name}}(&mut self, next: {{enum_name}}) -> Result<(), DbErr> {
+        let current: {{enum_name}} = self
+            .{{machine.name}}
+            .try_as_ref()
+            .ok_or_else(|| DbErr::Custom("`{{machine.name}}` is not set".to_string()))?
+            .parse()?;
+        if !current.can_transition_to(next) {
+            return Err(DbErr::Custom(format!(
+                "cannot move `{{machine.name}}` from {} to {}",
+                current.as_str(),
+                next.as_str()
+            )));
+        }
+        self.{{machine.name}} = sea_orm::ActiveValue::Set(next.as_str().to_string());
+        Ok(())
+    }
+    {%- endfor %}
+}
+{%- else -%}
+impl ActiveModel {}
+{%- endif %}
+{%- if builder | length > 0 %}
+{%- set required = builder | filter(attribute="nullable", value=false) %}
+
+/// Builds an [`ActiveModel`] from its not null columns, with setters for the
+/// nullable ones
+#[derive(Debug, Clone)]
+pub struct ActiveModelBuilder(ActiveModel);
+
+impl ActiveModelBuilder {
+    {%- if required | length > 7 %}
+    #[allow(clippy::too_many_arguments)]
+    {%- endif %}
+    #[must_use]
+    pub fn new({% for col in required %}{{col.name}}: {{col.rust_type}}{% if not loop.last %}, {% endif %}{% endfor %}) -> Self {
+        Self(ActiveModel {
+            {%- for col in required %}
+            {{col.name}}: sea_orm::ActiveValue::Set({{col.name}}),
+            {%- endfor %}
+            ..Default::default()
+        })
+    }
+    {%- for col in builder | filter(attribute="nullable", value=true) %}
+
+    #[must_use]
+    pub fn {{col.name}}(mut self, {{col.name}}: {{col.rust_type}}) -> Self {
+        self.0.{{col.name}} = sea_orm::ActiveValue::Set(Some({{col.name}}));
+        self
+    }
+    {%- endfor %}
+
+    #[must_use]
+    pub fn build(self) -> ActiveModel {
+        self.0
+    }
+}
+{%- endif %}
+
+// implement your custom finders, selectors oriented logic here
+{% if search_column | default(value="") or upsert | default(value="") or seed_conflict | length > 0 or content_hash -%}
+impl Entity {
+    {%- if search_column | default(value="") %}
+    /// Rows whose `{{search_column}}` matches `query`, best ranked first
+    ///
+    /// # Errors
+    ///
+    /// When the query could not be executed
+    pub async fn search<C>(db: &C, query: &str) -> Result<Vec<Model>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::find()
+            .filter(Expr::cust_with_values(
+                r#""{{search_column}}" @@ plainto_tsquery($1)"#,
+                [query],
+            ))
+            .order_by_desc(Expr::cust_with_values(
+                r#"ts_rank("{{search_column}}", plainto_tsquery($1))"#,
+                [query],
+            ))
+            .all(db)
+            .await
+    }
+    {%- endif %}
+    {%- if upsert | default(value="") %}
+
+    /// Inserts `model`, or updates the row it conflicts with on
+    /// {% for col in upsert.conflict %}`{{col}}`{% if not loop.last %}, {% endif %}{% endfor %}
+    ///
+    /// # Errors
+    ///
+    /// When the query could not be executed
+    pub async fn upsert<C>(db: &C, model: ActiveModel) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::insert(model)
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::columns([
+                    {%- for col in upsert.conflict %}
+                    Column::{{col | pascal_case}},
+                    {%- endfor %}
+                ])
+                .update_columns([
+                    {%- for col in upsert.update %}
+                    Column::{{col | pascal_case}},
+                    {%- endfor %}
+                ])
+                .to_owned(),
+            )
+            .exec_with_returning(db)
+            .await
+    }
+    {%- endif %}
+    {%- if seed_conflict | length > 0 %}
+
+    /// Seeds the rows of the file at `path`, skipping those that conflict with existing rows on
+    /// {% for col in seed_conflict %}`{{col}}`{% if not loop.last %}, {% endif %}{% endfor %}, so that seeding again is safe
+    ///
+    /// # Errors
+    ///
+    /// When the file could not be read, or the rows inserted
+    pub async fn seed(db: &DatabaseConnection, path: &str) -> loco_rs::Result<()> {
+        loco_rs::db::seed_on_conflict_do_nothing::<ActiveModel, _>(
+            db,
+            path,
+            [
+                {%- for col in seed_conflict %}
+                Column::{{col | pascal_case}},
+                {%- endfor %}
+            ],
+        )
+        .await
+    }
+    {%- endif %}
+    {%- if content_hash %}
+
+    /// Inserts `model`, or returns the row with the same `{{content_hash.column}}`, found by
+    /// its `{{content_hash.hash_column}}`
+    ///
+    /// # Errors
+    ///
+    /// When the query could not be executed
+    pub async fn insert_deduplicated<C>(db: &C, model: ActiveModel) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Self::insert(model)
+            .on_conflict(
+                // updating the hash to itself returns the existing row
+                sea_orm::sea_query::OnConflict::column(Column::{{content_hash.hash_column | pascal_case}})
+                    .update_column(Column::{{content_hash.hash_column | pascal_case}})
+                    .to_owned(),
+            )
+            .exec_with_returning(db)
+            .await
+    }
+    {%- endif %}
+}
+{%- else -%}
+impl Entity {}
+{%- endif %}
+{%- if soft_delete | default(value=false) %}
+
+// soft deletes, see `loco_rs::model::SoftDeletable`
+impl loco_rs::model::SoftDeletable for Entity {
+    fn deleted_at() -> Self::Column {
+        super::_entities::{{plural_snake}}::Column::DeletedAt
+    }
+}
+{%- endif %}
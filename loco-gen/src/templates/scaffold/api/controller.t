@@ -1,5 +1,14 @@
 {% set file_name = name |  snake_case -%}
 {% set module_name = file_name | pascal_case -%}
+{% if tenant -%}
+{% set scope = "tenant" -%}
+{% set db = "tenant.db" -%}
+{% set extractor = "tenant: Tenant" -%}
+{% else -%}
+{% set scope = "ctx" -%}
+{% set db = "ctx.db" -%}
+{% set extractor = "State(ctx): State<AppContext>" -%}
+{% endif -%}
 to: src/controllers/{{ file_name }}.rs
 skip_exists: true
 message: "Controller `{{module_name}}` was added successfully."
@@ -10,18 +19,51 @@ injections:
 - into: src/app.rs
   after: "AppRoutes::"
   content: "            .add_route(controllers::{{ file_name }}::routes())"
+{% if api_docs -%}
+- into: src/controllers/api_docs.rs
+  before: "api-docs-inject-above"
+  content: "    spec.merge(super::{{ file_name }}::ApiDoc::openapi());"
+{% endif -%}
 ---
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::unnecessary_struct_initialization)]
 #![allow(clippy::unused_async)]
-use loco_rs::prelude::*;
+use std::str::FromStr;
+
+use loco_rs::{
+    {%- if tenant %}
+    controller::{
+        extractor::tenant::Tenant,
+        views::pagination::{Pager, PagerMeta},
+    },
+    {%- else %}
+    controller::views::pagination::{Pager, PagerMeta},
+    {%- endif %}
+    prelude::*,
+};
+use sea_orm::{Order, QueryOrder};
 use serde::{Deserialize, Serialize};
+{%- if api_docs %}
+use utoipa::{IntoParams, OpenApi, ToSchema};
+{%- endif %}
+
+use crate::models::_entities::{{file_name | plural}}::{ActiveModel, Column, Entity, Model};
+
+/// The largest page `list` returns
+const MAX_PAGE_SIZE: u64 = 100;
+{%- if api_docs %}
 
-use crate::models::_entities::{{file_name | plural}}::{ActiveModel, Entity, Model};
+#[derive(OpenApi)]
+#[openapi(paths(list, add, update, remove, get_one))]
+pub struct ApiDoc;
+{%- endif %}
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize{% if api_docs %}, ToSchema{% endif %})]
+{% if rename_all -%}
+#[serde(rename_all = "{{rename_all}}")]
+{% endif -%}
 pub struct Params {
-    {% for column in columns -%}
+    {% for column in params -%}
     {%- if column.2 == "IntegerNull" -%}
     pub {{column.0}}: Option<i32>,
     {%- else -%}
@@ -32,7 +74,7 @@ pub struct Params {
 
 impl Params {
     fn update(&self, item: &mut ActiveModel) {
-      {% for column in columns -%}
+      {% for column in params -%}
       {%- if "Vec<" in column.1 -%}
       item.{{column.0}} = Set(self.{{column.0}}.clone());
       {%- elif column.2 == "IntegerNull" -%}
@@ -46,48 +88,252 @@ impl Params {
     }
 }
 
+/// The query of `list`: a page, the column it is sorted by, descending when
+/// prefixed with `-`, and the values of the columns it is filtered on, e.g.
+/// `?page=2&page_size=50&sort=-id`.
+{%- if soft_delete %} The soft deleted items are left out unless
+/// `include_deleted` is set.
+{%- endif %}
+#[derive(Debug, Default, Deserialize{% if api_docs %}, IntoParams{% endif %})]
+{% if api_docs -%}
+#[into_params(parameter_in = Query)]
+{% endif -%}
+{% if rename_all -%}
+#[serde(rename_all = "{{rename_all}}")]
+{% endif -%}
+pub struct ListParams {
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+    pub sort: Option<String>,
+    {%- if soft_delete %}
+    pub include_deleted: Option<bool>,
+    {%- endif %}
+    {% for column in filters -%}
+    pub {{column.0}}: Option<{{column.1}}>,
+    {% endfor -%}
+}
+
+impl ListParams {
+    fn pagination(&self) -> query::PaginationQuery {
+        let default = query::PaginationQuery::default();
+        query::PaginationQuery {
+            page: self.page.unwrap_or(default.page).max(1),
+            page_size: self
+                .page_size
+                .unwrap_or(default.page_size)
+                .clamp(1, MAX_PAGE_SIZE),
+        }
+    }
+
+    fn order(&self) -> Result<(Column, Order)> {
+        let Some(sort) = self.sort.as_deref() else {
+            return Ok((Column::Id, Order::Asc));
+        };
+        let (name, order) = sort
+            .strip_prefix('-')
+            .map_or((sort, Order::Asc), |name| (name, Order::Desc));
+        {%- if hidden | length > 0 %}
+        if [{% for column in hidden %}"{{column}}"{% if not loop.last %}, {% endif %}{% endfor %}].contains(&name) {
+            return Err(Error::BadRequest(format!("cannot sort by `{name}`")));
+        }
+        {%- endif %}
+        let column = Column::from_str(name)
+            .map_err(|_| Error::BadRequest(format!("cannot sort by `{name}`")))?;
+        Ok((column, order))
+    }
+
+    fn condition(self) -> query::ConditionBuilder {
+        {%- if filters | length > 0 %}
+        let mut condition = query::condition();
+        {%- for column in filters %}
+        if let Some(value) = self.{{column.0}} {
+            condition = condition.eq(Column::{{column.0 | pascal_case}}, value);
+        }
+        {%- endfor %}
+        condition
+        {%- else %}
+        query::condition()
+        {%- endif %}
+    }
+}
+
+{% if soft_delete -%}
+{% set find_item = "Entity::find_active().filter(Column::Id.eq(id))" -%}
+{% else -%}
+{% set find_item = "Entity::find_by_id(id)" -%}
+{% endif -%}
+{% if tenant -%}
+async fn load_item(tenant: &Tenant, id: i32) -> Result<Model> {
+    let item = {{find_item}}
+        .filter(Column::{{tenant | pascal_case}}.eq(tenant.pk()?))
+        .one(&tenant.db)
+        .await?;
+    item.ok_or_else(|| Error::NotFound)
+}
+{%- else -%}
 async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
-    let item = Entity::find_by_id(id).one(&ctx.db).await?;
+    let item = {{find_item}}.one(&ctx.db).await?;
     item.ok_or_else(|| Error::NotFound)
 }
+{%- endif %}
+{%- if hidden | length > 0 %}
+
+/// The item as returned, without its hidden columns
+fn public(item: &Model) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(item)?;
+    if let Some(fields) = value.as_object_mut() {
+        {%- for column in hidden %}
+        fields.remove("{{column}}");
+        {%- endfor %}
+    }
+    Ok(value)
+}
+{%- endif %}
 
+{% if api_docs -%}
+#[utoipa::path(
+    get,
+    path = "/api/{{file_name | plural}}/",
+    params(ListParams),
+    responses((status = 200, description = "A page of {{file_name | plural}}"))
+)]
+{% endif -%}
 #[debug_handler]
-pub async fn list(State(ctx): State<AppContext>) -> Result<Response> {
-    format::json(Entity::find().all(&ctx.db).await?)
+pub async fn list(
+    {%- if tenant %}
+    tenant: Tenant,
+    {%- else %}
+    State(ctx): State<AppContext>,
+    {%- endif %}
+    Query(params): Query<ListParams>,
+) -> Result<Response> {
+    let pagination = params.pagination();
+    let (column, order) = params.order()?;
+    {%- if soft_delete %}
+    let select = if params.include_deleted.unwrap_or(false) {
+        Entity::find()
+    } else {
+        Entity::find_active()
+    };
+    {%- endif %}
+    {%- if tenant %}
+    let condition = params
+        .condition()
+        .eq(Column::{{tenant | pascal_case}}, tenant.pk()?)
+        .build();
+    {%- else %}
+    let condition = params.condition().build();
+    {%- endif %}
+    let page = query::paginate(
+        &{{db}},
+        {% if soft_delete %}select{% else %}Entity::find(){% endif %}.order_by(column, order),
+        Some(condition),
+        &pagination,
+    )
+    .await?;
+    format::json(Pager::new(
+        {%- if hidden | length > 0 %}
+        page.page.iter().map(public).collect::<Result<Vec<_>>>()?,
+        {%- else %}
+        page.page,
+        {%- endif %}
+        PagerMeta {
+            page: pagination.page,
+            page_size: pagination.page_size,
+            total_pages: page.total_pages,
+            total_items: page.total_items,
+        },
+    ))
 }
 
+{% if api_docs -%}
+#[utoipa::path(
+    post,
+    path = "/api/{{file_name | plural}}/",
+    request_body = Params,
+    responses((status = 200, description = "The added {{file_name}}"))
+)]
+{% endif -%}
 #[debug_handler]
+{% if tenant -%}
+pub async fn add(tenant: Tenant, Json(params): Json<Params>) -> Result<Response> {
+    let mut item = ActiveModel {
+        {{tenant}}: Set(tenant.pk()?),
+        ..Default::default()
+    };
+{%- else -%}
 pub async fn add(State(ctx): State<AppContext>, Json(params): Json<Params>) -> Result<Response> {
     let mut item = ActiveModel {
         ..Default::default()
     };
+{%- endif %}
     params.update(&mut item);
-    let item = item.insert(&ctx.db).await?;
-    format::json(item)
+    let item = item.insert(&{{db}}).await?;
+    format::json({% if hidden | length > 0 %}public(&item)?{% else %}item{% endif %})
 }
 
+{% if api_docs -%}
+#[utoipa::path(
+    method(put, patch),
+    path = "/api/{{file_name | plural}}/{id}",
+    params(("id" = i32, Path)),
+    request_body = Params,
+    responses(
+        (status = 200, description = "The updated {{file_name}}"),
+        (status = 404),
+    )
+)]
+{% endif -%}
 #[debug_handler]
 pub async fn update(
     Path(id): Path<i32>,
+    {%- if tenant %}
+    tenant: Tenant,
+    {%- else %}
     State(ctx): State<AppContext>,
+    {%- endif %}
     Json(params): Json<Params>,
 ) -> Result<Response> {
-    let item = load_item(&ctx, id).await?;
+    let item = load_item(&{{scope}}, id).await?;
     let mut item = item.into_active_model();
     params.update(&mut item);
-    let item = item.update(&ctx.db).await?;
-    format::json(item)
+    let item = item.update(&{{db}}).await?;
+    format::json({% if hidden | length > 0 %}public(&item)?{% else %}item{% endif %})
 }
 
+{% if api_docs -%}
+#[utoipa::path(
+    delete,
+    path = "/api/{{file_name | plural}}/{id}",
+    params(("id" = i32, Path)),
+    responses((status = 200), (status = 404))
+)]
+{% endif -%}
 #[debug_handler]
-pub async fn remove(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
-    load_item(&ctx, id).await?.delete(&ctx.db).await?;
+pub async fn remove(Path(id): Path<i32>, {{extractor}}) -> Result<Response> {
+    {%- if soft_delete %}
+    Entity::soft_delete(&{{db}}, load_item(&{{scope}}, id).await?).await?;
+    {%- else %}
+    load_item(&{{scope}}, id).await?.delete(&{{db}}).await?;
+    {%- endif %}
     format::empty()
 }
 
+{% if api_docs -%}
+#[utoipa::path(
+    get,
+    path = "/api/{{file_name | plural}}/{id}",
+    params(("id" = i32, Path)),
+    responses((status = 200, description = "The {{file_name}}"), (status = 404))
+)]
+{% endif -%}
 #[debug_handler]
-pub async fn get_one(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
-    format::json(load_item(&ctx, id).await?)
+pub async fn get_one(Path(id): Path<i32>, {{extractor}}) -> Result<Response> {
+    {%- if hidden | length > 0 %}
+    format::json(public(&load_item(&{{scope}}, id).await?)?)
+    {%- else %}
+    format::json(load_item(&{{scope}}, id).await?)
+    {%- endif %}
 }
 
 pub fn routes() -> Routes {
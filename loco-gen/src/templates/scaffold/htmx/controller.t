@@ -25,7 +25,7 @@ use crate::{
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Params {
-    {% for column in columns -%}
+    {% for column in params -%}
     {%- if column.2 == "IntegerNull" -%}
     pub {{column.0}}: Option<i32>,
     {%- else -%}
@@ -36,7 +36,7 @@ pub struct Params {
 
 impl Params {
     fn update(&self, item: &mut ActiveModel) {
-      {% for column in columns -%}
+      {% for column in params -%}
       {%- if "Vec<" in column.1 -%}
       item.{{column.0}} = Set(self.{{column.0}}.clone());
       {%- elif column.2 == "IntegerNull" -%}
@@ -50,8 +50,23 @@ impl Params {
     }
 }
 
+{% if soft_delete -%}
+/// The query of `list`, whose soft deleted items are left out unless
+/// `include_deleted` is set
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+    pub include_deleted: Option<bool>,
+}
+
+async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
+    let item = Entity::find_active()
+        .filter(Column::Id.eq(id))
+        .one(&ctx.db)
+        .await?;
+{%- else -%}
 async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
     let item = Entity::find_by_id(id).one(&ctx.db).await?;
+{%- endif %}
     item.ok_or_else(|| Error::NotFound)
 }
 
@@ -59,8 +74,19 @@ async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
 pub async fn list(
     ViewEngine(v): ViewEngine<TeraView>,
     State(ctx): State<AppContext>,
+    {%- if soft_delete %}
+    Query(params): Query<ListParams>,
+) -> Result<Response> {
+    let select = if params.include_deleted.unwrap_or(false) {
+        Entity::find()
+    } else {
+        Entity::find_active()
+    };
+    let item = select
+    {%- else %}
 ) -> Result<Response> {
     let item = Entity::find()
+    {%- endif %}
         .order_by(Column::Id, Order::Desc)
         .all(&ctx.db)
         .await?;
@@ -123,7 +149,11 @@ pub async fn add(
 
 #[debug_handler]
 pub async fn remove(Path(id): Path<i32>, State(ctx): State<AppContext>) -> Result<Response> {
+    {%- if soft_delete %}
+    Entity::soft_delete(&ctx.db, load_item(&ctx, id).await?).await?;
+    {%- else %}
     load_item(&ctx, id).await?.delete(&ctx.db).await?;
+    {%- endif %}
     format::empty()
 }
 
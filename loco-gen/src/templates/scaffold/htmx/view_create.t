@@ -18,7 +18,7 @@ Create new {{name}}
 <div class="mb-10">
     <div id="error-message" class="mt-4 text-sm text-red-600"></div>
     <form hx-post="/{{name | plural}}" hx-ext="submitjson" class="flex-1 lg:max-w-2xl">
-        {% for column in columns -%}
+        {% for column in params -%}
             {{ render_form_field(fname=column.0, rust_type=column.1, ftype=column.2)}}
         {% endfor -%}
         <div class="mt-5">
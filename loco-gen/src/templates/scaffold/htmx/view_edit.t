@@ -18,7 +18,7 @@ Edit {{name}}: {% raw %}{{ item.id }}{% endraw %}
 <div class="mb-10">
     <div id="error-message" class="mt-4 text-sm text-red-600"></div>
     <form hx-put="/{{name | plural}}/{% raw %}{{ item.id }}{% endraw %}" hx-ext="submitjson" hx-target="#success-message" class="flex-1 lg:max-w-2xl">
-        {% for column in columns -%}
+        {% for column in params -%}
             {{ render_form_field(fname=column.0, rust_type=column.1, ftype=column.2, edit_form=true)}}
         {% endfor -%}
         <div>
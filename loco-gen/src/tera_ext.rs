@@ -170,7 +170,10 @@ impl tera::Function for FormField {
     <p id=":rh:-form-item-description" class="text-[0.8rem] text-muted-foreground">e.g: 123,123,123 .</p>"#,
                 )
             }
-            "Vec<String>" | "Option<Vec<String>>" => {
+            "Vec<String>"
+            | "Option<Vec<String>>"
+            | "Option<Vec<Option<String>>>"
+            | "Vec<Option<String>>" => {
                 format!(
                     r#"<button type="button" class="text-xs py-1 px-3 rounded-lg bg-gray-900 text-white add-more" data-group="{fname}">Add More</button>
     <div id="{fname}-inputs" class="space-y-2">
@@ -184,7 +187,7 @@ impl tera::Function for FormField {
     </div>"#
                 )
             }
-            "Vec<f32>" | "Option<Vec<f32>>" => {
+            "Vec<f32>" | "Option<Vec<f32>>" | "Option<Vec<Option<f32>>>" | "Vec<Option<f32>>" => {
                 let edit_input = input_number(
                     fname,
                     "{{val}}",
@@ -203,7 +206,7 @@ impl tera::Function for FormField {
                 );
                 input_group(fname, &create_input, &edit_input)
             }
-            "Vec<f64>" | "Option<Vec<f64>>" => {
+            "Vec<f64>" | "Option<Vec<f64>>" | "Option<Vec<Option<f64>>>" | "Vec<Option<f64>>" => {
                 let edit_input = input_number(
                     fname,
                     "{{val}}",
@@ -222,7 +225,7 @@ impl tera::Function for FormField {
                 );
                 input_group(fname, &create_input, &edit_input)
             }
-            "Vec<i32>" | "Option<Vec<i32>>" => {
+            "Vec<i32>" | "Option<Vec<i32>>" | "Option<Vec<Option<i32>>>" | "Vec<Option<i32>>" => {
                 let edit_input = input_number(
                     fname,
                     "{{val}}",
@@ -241,7 +244,7 @@ impl tera::Function for FormField {
                 );
                 input_group(fname, &create_input, &edit_input)
             }
-            "Vec<i64>" | "Option<Vec<i64>>" => {
+            "Vec<i64>" | "Option<Vec<i64>>" | "Option<Vec<Option<i64>>>" | "Vec<Option<i64>>" => {
                 let edit_input = input_number(
                     fname,
                     "{{val}}",
@@ -260,7 +263,10 @@ impl tera::Function for FormField {
                 );
                 input_group(fname, &create_input, &edit_input)
             }
-            "Vec<bool>" | "Option<Vec<bool>>" => String::new(),
+            "Vec<bool>"
+            | "Option<Vec<bool>>"
+            | "Option<Vec<Option<bool>>>"
+            | "Vec<Option<bool>>" => String::new(),
             _ => {
                 return Err(tera::Error::msg(format!(
                     "rust_type: `{rust_type}` not implemented"
@@ -0,0 +1,357 @@
+//! Reverting a generation.
+//!
+//! Every template rendered by [`crate::render_template`] is recorded as an
+//! [`Artifact`]: the file it wrote and the content it injected into other
+//! files. [`undo`] walks these artifacts backwards, deleting the written files
+//! and taking the injected content back out.
+//!
+//! The artifacts of a generation can also be kept in the app's manifest, see
+//! [`record`], for [`destroy`] to revert it later on.
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cruet::string::pluralize;
+use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase, ToTitleCase, ToUpperCamelCase};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{tera_ext, Error, GenerateResults, Result};
+
+/// A file written by a template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// Path of the written file, as given in the template's `to:`
+    pub path: PathBuf,
+    /// Content written to `path`
+    pub content: String,
+    /// Content injected into other files. Injections skipped because their
+    /// `skip_if` matched are left out, as are the ones injecting nothing
+    pub injections: Vec<Injection>,
+}
+
+/// Where the generations of an app are recorded, relative to its root.
+pub const MANIFEST_PATH: &str = ".loco/generated.json";
+
+/// A generation recorded in the manifest, see [`record`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Generation {
+    /// What was generated, e.g. `scaffold post`, see
+    /// [`crate::Component::label`]
+    pub label: String,
+    pub artifacts: Vec<Artifact>,
+}
+
+impl Generation {
+    /// The migrations the generation wrote, in the order it wrote them
+    #[must_use]
+    pub fn migrations(&self) -> Vec<&Path> {
+        self.artifacts
+            .iter()
+            .map(|artifact| artifact.path.as_path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| crate::migration_file_regex().is_match(name))
+            })
+            .collect()
+    }
+}
+
+/// Content injected into an existing file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Injection {
+    pub into: PathBuf,
+    pub content: String,
+}
+
+#[derive(Deserialize)]
+struct FrontMatter {
+    to: String,
+    #[serde(default)]
+    injections: Vec<InjectionFrontMatter>,
+}
+
+#[derive(Deserialize)]
+struct InjectionFrontMatter {
+    into: String,
+    content: String,
+    #[serde(default)]
+    skip_if: Option<String>,
+}
+
+/// Renders `template` the same way `rrgen` does and describes what it writes,
+/// before `rrgen` writes it: an injection whose `skip_if` matches the file it
+/// goes into, as it is then, is skipped by `rrgen` and left out. Like the
+/// `skip_glob` of `rrgen`, the files are looked up from the current
+/// directory.
+pub(crate) fn describe(template: &str, vars: &Value) -> Result<Artifact> {
+    let mut tera = tera_ext::new();
+    register_filters(&mut tera);
+
+    let context = tera::Context::from_serialize(vars).map_err(Error::msg)?;
+    let rendered = tera
+        .render_str(&template.replace("\r\n", "\n"), &context)
+        .map_err(Error::msg)?;
+    let (frontmatter, body) = rendered.split_once("---\n").ok_or_else(|| {
+        Error::Message("cannot split document to frontmatter and body".to_string())
+    })?;
+    let frontmatter: FrontMatter = serde_yaml::from_str(frontmatter).map_err(Error::msg)?;
+
+    // the content each injection sees, with the ones before it in place
+    let mut files: HashMap<PathBuf, String> = HashMap::new();
+    let mut injections = vec![];
+    for injection in frontmatter.injections {
+        let into = PathBuf::from(injection.into);
+        if !files.contains_key(&into) && into.exists() {
+            files.insert(into.clone(), fs::read_to_string(&into)?);
+        }
+        if let (Some(skip_if), Some(file)) = (&injection.skip_if, files.get(&into)) {
+            let skip_if = regex::Regex::new(skip_if).map_err(Error::msg)?;
+            if skip_if.is_match(file) {
+                continue;
+            }
+        }
+        if injection.content.is_empty() {
+            continue;
+        }
+        if let Some(file) = files.get_mut(&into) {
+            file.push('\n');
+            file.push_str(&injection.content);
+        }
+        injections.push(Injection {
+            into,
+            content: injection.content,
+        });
+    }
+
+    Ok(Artifact {
+        path: PathBuf::from(frontmatter.to),
+        content: body.to_string(),
+        injections,
+    })
+}
+
+/// Reverts a generation relative to the current directory.
+///
+/// # Errors
+///
+/// When a generated or injected file could not be read or written
+pub fn undo(results: &GenerateResults) -> Result<()> {
+    undo_in(Path::new("."), results)
+}
+
+/// Reverts a generation whose paths are relative to `root`.
+///
+/// Files created by the generation are deleted, unless they were edited
+/// afterwards. Files the generation injected into are kept, with the injected
+/// content taken out.
+///
+/// # Errors
+///
+/// When a generated or injected file could not be read or written
+pub fn undo_in(root: &Path, results: &GenerateResults) -> Result<()> {
+    undo_artifacts(root, &results.artifacts)
+}
+
+fn undo_artifacts(root: &Path, artifacts: &[Artifact]) -> Result<()> {
+    for artifact in artifacts.iter().rev() {
+        for injection in artifact.injections.iter().rev() {
+            let path = root.join(&injection.into);
+            if !path.exists() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)?;
+            if let Some(reverted) = remove_injected(&content, &injection.content) {
+                fs::write(&path, reverted)?;
+            }
+        }
+
+        let path = root.join(&artifact.path);
+        if !path.exists() {
+            continue;
+        }
+        if fs::read_to_string(&path)? == artifact.content {
+            fs::remove_file(&path)?;
+        } else {
+            tracing::warn!(
+                file = %path.display(),
+                "file was changed after it was generated, leaving it in place"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Records a generation in the manifest of the app in the current directory,
+/// under `label`, e.g. `scaffold post`.
+///
+/// # Errors
+///
+/// When the manifest could not be read or written
+pub fn record(label: &str, results: &GenerateResults) -> Result<()> {
+    record_in(Path::new("."), label, results)
+}
+
+/// Records a generation in the manifest of the app in `root`, see [`record`].
+///
+/// # Errors
+///
+/// When the manifest could not be read or written
+pub fn record_in(root: &Path, label: &str, results: &GenerateResults) -> Result<()> {
+    let mut generations = read_manifest(root)?;
+    generations.push(Generation {
+        label: label.to_string(),
+        artifacts: results.artifacts.clone(),
+    });
+    write_manifest(root, &generations)
+}
+
+/// The latest generation recorded under `label` in the manifest of the app in
+/// the current directory, e.g. to roll back its migrations before
+/// [`destroy`] reverts it.
+///
+/// # Errors
+///
+/// When the manifest could not be read, or has no generation of `label`
+pub fn recorded(label: &str) -> Result<Generation> {
+    recorded_in(Path::new("."), label)
+}
+
+/// The latest generation recorded under `label` in the manifest of the app in
+/// `root`.
+///
+/// # Errors
+///
+/// When the manifest could not be read, or has no generation of `label`
+pub fn recorded_in(root: &Path, label: &str) -> Result<Generation> {
+    read_manifest(root)?
+        .into_iter()
+        .rev()
+        .find(|generation| generation.label == label)
+        .ok_or_else(|| {
+            Error::Message(format!(
+                "no generation of `{label}` is recorded in `{MANIFEST_PATH}`"
+            ))
+        })
+}
+
+/// Reverts the latest generation recorded under `label`, e.g. `scaffold post`,
+/// in the app in the current directory, and takes it out of the manifest.
+///
+/// # Errors
+///
+/// When the manifest has no generation of `label`, or a generated or injected
+/// file could not be read or written
+pub fn destroy(label: &str) -> Result<Generation> {
+    destroy_in(Path::new("."), label)
+}
+
+/// Reverts a recorded generation of the app in `root`, see [`destroy`].
+///
+/// # Errors
+///
+/// When the manifest has no generation of `label`, or a generated or injected
+/// file could not be read or written
+pub fn destroy_in(root: &Path, label: &str) -> Result<Generation> {
+    let mut generations = read_manifest(root)?;
+    let index = generations
+        .iter()
+        .rposition(|generation| generation.label == label)
+        .ok_or_else(|| {
+            Error::Message(format!(
+                "no generation of `{label}` is recorded in `{MANIFEST_PATH}`"
+            ))
+        })?;
+    let generation = generations.remove(index);
+    undo_artifacts(root, &generation.artifacts)?;
+    write_manifest(root, &generations)?;
+    Ok(generation)
+}
+
+fn read_manifest(root: &Path) -> Result<Vec<Generation>> {
+    let path = root.join(MANIFEST_PATH);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|err| Error::Message(format!("cannot parse `{}`: {err}", path.display())))
+}
+
+fn write_manifest(root: &Path, generations: &[Generation]) -> Result<()> {
+    let path = root.join(MANIFEST_PATH);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(generations).map_err(Error::msg)?,
+    )?;
+    Ok(())
+}
+
+/// Takes one occurrence of `injected` out of `content`, along with the line
+/// break the injection added.
+fn remove_injected(content: &str, injected: &str) -> Option<String> {
+    [
+        format!("\n{injected}"),
+        format!("{injected}\n"),
+        injected.to_string(),
+    ]
+    .iter()
+    .find_map(|needle| {
+        content
+            .find(needle.as_str())
+            .map(|pos| format!("{}{}", &content[..pos], &content[pos + needle.len()..]))
+    })
+}
+
+/// The filters `rrgen` registers on its template engine.
+fn register_filters(tera: &mut tera::Tera) {
+    fn filter(
+        name: &'static str,
+        f: fn(&str) -> String,
+    ) -> impl Fn(&Value, &HashMap<String, Value>) -> tera::Result<Value> {
+        move |value, _| {
+            let s = value
+                .as_str()
+                .ok_or_else(|| tera::Error::msg(format!("filter `{name}` expects a string")))?;
+            Ok(Value::String(f(s)))
+        }
+    }
+
+    tera.register_filter(
+        "pascal_case",
+        filter("pascal_case", |s| s.to_upper_camel_case()),
+    );
+    tera.register_filter(
+        "camel_case",
+        filter("camel_case", |s| s.to_lower_camel_case()),
+    );
+    tera.register_filter("kebab_case", filter("kebab_case", |s| s.to_kebab_case()));
+    tera.register_filter("lower_case", filter("lower_case", str::to_lowercase));
+    tera.register_filter("snake_case", filter("snake_case", |s| s.to_snake_case()));
+    tera.register_filter("title_case", filter("title_case", |s| s.to_title_case()));
+    tera.register_filter("upper_case", filter("upper_case", str::to_uppercase));
+    tera.register_filter("plural", filter("plural", pluralize::to_plural));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_remove_injected_content() {
+        assert_eq!(
+            remove_injected("pub mod users;\npub mod movies;", "pub mod movies;"),
+            Some("pub mod users;".to_string())
+        );
+        assert_eq!(
+            remove_injected("mod movies;\npub struct Migrator;", "mod movies;"),
+            Some("pub struct Migrator;".to_string())
+        );
+        assert_eq!(remove_injected("pub mod users;", "pub mod movies;"), None);
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use chrono::Utc;
+use cruet::Inflector;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    get_mappings,
+    model::{self, get_columns_and_references, resolve_indexes},
+    render_template, AppInfo, DbBackend, Error, GenerateOptions, GenerateResults, IndexSpec,
+    Result, TemplateRenderer,
+};
+
+/// A column of the view's entity.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ViewColumn {
+    pub name: String,
+    /// The column the field maps to, when it is renamed, see
+    /// [`GenerateOptions::column_renames`]
+    pub column_name: Option<String>,
+    pub rust_type: String,
+    pub primary_key: bool,
+}
+
+/// Generates a materialized view over the rows `sql_body` selects: its
+/// migration, and the model file holding its read-only entity with a
+/// `refresh` helper. `fields` declare the columns the query selects, the
+/// entity cannot be generated by `db entities` as it only reads tables.
+///
+/// # Errors
+///
+/// When the target backend is not postgres, a field is not a value column,
+/// or the view is refreshed concurrently without a unique index over all its
+/// rows
+pub fn generate(
+    renderer: &dyn TemplateRenderer,
+    name: &str,
+    sql_body: &str,
+    fields: &[(String, String)],
+    options: &GenerateOptions,
+    appinfo: &AppInfo,
+) -> Result<GenerateResults> {
+    if let Some(backend) = options.backend.filter(|b| *b != DbBackend::Postgres) {
+        return Err(Error::Message(format!(
+            "materialized views are only supported on postgres, but the target backend is \
+             {backend}"
+        )));
+    }
+    model::validate_model_name(name)?;
+    let view = name.to_plural().to_snake_case();
+    if sql_body.trim().is_empty() {
+        return Err(Error::Message(format!(
+            "materialized view `{view}` needs a query selecting its rows"
+        )));
+    }
+    let (columns, references) = get_columns_and_references(fields, options)?;
+    if let Some((fname, _)) = references.first() {
+        return Err(Error::Message(format!(
+            "field `{}` is a reference, a materialized view only has value columns",
+            fname.trim_end_matches('?')
+        )));
+    }
+    if columns.is_empty() {
+        return Err(Error::Message(format!(
+            "materialized view `{view}` needs the columns its query selects as fields"
+        )));
+    }
+    let indexes = resolve_indexes(&options.indexes, &columns, &[], false, None)?;
+    if let Some(index) = indexes.iter().find(|index| index.deferrable) {
+        return Err(Error::Message(format!(
+            "index over `{}` is deferrable, which a materialized view cannot have",
+            index.columns.join(",")
+        )));
+    }
+    let unique = indexes.iter().find(|index| refreshable_index(index));
+    if options.refresh_concurrently && unique.is_none() {
+        return Err(Error::Message(format!(
+            "refreshing `{view}` concurrently needs a unique index over plain columns of all its \
+             rows"
+        )));
+    }
+
+    check_column_renames(&options.column_renames, fields)?;
+
+    // the entity's primary key is what tells the rows apart, as far as the
+    // indexes tell
+    let primary_key = unique.map_or_else(|| vec![columns[0].0.clone()], |i| i.columns.clone());
+    let mappings = get_mappings();
+    let mut entity_columns = Vec::new();
+    for (fname, ftype) in fields {
+        if options.ignores_field(fname) {
+            continue;
+        }
+        let rust_type = match crate::infer::parse_field_type(ftype)? {
+            crate::infer::FieldType::Type(ftype)
+            | crate::infer::FieldType::TypeWithDefault(ftype, _) => mappings.rust_field(&ftype)?,
+            crate::infer::FieldType::TypeWithParameters(ftype, params) => {
+                mappings.rust_field_with_params(&ftype, &params)?
+            }
+            crate::infer::FieldType::Enum(_) => mappings.rust_field("enum!")?,
+            crate::infer::FieldType::NullableEnum(_) => mappings.rust_field("enum")?,
+            // references are rejected above
+            _ => continue,
+        };
+        let renamed = options.column_renames.get(fname);
+        entity_columns.push(ViewColumn {
+            name: renamed.unwrap_or(fname).clone(),
+            column_name: renamed.map(|_| fname.clone()),
+            rust_type: rust_type.to_string(),
+            primary_key: primary_key.contains(fname),
+        });
+    }
+
+    let vars = json!({"name": name, "ts": Utc::now(), "pkg_name": appinfo.app_name, "query": sql_body.trim().trim_end_matches(';'), "columns": entity_columns, "indexes": indexes, "refresh_concurrently": options.refresh_concurrently, "migration_dir": options.migration_dir(), "metadata": options.migration_metadata, "if_exists": options.drop_if_exists});
+    render_template(renderer, Path::new("materialized_view"), &vars)
+}
+
+/// Checks the renamed columns are fields, renamed to new snake case field
+/// names.
+fn check_column_renames(
+    renames: &std::collections::HashMap<String, String>,
+    fields: &[(String, String)],
+) -> Result<()> {
+    let mut renames = renames.iter().collect::<Vec<_>>();
+    renames.sort();
+    for (column, field) in renames {
+        if !fields.iter().any(|(fname, _)| fname == column) {
+            return Err(Error::Message(format!(
+                "renamed column `{column}` is not a column of the model"
+            )));
+        }
+        if field.is_empty() || *field != field.to_snake_case() {
+            return Err(Error::Message(format!(
+                "column `{column}` is renamed to `{field}`, which is not a snake case field name"
+            )));
+        }
+        if fields.iter().any(|(fname, _)| fname == field) {
+            return Err(Error::Message(format!(
+                "column `{column}` is renamed to `{field}`, which is already a column of the model"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `REFRESH MATERIALIZED VIEW CONCURRENTLY` can tell the rows apart
+/// by the index: it has to be unique over plain columns, and not partial.
+fn refreshable_index(index: &IndexSpec) -> bool {
+    index.unique
+        && !index.not_null
+        && index.when.is_none()
+        && index.when_null.is_none()
+        && !index.columns.iter().any(|col| col.contains('('))
+}
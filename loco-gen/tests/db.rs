@@ -1,6 +1,6 @@
 use duct::cmd;
 use insta::assert_snapshot;
-use loco_gen::get_mappings;
+use loco_gen::{get_mappings, DbBackend};
 use rstest::rstest;
 use serial_test::serial;
 use std::{collections::HashMap, env::current_dir, fs::read_to_string};
@@ -58,6 +58,8 @@ fn test_migrations_flow(#[values("postgres", "sqlite")] db_kind: &str) {
         .iter()
         // only take non-argument types because its easy
         .filter(|n| mappings.col_type_arity(n).unwrap_or_default() == 0)
+        // skip types the backend can't create, e.g. postgres network types
+        .filter(|n| db_kind == "postgres" || mappings.check_backend(n, DbBackend::Sqlite).is_ok())
         .map(|t| format!("{}:{t}", t.replace('!', "_nonull").replace('^', "_uniq")))
         .collect::<Vec<_>>();
 
@@ -0,0 +1,175 @@
+use super::utils::APP_TASK;
+use loco_gen::{collect_messages, destroy_in, generate, record_in, AppInfo, Component};
+use rrgen::RRgen;
+use std::fs;
+
+#[test]
+fn can_generate() {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("src/lib.rs", "pub mod app;\n")
+        .add(
+            "src/app.rs",
+            &format!(
+                "{APP_TASK}
+    fn routes(_ctx: &AppContext) -> AppRoutes {{
+        AppRoutes::with_default_routes() // controller routes below
+    }}
+}}
+"
+            ),
+        )
+        .add(
+            "Cargo.toml",
+            r#"[dependencies]
+axum = { version = "0.8" }
+
+[dev-dependencies]
+serial_test = { version = "3.1.1" }
+"#,
+        )
+        .add("tests/mod.rs", "mod requests;\n")
+        .create()
+        .expect("Failed to create tree_fs structure");
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let gen_result = generate(
+        &rrgen,
+        Component::Channel {
+            name: "job_progress".to_string(),
+        },
+        &appinfo,
+    )
+    .expect("Failed to generate components");
+    assert!(
+        collect_messages(&gen_result).contains("* Channel `job_progress` was added successfully.")
+    );
+
+    // a second channel reuses the channels module
+    generate(
+        &rrgen,
+        Component::Channel {
+            name: "notifications".to_string(),
+        },
+        &appinfo,
+    )
+    .expect("Failed to generate components");
+
+    let read = |path: &str| {
+        fs::read_to_string(tree_fs.root.join(path)).unwrap_or_else(|_| panic!("{path} missing"))
+    };
+    let channel = read("src/channels/job_progress.rs");
+    syn::parse_file(&channel).expect("the channel should be valid Rust");
+    assert!(channel.contains("pub const NAME: &str = \"job_progress\";"));
+    assert!(channel.contains("pub async fn subscribe("));
+    let test = read("tests/channels/job_progress.rs");
+    syn::parse_file(&test).expect("the channel test should be valid Rust");
+    assert!(test.contains("async fn can_subscribe_to_job_progress_with_websocket()"));
+
+    // the WebSocket handlers need axum's `ws` feature, and their tests a client
+    let cargo_toml = read("Cargo.toml");
+    assert_eq!(
+        cargo_toml.matches("axum = ").count(),
+        1,
+        "axum should be declared once: {cargo_toml}"
+    );
+    assert!(cargo_toml.contains(r#"axum = { version = "0.8", features = ["ws"] }"#));
+    assert_eq!(cargo_toml.matches("tokio-tungstenite = ").count(), 1);
+    assert_eq!(cargo_toml.matches("futures-util = ").count(), 1);
+
+    assert_eq!(read("src/lib.rs").matches("pub mod channels;").count(), 1);
+    assert_eq!(read("tests/mod.rs").matches("mod channels;").count(), 1);
+    let channels_mod = read("src/channels/mod.rs");
+    assert!(channels_mod.contains("pub mod job_progress;"));
+    assert!(channels_mod.contains("pub mod notifications;"));
+    let tests_mod = read("tests/channels/mod.rs");
+    assert!(tests_mod.contains("pub mod job_progress;"));
+    assert!(tests_mod.contains("pub mod notifications;"));
+
+    let app = read("src/app.rs");
+    assert_eq!(app.matches("fn register_channels").count(), 1);
+    assert!(app.contains(
+        "        AppRoutes::with_default_routes() // controller routes below
+            .add_route(crate::channels::notifications::routes())
+            .add_route(crate::channels::job_progress::routes())"
+    ));
+    assert!(app.contains(
+        "    fn register_channels(channels: &mut loco_rs::channels::Channels) {
+        channels.register(crate::channels::job_progress::NAME);
+        channels.register(crate::channels::notifications::NAME);
+        // channels-inject (do not remove)
+    }
+
+    #[allow(unused_variables)]
+    fn register_tasks(tasks: &mut Tasks) {"
+    ));
+}
+
+#[test]
+fn can_destroy_channel_keeping_shared_injections() {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("src/lib.rs", "pub mod app;\n")
+        .add(
+            "src/app.rs",
+            &format!(
+                "{APP_TASK}
+    fn routes(_ctx: &AppContext) -> AppRoutes {{
+        AppRoutes::with_default_routes() // controller routes below
+    }}
+}}
+"
+            ),
+        )
+        .add(
+            "Cargo.toml",
+            r#"[dependencies]
+axum = { version = "0.8" }
+
+[dev-dependencies]
+serial_test = { version = "3.1.1" }
+"#,
+        )
+        .add("tests/mod.rs", "mod requests;\n")
+        .create()
+        .expect("Failed to create tree_fs structure");
+
+    // `skip_if` is checked from the current directory, as `rrgen` does
+    let previous = std::env::current_dir().unwrap();
+    std::env::set_current_dir(&tree_fs.root).unwrap();
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let res = ["job_progress", "notifications"].map(|name| {
+        let component = Component::Channel {
+            name: name.to_string(),
+        };
+        let label = component.label();
+        generate(&rrgen, component, &appinfo)
+            .and_then(|gen_result| record_in(&tree_fs.root, &label, &gen_result))
+    });
+    std::env::set_current_dir(previous).unwrap();
+    for res in res {
+        res.expect("Failed to generate components");
+    }
+
+    destroy_in(&tree_fs.root, "channel notifications").expect("Destroy failed");
+
+    // the dependencies the first channel injected are still needed by it
+    let cargo_toml = fs::read_to_string(tree_fs.root.join("Cargo.toml")).unwrap();
+    assert!(
+        cargo_toml.contains(r#"axum = { version = "0.8", features = ["ws"] }"#),
+        "{cargo_toml}"
+    );
+    assert_eq!(cargo_toml.matches("tokio-tungstenite = ").count(), 1);
+    assert_eq!(cargo_toml.matches("futures-util = ").count(), 1);
+    let channels_mod = fs::read_to_string(tree_fs.root.join("src/channels/mod.rs")).unwrap();
+    assert!(channels_mod.contains("pub mod job_progress;"));
+    assert!(!channels_mod.contains("pub mod notifications;"));
+    assert!(!tree_fs.root.join("src/channels/notifications.rs").exists());
+    assert!(tree_fs.root.join("src/channels/job_progress.rs").exists());
+}
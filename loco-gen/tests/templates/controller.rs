@@ -1,6 +1,9 @@
 use super::utils::APP_ROUTS;
 use insta::assert_snapshot;
-use loco_gen::{collect_messages, generate, AppInfo, Component, ScaffoldKind};
+use loco_gen::{
+    collect_messages, generate, generate_with_options, AppInfo, Component, GenerateOptions,
+    ScaffoldKind,
+};
 use rrgen::RRgen;
 use rstest::rstest;
 use std::fs;
@@ -86,3 +89,50 @@ fn can_generate(#[case] kind: ScaffoldKind) {
         }
     }
 }
+
+#[test]
+fn can_generate_api_docs() {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add_empty("src/controllers/mod.rs")
+        .add_empty("tests/requests/mod.rs")
+        .add("src/app.rs", APP_ROUTS)
+        .add(
+            "Cargo.toml",
+            "[package]\nname = \"tester\"\n\n[dependencies]\n",
+        )
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Controller {
+        name: "movie".to_string(),
+        actions: vec!["search".to_string()],
+        kind: ScaffoldKind::Api,
+    };
+    let options = GenerateOptions {
+        api_docs: true,
+        ..Default::default()
+    };
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let controllers_path = tree_fs.root.join("src").join("controllers");
+    let controller =
+        fs::read_to_string(controllers_path.join("movie.rs")).expect("controller file missing");
+    syn::parse_file(&controller).expect("the controller should be valid Rust");
+    assert!(controller.contains("#[openapi(paths(index, search))]"));
+    assert!(controller.contains(
+        "#[utoipa::path(get, path = \"/api/movies/search\", responses((status = 200)))]"
+    ));
+    let api_docs =
+        fs::read_to_string(controllers_path.join("api_docs.rs")).expect("api docs missing");
+    assert!(api_docs.contains("    spec.merge(super::movie::ApiDoc::openapi());"));
+}
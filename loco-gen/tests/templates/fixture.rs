@@ -0,0 +1,66 @@
+use insta::assert_snapshot;
+use loco_gen::{collect_messages, entity_columns, generate, AppInfo, Component};
+use rrgen::RRgen;
+use std::fs;
+
+macro_rules! configure_insta {
+    () => {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_prepend_module_to_snapshot(false);
+        settings.set_snapshot_suffix("fixture");
+        let _guard = settings.bind_to_scope();
+    };
+}
+
+const ENTITY: &str = r"
+pub struct Model {
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub pid: Uuid,
+    pub title: String,
+    pub views: i32,
+    pub published: bool,
+    pub published_at: Option<DateTimeWithTimeZone>,
+    pub meta: Json,
+    pub user_id: i32,
+}
+";
+
+#[test]
+fn can_generate() {
+    configure_insta!();
+
+    let component = Component::Fixture {
+        name: "post".to_string(),
+        columns: entity_columns(ENTITY),
+    };
+
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .create()
+        .expect("Failed to create tree_fs structure");
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+
+    let gen_result = generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Failed to generate components");
+
+    assert_eq!(
+        collect_messages(&gen_result),
+        "* Fixtures `src/fixtures/posts.yaml` added. Load them with `cargo loco db seed`.\n"
+    );
+
+    assert_snapshot!(
+        "generate[fixture_file]",
+        fs::read_to_string(tree_fs.root.join("src/fixtures/posts.yaml"))
+            .expect("Failed to read generated fixture file: posts.yaml")
+    );
+}
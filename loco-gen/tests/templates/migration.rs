@@ -1,9 +1,13 @@
 use super::utils::{guess_file_by_time, MIGRATION_SRC_LIB};
 use insta::{assert_snapshot, with_settings};
-use loco_gen::{collect_messages, generate, AppInfo, Component};
+use loco_gen::{
+    build_migration, check_drift, collect_messages, generate, generate_with_options,
+    render_migration, AppInfo, Component, Diff, GenerateOptions, IndexSpec, MigrationMetadata,
+    MigrationStatement,
+};
 use rrgen::RRgen;
 use rstest::rstest;
-use std::fs;
+use std::{fs, path::PathBuf};
 
 #[rstest]
 #[case("create_table", Component::Migration {
@@ -189,3 +193,413 @@ fn fail_when_migration_lib_not_exists(#[case] component: Component) {
         "cannot inject into migration/src/lib.rs: file does not exist"
     );
 }
+
+#[test]
+fn can_generate_into_migration_dir() {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("db/migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let options = GenerateOptions {
+        migration_dir: Some(PathBuf::from("db").join("migration")),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        Component::Migration {
+            name: "CreateMovies".to_string(),
+            with_tz: true,
+            fields: vec![("title".to_string(), "string".to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_path = tree_fs.root.join("db").join("migration").join("src");
+    guess_file_by_time(&migration_path, "m{TIME}_movies.rs", 3)
+        .expect("Failed to find the generated migration file");
+    assert!(fs::read_to_string(migration_path.join("lib.rs"))
+        .expect("Failed to read lib.rs")
+        .contains("Box::new(m"));
+    assert!(!tree_fs.root.join("migration").exists());
+}
+
+#[rstest]
+#[case(false, "add_reference(m, \"posts\", \"user\", \"\").await?;")]
+#[case(true, "add_foreign_key(m, \"posts\", \"user\", \"\").await?;")]
+fn can_generate_reference_for_existing_column(#[case] column_exists: bool, #[case] expected: &str) {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let options = GenerateOptions {
+        column_exists,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        Component::Migration {
+            name: "AddUserRefToPosts".to_string(),
+            with_tz: true,
+            fields: vec![("user".to_string(), "references".to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration").join("src"),
+        "m{TIME}_add_user_ref_to_posts.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(expected));
+    assert_eq!(migration.contains("add_reference("), !column_exists);
+}
+
+#[test]
+fn can_check_drift() {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let fields = vec![
+        ("title".to_string(), "string".to_string()),
+        ("notes".to_string(), "text".to_string()),
+        ("user".to_string(), "references".to_string()),
+    ];
+    generate(
+        &rrgen,
+        Component::Migration {
+            name: "CreateMovies".to_string(),
+            with_tz: true,
+            fields: fields.clone(),
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration").join("src"),
+        "m{TIME}_movies.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+
+    assert_eq!(
+        check_drift("movies", &fields, &migration_file).expect("Failed to check drift"),
+        vec![]
+    );
+
+    let drifted = vec![
+        ("title".to_string(), "string!".to_string()),
+        ("user".to_string(), "references".to_string()),
+        ("rating".to_string(), "int".to_string()),
+    ];
+    let diffs = check_drift("movies", &drifted, &migration_file).expect("Failed to check drift");
+    assert_eq!(
+        diffs,
+        vec![
+            Diff::Changed {
+                column: "title".to_string(),
+                migration: "StringNull".to_string(),
+                fields: "String".to_string(),
+            },
+            Diff::Removed {
+                column: "notes".to_string(),
+                col_type: "TextNull".to_string(),
+            },
+            Diff::Added {
+                column: "rating".to_string(),
+                col_type: "IntegerNull".to_string(),
+            },
+        ]
+    );
+    assert_eq!(diffs[0].to_string(), "~ title: StringNull -> String");
+
+    let err = check_drift("shows", &fields, &migration_file).expect_err("Expected Err");
+    assert!(err.to_string().ends_with("does not create `shows`"));
+}
+
+#[rstest]
+#[case("CreateMovies", "movies.rs")]
+#[case("AddRatingToMovies", "add_rating_to_movies.rs")]
+fn can_generate_with_metadata(#[case] name: &str, #[case] file: &str) {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let options = GenerateOptions {
+        migration_metadata: MigrationMetadata {
+            author: Some("Jane Doe".to_string()),
+            ticket: Some("PROJ-123".to_string()),
+        },
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        Component::Migration {
+            name: name.to_string(),
+            with_tz: true,
+            fields: vec![("rating".to_string(), "int".to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration").join("src"),
+        &format!("m{{TIME}}_{file}"),
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration
+        .contains("/// Author: Jane Doe\n/// Ticket: PROJ-123\n#[derive(DeriveMigrationName)]"));
+}
+
+#[rstest]
+#[case("CreateMovies", vec![("title", "string")], "movies.rs", vec!["drop_table_if_exists(m, \"movies\")"])]
+#[case("AddRatingToMovies", vec![("rating", "int")], "add_rating_to_movies.rs", vec!["remove_column_if_exists(m, \"movies\", \"rating\")"])]
+#[case(
+    "AddUserRefToPosts",
+    vec![("user", "references")],
+    "add_user_ref_to_posts.rs",
+    vec!["remove_reference_if_exists(m, \"posts\", \"user\", \"\")"]
+)]
+#[case("CreateJoinTableUsersAndGroups", vec![("count", "int")], "create_join_table_users_and_groups.rs", vec!["drop_table_if_exists(m, \"user_groups\")"])]
+fn can_generate_drop_if_exists(
+    #[case] name: &str,
+    #[case] fields: Vec<(&str, &str)>,
+    #[case] file: &str,
+    #[case] drops: Vec<&str>,
+) {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let options = GenerateOptions {
+        drop_if_exists: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        Component::Migration {
+            name: name.to_string(),
+            with_tz: true,
+            fields: fields
+                .into_iter()
+                .map(|(name, ftype)| (name.to_string(), ftype.to_string()))
+                .collect(),
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration").join("src"),
+        &format!("m{{TIME}}_{file}"),
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    let down = &migration[migration.find("async fn down").unwrap()..];
+    for drop in drops {
+        assert!(down.contains(drop), "`{drop}` not in:\n{down}");
+    }
+    assert!(!down.contains("drop_table(") && !down.contains("remove_column("));
+}
+
+#[test]
+#[serial_test::serial]
+fn can_append_to_latest_migration() {
+    let existing = r#"use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "users",
+            &[
+            ("id", ColType::PkAuto),
+            ("name", ColType::String),
+            ],
+            &[
+            ]
+        ).await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "users").await
+    }
+}
+"#;
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("migration/src/m20220101_000001_users.rs", existing)
+        .create()
+        .unwrap();
+
+    let previous = std::env::current_dir().unwrap();
+    std::env::set_current_dir(&tree_fs.root).unwrap();
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let gen_result = generate_with_options(
+        &rrgen,
+        Component::Migration {
+            name: "CreateMovies".to_string(),
+            with_tz: true,
+            fields: vec![
+                ("title".to_string(), "string".to_string()),
+                ("user".to_string(), "references".to_string()),
+            ],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            append_to_latest: true,
+            ..Default::default()
+        },
+    );
+    std::env::set_current_dir(previous).unwrap();
+    let gen_result = gen_result.expect("Generation failed");
+    assert!(collect_messages(&gen_result).contains("m20220101_000001_users.rs"));
+
+    let migration_path = tree_fs.root.join("migration/src");
+    assert!(
+        guess_file_by_time(&migration_path, "m{TIME}_movies.rs", 3).is_none(),
+        "no new migration should be created"
+    );
+    assert_eq!(
+        fs::read_to_string(migration_path.join("lib.rs")).unwrap(),
+        MIGRATION_SRC_LIB
+    );
+
+    let migration = fs::read_to_string(migration_path.join("m20220101_000001_users.rs")).unwrap();
+    let users = migration.find(r#"create_table(m, "users","#).unwrap();
+    let movies = migration.find(r#"create_table(m, "movies","#).unwrap();
+    assert!(users < movies);
+    assert!(migration.contains("        ).await?;\n"));
+    let drop_users = migration.find(r#"drop_table(m, "users").await?;"#).unwrap();
+    let drop_movies = migration
+        .find(r#"drop_table(m, "movies").await?;"#)
+        .unwrap();
+    assert!(drop_movies < drop_users);
+    assert_eq!(migration.matches("Ok(())").count(), 2);
+    syn::parse_file(&migration).expect("the merged migration should be valid Rust");
+}
+
+#[test]
+fn can_build_and_render_migration_statements() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["title".to_string()],
+            unique: true,
+            ..Default::default()
+        }],
+        checks: vec!["length(title) > 0".to_string()],
+        ..Default::default()
+    };
+    let fields = [
+        ("title".to_string(), "string!".to_string()),
+        ("user".to_string(), "references".to_string()),
+    ];
+
+    let added = build_migration("AddTitleToPosts", &fields[..1], &options).unwrap();
+    assert_eq!(
+        added.statements,
+        vec![MigrationStatement::AddColumn {
+            table: "posts".to_string(),
+            column: "title".to_string(),
+            col_type: "String".to_string(),
+        }]
+    );
+
+    let mut migration = build_migration("CreatePosts", &fields, &options).unwrap();
+    assert!(matches!(
+        migration.statements.as_slice(),
+        [
+            MigrationStatement::CreateTable { .. },
+            MigrationStatement::CreateIndex { .. },
+            MigrationStatement::AddCheck { .. },
+        ]
+    ));
+    // a caller drops the check before rendering
+    migration
+        .statements
+        .retain(|statement| !matches!(statement, MigrationStatement::AddCheck { .. }));
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    render_migration(
+        &rrgen,
+        &migration,
+        &options,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Rendering failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_create_posts.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let content = fs::read_to_string(migration_file).unwrap();
+    assert!(content.contains(r#"create_table(m, "posts","#), "{content}");
+    assert!(
+        content.contains(r#"("title", ColType::String),"#),
+        "{content}"
+    );
+    assert!(content.contains(r#"("user", ""),"#), "{content}");
+    assert!(content.contains(r#".name("idx-posts-title")"#), "{content}");
+    assert!(!content.contains("add_check"), "{content}");
+    let drop_index = content.find("m.drop_index(").unwrap();
+    let drop_table = content.find(r#"drop_table(m, "posts")"#).unwrap();
+    assert!(drop_index < drop_table);
+    syn::parse_file(&content).expect("the migration should be valid Rust");
+}
@@ -1,5 +1,8 @@
+mod channel;
 mod controller;
 mod deployment;
+#[cfg(feature = "with-db")]
+mod fixture;
 mod mailer;
 #[cfg(feature = "with-db")]
 mod migration;
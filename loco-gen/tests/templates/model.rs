@@ -1,7 +1,13 @@
 use super::utils::{guess_file_by_time, MIGRATION_SRC_LIB};
 use insta::{assert_snapshot, with_settings};
-use loco_gen::{collect_messages, generate, AppInfo, Component};
-use rrgen::RRgen;
+use loco_gen::{
+    collect_messages, destroy_in, generate, generate_changed, generate_from_csv,
+    generate_from_schema, generate_materialized_view, generate_with_options,
+    generate_with_renderer, record_in, recorded_in, undo_in, validate_models, AppInfo,
+    CollatedIndex, ColumnStorage, Component, DbBackend, GenerateMode, GenerateOptions, IndexSpec,
+    PkKind, PkSequence, TemplateRenderer, UuidVersion, MANIFEST_PATH,
+};
+use rrgen::{GenResult, RRgen};
 use std::fs;
 
 macro_rules! configure_insta {
@@ -137,3 +143,4714 @@ fn fail_when_test_models_mod_not_exists() {
         "cannot inject into tests/models/mod.rs: file does not exist"
     );
 }
+
+#[test]
+fn can_generate_with_before_save() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            before_save: true,
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/movies.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("impl ActiveModelBehavior for ActiveModel {"));
+    assert!(model.contains("async fn before_save<C>(self, _db: &C, insert: bool)"));
+    assert!(
+        model.contains("this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());")
+    );
+
+    assert_eq!(
+        fs::read_to_string(tree_fs.root.join("src/models/mod.rs")).unwrap(),
+        "pub mod _entities;\npub mod movies;"
+    );
+}
+
+#[test]
+fn can_generate_api_keys() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let gen_result = generate(
+        &rrgen,
+        Component::ApiKeys {},
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+    assert!(collect_messages(&gen_result).contains("Migration for `api_key` added!"));
+
+    let migration_path = tree_fs.root.join("migration/src");
+    let migration_file = guess_file_by_time(&migration_path, "m{TIME}_api_keys.rs", 3)
+        .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(&migration_file).unwrap();
+    assert!(migration.contains(r#"create_table(m, "api_keys","#));
+    for column in [
+        r#"("name", ColType::String)"#,
+        r#"("prefix", ColType::String)"#,
+        r#"("key_hash", ColType::StringUniq)"#,
+        r#"("scopes", ColType::TextNull)"#,
+        r#"("expires_at", ColType::TimestampWithTimeZoneNull)"#,
+        r#"("revoked_at", ColType::TimestampWithTimeZoneNull)"#,
+    ] {
+        assert!(migration.contains(column), "missing {column}");
+    }
+    assert!(tree_fs.root.join("tests/models/api_keys.rs").exists());
+}
+
+#[test]
+fn does_not_generate_model_file_by_default() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+
+    generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    assert!(!tree_fs.root.join("src/models/movies.rs").exists());
+}
+
+#[rstest::rstest]
+#[case(PkKind::Serial, "(\"id\", ColType::PkAuto)")]
+#[case(PkKind::IdentityAlways, "(\"id\", ColType::PkIdentity)")]
+#[case(PkKind::IdentityByDefault, "(\"id\", ColType::PkIdentityByDefault)")]
+#[case(PkKind::Uuid(UuidVersion::V4), "(\"id\", ColType::PkUuid)")]
+fn can_generate_pk_kind(#[case] pk_kind: PkKind, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            pk_kind,
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(expected), "{migration}");
+}
+
+#[rstest::rstest]
+#[case(PkKind::IdentityAlways, DbBackend::Sqlite)]
+#[case(PkKind::IdentityByDefault, DbBackend::MySql)]
+fn fail_pk_identity_off_postgres(#[case] pk_kind: PkKind, #[case] backend: DbBackend) {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            pk_kind,
+            backend: Some(backend),
+            ..Default::default()
+        },
+    )
+    .expect_err("Expected an error");
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "identity primary keys are only supported on postgres, but the target backend is \
+             {backend}"
+        )
+    );
+}
+
+#[rstest::rstest]
+#[case(
+    PkKind::IdentityAlways,
+    "(\"id\", ColType::PkIdentityWithSequence(IdentitySequence::new().start(1000).increment(10).cache(20))),"
+)]
+#[case(
+    PkKind::IdentityByDefault,
+    "(\"id\", ColType::PkIdentityByDefaultWithSequence(IdentitySequence::new().start(1000).increment(10).cache(20))),"
+)]
+fn can_generate_pk_sequence(#[case] pk_kind: PkKind, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "orders".to_string(),
+        with_tz: true,
+        fields: vec![("total".to_string(), "int".to_string())],
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            pk_kind,
+            pk_sequence: "start=1000,increment=10,cache=20"
+                .parse()
+                .expect("Failed to parse the sequence"),
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_orders.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(expected), "{migration}");
+}
+
+#[test]
+fn can_generate_uuid_v7_pk() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "events".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+    let options = GenerateOptions {
+        pk_kind: PkKind::Uuid(UuidVersion::V7),
+        ..Default::default()
+    };
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_events.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"("id", ColType::PkUuid),"#),
+        "{migration}"
+    );
+
+    // the hook sets the id on insert, unless the row is given one
+    let model = fs::read_to_string(tree_fs.root.join("src/models/events.rs"))
+        .expect("Failed to read the model file");
+    assert!(
+        model.contains(
+            "        if insert && self.id.is_not_set() {
+            let mut this = self;
+            this.id = sea_orm::ActiveValue::Set(Uuid::now_v7());
+            return Ok(this);
+        }"
+        ),
+        "{model}"
+    );
+    syn::parse_file(&model).expect("the model should be valid Rust");
+}
+
+#[test]
+fn can_generate_from_csv() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    generate_from_csv(
+        &rrgen,
+        &std::env::current_dir()
+            .unwrap()
+            .join("tests/fixtures/products.csv"),
+        "products",
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_products.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    for column in [
+        "(\"name\", ColType::StringNull),",
+        "(\"price\", ColType::FloatNull),",
+        "(\"stock\", ColType::IntegerNull),",
+        "(\"in_stock\", ColType::BooleanNull),",
+        "(\"released_on\", ColType::DateNull),",
+        "(\"barcode\", ColType::BigIntegerNull),",
+        "(\"notes\", ColType::StringNull),",
+    ] {
+        assert!(migration.contains(column), "{column} not in:\n{migration}");
+    }
+    assert_eq!(migration.matches("(\"id\", ColType::").count(), 1);
+}
+
+#[test]
+fn can_generate_default_string_len() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    generate_with_options(
+        &rrgen,
+        Component::Model {
+            name: "posts".to_string(),
+            with_tz: true,
+            fields: vec![
+                ("title".to_string(), "string!".to_string()),
+                ("slug".to_string(), "string:64".to_string()),
+                ("body".to_string(), "text".to_string()),
+            ],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            default_string_len: Some(255),
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_posts.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains("(\"title\", ColType::StringLen(255)),"));
+    assert!(migration.contains("(\"slug\", ColType::StringLenNull(64)),"));
+    assert!(migration.contains("(\"body\", ColType::TextNull),"));
+}
+
+#[test]
+fn can_generate_history_table() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    generate_with_options(
+        &rrgen,
+        Component::Model {
+            name: "prices".to_string(),
+            with_tz: true,
+            fields: vec![
+                ("sku".to_string(), "string^".to_string()),
+                ("amount".to_string(), "int!".to_string()),
+                ("product".to_string(), "references".to_string()),
+            ],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            history: true,
+            backend: Some(DbBackend::Sqlite),
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_prices.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    let columns = r#"            ("sku", ColType::StringUniq),
+            ("amount", ColType::Integer),
+            ("valid_from", ColType::TimestampWithTimeZoneWithDefaultExpr("CURRENT_TIMESTAMP".to_string())),
+            ],
+            &[
+            ("product", ""),
+            ]
+        )"#;
+    let history = migration
+        .find("create_history_table(m, \"prices\",")
+        .expect("history table not created");
+    // the main table is created with the period start, and mirrored as it is
+    assert!(migration[..history].contains(columns), "{migration}");
+    assert!(migration[history..].contains(columns), "{migration}");
+    assert!(migration.contains(
+        "drop_history_table(m, \"prices\").await?;\n        drop_table(m, \"prices\").await"
+    ));
+
+    let err = generate_with_options(
+        &rrgen,
+        Component::Model {
+            name: "rates".to_string(),
+            with_tz: true,
+            fields: vec![("valid_to".to_string(), "tstz".to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            history: true,
+            ..Default::default()
+        },
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "`valid_to` is a column of the validity period of a versioned model, it cannot be a field"
+    );
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[case(true)]
+fn can_check_reserved_identifiers(#[case] check_reserved: bool) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let res = generate_with_options(
+        &RRgen::with_working_dir(&tree_fs.root),
+        Component::Model {
+            name: "items".to_string(),
+            with_tz: true,
+            fields: vec![
+                ("title".to_string(), "string".to_string()),
+                ("order".to_string(), "int".to_string()),
+            ],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            check_reserved,
+            ..Default::default()
+        },
+    );
+
+    let warning = "column `order` of `items` is a reserved word in SQL";
+    if check_reserved {
+        assert_eq!(res.expect_err("Expected Err").to_string(), warning);
+        assert!(!tree_fs.root.join("src/models/items.rs").exists());
+    } else {
+        assert_eq!(res.expect("Generation failed").warnings(), [warning]);
+    }
+}
+
+#[test]
+fn can_generate_sequence_default() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let fields = vec![(
+        "number".to_string(),
+        "int^=nextval('invoice_numbers')".to_string(),
+    )];
+    generate_with_options(
+        &RRgen::with_working_dir(&tree_fs.root),
+        Component::Model {
+            name: "invoices".to_string(),
+            with_tz: true,
+            fields: fields.clone(),
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            create_sequences: true,
+            backend: Some(DbBackend::Postgres),
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_invoices.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(
+        "create_sequence(m, \"invoice_numbers\").await?;\n        create_table(m, \"invoices\","
+    ));
+    assert!(migration.contains(
+        "(\"number\", ColType::IntegerUniqWithDefaultExpr(\"nextval('invoice_numbers')\".to_string())),"
+    ));
+    assert!(migration.contains(
+        "drop_table(m, \"invoices\").await?;\n        drop_sequence(m, \"invoice_numbers\").await?;"
+    ));
+
+    let err = generate_with_options(
+        &RRgen::with_working_dir(&tree_fs.root),
+        Component::Model {
+            name: "receipts".to_string(),
+            with_tz: true,
+            fields,
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            backend: Some(DbBackend::Sqlite),
+            ..Default::default()
+        },
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "field `number`: sequence defaults are only supported on postgres, but the target backend \
+         is sqlite"
+    );
+}
+
+#[test]
+fn can_generate_unique_when_index() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let component = |flag: &str| Component::Model {
+        name: "addresses".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("user".to_string(), "references".to_string()),
+            ("street".to_string(), "string!".to_string()),
+            ("is_primary".to_string(), flag.to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec::unique_when(&["user"], "is_primary")],
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+
+    generate_with_options(&rrgen, component("bool!"), &appinfo, &options)
+        .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_addresses.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#".name("idx-addresses-user_id-is_primary")
+                .table(Alias::new("addresses"))
+                .col(Alias::new("user_id"))
+                .unique()
+                .and_where(Expr::col(Alias::new("is_primary")).eq(true))"#
+        ),
+        "{migration}"
+    );
+
+    let err = generate_with_options(&rrgen, component("string"), &appinfo, &options)
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "index condition `is_primary` is not a boolean column of the model"
+    );
+}
+
+#[test]
+fn fail_pk_sequence_without_identity() {
+    let err = generate_with_options(
+        &RRgen::default(),
+        Component::Model {
+            name: "orders".to_string(),
+            with_tz: true,
+            fields: vec![("total".to_string(), "int".to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            pk_sequence: PkSequence {
+                start: Some(1000),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .expect_err("Expected an error");
+    assert_eq!(
+        err.to_string(),
+        "sequence options need an identity primary key"
+    );
+}
+
+#[test]
+fn can_undo_generate() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("tests/models/mod.rs", "mod users;")
+        .add("src/models/mod.rs", "pub mod _entities;\npub mod users;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+
+    let gen_result = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            before_save: true,
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    // a hand edit after generation keeps the file around
+    fs::write(
+        tree_fs.root.join("tests/models/movies.rs"),
+        "// my own tests",
+    )
+    .unwrap();
+
+    undo_in(&tree_fs.root, &gen_result).expect("Undo failed");
+
+    assert!(!migration_file.exists());
+    assert!(!tree_fs.root.join("src/models/movies.rs").exists());
+    assert_eq!(
+        fs::read_to_string(tree_fs.root.join("tests/models/movies.rs")).unwrap(),
+        "// my own tests"
+    );
+    assert_eq!(
+        fs::read_to_string(tree_fs.root.join("src/models/mod.rs")).unwrap(),
+        "pub mod _entities;\npub mod users;"
+    );
+    assert_eq!(
+        fs::read_to_string(tree_fs.root.join("tests/models/mod.rs")).unwrap(),
+        "mod users;"
+    );
+    let migration_lib = fs::read_to_string(tree_fs.root.join("migration/src/lib.rs")).unwrap();
+    assert!(!migration_lib.contains("movies"));
+    assert!(migration_lib.contains("mod m20220101_000001_users;"));
+}
+
+#[test]
+fn can_destroy_recorded_generation() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("tests/models/mod.rs", "mod users;")
+        .add("src/models/mod.rs", "pub mod _entities;\npub mod users;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+    let label = component.label();
+    assert_eq!(label, "model movies");
+
+    let gen_result = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            before_save: true,
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+    record_in(&tree_fs.root, &label, &gen_result).expect("Record failed");
+    assert!(tree_fs.root.join(MANIFEST_PATH).exists());
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let generation = recorded_in(&tree_fs.root, &label).expect("Not recorded");
+    assert_eq!(
+        generation
+            .migrations()
+            .iter()
+            .map(|path| path.file_name().unwrap())
+            .collect::<Vec<_>>(),
+        vec![migration_file.file_name().unwrap()]
+    );
+
+    let destroyed = destroy_in(&tree_fs.root, &label).expect("Destroy failed");
+    assert_eq!(destroyed, generation);
+    assert!(!migration_file.exists());
+    assert!(!tree_fs.root.join("src/models/movies.rs").exists());
+    assert_eq!(
+        fs::read_to_string(tree_fs.root.join("src/models/mod.rs")).unwrap(),
+        "pub mod _entities;\npub mod users;"
+    );
+    let migration_lib = fs::read_to_string(tree_fs.root.join("migration/src/lib.rs")).unwrap();
+    assert!(!migration_lib.contains("movies"));
+
+    // taken out of the manifest
+    assert_eq!(
+        destroy_in(&tree_fs.root, &label)
+            .expect_err("Expected an error")
+            .to_string(),
+        "no generation of `model movies` is recorded in `.loco/generated.json`"
+    );
+}
+
+#[test]
+fn can_generate_unique_index_over_references() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "user_roles".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("user".to_string(), "references".to_string()),
+            ("role".to_string(), "references".to_string()),
+        ],
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            indexes: vec![IndexSpec {
+                columns: vec!["user".to_string(), "role".to_string()],
+                unique: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_user_roles.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(
+        r#"            Index::create()
+                .name("idx-user_roles-user_id-role_id")
+                .table(Alias::new("user_roles"))
+                .col(Alias::new("user_id"))
+                .col(Alias::new("role_id"))
+                .unique()
+                .to_owned(),"#
+    ));
+    assert!(migration.contains("Ok(())"));
+}
+
+#[test]
+fn can_generate_money_minor_helpers() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "orders".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("total".to_string(), "money_minor!".to_string()),
+            ("tip".to_string(), "money_minor".to_string()),
+        ],
+    };
+
+    generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_orders.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(r#"("total", ColType::BigInteger),"#));
+    assert!(migration.contains(r#"("tip", ColType::BigIntegerNull),"#));
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/orders.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("pub fn total_major(&self) -> Decimal {"));
+    assert!(model.contains("pub fn tip_major(&self) -> Option<Decimal> {"));
+    assert!(
+        model.contains("pub fn set_total_major(&mut self, major: Decimal) -> Result<(), DbErr> {")
+    );
+    assert!(
+        model.contains("pub fn set_tip_major(&mut self, major: Decimal) -> Result<(), DbErr> {")
+    );
+}
+
+#[test]
+fn can_capture_redundant_field_warning() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string".to_string()),
+            ("created_at".to_string(), "ts".to_string()),
+        ],
+    };
+
+    let results = generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    assert_eq!(
+        results.warnings(),
+        ["field `created_at` is redundant, it is already generated automatically"]
+    );
+    assert!(collect_messages(&results).contains("Warnings:"));
+}
+
+#[rstest::rstest]
+#[case(true)]
+#[case(false)]
+fn can_generate_cache_key(#[case] with_tz: bool) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz,
+        fields: vec![("title".to_string(), "string".to_string())],
+    };
+    let options = GenerateOptions {
+        cache_key: true,
+        ..Default::default()
+    };
+
+    let results = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model_file = tree_fs.root.join("src/models/movies.rs");
+    if with_tz {
+        let model = fs::read_to_string(model_file).expect("Failed to read the model file");
+        assert!(model.contains("pub fn cache_key(&self) -> String {"));
+        assert!(model.contains("self.updated_at.timestamp_micros()"));
+        assert!(results.warnings().is_empty());
+    } else {
+        assert!(!model_file.exists());
+        assert_eq!(results.warnings().len(), 1);
+    }
+}
+
+#[rstest::rstest]
+#[case(true, "create_unlogged_table(m, \"events\",")]
+#[case(false, "create_unlogged_table_without_timestamps(m, \"events\",")]
+fn can_generate_unlogged_table(#[case] with_tz: bool, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "events".to_string(),
+        with_tz,
+        fields: vec![("payload".to_string(), "json".to_string())],
+    };
+    let options = GenerateOptions {
+        unlogged: true,
+        backend: Some(DbBackend::Postgres),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_events.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(expected));
+}
+
+#[rstest::rstest]
+#[case(DbBackend::Postgres, "now()")]
+#[case(DbBackend::Sqlite, "CURRENT_TIMESTAMP")]
+#[case(DbBackend::MySql, "CURRENT_TIMESTAMP")]
+fn can_generate_db_created_at(#[case] backend: DbBackend, #[case] now: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "events".to_string(),
+        with_tz: false,
+        fields: vec![("payload".to_string(), "json".to_string())],
+    };
+    let options = GenerateOptions {
+        db_created_at: true,
+        backend: Some(backend),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_events.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains("create_table_without_timestamps(m, \"events\","));
+    assert!(migration.contains(&format!(
+        "(\"created_at\", ColType::TimestampWithTimeZoneWithDefaultExpr(\"{now}\".to_string())),"
+    )));
+}
+
+#[test]
+fn fail_unlogged_table_on_sqlite() {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "events".to_string(),
+        with_tz: true,
+        fields: vec![("payload".to_string(), "json".to_string())],
+    };
+    let options = GenerateOptions {
+        unlogged: true,
+        backend: Some(DbBackend::Sqlite),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error for sqlite");
+    assert_eq!(
+        err.to_string(),
+        "unlogged tables are only supported on postgres, but the target backend is sqlite"
+    );
+}
+
+#[rstest::rstest]
+#[case(true, "create_without_rowid_table(m, \"settings\",")]
+#[case(
+    false,
+    "create_without_rowid_table_without_timestamps(m, \"settings\","
+)]
+fn can_generate_without_rowid_table(#[case] with_tz: bool, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "settings".to_string(),
+        with_tz,
+        fields: vec![("value".to_string(), "text".to_string())],
+    };
+    let options = GenerateOptions {
+        without_rowid: true,
+        backend: Some(DbBackend::Sqlite),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_settings.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(expected));
+    assert!(migration.contains(r#"("id", ColType::PkAuto),"#));
+}
+
+#[rstest::rstest]
+#[case(
+    DbBackend::Postgres,
+    vec![("value".to_string(), "text".to_string())],
+    "WITHOUT ROWID tables are only supported on sqlite, but the target backend is postgres"
+)]
+#[case(
+    DbBackend::Sqlite,
+    vec![],
+    "a WITHOUT ROWID table needs a primary key, but `settings` has no columns to add one with"
+)]
+fn fail_without_rowid_table(
+    #[case] backend: DbBackend,
+    #[case] fields: Vec<(String, String)>,
+    #[case] expected: &str,
+) {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "settings".to_string(),
+        with_tz: true,
+        fields,
+    };
+    let options = GenerateOptions {
+        without_rowid: true,
+        backend: Some(backend),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(err.to_string(), expected);
+}
+
+#[rstest::rstest]
+#[case(true, "create_strict_table(m, \"movies\",")]
+#[case(false, "create_strict_table_without_timestamps(m, \"movies\",")]
+fn can_generate_sqlite_strict_table(#[case] with_tz: bool, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz,
+        fields: vec![("title".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        sqlite_strict: true,
+        backend: Some(DbBackend::Sqlite),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(expected));
+}
+
+#[rstest::rstest]
+#[case(true, "create_strict_without_rowid_table(m, \"settings\",")]
+#[case(
+    false,
+    "create_strict_without_rowid_table_without_timestamps(m, \"settings\","
+)]
+fn can_generate_sqlite_strict_without_rowid_table(#[case] with_tz: bool, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "settings".to_string(),
+        with_tz,
+        fields: vec![("value".to_string(), "text".to_string())],
+    };
+    let options = GenerateOptions {
+        sqlite_strict: true,
+        without_rowid: true,
+        backend: Some(DbBackend::Sqlite),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_settings.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(expected));
+}
+
+#[test]
+fn fail_sqlite_strict_table() {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        sqlite_strict: true,
+        backend: Some(DbBackend::Postgres),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(
+        err.to_string(),
+        "STRICT tables are only supported on sqlite, but the target backend is postgres"
+    );
+}
+
+#[test]
+fn can_generate_collated_index() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "products".to_string(),
+        with_tz: true,
+        fields: vec![("name".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        collated_indexes: vec![CollatedIndex {
+            column: "name".to_string(),
+            collation: "und-x-icu".to_string(),
+        }],
+        backend: Some(DbBackend::Postgres),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_products.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"create_collated_index(m, "products", "name", "und-x-icu").await?;"#)
+    );
+}
+
+#[rstest::rstest]
+#[case(
+    DbBackend::Sqlite,
+    "name",
+    "und-x-icu",
+    "ICU collated indexes are only supported on postgres, but the target backend is sqlite"
+)]
+#[case(
+    DbBackend::Postgres,
+    "title",
+    "und-x-icu",
+    "collated index column `title` is not a column of the model"
+)]
+#[case(
+    DbBackend::Postgres,
+    "name",
+    "und\" DESC",
+    "`und\" DESC` is not a collation name"
+)]
+fn fail_collated_index(
+    #[case] backend: DbBackend,
+    #[case] column: &str,
+    #[case] collation: &str,
+    #[case] expected: &str,
+) {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "products".to_string(),
+        with_tz: true,
+        fields: vec![("name".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        collated_indexes: vec![CollatedIndex {
+            column: column.to_string(),
+            collation: collation.to_string(),
+        }],
+        backend: Some(backend),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn can_generate_column_storage() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "documents".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string!".to_string()),
+            ("body".to_string(), "text".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        column_storages: vec![ColumnStorage {
+            column: "body".to_string(),
+            storage: "external".to_string(),
+        }],
+        backend: Some(DbBackend::Postgres),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_documents.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(r#"set_column_storage(m, "documents", "body", "EXTERNAL").await?;"#));
+    assert!(!migration.contains(r#""title", "EXTERNAL""#));
+}
+
+#[rstest::rstest]
+#[case(
+    DbBackend::Sqlite,
+    "body",
+    "external",
+    "column storage modes are only supported on postgres, but the target backend is sqlite"
+)]
+#[case(
+    DbBackend::Postgres,
+    "summary",
+    "external",
+    "storage column `summary` is not a column of the model"
+)]
+#[case(
+    DbBackend::Postgres,
+    "body",
+    "outside",
+    "`outside` is not a storage mode. try any of: `PLAIN`, `EXTERNAL`, `EXTENDED`, `MAIN`"
+)]
+#[case(
+    DbBackend::Postgres,
+    "pages",
+    "main",
+    "storage column `pages` is `IntegerNull`, which is not variable-length"
+)]
+fn fail_column_storage(
+    #[case] backend: DbBackend,
+    #[case] column: &str,
+    #[case] storage: &str,
+    #[case] expected: &str,
+) {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "documents".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("body".to_string(), "text".to_string()),
+            ("pages".to_string(), "int".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        column_storages: vec![ColumnStorage {
+            column: column.to_string(),
+            storage: storage.to_string(),
+        }],
+        backend: Some(backend),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn can_generate_reference_to_other_schema() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "projects".to_string(),
+        with_tz: true,
+        fields: vec![(
+            "org".to_string(),
+            "references:tenant.organizations".to_string(),
+        )],
+    };
+
+    generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_projects.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(r#"("tenant.organizations", "org_id"),"#));
+}
+
+#[test]
+fn can_generate_encrypted_columns() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "patients".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("ssn".to_string(), "string!@encrypted".to_string()),
+            ("notes".to_string(), "text@encrypted".to_string()),
+            ("name".to_string(), "string!".to_string()),
+        ],
+    };
+
+    generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_patients.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(r#"("ssn", ColType::String),"#));
+    assert!(migration.contains(r#"("notes", ColType::TextNull),"#));
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/patients.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("use loco_rs::model::Encrypted;"));
+    assert!(model.contains("pub fn ssn_encrypted(&self) -> Encrypted<&String> {"));
+    assert!(model.contains("pub fn notes_encrypted(&self) -> Option<Encrypted<&String>> {"));
+    assert!(model.contains("pub fn set_ssn_encrypted(&mut self, value: Encrypted<String>) {"));
+    assert!(!model.contains("name_encrypted"));
+}
+
+#[test]
+fn can_generate_search_rank() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "articles".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string!".to_string()),
+            ("document".to_string(), "tsvector".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        search_rank: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/articles.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("pub async fn search<C>(db: &C, query: &str)"));
+    assert!(model.contains(r##"r#""document" @@ plainto_tsquery($1)"#"##));
+    assert!(model.contains(r##"r#"ts_rank("document", plainto_tsquery($1))"#"##));
+}
+
+#[test]
+fn fail_search_rank_without_tsvector() {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "articles".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        search_rank: true,
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error without a tsvector column");
+    assert_eq!(
+        err.to_string(),
+        "a search helper needs a `tsvector` column in the model"
+    );
+}
+
+#[test]
+fn can_generate_delete_guard() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "authors".to_string(),
+        with_tz: true,
+        fields: vec![("name".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        delete_guard: vec!["books".to_string(), "article".to_string()],
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/authors.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("async fn before_delete<C>(self, db: &C)"));
+    assert!(model.contains(".filter(super::_entities::books::Column::AuthorId.eq(*id))"));
+    assert!(model.contains(".filter(super::_entities::articles::Column::AuthorId.eq(*id))"));
+}
+
+#[test]
+fn can_generate_concurrent_index() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["title".to_string()],
+            unique: false,
+            ..Default::default()
+        }],
+        concurrently: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains("/// Non-transactional: runs outside of the migrator's transaction"));
+    assert!(migration.contains("/// The indexes are built concurrently"));
+    let leave = migration.find("leave_transaction(m).await?;").unwrap();
+    let create = migration
+        .find("create_index_concurrently_without_transaction(m,\n            Index::create()")
+        .unwrap();
+    let resume = migration.find("resume_transaction(m).await?;").unwrap();
+    assert!(leave < create && create < resume);
+    assert!(!migration.contains("m.create_index("));
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[test]
+fn can_set_migration_in_transaction() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = |name: &str| Component::Model {
+        name: name.to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string!".to_string())],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component("movies"),
+        &appinfo,
+        &GenerateOptions {
+            indexes: vec![IndexSpec {
+                columns: vec!["title".to_string()],
+                ..Default::default()
+            }],
+            concurrently: true,
+            in_transaction: Some(true),
+            ..Default::default()
+        },
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "indexes built concurrently cannot be created in a transaction, the migration has to be \
+         non-transactional"
+    );
+
+    generate_with_options(
+        &rrgen,
+        component("books"),
+        &appinfo,
+        &GenerateOptions {
+            in_transaction: Some(false),
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_books.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains("/// Non-transactional: runs outside of the migrator's transaction"));
+    assert!(!migration.contains("/// The indexes are built concurrently"));
+    assert_eq!(migration.matches("leave_transaction(m).await?;").count(), 2);
+    assert_eq!(
+        migration.matches("resume_transaction(m).await?;").count(),
+        2
+    );
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[test]
+fn can_generate_state_machine() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "posts".to_string(),
+        with_tz: true,
+        fields: vec![(
+            "status".to_string(),
+            "state_machine:draft,review,published".to_string(),
+        )],
+    };
+
+    generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_posts.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(r#"("status", ColType::StringWithDefault("draft".to_string())),"#));
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/posts.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("pub enum Status {\n    Draft,\n    Review,\n    Published,\n}"));
+    assert!(model.contains("pub const fn can_transition_to(self, next: Self) -> bool {"));
+    assert!(model.contains("(Self::Draft, Self::Review)"));
+    assert!(model.contains("| (Self::Review, Self::Published)"));
+    assert!(!model.contains("(Self::Draft, Self::Published)"));
+    assert!(model.contains("pub fn transition_status(&mut self, next: Status)"));
+}
+
+#[test]
+fn can_generate_composite_type_column() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "stores".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("location".to_string(), "composite!:address".to_string()),
+            ("billing".to_string(), "composite:address".to_string()),
+            ("geo".to_string(), "composite:geo_point".to_string()),
+        ],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |spec: &str| GenerateOptions {
+        backend: Some(DbBackend::Postgres),
+        composite_types: vec![spec.parse().unwrap()],
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component(),
+        &appinfo,
+        &options("phone (number text)"),
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "composite type `phone` is not the type of any column of the model"
+    );
+    let err = generate_with_options(&rrgen, component(), &appinfo, &options("address (ip inet)"))
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "composite type `address` has `ip` of SQL type `inet`, which has no Rust type"
+    );
+
+    generate_with_options(
+        &rrgen,
+        component(),
+        &appinfo,
+        &options("address (street text, city varchar(80), zip int)"),
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_stores.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    let create_type = migration
+        .find(r#"create_composite_type(m, "address", &[("street", "text"), ("city", "varchar(80)"), ("zip", "int")]).await?;"#)
+        .expect(&migration);
+    let create_table = migration.find(r#"create_table(m, "stores","#).unwrap();
+    assert!(create_type < create_table);
+    assert!(migration.contains(r#"("location", ColType::Composite("address".to_string())),"#));
+    assert!(migration.contains(r#"("billing", ColType::CompositeNull("address".to_string())),"#));
+    // created by an earlier migration
+    assert!(migration.contains(r#"("geo", ColType::CompositeNull("geo_point".to_string())),"#));
+    assert!(!migration.contains(r#"create_composite_type(m, "geo_point""#));
+    assert!(migration.contains(r#"drop_composite_type(m, "address").await?;"#));
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/stores.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("pub struct Address {"), "{model}");
+    assert!(model.contains("    pub street: Option<String>,"));
+    assert!(model.contains("    pub zip: Option<i32>,"));
+    syn::parse_file(&model).expect("the model should be valid Rust");
+}
+
+#[test]
+fn can_generate_no_overlap_constraint() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "bookings".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("room".to_string(), "references".to_string()),
+            ("during".to_string(), "tstzrange!".to_string()),
+            ("note".to_string(), "string".to_string()),
+        ],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |spec: &str| GenerateOptions {
+        backend: Some(DbBackend::Postgres),
+        no_overlaps: vec![spec.parse().unwrap()],
+        ..Default::default()
+    };
+
+    let err = generate_with_options(&rrgen, component(), &appinfo, &options("room note"))
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "no overlap constraint `room note` needs a range column, `note` is not a range column of \
+         `bookings`"
+    );
+    let err = generate_with_options(&rrgen, component(), &appinfo, &options("desk during"))
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "no overlap constraint `desk during` refers to `desk`, which is not a column of `bookings`"
+    );
+
+    generate_with_options(&rrgen, component(), &appinfo, &options("room during"))
+        .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_bookings.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    let create_table = migration.find(r#"create_table(m, "bookings","#).unwrap();
+    let no_overlap = migration
+        .find(r#"add_no_overlap(m, "bookings", "excl-bookings-room_id-during", &["room_id"], "during").await?;"#)
+        .expect(&migration);
+    assert!(create_table < no_overlap);
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[rstest::rstest]
+#[case(
+    "composite:Address",
+    "field `location`: `Address` is not a composite type name, expected a snake_case identifier"
+)]
+#[case(
+    "composite",
+    "type: `composite` takes the name of a composite type, e.g. `composite:address`"
+)]
+fn fail_composite_type_name(#[case] ftype: &str, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let err = generate(
+        &rrgen,
+        Component::Model {
+            name: "stores".to_string(),
+            with_tz: true,
+            fields: vec![("location".to_string(), ftype.to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect_err("Expected Err");
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn can_generate_color_columns() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "swatches".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("accent".to_string(), "color".to_string()),
+            ("background".to_string(), "color!".to_string()),
+            ("brand".to_string(), "color^".to_string()),
+            ("overlay".to_string(), "color!:alpha".to_string()),
+        ],
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            backend: Some(DbBackend::Postgres),
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_swatches.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    for column in [
+        r##"("accent", ColType::CharLenNull(7).check(r#""accent" ~ '^#[0-9A-Fa-f]{6}$'"#)),"##,
+        r##"("background", ColType::CharLen(7).check(r#""background" ~ '^#[0-9A-Fa-f]{6}$'"#)),"##,
+        r##"("brand", ColType::CharLenUniq(7).check(r#""brand" ~ '^#[0-9A-Fa-f]{6}$'"#)),"##,
+        r##"("overlay", ColType::CharLen(9).check(r#""overlay" ~ '^#[0-9A-Fa-f]{8}$'"#)),"##,
+    ] {
+        assert!(migration.contains(column), "{migration}");
+    }
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[rstest::rstest]
+#[case("color:rgba")]
+#[case("color:alpha,alpha")]
+fn fail_color_alpha_parameter(#[case] ftype: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let err = generate(
+        &rrgen,
+        Component::Model {
+            name: "swatches".to_string(),
+            with_tz: true,
+            fields: vec![("overlay".to_string(), ftype.to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect_err("Expected Err");
+    assert!(
+        err.to_string()
+            .starts_with("type: `color` only takes the `alpha` parameter"),
+        "{err}"
+    );
+}
+
+#[test]
+fn can_generate_phone_column() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "contacts".to_string(),
+        with_tz: true,
+        fields: vec![("phone".to_string(), "phone!".to_string())],
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            backend: Some(DbBackend::Postgres),
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_contacts.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration
+        .contains(r##"("phone", ColType::Phone.check(r#""phone" ~ '^\+[1-9][0-9]{1,14}$'"#)),"##));
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/contacts.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("pub fn normalize_phone(input: &str) -> Option<String> {"));
+    assert!(model.contains("pub fn set_phone_normalized(&mut self, input: &str)"));
+    assert!(model.contains("self.phone = sea_orm::ActiveValue::Set(number);"));
+}
+
+#[test]
+fn can_generate_upsert() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "accounts".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("provider".to_string(), "string^".to_string()),
+            ("external_id".to_string(), "string^".to_string()),
+            ("name".to_string(), "string".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        upsert: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/accounts.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("pub async fn upsert<C>(db: &C, model: ActiveModel)"));
+    assert!(model.contains(
+        "OnConflict::columns([\n                    Column::Provider,\n                    Column::ExternalId,\n                ])"
+    ));
+    assert!(model.contains(
+        ".update_columns([\n                    Column::Name,\n                    Column::UpdatedAt,\n                ])"
+    ));
+    syn::parse_file(&model).expect("the model should be valid Rust");
+}
+
+#[test]
+fn fail_upsert_without_unique_column() {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "accounts".to_string(),
+        with_tz: true,
+        fields: vec![("name".to_string(), "string".to_string())],
+    };
+    let options = GenerateOptions {
+        upsert: true,
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error without a conflict target");
+    assert_eq!(
+        err.to_string(),
+        "an upsert needs a unique (`^`) column or an explicit conflict target"
+    );
+}
+
+#[test]
+fn can_generate_seed_loader() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "currencies".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("code".to_string(), "string^".to_string()),
+            ("country".to_string(), "string^".to_string()),
+            ("name".to_string(), "string!".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        seed_loader: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/currencies.rs"))
+        .expect("Failed to read the model file");
+    assert!(
+        model.contains("pub async fn seed(db: &DatabaseConnection, path: &str)"),
+        "{model}"
+    );
+    assert!(
+        model.contains(
+            "loco_rs::db::seed_on_conflict_do_nothing::<ActiveModel, _>(
+            db,
+            path,
+            [
+                Column::Code,
+                Column::Country,
+            ],
+        )"
+        ),
+        "{model}"
+    );
+    syn::parse_file(&model).expect("the model should be valid Rust");
+}
+
+#[test]
+fn fail_seed_loader_without_unique_column() {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "currencies".to_string(),
+        with_tz: true,
+        fields: vec![("code".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        seed_loader: true,
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error without a conflict target");
+    assert_eq!(
+        err.to_string(),
+        "a seed loader skips the rows conflicting on the unique (`^`) columns, but the model has \
+         none"
+    );
+}
+
+#[rstest::rstest]
+#[case(Some("name"), "write!(f, \"{}\", self.name)")]
+#[case(
+    Some("email"),
+    "Some(email) => write!(f, \"{}\", email),\n            None => write!(f, \"{}\", self.id),"
+)]
+#[case(None, "write!(f, \"{}\", self.id)")]
+fn can_generate_display(#[case] column: Option<&str>, #[case] expected: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "users".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("name".to_string(), "string!".to_string()),
+            ("email".to_string(), "string".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        display: true,
+        display_column: column.map(ToString::to_string),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/users.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("impl std::fmt::Display for Model {"));
+    assert!(model.contains(expected), "{model}");
+}
+
+#[test]
+fn fail_display_with_unknown_column() {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "users".to_string(),
+        with_tz: true,
+        fields: vec![("name".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        display_column: Some("title".to_string()),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error for an unknown column");
+    assert_eq!(
+        err.to_string(),
+        "display column `title` is not a column of the model"
+    );
+}
+
+#[rstest::rstest]
+#[case("references?@default=1", "user_id", "IntegerNullWithDefault(1)")]
+#[case(
+    "references?:owner_id@default=1",
+    "owner_id",
+    "IntegerNullWithDefault(1)"
+)]
+#[case("references?:owner_id", "owner_id", "")]
+fn can_generate_indexed_nullable_reference(
+    #[case] ftype: &str,
+    #[case] column: &str,
+    #[case] default: &str,
+) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "projects".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string!".to_string()),
+            ("user".to_string(), ftype.to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["user".to_string()],
+            not_null: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_projects.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+
+    let reference = ftype.split('@').next().unwrap();
+    let custom = reference
+        .strip_prefix("references?")
+        .unwrap()
+        .trim_start_matches(':');
+    assert!(migration.contains(&format!(r#"("user?", "{custom}"),"#)));
+    if default.is_empty() {
+        assert!(!migration.contains(&format!(r#"("{column}", ColType::"#)));
+    } else {
+        assert!(migration.contains(&format!(r#"("{column}", ColType::{default}),"#)));
+    }
+    assert!(migration.contains(&format!(r#".name("idx-projects-{column}")"#)));
+    assert!(migration.contains(&format!(
+        r#".and_where(Expr::col(Alias::new("{column}")).is_not_null())"#
+    )));
+    assert!(!migration.contains(".unique()"));
+}
+
+#[test]
+fn can_generate_counter_cache() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "comments".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("body".to_string(), "text!".to_string()),
+            ("post".to_string(), "references".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        counter_caches: vec!["posts.comments_count from comments".parse().unwrap()],
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_comments.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(
+        r#"add_counter_cache(m, "posts", "comments_count", "comments", "post_id").await?;
+        Ok(())"#
+    ));
+    assert!(migration.contains(
+        r#"remove_counter_cache(m, "posts", "comments_count", "comments").await?;
+        drop_table(m, "comments").await"#
+    ));
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[rstest::rstest]
+#[case(
+    "posts.comments_count from replies",
+    "counter cache `posts.comments_count from replies` counts `replies`, but the generated table is `comments`"
+)]
+#[case(
+    "users.comments_count from comments",
+    "counter cache `users.comments_count from comments` needs a reference from `comments` to `users`"
+)]
+fn fail_counter_cache(#[case] spec: &str, #[case] expected: &str) {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "comments".to_string(),
+        with_tz: true,
+        fields: vec![("post".to_string(), "references".to_string())],
+    };
+    let options = GenerateOptions {
+        counter_caches: vec![spec.parse().unwrap()],
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn can_generate_denormalized_column() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "users".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("first_name".to_string(), "string!".to_string()),
+            ("last_name".to_string(), "string".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        denormalized: vec!["full_name from first_name,last_name".parse().unwrap()],
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_users.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(
+        r#"add_denormalized_column(m, "users", "full_name", &["first_name", "last_name"]).await?;
+        Ok(())"#
+    ));
+    assert!(migration.contains(
+        r#"remove_denormalized_column(m, "users", "full_name").await?;
+        drop_table(m, "users").await"#
+    ));
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[rstest::rstest]
+#[case(
+    "first_name from first_name,last_name",
+    "denormalized column `first_name` is already a column of the model"
+)]
+#[case(
+    "full_name from first_name,middle_name",
+    "denormalized column `full_name` is built from `middle_name`, which is not a column of the model"
+)]
+fn fail_denormalized_column(#[case] spec: &str, #[case] expected: &str) {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "users".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("first_name".to_string(), "string!".to_string()),
+            ("last_name".to_string(), "string".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        denormalized: vec![spec.parse().unwrap()],
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(err.to_string(), expected);
+}
+
+#[derive(Default)]
+struct RecordingRenderer {
+    rendered: std::cell::RefCell<Vec<(std::path::PathBuf, serde_json::Value)>>,
+}
+
+impl TemplateRenderer for RecordingRenderer {
+    fn render(
+        &self,
+        path: &std::path::Path,
+        _template: &str,
+        vars: &serde_json::Value,
+    ) -> loco_gen::Result<GenResult> {
+        self.rendered
+            .borrow_mut()
+            .push((path.to_path_buf(), vars.clone()));
+        Ok(GenResult::Skipped)
+    }
+}
+
+#[test]
+fn can_generate_with_a_custom_renderer() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let renderer = RecordingRenderer::default();
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string!".to_string()),
+            ("user".to_string(), "references".to_string()),
+        ],
+    };
+
+    generate_with_renderer(
+        &renderer,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions::default(),
+    )
+    .expect("Generation failed");
+
+    let rendered = renderer.rendered.into_inner();
+    let model = rendered
+        .iter()
+        .find(|(path, _)| path.ends_with("model/model.t"))
+        .expect("the model migration was not rendered");
+    assert!(rendered
+        .iter()
+        .any(|(path, _)| path.ends_with("model/test.t")));
+
+    let vars = &model.1;
+    assert_eq!(vars["name"], "movies");
+    assert_eq!(vars["columns"][0][0], "title");
+    assert_eq!(vars["columns"][0][1], "String");
+    assert_eq!(vars["references"][0][0], "user");
+}
+
+#[rstest::rstest]
+#[case(vec![("name".to_string(), "string!".to_string())])]
+#[case(vec![
+    ("name".to_string(), "string!".to_string()),
+    ("tenant".to_string(), "references".to_string()),
+])]
+fn can_generate_with_tenant_reference(#[case] fields: Vec<(String, String)>) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "projects".to_string(),
+        with_tz: true,
+        fields,
+    };
+    let options = GenerateOptions {
+        tenant: Some("tenant".to_string()),
+        indexes: vec![IndexSpec {
+            columns: vec!["name".to_string()],
+            unique: true,
+            tenant_scoped: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_projects.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert_eq!(migration.matches(r#"("tenant", ""),"#).count(), 1);
+    assert!(migration.contains(r#".name("idx-projects-tenant_id-name")"#));
+    assert!(migration.contains(
+        r#".col(Alias::new("tenant_id"))
+                .col(Alias::new("name"))
+                .unique()"#
+    ));
+}
+
+#[test]
+fn can_generate_content_hash() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add_empty("src/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "blobs".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("content".to_string(), "blob!".to_string()),
+            ("mime".to_string(), "string".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        content_hash: Some("content".to_string()),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_blobs.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(r#"("content_sha256", ColType::StringLen(64)),"#));
+    assert!(migration.contains(
+        r#".name("idx-blobs-content_sha256")
+                .table(Alias::new("blobs"))
+                .col(Alias::new("content_sha256"))
+                .unique()"#
+    ));
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/blobs.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("pub fn set_content_hashed(&mut self, content: Vec<u8>) {"));
+    assert!(model.contains("sea_orm::ActiveValue::Set(loco_rs::hash::sha256_hex(&content));"));
+    assert!(model.contains(
+        "pub async fn insert_deduplicated<C>(db: &C, model: ActiveModel) -> Result<Model, DbErr>"
+    ));
+    assert!(model.contains("OnConflict::column(Column::ContentSha256)"));
+}
+
+#[test]
+fn can_reuse_enum_type() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    // earlier migrations are read from the current directory, not the generator's
+    let options = GenerateOptions {
+        migration_dir: Some(tree_fs.root.join("migration")),
+        ..Default::default()
+    };
+    let generate_model = |name: &str, variants: &str| {
+        generate_with_options(
+            &rrgen,
+            Component::Model {
+                name: name.to_string(),
+                with_tz: true,
+                fields: vec![(
+                    "status".to_string(),
+                    format!("enum!:order_status:{variants}"),
+                )],
+            },
+            &AppInfo {
+                app_name: "tester".to_string(),
+            },
+            &options,
+        )
+    };
+    let column = r#"("status", ColType::Enum("order_status".to_string(), vec!["draft".to_string(), "paid".to_string()])),"#;
+
+    generate_model("orders", "draft,paid").expect("Generation failed");
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_orders.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(column), "{migration}");
+    assert!(
+        migration.contains("drop_table(m, \"orders\").await?;\n        drop_enum_type(m, \"order_status\").await?;"),
+        "{migration}"
+    );
+
+    // the second model references the type, and leaves dropping it to the first
+    generate_model("invoices", "draft,paid").expect("Generation failed");
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_invoices.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(column), "{migration}");
+    assert!(!migration.contains("drop_enum_type"), "{migration}");
+
+    let err = generate_model("refunds", "draft,refunded").expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "field `status`: enum type `order_status` is already declared with the variants \
+         `draft,paid`, not `draft,refunded`"
+    );
+}
+
+#[test]
+fn can_generate_shared_primary_key() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let generate_model = |fields: Vec<(&str, &str)>| {
+        generate(
+            &rrgen,
+            Component::Model {
+                name: "user_profiles".to_string(),
+                with_tz: true,
+                fields: fields
+                    .into_iter()
+                    .map(|(name, ftype)| (name.to_string(), ftype.to_string()))
+                    .collect(),
+            },
+            &AppInfo {
+                app_name: "tester".to_string(),
+            },
+        )
+    };
+    generate_model(vec![("bio", "text"), ("user", "references:shared_pk")])
+        .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_user_profiles.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    // `user_id` is the primary key, and the foreign key `create_table` adds
+    // for the reference
+    assert!(
+        migration.contains(
+            r#"            ("user_id", ColType::PkInteger),
+            ("bio", ColType::TextNull),
+            ],
+            &[
+            ("user", ""),
+            ]"#
+        ),
+        "{migration}"
+    );
+    assert!(!migration.contains(r#"("id","#), "{migration}");
+
+    let err = generate_model(vec![
+        ("user", "references:shared_pk"),
+        ("account", "references:shared_pk"),
+    ])
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "field `account` cannot share the primary key, `user_id` already does"
+    );
+}
+
+#[test]
+fn can_generate_literal_defaults() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let generate_model = |fields: &[(&str, &str)]| {
+        generate(
+            &rrgen,
+            Component::Model {
+                name: "posts".to_string(),
+                with_tz: true,
+                fields: fields
+                    .iter()
+                    .map(|(name, ftype)| ((*name).to_string(), (*ftype).to_string()))
+                    .collect(),
+            },
+            &AppInfo {
+                app_name: "tester".to_string(),
+            },
+        )
+    };
+    generate_model(&[
+        ("status", "string!:default=draft"),
+        ("note", r#"string:64:default="in review, later""#),
+        ("views", "int:default=0"),
+        ("score", "big_int!:default=-1"),
+        ("published", "bool!:default=false"),
+    ])
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_posts.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"            ("status", ColType::String.default("draft")),
+            ("note", ColType::StringLenNull(64).default("in review, later")),
+            ("views", ColType::IntegerNull.default(0)),
+            ("score", ColType::BigInteger.default(-1i64)),
+            ("published", ColType::Boolean.default(false)),"#
+        ),
+        "{migration}"
+    );
+
+    for (field, err) in [
+        (
+            ("views", "int:default=many"),
+            "default of field `views` is not a valid `i32`: `many`",
+        ),
+        (
+            ("published", "bool:default=yes"),
+            "default of field `published` is not a valid `bool`: `yes`",
+        ),
+        (
+            ("user", "references:default=1"),
+            "field `user` cannot have a `default=`, only value fields can, references take \
+             `@default=<id>`",
+        ),
+    ] {
+        assert_eq!(
+            generate_model(&[field])
+                .expect_err("Expected Err")
+                .to_string(),
+            err
+        );
+    }
+}
+
+#[test]
+fn can_generate_reference_to_table() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "categories".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("name".to_string(), "string!".to_string()),
+            // back at the table itself
+            (
+                "parent".to_string(),
+                "references?:to=categories".to_string(),
+            ),
+            ("author".to_string(), "references:to=users".to_string()),
+            ("user".to_string(), "references".to_string()),
+        ],
+    };
+
+    generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_categories.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"            &[
+            ("categories?", "parent_id"),
+            ("users", "author_id"),
+            ("user", ""),
+            ]"#
+        ),
+        "{migration}"
+    );
+}
+
+#[test]
+fn can_generate_index_and_foreign_key_comments() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let component = Component::Model {
+        name: "posts".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("slug".to_string(), "string^".to_string()),
+            (
+                "user".to_string(),
+                "references@comment=the author".to_string(),
+            ),
+        ],
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["user".to_string(), "slug".to_string()],
+            comment: Some(r#"the "latest posts" of a user"#.to_string()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+
+    generate_with_options(&rrgen, component, &appinfo, &options).expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_posts.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"        comment_on_index(m, "idx-posts-user_id-slug", "the \"latest posts\" of a user").await?;"#
+        ),
+        "{migration}"
+    );
+    assert!(
+        migration.contains(
+            r#"        comment_on_foreign_key(m, "posts", "user_id", "the author").await?;"#
+        ),
+        "{migration}"
+    );
+
+    let err = generate_with_options(
+        &rrgen,
+        Component::Model {
+            name: "notes".to_string(),
+            with_tz: true,
+            fields: vec![("body".to_string(), "text@comment=the note".to_string())],
+        },
+        &appinfo,
+        &GenerateOptions::default(),
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "field `body` is not a reference, `@comment` only applies to the foreign key of a \
+         reference"
+    );
+}
+
+#[test]
+fn can_generate_template_only() {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let results = generate_with_options(
+        &rrgen,
+        Component::Model {
+            name: "movies".to_string(),
+            with_tz: true,
+            fields: vec![("title".to_string(), "string".to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &GenerateOptions {
+            mode: GenerateMode::TemplateOnly,
+            ..Default::default()
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    assert!(results
+        .artifacts()
+        .iter()
+        .any(|artifact| artifact.path.file_name() == migration_file.file_name()));
+}
+
+#[test]
+fn can_validate_models_directory() {
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add(
+            "migration/src/m20220101_000001_users.rs",
+            "create_table(m, \"users\",\n    &[],\n    &[]\n).await",
+        )
+        .create()
+        .unwrap();
+
+    let options = GenerateOptions {
+        migration_dir: Some(tree_fs.root.join("migration")),
+        ..Default::default()
+    };
+    let diagnostics = validate_models(
+        &std::env::current_dir()
+            .unwrap()
+            .join("tests/fixtures/models"),
+        &options,
+    )
+    .expect("Validation failed");
+
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].model, "post");
+    assert_eq!(diagnostics[0].diagnostic.index, 2);
+    assert_eq!(diagnostics[0].diagnostic.field, "category:references");
+    assert_eq!(
+        diagnostics[0].diagnostic.message,
+        "field `category` references `categories`, which no model or migration creates"
+    );
+}
+
+#[test]
+fn can_generate_inline_enum() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "orders".to_string(),
+        with_tz: true,
+        fields: vec![(
+            "status".to_string(),
+            "enum!:pending,shipped,delivered".to_string(),
+        )],
+    };
+
+    generate(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_orders.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"("status", ColType::Enum("status".to_string(), vec!["pending".to_string(), "shipped".to_string(), "delivered".to_string()])),"#),
+        "{migration}"
+    );
+    assert!(migration.contains("drop_enum_type(m, \"status\").await?;"));
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/orders.rs"))
+        .expect("Failed to read the model file");
+    assert!(
+        model.contains(
+            r#"#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "status")]
+pub enum Status {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "shipped")]
+    Shipped,
+    #[sea_orm(string_value = "delivered")]
+    Delivered,
+}"#
+        ),
+        "{model}"
+    );
+    assert!(model.contains("Self::Shipped => \"shipped\","));
+    assert!(model.contains("\"delivered\" => Ok(Self::Delivered),"));
+}
+
+#[test]
+fn can_guard_enum_type_creation() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "orders".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("status".to_string(), "enum!:pending,shipped".to_string()),
+            ("total".to_string(), "int!".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        enum_if_not_exists: true,
+        drop_if_exists: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_orders.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    let guard = migration
+        .find(r#"create_enum_type_if_not_exists(m, "status", &["pending", "shipped"]).await?;"#)
+        .expect("guarded enum type creation");
+    let table = migration.find("create_table(m, \"orders\"").expect("table");
+    assert!(guard < table, "{migration}");
+    assert!(
+        migration.contains("drop_table_if_exists(m, \"orders\").await?;"),
+        "{migration}"
+    );
+}
+
+#[test]
+fn can_generate_multi_column_check() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = |fields: &[(&str, &str)]| Component::Model {
+        name: "bookings".to_string(),
+        with_tz: true,
+        fields: fields
+            .iter()
+            .map(|(fname, ftype)| ((*fname).to_string(), (*ftype).to_string()))
+            .collect(),
+    };
+    let options = GenerateOptions {
+        checks: vec!["start_date <= end_date".to_string()],
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component(&[("start_date", "date!")]),
+        &appinfo,
+        &options,
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "check `start_date <= end_date` refers to `end_date`, which is not a column of `bookings`"
+    );
+
+    generate_with_options(
+        &rrgen,
+        component(&[("start_date", "date!"), ("end_date", "date!")]),
+        &appinfo,
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_bookings.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"add_check(m, "bookings", "chk-bookings-start_date-end_date", "start_date <= end_date").await?;"#
+        ),
+        "{migration}"
+    );
+    assert!(
+        migration.contains(
+            "drop_check(m, \"bookings\", \"chk-bookings-start_date-end_date\").await?;\n        drop_table(m, \"bookings\").await"
+        ),
+        "{migration}"
+    );
+}
+
+#[test]
+fn can_generate_check_on_reference() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "subscriptions".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("kind".to_string(), "string!".to_string()),
+            ("user".to_string(), "references?".to_string()),
+        ],
+    };
+    let options = |check: &str| GenerateOptions {
+        checks: vec![check.to_string()],
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component(),
+        &appinfo,
+        &options("(kind = 'team') = (team IS NOT NULL)"),
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "check `(kind = 'team') = (team IS NOT NULL)` refers to `team`, which is not a column of \
+         `subscriptions`"
+    );
+
+    generate_with_options(
+        &rrgen,
+        component(),
+        &appinfo,
+        &options("(kind = 'user') = (user IS NOT NULL)"),
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_subscriptions.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"add_check(m, "subscriptions", "chk-subscriptions-kind-user_id", "(kind = 'user') = (user_id IS NOT NULL)").await?;"#
+        ),
+        "{migration}"
+    );
+}
+
+#[test]
+fn can_generate_expression_index() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "users".to_string(),
+        with_tz: true,
+        fields: vec![("email".to_string(), "string!".to_string())],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |expr: &str| GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec![expr.to_string()],
+            unique: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let err = generate_with_options(&rrgen, component(), &appinfo, &options("lower(mail)"))
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "index expression `lower(mail)` refers to `mail`, which is not a column of the model"
+    );
+
+    generate_with_options(&rrgen, component(), &appinfo, &options("lower(email)"))
+        .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_users.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            "                .name(\"idx-users-lower_email\")\n                .table(Alias::new(\"users\"))\n                .col(Expr::cust(r#\"lower(email)\"#))\n                .unique()"
+        ),
+        "{migration}"
+    );
+}
+
+#[test]
+fn can_generate_field_and_composite_indexes() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "posts".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string!:index".to_string()),
+            ("author".to_string(), "references".to_string()),
+        ],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |index: &str| GenerateOptions {
+        indexes: vec![index.parse::<IndexSpec>().expect("Failed to parse index")],
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component(),
+        &appinfo,
+        &options("author_id,posted_at"),
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "index column `posted_at` is not a column or reference of the model"
+    );
+
+    generate_with_options(
+        &rrgen,
+        component(),
+        &appinfo,
+        &options("author_id,created_at"),
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_posts.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"("title", ColType::String),"#),
+        "{migration}"
+    );
+    for index in [
+        ".name(\"idx-posts-author_id-created_at\")\n                .table(Alias::new(\"posts\"))\n                .col(Alias::new(\"author_id\"))\n                .col(Alias::new(\"created_at\"))\n                .to_owned(),",
+        ".name(\"idx-posts-title\")\n                .table(Alias::new(\"posts\"))\n                .col(Alias::new(\"title\"))\n                .to_owned(),",
+    ] {
+        assert!(migration.contains(index), "{index} not in:\n{migration}");
+    }
+}
+
+#[rstest::rstest]
+#[case("payload", "idx-events-payload")]
+#[case("(payload -> 'tags')", "idx-events-payload_tags")]
+fn can_generate_jsonb_gin_index(#[case] column: &str, #[case] name: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "events".to_string(),
+        with_tz: true,
+        fields: vec![("payload".to_string(), "jsonb!".to_string())],
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec![column.to_string()],
+            gin: true,
+            ..Default::default()
+        }],
+        backend: Some(DbBackend::Postgres),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_events.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    let col = if column.contains('(') {
+        format!("Expr::cust(r#\"{column}\"#)")
+    } else {
+        format!("Alias::new(\"{column}\")")
+    };
+    let index = format!(
+        r#"Index::create()
+                .name("{name}")
+                .table(Alias::new("events"))
+                .col({col})
+                .full_text()
+                .to_owned(),"#
+    );
+    assert!(migration.contains(&index), "{index} not in:\n{migration}");
+}
+
+#[test]
+fn can_generate_soft_delete_unique_indexes() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let rrgen_in = |tree_fs: &tree_fs::Tree| RRgen::with_working_dir(&tree_fs.root);
+    let component = |soft_deletes: bool| {
+        let mut fields = vec![
+            ("email".to_string(), "string^".to_string()),
+            ("name".to_string(), "string!".to_string()),
+        ];
+        if soft_deletes {
+            fields.push(("deleted_at".to_string(), "tstz".to_string()));
+        }
+        Component::Model {
+            name: "users".to_string(),
+            with_tz: true,
+            fields,
+        }
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["name".to_string()],
+            unique: true,
+            ..Default::default()
+        }],
+        soft_delete_unique: true,
+        ..Default::default()
+    };
+    let generate = |soft_deletes: bool| {
+        let tree_fs = tree_fs::TreeBuilder::default()
+            .drop(true)
+            .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+            .add_empty("tests/models/mod.rs")
+            .create()
+            .unwrap();
+        generate_with_options(
+            &rrgen_in(&tree_fs),
+            component(soft_deletes),
+            &appinfo,
+            &options,
+        )
+        .expect("Generation failed");
+        let migration_file =
+            guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_users.rs", 3)
+                .expect("Failed to find the generated migration file");
+        fs::read_to_string(migration_file).unwrap()
+    };
+
+    let migration = generate(true);
+    assert!(
+        migration.contains(r#"("email", ColType::String),"#),
+        "{migration}"
+    );
+    for column in ["name", "email"] {
+        let index = format!(
+            r#"Index::create()
+                .name("idx-users-{column}")
+                .table(Alias::new("users"))
+                .col(Alias::new("{column}"))
+                .unique()
+                .and_where(Expr::col(Alias::new("deleted_at")).is_null())
+                .to_owned(),"#
+        );
+        assert!(migration.contains(&index), "{index} not in:\n{migration}");
+    }
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+
+    // without soft deletes, the unique columns and indexes are left alone
+    let migration = generate(false);
+    assert!(migration.contains(r#"("email", ColType::StringUniq),"#));
+    assert!(!migration.contains("is_null()"));
+}
+
+#[test]
+fn fail_soft_delete_unique_with_upsert() {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "users".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("email".to_string(), "string^".to_string()),
+            ("deleted_at".to_string(), "tstz".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        soft_delete_unique: true,
+        upsert: true,
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(
+        err.to_string(),
+        "`ON CONFLICT` cannot target the unique indexes made partial for soft deletes, which \
+         upserts, seed loaders and content hashes conflict on"
+    );
+}
+
+#[test]
+fn can_generate_index_in_tablespace() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string!".to_string()),
+            ("rating".to_string(), "int".to_string()),
+        ],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |backend, tablespace: &str| GenerateOptions {
+        indexes: vec![
+            IndexSpec {
+                columns: vec!["title".to_string()],
+                tablespace: Some(tablespace.to_string()),
+                ..Default::default()
+            },
+            IndexSpec {
+                columns: vec!["rating".to_string()],
+                ..Default::default()
+            },
+        ],
+        backend: Some(backend),
+        ..Default::default()
+    };
+
+    for (backend, tablespace, expected) in [
+        (
+            DbBackend::Sqlite,
+            "fast_ssd",
+            "index tablespaces are only supported on postgres, but the target backend is sqlite",
+        ),
+        (
+            DbBackend::Postgres,
+            "fast\" --",
+            "`fast\" --` is not a tablespace name",
+        ),
+    ] {
+        let err =
+            generate_with_options(&rrgen, component(), &appinfo, &options(backend, tablespace))
+                .expect_err("Expected Err");
+        assert_eq!(err.to_string(), expected);
+    }
+
+    generate_with_options(
+        &rrgen,
+        component(),
+        &appinfo,
+        &options(DbBackend::Postgres, "fast_ssd"),
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"create_index_in_tablespace(m,
+            Index::create()
+                .name("idx-movies-title")
+                .table(Alias::new("movies"))
+                .col(Alias::new("title"))
+                .to_owned(),
+            "fast_ssd",
+        )
+        .await?;"#
+        ),
+        "{migration}"
+    );
+    // only the index given one is placed in a tablespace
+    assert!(migration.contains(
+        r#"m.create_index(
+            Index::create()
+                .name("idx-movies-rating")"#
+    ));
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[test]
+fn can_generate_concurrent_index_in_tablespace() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "movies".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string!".to_string())],
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["title".to_string()],
+            tablespace: Some("fast_ssd".to_string()),
+            ..Default::default()
+        }],
+        concurrently: true,
+        backend: Some(DbBackend::Postgres),
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"create_index_concurrently_in_tablespace_without_transaction(m,
+            Index::create()
+                .name("idx-movies-title")
+                .table(Alias::new("movies"))
+                .col(Alias::new("title"))
+                .to_owned(),
+            "fast_ssd",
+        )
+        .await?;"#
+        ),
+        "{migration}"
+    );
+    let leave = migration.find("leave_transaction(m).await?;").unwrap();
+    let create = migration
+        .find("create_index_concurrently_in_tablespace_without_transaction(m,")
+        .unwrap();
+    let resume = migration.find("resume_transaction(m).await?;").unwrap();
+    assert!(leave < create && create < resume);
+    syn::parse_file(&migration).expect("the migration should be valid Rust");
+}
+
+#[rstest::rstest]
+#[case(
+    DbBackend::Sqlite,
+    "payload",
+    false,
+    "GIN indexes are only supported on postgres, but the target backend is sqlite"
+)]
+#[case(
+    DbBackend::Postgres,
+    "title",
+    false,
+    "GIN index over `title` refers to `title`, which is not a jsonb column of the model"
+)]
+#[case(
+    DbBackend::Postgres,
+    "(title -> 'tags')",
+    false,
+    "GIN index over `(title -> 'tags')` refers to `title`, which is not a jsonb column of the model"
+)]
+#[case(
+    DbBackend::Postgres,
+    "payload",
+    true,
+    "GIN index over `payload` cannot be unique"
+)]
+fn fail_jsonb_gin_index(
+    #[case] backend: DbBackend,
+    #[case] column: &str,
+    #[case] unique: bool,
+    #[case] expected: &str,
+) {
+    let rrgen = RRgen::default();
+    let component = Component::Model {
+        name: "events".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string".to_string()),
+            ("payload".to_string(), "jsonb!".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec![column.to_string()],
+            unique,
+            gin: true,
+            ..Default::default()
+        }],
+        backend: Some(backend),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect_err("Expected an error");
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn can_generate_deferrable_unique_constraint() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "slots".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("board".to_string(), "references".to_string()),
+            ("position".to_string(), "int!".to_string()),
+        ],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |backend| GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["board".to_string(), "position".to_string()],
+            unique: true,
+            deferrable: true,
+            ..Default::default()
+        }],
+        backend: Some(backend),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(&rrgen, component(), &appinfo, &options(DbBackend::Sqlite))
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "deferrable unique constraints are only supported on postgres, but the target backend \
+         is sqlite"
+    );
+
+    generate_with_options(&rrgen, component(), &appinfo, &options(DbBackend::Postgres))
+        .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_slots.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"add_deferrable_unique(m, "slots", "idx-slots-board_id-position", &["board_id", "position"]).await?;"#
+        ),
+        "{migration}"
+    );
+    assert!(!migration.contains("m.create_index("), "{migration}");
+}
+
+#[test]
+fn can_generate_active_model_builder() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "posts".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string!".to_string()),
+            ("body".to_string(), "text".to_string()),
+            ("views".to_string(), "int!".to_string()),
+            ("user".to_string(), "references".to_string()),
+            ("editor".to_string(), "references?".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        builder: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/posts.rs"))
+        .expect("Failed to read the model file");
+    assert!(
+        model.contains("pub fn new(title: String, views: i32, user_id: i32) -> Self {"),
+        "{model}"
+    );
+    assert!(model.contains("title: sea_orm::ActiveValue::Set(title),"));
+    assert!(model.contains("user_id: sea_orm::ActiveValue::Set(user_id),"));
+    assert!(model.contains("pub fn body(mut self, body: String) -> Self {"));
+    assert!(model.contains("self.0.body = sea_orm::ActiveValue::Set(Some(body));"));
+    assert!(model.contains("pub fn editor_id(mut self, editor_id: i32) -> Self {"));
+    assert!(!model.contains("pub fn title("));
+    assert!(model.contains("pub fn build(self) -> ActiveModel {"));
+}
+
+#[test]
+fn can_generate_reference_accessors() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = Component::Model {
+        name: "comments".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("body".to_string(), "text!".to_string()),
+            ("post".to_string(), "references".to_string()),
+            ("author".to_string(), "references:to=users".to_string()),
+            ("editor".to_string(), "references?:to=users".to_string()),
+        ],
+    };
+    let options = GenerateOptions {
+        reference_accessors: true,
+        ..Default::default()
+    };
+
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/comments.rs"))
+        .expect("Failed to read the model file");
+    assert!(
+        model.contains("use super::_entities::posts::Model as Post;"),
+        "{model}"
+    );
+    assert_eq!(
+        model
+            .matches("use super::_entities::users::Model as User;")
+            .count(),
+        1
+    );
+    assert!(
+        model.contains("pub async fn post<C>(&self, db: &C) -> Result<Post, DbErr>"),
+        "{model}"
+    );
+    assert!(model.contains("super::_entities::posts::Entity::find_by_id(self.post_id)"));
+    assert!(model.contains("pub async fn author<C>(&self, db: &C) -> Result<User, DbErr>"));
+    assert!(model.contains("super::_entities::users::Entity::find_by_id(self.author_id)"));
+    assert!(model.contains("pub async fn editor<C>(&self, db: &C) -> Result<Option<User>, DbErr>"));
+    assert!(model.contains("match self.editor_id {"));
+    syn::parse_file(&model).expect("the model should be valid Rust");
+}
+
+#[test]
+fn can_generate_many_to_many_join_table() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let generate_join = |name: &str, fields: Vec<(&str, &str)>, options: &GenerateOptions| {
+        generate_with_options(
+            &rrgen,
+            Component::Model {
+                name: name.to_string(),
+                with_tz: true,
+                fields: fields
+                    .into_iter()
+                    .map(|(name, ftype)| (name.to_string(), ftype.to_string()))
+                    .collect(),
+            },
+            &AppInfo {
+                app_name: "tester".to_string(),
+            },
+            options,
+        )
+    };
+    let many_to_many = |a: &str, b: &str| GenerateOptions {
+        many_to_many: Some((a.to_string(), b.to_string())),
+        ..Default::default()
+    };
+
+    generate_join(
+        "article_tags",
+        vec![("position", "int!")],
+        &many_to_many("article", "tag"),
+    )
+    .expect("Generation failed");
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_article_tags.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    // the foreign keys make up the primary key instead of an `id`
+    assert!(
+        migration.contains(r#"create_join_table(m, "article_tags","#),
+        "{migration}"
+    );
+    assert!(migration.contains(r#"("position", ColType::Integer),"#));
+    assert!(
+        migration.contains(
+            r#"            &[
+            ("article", ""),
+            ("tag", ""),
+            ]"#
+        ),
+        "{migration}"
+    );
+    assert!(!migration.contains(r#"("id","#), "{migration}");
+
+    // a self-join tells the second foreign key apart
+    generate_join("follows", vec![], &many_to_many("user", "users")).expect("Generation failed");
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_follows.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r#"            &[
+            ("user", ""),
+            ("user", "related_user_id"),
+            ]"#
+        ),
+        "{migration}"
+    );
+
+    let err = generate_join(
+        "memberships",
+        vec![],
+        &GenerateOptions {
+            unlogged: true,
+            ..many_to_many("user", "group")
+        },
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "a many-to-many join table is created with `create_join_table`, which cannot make it \
+         unlogged, WITHOUT ROWID or STRICT"
+    );
+}
+
+#[test]
+fn can_generate_many_to_many_field() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    generate(
+        &rrgen,
+        Component::Model {
+            name: "post".to_string(),
+            with_tz: true,
+            fields: vec![
+                ("title".to_string(), "string".to_string()),
+                ("tags".to_string(), "many_to_many".to_string()),
+                ("editors".to_string(), "many_to_many:users".to_string()),
+            ],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+    )
+    .expect("Generation failed");
+
+    let migration_src = tree_fs.root.join("migration/src");
+    let migration = fs::read_to_string(
+        guess_file_by_time(&migration_src, "m{TIME}_posts.rs", 3)
+            .expect("Failed to find the generated migration file"),
+    )
+    .unwrap();
+    // a relation rather than a column
+    assert!(!migration.contains(r#""tags""#), "{migration}");
+
+    let join_table = fs::read_to_string(
+        guess_file_by_time(
+            &migration_src,
+            "m{TIME}_create_join_table_posts_and_tags.rs",
+            3,
+        )
+        .expect("Failed to find the join table migration"),
+    )
+    .unwrap();
+    assert!(
+        join_table.contains(
+            r#"create_join_table_without_timestamps(m, "post_tags",
+            &[
+            ],
+            &[
+            ("post", ""),
+            ("tag", ""),
+            ]"#
+        ),
+        "{join_table}"
+    );
+    assert!(guess_file_by_time(
+        &migration_src,
+        "m{TIME}_create_join_table_posts_and_users.rs",
+        3
+    )
+    .is_some());
+
+    // registered after the table the foreign keys refer to
+    let lib = fs::read_to_string(migration_src.join("lib.rs")).unwrap();
+    let posts = lib.find("_posts::Migration").unwrap();
+    let tags = lib
+        .find("_create_join_table_posts_and_tags::Migration")
+        .unwrap();
+    assert!(posts < tags, "{lib}");
+}
+
+#[test]
+fn can_generate_materialized_view() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let fields = vec![
+        ("day".to_string(), "date!".to_string()),
+        ("total".to_string(), "big_int!".to_string()),
+    ];
+    let sql = "SELECT day, sum(total) AS total FROM orders GROUP BY day;";
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = GenerateOptions {
+        refresh_concurrently: true,
+        ..Default::default()
+    };
+
+    let err = generate_materialized_view(&rrgen, "daily_sales", sql, &fields, &appinfo, &options)
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "refreshing `daily_sales` concurrently needs a unique index over plain columns of all \
+         its rows"
+    );
+
+    let options = GenerateOptions {
+        indexes: vec![IndexSpec {
+            columns: vec!["day".to_string()],
+            unique: true,
+            ..Default::default()
+        }],
+        ..options
+    };
+    generate_materialized_view(&rrgen, "daily_sales", sql, &fields, &appinfo, &options)
+        .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_daily_sales.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(
+            r##"create_materialized_view(
+            m,
+            "daily_sales",
+            r#"SELECT day, sum(total) AS total FROM orders GROUP BY day"#,
+        )"##
+        ),
+        "{migration}"
+    );
+    assert!(migration.contains(r#".name("idx-daily_sales-day")"#));
+    assert!(migration.contains(".unique()"));
+    assert!(migration.contains(r#"drop_materialized_view(m, "daily_sales").await"#));
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/daily_sales.rs"))
+        .expect("Failed to read the model file");
+    // the read-only entity, keyed by the unique index
+    assert!(
+        model.contains(
+            "    #[sea_orm(primary_key, auto_increment = false)]\n    pub day: Date,\n    pub \
+             total: i64,"
+        ),
+        "{model}"
+    );
+    assert!(model.contains("`daily_sales` is a materialized view, its rows are read-only"));
+    assert!(
+        model.contains(r##"r#"REFRESH MATERIALIZED VIEW CONCURRENTLY "daily_sales""#,"##),
+        "{model}"
+    );
+    let mod_rs = fs::read_to_string(tree_fs.root.join("src/models/mod.rs")).unwrap();
+    assert!(mod_rs.contains("pub mod daily_sales;"));
+}
+
+#[test]
+fn can_generate_renamed_entity_columns() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let fields = vec![
+        ("usr_id".to_string(), "int!".to_string()),
+        ("usr_nm".to_string(), "string!".to_string()),
+    ];
+    let sql = "SELECT usr_id, usr_nm FROM legacy_users";
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |renames: &[(&str, &str)]| GenerateOptions {
+        column_renames: renames
+            .iter()
+            .map(|(column, field)| ((*column).to_string(), (*field).to_string()))
+            .collect(),
+        ..Default::default()
+    };
+
+    for (renames, expected) in [
+        (
+            [("usr_name", "user_name")],
+            "renamed column `usr_name` is not a column of the model",
+        ),
+        (
+            [("usr_nm", "UserName")],
+            "column `usr_nm` is renamed to `UserName`, which is not a snake case field name",
+        ),
+        (
+            [("usr_nm", "usr_id")],
+            "column `usr_nm` is renamed to `usr_id`, which is already a column of the model",
+        ),
+    ] {
+        let err = generate_materialized_view(
+            &rrgen,
+            "user_names",
+            sql,
+            &fields,
+            &appinfo,
+            &options(&renames),
+        )
+        .expect_err("Expected Err");
+        assert_eq!(err.to_string(), expected);
+    }
+
+    generate_materialized_view(
+        &rrgen,
+        "user_names",
+        sql,
+        &fields,
+        &appinfo,
+        &options(&[("usr_id", "user_id"), ("usr_nm", "user_name")]),
+    )
+    .expect("Generation failed");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/user_names.rs"))
+        .expect("Failed to read the model file");
+    assert!(
+        model.contains(
+            r#"    #[sea_orm(primary_key, auto_increment = false)]
+    #[sea_orm(column_name = "usr_id")]
+    pub user_id: i32,
+    #[sea_orm(column_name = "usr_nm")]
+    pub user_name: String,"#
+        ),
+        "{model}"
+    );
+}
+
+#[test]
+fn can_generate_file_headers() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let options = GenerateOptions {
+        before_save: true,
+        file_header: Some("Copyright (c) Acme Corp\n\nGenerated for `{{name}}`".to_string()),
+        ..Default::default()
+    };
+    generate_with_options(
+        &rrgen,
+        Component::Model {
+            name: "movies".to_string(),
+            with_tz: true,
+            fields: vec![("title".to_string(), "string".to_string())],
+        },
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let header = "// Copyright (c) Acme Corp\n//\n// Generated for `movies`\n\n";
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_movies.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.starts_with(&format!("{header}use loco_rs::schema::*;")),
+        "{migration}"
+    );
+    let model = fs::read_to_string(tree_fs.root.join("src/models/movies.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.starts_with(header), "{model}");
+    // injections are left as they are
+    let lib = fs::read_to_string(tree_fs.root.join("migration/src/lib.rs")).unwrap();
+    assert!(!lib.contains("Acme"), "{lib}");
+}
+
+#[test]
+fn can_generate_models_from_schema() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add(
+            "schema.yaml",
+            r#"
+models:
+  - name: author
+    fields: ["name:string!"]
+  - name: book
+    fields: ["title:string!", "author:references"]
+    without_tz: true
+"#,
+        )
+        .add(
+            "broken.json",
+            r#"{"models": [
+                {"name": "shelf", "fields": ["label:string"]},
+                {"name": "slot", "fields": ["position:integer!"]}
+            ]}"#,
+        )
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+
+    // none of the models are rendered when one of them does not parse
+    let err = generate_from_schema(
+        &rrgen,
+        &tree_fs.root.join("broken.json"),
+        &GenerateOptions::default(),
+        &appinfo,
+    )
+    .expect_err("Expected Err");
+    assert!(err.to_string().starts_with("model `slot`: "), "{err}");
+    let lib = fs::read_to_string(tree_fs.root.join("migration/src/lib.rs")).unwrap();
+    assert!(!lib.contains("shelves"), "{lib}");
+
+    let res = generate_from_schema(
+        &rrgen,
+        &tree_fs.root.join("schema.yaml"),
+        &GenerateOptions::default(),
+        &appinfo,
+    )
+    .expect("Generation failed");
+    // a migration and a model test each
+    assert_eq!(res.artifacts().len(), 4);
+
+    // in the order of the file, the referenced table first
+    let lib = fs::read_to_string(tree_fs.root.join("migration/src/lib.rs")).unwrap();
+    let authors = lib.find("_authors::Migration").expect("authors migration");
+    let books = lib.find("_books::Migration").expect("books migration");
+    assert!(authors < books, "{lib}");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_books.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"create_table_without_timestamps(m, "books","#),
+        "{migration}"
+    );
+    assert!(migration.contains(r#"("author", ""),"#), "{migration}");
+}
+
+#[test]
+fn can_generate_changed_models() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let field = |fname: &str, ftype: &str| (fname.to_string(), ftype.to_string());
+    let author = ("author".to_string(), vec![field("name", "string!")]);
+    let book = |fields: Vec<(String, String)>| ("book".to_string(), fields);
+
+    let first = generate_changed(
+        &rrgen,
+        &[
+            book(vec![
+                field("title", "string!"),
+                field("author", "references"),
+            ]),
+            author.clone(),
+        ],
+        &std::collections::HashMap::new(),
+        &GenerateOptions::default(),
+        &appinfo,
+    )
+    .expect("Generation failed");
+    // the referenced model first
+    assert_eq!(first.regenerated, vec!["author", "book"]);
+    assert_eq!(first.hashes.len(), 2);
+
+    let second = generate_changed(
+        &rrgen,
+        &[
+            book(vec![
+                field("title", "string!"),
+                field("pages", "int"),
+                field("author", "references"),
+            ]),
+            author,
+        ],
+        &first.hashes,
+        &GenerateOptions::default(),
+        &appinfo,
+    )
+    .expect("Generation failed");
+    assert_eq!(second.regenerated, vec!["book"]);
+    assert_eq!(second.hashes["author"], first.hashes["author"]);
+    assert_ne!(second.hashes["book"], first.hashes["book"]);
+    // the migration of the book only, its model test is already there
+    assert_eq!(second.results.artifacts().len(), 1);
+    let lib = fs::read_to_string(tree_fs.root.join("migration/src/lib.rs")).unwrap();
+    assert_eq!(lib.matches("_authors::Migration").count(), 1, "{lib}");
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_books.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"("pages", ColType::IntegerNull),"#),
+        "{migration}"
+    );
+}
+
+#[test]
+fn can_generate_active_period_helper() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add_empty("tests/models/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root);
+    let component = || Component::Model {
+        name: "prices".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("amount".to_string(), "int!".to_string()),
+            ("valid_during".to_string(), "tstzrange!".to_string()),
+        ],
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    let options = |column: &str| GenerateOptions {
+        active_period: Some(column.to_string()),
+        ..Default::default()
+    };
+
+    let err = generate_with_options(&rrgen, component(), &appinfo, &options("amount"))
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "active period `amount` is not a range, `is_active_at` needs a `tstzrange` column"
+    );
+
+    generate_with_options(&rrgen, component(), &appinfo, &options("valid_during"))
+        .expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_prices.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"("valid_during", ColType::TstzRange),"#),
+        "{migration}"
+    );
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/prices.rs"))
+        .expect("Failed to read the model file");
+    assert!(model.contains("use loco_rs::model::TstzRange;"), "{model}");
+    assert!(
+        model.contains(
+            "    pub fn is_active_at(&self, t: DateTimeWithTimeZone) -> bool {
+        self.valid_during
+            .parse::<TstzRange>()
+            .is_ok_and(|period| period.contains(t))
+    }"
+        ),
+        "{model}"
+    );
+}
@@ -1,6 +1,9 @@
 use super::utils::{guess_file_by_time, APP_ROUTS, MIGRATION_SRC_LIB};
 use insta::{assert_snapshot, with_settings};
-use loco_gen::{collect_messages, generate, tera_ext, AppInfo, Component, ScaffoldKind};
+use loco_gen::{
+    collect_messages, generate, generate_with_options, tera_ext, AppInfo, Component,
+    GenerateOptions, RenameAll, ScaffoldKind,
+};
 use rrgen::RRgen;
 use rstest::rstest;
 use std::fs;
@@ -136,3 +139,435 @@ fn can_generate(#[case] kind: ScaffoldKind) {
 }
 
 // thread 'templates::scaffold::can_generate::case_1' panicked at loco-gen/tests/templates/scaffold.rs:48:6:
+
+#[rstest]
+#[case(GenerateOptions::default(), &["title", "user_id"], &["status", "search", "token"])]
+#[case(GenerateOptions {
+        params_include: vec!["status".to_string()],
+        params_exclude: vec!["user_id".to_string()],
+        ..Default::default()
+    }, &["title", "status"], &["user_id", "search", "token"])]
+#[test]
+fn can_generate_request_params(
+    #[case] options: GenerateOptions,
+    #[case] included: &[&str],
+    #[case] excluded: &[&str],
+) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let component = Component::Scaffold {
+        name: "movie".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("title".to_string(), "string".to_string()),
+            ("user".to_string(), "references".to_string()),
+            (
+                "status".to_string(),
+                "state_machine:draft,published".to_string(),
+            ),
+            ("search".to_string(), "tsvector".to_string()),
+            ("token".to_string(), "string@encrypted".to_string()),
+        ],
+        kind: ScaffoldKind::Api,
+    };
+
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add_empty("src/controllers/mod.rs")
+        .add_empty("tests/models/mod.rs")
+        .add_empty("tests/requests/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/app.rs", APP_ROUTS)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root).add_template_engine(tera_ext::new());
+    generate_with_options(
+        &rrgen,
+        component,
+        &AppInfo {
+            app_name: "tester".to_string(),
+        },
+        &options,
+    )
+    .expect("Generation failed");
+
+    let controller = fs::read_to_string(tree_fs.root.join("src/controllers/movie.rs"))
+        .expect("controller file missing");
+    let params = controller
+        .split("pub struct Params {")
+        .nth(1)
+        .and_then(|rest| rest.split('}').next())
+        .expect("Params struct missing");
+    for column in included {
+        assert!(
+            params.contains(&format!("pub {column}:")),
+            "`{column}` should be in Params"
+        );
+    }
+    for column in excluded {
+        assert!(
+            !params.contains(&format!("pub {column}:")),
+            "`{column}` should not be in Params"
+        );
+    }
+}
+
+#[rstest]
+#[case("camelCase")]
+#[case("SCREAMING-KEBAB-CASE")]
+#[test]
+fn can_generate_serde_rename_all(#[case] strategy: &str) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add_empty("src/controllers/mod.rs")
+        .add_empty("tests/models/mod.rs")
+        .add_empty("tests/requests/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/app.rs", APP_ROUTS)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root).add_template_engine(tera_ext::new());
+    let component = |kind| Component::Scaffold {
+        name: "movie".to_string(),
+        with_tz: true,
+        fields: vec![("release_date".to_string(), "date".to_string())],
+        kind,
+    };
+    let options = GenerateOptions {
+        serde_rename_all: Some(strategy.parse::<RenameAll>().expect("Failed to parse")),
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    generate_with_options(&rrgen, component(ScaffoldKind::Api), &appinfo, &options)
+        .expect("Generation failed");
+
+    let controller = fs::read_to_string(tree_fs.root.join("src/controllers/movie.rs"))
+        .expect("controller file missing");
+    assert!(controller.contains(&format!(
+        "#[derive(Clone, Debug, Serialize, Deserialize)]\n#[serde(rename_all = \"{strategy}\")]\npub struct Params {{"
+    )));
+
+    let err = generate_with_options(&rrgen, component(ScaffoldKind::Html), &appinfo, &options)
+        .expect_err("Expected Err");
+    assert!(err
+        .to_string()
+        .starts_with("serde rename_all is only supported by the api scaffold"));
+}
+
+#[test]
+fn can_generate_password_field() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add_empty("src/controllers/mod.rs")
+        .add_empty("tests/models/mod.rs")
+        .add_empty("tests/requests/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/app.rs", APP_ROUTS)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root).add_template_engine(tera_ext::new());
+    let component = |password: &str| Component::Scaffold {
+        name: "account".to_string(),
+        with_tz: true,
+        fields: vec![
+            ("email".to_string(), "string^".to_string()),
+            ("password".to_string(), password.to_string()),
+        ],
+        kind: ScaffoldKind::Api,
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+
+    let err = generate_with_options(
+        &rrgen,
+        component("password^"),
+        &appinfo,
+        &GenerateOptions::default(),
+    )
+    .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "password `password` cannot be unique, each of its hashes is salted"
+    );
+
+    generate_with_options(
+        &rrgen,
+        component("password!"),
+        &appinfo,
+        &GenerateOptions::default(),
+    )
+    .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_accounts.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"("password_hash", ColType::String),"#),
+        "{migration}"
+    );
+    assert!(!migration.contains(r#"("password","#), "{migration}");
+
+    let model = fs::read_to_string(tree_fs.root.join("src/models/accounts.rs"))
+        .expect("Failed to read the model file");
+    assert!(
+        model.contains(
+            "    pub fn verify_password(&self, password: &str) -> bool {
+        loco_rs::hash::verify_password(password, &self.password_hash)
+    }"
+        ),
+        "{model}"
+    );
+    assert!(
+        model.contains(
+            "    pub fn set_password(&mut self, password: &str) -> loco_rs::Result<()> {
+        let hash = loco_rs::hash::hash_password(password)?;
+        self.password_hash = sea_orm::ActiveValue::Set(hash);"
+        ),
+        "{model}"
+    );
+
+    // the hash is neither taken in the request nor returned
+    let controller = fs::read_to_string(tree_fs.root.join("src/controllers/account.rs"))
+        .expect("controller file missing");
+    assert!(!controller.contains("pub password_hash:"), "{controller}");
+    assert!(
+        controller.contains(r#"fields.remove("password_hash");"#),
+        "{controller}"
+    );
+    assert!(
+        controller.contains("format::json(public(&load_item(&ctx, id).await?)?)"),
+        "{controller}"
+    );
+    syn::parse_file(&controller).expect("the controller should be valid Rust");
+}
+
+#[test]
+fn can_generate_tenant_scoped() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add_empty("src/controllers/mod.rs")
+        .add_empty("tests/models/mod.rs")
+        .add_empty("tests/requests/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/app.rs", APP_ROUTS)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root).add_template_engine(tera_ext::new());
+    let component = |kind| Component::Scaffold {
+        name: "project".to_string(),
+        with_tz: true,
+        fields: vec![("name".to_string(), "string!".to_string())],
+        kind,
+    };
+    let options = GenerateOptions {
+        tenant: Some("tenant".to_string()),
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    generate_with_options(&rrgen, component(ScaffoldKind::Api), &appinfo, &options)
+        .expect("Generation failed");
+
+    let migration_file = guess_file_by_time(
+        &tree_fs.root.join("migration/src"),
+        "m{TIME}_projects.rs",
+        3,
+    )
+    .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(migration.contains(r#"("tenant", ""),"#), "{migration}");
+
+    // the tenant is set from the request's, and scopes every query
+    let controller = fs::read_to_string(tree_fs.root.join("src/controllers/project.rs"))
+        .expect("controller file missing");
+    assert!(!controller.contains("pub tenant_id:"), "{controller}");
+    assert!(
+        controller.contains(
+            "        .filter(Column::TenantId.eq(tenant.pk()?))
+        .one(&tenant.db)"
+        ),
+        "{controller}"
+    );
+    assert!(
+        controller.contains(".eq(Column::TenantId, tenant.pk()?)"),
+        "{controller}"
+    );
+    assert!(
+        controller.contains("        tenant_id: Set(tenant.pk()?),"),
+        "{controller}"
+    );
+    assert!(
+        controller.contains(
+            "pub async fn remove(Path(id): Path<i32>, tenant: Tenant) -> Result<Response> {
+    load_item(&tenant, id).await?.delete(&tenant.db).await?;"
+        ),
+        "{controller}"
+    );
+    assert!(!controller.contains("ctx"), "{controller}");
+    syn::parse_file(&controller).expect("the controller should be valid Rust");
+
+    let err = generate_with_options(&rrgen, component(ScaffoldKind::Html), &appinfo, &options)
+        .expect_err("Expected Err");
+    assert_eq!(
+        err.to_string(),
+        "tenant scoped scaffolds are only supported by the api scaffold"
+    );
+}
+
+#[rstest]
+#[case(ScaffoldKind::Api)]
+#[case(ScaffoldKind::Html)]
+#[case(ScaffoldKind::Htmx)]
+#[test]
+fn can_generate_soft_delete(#[case] kind: ScaffoldKind) {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add_empty("src/controllers/mod.rs")
+        .add_empty("tests/models/mod.rs")
+        .add_empty("src/views/mod.rs")
+        .add_empty("tests/requests/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/app.rs", APP_ROUTS)
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root).add_template_engine(tera_ext::new());
+    let component = Component::Scaffold {
+        name: "post".to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string!".to_string())],
+        kind,
+    };
+    let options = GenerateOptions {
+        soft_delete: true,
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    generate_with_options(&rrgen, component, &appinfo, &options).expect("Generation failed");
+
+    let migration_file =
+        guess_file_by_time(&tree_fs.root.join("migration/src"), "m{TIME}_posts.rs", 3)
+            .expect("Failed to find the generated migration file");
+    let migration = fs::read_to_string(migration_file).unwrap();
+    assert!(
+        migration.contains(r#"("deleted_at", ColType::TimestampWithTimeZoneNull),"#),
+        "{migration}"
+    );
+
+    let model =
+        fs::read_to_string(tree_fs.root.join("src/models/posts.rs")).expect("model file missing");
+    assert!(
+        model.contains("impl loco_rs::model::SoftDeletable for Entity {"),
+        "{model}"
+    );
+    syn::parse_file(&model).expect("the model should be valid Rust");
+
+    // removed items are soft deleted, and left out unless asked for
+    let controller = fs::read_to_string(tree_fs.root.join("src/controllers/post.rs"))
+        .expect("controller file missing");
+    assert!(!controller.contains("pub deleted_at:"), "{controller}");
+    assert!(
+        controller.contains("pub include_deleted: Option<bool>,"),
+        "{controller}"
+    );
+    assert!(
+        controller.contains("Entity::soft_delete(&ctx.db, load_item(&ctx, id).await?).await?;"),
+        "{controller}"
+    );
+    assert!(controller.contains("Entity::find_active()"), "{controller}");
+    assert!(!controller.contains(".delete(&ctx.db)"), "{controller}");
+    syn::parse_file(&controller).expect("the controller should be valid Rust");
+}
+
+#[test]
+fn can_generate_api_docs() {
+    std::env::set_var("SKIP_MIGRATION", "");
+    let tree_fs = tree_fs::TreeBuilder::default()
+        .drop(true)
+        .add_empty("src/controllers/mod.rs")
+        .add_empty("tests/models/mod.rs")
+        .add_empty("tests/requests/mod.rs")
+        .add("src/models/mod.rs", "pub mod _entities;")
+        .add("migration/src/lib.rs", MIGRATION_SRC_LIB)
+        .add("src/app.rs", APP_ROUTS)
+        .add(
+            "Cargo.toml",
+            "[package]\nname = \"tester\"\n\n[dependencies]\nloco-rs = \"*\"\n",
+        )
+        .create()
+        .unwrap();
+
+    let rrgen = RRgen::with_working_dir(&tree_fs.root).add_template_engine(tera_ext::new());
+    let component = |name: &str, kind| Component::Scaffold {
+        name: name.to_string(),
+        with_tz: true,
+        fields: vec![("title".to_string(), "string".to_string())],
+        kind,
+    };
+    let options = GenerateOptions {
+        api_docs: true,
+        ..Default::default()
+    };
+    let appinfo = AppInfo {
+        app_name: "tester".to_string(),
+    };
+    for name in ["movie", "book"] {
+        generate_with_options(
+            &rrgen,
+            component(name, ScaffoldKind::Api),
+            &appinfo,
+            &options,
+        )
+        .expect("Generation failed");
+    }
+
+    let controller = fs::read_to_string(tree_fs.root.join("src/controllers/movie.rs"))
+        .expect("controller file missing");
+    syn::parse_file(&controller).expect("the controller should be valid Rust");
+    assert!(controller.contains("#[openapi(paths(list, add, update, remove, get_one))]"));
+    assert!(controller.contains("Deserialize, ToSchema)]\npub struct Params {"));
+
+    // the spec is served once, and merges both controllers'
+    let api_docs = fs::read_to_string(tree_fs.root.join("src/controllers/api_docs.rs"))
+        .expect("api docs controller missing");
+    syn::parse_file(&api_docs).expect("the api docs controller should be valid Rust");
+    assert!(api_docs.contains("    spec.merge(super::movie::ApiDoc::openapi());"));
+    assert!(api_docs.contains("    spec.merge(super::book::ApiDoc::openapi());"));
+    let app = fs::read_to_string(tree_fs.root.join("src/app.rs")).expect("app.rs missing");
+    assert_eq!(app.matches("controllers::api_docs::routes()").count(), 1);
+    let cargo = fs::read_to_string(tree_fs.root.join("Cargo.toml")).expect("Cargo.toml missing");
+    assert_eq!(cargo.matches("utoipa = ").count(), 1);
+
+    let err = generate_with_options(
+        &rrgen,
+        component("show", ScaffoldKind::Html),
+        &appinfo,
+        &options,
+    )
+    .expect_err("Expected Err");
+    assert!(err
+        .to_string()
+        .starts_with("api docs are only supported by api controllers and scaffolds"));
+}
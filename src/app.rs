@@ -1,7 +1,11 @@
 //! This module contains the core components and traits for building a web
 //! server application.
 #[cfg(feature = "with-db")]
-use {sea_orm::DatabaseConnection, std::path::Path};
+use {
+    crate::tenancy::{self, TenantResolver},
+    sea_orm::DatabaseConnection,
+    std::path::Path,
+};
 
 use std::{
     any::{Any, TypeId},
@@ -17,6 +21,7 @@ use crate::{
     bgworker::{self, Queue},
     boot::{shutdown_signal, BootResult, ServeParams, StartMode},
     cache::{self},
+    channels::Channels,
     config::Config,
     controller::{
         middleware::{self, MiddlewareLayer},
@@ -269,6 +274,8 @@ pub struct AppContext {
     pub cache: Arc<cache::Cache>,
     /// Shared store for arbitrary application data
     pub shared_store: Arc<SharedStore>,
+    /// Realtime channels, registered in [`Hooks::register_channels`]
+    pub channels: Arc<Channels>,
 }
 
 /// A trait that defines hooks for customizing and extending the behavior of a
@@ -423,6 +430,11 @@ pub trait Hooks: Send {
     /// Registers custom tasks with the provided [`Tasks`] object.
     fn register_tasks(tasks: &mut Tasks);
 
+    /// Registers the realtime channels the app broadcasts on. Their
+    /// subscribers are served by the routes the app mounts, see
+    /// [`crate::channels`].
+    fn register_channels(_channels: &mut Channels) {}
+
     /// Truncates the database as required. Users should implement this
     /// function. The truncate controlled from the [`crate::config::Database`]
     /// by changing `dangerously_truncate` to true (default false).
@@ -431,9 +443,41 @@ pub trait Hooks: Send {
     #[cfg(feature = "with-db")]
     async fn truncate(_ctx: &AppContext) -> Result<()>;
 
-    /// Seeds the database with initial data.
+    /// Seeds the database with initial data, by default by loading the
+    /// fixtures of `path`, see [`crate::fixtures`]. They are upserted with
+    /// `db seed --upsert`, see [`crate::fixtures::Mode::of`].
+    #[cfg(feature = "with-db")]
+    async fn seed(ctx: &AppContext, path: &Path) -> Result<()> {
+        crate::fixtures::load(
+            &ctx.db,
+            path,
+            &ctx.environment,
+            crate::fixtures::Mode::of(ctx),
+        )
+        .await
+    }
+
+    /// Resolves the tenant of a request when `tenancy` is configured, by
+    /// default as its `resolver` says. Override it to e.g. look tenants up by
+    /// custom domain.
     #[cfg(feature = "with-db")]
-    async fn seed(_ctx: &AppContext, path: &Path) -> Result<()>;
+    #[must_use]
+    fn tenant_resolver(config: &crate::config::Tenancy) -> Box<dyn TenantResolver> {
+        tenancy::resolver(&config.resolver)
+    }
+
+    /// The tenants `db migrate` migrates along with the app's database, by
+    /// default the configured `tenancy.tenants`. Override it when tenants are
+    /// kept in the database.
+    #[cfg(feature = "with-db")]
+    async fn tenants(ctx: &AppContext) -> Result<Vec<String>> {
+        Ok(ctx
+            .config
+            .tenancy
+            .as_ref()
+            .map(|tenancy| tenancy.tenants.clone())
+            .unwrap_or_default())
+    }
 
     /// Called when the application is shutting down.
     /// This function allows users to perform any necessary cleanup or final
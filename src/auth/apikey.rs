@@ -0,0 +1,317 @@
+//! # API Keys
+//!
+//! Keys for machine-to-machine access, kept in the `api_keys` table that
+//! `cargo loco generate api_keys` creates. A key is shown once, when it is
+//! issued: only its SHA-256 hash is stored, along with a short prefix that
+//! names the key in listings and when revoking it.
+//!
+//! A key is scoped, e.g. `reports:read`, where `*` grants every scope, and
+//! can expire. Requests present it in the `x-api-key` header, see
+//! [`crate::controller::extractor::auth::ApiKey`].
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    sea_query::{Alias, Expr, Order, Query},
+    ConnectionTrait, DatabaseConnection, QueryResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{hash, Result};
+
+/// The table the keys are kept in.
+pub const TABLE: &str = "api_keys";
+
+/// What every key starts with, telling it apart from other secrets.
+const KEY_PREFIX: &str = "lk";
+
+/// The length of the random part of the prefix naming a key.
+const PREFIX_LEN: usize = 8;
+
+/// The length of the secret part of a key.
+const SECRET_LEN: usize = 32;
+
+/// The scope granting every other scope.
+const ANY_SCOPE: &str = "*";
+
+/// An issued API key, as stored. The key itself is not, only its hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub name: String,
+    /// Names the key, e.g. `lk_3fA9c2dQ`, the key starts with it.
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    pub revoked_at: Option<DateTime<FixedOffset>>,
+}
+
+impl ApiKey {
+    /// Whether the key grants `scope`.
+    #[must_use]
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == ANY_SCOPE || s == scope)
+    }
+
+    /// Whether the key expired by `now`.
+    #[must_use]
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether the key is neither revoked nor expired.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && !self.is_expired(Utc::now())
+    }
+
+    fn from_row(row: &QueryResult) -> Result<Self> {
+        let scopes: Option<String> = row.try_get("", "scopes")?;
+        Ok(Self {
+            name: row.try_get("", "name")?,
+            prefix: row.try_get("", "prefix")?,
+            scopes: scopes
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+            expires_at: row.try_get("", "expires_at")?,
+            revoked_at: row.try_get("", "revoked_at")?,
+        })
+    }
+}
+
+/// A newly issued key, the only time the key itself is at hand.
+#[derive(Debug, Clone)]
+pub struct IssuedKey {
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+/// Generates a key, e.g. `lk_3fA9c2dQ_<32 characters>`, and the prefix
+/// naming it.
+#[must_use]
+pub fn generate() -> (String, String) {
+    let prefix = format!("{KEY_PREFIX}_{}", hash::random_string(PREFIX_LEN));
+    let key = format!("{prefix}_{}", hash::random_string(SECRET_LEN));
+    (key, prefix)
+}
+
+/// Hashes a key the way it is stored. The keys are random and long, so a
+/// fast hash is as good as a password hash and lets them be looked up by it.
+#[must_use]
+pub fn hash_key(key: &str) -> String {
+    hash::sha256_hex(key.as_bytes())
+}
+
+/// Issues a key named `name`, granting `scopes` until `expires_at`, if given.
+///
+/// # Errors
+///
+/// When the key cannot be stored, e.g. the `api_keys` table is missing
+pub async fn issue(
+    db: &DatabaseConnection,
+    name: &str,
+    scopes: &[String],
+    expires_at: Option<DateTime<FixedOffset>>,
+) -> Result<IssuedKey> {
+    let (key, prefix) = generate();
+    let now: DateTime<FixedOffset> = Utc::now().into();
+    let insert = Query::insert()
+        .into_table(Alias::new(TABLE))
+        .columns([
+            Alias::new("name"),
+            Alias::new("prefix"),
+            Alias::new("key_hash"),
+            Alias::new("scopes"),
+            Alias::new("expires_at"),
+            Alias::new("created_at"),
+            Alias::new("updated_at"),
+        ])
+        .values_panic([
+            name.into(),
+            prefix.as_str().into(),
+            hash_key(&key).into(),
+            scopes.join(" ").into(),
+            expires_at.into(),
+            now.into(),
+            now.into(),
+        ])
+        .to_owned();
+    db.execute(db.get_database_backend().build(&insert)).await?;
+
+    Ok(IssuedKey {
+        key,
+        api_key: ApiKey {
+            name: name.to_string(),
+            prefix,
+            scopes: scopes.to_vec(),
+            expires_at,
+            revoked_at: None,
+        },
+    })
+}
+
+/// Finds the key given in a request by its hash, revoked or expired ones
+/// included.
+///
+/// # Errors
+///
+/// When the key cannot be looked up
+pub async fn find(db: &DatabaseConnection, key: &str) -> Result<Option<ApiKey>> {
+    let select = Query::select()
+        .columns(columns())
+        .from(Alias::new(TABLE))
+        .and_where(Expr::col(Alias::new("key_hash")).eq(hash_key(key)))
+        .to_owned();
+    db.query_one(db.get_database_backend().build(&select))
+        .await?
+        .as_ref()
+        .map(ApiKey::from_row)
+        .transpose()
+}
+
+/// Lists the keys, latest first.
+///
+/// # Errors
+///
+/// When the keys cannot be read
+pub async fn list(db: &DatabaseConnection) -> Result<Vec<ApiKey>> {
+    let select = Query::select()
+        .columns(columns())
+        .from(Alias::new(TABLE))
+        .order_by(Alias::new("created_at"), Order::Desc)
+        .to_owned();
+    db.query_all(db.get_database_backend().build(&select))
+        .await?
+        .iter()
+        .map(ApiKey::from_row)
+        .collect()
+}
+
+/// Revokes the key named by `prefix`, returning whether there was one to
+/// revoke.
+///
+/// # Errors
+///
+/// When the key cannot be updated
+pub async fn revoke(db: &DatabaseConnection, prefix: &str) -> Result<bool> {
+    let now: DateTime<FixedOffset> = Utc::now().into();
+    let update = Query::update()
+        .table(Alias::new(TABLE))
+        .values([
+            (Alias::new("revoked_at"), now.into()),
+            (Alias::new("updated_at"), now.into()),
+        ])
+        .and_where(Expr::col(Alias::new("prefix")).eq(prefix))
+        .and_where(Expr::col(Alias::new("revoked_at")).is_null())
+        .to_owned();
+    let res = db.execute(db.get_database_backend().build(&update)).await?;
+    Ok(res.rows_affected() > 0)
+}
+
+fn columns() -> [Alias; 5] {
+    [
+        Alias::new("name"),
+        Alias::new("prefix"),
+        Alias::new("scopes"),
+        Alias::new("expires_at"),
+        Alias::new("revoked_at"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use sea_orm::{ConnectOptions, Database};
+    use sea_orm_migration::SchemaManager;
+
+    use super::*;
+    use crate::schema::{create_table, ColType};
+
+    async fn setup() -> DatabaseConnection {
+        let mut opt = ConnectOptions::new("sqlite::memory:");
+        opt.max_connections(1);
+        let db = Database::connect(opt).await.unwrap();
+        let m = SchemaManager::new(&db);
+        create_table(
+            &m,
+            TABLE,
+            &[
+                ("id", ColType::PkAuto),
+                ("name", ColType::String),
+                ("prefix", ColType::String),
+                ("key_hash", ColType::StringUniq),
+                ("scopes", ColType::TextNull),
+                ("expires_at", ColType::TimestampWithTimeZoneNull),
+                ("revoked_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn can_generate_key() {
+        let (key, prefix) = generate();
+        assert!(key.starts_with(&format!("{prefix}_")));
+        assert_eq!(prefix.len(), KEY_PREFIX.len() + 1 + PREFIX_LEN);
+        assert_eq!(key.len(), prefix.len() + 1 + SECRET_LEN);
+        assert_ne!(generate().0, key);
+        assert_eq!(hash_key(&key), hash_key(&key));
+        assert_ne!(hash_key(&key), key);
+    }
+
+    #[test]
+    fn can_check_scopes_and_expiry() {
+        let now = Utc::now();
+        let mut key = ApiKey {
+            name: "reports".to_string(),
+            prefix: "lk_abcdefgh".to_string(),
+            scopes: vec!["reports:read".to_string()],
+            expires_at: Some((now + Duration::hours(1)).into()),
+            revoked_at: None,
+        };
+        assert!(key.has_scope("reports:read"));
+        assert!(!key.has_scope("reports:write"));
+        assert!(key.is_active());
+        assert!(key.is_expired(now + Duration::hours(2)));
+
+        key.scopes = vec![ANY_SCOPE.to_string()];
+        assert!(key.has_scope("reports:write"));
+
+        key.revoked_at = Some(now.into());
+        assert!(!key.is_active());
+    }
+
+    #[tokio::test]
+    async fn can_issue_find_and_revoke_key() {
+        let db = setup().await;
+        let scopes = vec!["reports:read".to_string(), "reports:write".to_string()];
+        let issued = issue(&db, "reports", &scopes, None).await.unwrap();
+
+        let found = find(&db, &issued.key).await.unwrap().unwrap();
+        assert_eq!(found, issued.api_key);
+        assert!(found.is_active());
+        assert!(find(&db, "lk_unknown").await.unwrap().is_none());
+        assert_eq!(list(&db).await.unwrap(), vec![issued.api_key.clone()]);
+
+        assert!(revoke(&db, &issued.api_key.prefix).await.unwrap());
+        assert!(!revoke(&db, &issued.api_key.prefix).await.unwrap());
+        let found = find(&db, &issued.key).await.unwrap().unwrap();
+        assert!(found.revoked_at.is_some());
+        assert!(!found.is_active());
+    }
+
+    #[tokio::test]
+    async fn can_issue_expiring_key() {
+        let db = setup().await;
+        let expires_at = Utc::now() - Duration::minutes(1);
+        let issued = issue(&db, "old", &[], Some(expires_at.into()))
+            .await
+            .unwrap();
+
+        let found = find(&db, &issued.key).await.unwrap().unwrap();
+        assert!(found.scopes.is_empty());
+        assert!(!found.is_active());
+    }
+}
@@ -1,2 +1,4 @@
+#[cfg(feature = "with-db")]
+pub mod apikey;
 #[cfg(feature = "auth_jwt")]
 pub mod jwt;
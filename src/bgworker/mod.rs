@@ -6,6 +6,7 @@ use std::{
 };
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 #[cfg(feature = "cli")]
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
@@ -23,6 +24,7 @@ use crate::{
         self, Config, PostgresQueueConfig, QueueConfig, RedisQueueConfig, SqliteQueueConfig,
         WorkerMode,
     },
+    scheduler::Run,
     Error, Result,
 };
 
@@ -62,6 +64,106 @@ impl std::fmt::Display for JobStatus {
     }
 }
 
+/// A job waiting to run, as listed by [`Queue::pending_jobs`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingJob {
+    pub id: String,
+    pub name: String,
+    pub run_at: DateTime<Utc>,
+    #[serde(default)]
+    pub priority: i32,
+    pub tags: Option<Vec<String>>,
+}
+
+impl PendingJob {
+    /// Whether the job waits for a later time, rather than for a worker
+    #[must_use]
+    pub fn is_delayed(&self) -> bool {
+        self.run_at > Utc::now()
+    }
+}
+
+/// How the failed jobs of a worker are retried, see
+/// [`BackgroundWorker::retry_policy`]. The delay doubles with every attempt,
+/// from `base_delay` up to `max_delay`. With `jitter`, each delay is randomized
+/// between half and all of it, so jobs failing together are not all retried
+/// together.
+///
+/// Jobs that fail their last attempt are kept as [`JobStatus::Failed`], the
+/// dead letters of the queue, until retried or purged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first one included
+    pub max_attempts: u32,
+    pub base_delay: std::time::Duration,
+    pub max_delay: std::time::Duration,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl RetryPolicy {
+    /// A policy giving jobs `max_attempts` attempts, retried after 10 seconds,
+    /// 20 seconds and so on, up to an hour.
+    #[must_use]
+    pub const fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            base_delay: std::time::Duration::from_secs(10),
+            max_delay: std::time::Duration::from_secs(60 * 60),
+            jitter: true,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_backoff(
+        mut self,
+        base_delay: std::time::Duration,
+        max_delay: std::time::Duration,
+    ) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    #[must_use]
+    pub const fn without_jitter(mut self) -> Self {
+        self.jitter = false;
+        self
+    }
+
+    /// The delay before retrying a job that failed its `attempt`th attempt
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        if self.jitter {
+            use rand::Rng;
+            rand::rng().random_range(delay / 2..=delay)
+        } else {
+            delay
+        }
+    }
+
+    /// When to retry a job that failed its `attempt`th attempt, `None` once its
+    /// attempts are exhausted.
+    #[must_use]
+    pub fn retry_at(&self, attempt: u32) -> Option<DateTime<Utc>> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let delay = chrono::Duration::from_std(self.delay(attempt)).ok()?;
+        Some(Utc::now() + delay)
+    }
+}
+
 // Queue struct now holds both a QueueProvider and QueueRegistrar
 pub enum Queue {
     #[cfg(feature = "bg_redis")]
@@ -88,13 +190,26 @@ pub enum Queue {
     None,
 }
 
+impl std::fmt::Debug for Queue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "bg_redis")]
+            Self::Redis(..) => f.write_str("Redis"),
+            #[cfg(feature = "bg_pg")]
+            Self::Postgres(..) => f.write_str("Postgres"),
+            #[cfg(feature = "bg_sqlt")]
+            Self::Sqlite(..) => f.write_str("Sqlite"),
+            Self::None => f.write_str("None"),
+        }
+    }
+}
+
 impl Queue {
     /// Add a job to the queue
     ///
     /// # Errors
     ///
     /// This function will return an error if fails
-    #[allow(unused_variables)]
     pub async fn enqueue<A: Serialize + Send + Sync>(
         &self,
         class: String,
@@ -102,34 +217,56 @@ impl Queue {
         args: A,
         tags: Option<Vec<String>>,
     ) -> Result<()> {
-        tracing::debug!(worker = class, queue = ?queue, tags = ?tags, "Enqueuing background job");
+        self.enqueue_at(class, queue, args, tags, Utc::now(), 0)
+            .await
+    }
+
+    /// Add a job to the queue, to run at `run_at`. Of the jobs due, those with
+    /// a higher `priority` run first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if fails
+    #[allow(unused_variables)]
+    pub async fn enqueue_at<A: Serialize + Send + Sync>(
+        &self,
+        class: String,
+        queue: Option<String>,
+        args: A,
+        tags: Option<Vec<String>>,
+        run_at: DateTime<Utc>,
+        priority: i32,
+    ) -> Result<()> {
+        tracing::debug!(worker = class, queue = ?queue, tags = ?tags, run_at = %run_at, priority, "Enqueuing background job");
         match self {
             #[cfg(feature = "bg_redis")]
             Self::Redis(pool, _, _, _) => {
-                redis::enqueue(pool, class, queue, args, tags).await?;
+                redis::enqueue_at(pool, class, queue, args, tags, run_at, priority).await?;
             }
             #[cfg(feature = "bg_pg")]
             Self::Postgres(pool, _, _, _) => {
-                pg::enqueue(
+                pg::enqueue_with_priority(
                     pool,
                     &class,
                     serde_json::to_value(args)?,
-                    chrono::Utc::now(),
+                    run_at,
                     None,
                     tags,
+                    priority,
                 )
                 .await
                 .map_err(Box::from)?;
             }
             #[cfg(feature = "bg_sqlt")]
             Self::Sqlite(pool, _, _, _) => {
-                sqlt::enqueue(
+                sqlt::enqueue_with_priority(
                     pool,
                     &class,
                     serde_json::to_value(args)?,
-                    chrono::Utc::now(),
+                    run_at,
                     None,
                     tags,
+                    priority,
                 )
                 .await
                 .map_err(Box::from)?;
@@ -256,7 +393,9 @@ impl Queue {
     pub async fn setup(&self) -> Result<()> {
         match self {
             #[cfg(feature = "bg_redis")]
-            Self::Redis(_, _, _, _) => {}
+            Self::Redis(pool, _, _, _) => {
+                redis::initialize(pool).await?;
+            }
             #[cfg(feature = "bg_pg")]
             Self::Postgres(pool, _, _, _) => {
                 pg::initialize_database(pool).await.map_err(Box::from)?;
@@ -388,6 +527,25 @@ impl Queue {
         }
     }
 
+    /// Lists the jobs waiting to run, the ones due first, by priority, then the
+    /// delayed ones by when they run.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if fails
+    pub async fn pending_jobs(&self) -> Result<Vec<PendingJob>> {
+        let jobs = self.get_jobs(Some(&vec![JobStatus::Queued]), None).await?;
+        let mut jobs: Vec<PendingJob> = serde_json::from_value(jobs)?;
+        jobs.sort_by_key(|job| {
+            if job.is_delayed() {
+                (true, 0, job.run_at)
+            } else {
+                (false, -i64::from(job.priority), job.run_at)
+            }
+        });
+        Ok(jobs)
+    }
+
     /// Cancels jobs based on the given job name for the configured queue provider.
     ///
     /// # Errors
@@ -498,6 +656,121 @@ impl Queue {
         }
     }
 
+    /// Queues a failed job again, with its attempts reset.
+    ///
+    /// # Errors
+    /// - If no queue provider is configured, it will return an error indicating the lack of configuration.
+    /// - If there is no failed job with the given id.
+    /// - Any error in the underlying provider's retry logic will propagate from the respective function.
+    pub async fn retry_job(&self, id: &str) -> Result<()> {
+        tracing::info!(job_id = id, "Retrying failed job");
+        match self {
+            #[cfg(feature = "bg_pg")]
+            Self::Postgres(pool, _, _, _) => pg::retry_failed_job(pool, id).await,
+            #[cfg(feature = "bg_sqlt")]
+            Self::Sqlite(pool, _, _, _) => sqlt::retry_failed_job(pool, id).await,
+            #[cfg(feature = "bg_redis")]
+            Self::Redis(pool, _, _, _) => redis::retry_failed_job(pool, id).await,
+            Self::None => {
+                tracing::error!(
+                    "No queue provider is configured: compile with at least one queue provider feature"
+                );
+                Err(Error::string("provider not configured"))
+            }
+        }
+    }
+
+    /// Claims the run of a scheduler job for the tick at `scheduled_at`,
+    /// recording it as running. Returns `None` when another instance claimed
+    /// the tick already, or when, with `no_overlap`, a run of the job started
+    /// within `lock_timeout` is still running: the tick is then recorded as
+    /// skipped.
+    ///
+    /// # Errors
+    /// - If no queue provider is configured, it will return an error indicating the lack of configuration.
+    /// - Any error in the underlying provider's logic will propagate from the respective function.
+    #[allow(unused_variables)]
+    pub async fn claim_scheduled_run(
+        &self,
+        job_name: &str,
+        scheduled_at: DateTime<Utc>,
+        no_overlap: bool,
+        lock_timeout: std::time::Duration,
+    ) -> Result<Option<Run>> {
+        let lock_timeout = chrono::Duration::from_std(lock_timeout)
+            .map_err(|err| Error::Message(format!("invalid lock timeout: {err}")))?;
+        match self {
+            #[cfg(feature = "bg_pg")]
+            Self::Postgres(pool, _, _, _) => {
+                pg::claim_scheduled_run(pool, job_name, scheduled_at, no_overlap, lock_timeout)
+                    .await
+            }
+            #[cfg(feature = "bg_sqlt")]
+            Self::Sqlite(pool, _, _, _) => {
+                sqlt::claim_scheduled_run(pool, job_name, scheduled_at, no_overlap, lock_timeout)
+                    .await
+            }
+            #[cfg(feature = "bg_redis")]
+            Self::Redis(pool, _, _, _) => {
+                redis::claim_scheduled_run(pool, job_name, scheduled_at, no_overlap, lock_timeout)
+                    .await
+            }
+            Self::None => {
+                tracing::error!(
+                    "No queue provider is configured: compile with at least one queue provider feature"
+                );
+                Err(Error::string("provider not configured"))
+            }
+        }
+    }
+
+    /// Records the outcome of a run claimed with
+    /// [`Queue::claim_scheduled_run`].
+    ///
+    /// # Errors
+    /// - If no queue provider is configured, it will return an error indicating the lack of configuration.
+    /// - Any error in the underlying provider's logic will propagate from the respective function.
+    #[allow(unused_variables)]
+    pub async fn finish_scheduled_run(&self, run: &Run) -> Result<()> {
+        match self {
+            #[cfg(feature = "bg_pg")]
+            Self::Postgres(pool, _, _, _) => pg::finish_scheduled_run(pool, run).await,
+            #[cfg(feature = "bg_sqlt")]
+            Self::Sqlite(pool, _, _, _) => sqlt::finish_scheduled_run(pool, run).await,
+            #[cfg(feature = "bg_redis")]
+            Self::Redis(pool, _, _, _) => redis::finish_scheduled_run(pool, run).await,
+            Self::None => {
+                tracing::error!(
+                    "No queue provider is configured: compile with at least one queue provider feature"
+                );
+                Err(Error::string("provider not configured"))
+            }
+        }
+    }
+
+    /// The latest recorded runs of scheduler jobs, most recent first.
+    ///
+    /// # Errors
+    /// - If no queue provider is configured, it will return an error indicating the lack of configuration.
+    /// - Any error in the underlying provider's logic will propagate from the respective function.
+    #[allow(unused_variables)]
+    pub async fn scheduled_runs(&self, limit: usize) -> Result<Vec<Run>> {
+        match self {
+            #[cfg(feature = "bg_pg")]
+            Self::Postgres(pool, _, _, _) => pg::scheduled_runs(pool, limit).await,
+            #[cfg(feature = "bg_sqlt")]
+            Self::Sqlite(pool, _, _, _) => sqlt::scheduled_runs(pool, limit).await,
+            #[cfg(feature = "bg_redis")]
+            Self::Redis(pool, _, _, _) => redis::scheduled_runs(pool, limit).await,
+            Self::None => {
+                tracing::error!(
+                    "No queue provider is configured: compile with at least one queue provider feature"
+                );
+                Err(Error::string("provider not configured"))
+            }
+        }
+    }
+
     /// Dumps the list of jobs to a YAML file at the specified path.
     ///
     /// This function retrieves jobs from the queue, optionally filtered by their status, and
@@ -553,7 +826,15 @@ impl Queue {
             Self::Postgres(_, _, _, _) => {
                 let jobs: Vec<pg::Job> = serde_yaml::from_reader(File::open(path)?)?;
                 for job in jobs {
-                    self.enqueue(job.name.clone(), None, job.data, None).await?;
+                    self.enqueue_at(
+                        job.name.clone(),
+                        None,
+                        job.data,
+                        None,
+                        Utc::now(),
+                        job.priority,
+                    )
+                    .await?;
                 }
 
                 Ok(())
@@ -562,7 +843,15 @@ impl Queue {
             Self::Sqlite(_, _, _, _) => {
                 let jobs: Vec<sqlt::Job> = serde_yaml::from_reader(File::open(path)?)?;
                 for job in jobs {
-                    self.enqueue(job.name.clone(), None, job.data, None).await?;
+                    self.enqueue_at(
+                        job.name.clone(),
+                        None,
+                        job.data,
+                        None,
+                        Utc::now(),
+                        job.priority,
+                    )
+                    .await?;
                 }
                 Ok(())
             }
@@ -570,7 +859,15 @@ impl Queue {
             Self::Redis(_, _, _, _) => {
                 let jobs: Vec<redis::Job> = serde_yaml::from_reader(File::open(path)?)?;
                 for job in jobs {
-                    self.enqueue(job.name.clone(), None, job.data, None).await?;
+                    self.enqueue_at(
+                        job.name.clone(),
+                        None,
+                        job.data,
+                        None,
+                        Utc::now(),
+                        job.priority,
+                    )
+                    .await?;
                 }
                 Ok(())
             }
@@ -594,6 +891,20 @@ pub trait BackgroundWorker<A: Send + Sync + serde::Serialize + 'static>: Send +
         None
     }
 
+    /// The priority of this worker's jobs: of the jobs due, those with a higher
+    /// priority run first.
+    #[must_use]
+    fn priority() -> i32 {
+        0
+    }
+
+    /// How the failed jobs of this worker are retried. By default they are not,
+    /// and go straight to the dead letters.
+    #[must_use]
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
     /// Specifies tags associated with this worker. Workers might only process jobs
     /// matching specific tags during startup.
     #[must_use]
@@ -613,6 +924,34 @@ pub trait BackgroundWorker<A: Send + Sync + serde::Serialize + 'static>: Send +
         name.to_upper_camel_case()
     }
     async fn perform_later(ctx: &AppContext, args: A) -> crate::Result<()>
+    where
+        Self: Sized,
+    {
+        Self::perform_at(ctx, Utc::now(), args).await
+    }
+
+    /// Performs the job after `delay`, see [`BackgroundWorker::perform_at`].
+    ///
+    /// # Errors
+    ///
+    /// When `delay` is out of range, or the job fails to enqueue
+    async fn perform_in(ctx: &AppContext, delay: std::time::Duration, args: A) -> crate::Result<()>
+    where
+        Self: Sized,
+    {
+        let delay = chrono::Duration::from_std(delay)
+            .map_err(|err| Error::Message(format!("invalid job delay: {err}")))?;
+        Self::perform_at(ctx, Utc::now() + delay, args).await
+    }
+
+    /// Performs the job at `run_at`. In `ForegroundBlocking` mode, the job is
+    /// performed right away.
+    ///
+    /// # Errors
+    ///
+    /// When the job fails to enqueue, or to perform in `ForegroundBlocking`
+    /// mode
+    async fn perform_at(ctx: &AppContext, run_at: DateTime<Utc>, args: A) -> crate::Result<()>
     where
         Self: Sized,
     {
@@ -621,8 +960,15 @@ pub trait BackgroundWorker<A: Send + Sync + serde::Serialize + 'static>: Send +
                 if let Some(p) = &ctx.queue_provider {
                     let tags = Self::tags();
                     let tags_option = if tags.is_empty() { None } else { Some(tags) };
-                    p.enqueue(Self::class_name(), Self::queue(), args, tags_option)
-                        .await?;
+                    p.enqueue_at(
+                        Self::class_name(),
+                        Self::queue(),
+                        args,
+                        tags_option,
+                        run_at,
+                        Self::priority(),
+                    )
+                    .await?;
                 } else {
                     tracing::error!(
                         "perform_later: background queue is selected, but queue was not populated \
@@ -636,6 +982,9 @@ pub trait BackgroundWorker<A: Send + Sync + serde::Serialize + 'static>: Send +
             WorkerMode::BackgroundAsync => {
                 let dx = ctx.clone();
                 tokio::spawn(async move {
+                    if let Ok(delay) = (run_at - Utc::now()).to_std() {
+                        tokio::time::sleep(delay).await;
+                    }
                     if let Err(err) = Self::build(&dx).perform(args).await {
                         tracing::error!(err = err.to_string(), "worker failed to perform job");
                     }
@@ -792,6 +1141,54 @@ mod tests {
         assert_debug_snapshot!(std::fs::read_to_string(dump_file).unwrap());
     }
 
+    #[tokio::test]
+    async fn can_list_pending_jobs() {
+        let tree_fs = tree_fs::TreeBuilder::default()
+            .drop(true)
+            .create()
+            .expect("create temp folder");
+        let qcfg = sqlite_config(tree_fs.root.as_path());
+        let queue = sqlt::create_provider(&qcfg)
+            .await
+            .expect("create sqlite queue");
+        queue.setup().await.expect("setup sqlite db");
+
+        let now = Utc::now();
+        for (name, run_at, priority) in [
+            ("Later", now + chrono::Duration::hours(2), 10),
+            ("Soon", now + chrono::Duration::hours(1), 0),
+            ("Default", now - chrono::Duration::minutes(1), 0),
+            ("High", now - chrono::Duration::minutes(1), 5),
+        ] {
+            queue
+                .enqueue_at(
+                    name.to_string(),
+                    None,
+                    serde_json::json!({}),
+                    None,
+                    run_at,
+                    priority,
+                )
+                .await
+                .expect("enqueue job");
+        }
+
+        let jobs = queue.pending_jobs().await.expect("list pending jobs");
+        let listed = jobs
+            .iter()
+            .map(|job| (job.name.as_str(), job.is_delayed()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listed,
+            [
+                ("High", false),
+                ("Default", false),
+                ("Soon", true),
+                ("Later", true)
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn cat_import_jobs_form_file() {
         let tree_fs = tree_fs::TreeBuilder::default()
@@ -834,4 +1231,33 @@ mod tests {
 
         assert_eq!(count, 14);
     }
+
+    #[test]
+    fn can_back_off_retries() {
+        let policy = RetryPolicy::new(4)
+            .with_backoff(
+                std::time::Duration::from_secs(10),
+                std::time::Duration::from_secs(30),
+            )
+            .without_jitter();
+
+        let delays: Vec<u64> = (1..=4)
+            .map(|attempt| policy.delay(attempt).as_secs())
+            .collect();
+        assert_eq!(delays, vec![10, 20, 30, 30]);
+
+        assert!(policy.retry_at(3).is_some());
+        assert!(policy.retry_at(4).is_none());
+        assert!(RetryPolicy::default().retry_at(1).is_none());
+
+        let policy = RetryPolicy::new(4).with_backoff(
+            std::time::Duration::from_secs(10),
+            std::time::Duration::from_secs(30),
+        );
+        for attempt in 1..=4 {
+            let delay = policy.delay(attempt).as_secs_f64();
+            let max = policy.clone().without_jitter().delay(attempt).as_secs_f64();
+            assert!(delay >= max / 2.0 && delay <= max);
+        }
+    }
 }
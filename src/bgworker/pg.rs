@@ -4,8 +4,12 @@ use std::{
     time::Duration,
 };
 
-use super::{BackgroundWorker, JobStatus, Queue};
-use crate::{config::PostgresQueueConfig, Error, Result};
+use super::{BackgroundWorker, JobStatus, Queue, RetryPolicy};
+use crate::{
+    config::PostgresQueueConfig,
+    scheduler::{Run, RunStatus},
+    Error, Result,
+};
 use chrono::{DateTime, Utc};
 use futures_util::FutureExt;
 use serde::{Deserialize, Serialize};
@@ -44,10 +48,17 @@ pub struct Job {
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
     pub tags: Option<Vec<String>>,
+    /// Jobs with a higher priority run first
+    #[serde(default)]
+    pub priority: i32,
+    /// The attempts that failed so far
+    #[serde(default)]
+    pub attempts: i32,
 }
 
 pub struct JobRegistry {
     handlers: Arc<HashMap<String, JobHandler>>,
+    retry_policies: HashMap<String, RetryPolicy>,
 }
 
 impl JobRegistry {
@@ -56,6 +67,7 @@ impl JobRegistry {
     pub fn new() -> Self {
         Self {
             handlers: Arc::new(HashMap::new()),
+            retry_policies: HashMap::new(),
         }
     }
 
@@ -97,7 +109,8 @@ impl JobRegistry {
 
         Arc::get_mut(&mut self.handlers)
             .ok_or_else(|| Error::string("cannot register worker"))?
-            .insert(name, Box::new(wrapped_handler));
+            .insert(name.clone(), Box::new(wrapped_handler));
+        self.retry_policies.insert(name, W::retry_policy());
         Ok(())
     }
 
@@ -119,8 +132,10 @@ impl JobRegistry {
         let mut jobs = Vec::new();
 
         let interval = opts.poll_interval_sec;
+        let retry_policies = Arc::new(self.retry_policies.clone());
         for idx in 0..opts.num_workers {
             let handlers = self.handlers.clone();
+            let retry_policies = retry_policies.clone();
             let worker_token = token.clone(); // Clone token for this worker
             let worker_tags = tags.to_vec();
 
@@ -164,7 +179,26 @@ impl JobRegistry {
                                     }
                                 }
                                 Err(err) => {
-                                    if let Err(fail_err) = fail_job(&pool, &job.id, &err).await {
+                                    let attempt = job.attempts.unsigned_abs() + 1;
+                                    let retry_at = retry_policies
+                                        .get(&job.name)
+                                        .and_then(|policy| policy.retry_at(attempt));
+                                    if let Some(retry_at) = retry_at {
+                                        if let Err(retry_err) =
+                                            retry_job(&pool, &job.id, retry_at).await
+                                        {
+                                            error!(
+                                                error = %retry_err,
+                                                job_id = %job.id,
+                                                job_name = %job.name,
+                                                "Failed to reschedule job for retry"
+                                            );
+                                        } else {
+                                            debug!(job_id = %job.id, error = %err, attempt, retry_at = %retry_at, "Job execution failed, retrying later");
+                                        }
+                                    } else if let Err(fail_err) =
+                                        fail_job(&pool, &job.id, &err).await
+                                    {
                                         error!(
                                             error = %fail_err,
                                             job_id = %job.id,
@@ -241,7 +275,23 @@ pub async fn initialize_database(pool: &PgPool) -> Result<()> {
                 interval BIGINT,
                 created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
                 updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-                tags JSONB
+                tags JSONB,
+                priority INTEGER NOT NULL DEFAULT 0,
+                attempts INTEGER NOT NULL DEFAULT 0
+            );
+
+            ALTER TABLE pg_loco_queue ADD COLUMN IF NOT EXISTS priority INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE pg_loco_queue ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+
+            CREATE TABLE IF NOT EXISTS pg_loco_scheduler_runs (
+                id VARCHAR NOT NULL PRIMARY KEY,
+                job_name VARCHAR NOT NULL,
+                scheduled_at TIMESTAMPTZ NOT NULL,
+                status VARCHAR NOT NULL,
+                started_at TIMESTAMPTZ NOT NULL,
+                finished_at TIMESTAMPTZ,
+                error TEXT,
+                UNIQUE (job_name, scheduled_at)
             );
             ",
         JobStatus::Queued
@@ -263,6 +313,24 @@ pub async fn enqueue(
     run_at: DateTime<Utc>,
     interval: Option<Duration>,
     tags: Option<Vec<String>>,
+) -> Result<JobId> {
+    enqueue_with_priority(pool, name, data, run_at, interval, tags, 0).await
+}
+
+/// Add a job with a `priority`: of the jobs due, those with a higher priority
+/// run first.
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn enqueue_with_priority(
+    pool: &PgPool,
+    name: &str,
+    data: JobData,
+    run_at: DateTime<Utc>,
+    interval: Option<Duration>,
+    tags: Option<Vec<String>>,
+    priority: i32,
 ) -> Result<JobId> {
     let data_json = serde_json::to_value(data)?;
     let tags_json = tags
@@ -273,10 +341,10 @@ pub async fn enqueue(
     let interval_ms: Option<i64> = interval.map(|i| i.as_millis() as i64);
 
     let id = Ulid::new().to_string();
-    debug!(job_id = %id, job_name = %name, run_at = %run_at, tags = ?tags, "Enqueueing job");
+    debug!(job_id = %id, job_name = %name, run_at = %run_at, tags = ?tags, priority, "Enqueueing job");
     sqlx::query(
-        "INSERT INTO pg_loco_queue (id, task_data, name, run_at, interval, tags) VALUES ($1, $2, $3, \
-         $4, $5, $6)",
+        "INSERT INTO pg_loco_queue (id, task_data, name, run_at, interval, tags, priority) VALUES \
+         ($1, $2, $3, $4, $5, $6, $7)",
     )
     .bind(id.clone())
     .bind(data_json)
@@ -284,6 +352,7 @@ pub async fn enqueue(
     .bind(run_at)
     .bind(interval_ms)
     .bind(tags_json)
+    .bind(priority)
     .execute(pool)
     .await?;
     Ok(id)
@@ -294,7 +363,7 @@ async fn dequeue(client: &PgPool, worker_tags: &[String]) -> Result<Option<Job>>
 
     // Base query
     let mut query = String::from(
-        "SELECT id, name, task_data, status, run_at, interval, tags FROM pg_loco_queue WHERE status = $1 AND run_at <= NOW() "
+        "SELECT id, name, task_data, status, run_at, interval, tags, priority, attempts FROM pg_loco_queue WHERE status = $1 AND run_at <= NOW() "
     );
 
     // Apply tag filtering logic
@@ -321,7 +390,7 @@ async fn dequeue(client: &PgPool, worker_tags: &[String]) -> Result<Option<Job>>
         }
     }
 
-    query.push_str(" ORDER BY run_at LIMIT 1 FOR UPDATE SKIP LOCKED");
+    query.push_str(" ORDER BY priority DESC, run_at LIMIT 1 FOR UPDATE SKIP LOCKED");
 
     // Create the query
     let mut db_query = sqlx::query(&query).bind(JobStatus::Queued.to_string());
@@ -389,7 +458,7 @@ async fn fail_job(pool: &PgPool, id: &JobId, error: &crate::Error) -> Result<()>
     let error_json = serde_json::json!({ "error": msg });
     sqlx::query(
         "UPDATE pg_loco_queue SET status = $1, updated_at = NOW(), task_data = task_data || \
-         $2::jsonb WHERE id = $3",
+         $2::jsonb, attempts = attempts + 1 WHERE id = $3",
     )
     .bind(JobStatus::Failed.to_string())
     .bind(error_json)
@@ -399,6 +468,138 @@ async fn fail_job(pool: &PgPool, id: &JobId, error: &crate::Error) -> Result<()>
     Ok(())
 }
 
+async fn retry_job(pool: &PgPool, id: &JobId, run_at: DateTime<Utc>) -> Result<()> {
+    debug!(job_id = %id, run_at = %run_at, "Rescheduling failed job");
+    sqlx::query(
+        "UPDATE pg_loco_queue SET status = $1, updated_at = NOW(), run_at = $2, attempts = \
+         attempts + 1 WHERE id = $3",
+    )
+    .bind(JobStatus::Queued.to_string())
+    .bind(run_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Queues a [`JobStatus::Failed`] job again to run now, with its attempts
+/// reset and the error removed from its data.
+///
+/// # Errors
+///
+/// This function will return an error if it fails, or if there is no failed
+/// job with the given id
+pub async fn retry_failed_job(pool: &PgPool, id: &str) -> Result<()> {
+    debug!(job_id = %id, "Retrying failed job");
+    let result = sqlx::query(
+        "UPDATE pg_loco_queue SET status = $1, updated_at = NOW(), run_at = NOW(), attempts = 0, \
+         task_data = task_data - 'error' WHERE id = $2 AND status = $3",
+    )
+    .bind(JobStatus::Queued.to_string())
+    .bind(id)
+    .bind(JobStatus::Failed.to_string())
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::Message(format!("no failed job with id `{id}`")));
+    }
+    Ok(())
+}
+
+/// Claims the run of a scheduler job, see
+/// [`Queue::claim_scheduled_run`](super::Queue::claim_scheduled_run).
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn claim_scheduled_run(
+    pool: &PgPool,
+    job_name: &str,
+    scheduled_at: DateTime<Utc>,
+    no_overlap: bool,
+    lock_timeout: chrono::Duration,
+) -> Result<Option<Run>> {
+    let overlapping = no_overlap
+        && sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM pg_loco_scheduler_runs WHERE job_name = $1 AND status = $2 \
+             AND started_at > $3)",
+        )
+        .bind(job_name)
+        .bind(RunStatus::Running.to_string())
+        .bind(Utc::now() - lock_timeout)
+        .fetch_one(pool)
+        .await?;
+
+    let mut run = Run::new(job_name, scheduled_at);
+    if overlapping {
+        run.finish(RunStatus::Skipped, None);
+    }
+    let result = sqlx::query(
+        "INSERT INTO pg_loco_scheduler_runs (id, job_name, scheduled_at, status, started_at, finished_at) \
+         VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (job_name, scheduled_at) DO NOTHING",
+    )
+    .bind(&run.id)
+    .bind(job_name)
+    .bind(scheduled_at)
+    .bind(run.status.to_string())
+    .bind(run.started_at)
+    .bind(run.finished_at)
+    .execute(pool)
+    .await?;
+
+    let claimed = result.rows_affected() > 0;
+    debug!(job_name, scheduled_at = %scheduled_at, claimed, overlapping, "Claiming scheduler job run");
+    Ok((claimed && !overlapping).then_some(run))
+}
+
+/// Records the outcome of a scheduler job run
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn finish_scheduled_run(pool: &PgPool, run: &Run) -> Result<()> {
+    debug!(run_id = %run.id, job_name = %run.job_name, status = %run.status, "Recording scheduler job run");
+    sqlx::query(
+        "UPDATE pg_loco_scheduler_runs SET status = $1, finished_at = $2, error = $3 WHERE id = $4",
+    )
+    .bind(run.status.to_string())
+    .bind(run.finished_at)
+    .bind(&run.error)
+    .bind(&run.id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Retrieves the latest scheduler job runs, most recent first
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn scheduled_runs(pool: &PgPool, limit: usize) -> Result<Vec<Run>> {
+    let rows =
+        sqlx::query("SELECT * FROM pg_loco_scheduler_runs ORDER BY started_at DESC LIMIT $1")
+            .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+            .fetch_all(pool)
+            .await?;
+    rows.iter().map(to_run).collect()
+}
+
+fn to_run(row: &PgRow) -> Result<Run> {
+    Ok(Run {
+        id: row.get("id"),
+        job_name: row.get("job_name"),
+        scheduled_at: row.get("scheduled_at"),
+        status: row
+            .get::<String, _>("status")
+            .parse()
+            .map_err(|_| Error::string("invalid scheduler run status"))?,
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+        error: row.get("error"),
+    })
+}
+
 /// Cancels jobs in the `pg_loco_queue` table by their name.
 ///
 /// This function updates the status of all jobs with the given `name` and a status of
@@ -612,6 +813,8 @@ fn to_job(row: &PgRow) -> Result<Job> {
         created_at: row.try_get("created_at").unwrap_or_default(),
         updated_at: row.try_get("updated_at").unwrap_or_default(),
         tags,
+        priority: row.try_get("priority").unwrap_or_default(),
+        attempts: row.try_get("attempts").unwrap_or_default(),
     })
 }
 
@@ -720,7 +923,7 @@ mod tests {
 
         let table_info: Vec<TableInfo> = query_as::<_, TableInfo>(
             "SELECT * FROM information_schema.columns WHERE table_name =
-    'pg_loco_queue'",
+    'pg_loco_queue' ORDER BY ordinal_position",
         )
         .fetch_all(&pool)
         .await
@@ -813,6 +1016,52 @@ mod tests {
             });
     }
 
+    #[tokio::test]
+    async fn can_dequeue_by_priority() {
+        let (pool, _container) = setup_pg_test().await;
+
+        let run_at = Utc::now() - chrono::Duration::minutes(5);
+        for (name, priority) in [("Low", -1), ("Default", 0), ("High", 10)] {
+            assert!(enqueue_with_priority(
+                &pool,
+                name,
+                serde_json::json!({}),
+                run_at,
+                None,
+                None,
+                priority
+            )
+            .await
+            .is_ok());
+        }
+
+        for name in ["High", "Default", "Low"] {
+            let job = dequeue(&pool, &[]).await.unwrap().expect("job to dequeue");
+            assert_eq!(job.name, name);
+        }
+    }
+
+    #[tokio::test]
+    async fn cannot_dequeue_delayed_job() {
+        let (pool, _container) = setup_pg_test().await;
+
+        let run_at = Utc::now() + chrono::Duration::hours(1);
+        assert!(enqueue_with_priority(
+            &pool,
+            "Reminder",
+            serde_json::json!({}),
+            run_at,
+            None,
+            None,
+            10
+        )
+        .await
+        .is_ok());
+
+        assert!(dequeue(&pool, &[]).await.unwrap().is_none());
+        assert_eq!(get_all_jobs(&pool).await[0].status, JobStatus::Queued);
+    }
+
     #[tokio::test]
     async fn can_complete_job_without_interval() {
         let (pool, _container) = setup_pg_test().await;
@@ -1188,6 +1437,93 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn can_retry_job_until_attempts_run_out() {
+        let (pool, _container) = setup_pg_test().await;
+
+        let job_id = enqueue(
+            &pool,
+            "FlakyJob",
+            serde_json::json!({"user_id": 1}),
+            Utc::now(),
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to enqueue job");
+
+        struct FlakyWorker;
+        #[async_trait::async_trait]
+        impl BackgroundWorker<JsonValue> for FlakyWorker {
+            fn build(_ctx: &crate::app::AppContext) -> Self {
+                Self
+            }
+            fn retry_policy() -> RetryPolicy {
+                RetryPolicy::new(3).with_backoff(Duration::ZERO, Duration::ZERO)
+            }
+            async fn perform(&self, _args: JsonValue) -> crate::Result<()> {
+                Err(Error::string("service unavailable"))
+            }
+        }
+
+        let mut registry = JobRegistry::new();
+        assert!(registry
+            .register_worker("FlakyJob".to_string(), FlakyWorker)
+            .is_ok());
+
+        let opts = RunOpts {
+            num_workers: 1,
+            poll_interval_sec: 1,
+        };
+        let token = CancellationToken::new();
+        let handles = registry.run(&pool, &opts, &token, &[]);
+        sleep(Duration::from_secs(2)).await;
+        for handle in handles {
+            handle.abort();
+        }
+
+        let failed_job = get_job(&pool, &job_id).await;
+        assert_eq!(failed_job.status, JobStatus::Failed);
+        assert_eq!(failed_job.attempts, 3);
+        assert_eq!(
+            failed_job.data,
+            serde_json::json!({"user_id": 1, "error": "service unavailable"})
+        );
+
+        assert!(retry_failed_job(&pool, &job_id).await.is_ok());
+        let retried_job = get_job(&pool, &job_id).await;
+        assert_eq!(retried_job.status, JobStatus::Queued);
+        assert_eq!(retried_job.attempts, 0);
+        assert_eq!(retried_job.data, serde_json::json!({"user_id": 1}));
+
+        assert!(retry_failed_job(&pool, &job_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_reschedule_job_for_retry() {
+        let (pool, _container) = setup_pg_test().await;
+
+        let job_id = enqueue(
+            &pool,
+            "FlakyJob",
+            serde_json::json!({}),
+            Utc::now(),
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to enqueue job");
+        let job = dequeue(&pool, &[]).await.unwrap().expect("job to dequeue");
+
+        let retry_at = Utc::now() + chrono::Duration::hours(1);
+        assert!(retry_job(&pool, &job.id, retry_at).await.is_ok());
+
+        let job = get_job(&pool, &job_id).await;
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.attempts, 1);
+        assert!(dequeue(&pool, &[]).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn can_dequeue_with_tags() {
         let (pool, _container) = setup_pg_test().await;
@@ -1321,4 +1657,54 @@ mod tests {
         let job = dequeue(&pool, &[]).await.expect("dequeue failed");
         assert!(job.is_none());
     }
+
+    #[tokio::test]
+    async fn can_claim_scheduled_run() {
+        let (pool, _container) = setup_pg_test().await;
+
+        let scheduled_at = Utc::now();
+        let lock_timeout = chrono::Duration::minutes(1);
+        let mut run = claim_scheduled_run(&pool, "Backup", scheduled_at, true, lock_timeout)
+            .await
+            .unwrap()
+            .expect("claimed run");
+        assert_eq!(run.status, RunStatus::Running);
+
+        // another instance, for the same tick
+        assert!(
+            claim_scheduled_run(&pool, "Backup", scheduled_at, true, lock_timeout)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // the next tick, overlapping the run in progress
+        let next_tick = scheduled_at + chrono::Duration::seconds(1);
+        assert!(
+            claim_scheduled_run(&pool, "Backup", next_tick, true, lock_timeout)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        run.finish(RunStatus::Succeeded, None);
+        assert!(finish_scheduled_run(&pool, &run).await.is_ok());
+
+        let next_tick = next_tick + chrono::Duration::seconds(1);
+        assert!(
+            claim_scheduled_run(&pool, "Backup", next_tick, true, lock_timeout)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let mut statuses: Vec<String> = scheduled_runs(&pool, 10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|run| run.status.to_string())
+            .collect();
+        statuses.sort();
+        assert_eq!(statuses, vec!["running", "skipped", "succeeded"]);
+    }
 }
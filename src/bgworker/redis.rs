@@ -4,8 +4,12 @@ use std::{
     time::Duration,
 };
 
-use super::{BackgroundWorker, JobStatus, Queue};
-use crate::{config::RedisQueueConfig, Error, Result};
+use super::{BackgroundWorker, JobStatus, Queue, RetryPolicy};
+use crate::{
+    config::RedisQueueConfig,
+    scheduler::{Run, RunStatus},
+    Error, Result,
+};
 use chrono::{DateTime, Utc};
 use futures_util::FutureExt;
 use redis::{aio::MultiplexedConnection as Connection, AsyncCommands, Client, Script};
@@ -23,6 +27,15 @@ type JobData = JsonValue;
 const QUEUE_KEY_PREFIX: &str = "queue:";
 const JOB_KEY_PREFIX: &str = "job:";
 const PROCESSING_KEY_PREFIX: &str = "processing:";
+const SCHEDULED_KEY_PREFIX: &str = "scheduled:";
+const FAILED_KEY_PREFIX: &str = "failed:";
+const SCHEDULER_RUN_KEY_PREFIX: &str = "scheduler:run:";
+const SCHEDULER_CLAIM_KEY_PREFIX: &str = "scheduler:claim:";
+const SCHEDULER_LOCK_KEY_PREFIX: &str = "scheduler:lock:";
+/// How long scheduler job runs are kept, in seconds
+const SCHEDULER_RUN_TTL: u64 = 7 * 24 * 60 * 60;
+/// How many jobs of a queue a worker looks through for one it can process
+const DEQUEUE_SCAN_LIMIT: usize = 100;
 
 type JobHandler = Box<
     dyn Fn(
@@ -45,6 +58,12 @@ pub struct Job {
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
     pub tags: Option<Vec<String>>,
+    /// Jobs with a higher priority run first
+    #[serde(default)]
+    pub priority: i32,
+    /// The attempts that failed so far
+    #[serde(default)]
+    pub attempts: i32,
 }
 
 // Implementation for job creation and serialization
@@ -61,6 +80,8 @@ impl Job {
             created_at: Some(now),
             updated_at: Some(now),
             tags: None,
+            priority: 0,
+            attempts: 0,
         }
     }
 
@@ -77,6 +98,7 @@ impl Job {
 
 pub struct JobRegistry {
     handlers: Arc<HashMap<String, JobHandler>>,
+    retry_policies: HashMap<String, RetryPolicy>,
 }
 
 impl JobRegistry {
@@ -85,6 +107,7 @@ impl JobRegistry {
     pub fn new() -> Self {
         Self {
             handlers: Arc::new(HashMap::new()),
+            retry_policies: HashMap::new(),
         }
     }
 
@@ -126,7 +149,8 @@ impl JobRegistry {
         };
         Arc::get_mut(&mut self.handlers)
             .ok_or_else(|| Error::string("cannot register worker"))?
-            .insert(name, Box::new(wrapped_handler));
+            .insert(name.clone(), Box::new(wrapped_handler));
+        self.retry_policies.insert(name, W::retry_policy());
         Ok(())
     }
 
@@ -148,9 +172,11 @@ impl JobRegistry {
         let mut jobs = Vec::new();
         let queues = get_queues(&opts.queues);
         let interval = opts.poll_interval_sec;
+        let retry_policies = Arc::new(self.retry_policies.clone());
 
         for idx in 0..opts.num_workers {
             let handlers = self.handlers.clone();
+            let retry_policies = retry_policies.clone();
             let worker_token = token.clone();
             let client = client.clone();
             let queues = queues.clone();
@@ -197,7 +223,28 @@ impl JobRegistry {
                                     }
                                 }
                                 Err(err) => {
-                                    if let Err(err) =
+                                    let attempt = job.attempts.unsigned_abs() + 1;
+                                    let retry_at = retry_policies
+                                        .get(&job.name)
+                                        .and_then(|policy| policy.retry_at(attempt));
+                                    if let Some(retry_at) = retry_at {
+                                        debug!(
+                                            job_id = job.id,
+                                            err = err.to_string(),
+                                            attempt,
+                                            "job failed, retrying later"
+                                        );
+                                        if let Err(err) = retry_job_with_conn(
+                                            &mut conn,
+                                            &job.id,
+                                            &queue_name,
+                                            retry_at,
+                                        )
+                                        .await
+                                        {
+                                            error!(err = err.to_string(), job = ?job, "cannot retry job");
+                                        }
+                                    } else if let Err(err) =
                                         fail_job_with_conn(&mut conn, &job.id, &queue_name, &err)
                                             .await
                                     {
@@ -253,6 +300,48 @@ pub async fn clear(client: &RedisPool) -> Result<()> {
     Ok(())
 }
 
+/// The score of a job in its queue, popping the highest priority first. Jobs
+/// of the same priority pop by their ids, i.e. in the order they were
+/// enqueued.
+fn queue_score(priority: i32) -> i64 {
+    -i64::from(priority)
+}
+
+/// Converts the queues earlier versions left as lists to sorted sets
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn initialize(client: &RedisPool) -> Result<()> {
+    let mut conn = get_connection(client).await?;
+    let queue_keys: Vec<String> = redis::cmd("KEYS")
+        .arg(format!("{QUEUE_KEY_PREFIX}*"))
+        .query_async(&mut conn)
+        .await?;
+    for queue_key in queue_keys {
+        let kind: String = redis::cmd("TYPE")
+            .arg(&queue_key)
+            .query_async(&mut conn)
+            .await?;
+        if kind != "list" {
+            continue;
+        }
+        let job_ids: Vec<String> = conn.lrange(&queue_key, 0, -1).await?;
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&queue_key);
+        for job_id in &job_ids {
+            pipe.zadd(&queue_key, job_id, queue_score(0));
+        }
+        let _: () = pipe.query_async(&mut conn).await?;
+        debug!(
+            queue = queue_name(&queue_key),
+            count = job_ids.len(),
+            "converted queue list to a sorted set"
+        );
+    }
+    Ok(())
+}
+
 /// Add a task
 ///
 /// # Errors
@@ -264,6 +353,25 @@ pub async fn enqueue(
     queue: Option<String>,
     args: impl serde::Serialize + Send,
     tags: Option<Vec<String>>,
+) -> Result<()> {
+    enqueue_at(client, class, queue, args, tags, Utc::now(), 0).await
+}
+
+/// Add a task, to run at `run_at`. A task to run later waits in the scheduled
+/// set of its queue until due. Of the tasks due, those with a higher
+/// `priority` run first.
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn enqueue_at(
+    client: &RedisPool,
+    class: String,
+    queue: Option<String>,
+    args: impl serde::Serialize + Send,
+    tags: Option<Vec<String>>,
+    run_at: DateTime<Utc>,
+    priority: i32,
 ) -> Result<()> {
     let mut conn = get_connection(client).await?;
     let queue_name = queue.unwrap_or_else(|| "default".to_string());
@@ -278,34 +386,101 @@ pub async fn enqueue(
     // Create job
     let mut job = Job::new(job_id.clone(), class, args_json);
     job.tags = tags;
+    job.run_at = run_at;
+    job.priority = priority;
 
     // Serialize job for Redis storage
     let job_json = job.to_json()?;
 
-    // Store job in Redis queue and in job key
+    // Store job in Redis queue, or its scheduled set, and in job key
     let job_key = format!("{JOB_KEY_PREFIX}{}", job.id);
     let _: () = conn.set(&job_key, &job_json).await?;
-    let _: () = conn.rpush(&queue_key, &job.id).await?;
+    if run_at > Utc::now() {
+        let scheduled_key = format!("{SCHEDULED_KEY_PREFIX}{queue_name}");
+        let _: () = conn
+            .zadd(&scheduled_key, &job.id, run_at.timestamp_millis())
+            .await?;
+    } else {
+        let _: () = conn
+            .zadd(&queue_key, &job.id, queue_score(priority))
+            .await?;
+    }
 
     Ok(())
 }
 
-const DEQUEUE_SCRIPT: &str = r#"
-local queue_key = KEYS[1]
-local processing_key = KEYS[2]
-local job_id = redis.call('LPOP', queue_key)
-if job_id then
-    local added = redis.call('SADD', processing_key, job_id)
-    if added == 1 then
-        return job_id
-    else
-        redis.log(redis.LOG_WARNING, "Job already in processing: " .. job_id)
-        return nil
-    end
-else
-    return nil
+/// Moves a due job from the scheduled set, `KEYS[1]`, to the queue, `KEYS[2]`,
+/// unless another worker did
+const PROMOTE_SCRIPT: &str = r"
+if redis.call('ZREM', KEYS[1], ARGV[1]) == 1 then
+    redis.call('ZADD', KEYS[2], ARGV[2], ARGV[1])
+end
+return 0
+";
+
+/// Moves a job from the queue, `KEYS[1]`, to the processing set, `KEYS[2]`,
+/// returning whether it did, i.e. no other worker claimed it first
+const CLAIM_SCRIPT: &str = r"
+if redis.call('ZREM', KEYS[1], ARGV[1]) == 1 then
+    redis.call('SADD', KEYS[2], ARGV[1])
+    return 1
 end
-"#;
+return 0
+";
+
+/// The jobs of `job_ids`, `None` for those missing or invalid
+async fn read_jobs(conn: &mut Connection, job_ids: &[String]) -> Result<Vec<Option<Job>>> {
+    if job_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut pipe = redis::pipe();
+    for job_id in job_ids {
+        pipe.get(format!("{JOB_KEY_PREFIX}{job_id}"));
+    }
+    let jobs: Vec<Option<String>> = pipe.query_async(conn).await?;
+    Ok(jobs
+        .into_iter()
+        .map(|json| json.and_then(|json| Job::from_json(&json).ok()))
+        .collect())
+}
+
+/// Whether a worker with `tags` runs the job. A job without tags runs on a
+/// worker without tags only.
+fn matches_tags(job: &Job, tags: &[String]) -> bool {
+    match job.tags.as_deref() {
+        None | Some([]) => tags.is_empty(),
+        Some(job_tags) => job_tags.iter().any(|tag| tags.contains(tag)),
+    }
+}
+
+/// Moves the due jobs of the scheduled set of the queue to the queue
+async fn promote_due_jobs(
+    conn: &mut Connection,
+    queue_key: &str,
+    scheduled_key: &str,
+) -> Result<()> {
+    let due: Vec<String> = conn
+        .zrangebyscore_limit(
+            scheduled_key,
+            "-inf",
+            Utc::now().timestamp_millis(),
+            0,
+            DEQUEUE_SCAN_LIMIT as isize,
+        )
+        .await?;
+    let script = Script::new(PROMOTE_SCRIPT);
+    for (job_id, job) in due.iter().zip(read_jobs(conn, &due).await?) {
+        let priority = job.map_or(0, |job| job.priority);
+        let _: i64 = script
+            .key(scheduled_key)
+            .key(queue_key)
+            .arg(job_id)
+            .arg(queue_score(priority))
+            .invoke_async(conn)
+            .await?;
+    }
+    Ok(())
+}
 
 async fn dequeue_with_conn(
     conn: &mut Connection,
@@ -316,58 +491,47 @@ async fn dequeue_with_conn(
         return Ok(None);
     }
 
-    let script = Script::new(DEQUEUE_SCRIPT);
+    let claim = Script::new(CLAIM_SCRIPT);
 
     // Try to get a job from each queue in order (round-robin is more complex)
     for queue_name in queues {
         let queue_key = format!("{QUEUE_KEY_PREFIX}{queue_name}");
         let processing_key = format!("{PROCESSING_KEY_PREFIX}{queue_name}");
+        let scheduled_key = format!("{SCHEDULED_KEY_PREFIX}{queue_name}");
+        promote_due_jobs(conn, &queue_key, &scheduled_key).await?;
 
-        let job_id: Option<String> = script
-            .key(&queue_key)
-            .key(&processing_key)
-            .invoke_async(conn)
+        // the first jobs by priority, then the first job matching the tags
+        // no other worker claims meanwhile
+        let job_ids: Vec<String> = conn
+            .zrange(&queue_key, 0, DEQUEUE_SCAN_LIMIT as isize - 1)
             .await?;
-
-        if let Some(job_id) = job_id {
-            let job_key = format!("{JOB_KEY_PREFIX}{job_id}");
-            let job_json: Option<String> = conn.get(&job_key).await?;
-
-            if let Some(json) = job_json {
-                match Job::from_json(&json) {
-                    Ok(job) => {
-                        let should_process = if tags.is_empty() {
-                            job.tags.is_none() || job.tags.as_ref().map_or(true, Vec::is_empty)
-                        } else {
-                            job.tags.as_ref().is_some_and(|job_tags| {
-                                job_tags.iter().any(|tag| tags.contains(tag))
-                            })
-                        };
-
-                        if should_process {
-                            return Ok(Some((job, queue_name.clone())));
-                        }
-                        let _: () = conn.srem(&processing_key, &job_id).await?;
-                        let _: () = conn.rpush(&queue_key, &job_id).await?;
-                        trace!(
-                            job_id = job_id,
-                            job_tags = ?job.tags,
-                            worker_tags = ?tags,
-                            "Job doesn't match tag criteria, returned to queue"
-                        );
-                    }
-                    Err(err) => {
-                        error!(
-                            err = err.to_string(),
-                            job_id = job_id,
-                            "Failed to parse job JSON"
-                        );
-                        let _: () = conn.srem(&processing_key, &job_id).await?;
-                    }
-                }
-            } else {
-                error!(job_id = job_id, queue = queue_name, "Job data not found.");
-                let _: () = conn.srem(&processing_key, &job_id).await?;
+        for (job_id, job) in job_ids.iter().zip(read_jobs(conn, &job_ids).await?) {
+            let Some(job) = job else {
+                error!(
+                    job_id = job_id,
+                    queue = queue_name,
+                    "Job data not found or invalid, removed from queue."
+                );
+                let _: () = conn.zrem(&queue_key, job_id).await?;
+                continue;
+            };
+            if !matches_tags(&job, tags) {
+                trace!(
+                    job_id = job_id,
+                    job_tags = ?job.tags,
+                    worker_tags = ?tags,
+                    "Job doesn't match tag criteria, left in queue"
+                );
+                continue;
+            }
+            let claimed: bool = claim
+                .key(&queue_key)
+                .key(&processing_key)
+                .arg(job_id)
+                .invoke_async(conn)
+                .await?;
+            if claimed {
+                return Ok(Some((job, queue_name.clone())));
             }
         }
     }
@@ -390,10 +554,10 @@ async fn complete_job_with_conn(
                 job.run_at = Utc::now() + chrono::Duration::milliseconds(interval);
                 job.status = JobStatus::Queued;
                 let new_json = job.to_json()?;
-                let queue_key = format!("{QUEUE_KEY_PREFIX}{queue_name}");
+                let scheduled_key = format!("{SCHEDULED_KEY_PREFIX}{queue_name}");
                 let _: () = redis::pipe()
                     .set(&job_key, &new_json)
-                    .rpush(&queue_key, id)
+                    .zadd(&scheduled_key, id, job.run_at.timestamp_millis())
                     .query_async(conn)
                     .await?;
             } else {
@@ -420,18 +584,104 @@ async fn fail_job_with_conn(
     let job_json: Option<String> = conn.get(&job_key).await?;
     if let Some(json) = job_json {
         if let Ok(mut job) = Job::from_json(&json) {
-            let error_json = serde_json::json!({ "error": error.to_string() });
-            job.data = error_json;
+            let error = JsonValue::String(error.to_string());
+            if let Some(data) = job.data.as_object_mut() {
+                data.insert("error".to_string(), error);
+            } else {
+                job.data = serde_json::json!({ "error": error });
+            }
             job.status = JobStatus::Failed;
+            job.attempts += 1;
             job.updated_at = Some(Utc::now());
             let updated_json = job.to_json()?;
-            let _: () = conn.set(&job_key, &updated_json).await?;
+            let failed_key = format!("{FAILED_KEY_PREFIX}{queue_name}");
+            let _: () = redis::pipe()
+                .set(&job_key, &updated_json)
+                .sadd(&failed_key, id)
+                .query_async(conn)
+                .await?;
         }
     }
     let _: () = conn.srem(&processing_key, id).await?;
     Ok(())
 }
 
+async fn retry_job_with_conn(
+    conn: &mut Connection,
+    id: &JobId,
+    queue_name: &str,
+    run_at: DateTime<Utc>,
+) -> Result<()> {
+    let job_key = format!("{JOB_KEY_PREFIX}{id}");
+    let processing_key = format!("{PROCESSING_KEY_PREFIX}{queue_name}");
+
+    let job_json: Option<String> = conn.get(&job_key).await?;
+    if let Some(json) = job_json {
+        if let Ok(mut job) = Job::from_json(&json) {
+            job.status = JobStatus::Queued;
+            job.attempts += 1;
+            job.run_at = run_at;
+            job.updated_at = Some(Utc::now());
+            let updated_json = job.to_json()?;
+            let scheduled_key = format!("{SCHEDULED_KEY_PREFIX}{queue_name}");
+            let _: () = redis::pipe()
+                .set(&job_key, &updated_json)
+                .zadd(&scheduled_key, id, run_at.timestamp_millis())
+                .query_async(conn)
+                .await?;
+        }
+    }
+    let _: () = conn.srem(&processing_key, id).await?;
+    Ok(())
+}
+
+/// Queues a [`JobStatus::Failed`] job again to run now, with its attempts
+/// reset and the error removed from its data.
+///
+/// # Errors
+///
+/// This function will return an error if it fails, or if there is no failed
+/// job with the given id
+pub async fn retry_failed_job(client: &RedisPool, id: &str) -> Result<()> {
+    let mut conn = get_connection(client).await?;
+    let job_key = format!("{JOB_KEY_PREFIX}{id}");
+
+    let job_json: Option<String> = conn.get(&job_key).await?;
+    let mut job = match job_json.as_deref().map(Job::from_json) {
+        Some(Ok(job)) if job.status == JobStatus::Failed => job,
+        _ => return Err(Error::Message(format!("no failed job with id `{id}`"))),
+    };
+
+    // the failed set holding the job tells its queue
+    let failed_keys: Vec<String> = redis::cmd("KEYS")
+        .arg(format!("{FAILED_KEY_PREFIX}*"))
+        .query_async(&mut conn)
+        .await?;
+    let mut queue_name = "default";
+    for failed_key in &failed_keys {
+        let removed: i64 = conn.srem(failed_key, id).await?;
+        if removed > 0 {
+            queue_name = failed_key.trim_start_matches(FAILED_KEY_PREFIX);
+        }
+    }
+
+    if let Some(data) = job.data.as_object_mut() {
+        data.remove("error");
+    }
+    job.status = JobStatus::Queued;
+    job.attempts = 0;
+    job.run_at = Utc::now();
+    job.updated_at = Some(Utc::now());
+    let updated_json = job.to_json()?;
+    let queue_key = format!("{QUEUE_KEY_PREFIX}{queue_name}");
+    let _: () = redis::pipe()
+        .set(&job_key, &updated_json)
+        .zadd(&queue_key, id, queue_score(job.priority))
+        .query_async(&mut conn)
+        .await?;
+    Ok(())
+}
+
 /// Ping system
 ///
 /// # Errors
@@ -443,6 +693,27 @@ pub async fn ping(client: &RedisPool) -> Result<()> {
     Ok(())
 }
 
+/// The keys of the queues and of their scheduled sets, holding the jobs
+/// waiting to run
+async fn waiting_keys(conn: &mut Connection) -> Result<Vec<String>> {
+    let mut keys = Vec::new();
+    for prefix in [QUEUE_KEY_PREFIX, SCHEDULED_KEY_PREFIX] {
+        let found: Vec<String> = redis::cmd("KEYS")
+            .arg(format!("{prefix}*"))
+            .query_async(conn)
+            .await?;
+        keys.extend(found);
+    }
+    Ok(keys)
+}
+
+/// The name of the queue of a queue key or scheduled set key
+fn queue_name(key: &str) -> &str {
+    key.strip_prefix(QUEUE_KEY_PREFIX)
+        .or_else(|| key.strip_prefix(SCHEDULED_KEY_PREFIX))
+        .unwrap_or(key)
+}
+
 /// Retrieves a list of jobs from the Redis queues.
 ///
 /// This function queries Redis for jobs, optionally filtering by their
@@ -460,12 +731,8 @@ pub async fn get_jobs(
     let mut conn = get_connection(client).await?;
     let mut jobs = Vec::new();
 
-    // Get all queue keys
-    let queue_pattern = format!("{QUEUE_KEY_PREFIX}*");
-    let queue_keys: Vec<String> = redis::cmd("KEYS")
-        .arg(&queue_pattern)
-        .query_async(&mut conn)
-        .await?;
+    // Get all queue keys and scheduled sets
+    let queue_keys = waiting_keys(&mut conn).await?;
 
     // Get all processing keys
     let processing_pattern = format!("{PROCESSING_KEY_PREFIX}*");
@@ -476,7 +743,7 @@ pub async fn get_jobs(
 
     // Collect jobs from queues
     for queue_key in queue_keys {
-        let job_ids: Vec<String> = conn.lrange(&queue_key, 0, -1).await?;
+        let job_ids: Vec<String> = conn.zrange(&queue_key, 0, -1).await?;
         for job_id in job_ids {
             let job_key = format!("{JOB_KEY_PREFIX}{job_id}");
             let job_json: Option<String> = conn.get(&job_key).await?;
@@ -544,12 +811,8 @@ fn should_include_job(job: &Job, status: Option<&Vec<JobStatus>>, age_days: Opti
 pub async fn clear_by_status(client: &RedisPool, status: Vec<JobStatus>) -> Result<()> {
     let mut conn = get_connection(client).await?;
 
-    // Get all queue keys
-    let queue_pattern = format!("{QUEUE_KEY_PREFIX}*");
-    let queue_keys: Vec<String> = redis::cmd("KEYS")
-        .arg(&queue_pattern)
-        .query_async(&mut conn)
-        .await?;
+    // Get all queue keys and scheduled sets
+    let queue_keys = waiting_keys(&mut conn).await?;
 
     // Get all processing keys
     let processing_pattern = format!("{PROCESSING_KEY_PREFIX}*");
@@ -568,7 +831,7 @@ pub async fn clear_by_status(client: &RedisPool, status: Vec<JobStatus>) -> Resu
     // Process queues
     for queue_key in queue_keys {
         // Get all jobs in the queue
-        let job_ids: Vec<String> = conn.lrange(&queue_key, 0, -1).await?;
+        let job_ids: Vec<String> = conn.zrange(&queue_key, 0, -1).await?;
 
         // Process each job individually
         for job_id in job_ids {
@@ -577,7 +840,7 @@ pub async fn clear_by_status(client: &RedisPool, status: Vec<JobStatus>) -> Resu
             if let Some(json) = job_json {
                 if let Ok(job) = Job::from_json(&json) {
                     if status.contains(&job.status) {
-                        let _: () = conn.lrem(&queue_key, 1, &job_id).await?;
+                        let _: () = conn.zrem(&queue_key, &job_id).await?;
                         let _: () = conn.del(&job_key).await?;
                     }
                 }
@@ -635,12 +898,8 @@ pub async fn clear_jobs_older_than(
     let mut conn = get_connection(client).await?;
     let cutoff_date = Utc::now() - chrono::Duration::days(age_days);
 
-    // Get all queue keys
-    let queue_pattern = format!("{QUEUE_KEY_PREFIX}*");
-    let queue_keys: Vec<String> = redis::cmd("KEYS")
-        .arg(&queue_pattern)
-        .query_async(&mut conn)
-        .await?;
+    // Get all queue keys and scheduled sets
+    let queue_keys = waiting_keys(&mut conn).await?;
 
     // Get all processing keys
     let processing_pattern = format!("{PROCESSING_KEY_PREFIX}*");
@@ -659,7 +918,7 @@ pub async fn clear_jobs_older_than(
     // Process queues
     for queue_key in queue_keys {
         // Get all jobs in the queue
-        let job_ids: Vec<String> = conn.lrange(&queue_key, 0, -1).await?;
+        let job_ids: Vec<String> = conn.zrange(&queue_key, 0, -1).await?;
 
         // Process each job individually
         for job_id in job_ids {
@@ -671,7 +930,7 @@ pub async fn clear_jobs_older_than(
                         created_at < cutoff_date && status.map_or(true, |s| s.contains(&job.status))
                     });
                     if should_remove {
-                        let _: () = conn.lrem(&queue_key, 1, &job_id).await?;
+                        let _: () = conn.zrem(&queue_key, &job_id).await?;
                         let _: () = conn.del(&job_key).await?;
                     }
                 }
@@ -769,7 +1028,9 @@ pub async fn requeue(client: &RedisPool, age_minutes: &i64) -> Result<()> {
                         let updated_json = job.to_json()?;
                         let _: () = conn.srem(&processing_key, &job_id).await?;
                         let _: () = conn.set(&job_key, &updated_json).await?;
-                        let _: () = conn.rpush(&queue_key, &job_id).await?;
+                        let _: () = conn
+                            .zadd(&queue_key, &job_id, queue_score(job.priority))
+                            .await?;
                         *requeued_counts.entry(queue_name.clone()).or_insert(0) += 1;
                     }
                 }
@@ -777,14 +1038,14 @@ pub async fn requeue(client: &RedisPool, age_minutes: &i64) -> Result<()> {
         }
     }
 
-    let failed_pattern = "failed:*";
+    let failed_pattern = format!("{FAILED_KEY_PREFIX}*");
     let failed_keys: Vec<String> = redis::cmd("KEYS")
-        .arg(failed_pattern)
+        .arg(&failed_pattern)
         .query_async(&mut conn)
         .await?;
 
     for failed_key in failed_keys {
-        let queue_name = failed_key.trim_start_matches("failed:").to_string();
+        let queue_name = failed_key.trim_start_matches(FAILED_KEY_PREFIX).to_string();
         let queue_key = format!("{QUEUE_KEY_PREFIX}{queue_name}");
         let job_ids: Vec<String> = conn.smembers(&failed_key).await?;
 
@@ -804,7 +1065,9 @@ pub async fn requeue(client: &RedisPool, age_minutes: &i64) -> Result<()> {
                         let updated_json = job.to_json()?;
                         let _: () = conn.srem(&failed_key, &job_id).await?;
                         let _: () = conn.set(&job_key, &updated_json).await?;
-                        let _: () = conn.rpush(&queue_key, &job_id).await?;
+                        let _: () = conn
+                            .zadd(&queue_key, &job_id, queue_score(job.priority))
+                            .await?;
                         *requeued_counts.entry(queue_name.clone()).or_insert(0) += 1;
                     }
                 }
@@ -832,17 +1095,13 @@ pub async fn requeue(client: &RedisPool, age_minutes: &i64) -> Result<()> {
 pub async fn cancel_jobs_by_name(client: &RedisPool, job_name: &str) -> Result<()> {
     let mut conn = get_connection(client).await?;
 
-    // Get all queue keys
-    let queue_pattern = format!("{QUEUE_KEY_PREFIX}*");
-    let queue_keys: Vec<String> = redis::cmd("KEYS")
-        .arg(&queue_pattern)
-        .query_async(&mut conn)
-        .await?;
+    // Get all queue keys and scheduled sets
+    let queue_keys = waiting_keys(&mut conn).await?;
 
     // Process each queue
     for queue_key in queue_keys {
         // Get all jobs in the queue
-        let job_ids: Vec<String> = conn.lrange(&queue_key, 0, -1).await?;
+        let job_ids: Vec<String> = conn.zrange(&queue_key, 0, -1).await?;
         for job_id in job_ids {
             let job_key = format!("{JOB_KEY_PREFIX}{job_id}");
             let job_json: Option<String> = conn.get(&job_key).await?;
@@ -852,12 +1111,9 @@ pub async fn cancel_jobs_by_name(client: &RedisPool, job_name: &str) -> Result<(
                         job.status = JobStatus::Cancelled;
                         job.updated_at = Some(Utc::now());
                         let updated_json = job.to_json()?;
-                        let _: () = conn.lrem(&queue_key, 1, &job_id).await?;
+                        let _: () = conn.zrem(&queue_key, &job_id).await?;
                         let _: () = conn.set(&job_key, &updated_json).await?;
-                        let cancelled_key = format!(
-                            "cancelled:{}",
-                            queue_key.trim_start_matches(QUEUE_KEY_PREFIX)
-                        );
+                        let cancelled_key = format!("cancelled:{}", queue_name(&queue_key));
                         let _: () = conn.sadd(&cancelled_key, &job_id).await?;
                     }
                 }
@@ -867,6 +1123,122 @@ pub async fn cancel_jobs_by_name(client: &RedisPool, job_name: &str) -> Result<(
     Ok(())
 }
 
+/// Releases the lock of a scheduler job, if still held by the given run
+const RELEASE_LOCK_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
+async fn save_run(conn: &mut Connection, run: &Run) -> Result<()> {
+    let run_key = format!("{SCHEDULER_RUN_KEY_PREFIX}{}", run.id);
+    let _: () = conn
+        .set_ex(&run_key, serde_json::to_string(run)?, SCHEDULER_RUN_TTL)
+        .await?;
+    Ok(())
+}
+
+/// Claims the run of a scheduler job, see
+/// [`Queue::claim_scheduled_run`](super::Queue::claim_scheduled_run). The tick
+/// is claimed with a key set only once, and a job with `no_overlap` is locked
+/// with a key expiring after `lock_timeout`. Runs are kept for a week.
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn claim_scheduled_run(
+    client: &RedisPool,
+    job_name: &str,
+    scheduled_at: DateTime<Utc>,
+    no_overlap: bool,
+    lock_timeout: chrono::Duration,
+) -> Result<Option<Run>> {
+    let mut conn = get_connection(client).await?;
+    let mut run = Run::new(job_name, scheduled_at);
+
+    let claim_key = format!(
+        "{SCHEDULER_CLAIM_KEY_PREFIX}{job_name}:{}",
+        scheduled_at.timestamp()
+    );
+    let claimed: bool = redis::cmd("SET")
+        .arg(&claim_key)
+        .arg(&run.id)
+        .arg("NX")
+        .arg("EX")
+        .arg(SCHEDULER_RUN_TTL)
+        .query_async::<Option<String>>(&mut conn)
+        .await?
+        .is_some();
+    if !claimed {
+        debug!(job_name, scheduled_at = %scheduled_at, "scheduler job run claimed already");
+        return Ok(None);
+    }
+
+    if no_overlap {
+        let lock_key = format!("{SCHEDULER_LOCK_KEY_PREFIX}{job_name}");
+        let locked: bool = redis::cmd("SET")
+            .arg(&lock_key)
+            .arg(&run.id)
+            .arg("NX")
+            .arg("EX")
+            .arg(lock_timeout.num_seconds().max(1))
+            .query_async::<Option<String>>(&mut conn)
+            .await?
+            .is_some();
+        if !locked {
+            debug!(job_name, "scheduler job still running, skipping run");
+            run.finish(RunStatus::Skipped, None);
+            save_run(&mut conn, &run).await?;
+            return Ok(None);
+        }
+    }
+
+    save_run(&mut conn, &run).await?;
+    Ok(Some(run))
+}
+
+/// Records the outcome of a scheduler job run, releasing the lock of its job
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn finish_scheduled_run(client: &RedisPool, run: &Run) -> Result<()> {
+    let mut conn = get_connection(client).await?;
+    save_run(&mut conn, run).await?;
+    let lock_key = format!("{SCHEDULER_LOCK_KEY_PREFIX}{}", run.job_name);
+    let _: i64 = Script::new(RELEASE_LOCK_SCRIPT)
+        .key(&lock_key)
+        .arg(&run.id)
+        .invoke_async(&mut conn)
+        .await?;
+    Ok(())
+}
+
+/// Retrieves the latest scheduler job runs, most recent first
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn scheduled_runs(client: &RedisPool, limit: usize) -> Result<Vec<Run>> {
+    let mut conn = get_connection(client).await?;
+    let run_keys: Vec<String> = redis::cmd("KEYS")
+        .arg(format!("{SCHEDULER_RUN_KEY_PREFIX}*"))
+        .query_async(&mut conn)
+        .await?;
+
+    let mut runs = Vec::new();
+    for run_key in run_keys {
+        let run_json: Option<String> = conn.get(&run_key).await?;
+        if let Some(run) = run_json.and_then(|json| serde_json::from_str::<Run>(&json).ok()) {
+            runs.push(run);
+        }
+    }
+    runs.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+    runs.truncate(limit);
+    Ok(runs)
+}
+
 pub const DEFAULT_QUEUES: &[&str] = &["default", "mailer"];
 
 pub fn get_queues(config_queues: &Option<Vec<String>>) -> Vec<String> {
@@ -953,6 +1325,8 @@ mod tests {
                 created_at: Some(now - chrono::Duration::days(15)),
                 updated_at: Some(now - chrono::Duration::days(15)),
                 tags: None,
+                priority: 0,
+                attempts: 0,
             };
 
             let mut conn = get_connection(client).await?;
@@ -1073,7 +1447,7 @@ mod tests {
         // Verify job was created in correct queue first
         let mut conn = get_test_connection(&client).await;
         let queue_key = format!("{QUEUE_KEY_PREFIX}mailer");
-        let queue_len: i64 = conn.llen(&queue_key).await.expect("get queue length");
+        let queue_len: i64 = conn.zcard(&queue_key).await.expect("get queue length");
         assert_eq!(queue_len, 1);
 
         // Test dequeue from mailer queue
@@ -1083,10 +1457,102 @@ mod tests {
             .expect("dequeue");
 
         // Queue should now be empty
-        let queue_len: i64 = conn.llen(&queue_key).await.expect("get queue length");
+        let queue_len: i64 = conn.zcard(&queue_key).await.expect("get queue length");
         assert_eq!(queue_len, 0);
     }
 
+    #[tokio::test]
+    async fn test_can_schedule_and_prioritize_redis() {
+        let (client, _container) = setup_redis().await;
+
+        let later = Utc::now() + chrono::Duration::hours(1);
+        enqueue_at(
+            &client,
+            "Later".to_string(),
+            None,
+            serde_json::json!({}),
+            None,
+            later,
+            10,
+        )
+        .await
+        .expect("enqueue delayed job");
+        for (name, priority) in [("Low", -1), ("Default", 0), ("High", 10)] {
+            enqueue_at(
+                &client,
+                name.to_string(),
+                None,
+                serde_json::json!({}),
+                None,
+                Utc::now(),
+                priority,
+            )
+            .await
+            .expect("enqueue job");
+        }
+
+        let queues = vec!["default".to_string()];
+        let mut conn = get_test_connection(&client).await;
+        for name in ["High", "Default", "Low"] {
+            let (job, _) = dequeue_with_conn(&mut conn, &queues, &[])
+                .await
+                .expect("dequeue")
+                .expect("job to dequeue");
+            assert_eq!(job.name, name);
+        }
+        assert!(dequeue_with_conn(&mut conn, &queues, &[])
+            .await
+            .expect("dequeue")
+            .is_none());
+
+        // once due, the delayed job moves to the queue
+        let scheduled_key = format!("{SCHEDULED_KEY_PREFIX}default");
+        let job_ids: Vec<String> = conn
+            .zrange(&scheduled_key, 0, -1)
+            .await
+            .expect("get scheduled jobs");
+        let _: () = conn
+            .zadd(&scheduled_key, &job_ids[0], Utc::now().timestamp_millis())
+            .await
+            .expect("make job due");
+        let (job, _) = dequeue_with_conn(&mut conn, &queues, &[])
+            .await
+            .expect("dequeue")
+            .expect("job to dequeue");
+        assert_eq!(job.name, "Later");
+    }
+
+    #[tokio::test]
+    async fn test_can_convert_queue_lists_redis() {
+        let (client, _container) = setup_redis().await;
+
+        let mut conn = get_test_connection(&client).await;
+        let job = Job::new(
+            Ulid::new().to_string(),
+            "TestJob".to_string(),
+            serde_json::json!({}),
+        );
+        let _: () = conn
+            .set(
+                format!("{JOB_KEY_PREFIX}{}", job.id),
+                job.to_json().unwrap(),
+            )
+            .await
+            .expect("store job");
+        let _: () = conn
+            .rpush(format!("{QUEUE_KEY_PREFIX}default"), &job.id)
+            .await
+            .expect("push job");
+
+        initialize(&client).await.expect("initialize");
+
+        let (dequeued, _) = dequeue_with_conn(&mut conn, &["default".to_string()], &[])
+            .await
+            .expect("dequeue")
+            .expect("job to dequeue");
+        assert_eq!(dequeued.id, job.id);
+    }
+
     #[tokio::test]
     async fn test_can_complete_job_redis() {
         let (client, _container) = setup_redis().await;
@@ -1156,13 +1622,14 @@ mod tests {
                 .is_ok()
         );
 
-        // Verify job is back in queue
-        let queue_key = format!("{QUEUE_KEY_PREFIX}{queue}");
-        let queue_len: i64 = conn.llen(&queue_key).await.expect("get queue length");
-        assert_eq!(queue_len, 1);
-
-        // Get the job ID from the queue
-        let job_id: String = conn.lindex(&queue_key, 0).await.expect("get job id");
+        // Verify job is scheduled to run again
+        let scheduled_key = format!("{SCHEDULED_KEY_PREFIX}{queue}");
+        let job_ids: Vec<String> = conn
+            .zrange(&scheduled_key, 0, -1)
+            .await
+            .expect("get scheduled jobs");
+        assert_eq!(job_ids.len(), 1);
+        let job_id = &job_ids[0];
 
         // Get the job data using the ID
         let job_key = format!("{JOB_KEY_PREFIX}{job_id}");
@@ -1210,7 +1677,74 @@ mod tests {
         let job_json: String = conn.get(&job_key).await.expect("get job");
         let failed_job = Job::from_json(&job_json).expect("parse job");
         assert_eq!(failed_job.status, JobStatus::Failed);
+        assert_eq!(failed_job.attempts, 1);
+        assert_eq!(
+            failed_job.data.get("task"),
+            Some(&serde_json::json!("test"))
+        );
         assert!(failed_job.data.get("error").is_some());
+
+        // Verify job is in the failed set
+        let failed_key = format!("{FAILED_KEY_PREFIX}{queue}");
+        let is_member: bool = conn
+            .sismember(&failed_key, &job.id)
+            .await
+            .expect("check membership");
+        assert!(is_member);
+    }
+
+    #[tokio::test]
+    async fn test_can_retry_job_redis() {
+        let (client, _container) = setup_redis().await;
+
+        let args = serde_json::json!({"task": "test"});
+        assert!(enqueue(&client, "TestJob".to_string(), None, args, None)
+            .await
+            .is_ok());
+
+        let queues = vec!["default".to_string()];
+        let mut conn = get_test_connection(&client).await;
+        let (job, queue) = dequeue_with_conn(&mut conn, &queues, &[])
+            .await
+            .expect("dequeue")
+            .unwrap();
+
+        // A retry waits in the scheduled set
+        let retry_at = Utc::now() + chrono::Duration::hours(1);
+        assert!(retry_job_with_conn(&mut conn, &job.id, &queue, retry_at)
+            .await
+            .is_ok());
+        let scheduled_key = format!("{SCHEDULED_KEY_PREFIX}{queue}");
+        let scheduled: i64 = conn.zcard(&scheduled_key).await.expect("zcard");
+        assert_eq!(scheduled, 1);
+        assert!(dequeue_with_conn(&mut conn, &queues, &[])
+            .await
+            .expect("dequeue")
+            .is_none());
+
+        // Once failed for good, it can be retried by hand
+        let error = Error::string("test failure");
+        assert!(fail_job_with_conn(&mut conn, &job.id, &queue, &error)
+            .await
+            .is_ok());
+        let _: () = conn.zrem(&scheduled_key, &job.id).await.expect("zrem");
+        assert!(retry_failed_job(&client, &job.id).await.is_ok());
+
+        let job_key = format!("{JOB_KEY_PREFIX}{}", job.id);
+        let job_json: String = conn.get(&job_key).await.expect("get job");
+        let retried_job = Job::from_json(&job_json).expect("parse job");
+        assert_eq!(retried_job.status, JobStatus::Queued);
+        assert_eq!(retried_job.attempts, 0);
+        assert_eq!(retried_job.data, serde_json::json!({"task": "test"}));
+
+        let failed_key = format!("{FAILED_KEY_PREFIX}{queue}");
+        let failed: i64 = conn.scard(&failed_key).await.expect("scard");
+        assert_eq!(failed, 0);
+        let queue_key = format!("{QUEUE_KEY_PREFIX}{queue}");
+        let queued: i64 = conn.zcard(&queue_key).await.expect("zcard");
+        assert_eq!(queued, 1);
+
+        assert!(retry_failed_job(&client, &job.id).await.is_err());
     }
 
     #[tokio::test]
@@ -1443,6 +1977,8 @@ mod tests {
             created_at: Some(Utc::now() - chrono::Duration::days(15)),
             updated_at: Some(Utc::now() - chrono::Duration::days(15)),
             tags: None,
+            priority: 0,
+            attempts: 0,
         };
 
         // Create an old completed job (older than 10 days)
@@ -1456,6 +1992,8 @@ mod tests {
             created_at: Some(Utc::now() - chrono::Duration::days(15)),
             updated_at: Some(Utc::now() - chrono::Duration::days(15)),
             tags: None,
+            priority: 0,
+            attempts: 0,
         };
 
         // Store both jobs directly
@@ -1529,4 +2067,54 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_can_claim_scheduled_run_redis() {
+        let (client, _container) = setup_redis().await;
+
+        let scheduled_at = Utc::now();
+        let lock_timeout = chrono::Duration::minutes(1);
+        let mut run = claim_scheduled_run(&client, "Backup", scheduled_at, true, lock_timeout)
+            .await
+            .unwrap()
+            .expect("claimed run");
+        assert_eq!(run.status, RunStatus::Running);
+
+        // another instance, for the same tick
+        assert!(
+            claim_scheduled_run(&client, "Backup", scheduled_at, true, lock_timeout)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // the next tick, overlapping the run in progress
+        let next_tick = scheduled_at + chrono::Duration::seconds(1);
+        assert!(
+            claim_scheduled_run(&client, "Backup", next_tick, true, lock_timeout)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        run.finish(RunStatus::Succeeded, None);
+        assert!(finish_scheduled_run(&client, &run).await.is_ok());
+
+        let next_tick = next_tick + chrono::Duration::seconds(1);
+        assert!(
+            claim_scheduled_run(&client, "Backup", next_tick, true, lock_timeout)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let mut statuses: Vec<String> = scheduled_runs(&client, 10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|run| run.status.to_string())
+            .collect();
+        statuses.sort();
+        assert_eq!(statuses, vec!["running", "skipped", "succeeded"]);
+    }
 }
@@ -4,8 +4,12 @@ use std::{
     time::Duration,
 };
 
-use super::{BackgroundWorker, JobStatus, Queue};
-use crate::{config::SqliteQueueConfig, Error, Result};
+use super::{BackgroundWorker, JobStatus, Queue, RetryPolicy};
+use crate::{
+    config::SqliteQueueConfig,
+    scheduler::{Run, RunStatus},
+    Error, Result,
+};
 use chrono::{DateTime, Utc};
 use futures_util::FutureExt;
 use serde::{Deserialize, Serialize};
@@ -44,10 +48,17 @@ pub struct Job {
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
     pub tags: Option<Vec<String>>,
+    /// Jobs with a higher priority run first
+    #[serde(default)]
+    pub priority: i32,
+    /// The attempts that failed so far
+    #[serde(default)]
+    pub attempts: i32,
 }
 
 pub struct JobRegistry {
     handlers: Arc<HashMap<String, JobHandler>>,
+    retry_policies: HashMap<String, RetryPolicy>,
 }
 
 impl JobRegistry {
@@ -56,6 +67,7 @@ impl JobRegistry {
     pub fn new() -> Self {
         Self {
             handlers: Arc::new(HashMap::new()),
+            retry_policies: HashMap::new(),
         }
     }
 
@@ -97,7 +109,8 @@ impl JobRegistry {
 
         Arc::get_mut(&mut self.handlers)
             .ok_or_else(|| Error::string("cannot register worker"))?
-            .insert(name, Box::new(wrapped_handler));
+            .insert(name.clone(), Box::new(wrapped_handler));
+        self.retry_policies.insert(name, W::retry_policy());
         Ok(())
     }
 
@@ -119,8 +132,10 @@ impl JobRegistry {
         let mut jobs = Vec::new();
 
         let interval = opts.poll_interval_sec;
+        let retry_policies = Arc::new(self.retry_policies.clone());
         for idx in 0..opts.num_workers {
             let handlers = self.handlers.clone();
+            let retry_policies = retry_policies.clone();
             let worker_token = token.clone();
             let worker_tags = tags.to_vec();
 
@@ -163,7 +178,26 @@ impl JobRegistry {
                                     }
                                 }
                                 Err(err) => {
-                                    if let Err(fail_err) = fail_job(&pool, &job.id, &err).await {
+                                    let attempt = job.attempts.unsigned_abs() + 1;
+                                    let retry_at = retry_policies
+                                        .get(&job.name)
+                                        .and_then(|policy| policy.retry_at(attempt));
+                                    if let Some(retry_at) = retry_at {
+                                        if let Err(retry_err) =
+                                            retry_job(&pool, &job.id, retry_at).await
+                                        {
+                                            error!(
+                                                error = %retry_err,
+                                                job_id = %job.id,
+                                                job_name = %job.name,
+                                                "Failed to reschedule job for retry"
+                                            );
+                                        } else {
+                                            debug!(job_id = %job.id, error = %err, attempt, retry_at = %retry_at, "Job execution failed, retrying later");
+                                        }
+                                    } else if let Err(fail_err) =
+                                        fail_job(&pool, &job.id, &err).await
+                                    {
                                         error!(
                                             error = %fail_err,
                                             job_id = %job.id,
@@ -239,7 +273,9 @@ pub async fn initialize_database(pool: &SqlitePool) -> Result<()> {
                 interval INTEGER,
                 created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
                 updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                tags JSON
+                tags JSON,
+                priority INTEGER NOT NULL DEFAULT 0,
+                attempts INTEGER NOT NULL DEFAULT 0
             );
 
             CREATE TABLE IF NOT EXISTS sqlt_loco_queue_lock (
@@ -251,10 +287,38 @@ pub async fn initialize_database(pool: &SqlitePool) -> Result<()> {
             INSERT OR IGNORE INTO sqlt_loco_queue_lock (id, is_locked) VALUES (1, FALSE);
 
             CREATE INDEX IF NOT EXISTS idx_sqlt_queue_status_run_at ON sqlt_loco_queue(status, run_at);
+
+            CREATE TABLE IF NOT EXISTS sqlt_loco_scheduler_runs (
+                id TEXT NOT NULL PRIMARY KEY,
+                job_name TEXT NOT NULL,
+                scheduled_at TIMESTAMP NOT NULL,
+                status TEXT NOT NULL,
+                started_at TIMESTAMP NOT NULL,
+                finished_at TIMESTAMP,
+                error TEXT,
+                UNIQUE (job_name, scheduled_at)
+            );
             ", JobStatus::Queued),
     )
     .execute(pool)
     .await?;
+
+    // tables created by earlier versions lack some of the columns
+    for column in ["priority", "attempts"] {
+        let has_column: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('sqlt_loco_queue') WHERE name = $1",
+        )
+        .bind(column)
+        .fetch_one(pool)
+        .await?;
+        if !has_column {
+            sqlx::query(&format!(
+                "ALTER TABLE sqlt_loco_queue ADD COLUMN {column} INTEGER NOT NULL DEFAULT 0"
+            ))
+            .execute(pool)
+            .await?;
+        }
+    }
     Ok(())
 }
 
@@ -270,6 +334,24 @@ pub async fn enqueue(
     run_at: DateTime<Utc>,
     interval: Option<Duration>,
     tags: Option<Vec<String>>,
+) -> Result<JobId> {
+    enqueue_with_priority(pool, name, data, run_at, interval, tags, 0).await
+}
+
+/// Add a job with a `priority`: of the jobs due, those with a higher priority
+/// run first.
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn enqueue_with_priority(
+    pool: &SqlitePool,
+    name: &str,
+    data: JobData,
+    run_at: DateTime<Utc>,
+    interval: Option<Duration>,
+    tags: Option<Vec<String>>,
+    priority: i32,
 ) -> Result<JobId> {
     let data = serde_json::to_value(data)?;
     let tags_json = match &tags {
@@ -281,10 +363,10 @@ pub async fn enqueue(
     let interval_ms: Option<i64> = interval.map(|i| i.as_millis() as i64);
 
     let id = Ulid::new().to_string();
-    debug!(job_id = %id, job_name = %name, run_at = %run_at, tags = ?tags, "Enqueueing job");
+    debug!(job_id = %id, job_name = %name, run_at = %run_at, tags = ?tags, priority, "Enqueueing job");
     sqlx::query(
-        "INSERT INTO sqlt_loco_queue (id, task_data, name, run_at, interval, tags) VALUES ($1, $2, $3, \
-         DATETIME($4), $5, $6)",
+        "INSERT INTO sqlt_loco_queue (id, task_data, name, run_at, interval, tags, priority) VALUES \
+         ($1, $2, $3, DATETIME($4), $5, $6, $7)",
     )
     .bind(id.clone())
     .bind(data)
@@ -292,6 +374,7 @@ pub async fn enqueue(
     .bind(run_at)
     .bind(interval_ms)
     .bind(tags_json)
+    .bind(priority)
     .execute(pool)
     .await?;
     Ok(id)
@@ -318,7 +401,7 @@ async fn dequeue(client: &SqlitePool, worker_tags: &[String]) -> Result<Option<J
 
     // Build the query with tag filtering
     let mut query = String::from(
-        "SELECT id, name, task_data, status, run_at, interval, tags
+        "SELECT id, name, task_data, status, run_at, interval, tags, priority, attempts
         FROM sqlt_loco_queue
         WHERE
             status = ? AND
@@ -346,7 +429,7 @@ async fn dequeue(client: &SqlitePool, worker_tags: &[String]) -> Result<Option<J
         }
     }
 
-    query.push_str(" ORDER BY run_at LIMIT 1");
+    query.push_str(" ORDER BY priority DESC, run_at LIMIT 1");
 
     let mut db_query = sqlx::query(&query).bind(JobStatus::Queued.to_string());
 
@@ -439,7 +522,7 @@ async fn fail_job(pool: &SqlitePool, id: &JobId, error: &crate::Error) -> Result
     let error_json = serde_json::json!({ "error": msg });
     sqlx::query(
         "UPDATE sqlt_loco_queue SET status = $1, updated_at = CURRENT_TIMESTAMP, task_data = \
-         json_patch(task_data, $2) WHERE id = $3",
+         json_patch(task_data, $2), attempts = attempts + 1 WHERE id = $3",
     )
     .bind(JobStatus::Failed.to_string())
     .bind(error_json)
@@ -449,6 +532,139 @@ async fn fail_job(pool: &SqlitePool, id: &JobId, error: &crate::Error) -> Result
     Ok(())
 }
 
+async fn retry_job(pool: &SqlitePool, id: &JobId, run_at: DateTime<Utc>) -> Result<()> {
+    debug!(job_id = %id, run_at = %run_at, "Rescheduling failed job");
+    sqlx::query(
+        "UPDATE sqlt_loco_queue SET status = $1, updated_at = CURRENT_TIMESTAMP, run_at = \
+         DATETIME($2), attempts = attempts + 1 WHERE id = $3",
+    )
+    .bind(JobStatus::Queued.to_string())
+    .bind(run_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Queues a [`JobStatus::Failed`] job again to run now, with its attempts
+/// reset and the error removed from its data.
+///
+/// # Errors
+///
+/// This function will return an error if it fails, or if there is no failed
+/// job with the given id
+pub async fn retry_failed_job(pool: &SqlitePool, id: &str) -> Result<()> {
+    debug!(job_id = %id, "Retrying failed job");
+    let result = sqlx::query(
+        "UPDATE sqlt_loco_queue SET status = $1, updated_at = CURRENT_TIMESTAMP, run_at = \
+         CURRENT_TIMESTAMP, attempts = 0, task_data = json_remove(task_data, '$.error') WHERE id \
+         = $2 AND status = $3",
+    )
+    .bind(JobStatus::Queued.to_string())
+    .bind(id)
+    .bind(JobStatus::Failed.to_string())
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::Message(format!("no failed job with id `{id}`")));
+    }
+    Ok(())
+}
+
+/// Claims the run of a scheduler job, see
+/// [`Queue::claim_scheduled_run`](super::Queue::claim_scheduled_run).
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn claim_scheduled_run(
+    pool: &SqlitePool,
+    job_name: &str,
+    scheduled_at: DateTime<Utc>,
+    no_overlap: bool,
+    lock_timeout: chrono::Duration,
+) -> Result<Option<Run>> {
+    let overlapping = no_overlap
+        && sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM sqlt_loco_scheduler_runs WHERE job_name = $1 AND status = $2 \
+             AND started_at > DATETIME($3))",
+        )
+        .bind(job_name)
+        .bind(RunStatus::Running.to_string())
+        .bind(Utc::now() - lock_timeout)
+        .fetch_one(pool)
+        .await?;
+
+    let mut run = Run::new(job_name, scheduled_at);
+    if overlapping {
+        run.finish(RunStatus::Skipped, None);
+    }
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO sqlt_loco_scheduler_runs (id, job_name, scheduled_at, status, started_at, \
+         finished_at) VALUES ($1, $2, DATETIME($3), $4, DATETIME($5), DATETIME($6))",
+    )
+    .bind(&run.id)
+    .bind(job_name)
+    .bind(scheduled_at)
+    .bind(run.status.to_string())
+    .bind(run.started_at)
+    .bind(run.finished_at)
+    .execute(pool)
+    .await?;
+
+    let claimed = result.rows_affected() > 0;
+    debug!(job_name, scheduled_at = %scheduled_at, claimed, overlapping, "Claiming scheduler job run");
+    Ok((claimed && !overlapping).then_some(run))
+}
+
+/// Records the outcome of a scheduler job run
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn finish_scheduled_run(pool: &SqlitePool, run: &Run) -> Result<()> {
+    debug!(run_id = %run.id, job_name = %run.job_name, status = %run.status, "Recording scheduler job run");
+    sqlx::query(
+        "UPDATE sqlt_loco_scheduler_runs SET status = $1, finished_at = DATETIME($2), error = $3 WHERE id = $4",
+    )
+    .bind(run.status.to_string())
+    .bind(run.finished_at)
+    .bind(&run.error)
+    .bind(&run.id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Retrieves the latest scheduler job runs, most recent first
+///
+/// # Errors
+///
+/// This function will return an error if it fails
+pub async fn scheduled_runs(pool: &SqlitePool, limit: usize) -> Result<Vec<Run>> {
+    let rows =
+        sqlx::query("SELECT * FROM sqlt_loco_scheduler_runs ORDER BY started_at DESC LIMIT $1")
+            .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+            .fetch_all(pool)
+            .await?;
+    rows.iter().map(to_run).collect()
+}
+
+fn to_run(row: &SqliteRow) -> Result<Run> {
+    Ok(Run {
+        id: row.get("id"),
+        job_name: row.get("job_name"),
+        scheduled_at: row.get("scheduled_at"),
+        status: row
+            .get::<String, _>("status")
+            .parse()
+            .map_err(|_| Error::string("invalid scheduler run status"))?,
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+        error: row.get("error"),
+    })
+}
+
 /// Cancels jobs in the `sqlt_loco_queue` table by their name.
 ///
 /// This function updates the status of all jobs with the given `name` and a status of
@@ -701,6 +917,8 @@ fn to_job(row: &SqliteRow) -> Result<Job> {
         created_at: row.try_get("created_at").unwrap_or_default(),
         updated_at: row.try_get("updated_at").unwrap_or_default(),
         tags,
+        priority: row.try_get("priority").unwrap_or_default(),
+        attempts: row.try_get("attempts").unwrap_or_default(),
     })
 }
 
@@ -812,6 +1030,48 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn can_add_priority_to_existing_queue() {
+        let tree_fs = tree_fs::TreeBuilder::default()
+            .drop(true)
+            .create()
+            .expect("create temp folder");
+        let pool = init(&tree_fs.root).await;
+
+        sqlx::query(
+            "CREATE TABLE sqlt_loco_queue (
+                id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                task_data JSON NOT NULL,
+                status TEXT NOT NULL DEFAULT 'queued',
+                run_at TIMESTAMP NOT NULL,
+                interval INTEGER,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                tags JSON
+            )",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(initialize_database(&pool).await.is_ok());
+        assert!(initialize_database(&pool).await.is_ok());
+
+        assert!(enqueue_with_priority(
+            &pool,
+            "PasswordChangeNotification",
+            serde_json::json!({}),
+            Utc::now(),
+            None,
+            None,
+            3
+        )
+        .await
+        .is_ok());
+        assert_eq!(get_all_jobs(&pool).await[0].priority, 3);
+    }
+
     #[tokio::test]
     async fn can_enqueue() {
         let tree_fs = tree_fs::TreeBuilder::default()
@@ -921,6 +1181,64 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn can_dequeue_by_priority() {
+        let tree_fs = tree_fs::TreeBuilder::default()
+            .drop(true)
+            .create()
+            .expect("create temp folder");
+        let pool = init(&tree_fs.root).await;
+
+        assert!(initialize_database(&pool).await.is_ok());
+
+        let run_at = Utc::now() - chrono::Duration::minutes(5);
+        for (name, priority) in [("Low", -1), ("Default", 0), ("High", 10)] {
+            assert!(enqueue_with_priority(
+                &pool,
+                name,
+                serde_json::json!({}),
+                run_at,
+                None,
+                None,
+                priority
+            )
+            .await
+            .is_ok());
+        }
+
+        for name in ["High", "Default", "Low"] {
+            let job = dequeue(&pool, &[]).await.unwrap().expect("job to dequeue");
+            assert_eq!(job.name, name);
+        }
+    }
+
+    #[tokio::test]
+    async fn cannot_dequeue_delayed_job() {
+        let tree_fs = tree_fs::TreeBuilder::default()
+            .drop(true)
+            .create()
+            .expect("create temp folder");
+        let pool = init(&tree_fs.root).await;
+
+        assert!(initialize_database(&pool).await.is_ok());
+
+        let run_at = Utc::now() + chrono::Duration::hours(1);
+        assert!(enqueue_with_priority(
+            &pool,
+            "Reminder",
+            serde_json::json!({}),
+            run_at,
+            None,
+            None,
+            10
+        )
+        .await
+        .is_ok());
+
+        assert!(dequeue(&pool, &[]).await.unwrap().is_none());
+        assert_eq!(get_all_jobs(&pool).await[0].status, JobStatus::Queued);
+    }
+
     #[tokio::test]
     async fn can_complete_job_without_interval() {
         let tree_fs = tree_fs::TreeBuilder::default()
@@ -1365,6 +1683,105 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn can_retry_job_until_attempts_run_out() {
+        let tree_fs = tree_fs::TreeBuilder::default()
+            .drop(true)
+            .create()
+            .expect("create temp folder");
+        let pool = init(&tree_fs.root).await;
+
+        assert!(initialize_database(&pool).await.is_ok());
+
+        let job_id = enqueue(
+            &pool,
+            "FlakyJob",
+            serde_json::json!({"user_id": 1}),
+            Utc::now(),
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to enqueue job");
+
+        struct FlakyWorker;
+        #[async_trait::async_trait]
+        impl BackgroundWorker<JsonValue> for FlakyWorker {
+            fn build(_ctx: &crate::app::AppContext) -> Self {
+                Self
+            }
+            fn retry_policy() -> RetryPolicy {
+                RetryPolicy::new(3).with_backoff(Duration::ZERO, Duration::ZERO)
+            }
+            async fn perform(&self, _args: JsonValue) -> crate::Result<()> {
+                Err(Error::string("service unavailable"))
+            }
+        }
+
+        let mut registry = JobRegistry::new();
+        assert!(registry
+            .register_worker("FlakyJob".to_string(), FlakyWorker)
+            .is_ok());
+
+        let opts = RunOpts {
+            num_workers: 1,
+            poll_interval_sec: 1,
+        };
+        let token = CancellationToken::new();
+        let handles = registry.run(&pool, &opts, &token, &[]);
+        sleep(Duration::from_secs(2)).await;
+        for handle in handles {
+            handle.abort();
+        }
+
+        let failed_job = get_job(&pool, &job_id).await;
+        assert_eq!(failed_job.status, JobStatus::Failed);
+        assert_eq!(failed_job.attempts, 3);
+        assert_eq!(
+            failed_job.data,
+            serde_json::json!({"user_id": 1, "error": "service unavailable"})
+        );
+
+        assert!(retry_failed_job(&pool, &job_id).await.is_ok());
+        let retried_job = get_job(&pool, &job_id).await;
+        assert_eq!(retried_job.status, JobStatus::Queued);
+        assert_eq!(retried_job.attempts, 0);
+        assert_eq!(retried_job.data, serde_json::json!({"user_id": 1}));
+
+        assert!(retry_failed_job(&pool, &job_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_reschedule_job_for_retry() {
+        let tree_fs = tree_fs::TreeBuilder::default()
+            .drop(true)
+            .create()
+            .expect("create temp folder");
+        let pool = init(&tree_fs.root).await;
+
+        assert!(initialize_database(&pool).await.is_ok());
+
+        let job_id = enqueue(
+            &pool,
+            "FlakyJob",
+            serde_json::json!({}),
+            Utc::now(),
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to enqueue job");
+        let job = dequeue(&pool, &[]).await.unwrap().expect("job to dequeue");
+
+        let retry_at = Utc::now() + chrono::Duration::hours(1);
+        assert!(retry_job(&pool, &job.id, retry_at).await.is_ok());
+
+        let job = get_job(&pool, &job_id).await;
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.attempts, 1);
+        assert!(dequeue(&pool, &[]).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn can_dequeue_with_tags() {
         let tree_fs = tree_fs::TreeBuilder::default()
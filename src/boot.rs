@@ -13,12 +13,11 @@ use sea_orm_migration::MigratorTrait;
 use tokio::{select, signal, task::JoinHandle};
 use tracing::{debug, error, info, warn};
 
-#[cfg(feature = "with-db")]
-use crate::db;
 use crate::{
     app::{AppContext, Hooks, Initializer},
     banner::print_banner,
     bgworker, cache,
+    channels::Channels,
     config::{self, Config, WorkerMode},
     controller::ListRoutes,
     env_vars,
@@ -31,6 +30,8 @@ use crate::{
     task::{self, Tasks},
     Result,
 };
+#[cfg(feature = "with-db")]
+use crate::{db, fixtures};
 
 /// Represents the application startup mode.
 #[derive(Debug)]
@@ -223,11 +224,17 @@ fn scheduler<H: Hooks>(
         }
     };
 
+    let scheduler = match &app_context.queue_provider {
+        Some(queue) => scheduler.with_queue(queue.clone()),
+        None => scheduler,
+    };
+
     Ok(scheduler.by_spec(&scheduler::Spec { name, tag }))
 }
 
 /// Runs the scheduler with the given configuration and context. in case if list
-/// args is true prints scheduler job configuration
+/// args is true prints scheduler job configuration, and in case if history
+/// args is true prints the latest recorded job runs
 ///
 /// This function initializes the scheduler, registers tasks through the
 /// provided [`Hooks`], and executes the scheduler based on the specified
@@ -245,6 +252,7 @@ pub async fn run_scheduler<H: Hooks>(
     name: Option<String>,
     tag: Option<String>,
     list: bool,
+    history: bool,
 ) -> Result<()> {
     let task_span = tracing::span!(tracing::Level::DEBUG, "scheduler_jobs");
     let _guard = task_span.enter();
@@ -253,6 +261,21 @@ pub async fn run_scheduler<H: Hooks>(
     if list {
         println!("{scheduler}");
         Ok(())
+    } else if history {
+        for run in scheduler.history(50).await? {
+            println!(
+                "{:<20} {:<10} {:<19} {:<19} {}",
+                run.job_name,
+                run.status,
+                run.started_at.format("%Y-%m-%d %H:%M:%S"),
+                run.finished_at.map_or_else(
+                    || "-".to_string(),
+                    |finished_at| finished_at.format("%Y-%m-%d %H:%M:%S").to_string()
+                ),
+                run.error.unwrap_or_default()
+            );
+        }
+        Ok(())
     } else {
         Ok(scheduler.run().await?)
     }
@@ -277,6 +300,7 @@ pub enum RunDbCommand {
     /// Seed database.
     Seed {
         reset: bool,
+        upsert: bool,
         from: PathBuf,
         dump: bool,
         dump_tables: Option<Vec<String>>,
@@ -301,6 +325,7 @@ pub async fn run_db<H: Hooks, M: MigratorTrait>(
         RunDbCommand::Migrate => {
             tracing::warn!("migrate:");
             db::migrate::<M>(&app_context.db).await?;
+            crate::tenancy::migrate::<H, M>(app_context).await?;
         }
         RunDbCommand::Down(steps) => {
             tracing::warn!("down:");
@@ -325,6 +350,7 @@ pub async fn run_db<H: Hooks, M: MigratorTrait>(
         }
         RunDbCommand::Seed {
             reset,
+            upsert,
             from,
             dump,
             dump_tables,
@@ -337,6 +363,9 @@ pub async fn run_db<H: Hooks, M: MigratorTrait>(
                 if reset {
                     db::reset::<M>(&app_context.db).await?;
                 }
+                if upsert {
+                    app_context.shared_store.insert(fixtures::Mode::Upsert);
+                }
                 db::run_app_seed::<H>(app_context, &from).await?;
             }
         }
@@ -374,6 +403,8 @@ pub async fn create_context<H: Hooks>(
     };
 
     let queue_provider = bgworker::create_queue_provider(&config).await?;
+    let mut channels = Channels::default();
+    H::register_channels(&mut channels);
     let ctx = AppContext {
         environment: environment.clone(),
         #[cfg(feature = "with-db")]
@@ -384,8 +415,20 @@ pub async fn create_context<H: Hooks>(
         config,
         mailer,
         shared_store: Arc::new(crate::app::SharedStore::default()),
+        channels: Arc::new(channels),
     };
 
+    #[cfg(feature = "with-db")]
+    if let Some(tenancy) = &ctx.config.tenancy {
+        let resolver = H::tenant_resolver(tenancy);
+        ctx.shared_store
+            .insert(Arc::new(crate::tenancy::Tenants::new(
+                tenancy.clone(),
+                resolver,
+                crate::tenancy::list_tenants::<H>,
+            )));
+    }
+
     H::after_context(ctx).await
 }
 
@@ -578,5 +621,17 @@ fn create_mailer(config: &config::Mailer) -> Result<Option<EmailSender>> {
             return Ok(Some(EmailSender::smtp(smtp)?));
         }
     }
+    #[cfg(feature = "mailer_ses")]
+    if let Some(ses) = config.ses.as_ref() {
+        if ses.enable {
+            return Ok(Some(EmailSender::ses(ses)));
+        }
+    }
+    #[cfg(feature = "mailer_sendgrid")]
+    if let Some(sendgrid) = config.sendgrid.as_ref() {
+        if sendgrid.enable {
+            return Ok(Some(EmailSender::sendgrid(sendgrid)));
+        }
+    }
     Ok(None)
 }
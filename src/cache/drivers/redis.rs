@@ -1,7 +1,7 @@
 //! # Redis Cache Driver
 //!
 //! This module implements a cache driver using Redis.
-use std::time::Duration;
+use std::{borrow::Cow, time::Duration};
 
 use async_trait::async_trait;
 use bb8::Pool;
@@ -31,13 +31,18 @@ pub async fn new(config: &RedisCacheConfig) -> CacheResult<crate::cache::Cache>
         .build(manager)
         .await?;
 
-    Ok(crate::cache::Cache::new(Redis::from(pool)))
+    let driver = match &config.namespace {
+        Some(namespace) => Redis::with_namespace(pool, namespace),
+        None => Redis::from(pool),
+    };
+    Ok(crate::cache::Cache::new(driver))
 }
 
 /// Represents the Redis cache driver.
 #[derive(Clone, Debug)]
 pub struct Redis {
     pool: Pool<RedisConnectionManager>,
+    namespace: Option<String>,
 }
 
 impl Redis {
@@ -48,8 +53,54 @@ impl Redis {
     /// A boxed [`CacheDriver`] instance.
     #[must_use]
     pub fn from(pool: Pool<RedisConnectionManager>) -> Box<dyn CacheDriver> {
-        Box::new(Self { pool })
+        Box::new(Self {
+            pool,
+            namespace: None,
+        })
     }
+
+    /// Constructs a new [`Redis`] instance prefixing its keys with
+    /// `<namespace>:`.
+    ///
+    /// # Returns
+    ///
+    /// A boxed [`CacheDriver`] instance.
+    #[must_use]
+    pub fn with_namespace(
+        pool: Pool<RedisConnectionManager>,
+        namespace: &str,
+    ) -> Box<dyn CacheDriver> {
+        Box::new(Self {
+            pool,
+            namespace: Some(namespace.to_string()),
+        })
+    }
+
+    /// The Redis key of `key`, in the namespace if any
+    fn key<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        namespaced(self.namespace.as_deref(), key)
+    }
+}
+
+fn namespaced<'a>(namespace: Option<&str>, key: &'a str) -> Cow<'a, str> {
+    match namespace {
+        Some(namespace) => Cow::Owned(format!("{namespace}:{key}")),
+        None => Cow::Borrowed(key),
+    }
+}
+
+/// The `SCAN` pattern matching the keys of `namespace`, its glob characters
+/// escaped.
+fn namespace_pattern(namespace: &str) -> String {
+    let mut pattern = String::with_capacity(namespace.len() + 2);
+    for c in namespace.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push_str(":*");
+    pattern
 }
 
 #[async_trait]
@@ -74,7 +125,7 @@ impl CacheDriver for Redis {
     /// Returns a `CacheError` if there is an error during the operation.
     async fn contains_key(&self, key: &str) -> CacheResult<bool> {
         let mut connection = self.pool.get().await?;
-        Ok(connection.exists(key).await?)
+        Ok(connection.exists(self.key(key).as_ref()).await?)
     }
 
     /// Retrieves a value from the cache based on the provided key.
@@ -84,7 +135,7 @@ impl CacheDriver for Redis {
     /// Returns a `CacheError` if there is an error during the operation.
     async fn get(&self, key: &str) -> CacheResult<Option<String>> {
         let mut conn = self.pool.get().await?;
-        let result: Option<String> = conn.get(key).await?;
+        let result: Option<String> = conn.get(self.key(key).as_ref()).await?;
         Ok(result)
     }
 
@@ -95,7 +146,7 @@ impl CacheDriver for Redis {
     /// Returns a `CacheError` if there is an error during the operation.
     async fn insert(&self, key: &str, value: &str) -> CacheResult<()> {
         let mut conn = self.pool.get().await?;
-        conn.set::<_, _, ()>(key, value).await?;
+        conn.set::<_, _, ()>(self.key(key).as_ref(), value).await?;
         Ok(())
     }
 
@@ -114,7 +165,7 @@ impl CacheDriver for Redis {
     ) -> CacheResult<()> {
         let mut conn = self.pool.get().await?;
         // Redis expects the expiry in seconds as a u64
-        conn.set_ex::<_, _, ()>(key, value, duration.as_secs())
+        conn.set_ex::<_, _, ()>(self.key(key).as_ref(), value, duration.as_secs())
             .await?;
         Ok(())
     }
@@ -126,19 +177,41 @@ impl CacheDriver for Redis {
     /// Returns a `CacheError` if there is an error during the operation.
     async fn remove(&self, key: &str) -> CacheResult<()> {
         let mut conn = self.pool.get().await?;
-        conn.del::<_, ()>(key).await?;
+        conn.del::<_, ()>(self.key(key).as_ref()).await?;
         Ok(())
     }
 
-    /// Clears all key-value pairs from the cache.
+    /// Clears all key-value pairs from the cache, only the namespace's when
+    /// namespaced.
     ///
     /// # Errors
     ///
     /// Returns a `CacheError` if there is an error during the operation.
     async fn clear(&self) -> CacheResult<()> {
         let mut conn = self.pool.get().await?;
-        cmd("FLUSHDB").query_async::<()>(&mut *conn).await?;
-        Ok(())
+        let Some(namespace) = &self.namespace else {
+            cmd("FLUSHDB").query_async::<()>(&mut *conn).await?;
+            return Ok(());
+        };
+        let pattern = namespace_pattern(namespace);
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(100)
+                .query_async(&mut *conn)
+                .await?;
+            if !keys.is_empty() {
+                conn.del::<_, ()>(keys).await?;
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
     }
 }
 
@@ -156,6 +229,7 @@ mod tests {
         let redis_config = crate::config::RedisCacheConfig {
             uri: redis_url,
             max_size: 10,
+            namespace: None,
         };
 
         let cache = new(&redis_config)
@@ -296,4 +370,58 @@ mod tests {
             .await
             .expect("Failed to check if key exists after expiry"));
     }
+
+    #[tokio::test]
+    async fn test_namespace() {
+        let (redis_url, _container) = setup_redis_container().await;
+        let driver = |namespace: Option<&str>| {
+            let config = crate::config::RedisCacheConfig {
+                uri: redis_url.clone(),
+                max_size: 10,
+                namespace: namespace.map(ToString::to_string),
+            };
+            async move {
+                new(&config)
+                    .await
+                    .expect("Failed to create Redis driver")
+                    .driver
+            }
+        };
+        let plain = driver(None).await;
+        let app = driver(Some("app")).await;
+
+        app.insert("key", "namespaced")
+            .await
+            .expect("Failed to insert key");
+        plain
+            .insert("key", "plain")
+            .await
+            .expect("Failed to insert key");
+        assert_eq!(
+            plain.get("app:key").await.expect("Failed to get key"),
+            Some("namespaced".to_string())
+        );
+        assert_eq!(
+            app.get("key").await.expect("Failed to get key"),
+            Some("namespaced".to_string())
+        );
+
+        app.clear().await.expect("Failed to clear cache");
+        assert!(!app
+            .contains_key("key")
+            .await
+            .expect("Failed to check if key exists"));
+        assert!(plain
+            .contains_key("key")
+            .await
+            .expect("Failed to check if key exists"));
+    }
+
+    #[test]
+    fn can_namespace_keys() {
+        assert_eq!(namespaced(None, "key"), "key");
+        assert_eq!(namespaced(Some("app"), "key"), "app:key");
+        assert_eq!(namespace_pattern("app"), "app:*");
+        assert_eq!(namespace_pattern("a*p[1]"), r"a\*p\[1\]:*");
+    }
 }
@@ -0,0 +1,207 @@
+//! Realtime channels, broadcasting messages to the subscribers of a named
+//! channel, e.g. a job's progress from a worker.
+//!
+//! Register the channels in [`Hooks::register_channels`](crate::app::Hooks),
+//! broadcast on them through [`AppContext::channels`]. No route serves them
+//! until the app mounts one in `Hooks::routes`, authorizing the subscribers
+//! as it sees fit: a handler of its own, e.g. the WebSocket one `cargo loco
+//! generate channel` scaffolds, or [`routes`], streaming every channel as
+//! server-sent events at `/channels/<name>`.
+//!
+//! ```rust
+//! use loco_rs::{app::AppContext, channels::Channels, Result};
+//!
+//! fn register_channels(channels: &mut Channels) {
+//!     channels.register("progress");
+//! }
+//!
+//! fn report(ctx: &AppContext, done: u32) -> Result<()> {
+//!     ctx.channels.broadcast("progress", "done", &done)?;
+//!     Ok(())
+//! }
+//! ```
+use std::{collections::BTreeMap, convert::Infallible};
+
+use axum::{
+    extract::{Path, State},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+};
+use futures_util::Stream;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::{app::AppContext, controller::Routes, Error, Result};
+
+/// The messages a channel buffers for a subscriber lagging behind, before it
+/// misses the oldest of them.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// A message broadcast on a channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Message {
+    /// The kind of message, e.g. `progress`, sent as the event's name
+    pub event: String,
+    pub data: serde_json::Value,
+}
+
+/// The channels of the app, by name
+#[derive(Debug, Default)]
+pub struct Channels {
+    senders: BTreeMap<String, broadcast::Sender<Message>>,
+}
+
+impl Channels {
+    /// Registers the channel `name`
+    pub fn register(&mut self, name: &str) {
+        self.register_with_capacity(name, DEFAULT_CAPACITY);
+    }
+
+    /// Registers the channel `name`, buffering up to `capacity` messages for a
+    /// subscriber lagging behind. A channel registered already keeps its
+    /// capacity.
+    ///
+    /// # Panics
+    ///
+    /// When `capacity` is zero
+    pub fn register_with_capacity(&mut self, name: &str, capacity: usize) {
+        self.senders
+            .entry(name.to_string())
+            .or_insert_with(|| broadcast::channel(capacity).0);
+    }
+
+    /// Whether no channel is registered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    /// Whether the channel `name` is registered
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.senders.contains_key(name)
+    }
+
+    /// The names of the registered channels
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.senders.keys().map(String::as_str)
+    }
+
+    fn sender(&self, name: &str) -> Result<&broadcast::Sender<Message>> {
+        self.senders
+            .get(name)
+            .ok_or_else(|| Error::Message(format!("channel `{name}` is not registered")))
+    }
+
+    /// Broadcasts `event` with its `data` to the current subscribers of the
+    /// channel `name`, returning how many there are. No subscriber is not an
+    /// error.
+    ///
+    /// # Errors
+    ///
+    /// When the channel is not registered, or `data` fails to serialize
+    pub fn broadcast<T: Serialize>(&self, name: &str, event: &str, data: &T) -> Result<usize> {
+        let sender = self.sender(name)?;
+        let message = Message {
+            event: event.to_string(),
+            data: serde_json::to_value(data)?,
+        };
+        Ok(sender.send(message).unwrap_or(0))
+    }
+
+    /// Subscribes to the messages broadcast on the channel `name` from now on
+    ///
+    /// # Errors
+    ///
+    /// When the channel is not registered
+    pub fn subscribe(&self, name: &str) -> Result<broadcast::Receiver<Message>> {
+        Ok(self.sender(name)?.subscribe())
+    }
+
+    /// The server-sent events streaming the channel `name` to a new
+    /// subscriber, e.g. from a controller authorizing it first. The messages
+    /// it lags behind on are skipped.
+    ///
+    /// # Errors
+    ///
+    /// When the channel is not registered
+    pub fn sse(
+        &self,
+        name: &str,
+    ) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+        let receiver = self.subscribe(name)?;
+        let events = futures_util::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(message) => {
+                        let event = Event::default()
+                            .event(&message.event)
+                            .data(message.data.to_string());
+                        return Some((Ok(event), receiver));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+    }
+}
+
+/// The routes streaming each channel as server-sent events at
+/// `/channels/<name>`, to anyone.
+///
+/// ```rust,ignore
+/// AppRoutes::with_default_routes().add_route(loco_rs::channels::routes())
+/// ```
+#[must_use]
+pub fn routes() -> Routes {
+    Routes::new().prefix("channels").add("/{name}", get(stream))
+}
+
+async fn stream(State(ctx): State<AppContext>, Path(name): Path<String>) -> Result<Response> {
+    if !ctx.channels.contains(&name) {
+        return Err(Error::NotFound);
+    }
+    Ok(ctx.channels.sse(&name)?.into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn can_broadcast_to_subscribers() {
+        let mut channels = Channels::default();
+        channels.register("progress");
+
+        assert_eq!(channels.broadcast("progress", "done", &1).unwrap(), 0);
+        let mut first = channels.subscribe("progress").unwrap();
+        let mut second = channels.subscribe("progress").unwrap();
+        assert_eq!(channels.broadcast("progress", "done", &50).unwrap(), 2);
+
+        let message = Message {
+            event: "done".to_string(),
+            data: serde_json::json!(50),
+        };
+        assert_eq!(first.recv().await.unwrap(), message);
+        assert_eq!(second.recv().await.unwrap(), message);
+    }
+
+    #[test]
+    fn cannot_use_unregistered_channel() {
+        let channels = Channels::default();
+        assert!(channels.is_empty());
+        assert_eq!(
+            channels
+                .broadcast("progress", "done", &1)
+                .unwrap_err()
+                .to_string(),
+            "channel `progress` is not registered"
+        );
+        assert!(channels.subscribe("progress").is_err());
+    }
+}
@@ -15,7 +15,7 @@
 //! }
 //! ```
 #[cfg(feature = "with-db")]
-use {crate::boot::run_db, crate::db, sea_orm_migration::MigratorTrait};
+use {crate::boot::run_db, crate::db, cruet::Inflector, sea_orm_migration::MigratorTrait};
 
 use clap::{ArgAction, ArgGroup, Parser, Subcommand, ValueHint};
 use colored::Colorize;
@@ -85,6 +85,10 @@ enum Commands {
         /// disable the banner display
         #[arg(short, long, action = ArgAction::SetTrue)]
         no_banner: bool,
+        /// Rebuild and restart on changes of `src/`, `config/`, `assets/` and
+        /// `migration/`, reloading the browser with server-side views
+        #[arg(long, action)]
+        watch: bool,
     },
     #[cfg(feature = "with-db")]
     /// Perform DB operations
@@ -92,6 +96,12 @@ enum Commands {
         #[command(subcommand)]
         command: DbCommands,
     },
+    #[cfg(feature = "with-db")]
+    /// Issue, list and revoke API keys, kept in the `api_keys` table
+    ApiKeys {
+        #[command(subcommand)]
+        command: ApiKeysCommands,
+    },
     /// Describe all application endpoints
     Routes {},
     /// Describe all application middlewares
@@ -131,6 +141,9 @@ enum Commands {
         /// Show all configured jobs
         #[arg(short, long, action)]
         list: bool,
+        /// Show the latest recorded runs of the jobs, with the `queue` lock
+        #[arg(long, action)]
+        history: bool,
     },
     /// code generation creates a set of files and code templates based on a
     /// predefined set of rules.
@@ -141,6 +154,18 @@ enum Commands {
         #[command(subcommand)]
         component: ComponentArg,
     },
+    /// reverse a generation: delete the files it created and take out what
+    /// it injected into other files.
+    #[cfg(debug_assertions)]
+    Destroy {
+        /// The generation, as given to `generate`, e.g. `scaffold post`
+        #[arg(required = true, num_args = 1..)]
+        generator: Vec<String>,
+        /// Roll back the migrations the generation created first
+        #[cfg(feature = "with-db")]
+        #[arg(long, action)]
+        rollback: bool,
+    },
     /// Validate and diagnose configurations.
     Doctor {
         /// print out the current configurations.
@@ -152,7 +177,8 @@ enum Commands {
     /// Display the app version
     Version {},
 
-    /// Watch and restart the app
+    /// Watch and restart the app with `cargo-watch`, see `start --watch`
+    /// for a built-in watch mode
     #[clap(alias("w"))]
     Watch {
         /// start worker
@@ -185,6 +211,21 @@ enum ComponentArg {
 
   - Generate model without timestamps:
       $ cargo loco g model posts title:string content:text --without-tz
+
+  - Generate model with composite indexes:
+      $ cargo loco g model posts title:string user:references --index user_id,created_at --unique user_id,title
+
+  - Generate model with rows stamped with their tenant:
+      $ cargo loco g model projects name:string! --tenant
+
+  - Generate model with soft deletes (a `deleted_at` column):
+      $ cargo loco g model posts title:string! --soft-delete
+
+  - Generate model with one primary address per user:
+      $ cargo loco g model addresses street:string! is_primary:bool! user:references --unique-when \"user_id is_primary\"
+
+  - Generate the join table of articles and tags:
+      $ cargo loco g model article_tags position:int! --many-to-many article,tag
 ",
     "Examples:".bold().underline()
 ))]
@@ -199,6 +240,36 @@ enum ComponentArg {
         /// Model fields, eg. title:string hits:int
         #[clap(value_parser = parse_key_val::<String,String>)]
         fields: Vec<(String, String)>,
+
+        /// An index over comma separated columns, e.g. `user_id,created_at`
+        #[arg(long, value_name = "COLUMNS")]
+        index: Vec<String>,
+
+        /// A unique index over comma separated columns, e.g. `tenant_id,email`
+        #[arg(long, value_name = "COLUMNS")]
+        unique: Vec<String>,
+
+        /// Stamp rows with their tenant, a reference to `tenants`, or to the
+        /// tenant table named, e.g. `--tenant=organization`
+        #[arg(long, value_name = "NAME", num_args = 0..=1, require_equals = true, default_missing_value = "tenant")]
+        tenant: Option<String>,
+
+        /// Soft delete rows, with a `deleted_at` column and the
+        /// `SoftDeletable` helpers on the model
+        #[arg(long)]
+        soft_delete: bool,
+
+        /// Implement `Display` on the model, showing this column, e.g. `name`
+        #[arg(long, value_name = "COLUMN")]
+        display_column: Option<String>,
+
+        /// Generate the join table of a many-to-many relation between two
+        /// models, e.g. `article,tag`
+        #[arg(long, value_name = "MODELS")]
+        many_to_many: Option<String>,
+
+        #[command(flatten)]
+        migration: Box<MigrationArgs>,
     },
     #[cfg(feature = "with-db")]
     /// Generates a new migration file
@@ -252,13 +323,33 @@ After running the migration, follow these steps to complete the process:
         /// Table fields, eg. title:string hits:int
         #[clap(value_parser = parse_key_val::<String,String>, )]
         fields: Vec<(String, String)>,
+
+        /// An index over comma separated columns, e.g. `user_id,created_at`
+        #[arg(long, value_name = "COLUMNS")]
+        index: Vec<String>,
+
+        /// A unique index over comma separated columns, e.g. `tenant_id,email`
+        #[arg(long, value_name = "COLUMNS")]
+        unique: Vec<String>,
+
+        /// For a migration adding references, the `_id` columns already
+        /// exist: only add their foreign keys
+        #[arg(long)]
+        column_exists: bool,
+
+        #[command(flatten)]
+        migration: Box<MigrationArgs>,
     },
     #[cfg(feature = "with-db")]
     /// Generates a CRUD scaffold, model and controller
     #[command(after_help = format!("{}
  $ cargo loco g model posts title:string! user:references --api
 
- $ cargo loco g scaffold posts title:string! user:references --api --without-tz", "Examples:".bold().underline()))]
+ $ cargo loco g scaffold posts title:string! user:references --api --without-tz
+
+ $ cargo loco g scaffold posts title:string! --api --api-docs
+
+ $ cargo loco g scaffold posts title:string! --api --soft-delete", "Examples:".bold().underline()))]
     Scaffold {
         /// Name of the thing to generate
         name: String,
@@ -270,6 +361,13 @@ After running the migration, follow these steps to complete the process:
         /// Model fields, eg. title:string hits:int
         #[clap(value_parser = parse_key_val::<String,String>)]
         fields: Vec<(String, String)>,
+        /// An index over comma separated columns, e.g. `user_id,created_at`
+        #[arg(long, value_name = "COLUMNS")]
+        index: Vec<String>,
+
+        /// A unique index over comma separated columns, e.g. `tenant_id,email`
+        #[arg(long, value_name = "COLUMNS")]
+        unique: Vec<String>,
 
         /// The kind of scaffold to generate
         #[clap(short, long, value_enum, group = "scaffold_kind_group")]
@@ -286,6 +384,35 @@ After running the migration, follow these steps to complete the process:
         /// Use API scaffold
         #[clap(long, group = "scaffold_kind_group")]
         api: bool,
+
+        /// Document the api actions with `utoipa`, served as an OpenAPI spec
+        /// at `/api-docs`
+        #[arg(long)]
+        api_docs: bool,
+
+        /// Stamp rows with their tenant, a reference to `tenants`, or to the
+        /// tenant table named, e.g. `--tenant=organization`. The api actions
+        /// are scoped to the request's tenant.
+        #[arg(long, value_name = "NAME", num_args = 0..=1, require_equals = true, default_missing_value = "tenant")]
+        tenant: Option<String>,
+
+        /// Soft delete rows, with a `deleted_at` column. `remove` soft
+        /// deletes, and `list` leaves the deleted rows out unless queried
+        /// with `include_deleted=true`.
+        #[arg(long)]
+        soft_delete: bool,
+
+        /// Implement `Display` on the model, showing this column, e.g. `name`
+        #[arg(long, value_name = "COLUMN")]
+        display_column: Option<String>,
+
+        /// Generate the join table of a many-to-many relation between two
+        /// models, e.g. `article,tag`
+        #[arg(long, value_name = "MODELS")]
+        many_to_many: Option<String>,
+
+        #[command(flatten)]
+        migration: Box<MigrationArgs>,
     },
     /// Generate a new controller with the given controller name, and test file.
     #[command(after_help = format!(
@@ -320,6 +447,21 @@ After running the migration, follow these steps to complete the process:
         /// Use API controller actions
         #[clap(long, group = "scaffold_kind_group")]
         api: bool,
+
+        /// Document the api actions with `utoipa`, served as an OpenAPI spec
+        /// at `/api-docs`
+        #[arg(long)]
+        api_docs: bool,
+    },
+    #[cfg(feature = "with-db")]
+    /// Generate the `api_keys` table and model, for API key authentication
+    ApiKeys {},
+    #[cfg(feature = "with-db")]
+    /// Generate a fixture file of a model, with a record of its columns to
+    /// fill in
+    Fixture {
+        /// Name of the model
+        name: String,
     },
     /// Generate a Task based on the given name
     Task {
@@ -338,6 +480,11 @@ After running the migration, follow these steps to complete the process:
         /// Name of the thing to generate
         name: String,
     },
+    /// Generate a realtime channel, with a WebSocket handler at `/channels/<name>`
+    Channel {
+        /// Name of the thing to generate
+        name: String,
+    },
     /// Generate data loader
     Data {
         /// Name of the thing to generate
@@ -375,8 +522,166 @@ After running the migration, follow these steps to complete the process:
     },
 }
 
+/// Flags shaping the migration of the model, migration and scaffold
+/// generators
+#[cfg(all(debug_assertions, feature = "with-db"))]
+#[derive(clap::Args)]
+struct MigrationArgs {
+    /// A unique index over comma separated columns among the rows where a
+    /// boolean column is true, e.g. `"user_id is_primary"`
+    #[arg(long, value_name = "COLUMNS FLAG")]
+    unique_when: Vec<String>,
+
+    /// Create the table as a Postgres `UNLOGGED` table
+    #[arg(long)]
+    unlogged: bool,
+
+    /// Create the table as a `SQLite` `WITHOUT ROWID` table
+    #[arg(long)]
+    without_rowid: bool,
+
+    /// Create the table as a `SQLite` `STRICT` table
+    #[arg(long)]
+    sqlite_strict: bool,
+
+    /// A counter cache maintained by triggers, e.g.
+    /// `"posts.comments_count from comments"`
+    #[arg(long, value_name = "CACHE")]
+    counter_cache: Vec<String>,
+
+    /// A column maintained by triggers from others, e.g.
+    /// `"full_name from first_name,last_name"`
+    #[arg(long, value_name = "COLUMN")]
+    denormalize: Vec<String>,
+
+    /// A Postgres composite type created along with the table, e.g.
+    /// `"address (street text, city text)"`
+    #[arg(long, value_name = "TYPE")]
+    composite_type: Vec<String>,
+
+    /// An exclusion constraint keeping rows from overlapping on a range
+    /// column, e.g. `"room during"`
+    #[arg(long, value_name = "CONSTRAINT")]
+    no_overlap: Vec<String>,
+
+    /// Append to the latest migration instead of creating a new one
+    #[arg(long)]
+    append_to_latest: bool,
+}
+
+#[cfg(all(debug_assertions, feature = "with-db"))]
+impl MigrationArgs {
+    fn apply(&self, options: &mut loco_gen::GenerateOptions) -> crate::Result<()> {
+        for spec in &self.unique_when {
+            let Some((columns, flag)) = spec.trim().rsplit_once(char::is_whitespace) else {
+                return Err(Error::Message(format!(
+                    "cannot parse unique index: `{spec}`, expected `<columns> <flag>` such as \
+                     `user_id is_primary`"
+                )));
+            };
+            let mut index: loco_gen::IndexSpec = columns.parse()?;
+            index.unique = true;
+            index.when = Some(flag.to_string());
+            options.indexes.push(index);
+        }
+        options.unlogged = self.unlogged;
+        options.without_rowid = self.without_rowid;
+        options.sqlite_strict = self.sqlite_strict;
+        for cache in &self.counter_cache {
+            options.counter_caches.push(cache.parse()?);
+        }
+        for column in &self.denormalize {
+            options.denormalized.push(column.parse()?);
+        }
+        for composite in &self.composite_type {
+            options.composite_types.push(composite.parse()?);
+        }
+        for constraint in &self.no_overlap {
+            options.no_overlaps.push(constraint.parse()?);
+        }
+        options.append_to_latest = self.append_to_latest;
+        Ok(())
+    }
+}
+
 #[cfg(debug_assertions)]
 impl ComponentArg {
+    /// The generator options set by the component's flags, e.g. its
+    /// `--index` and `--unique` indexes.
+    fn gen_options(&self) -> crate::Result<loco_gen::GenerateOptions> {
+        let mut options = loco_gen::GenerateOptions::default();
+        #[cfg(feature = "with-db")]
+        if let Self::Model {
+            index,
+            unique,
+            migration,
+            ..
+        }
+        | Self::Migration {
+            index,
+            unique,
+            migration,
+            ..
+        }
+        | Self::Scaffold {
+            index,
+            unique,
+            migration,
+            ..
+        } = self
+        {
+            for columns in index {
+                options.indexes.push(columns.parse()?);
+            }
+            for columns in unique {
+                let mut index: loco_gen::IndexSpec = columns.parse()?;
+                index.unique = true;
+                options.indexes.push(index);
+            }
+            migration.apply(&mut options)?;
+        }
+        #[cfg(feature = "with-db")]
+        if let Self::Migration { column_exists, .. } = self {
+            options.column_exists = *column_exists;
+        }
+        #[cfg(feature = "with-db")]
+        if let Self::Model {
+            tenant,
+            soft_delete,
+            display_column,
+            many_to_many,
+            ..
+        }
+        | Self::Scaffold {
+            tenant,
+            soft_delete,
+            display_column,
+            many_to_many,
+            ..
+        } = self
+        {
+            options.tenant.clone_from(tenant);
+            options.soft_delete = *soft_delete;
+            options.display_column.clone_from(display_column);
+            if let Some(models) = many_to_many {
+                let Some((a, b)) = models.split_once(',') else {
+                    return Err(Error::Message(format!(
+                        "cannot parse many-to-many: `{models}`, expected two models such as \
+                         `article,tag`"
+                    )));
+                };
+                options.many_to_many = Some((a.trim().to_string(), b.trim().to_string()));
+            }
+        }
+        match self {
+            #[cfg(feature = "with-db")]
+            Self::Scaffold { api_docs, .. } => options.api_docs = *api_docs,
+            Self::Controller { api_docs, .. } => options.api_docs = *api_docs,
+            _ => {}
+        }
+        Ok(options)
+    }
+
     fn into_gen_component(self, config: &Config) -> crate::Result<loco_gen::Component> {
         match self {
             #[cfg(feature = "with-db")]
@@ -384,6 +689,7 @@ impl ComponentArg {
                 name,
                 without_tz,
                 fields,
+                ..
             } => Ok(loco_gen::Component::Model {
                 name,
                 with_tz: !without_tz,
@@ -394,6 +700,7 @@ impl ComponentArg {
                 name,
                 without_tz,
                 fields,
+                ..
             } => Ok(loco_gen::Component::Migration {
                 name,
                 with_tz: !without_tz,
@@ -408,6 +715,7 @@ impl ComponentArg {
                 htmx,
                 html,
                 api,
+                ..
             } => {
                 let kind = if let Some(kind) = kind {
                     kind
@@ -437,6 +745,7 @@ impl ComponentArg {
                 htmx,
                 html,
                 api,
+                ..
             } => {
                 let kind = if let Some(kind) = kind {
                     kind
@@ -458,10 +767,28 @@ impl ComponentArg {
                     kind,
                 })
             }
+            #[cfg(feature = "with-db")]
+            Self::ApiKeys {} => Ok(loco_gen::Component::ApiKeys {}),
+            #[cfg(feature = "with-db")]
+            Self::Fixture { name } => {
+                let entity = PathBuf::from("src/models/_entities")
+                    .join(format!("{}.rs", name.to_snake_case().to_plural()));
+                let source = std::fs::read_to_string(&entity).map_err(|err| {
+                    crate::Error::Message(format!(
+                        "could not read the entity of `{name}` at `{}`: {err}",
+                        entity.display()
+                    ))
+                })?;
+                Ok(loco_gen::Component::Fixture {
+                    name,
+                    columns: loco_gen::entity_columns(&source),
+                })
+            }
             Self::Task { name } => Ok(loco_gen::Component::Task { name }),
             Self::Scheduler {} => Ok(loco_gen::Component::Scheduler {}),
             Self::Worker { name } => Ok(loco_gen::Component::Worker { name }),
             Self::Mailer { name } => Ok(loco_gen::Component::Mailer { name }),
+            Self::Channel { name } => Ok(loco_gen::Component::Channel { name }),
             Self::Data { name } => Ok(loco_gen::Component::Data { name }),
             Self::Deployment { kind } => Ok(kind.to_generator_component(config)),
             Self::Override {
@@ -474,6 +801,30 @@ impl ComponentArg {
     }
 }
 
+#[cfg(feature = "with-db")]
+#[derive(Subcommand)]
+enum ApiKeysCommands {
+    /// Issues a key, printing it. It is only shown this once.
+    Issue {
+        /// What the key is for, e.g. the service using it.
+        #[arg(long)]
+        name: String,
+        /// Scopes the key grants, e.g. `reports:read`, `*` grants all.
+        #[arg(long, value_delimiter = ',')]
+        scope: Vec<String>,
+        /// Expires the key after this many days.
+        #[arg(long)]
+        expires_in: Option<u32>,
+    },
+    /// Lists the keys, latest first.
+    List {},
+    /// Revokes a key.
+    Revoke {
+        /// Prefix of the key, as listed, e.g. `lk_3fA9c2dQ`.
+        prefix: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum DbCommands {
     /// Create schema
@@ -501,6 +852,10 @@ enum DbCommands {
         /// Clears all data in the database before seeding.
         #[arg(short, long)]
         reset: bool,
+        /// Inserts the fixtures or updates the existing ones, so that seeding
+        /// again is safe.
+        #[arg(long)]
+        upsert: bool,
         /// Dumps all database tables to files.
         #[arg(short, long)]
         dump: bool,
@@ -528,11 +883,13 @@ impl From<DbCommands> for RunDbCommand {
             DbCommands::Truncate => Self::Truncate,
             DbCommands::Seed {
                 reset,
+                upsert,
                 from,
                 dump,
                 dump_tables,
             } => Self::Seed {
                 reset,
+                upsert,
                 from,
                 dump,
                 dump_tables,
@@ -631,6 +988,9 @@ enum JobsCommands {
         #[arg(short, long)]
         file: PathBuf,
     },
+    /// Lists the jobs waiting to run: the ones due by priority, then the
+    /// delayed ones by when they run.
+    List {},
     /// Change `processing` status to `queue`.
     Requeue {
         /// Change `processing` jobs older than the specified
@@ -638,6 +998,11 @@ enum JobsCommands {
         #[arg(long, default_value_t = 0)]
         from_age: i64,
     },
+    /// Queues a `failed` job again, once its retries ran out.
+    Retry {
+        /// Id of the failed job.
+        id: String,
+    },
 }
 
 /// Parse a single key-value pair
@@ -725,7 +1090,15 @@ pub async fn main<H: Hooks, M: MigratorTrait>() -> crate::Result<()> {
             binding,
             port,
             no_banner,
+            watch,
         } => {
+            if watch {
+                let args = std::env::args()
+                    .skip(1)
+                    .filter(|arg| arg != "--watch")
+                    .collect::<Vec<_>>();
+                return crate::watch::run(&args);
+            }
             let start_mode = worker.map_or(
                 if server_and_worker {
                     StartMode::ServerAndWorker
@@ -754,6 +1127,9 @@ pub async fn main<H: Hooks, M: MigratorTrait>() -> crate::Result<()> {
                 run_db::<H, M>(&app_context, command.into()).await?;
             }
         }
+        Commands::ApiKeys { command } => {
+            handle_api_keys_command(command, &app_context).await?;
+        }
         #[cfg(any(feature = "bg_redis", feature = "bg_pg", feature = "bg_sqlt"))]
         Commands::Jobs { command } => {
             handle_job_command::<H>(command, &environment, app_context.config).await?;
@@ -791,14 +1167,36 @@ pub async fn main<H: Hooks, M: MigratorTrait>() -> crate::Result<()> {
             config_path,
             tag,
             list,
+            history,
         } => {
             let app_context = create_context::<H>(&environment, app_context.config).await?;
-            run_scheduler::<H>(&app_context, config_path.as_ref(), name, tag, list).await?;
+            run_scheduler::<H>(&app_context, config_path.as_ref(), name, tag, list, history)
+                .await?;
         }
         #[cfg(debug_assertions)]
         Commands::Generate { component } => {
             handle_generate_command::<H>(component, &app_context.config)?;
         }
+        #[cfg(debug_assertions)]
+        Commands::Destroy {
+            generator,
+            rollback,
+        } => {
+            let label = generator.join(" ");
+            if rollback {
+                let generation = loco_gen::recorded(&label)?;
+                let migrations = generation
+                    .migrations()
+                    .iter()
+                    .filter_map(|path| path.file_stem()?.to_str().map(ToString::to_string))
+                    .collect::<Vec<_>>();
+                if !migrations.is_empty() {
+                    tracing::warn!("down:");
+                    db::down_latest::<M>(&app_context.db, &migrations).await?;
+                }
+            }
+            handle_destroy_command(&label)?;
+        }
         Commands::Doctor {
             config: config_arg,
             production,
@@ -875,7 +1273,15 @@ pub async fn main<H: Hooks>() -> crate::Result<()> {
             binding,
             port,
             no_banner,
+            watch,
         } => {
+            if watch {
+                let args = std::env::args()
+                    .skip(1)
+                    .filter(|arg| arg != "--watch")
+                    .collect::<Vec<_>>();
+                return crate::watch::run(&args);
+            }
             let start_mode = worker.map_or(
                 if server_and_worker {
                     StartMode::ServerAndWorker
@@ -929,13 +1335,19 @@ pub async fn main<H: Hooks>() -> crate::Result<()> {
             config_path,
             tag,
             list,
+            history,
         } => {
-            run_scheduler::<H>(&app_context, config_path.as_ref(), name, tag, list).await?;
+            run_scheduler::<H>(&app_context, config_path.as_ref(), name, tag, list, history)
+                .await?;
         }
         #[cfg(debug_assertions)]
         Commands::Generate { component } => {
             handle_generate_command::<H>(component, &app_context.config)?;
         }
+        #[cfg(debug_assertions)]
+        Commands::Destroy { generator } => {
+            handle_destroy_command(&generator.join(" "))?;
+        }
         Commands::Doctor {
             config: config_arg,
             production,
@@ -1210,6 +1622,60 @@ fn create_root_span(environment: &Environment) -> tracing::Span {
     tracing::span!(tracing::Level::DEBUG, "app", environment = %environment)
 }
 
+#[cfg(feature = "with-db")]
+async fn handle_api_keys_command(
+    command: ApiKeysCommands,
+    app_context: &AppContext,
+) -> crate::Result<()> {
+    use crate::auth::apikey;
+
+    match command {
+        ApiKeysCommands::Issue {
+            name,
+            scope,
+            expires_in,
+        } => {
+            let expires_at = expires_in
+                .map(|days| (chrono::Utc::now() + chrono::Duration::days(days.into())).into());
+            let issued = apikey::issue(&app_context.db, &name, &scope, expires_at).await?;
+            println!("{}", issued.key);
+            println!(
+                "Issued `{}` ({}), keep the key safe, it is not shown again.",
+                issued.api_key.name, issued.api_key.prefix
+            );
+        }
+        ApiKeysCommands::List {} => {
+            for key in apikey::list(&app_context.db).await? {
+                let expires_at = key.expires_at.map_or_else(
+                    || "never".to_string(),
+                    |at| at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                );
+                println!(
+                    "{:<12} {:<30} {:<19} {:<8} {}",
+                    key.prefix,
+                    key.name,
+                    expires_at,
+                    if key.is_active() {
+                        "active"
+                    } else {
+                        "inactive"
+                    },
+                    key.scopes.join(",")
+                );
+            }
+        }
+        ApiKeysCommands::Revoke { prefix } => {
+            if !apikey::revoke(&app_context.db, &prefix).await? {
+                return Err(crate::Error::Message(format!(
+                    "no active api key `{prefix}`"
+                )));
+            }
+            println!("Revoked `{prefix}`.");
+        }
+    }
+    Ok(())
+}
+
 #[cfg(any(feature = "bg_redis", feature = "bg_pg", feature = "bg_sqlt"))]
 async fn handle_job_command<H: Hooks>(
     command: JobsCommands,
@@ -1263,6 +1729,21 @@ async fn handle_job_command<H: Hooks>(
         }
         JobsCommands::Import { file } => queue.import(file.as_path()).await,
         JobsCommands::Requeue { from_age } => queue.requeue(from_age).await,
+        JobsCommands::Retry { id } => queue.retry_job(id).await,
+        JobsCommands::List {} => {
+            for job in queue.pending_jobs().await? {
+                let run_at = job.run_at.format("%Y-%m-%d %H:%M:%S").to_string();
+                println!(
+                    "{:<26} {:<30} {:>8} {:<19} {}",
+                    job.id,
+                    job.name,
+                    job.priority,
+                    run_at,
+                    if job.is_delayed() { "delayed" } else { "due" }
+                );
+            }
+            Ok(())
+        }
     }
 }
 
@@ -1311,19 +1792,54 @@ fn handle_generate_command<H: Hooks>(
             }
         }
     } else {
-        let get_result = loco_gen::generate(
+        let options = loco_gen::GenerateOptions {
+            #[cfg(feature = "with-db")]
+            backend: gen_backend(config),
+            ..component.gen_options()?
+        };
+        let component = component.into_gen_component(config)?;
+        let label = component.label();
+        let get_result = loco_gen::generate_with_options(
             &loco_gen::new_generator(),
-            component.into_gen_component(config)?,
+            component,
             &loco_gen::AppInfo {
                 app_name: H::app_name().to_string(),
             },
+            &options,
         )?;
+        // for `destroy` to reverse it
+        loco_gen::record(&label, &get_result)?;
         let messages = loco_gen::collect_messages(&get_result);
         println!("{messages}");
     }
     Ok(())
 }
 
+/// The backend of the app's database, for generated migrations to target.
+/// `None`, with a warning, when its URI is not recognized, for them to be
+/// generated portable.
+#[cfg(all(debug_assertions, feature = "with-db"))]
+fn gen_backend(config: &Config) -> Option<loco_gen::DbBackend> {
+    let backend = loco_gen::DbBackend::from_database_url(&config.database.uri);
+    if backend.is_none() {
+        tracing::warn!(
+            uri = config.database.uri,
+            "could not detect the database backend of the app, generating portable migrations"
+        );
+    }
+    backend
+}
+
+#[cfg(debug_assertions)]
+fn handle_destroy_command(label: &str) -> crate::Result<()> {
+    let generation = loco_gen::destroy(label)?;
+    println!("{}", format!("`{label}` was destroyed:").green());
+    for artifact in &generation.artifacts {
+        println!(" * {}", artifact.path.display());
+    }
+    Ok(())
+}
+
 #[must_use]
 pub fn format_templates_as_tree(paths: Vec<PathBuf>) -> String {
     let mut categories: BTreeMap<String, BTreeMap<String, Vec<PathBuf>>> = BTreeMap::new();
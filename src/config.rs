@@ -57,6 +57,10 @@ pub struct Config {
     pub workers: Workers,
     pub mailer: Option<Mailer>,
     pub initializers: Option<Initializers>,
+    /// Multi-tenancy, see [`crate::tenancy`]
+    #[cfg(feature = "with-db")]
+    #[serde(default)]
+    pub tenancy: Option<Tenancy>,
 
     /// Custom app settings
     ///
@@ -241,6 +245,91 @@ pub struct Database {
     pub run_on_start: Option<String>,
 }
 
+/// Multi-tenancy configuration: how the tenant of a request is resolved and
+/// how the data of tenants is kept apart. See [`crate::tenancy`].
+///
+/// Example, a schema per tenant, resolved by subdomain:
+/// ```yaml
+/// # config/development.yaml
+/// tenancy:
+///   resolver:
+///     kind: Subdomain
+///     domain: myapp.com
+///   isolation:
+///     kind: Schema
+///   tenants:
+///     - acme
+///     - globex
+/// ```
+#[cfg(feature = "with-db")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Tenancy {
+    /// Resolves the tenant of a request, unless the app overrides
+    /// [`crate::app::Hooks::tenant_resolver`]
+    pub resolver: TenantResolver,
+    #[serde(default)]
+    pub isolation: TenantIsolation,
+    /// The tenants, which `db migrate` migrates. When given, requests for any
+    /// other tenant are rejected.
+    #[serde(default)]
+    pub tenants: Vec<String>,
+    /// The most tenant pools kept open with schema or database isolation,
+    /// beyond which the least recently used one is closed
+    #[serde(default = "default_tenant_pools")]
+    pub max_pools: usize,
+}
+
+#[cfg(feature = "with-db")]
+const fn default_tenant_pools() -> usize {
+    64
+}
+
+/// Where the tenant of a request is taken from
+#[cfg(feature = "with-db")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind")]
+pub enum TenantResolver {
+    /// The subdomain of the `Host`, e.g. `acme` of `acme.myapp.com`
+    Subdomain { domain: String },
+    /// A request header, `x-tenant-id` by default
+    Header {
+        #[serde(default = "default_tenant_header")]
+        name: String,
+    },
+    /// A claim of the request's JWT, `tenant` by default
+    #[cfg(feature = "auth_jwt")]
+    Claim {
+        #[serde(default = "default_tenant_claim")]
+        name: String,
+    },
+}
+
+#[cfg(feature = "with-db")]
+fn default_tenant_header() -> String {
+    "x-tenant-id".to_string()
+}
+
+#[cfg(all(feature = "with-db", feature = "auth_jwt"))]
+fn default_tenant_claim() -> String {
+    "tenant".to_string()
+}
+
+/// How the data of tenants is kept apart
+#[cfg(feature = "with-db")]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(tag = "kind")]
+pub enum TenantIsolation {
+    /// Tenants share the tables, rows are stamped with their tenant's id
+    #[default]
+    Row,
+    /// Each tenant has a Postgres schema of its own, `tenant_<id>`, in the
+    /// database
+    Schema,
+    /// Each tenant has a database of its own, at `uri` with `{tenant}`
+    /// replaced by the tenant, e.g. `sqlite://data/{tenant}.sqlite?mode=rwc`
+    Database { uri: String },
+}
+
 /// Cache configurations for the application
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(tag = "kind")]
@@ -271,6 +360,11 @@ pub struct RedisCacheConfig {
     pub uri: String,
     /// Sets the maximum number of connections managed by the pool.
     pub max_size: u32,
+    /// Prefixes the keys with `<namespace>:`, e.g. to share a Redis database
+    /// between apps. Clearing the cache then only removes the namespace's
+    /// keys.
+    #[serde(default)]
+    pub namespace: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -477,12 +571,42 @@ pub struct Server {
     /// logging, and error handling.
     #[serde(default)]
     pub middlewares: middleware::Config,
+    /// How the `/_ready` route checks the app's dependencies
+    #[serde(default)]
+    pub readiness: Readiness,
 }
 
 fn default_binding() -> String {
     "localhost".to_string()
 }
 
+/// Readiness check configuration
+/// Example (development):
+/// ```yaml
+/// # config/development.yaml
+/// server:
+///   readiness:
+///     timeout: 2000
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Readiness {
+    /// How long each dependency has to answer, in milliseconds
+    #[serde(default = "default_readiness_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self {
+            timeout: default_readiness_timeout(),
+        }
+    }
+}
+
+fn default_readiness_timeout() -> u64 {
+    2_000
+}
+
 impl Server {
     #[must_use]
     pub fn full_url(&self) -> String {
@@ -529,10 +653,28 @@ pub enum WorkerMode {
 ///     port: 1025
 ///     secure: false
 /// ```
+///
+/// Example (production), sending through the SendGrid API instead, which
+/// needs the `mailer_sendgrid` feature (`mailer_ses` for `ses`):
+/// ```yaml
+/// # config/production.yaml
+/// mailer:
+///   sendgrid:
+///     enable: true
+///     api_key: {{ get_env(name="SENDGRID_API_KEY") }}
+/// ```
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Mailer {
     pub smtp: Option<SmtpMailer>,
 
+    /// Amazon SES API
+    #[cfg(feature = "mailer_ses")]
+    pub ses: Option<SesMailer>,
+
+    /// SendGrid API
+    #[cfg(feature = "mailer_sendgrid")]
+    pub sendgrid: Option<SendGridMailer>,
+
     #[serde(default)]
     pub stub: bool,
 }
@@ -567,6 +709,31 @@ pub struct SmtpMailer {
     pub hello_name: Option<String>,
 }
 
+/// Amazon SES mailer configuration, sending with its v2 API.
+#[cfg(feature = "mailer_ses")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SesMailer {
+    pub enable: bool,
+    /// AWS region, for example: us-east-1
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// For temporary credentials
+    pub session_token: Option<String>,
+    /// Overrides `https://email.<region>.amazonaws.com`
+    pub endpoint: Option<String>,
+}
+
+/// SendGrid mailer configuration, sending with its v3 API.
+#[cfg(feature = "mailer_sendgrid")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SendGridMailer {
+    pub enable: bool,
+    pub api_key: String,
+    /// Overrides `https://api.sendgrid.com`
+    pub endpoint: Option<String>,
+}
+
 /// Authentication details for the mailer
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MailerAuth {
@@ -33,7 +33,13 @@ use tracing;
 use crate::{app::AppContext, auth, config::JWT as JWTConfig, errors::Error, Result as LocoResult};
 
 #[cfg(feature = "with-db")]
-use crate::model::{Authenticable, ModelError};
+use axum::http::StatusCode;
+
+#[cfg(feature = "with-db")]
+use crate::{
+    controller::ErrorDetail,
+    model::{Authenticable, ModelError},
+};
 
 // ---------------------------------------
 //
@@ -296,6 +302,92 @@ where
     }
 }
 
+// ---------------------------------------
+//
+// API Key Auth / Extractor
+//
+// ---------------------------------------
+const API_KEY_HEADER: &str = "x-api-key";
+
+#[cfg(feature = "with-db")]
+#[derive(Debug, Deserialize, Serialize)]
+// Represents an active key of the `api_keys` table, see [`auth::apikey`].
+pub struct ApiKey {
+    pub key: auth::apikey::ApiKey,
+}
+
+#[cfg(feature = "with-db")]
+impl ApiKey {
+    /// Checks that the key grants `scope`, e.g. `reports:read`.
+    ///
+    /// # Errors
+    ///
+    /// A `403 Forbidden` when it does not
+    pub fn require_scope(&self, scope: &str) -> LocoResult<()> {
+        if self.key.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(Error::CustomError(
+                StatusCode::FORBIDDEN,
+                ErrorDetail::new("forbidden", format!("missing the `{scope}` scope")),
+            ))
+        }
+    }
+}
+
+// Implementing the `FromRequestParts` trait for `ApiKey` to enable extracting
+// it from the request.
+#[cfg(feature = "with-db")]
+impl<S> FromRequestParts<S> for ApiKey
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Error> {
+        let api_key = extract_api_key_from_header(&parts.headers)?;
+
+        let state: AppContext = AppContext::from_ref(state);
+
+        let key = auth::apikey::find(&state.db, &api_key)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error during API key authentication: {}", e);
+                Error::InternalServerError
+            })?
+            .ok_or_else(|| Error::Unauthorized("not found".to_string()))?;
+        if !key.is_active() {
+            return Err(Error::Unauthorized(
+                "api key is revoked or expired".to_string(),
+            ));
+        }
+
+        Ok(Self { key })
+    }
+}
+
+/// Function to extract an API key from the `x-api-key` header
+///
+/// # Errors
+///
+/// When the header is not found or not valid
+pub fn extract_api_key_from_header(headers: &HeaderMap) -> LocoResult<String> {
+    let key = headers
+        .get(API_KEY_HEADER)
+        .ok_or_else(|| Error::Unauthorized(format!("header {API_KEY_HEADER} not found")))?
+        .to_str()
+        .map_err(|err| Error::Unauthorized(err.to_string()))?
+        .trim();
+    if key.is_empty() {
+        return Err(Error::Unauthorized(format!(
+            "header {API_KEY_HEADER} is empty"
+        )));
+    }
+
+    Ok(key.to_string())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -304,6 +396,18 @@ mod tests {
     use super::*;
     use crate::config;
 
+    #[test]
+    fn test_extract_api_key_from_header() {
+        let mut headers = HeaderMap::new();
+        assert!(extract_api_key_from_header(&headers).is_err());
+
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static(" lk_abc_123 "));
+        assert_eq!(extract_api_key_from_header(&headers).unwrap(), "lk_abc_123");
+
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static(""));
+        assert!(extract_api_key_from_header(&headers).is_err());
+    }
+
     #[test]
     fn test_extract_token_from_header_success() {
         let mut headers = HeaderMap::new();
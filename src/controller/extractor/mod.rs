@@ -1,4 +1,6 @@
 #[cfg(feature = "auth_jwt")]
 pub mod auth;
 pub mod shared_store;
+#[cfg(feature = "with-db")]
+pub mod tenant;
 pub mod validate;
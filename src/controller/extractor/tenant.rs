@@ -0,0 +1,61 @@
+//! Extracts the tenant of a request, see [`crate::tenancy`].
+//!
+//! # Example:
+//!
+//! ```
+//! use loco_rs::prelude::*;
+//! use loco_rs::controller::extractor::tenant::Tenant;
+//!
+//! async fn current(tenant: Tenant) -> Result<Response> {
+//!     // query the tenant's data on `tenant.db`
+//!     format::json(tenant.id)
+//! }
+//! ```
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use sea_orm::DatabaseConnection;
+
+use crate::{app::AppContext, errors::Error, tenancy::Tenants, Result};
+
+/// The tenant a request is for, along with the connection to its data.
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    pub id: String,
+    pub db: DatabaseConnection,
+}
+
+impl Tenant {
+    /// The tenant's id as the primary key of its row in the tenants table,
+    /// which stamps the rows of its data with row isolation.
+    ///
+    /// # Errors
+    ///
+    /// When the id is not a number
+    pub fn pk(&self) -> Result<i32> {
+        self.id
+            .parse()
+            .map_err(|_| Error::BadRequest(format!("tenant `{}` is not a tenant key", self.id)))
+    }
+}
+
+impl<S> FromRequestParts<S> for Tenant
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Error> {
+        let ctx: AppContext = AppContext::from_ref(state);
+        let tenants =
+            Tenants::get(&ctx).ok_or_else(|| Error::string("tenancy is not configured"))?;
+        let id = tenants
+            .resolve(parts, &ctx)
+            .await?
+            .ok_or_else(|| Error::BadRequest("the request names no tenant".to_string()))?;
+        let db = tenants.connection(&ctx, &id).await?;
+        Ok(Self { id, db })
+    }
+}
@@ -0,0 +1,259 @@
+//! Livereload Middleware
+//!
+//! Reloads the pages open in the browser when the app changes, while it runs
+//! under `cargo loco start --watch` with server-side views. Each HTML
+//! response gets a script subscribing to the server-sent events at
+//! [`PATH`]. They tell the page the build it was served by, which changes
+//! when the watcher restarts the server, and when a view under
+//! `assets/views` changes, which the view engine reloads without a restart.
+
+use std::{
+    convert::Infallible,
+    path::Path,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE},
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Router as AXRouter,
+};
+use notify::{
+    event::{EventKind, ModifyKind},
+    RecursiveMode, Watcher,
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    app::AppContext,
+    controller::{middleware::MiddlewareLayer, views::engines::DEFAULT_ASSET_FOLDER},
+    Error, Result,
+};
+
+/// The environment variable `start --watch` enables livereload with, in the
+/// server it starts
+pub const ENV_VAR: &str = "LOCO_LIVERELOAD";
+
+/// The server-sent events of livereload
+pub const PATH: &str = "/_livereload";
+
+/// How soon the browser reconnects after the server went away, e.g. while
+/// it rebuilds
+const RETRY: Duration = Duration::from_millis(500);
+
+/// Reloads the page when the build serving it changes, or on a `reload`
+const SCRIPT: &str = r#"<script>
+(() => {
+  let build;
+  const source = new EventSource("/_livereload");
+  source.addEventListener("build", (e) => {
+    if (build && build !== e.data) location.reload();
+    build = e.data;
+  });
+  source.addEventListener("reload", () => location.reload());
+})();
+</script>"#;
+
+/// Identifies the running server, for a page to tell it was restarted
+fn build_id() -> &'static str {
+    static BUILD: OnceLock<String> = OnceLock::new();
+    BUILD.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// [`Middleware`] injecting the livereload script into HTML responses
+#[derive(Debug)]
+pub struct Middleware {
+    enable: bool,
+}
+
+/// Creates the livereload middleware, enabled when [`ENV_VAR`] is set
+#[must_use]
+pub fn new() -> Middleware {
+    Middleware {
+        enable: std::env::var_os(ENV_VAR).is_some(),
+    }
+}
+
+impl MiddlewareLayer for Middleware {
+    /// Returns the name of the middleware
+    fn name(&self) -> &'static str {
+        "livereload"
+    }
+
+    /// Returns whether the middleware is enabled or not
+    fn is_enabled(&self) -> bool {
+        self.enable
+    }
+
+    fn config(&self) -> serde_json::Result<serde_json::Value> {
+        Ok(serde_json::json!({ "path": PATH }))
+    }
+
+    /// Streams the livereload events at [`PATH`], and injects their script
+    /// into each HTML response.
+    fn apply(&self, app: AXRouter<AppContext>) -> Result<AXRouter<AppContext>> {
+        let (sender, _) = broadcast::channel(16);
+        let views = Path::new(DEFAULT_ASSET_FOLDER).join("views");
+        let watcher = if views.exists() {
+            let sender = sender.clone();
+            let mut watcher = notify::recommended_watcher(move |event| {
+                let Ok(notify::Event { kind, .. }) = event else {
+                    return;
+                };
+                if !matches!(
+                    kind,
+                    EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))
+                ) {
+                    let _ = sender.send(());
+                }
+            })
+            .map_err(|_| Error::string("error creating file watcher"))?;
+            watcher
+                .watch(&views, RecursiveMode::Recursive)
+                .map_err(|_| Error::string("error watching for file changes in view directory"))?;
+            Some(watcher)
+        } else {
+            None
+        };
+
+        // the watcher lives as long as the route streaming its changes
+        let watched = Arc::new((sender, watcher));
+        Ok(app
+            .route(
+                PATH,
+                get(move || {
+                    let watched = watched.clone();
+                    async move { events(&watched.0).into_response() }
+                }),
+            )
+            .layer(axum::middleware::from_fn(inject)))
+    }
+}
+
+/// The build serving the page, then a `reload` on each change of a view
+fn events(
+    sender: &broadcast::Sender<()>,
+) -> Sse<impl futures_util::Stream<Item = std::result::Result<Event, Infallible>>> {
+    let build = Event::default()
+        .event("build")
+        .data(build_id())
+        .retry(RETRY);
+    let reloads = futures_util::stream::unfold(sender.subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(()) => {
+                    let event = Event::default().event("reload").data("reload");
+                    return Some((Ok(event), receiver));
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = futures_util::StreamExt::chain(
+        futures_util::stream::once(async move { Ok(build) }),
+        reloads,
+    );
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+async fn inject(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let headers = response.headers();
+    let is_html = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if !is_html || headers.contains_key(CONTENT_ENCODING) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => return Error::wrap(err).into_response(),
+    };
+    let body = match String::from_utf8(bytes.to_vec()) {
+        Ok(html) => Body::from(inject_script(&html)),
+        Err(_) => Body::from(bytes),
+    };
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, body)
+}
+
+/// The page with the livereload script at the end of its body
+fn inject_script(html: &str) -> String {
+    let at = html
+        .to_ascii_lowercase()
+        .rfind("</body>")
+        .unwrap_or(html.len());
+    let mut page = String::with_capacity(html.len() + SCRIPT.len());
+    page.push_str(&html[..at]);
+    page.push_str(SCRIPT);
+    page.push_str(&html[at..]);
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{controller::format, tests_cfg};
+
+    #[test]
+    fn can_inject_script() {
+        assert_eq!(
+            inject_script("<html><BODY><p>hi</p></BODY></html>"),
+            format!("<html><BODY><p>hi</p>{SCRIPT}</BODY></html>")
+        );
+        assert_eq!(inject_script("<p>hi</p>"), format!("<p>hi</p>{SCRIPT}"));
+    }
+
+    #[tokio::test]
+    async fn injects_into_html_responses_only() {
+        let ctx = tests_cfg::app::get_app_context().await;
+        let app = Router::new()
+            .route("/page", get(|| async { format::html("<body>page</body>") }))
+            .route("/data", get(|| async { format::json("<body>") }));
+        let app = Middleware { enable: true }
+            .apply(app)
+            .unwrap()
+            .with_state(ctx);
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/page").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, format!("<body>page{SCRIPT}</body>"));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/data").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "\"<body>\"");
+
+        let response = app
+            .oneshot(Request::get(PATH).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+    }
+}
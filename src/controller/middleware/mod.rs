@@ -13,8 +13,10 @@ pub mod etag;
 pub mod fallback;
 pub mod format;
 pub mod limit_payload;
+pub mod livereload;
 pub mod logger;
 pub mod powered_by;
+pub mod rate_limit;
 pub mod remote_ip;
 pub mod request_id;
 pub mod secure_headers;
@@ -78,6 +80,9 @@ pub fn default_middleware_stack(ctx: &AppContext) -> Vec<Box<dyn MiddlewareLayer
     let middlewares = &ctx.config.server.middlewares;
 
     vec![
+        // Livereload middleware, innermost to inject into the response body
+        // before it is tagged or compressed
+        Box::new(livereload::new()),
         // Limit Payload middleware with a default if none
         Box::new(middlewares.limit_payload.clone().unwrap_or_default()),
         // CORS middleware with a default if none
@@ -99,6 +104,11 @@ pub fn default_middleware_stack(ctx: &AppContext) -> Vec<Box<dyn MiddlewareLayer
                 .clone()
                 .unwrap_or_else(|| etag::Etag { enable: true }),
         ),
+        // Rate Limit middleware, inside Remote IP to key by the IP it resolves
+        Box::new(rate_limit::new(
+            &middlewares.rate_limit.clone().unwrap_or_default(),
+            ctx,
+        )),
         // Remote IP middleware with a default if none
         Box::new(
             middlewares
@@ -201,6 +211,9 @@ pub struct Config {
     /// Sets a set of secure headers
     pub secure_headers: Option<secure_headers::SecureHeader>,
 
+    /// Limits the rate of requests by client
+    pub rate_limit: Option<rate_limit::RateLimit>,
+
     /// Calculates a remote IP based on `X-Forwarded-For` when behind a proxy
     pub remote_ip: Option<remote_ip::RemoteIpMiddleware>,
 
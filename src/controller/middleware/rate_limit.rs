@@ -0,0 +1,779 @@
+//! Rate Limit Middleware
+//!
+//! Limits how many requests a client makes in a period, globally and per
+//! route, answering the ones over the limit with `429 Too Many Requests`.
+//! Clients are told where they stand with the `RateLimit-Limit`,
+//! `RateLimit-Remaining` and `RateLimit-Reset` headers, and when to retry
+//! with `Retry-After`.
+//!
+//! Clients are keyed by IP (as [`super::remote_ip`] resolves it when
+//! enabled), by a header, or by the authenticated user. The counts are kept
+//! in memory, or in Redis (`rate_limit_redis` feature) so that all the
+//! instances of an app share them.
+//!
+//! ```yaml
+//! server:
+//!   middlewares:
+//!     rate_limit:
+//!       enable: true
+//!       algorithm: token_bucket
+//!       key:
+//!         kind: ip
+//!       limit:
+//!         requests: 100
+//!         period: 60
+//!       routes:
+//!         - path: /api/auth/*
+//!           requests: 5
+//!           period: 60
+//! ```
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request},
+    http::{request::Parts, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json, Router as AXRouter,
+};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tower::{Layer, Service};
+
+use super::remote_ip::RemoteIP;
+use crate::{
+    app::AppContext, controller::middleware::MiddlewareLayer, controller::ErrorDetail, Error,
+    Result,
+};
+
+/// Memory keys kept before the idle ones are dropped
+const MEMORY_PRUNE_THRESHOLD: usize = 10_000;
+
+/// Rate limit middleware configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimit {
+    #[serde(default)]
+    pub enable: bool,
+    /// How requests are counted
+    #[serde(default)]
+    pub algorithm: Algorithm,
+    /// What a client is known by
+    #[serde(default)]
+    pub key: Key,
+    /// The limit of the routes without one of their own, none when unset
+    pub limit: Option<Quota>,
+    /// The limits of routes, the first matching one applying
+    #[serde(default)]
+    pub routes: Vec<RouteLimit>,
+    /// Where counts are kept
+    #[serde(default)]
+    pub backend: Backend,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        serde_json::from_value(json!({})).unwrap()
+    }
+}
+
+/// A number of requests allowed in a period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Quota {
+    pub requests: u32,
+    /// The period, in seconds
+    pub period: u64,
+}
+
+/// The limit of the routes matching `path`, either exactly or, when it ends
+/// with `*`, by prefix. Only for `methods` when given.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RouteLimit {
+    pub path: String,
+    #[serde(default)]
+    pub methods: Vec<String>,
+    #[serde(flatten)]
+    pub quota: Quota,
+}
+
+impl RouteLimit {
+    fn matches(&self, method: &str, path: &str) -> bool {
+        let path_matches = self
+            .path
+            .strip_suffix('*')
+            .map_or_else(|| path == self.path, |prefix| path.starts_with(prefix));
+        path_matches
+            && (self.methods.is_empty()
+                || self.methods.iter().any(|m| m.eq_ignore_ascii_case(method)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    /// Allows bursts of up to `requests`, refilled evenly over the period
+    #[default]
+    TokenBucket,
+    /// Allows `requests` in any period, weighing the previous period's count
+    /// by how much of it the sliding period still covers
+    SlidingWindow,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Key {
+    /// The client's IP
+    #[default]
+    Ip,
+    /// The value of a header, e.g. an API key, or the IP without it
+    Header { name: String },
+    /// The `pid` of the user the request's JWT authenticates, or the IP
+    /// without one
+    #[cfg(feature = "auth_jwt")]
+    User,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Backend {
+    /// Counts in the memory of the instance
+    #[default]
+    Memory,
+    /// Counts in Redis, shared by the instances
+    #[cfg(feature = "rate_limit_redis")]
+    Redis {
+        uri: String,
+        /// Prefix of the keys, `rate_limit` by default
+        #[serde(default = "default_namespace")]
+        namespace: String,
+    },
+}
+
+#[cfg(feature = "rate_limit_redis")]
+fn default_namespace() -> String {
+    "rate_limit".to_string()
+}
+
+/// [`Middleware`] limiting the rate of requests, with the JWT settings of
+/// the app to key by user.
+#[derive(Serialize, Debug)]
+pub struct Middleware {
+    config: RateLimit,
+    #[cfg(feature = "auth_jwt")]
+    #[serde(skip)]
+    jwt: Option<crate::config::JWT>,
+}
+
+/// Creates a new instance of [`Middleware`] from its configuration and the
+/// app's.
+#[must_use]
+#[cfg_attr(not(feature = "auth_jwt"), allow(unused_variables))]
+pub fn new(config: &RateLimit, ctx: &AppContext) -> Middleware {
+    Middleware {
+        config: config.clone(),
+        #[cfg(feature = "auth_jwt")]
+        jwt: ctx.config.auth.as_ref().and_then(|auth| auth.jwt.clone()),
+    }
+}
+
+impl MiddlewareLayer for Middleware {
+    /// Returns the name of the middleware
+    fn name(&self) -> &'static str {
+        "rate_limit"
+    }
+
+    /// Returns whether the middleware is enabled or not
+    fn is_enabled(&self) -> bool {
+        self.config.enable
+    }
+
+    fn config(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(&self.config)
+    }
+
+    /// Applies the rate limit middleware to the application router.
+    fn apply(&self, app: AXRouter<AppContext>) -> Result<AXRouter<AppContext>> {
+        let quotas = self
+            .config
+            .limit
+            .iter()
+            .chain(self.config.routes.iter().map(|route| &route.quota));
+        for quota in quotas {
+            if quota.requests == 0 || quota.period == 0 {
+                return Err(Error::Message(format!(
+                    "rate limit of {} requests in {} seconds: both have to be positive",
+                    quota.requests, quota.period
+                )));
+            }
+        }
+
+        #[cfg(feature = "auth_jwt")]
+        if self.config.key == Key::User && self.jwt.is_none() {
+            return Err(Error::string(
+                "rate limit keyed by user requires `auth.jwt` to be configured",
+            ));
+        }
+
+        Ok(app.layer(RateLimitLayer {
+            config: Arc::new(self.config.clone()),
+            store: Arc::new(Store::new(&self.config.backend)?),
+            #[cfg(feature = "auth_jwt")]
+            jwt: self.jwt.clone().map(Arc::new),
+        }))
+    }
+}
+
+/// Where a request stands against its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Decision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    /// Seconds until the full quota is available again
+    reset: u64,
+    /// Seconds until a request is allowed again, when it is not
+    retry_after: Option<u64>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn ceil_secs(ms: f64) -> u64 {
+    (ms / 1000.0).ceil().max(0.0) as u64
+}
+
+/// A token bucket of `tokens` left, refilled at `quota.requests` per period,
+/// as of `updated`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bucket {
+    tokens: f64,
+    updated: u64,
+}
+
+fn token_bucket(bucket: Option<Bucket>, quota: &Quota, now: u64) -> (Bucket, Decision) {
+    let capacity = f64::from(quota.requests);
+    #[allow(clippy::cast_precision_loss)]
+    let rate = capacity / (quota.period * 1000) as f64;
+    let bucket = bucket.unwrap_or(Bucket {
+        tokens: capacity,
+        updated: now,
+    });
+    #[allow(clippy::cast_precision_loss)]
+    let tokens = capacity.min(bucket.tokens + now.saturating_sub(bucket.updated) as f64 * rate);
+    let allowed = tokens >= 1.0;
+    let tokens = if allowed { tokens - 1.0 } else { tokens };
+    (
+        Bucket {
+            tokens,
+            updated: now,
+        },
+        bucket_decision(allowed, tokens, quota),
+    )
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn bucket_decision(allowed: bool, tokens: f64, quota: &Quota) -> Decision {
+    let capacity = f64::from(quota.requests);
+    #[allow(clippy::cast_precision_loss)]
+    let rate = capacity / (quota.period * 1000) as f64;
+    Decision {
+        allowed,
+        limit: quota.requests,
+        remaining: tokens.floor().max(0.0) as u32,
+        reset: ceil_secs((capacity - tokens) / rate),
+        retry_after: (!allowed).then(|| ceil_secs((1.0 - tokens) / rate).max(1)),
+    }
+}
+
+/// The counts of the current fixed window, the `window`th period since the
+/// epoch, and of the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Window {
+    window: u64,
+    current: u64,
+    previous: u64,
+}
+
+fn sliding_window(window: Option<Window>, quota: &Quota, now: u64) -> (Window, Decision) {
+    let period = quota.period * 1000;
+    let index = now / period;
+    let elapsed = now % period;
+    let mut window = match window {
+        Some(w) if w.window == index => w,
+        Some(w) if w.window + 1 == index => Window {
+            window: index,
+            current: 0,
+            previous: w.current,
+        },
+        _ => Window {
+            window: index,
+            current: 0,
+            previous: 0,
+        },
+    };
+    let allowed = estimate(window.current, window.previous, elapsed, period) + 1.0
+        <= f64::from(quota.requests);
+    if allowed {
+        window.current += 1;
+    }
+    (
+        window,
+        window_decision(allowed, window.current, window.previous, elapsed, quota),
+    )
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn estimate(current: u64, previous: u64, elapsed: u64, period: u64) -> f64 {
+    previous as f64 * (period - elapsed) as f64 / period as f64 + current as f64
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn window_decision(
+    allowed: bool,
+    current: u64,
+    previous: u64,
+    elapsed: u64,
+    quota: &Quota,
+) -> Decision {
+    let period = quota.period * 1000;
+    let requests = f64::from(quota.requests);
+    let retry_after = (!allowed).then(|| {
+        let (current, previous, elapsed) = (current as f64, previous as f64, elapsed as f64);
+        let period = period as f64;
+        let wait = if current + 1.0 <= requests {
+            // the previous window's weight fades within this one
+            period * (1.0 - (requests - 1.0 - current) / previous) - elapsed
+        } else {
+            // this window's count fades within the next one
+            period - elapsed + period * (1.0 - (requests - 1.0) / current)
+        };
+        ceil_secs(wait).max(1)
+    });
+    Decision {
+        allowed,
+        limit: quota.requests,
+        remaining: (requests - estimate(current, previous, elapsed, period).ceil()).max(0.0) as u32,
+        reset: ceil_secs((period - elapsed) as f64),
+        retry_after,
+    }
+}
+
+enum State {
+    Bucket(Bucket),
+    Window(Window),
+}
+
+enum Store {
+    Memory(Mutex<HashMap<String, (State, u64)>>),
+    #[cfg(feature = "rate_limit_redis")]
+    Redis(redis::Redis),
+}
+
+impl Store {
+    fn new(backend: &Backend) -> Result<Self> {
+        Ok(match backend {
+            Backend::Memory => Self::Memory(Mutex::new(HashMap::new())),
+            #[cfg(feature = "rate_limit_redis")]
+            Backend::Redis { uri, namespace } => Self::Redis(redis::Redis::new(uri, namespace)?),
+        })
+    }
+
+    /// Counts a request of `key` against `quota`.
+    async fn hit(&self, key: &str, quota: &Quota, algorithm: Algorithm) -> Result<Decision> {
+        match self {
+            Self::Memory(states) => {
+                let now = now_ms();
+                let mut states = states
+                    .lock()
+                    .map_err(|_| Error::string("rate limit state is poisoned"))?;
+                if states.len() > MEMORY_PRUNE_THRESHOLD {
+                    states.retain(|_, (_, expires)| *expires > now);
+                }
+                let state = states.remove(key).map(|(state, _)| state);
+                let (state, decision) = match (algorithm, state) {
+                    (Algorithm::TokenBucket, state) => {
+                        let bucket = match state {
+                            Some(State::Bucket(bucket)) => Some(bucket),
+                            _ => None,
+                        };
+                        let (bucket, decision) = token_bucket(bucket, quota, now);
+                        (State::Bucket(bucket), decision)
+                    }
+                    (Algorithm::SlidingWindow, state) => {
+                        let window = match state {
+                            Some(State::Window(window)) => Some(window),
+                            _ => None,
+                        };
+                        let (window, decision) = sliding_window(window, quota, now);
+                        (State::Window(window), decision)
+                    }
+                };
+                // idle for two periods, a key is as good as new
+                states.insert(key.to_string(), (state, now + quota.period * 2000));
+                Ok(decision)
+            }
+            #[cfg(feature = "rate_limit_redis")]
+            Self::Redis(redis) => redis.hit(key, quota, algorithm).await,
+        }
+    }
+}
+
+#[cfg(feature = "rate_limit_redis")]
+mod redis {
+    use ::redis::{aio::MultiplexedConnection, Client, Script};
+    use tokio::sync::Mutex;
+
+    use super::{bucket_decision, window_decision, Algorithm, Decision, Quota};
+    use crate::{Error, Result};
+
+    /// The scripts take the time of the Redis server, which all the instances
+    /// share.
+    const TOKEN_BUCKET: &str = r"
+local capacity = tonumber(ARGV[1])
+local period = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'updated')
+local tokens = tonumber(state[1]) or capacity
+local updated = tonumber(state[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - updated) * capacity / period)
+local allowed = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+  allowed = 1
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated', now)
+redis.call('PEXPIRE', KEYS[1], period * 2)
+return {allowed, tostring(tokens)}
+";
+
+    /// The counts of both windows are kept in the one hash at `KEYS[1]`, as
+    /// Redis Cluster only lets a script use the keys it is given.
+    const SLIDING_WINDOW: &str = r"
+local requests = tonumber(ARGV[1])
+local period = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local window = math.floor(now / period)
+local elapsed = now % period
+local state = redis.call('HMGET', KEYS[1], 'window', 'current', 'previous')
+local stored = tonumber(state[1]) or window
+local current = tonumber(state[2]) or 0
+local previous = tonumber(state[3]) or 0
+if stored == window - 1 then
+  previous = current
+  current = 0
+elseif stored ~= window then
+  previous = 0
+  current = 0
+end
+local allowed = 0
+if previous * (period - elapsed) / period + current + 1 <= requests then
+  current = current + 1
+  allowed = 1
+end
+redis.call('HSET', KEYS[1], 'window', window, 'current', current, 'previous', previous)
+redis.call('PEXPIRE', KEYS[1], period * 2)
+return {allowed, current, previous, elapsed}
+";
+
+    pub struct Redis {
+        client: Client,
+        namespace: String,
+        connection: Mutex<Option<MultiplexedConnection>>,
+    }
+
+    impl Redis {
+        pub fn new(uri: &str, namespace: &str) -> Result<Self> {
+            Ok(Self {
+                client: Client::open(uri).map_err(Error::wrap)?,
+                namespace: namespace.to_string(),
+                connection: Mutex::new(None),
+            })
+        }
+
+        async fn connection(&self) -> Result<MultiplexedConnection> {
+            let mut connection = self.connection.lock().await;
+            if let Some(connection) = connection.as_ref() {
+                return Ok(connection.clone());
+            }
+            let conn = self
+                .client
+                .get_multiplexed_async_connection()
+                .await
+                .map_err(Error::wrap)?;
+            *connection = Some(conn.clone());
+            Ok(conn)
+        }
+
+        pub async fn hit(
+            &self,
+            key: &str,
+            quota: &Quota,
+            algorithm: Algorithm,
+        ) -> Result<Decision> {
+            let mut conn = self.connection().await?;
+            let key = format!("{}:{key}", self.namespace);
+            let period = quota.period * 1000;
+            let result = match algorithm {
+                Algorithm::TokenBucket => Script::new(TOKEN_BUCKET)
+                    .key(&key)
+                    .arg(quota.requests)
+                    .arg(period)
+                    .invoke_async::<(i64, String)>(&mut conn)
+                    .await
+                    .map(|(allowed, tokens)| {
+                        bucket_decision(allowed == 1, tokens.parse().unwrap_or_default(), quota)
+                    }),
+                Algorithm::SlidingWindow => Script::new(SLIDING_WINDOW)
+                    .key(&key)
+                    .arg(quota.requests)
+                    .arg(period)
+                    .invoke_async::<(i64, u64, u64, u64)>(&mut conn)
+                    .await
+                    .map(|(allowed, current, previous, elapsed)| {
+                        window_decision(allowed == 1, current, previous, elapsed, quota)
+                    }),
+            };
+            if result.is_err() {
+                // reconnect on the next hit
+                self.connection.lock().await.take();
+            }
+            result.map_err(Error::wrap)
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RateLimitLayer {
+    config: Arc<RateLimit>,
+    store: Arc<Store>,
+    #[cfg(feature = "auth_jwt")]
+    jwt: Option<Arc<crate::config::JWT>>,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+impl RateLimitLayer {
+    /// The quota of a request and the name of its counter, the matching
+    /// route's pattern or `*` for the global limit.
+    fn quota(&self, parts: &Parts) -> Option<(&str, Quota)> {
+        let method = parts.method.as_str();
+        let path = parts.uri.path();
+        self.config
+            .routes
+            .iter()
+            .find(|route| route.matches(method, path))
+            .map(|route| (route.path.as_str(), route.quota))
+            .or_else(|| self.config.limit.map(|quota| ("*", quota)))
+    }
+
+    /// What the client of a request is known by.
+    fn client(&self, parts: &Parts) -> String {
+        match &self.config.key {
+            Key::Ip => {}
+            Key::Header { name } => {
+                if let Some(value) = parts.headers.get(name).and_then(|v| v.to_str().ok()) {
+                    return format!("header:{value}");
+                }
+            }
+            #[cfg(feature = "auth_jwt")]
+            Key::User => {
+                if let Some(pid) = self.jwt.as_ref().and_then(|jwt| user(jwt, parts)) {
+                    return format!("user:{pid}");
+                }
+            }
+        }
+        let ip = match parts.extensions.get::<RemoteIP>() {
+            Some(RemoteIP::Forwarded(ip) | RemoteIP::Socket(ip)) => Some(*ip),
+            _ => parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|info| info.ip()),
+        };
+        ip.map_or_else(|| "ip:unknown".to_string(), |ip| format!("ip:{ip}"))
+    }
+}
+
+#[cfg(feature = "auth_jwt")]
+fn user(jwt: &crate::config::JWT, parts: &Parts) -> Option<String> {
+    let token = crate::controller::extractor::auth::extract_token(jwt, parts).ok()?;
+    crate::auth::jwt::JWT::new(&jwt.secret)
+        .validate(&token)
+        .ok()
+        .map(|data| data.claims.pid)
+}
+
+/// Rate Limit Middleware
+#[derive(Clone)]
+#[must_use]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    layer: RateLimitLayer,
+}
+
+impl<S> Service<Request<Body>> for RateLimitMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let layer = self.layer.clone();
+        // the ready service serves this request, a clone of it the next one
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let Some((counter, quota)) = layer.quota(&parts) else {
+                return inner.call(Request::from_parts(parts, body)).await;
+            };
+            let key = format!("{counter}:{}", layer.client(&parts));
+
+            let decision = match layer.store.hit(&key, &quota, layer.config.algorithm).await {
+                Ok(decision) => decision,
+                Err(err) => {
+                    // better to serve unlimited than not at all
+                    tracing::error!(err.msg = %err, err.detail = ?err, "rate_limit_error");
+                    return inner.call(Request::from_parts(parts, body)).await;
+                }
+            };
+
+            let mut res = if decision.allowed {
+                inner.call(Request::from_parts(parts, body)).await?
+            } else {
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    Json(ErrorDetail::new(
+                        "too_many_requests",
+                        "Too many requests, retry later",
+                    )),
+                )
+                    .into_response()
+            };
+
+            let headers = res.headers_mut();
+            headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+            headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+            headers.insert("ratelimit-reset", HeaderValue::from(decision.reset));
+            if let Some(retry_after) = decision.retry_after {
+                headers.insert(
+                    axum::http::header::RETRY_AFTER,
+                    HeaderValue::from(retry_after),
+                );
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUOTA: Quota = Quota {
+        requests: 2,
+        period: 10,
+    };
+
+    #[test]
+    fn token_bucket_allows_bursts_and_refills() {
+        let (bucket, decision) = token_bucket(None, &QUOTA, 0);
+        assert_eq!(
+            decision,
+            Decision {
+                allowed: true,
+                limit: 2,
+                remaining: 1,
+                reset: 5,
+                retry_after: None
+            }
+        );
+        let (bucket, decision) = token_bucket(Some(bucket), &QUOTA, 0);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        let (bucket, decision) = token_bucket(Some(bucket), &QUOTA, 1_000);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Some(4));
+
+        // a token every 5 seconds
+        let (_, decision) = token_bucket(Some(bucket), &QUOTA, 6_000);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+    }
+
+    #[test]
+    fn sliding_window_weighs_previous_window() {
+        let (window, _) = sliding_window(None, &QUOTA, 0);
+        let (window, decision) = sliding_window(Some(window), &QUOTA, 1_000);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        let (window, decision) = sliding_window(Some(window), &QUOTA, 2_000);
+        assert_eq!(
+            decision,
+            Decision {
+                allowed: false,
+                limit: 2,
+                remaining: 0,
+                reset: 8,
+                retry_after: Some(13)
+            }
+        );
+
+        // a quarter into the next window, 1.5 of the 2 previous ones count
+        let (window, decision) = sliding_window(Some(window), &QUOTA, 12_500);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Some(3));
+
+        let (_, decision) = sliding_window(Some(window), &QUOTA, 15_000);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+    }
+
+    #[test]
+    fn can_match_routes() {
+        let route = |path: &str, methods: &[&str]| RouteLimit {
+            path: path.to_string(),
+            methods: methods.iter().map(ToString::to_string).collect(),
+            quota: QUOTA,
+        };
+        assert!(route("/api/auth/*", &[]).matches("GET", "/api/auth/login"));
+        assert!(!route("/api/auth/*", &[]).matches("GET", "/api/users"));
+        assert!(route("/api/users", &["post"]).matches("POST", "/api/users"));
+        assert!(!route("/api/users", &["post"]).matches("GET", "/api/users"));
+        assert!(!route("/api/users", &[]).matches("GET", "/api/users/1"));
+    }
+}
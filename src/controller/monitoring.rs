@@ -2,11 +2,17 @@
 //! reporting. These routes are commonly used to monitor the readiness of the
 //! application and its dependencies.
 
+use std::{collections::BTreeMap, time::Duration};
+
 use super::{format, routes::Routes};
 #[cfg(any(feature = "cache_inmem", feature = "cache_redis"))]
 use crate::config;
-use crate::{app::AppContext, Result};
-use axum::{extract::State, response::Response, routing::get};
+use crate::{
+    app::AppContext,
+    doctor::{self, Dependency, DependencyCheck},
+    Result,
+};
+use axum::{extract::State, http::StatusCode, response::Response, routing::get};
 use serde::Serialize;
 
 /// Represents the health status of the application.
@@ -77,10 +83,68 @@ pub async fn readiness(State(ctx): State<AppContext>) -> Result<Response> {
     format::json(Health { ok: true })
 }
 
+/// Represents the readiness of the application, by dependency.
+#[derive(Serialize)]
+pub struct Ready {
+    pub ok: bool,
+    pub checks: BTreeMap<Dependency, ReadyCheck>,
+}
+
+/// The public outcome of a dependency check. Why a check failed is only
+/// logged, as `/_ready` is served to anyone who can reach the app.
+#[derive(Serialize)]
+pub struct ReadyCheck {
+    pub ok: bool,
+    /// How long the dependency took to answer, in milliseconds
+    pub latency_ms: u64,
+}
+
+impl From<DependencyCheck> for ReadyCheck {
+    fn from(check: DependencyCheck) -> Self {
+        Self {
+            ok: check.ok,
+            latency_ms: check.latency_ms,
+        }
+    }
+}
+
+/// Check the readiness of the application for a readiness probe: each
+/// dependency it has configured (database, queue, cache and storage) is
+/// checked within `server.readiness.timeout`, and reported with how long it
+/// took to answer. Why a check failed is logged rather than returned.
+///
+/// # Errors
+/// Responds `503 Service Unavailable` when a dependency is not ready.
+pub async fn ready(State(ctx): State<AppContext>) -> Result<Response> {
+    let timeout = Duration::from_millis(ctx.config.server.readiness.timeout);
+    let checks = doctor::check_dependencies(&ctx, timeout).await;
+    for (dependency, check) in checks.iter().filter(|(_, check)| !check.ok) {
+        tracing::error!(
+            dependency = %dependency,
+            err.msg = check.error.as_deref().unwrap_or_default(),
+            "readiness_check_error"
+        );
+    }
+
+    let ok = checks.values().all(|check| check.ok);
+    let checks = checks
+        .into_iter()
+        .map(|(dependency, check)| (dependency, check.into()))
+        .collect();
+    format::render()
+        .status(if ok {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        })
+        .json(Ready { ok, checks })
+}
+
 /// Defines and returns the readiness-related routes.
 pub fn routes() -> Routes {
     Routes::new()
         .add("/_readiness", get(readiness))
+        .add("/_ready", get(ready))
         .add("/_ping", get(ping))
         .add("/_health", get(health))
 }
@@ -284,6 +348,7 @@ mod tests {
         let redis_cache = cache::drivers::redis::new(&config::RedisCacheConfig {
             uri: redis_url,
             max_size: 10,
+            namespace: None,
         })
         .await
         .expect("Failed to create Redis cache");
@@ -322,11 +387,13 @@ mod tests {
         ctx.config.cache = config::CacheConfig::Redis(loco_rs::config::RedisCacheConfig {
             uri: failour_redis_url.to_string(),
             max_size: 10,
+            namespace: None,
         });
         // Create Redis cache driver and assign to ctx.cache
         ctx.cache = cache::drivers::redis::new(&config::RedisCacheConfig {
             uri: failour_redis_url.to_string(),
             max_size: 10,
+            namespace: None,
         })
         .await
         .expect("Failed to create Redis cache")
@@ -437,4 +504,69 @@ mod tests {
         let res_json: Value = serde_json::from_slice(&body).expect("Valid JSON response");
         assert_eq!(res_json["ok"], false);
     }
+
+    #[tokio::test]
+    async fn ready_reports_checks() {
+        let ctx = tests_cfg::app::get_app_context().await;
+
+        // Create a router with the ready route
+        let router = axum::Router::new()
+            .route("/_ready", get(monitoring::ready))
+            .with_state(ctx);
+
+        // Create a request
+        let req = axum::http::Request::builder()
+            .uri("/_ready")
+            .method("GET")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        // Test the router directly using oneshot
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), 200);
+
+        // Get the response body
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let res_json: Value = serde_json::from_slice(&body).expect("Valid JSON response");
+        assert_eq!(res_json["ok"], true);
+        assert_eq!(res_json["checks"]["storage"]["ok"], true);
+        assert!(res_json["checks"]["storage"]["latency_ms"].is_u64());
+        #[cfg(feature = "with-db")]
+        assert_eq!(res_json["checks"]["database"]["ok"], true);
+    }
+
+    #[cfg(feature = "with-db")]
+    #[tokio::test]
+    async fn ready_with_db_failure() {
+        let mut ctx = tests_cfg::app::get_app_context().await;
+        ctx.db = fail_connection().await;
+
+        // Create a router with the ready route
+        let router = axum::Router::new()
+            .route("/_ready", get(monitoring::ready))
+            .with_state(ctx);
+
+        // Create a request
+        let req = axum::http::Request::builder()
+            .uri("/_ready")
+            .method("GET")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        // Test the router directly using oneshot
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), 503);
+
+        // Get the response body
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let res_json: Value = serde_json::from_slice(&body).expect("Valid JSON response");
+        assert_eq!(res_json["ok"], false);
+        assert_eq!(res_json["checks"]["database"]["ok"], false);
+        assert!(res_json["checks"]["database"]["latency_ms"].is_u64());
+        assert!(res_json["checks"]["database"].get("error").is_none());
+    }
 }
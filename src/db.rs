@@ -126,6 +126,7 @@ pub async fn converge<H: Hooks, M: MigratorTrait>(
     if config.auto_migrate {
         info!("auto migrating");
         migrate::<M>(&ctx.db).await?;
+        crate::tenancy::migrate::<H, M>(ctx).await?;
     }
 
     if config.dangerously_truncate {
@@ -143,6 +144,27 @@ pub async fn converge<H: Hooks, M: MigratorTrait>(
 /// Returns a [`sea_orm::DbErr`] if an error occurs during the database
 /// connection establishment.
 pub async fn connect(config: &config::Database) -> Result<DbConn, sea_orm::DbErr> {
+    connect_with_search_path(config, None).await
+}
+
+/// Establishes a connection to a Postgres database whose statements resolve
+/// unqualified names in `schema`, e.g. the schema of a tenant.
+///
+/// # Errors
+///
+/// Returns a [`sea_orm::DbErr`] if an error occurs during the database
+/// connection establishment.
+pub async fn connect_to_schema(
+    config: &config::Database,
+    schema: &str,
+) -> Result<DbConn, sea_orm::DbErr> {
+    connect_with_search_path(config, Some(schema)).await
+}
+
+async fn connect_with_search_path(
+    config: &config::Database,
+    schema: Option<&str>,
+) -> Result<DbConn, sea_orm::DbErr> {
     let mut opt = ConnectOptions::new(&config.uri);
     opt.max_connections(config.max_connections)
         .min_connections(config.min_connections)
@@ -154,6 +176,10 @@ pub async fn connect(config: &config::Database) -> Result<DbConn, sea_orm::DbErr
         opt.acquire_timeout(Duration::from_millis(acquire_timeout));
     }
 
+    if let Some(schema) = schema {
+        opt.set_schema_search_path(schema);
+    }
+
     let db = Database::connect(opt).await?;
 
     match db.get_database_backend() {
@@ -244,6 +270,50 @@ pub async fn down<M: MigratorTrait>(
     M::down(db, Some(steps)).await
 }
 
+/// Roll back the given migrations, e.g. the ones of a generation being
+/// destroyed, skipping those not applied. They have to be the latest applied
+/// migrations, so that no other migration is rolled back with them.
+///
+/// # Errors
+///
+/// When migrations were applied after them, or they cannot be rolled back
+pub async fn down_latest<M: MigratorTrait>(
+    db: &DatabaseConnection,
+    migrations: &[String],
+) -> AppResult<()> {
+    let applied = M::get_applied_migrations(db)
+        .await?
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect::<Vec<_>>();
+    let steps = latest_steps(&applied, migrations)?;
+    if steps > 0 {
+        down::<M>(db, steps).await?;
+    }
+    Ok(())
+}
+
+/// The number of steps rolling back `migrations` when they are the latest of
+/// the `applied` ones, oldest first
+fn latest_steps(applied: &[String], migrations: &[String]) -> AppResult<u32> {
+    let Some(first) = applied.iter().position(|name| migrations.contains(name)) else {
+        return Ok(0);
+    };
+    let on_top = applied[first..]
+        .iter()
+        .filter(|name| !migrations.contains(name))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !on_top.is_empty() {
+        return Err(Error::Message(format!(
+            "cannot roll back `{}`, as `{}` were applied after them. roll those back first",
+            migrations.join("`, `"),
+            on_top.join("`, `")
+        )));
+    }
+    Ok(u32::try_from(applied.len() - first).unwrap_or(u32::MAX))
+}
+
 /// Check the migration status of the database.
 ///
 /// # Errors
@@ -284,15 +354,7 @@ where
     sea_orm::Insert<A>: Send + Sync,
     <A as ActiveModelTrait>::Entity: EntityName,
 {
-    // Deserialize YAML file into a vector of JSON values
-    let seed_data: Vec<Value> = serde_yaml::from_reader(File::open(path)?)?;
-
-    // Insert each row
-    let mut seed_models = Vec::new();
-    for row in seed_data {
-        let model = A::from_json(row)?;
-        seed_models.push(model);
-    }
+    let seed_models = read_seed::<A>(path)?;
     A::Entity::insert_many(seed_models).exec(db).await?;
 
     // Get the table name from the entity
@@ -307,6 +369,64 @@ where
     Ok(())
 }
 
+/// Seed the database like [`seed`], skipping the rows that conflict with
+/// existing ones on the `conflict` columns (`ON CONFLICT DO NOTHING`), so
+/// that seeding again is safe, e.g. for lookup tables.
+///
+/// # Errors
+///
+/// Returns a [`AppResult`] if could not render the path content into
+/// [`Vec<serde_json::Value>`] or could not inset the vector to DB.
+#[allow(clippy::type_repetition_in_bounds)]
+pub async fn seed_on_conflict_do_nothing<A, I>(
+    db: &DatabaseConnection,
+    path: &str,
+    conflict: I,
+) -> crate::Result<()>
+where
+    <<A as ActiveModelTrait>::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+    for<'de> <<A as ActiveModelTrait>::Entity as EntityTrait>::Model: serde::de::Deserialize<'de>,
+    A: ActiveModelTrait + Send + Sync,
+    sea_orm::Insert<A>: Send + Sync,
+    <A as ActiveModelTrait>::Entity: EntityName,
+    I: IntoIterator<Item = <<A as ActiveModelTrait>::Entity as EntityTrait>::Column>,
+{
+    let seed_models = read_seed::<A>(path)?;
+    A::Entity::insert_many(seed_models)
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::columns(conflict)
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+
+    let table_name = A::Entity::default().table_name().to_string();
+    reset_autoincrement(db.get_database_backend(), &table_name, db).await?;
+
+    Ok(())
+}
+
+/// Reads the rows of a seed file, into the active models to insert.
+fn read_seed<A>(path: &str) -> crate::Result<Vec<A>>
+where
+    <<A as ActiveModelTrait>::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+    for<'de> <<A as ActiveModelTrait>::Entity as EntityTrait>::Model: serde::de::Deserialize<'de>,
+    A: ActiveModelTrait,
+{
+    // Deserialize YAML file into a vector of JSON values
+    let seed_data: Vec<Value> = serde_yaml::from_reader(File::open(path)?)?;
+
+    // Insert each row
+    let mut seed_models = Vec::new();
+    for row in seed_data {
+        let model = A::from_json(row)?;
+        seed_models.push(model);
+    }
+    Ok(seed_models)
+}
+
 /// Checks if the specified table has an 'id' column.
 ///
 /// This function checks if the specified table has an 'id' column, which is a
@@ -323,7 +443,7 @@ where
 ///
 /// A `Result` containing a `bool` indicating whether the table has an 'id'
 /// column.
-async fn has_id_column(
+pub(crate) async fn has_id_column(
     db: &DatabaseConnection,
     db_backend: &DatabaseBackend,
     table_name: &str,
@@ -1074,6 +1194,32 @@ mod tests {
         config::get_database_config, db::get_value, postgres::setup_postgres_container,
     };
 
+    #[test]
+    fn can_count_latest_steps() {
+        let names = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let applied = names(&["m1_users", "m2_posts", "m3_tags"]);
+
+        assert_eq!(latest_steps(&applied, &names(&["m3_tags"])).unwrap(), 1);
+        assert_eq!(
+            latest_steps(&applied, &names(&["m2_posts", "m3_tags"])).unwrap(),
+            2
+        );
+        assert_eq!(latest_steps(&applied, &names(&["m4_comments"])).unwrap(), 0);
+    }
+
+    #[test]
+    fn refuses_rolling_back_migrations_not_the_latest() {
+        let names = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let applied = names(&["m1_users", "m2_posts", "m3_tags"]);
+
+        let err = latest_steps(&applied, &names(&["m2_posts"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot roll back `m2_posts`, as `m3_tags` were applied after them. roll those back \
+             first"
+        );
+    }
+
     #[tokio::test]
     async fn test_sqlite_connect_success() {
         let (config, _tree_fs) = crate::tests_cfg::config::get_sqlite_test_config("test");
@@ -14,18 +14,24 @@
 use colored::Colorize;
 use regex::Regex;
 use semver::Version;
+use serde::Serialize;
 use std::fmt::Write;
 use std::{
     collections::{BTreeMap, HashMap},
+    future::Future,
     process::Command,
     sync::OnceLock,
+    time::{Duration, Instant},
 };
 
 use crate::{
+    app::AppContext,
     bgworker,
     cargo_config::CargoConfig,
     config::{self, Config},
-    depcheck, Error, Result,
+    depcheck,
+    storage::StorageError,
+    Error, Result,
 };
 
 const SEAORM_INSTALLED: &str = "SeaORM CLI is installed";
@@ -103,6 +109,8 @@ pub enum Resource {
     SeaOrmCLI,
     Database,
     Queue,
+    Cache,
+    Storage,
     Deps,
     PublishedLocoVersion,
     Initializer(String),
@@ -178,17 +186,24 @@ pub async fn run_all<H: crate::app::Hooks>(
     app_context: &crate::app::AppContext,
     production: bool,
 ) -> Result<BTreeMap<Resource, Check>> {
-    let mut checks = BTreeMap::from(
-        #[cfg(feature = "with-db")]
-        [(
-            Resource::Database,
-            check_db(&app_context.config.database).await,
-        )],
-        #[cfg(not(feature = "with-db"))]
-        [],
-    );
-
-    if app_context.config.workers.mode == config::WorkerMode::BackgroundQueue {
+    let timeout = Duration::from_millis(app_context.config.server.readiness.timeout);
+    let mut checks = check_dependencies(app_context, timeout)
+        .await
+        .into_iter()
+        .map(|(dependency, check)| {
+            let resource = match dependency {
+                Dependency::Database => Resource::Database,
+                Dependency::Queue => Resource::Queue,
+                Dependency::Cache => Resource::Cache,
+                Dependency::Storage => Resource::Storage,
+            };
+            (resource, check.to_check(dependency))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    if app_context.config.workers.mode == config::WorkerMode::BackgroundQueue
+        && !checks.contains_key(&Resource::Queue)
+    {
         checks.insert(Resource::Queue, check_queue(&app_context.config).await);
     }
 
@@ -215,6 +230,139 @@ pub async fn run_all<H: crate::app::Hooks>(
     Ok(checks)
 }
 
+/// A service the app depends on at runtime, checked by the `/_ready` route
+/// and by `doctor`.
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dependency {
+    Database,
+    Queue,
+    Cache,
+    Storage,
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Database => "database",
+            Self::Queue => "queue",
+            Self::Cache => "cache",
+            Self::Storage => "storage",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The outcome of checking a [`Dependency`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCheck {
+    pub ok: bool,
+    /// How long the dependency took to answer, in milliseconds
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyCheck {
+    fn to_check(&self, dependency: Dependency) -> Check {
+        if self.ok {
+            Check {
+                status: CheckStatus::Ok,
+                message: format!("{dependency} connection: success ({}ms)", self.latency_ms),
+                description: None,
+            }
+        } else {
+            Check {
+                status: CheckStatus::NotOk,
+                message: format!("{dependency} connection: failed ({}ms)", self.latency_ms),
+                description: self.error.clone(),
+            }
+        }
+    }
+}
+
+/// Checks the connections of the app to the dependencies it has configured,
+/// concurrently, each given `timeout` to answer.
+pub async fn check_dependencies(
+    ctx: &AppContext,
+    timeout: Duration,
+) -> BTreeMap<Dependency, DependencyCheck> {
+    let (database, queue, cache, storage) = tokio::join!(
+        timed(timeout, ping_database(ctx)),
+        timed(timeout, ping_queue(ctx)),
+        timed(timeout, ping_cache(ctx)),
+        timed(timeout, ping_storage(ctx)),
+    );
+    [
+        (Dependency::Database, database),
+        (Dependency::Queue, queue),
+        (Dependency::Cache, cache),
+        (Dependency::Storage, storage),
+    ]
+    .into_iter()
+    .filter_map(|(dependency, check)| Some((dependency, check?)))
+    .collect()
+}
+
+/// Times a check, `None` when there is nothing to check.
+async fn timed(
+    timeout: Duration,
+    check: impl Future<Output = Option<Result<()>>> + Send,
+) -> Option<DependencyCheck> {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(timeout, check).await {
+        Ok(result) => result?,
+        Err(_) => Err(Error::Message(format!(
+            "timed out after {}ms",
+            timeout.as_millis()
+        ))),
+    };
+    Some(DependencyCheck {
+        ok: result.is_ok(),
+        latency_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        error: result.err().map(|err| err.to_string()),
+    })
+}
+
+#[cfg(feature = "with-db")]
+async fn ping_database(ctx: &AppContext) -> Option<Result<()>> {
+    let ping = async {
+        ctx.db.ping().await?;
+        crate::db::verify_access(&ctx.db).await
+    };
+    Some(ping.await)
+}
+
+#[cfg(not(feature = "with-db"))]
+#[allow(clippy::unused_async)]
+async fn ping_database(_ctx: &AppContext) -> Option<Result<()>> {
+    None
+}
+
+async fn ping_queue(ctx: &AppContext) -> Option<Result<()>> {
+    let queue = ctx.queue_provider.as_ref()?;
+    Some(queue.ping().await)
+}
+
+async fn ping_cache(ctx: &AppContext) -> Option<Result<()>> {
+    if matches!(ctx.config.cache, config::CacheConfig::Null) {
+        return None;
+    }
+    Some(ctx.cache.ping().await.map_err(Into::into))
+}
+
+async fn ping_storage(ctx: &AppContext) -> Option<Result<()>> {
+    let mut checked = false;
+    for (name, store) in &ctx.storage.stores {
+        match store.ping().await {
+            Ok(()) => checked = true,
+            Err(StorageError::Unsupported(_)) => {}
+            Err(err) => return Some(Err(Error::Message(format!("store `{name}`: {err}")))),
+        }
+    }
+    checked.then_some(Ok(()))
+}
+
 /// Checks "blessed" / major dependencies in a Loco app Cargo.toml, and
 /// recommend to update.
 /// Only if a dep exists, we check it against a min version
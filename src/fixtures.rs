@@ -0,0 +1,495 @@
+//! # Fixtures
+//!
+//! Seeds the database from fixture files, one per table named after it, e.g.
+//! `src/fixtures/users.yaml` (or `.yml`, `.json`). A file holds the table's
+//! records, keyed by a label:
+//!
+//! ```yaml
+//! # src/fixtures/users.yaml
+//! admin:
+//!   email: admin@example.com
+//!   name: Admin
+//! ```
+//!
+//! A record references the record of another table by its label: `user:
+//! admin` sets `user_id` to the id of the `admin` fixture of `users`, and
+//! `author: users.admin` does the same for `author_id`. Records get an id
+//! derived from their table and label, unless they give one, so that these
+//! ids are known up front and stay the same from one seed to the next. A
+//! file may also hold a list of unlabeled records, as dumped by `db seed
+//! --dump`, which cannot be referenced.
+//!
+//! The fixtures of the environment's folder, e.g. `src/fixtures/test/`, are
+//! loaded along with the shared ones: a record labeled like a shared one
+//! overrides its fields, others are added.
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use cruet::Inflector;
+use sea_orm::{
+    sea_query::{
+        Alias, InsertStatement, MysqlQueryBuilder, OnConflict, PostgresQueryBuilder, Query,
+        SqliteQueryBuilder, Value,
+    },
+    ConnectionTrait, DatabaseBackend, DatabaseConnection, TransactionTrait,
+};
+use serde_json::Map;
+
+use crate::{app::AppContext, db, environment::Environment, Error, Result};
+
+/// How records are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Insert the records, failing on those that exist already
+    Insert,
+    /// Insert the records or update them by id, so that seeding again is safe
+    Upsert,
+}
+
+impl Mode {
+    /// The mode the app is seeded in, kept in [`AppContext::shared_store`]:
+    /// `Upsert` with `db seed --upsert`, or else `Insert`. A
+    /// [`crate::app::Hooks::seed`] of the app's own follows it to honor
+    /// `--upsert`.
+    #[must_use]
+    pub fn of(ctx: &AppContext) -> Self {
+        ctx.shared_store.get::<Self>().unwrap_or(Self::Insert)
+    }
+}
+
+/// A record of a fixture file
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    label: Option<String>,
+    fields: Map<String, serde_json::Value>,
+}
+
+/// Loads the fixtures of `dir`, and of its `environment` folder, into the
+/// database, the tables referenced by others first.
+///
+/// # Errors
+///
+/// When a fixture file cannot be read, a reference does not resolve, the
+/// tables reference each other in a cycle, or a record cannot be written
+pub async fn load(
+    db: &DatabaseConnection,
+    dir: &Path,
+    environment: &Environment,
+    mode: Mode,
+) -> Result<()> {
+    let mut tables = read_dir(dir)?;
+    let env_dir = dir.join(environment.to_string());
+    if env_dir.is_dir() {
+        merge(&mut tables, read_dir(&env_dir)?);
+    }
+
+    let backend = db.get_database_backend();
+    let mut with_id = HashSet::new();
+    for table in tables.keys() {
+        if db::has_id_column(db, &backend, table).await? {
+            with_id.insert(table.clone());
+        }
+    }
+
+    let ids = assign_ids(&mut tables, &with_id);
+    let order = resolve(&mut tables, &ids)?;
+
+    let txn = db.begin().await?;
+    for table in &order {
+        for record in &tables[table] {
+            let insert = insert_statement(table, record, mode, with_id.contains(table));
+            txn.execute_unprepared(&build(backend, &insert)?).await?;
+        }
+        tracing::info!(table, records = tables[table].len(), "seeded fixtures");
+    }
+    txn.commit().await?;
+
+    for table in order.iter().filter(|table| with_id.contains(*table)) {
+        db::reset_autoincrement(backend, table, db).await?;
+    }
+    Ok(())
+}
+
+/// Reads the fixture files of `dir`, by table.
+fn read_dir(dir: &Path) -> Result<BTreeMap<String, Vec<Record>>> {
+    let mut tables = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(table) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let content = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&fs::read_to_string(&path)?)?,
+            Some("json") => serde_json::from_str(&fs::read_to_string(&path)?)?,
+            _ => continue,
+        };
+        tables.insert(table.to_string(), records(&path, content)?);
+    }
+    Ok(tables)
+}
+
+fn records(path: &Path, content: serde_json::Value) -> Result<Vec<Record>> {
+    let fields = |value: serde_json::Value| match value {
+        serde_json::Value::Object(fields) => Ok(fields),
+        _ => Err(Error::Message(format!(
+            "fixture `{}`: a record is not a map of its fields",
+            path.display()
+        ))),
+    };
+    match content {
+        serde_json::Value::Object(labeled) => labeled
+            .into_iter()
+            .map(|(label, value)| {
+                Ok(Record {
+                    label: Some(label),
+                    fields: fields(value)?,
+                })
+            })
+            .collect(),
+        serde_json::Value::Array(unlabeled) => unlabeled
+            .into_iter()
+            .map(|value| {
+                Ok(Record {
+                    label: None,
+                    fields: fields(value)?,
+                })
+            })
+            .collect(),
+        serde_json::Value::Null => Ok(vec![]),
+        _ => Err(Error::Message(format!(
+            "fixture `{}`: expected labeled records or a list of records",
+            path.display()
+        ))),
+    }
+}
+
+/// Merges the fixtures of an environment into the shared ones.
+fn merge(tables: &mut BTreeMap<String, Vec<Record>>, overrides: BTreeMap<String, Vec<Record>>) {
+    for (table, records) in overrides {
+        let shared = tables.entry(table).or_default();
+        for record in records {
+            match shared
+                .iter_mut()
+                .find(|r| r.label.is_some() && r.label == record.label)
+            {
+                Some(existing) => existing.fields.extend(record.fields),
+                None => shared.push(record),
+            }
+        }
+    }
+}
+
+/// Gives the labeled records of the tables with an `id` column the id derived
+/// from their label, unless they have one. Returns the ids by table and label.
+fn assign_ids(
+    tables: &mut BTreeMap<String, Vec<Record>>,
+    with_id: &HashSet<String>,
+) -> HashMap<(String, String), serde_json::Value> {
+    let mut ids = HashMap::new();
+    for (table, records) in tables.iter_mut() {
+        if !with_id.contains(table) {
+            continue;
+        }
+        for record in records {
+            let Some(label) = &record.label else {
+                continue;
+            };
+            let id = record
+                .fields
+                .entry("id")
+                .or_insert_with(|| label_id(table, label).into())
+                .clone();
+            ids.insert((table.clone(), label.clone()), id);
+        }
+    }
+    ids
+}
+
+/// The id of a record derived from its table and label, an FNV-1a hash kept
+/// within 30 bits to fit any integer key and stay clear of sequence ids.
+#[must_use]
+pub fn label_id(table: &str, label: &str) -> i32 {
+    let hash = format!("{table}/{label}")
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        });
+    i32::try_from(hash & 0x3fff_ffff).unwrap_or_default()
+}
+
+/// Replaces the references of the records with the ids they reference, and
+/// orders the tables so that referenced ones come first.
+fn resolve(
+    tables: &mut BTreeMap<String, Vec<Record>>,
+    ids: &HashMap<(String, String), serde_json::Value>,
+) -> Result<Vec<String>> {
+    let names = tables.keys().cloned().collect::<HashSet<_>>();
+    let mut dependencies: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+
+    for (table, records) in tables.iter_mut() {
+        let deps = dependencies.entry(table.clone()).or_default();
+        for record in records {
+            let mut resolved = Map::new();
+            for (field, value) in std::mem::take(&mut record.fields) {
+                let Some((target, label)) = reference(&field, &value, &names, ids) else {
+                    resolved.insert(field, value);
+                    continue;
+                };
+                let id = ids.get(&(target.clone(), label.clone())).ok_or_else(|| {
+                    Error::Message(format!(
+                        "fixture `{table}.{}`: `{field}` references `{target}.{label}`, which is \
+                         not a labeled fixture of a table with an `id`",
+                        record.label.as_deref().unwrap_or_default()
+                    ))
+                })?;
+                if target != *table {
+                    deps.insert(target);
+                }
+                resolved.insert(format!("{field}_id"), id.clone());
+            }
+            record.fields = resolved;
+        }
+    }
+
+    let mut order = Vec::new();
+    while !dependencies.is_empty() {
+        let ready = dependencies
+            .iter()
+            .filter(|(_, deps)| deps.iter().all(|dep| order.contains(dep)))
+            .map(|(table, _)| table.clone())
+            .collect::<Vec<_>>();
+        if ready.is_empty() {
+            return Err(Error::Message(format!(
+                "fixtures reference each other in a cycle: {}",
+                dependencies.keys().cloned().collect::<Vec<_>>().join(", ")
+            )));
+        }
+        for table in ready {
+            dependencies.remove(&table);
+            order.push(table);
+        }
+    }
+    Ok(order)
+}
+
+/// The `(table, label)` a field references: `user: admin` the `admin` of
+/// `users`, or `author: users.admin` the `admin` of `users`.
+fn reference(
+    field: &str,
+    value: &serde_json::Value,
+    tables: &HashSet<String>,
+    ids: &HashMap<(String, String), serde_json::Value>,
+) -> Option<(String, String)> {
+    let serde_json::Value::String(value) = value else {
+        return None;
+    };
+    let plural = field.to_plural();
+    if plural != field && tables.contains(&plural) {
+        return Some((plural, value.clone()));
+    }
+    value
+        .split_once('.')
+        .map(|(table, label)| (table.to_string(), label.to_string()))
+        .filter(|key| ids.contains_key(key))
+}
+
+fn insert_statement(table: &str, record: &Record, mode: Mode, with_id: bool) -> InsertStatement {
+    let columns = record.fields.keys().map(Alias::new).collect::<Vec<_>>();
+    let values = record.fields.values().map(|v| value(v).into());
+    let mut insert = Query::insert();
+    insert
+        .into_table(Alias::new(table))
+        .columns(columns)
+        .values_panic(values);
+
+    if mode == Mode::Upsert {
+        let updates = record
+            .fields
+            .keys()
+            .filter(|column| *column != "id")
+            .map(Alias::new)
+            .collect::<Vec<_>>();
+        let on_conflict = match (with_id, updates.is_empty()) {
+            (true, false) => OnConflict::column(Alias::new("id"))
+                .update_columns(updates)
+                .to_owned(),
+            (true, true) => OnConflict::column(Alias::new("id")).do_nothing().to_owned(),
+            (false, _) => OnConflict::new().do_nothing().to_owned(),
+        };
+        insert.on_conflict(on_conflict);
+    }
+    insert
+}
+
+/// A field as a SQL value. Values are inlined in the statement, so that the
+/// database converts them to the column's type, e.g. a timestamp from its
+/// text. Maps and lists are stored as their JSON text.
+fn value(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::String(None),
+        serde_json::Value::Bool(value) => Value::Bool(Some(*value)),
+        serde_json::Value::Number(number) => number.as_i64().map_or_else(
+            || Value::Double(number.as_f64()),
+            |number| Value::BigInt(Some(number)),
+        ),
+        serde_json::Value::String(value) => Value::String(Some(Box::new(value.clone()))),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+            Value::String(Some(Box::new(value.to_string())))
+        }
+    }
+}
+
+fn build(backend: DatabaseBackend, insert: &InsertStatement) -> Result<String> {
+    Ok(match backend {
+        DatabaseBackend::Postgres => insert.to_string(PostgresQueryBuilder),
+        DatabaseBackend::Sqlite => insert.to_string(SqliteQueryBuilder),
+        DatabaseBackend::MySql => insert.to_string(MysqlQueryBuilder),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ConnectOptions, Database};
+    use sea_orm_migration::SchemaManager;
+    use tree_fs::TreeBuilder;
+
+    use super::*;
+    use crate::{
+        schema::{create_table, ColType},
+        tests_cfg::db::get_value,
+    };
+
+    async fn setup() -> DatabaseConnection {
+        let mut opt = ConnectOptions::new("sqlite::memory:");
+        opt.max_connections(1);
+        let db = Database::connect(opt).await.unwrap();
+        let m = SchemaManager::new(&db);
+        create_table(
+            &m,
+            "users",
+            &[
+                ("id", ColType::PkAuto),
+                ("email", ColType::StringUniq),
+                ("admin", ColType::BooleanNull),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        create_table(
+            &m,
+            "posts",
+            &[("id", ColType::PkAuto), ("title", ColType::String)],
+            &[("user", ""), ("users?", "reviewer_id")],
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    const USERS: &str = "
+admin:
+  email: admin@example.com
+  admin: true
+member:
+  email: member@example.com
+";
+
+    const POSTS: &str = "
+hello:
+  title: Hello
+  user: admin
+  reviewer: users.member
+";
+
+    #[tokio::test]
+    async fn can_load_fixtures_with_references() {
+        let db = setup().await;
+        let tree = TreeBuilder::default()
+            .add("posts.yaml", POSTS)
+            .add("users.yaml", USERS)
+            .add("test/users.yaml", "member:\n  email: tester@example.com\n")
+            .create()
+            .unwrap();
+
+        load(&db, &tree.root, &Environment::Test, Mode::Insert)
+            .await
+            .unwrap();
+
+        let admin = label_id("users", "admin");
+        assert_eq!(
+            get_value(&db, &format!("SELECT email FROM users WHERE id = {admin}")).await,
+            "admin@example.com"
+        );
+        assert_eq!(
+            get_value(
+                &db,
+                "SELECT users.email FROM posts JOIN users ON users.id = posts.reviewer_id"
+            )
+            .await,
+            "tester@example.com"
+        );
+        assert_eq!(
+            get_value(&db, "SELECT user_id FROM posts").await,
+            admin.to_string()
+        );
+
+        // inserting again conflicts, upserting does not
+        assert!(load(&db, &tree.root, &Environment::Test, Mode::Insert)
+            .await
+            .is_err());
+        load(&db, &tree.root, &Environment::Test, Mode::Upsert)
+            .await
+            .unwrap();
+        assert_eq!(get_value(&db, "SELECT COUNT(*) FROM users").await, "2");
+    }
+
+    #[tokio::test]
+    async fn can_tell_seed_mode() {
+        let ctx = crate::tests_cfg::app::get_app_context().await;
+        assert_eq!(Mode::of(&ctx), Mode::Insert);
+        ctx.shared_store.insert(Mode::Upsert);
+        assert_eq!(Mode::of(&ctx), Mode::Upsert);
+    }
+
+    #[test]
+    fn cannot_resolve_missing_or_cyclic_references() {
+        let mut tables = BTreeMap::from([(
+            "posts".to_string(),
+            records(
+                Path::new("posts.yaml"),
+                serde_yaml::from_str(POSTS).unwrap(),
+            )
+            .unwrap(),
+        )]);
+        tables.insert("users".to_string(), vec![]);
+        let ids = assign_ids(&mut tables, &HashSet::from(["posts".to_string()]));
+        let err = resolve(&mut tables, &ids).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "fixture `posts.hello`: `user` references `users.admin`, which is not a labeled \
+             fixture of a table with an `id`"
+        );
+
+        let record = |field: &str, label: &str| Record {
+            label: Some("one".to_string()),
+            fields: Map::from_iter([(field.to_string(), label.into())]),
+        };
+        let mut tables = BTreeMap::from([
+            ("users".to_string(), vec![record("post", "one")]),
+            ("posts".to_string(), vec![record("user", "one")]),
+        ]);
+        let ids = assign_ids(
+            &mut tables,
+            &HashSet::from(["users".to_string(), "posts".to_string()]),
+        );
+        let err = resolve(&mut tables, &ids).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "fixtures reference each other in a cycle: posts, users"
+        );
+    }
+}
@@ -4,6 +4,7 @@ use argon2::{
     Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
 };
 use rand::{distr::Alphanumeric, rng, Rng};
+use sha2::{Digest, Sha256};
 
 /// Hashes a plain text password and returns the hashed result.
 ///
@@ -77,6 +78,24 @@ pub fn random_string(length: usize) -> String {
         .collect()
 }
 
+/// Hashes `data` with SHA-256, e.g. to address a blob by its content, and
+/// returns the hash in lowercase hex.
+///
+/// # Example
+///
+/// ```rust
+/// use loco_rs::hash;
+///
+/// assert_eq!(
+///     hash::sha256_hex(b"loco"),
+///     hash::sha256_hex("loco".as_bytes())
+/// );
+/// ```
+#[must_use]
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -100,4 +119,12 @@ mod tests {
         assert_eq!(second.len(), random_length);
         assert_ne!(first, second);
     }
+
+    #[test]
+    fn can_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
 }
@@ -16,6 +16,8 @@ pub mod doctor;
 #[cfg(feature = "with-db")]
 pub mod db;
 #[cfg(feature = "with-db")]
+pub mod fixtures;
+#[cfg(feature = "with-db")]
 pub mod model;
 #[cfg(feature = "with-db")]
 pub mod schema;
@@ -25,6 +27,7 @@ pub mod app;
 pub mod auth;
 pub mod boot;
 pub mod cache;
+pub mod channels;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod config;
@@ -37,6 +40,8 @@ pub mod logger;
 pub mod mailer;
 pub mod scheduler;
 pub mod task;
+#[cfg(feature = "with-db")]
+pub mod tenancy;
 #[cfg(feature = "testing")]
 pub mod testing;
 #[cfg(feature = "testing")]
@@ -47,6 +52,8 @@ pub mod tests_cfg;
 pub mod validation;
 pub use validator;
 pub mod cargo_config;
+#[cfg(feature = "cli")]
+pub mod watch;
 
 /// Application results options list
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -1,16 +1,18 @@
 //! This module defines an [`EmailSender`] responsible for sending emails using
-//! either the SMTP protocol. It includes an asynchronous method `mail` for
-//! sending emails with options like sender, recipient, subject, and content.
+//! either the SMTP protocol or a [`MailerTransport`]. It includes an
+//! asynchronous method `mail` for sending emails with options like sender,
+//! recipient, subject, and content.
+
+use std::sync::Arc;
 
 use lettre::{
-    message::{header, MultiPart},
     transport::smtp::{authentication::Credentials, extension::ClientId},
-    AsyncTransport, Message, Tokio1Executor, Transport,
+    Tokio1Executor,
 };
 use tracing::error;
 
-use super::{Email, Result, DEFAULT_FROM_SENDER};
-use crate::{config, errors::Error};
+use super::{transport::MailerTransport, Email, Result};
+use crate::config;
 
 /// An enumeration representing the possible transport methods for sending
 /// emails.
@@ -20,6 +22,8 @@ pub enum EmailTransport {
     Smtp(lettre::AsyncSmtpTransport<lettre::Tokio1Executor>),
     /// Test/stub transport for testing purposes.
     Test(lettre::transport::stub::StubTransport),
+    /// Any other transport, e.g. the HTTP API of a provider.
+    Custom(Arc<dyn MailerTransport>),
 }
 
 /// A structure representing the email sender, encapsulating the chosen
@@ -70,6 +74,28 @@ impl EmailSender {
         })
     }
 
+    /// Creates a new `EmailSender` sending through the Amazon SES API.
+    #[cfg(feature = "mailer_ses")]
+    #[must_use]
+    pub fn ses(config: &config::SesMailer) -> Self {
+        Self::custom(super::transport::ses::Ses::new(config))
+    }
+
+    /// Creates a new `EmailSender` sending through the SendGrid API.
+    #[cfg(feature = "mailer_sendgrid")]
+    #[must_use]
+    pub fn sendgrid(config: &config::SendGridMailer) -> Self {
+        Self::custom(super::transport::sendgrid::SendGrid::new(config))
+    }
+
+    /// Creates a new `EmailSender` sending through a transport of your own.
+    #[must_use]
+    pub fn custom(transport: impl MailerTransport + 'static) -> Self {
+        Self {
+            transport: EmailTransport::Custom(Arc::new(transport)),
+        }
+    }
+
     #[must_use]
     pub fn stub() -> Self {
         Self {
@@ -101,59 +127,11 @@ impl EmailSender {
     /// When email doesn't send successfully or has an error to build the
     /// message
     pub async fn mail(&self, email: &Email) -> Result<()> {
-        let content = MultiPart::alternative_plain_html(email.text.clone(), email.html.clone());
-        let mut builder = Message::builder()
-            .from(
-                email
-                    .from
-                    .clone()
-                    .unwrap_or_else(|| DEFAULT_FROM_SENDER.to_string())
-                    .parse()?,
-            )
-            .to(email.to.parse()?);
-
-        if let Some(bcc) = &email.bcc {
-            builder = builder.bcc(bcc.parse()?);
-        }
-
-        if let Some(cc) = &email.cc {
-            builder = builder.cc(cc.parse()?);
-        }
-
-        if let Some(reply_to) = &email.reply_to {
-            builder = builder.reply_to(reply_to.parse()?);
-        }
-
-        if let Some(headers) = &email.headers {
-            if let Some(references) = &headers.references {
-                builder = builder.header(header::References::from(references.clone()));
-            }
-            if let Some(in_reply_to) = &headers.in_reply_to {
-                builder = builder.header(header::InReplyTo::from(in_reply_to.clone()));
-            }
-            if let Some(message_id) = &headers.message_id {
-                builder = builder.header(header::MessageId::from(message_id.clone()));
-            }
-        }
-
-        let msg = builder
-            .subject(email.subject.clone())
-            .multipart(content)
-            .map_err(|error| {
-                error!(err.msg = %error, err.detail = ?error, "email_building_error");
-                error
-            })?;
-
         match &self.transport {
-            EmailTransport::Smtp(xp) => {
-                xp.send(msg).await?;
-            }
-            EmailTransport::Test(xp) => {
-                xp.send(&msg)
-                    .map_err(|e| Error::Message(format!("sending email error: {e}")))?;
-            }
+            EmailTransport::Smtp(xp) => MailerTransport::send(xp, email).await,
+            EmailTransport::Test(xp) => MailerTransport::send(xp, email).await,
+            EmailTransport::Custom(xp) => xp.send(email).await,
         }
-        Ok(())
     }
 }
 
@@ -190,6 +168,7 @@ mod tests {
             bcc: None,
             cc: None,
             headers: None,
+            attachments: vec![],
         };
         assert!(sender.mail(&data).await.is_ok());
 
@@ -232,6 +211,42 @@ mod tests {
             bcc: None,
             cc: None,
             headers: Some(headers),
+            attachments: vec![],
+        };
+        assert!(sender.mail(&data).await.is_ok());
+
+        with_settings!({filters => vec![
+            (r"[0-9A-Za-z]+{40}", "IDENTIFIER"),
+            (r"\w+, \d{1,2} \w+ \d{4} \d{2}:\d{2}:\d{2} [+-]\d{4}", "DATE")
+        ]}, {
+            assert_debug_snapshot!(stub.messages());
+        });
+    }
+
+    #[tokio::test]
+    async fn can_send_email_with_attachments() {
+        let stub = StubTransport::new_ok();
+
+        let sender = EmailSender {
+            transport: EmailTransport::Test(stub.clone()),
+        };
+
+        let data = Email {
+            from: Some("test@framework.com".to_string()),
+            to: "user1@framework.com".to_string(),
+            subject: "Your invoice".to_string(),
+            text: "Your invoice is attached".to_string(),
+            html: r#"<img src="cid:logo"> Your invoice is attached"#.to_string(),
+            attachments: vec![
+                crate::mailer::Attachment::new("logo.png", "image/png", b"png".to_vec())
+                    .inline("logo"),
+                crate::mailer::Attachment::new(
+                    "invoice.pdf",
+                    "application/pdf",
+                    b"%PDF-1.4".to_vec(),
+                ),
+            ],
+            ..Default::default()
         };
         assert!(sender.mail(&data).await.is_ok());
 
@@ -242,4 +257,45 @@ mod tests {
             assert_debug_snapshot!(stub.messages());
         });
     }
+
+    #[tokio::test]
+    async fn cannot_send_attachment_with_invalid_content_type() {
+        let sender = EmailSender::stub();
+
+        let data = Email {
+            to: "user1@framework.com".to_string(),
+            attachments: vec![crate::mailer::Attachment::new(
+                "invoice.pdf",
+                "not a content type",
+                b"%PDF-1.4".to_vec(),
+            )],
+            ..Default::default()
+        };
+        assert!(sender.mail(&data).await.is_err());
+    }
+
+    #[test]
+    fn can_serialize_attachments() {
+        let email = Email {
+            to: "user1@framework.com".to_string(),
+            attachments: vec![crate::mailer::Attachment::new(
+                "invoice.pdf",
+                "application/pdf",
+                b"%PDF-1.4".to_vec(),
+            )],
+            ..Default::default()
+        };
+
+        let value = serde_json::to_value(&email).unwrap();
+        assert_eq!(value["attachments"][0]["content"], "JVBERi0xLjQ=");
+        assert!(value["attachments"][0].get("content_id").is_none());
+        let email: Email = serde_json::from_value(value).unwrap();
+        assert_eq!(email.attachments[0].content, b"%PDF-1.4");
+
+        let email: Email = serde_json::from_str(
+            r#"{"to": "user1@framework.com", "subject": "", "text": "", "html": ""}"#,
+        )
+        .unwrap();
+        assert!(email.attachments.is_empty());
+    }
 }
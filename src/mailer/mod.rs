@@ -4,8 +4,10 @@
 
 mod email_sender;
 mod template;
+pub mod transport;
 
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
 pub use email_sender::EmailSender;
 use include_dir::Dir;
 use serde::{Deserialize, Serialize};
@@ -24,6 +26,70 @@ pub struct EmailHeaders {
     pub message_id: Option<String>,
 }
 
+/// A file attached to an email, e.g. an invoice. An inline one, with a
+/// content id, is shown by the html of the email instead, e.g. an image as
+/// `<img src="cid:logo">`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub filename: String,
+    /// The MIME type, e.g. `application/pdf`
+    pub content_type: String,
+    /// Kept base64 encoded when serialized, e.g. in the mailer queue
+    #[serde(with = "base64_content")]
+    pub content: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
+}
+
+impl Attachment {
+    #[must_use]
+    pub fn new(
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        content: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            filename: filename.into(),
+            content_type: content_type.into(),
+            content: content.into(),
+            content_id: None,
+        }
+    }
+
+    /// Attaches the file inline, for the html to refer to as
+    /// `cid:<content_id>`.
+    #[must_use]
+    pub fn inline(mut self, content_id: impl Into<String>) -> Self {
+        self.content_id = Some(content_id.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn is_inline(&self) -> bool {
+        self.content_id.is_some()
+    }
+
+    /// The content, base64 encoded, as the HTTP API transports take it.
+    #[must_use]
+    pub fn base64_content(&self) -> String {
+        STANDARD.encode(&self.content)
+    }
+}
+
+mod base64_content {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(content: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(content))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
 /// The arguments struct for specifying email details such as sender, recipient,
 /// reply-to, and locals.
 #[derive(Debug, Clone, Default)]
@@ -35,6 +101,7 @@ pub struct Args {
     pub bcc: Option<String>,
     pub cc: Option<String>,
     pub headers: Option<EmailHeaders>,
+    pub attachments: Vec<Attachment>,
 }
 
 /// The structure representing an email details.
@@ -58,6 +125,9 @@ pub struct Email {
     pub cc: Option<String>,
     /// Custom headers for the email (e.g., References, In-Reply-To, Message-ID)
     pub headers: Option<EmailHeaders>,
+    /// Files attached to the email, or shown inline by its html
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
 }
 
 /// The options struct for configuring the email sender.
@@ -109,6 +179,7 @@ pub trait Mailer {
                 bcc: args.bcc.clone(),
                 cc: args.cc.clone(),
                 headers: args.headers.clone(),
+                attachments: args.attachments,
             },
         )
         .await
@@ -0,0 +1,128 @@
+//! Transports deliver an [`Email`]: SMTP by default, or the HTTP API of a
+//! provider, Amazon SES (`mailer_ses` feature) or SendGrid
+//! (`mailer_sendgrid` feature).
+
+#[cfg(feature = "mailer_sendgrid")]
+pub mod sendgrid;
+#[cfg(feature = "mailer_ses")]
+pub mod ses;
+
+use async_trait::async_trait;
+use lettre::{
+    message::{header, header::ContentType, Attachment, MultiPart},
+    transport::stub::StubTransport,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, Transport,
+};
+use tracing::error;
+
+use super::{Email, Result, DEFAULT_FROM_SENDER};
+use crate::errors::Error;
+
+/// Delivers emails, see [`super::EmailSender::custom`] for sending through a
+/// transport of your own.
+#[async_trait]
+pub trait MailerTransport: Send + Sync + std::fmt::Debug {
+    /// Delivers `email`.
+    ///
+    /// # Errors
+    ///
+    /// When the email cannot be built or is not delivered
+    async fn send(&self, email: &Email) -> Result<()>;
+}
+
+#[async_trait]
+impl MailerTransport for AsyncSmtpTransport<Tokio1Executor> {
+    async fn send(&self, email: &Email) -> Result<()> {
+        AsyncTransport::send(self, message(email)?).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MailerTransport for StubTransport {
+    async fn send(&self, email: &Email) -> Result<()> {
+        Transport::send(self, &message(email)?)
+            .map_err(|e| Error::Message(format!("sending email error: {e}")))?;
+        Ok(())
+    }
+}
+
+/// Builds the MIME message of an email: its text and html alternatives,
+/// along with the images they show inline and its attachments.
+///
+/// # Errors
+///
+/// When an address or a content type does not parse
+pub fn message(email: &Email) -> Result<Message> {
+    let mut builder = Message::builder()
+        .from(
+            email
+                .from
+                .clone()
+                .unwrap_or_else(|| DEFAULT_FROM_SENDER.to_string())
+                .parse()?,
+        )
+        .to(email.to.parse()?);
+
+    if let Some(bcc) = &email.bcc {
+        builder = builder.bcc(bcc.parse()?);
+    }
+
+    if let Some(cc) = &email.cc {
+        builder = builder.cc(cc.parse()?);
+    }
+
+    if let Some(reply_to) = &email.reply_to {
+        builder = builder.reply_to(reply_to.parse()?);
+    }
+
+    if let Some(headers) = &email.headers {
+        if let Some(references) = &headers.references {
+            builder = builder.header(header::References::from(references.clone()));
+        }
+        if let Some(in_reply_to) = &headers.in_reply_to {
+            builder = builder.header(header::InReplyTo::from(in_reply_to.clone()));
+        }
+        if let Some(message_id) = &headers.message_id {
+            builder = builder.header(header::MessageId::from(message_id.clone()));
+        }
+    }
+
+    let mut content = MultiPart::alternative_plain_html(email.text.clone(), email.html.clone());
+    let (inline, attached): (Vec<_>, Vec<_>) =
+        email.attachments.iter().partition(|a| a.is_inline());
+    if !inline.is_empty() {
+        let mut related = MultiPart::related().multipart(content);
+        for attachment in inline {
+            let content_id = attachment.content_id.clone().unwrap_or_default();
+            related = related.singlepart(Attachment::new_inline(content_id).body(
+                attachment.content.clone(),
+                content_type(&attachment.content_type)?,
+            ));
+        }
+        content = related;
+    }
+    if !attached.is_empty() {
+        let mut mixed = MultiPart::mixed().multipart(content);
+        for attachment in attached {
+            mixed = mixed.singlepart(Attachment::new(attachment.filename.clone()).body(
+                attachment.content.clone(),
+                content_type(&attachment.content_type)?,
+            ));
+        }
+        content = mixed;
+    }
+
+    Ok(builder
+        .subject(email.subject.clone())
+        .multipart(content)
+        .map_err(|error| {
+            error!(err.msg = %error, err.detail = ?error, "email_building_error");
+            error
+        })?)
+}
+
+fn content_type(content_type: &str) -> Result<ContentType> {
+    ContentType::parse(content_type)
+        .map_err(|err| Error::Message(format!("invalid content type `{content_type}`: {err}")))
+}
@@ -0,0 +1,216 @@
+//! Sends emails with the [SendGrid v3 API](https://www.twilio.com/docs/sendgrid/api-reference/mail-send/mail-send).
+
+use async_trait::async_trait;
+use lettre::message::{Mailbox, Mailboxes};
+use serde_json::{json, Map, Value};
+
+use super::MailerTransport;
+use crate::{
+    config,
+    errors::Error,
+    mailer::{Email, Result, DEFAULT_FROM_SENDER},
+};
+
+const DEFAULT_ENDPOINT: &str = "https://api.sendgrid.com";
+
+/// The SendGrid API transport.
+#[derive(Debug, Clone)]
+pub struct SendGrid {
+    client: reqwest::Client,
+    api_key: String,
+    endpoint: String,
+}
+
+impl SendGrid {
+    #[must_use]
+    pub fn new(config: &config::SendGridMailer) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_key: config.api_key.clone(),
+            endpoint: config
+                .endpoint
+                .clone()
+                .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl MailerTransport for SendGrid {
+    async fn send(&self, email: &Email) -> Result<()> {
+        let res = self
+            .client
+            .post(format!(
+                "{}/v3/mail/send",
+                self.endpoint.trim_end_matches('/')
+            ))
+            .bearer_auth(&self.api_key)
+            .json(&request(email)?)
+            .send()
+            .await
+            .map_err(Error::wrap)?;
+
+        let status = res.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let body = res.text().await.unwrap_or_default();
+            Err(Error::Message(format!(
+                "sendgrid responded with {status}: {body}"
+            )))
+        }
+    }
+}
+
+/// The `mail/send` request of an email.
+fn request(email: &Email) -> Result<Value> {
+    let from = email
+        .from
+        .clone()
+        .unwrap_or_else(|| DEFAULT_FROM_SENDER.to_string());
+
+    let mut personalization = Map::new();
+    personalization.insert("to".to_string(), addresses(&email.to)?);
+    if let Some(cc) = &email.cc {
+        personalization.insert("cc".to_string(), addresses(cc)?);
+    }
+    if let Some(bcc) = &email.bcc {
+        personalization.insert("bcc".to_string(), addresses(bcc)?);
+    }
+
+    // the plain text has to come first, and neither can be empty
+    let content = [("text/plain", &email.text), ("text/html", &email.html)]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(kind, value)| json!({ "type": kind, "value": value }))
+        .collect::<Vec<_>>();
+
+    let mut request = json!({
+        "personalizations": [personalization],
+        "from": address(&from.parse()?),
+        "subject": email.subject,
+        "content": content,
+    });
+
+    if let Some(reply_to) = &email.reply_to {
+        request["reply_to"] = address(&reply_to.parse()?);
+    }
+
+    if let Some(headers) = &email.headers {
+        let headers = [
+            ("References", &headers.references),
+            ("In-Reply-To", &headers.in_reply_to),
+            ("Message-ID", &headers.message_id),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), Value::from(value.clone()?))))
+        .collect::<Map<_, _>>();
+        if !headers.is_empty() {
+            request["headers"] = Value::Object(headers);
+        }
+    }
+
+    if !email.attachments.is_empty() {
+        request["attachments"] = email
+            .attachments
+            .iter()
+            .map(|attachment| {
+                let mut value = json!({
+                    "content": attachment.base64_content(),
+                    "type": attachment.content_type,
+                    "filename": attachment.filename,
+                    "disposition": if attachment.is_inline() { "inline" } else { "attachment" },
+                });
+                if let Some(content_id) = &attachment.content_id {
+                    value["content_id"] = Value::from(content_id.clone());
+                }
+                value
+            })
+            .collect();
+    }
+
+    Ok(request)
+}
+
+fn addresses(mailboxes: &str) -> Result<Value> {
+    let mailboxes: Mailboxes = mailboxes.parse()?;
+    Ok(mailboxes.iter().map(address).collect())
+}
+
+fn address(mailbox: &Mailbox) -> Value {
+    let mut address = json!({ "email": mailbox.email.to_string() });
+    if let Some(name) = &mailbox.name {
+        address["name"] = Value::from(name.clone());
+    }
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailer::{Attachment, EmailHeaders};
+
+    #[test]
+    fn can_build_request() {
+        let email = Email {
+            from: Some("Billing <billing@framework.com>".to_string()),
+            to: "User One <user1@framework.com>, user2@framework.com".to_string(),
+            cc: Some("cc@framework.com".to_string()),
+            reply_to: Some("support@framework.com".to_string()),
+            subject: "Your invoice".to_string(),
+            text: String::new(),
+            html: r#"<img src="cid:logo">"#.to_string(),
+            headers: Some(EmailHeaders {
+                message_id: Some("<invoice-1@framework.com>".to_string()),
+                ..Default::default()
+            }),
+            attachments: vec![
+                Attachment::new("logo.png", "image/png", b"png".to_vec()).inline("logo"),
+                Attachment::new("invoice.pdf", "application/pdf", b"pdf".to_vec()),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            request(&email).unwrap(),
+            json!({
+                "personalizations": [{
+                    "to": [
+                        { "email": "user1@framework.com", "name": "User One" },
+                        { "email": "user2@framework.com" },
+                    ],
+                    "cc": [{ "email": "cc@framework.com" }],
+                }],
+                "from": { "email": "billing@framework.com", "name": "Billing" },
+                "reply_to": { "email": "support@framework.com" },
+                "subject": "Your invoice",
+                "content": [{ "type": "text/html", "value": r#"<img src="cid:logo">"# }],
+                "headers": { "Message-ID": "<invoice-1@framework.com>" },
+                "attachments": [
+                    {
+                        "content": "cG5n",
+                        "type": "image/png",
+                        "filename": "logo.png",
+                        "disposition": "inline",
+                        "content_id": "logo",
+                    },
+                    {
+                        "content": "cGRm",
+                        "type": "application/pdf",
+                        "filename": "invoice.pdf",
+                        "disposition": "attachment",
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn cannot_build_request_with_invalid_address() {
+        let email = Email {
+            to: "not an address".to_string(),
+            ..Default::default()
+        };
+        assert!(request(&email).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Sends emails with the [Amazon SES v2 API](https://docs.aws.amazon.com/ses/latest/APIReference-V2/API_SendEmail.html),
+//! as raw MIME messages so that attachments go along.
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::MailerTransport;
+use crate::{
+    config,
+    errors::Error,
+    mailer::{Email, Result},
+};
+
+const SERVICE: &str = "ses";
+const PATH: &str = "/v2/email/outbound-emails";
+
+/// The Amazon SES API transport.
+#[derive(Debug, Clone)]
+pub struct Ses {
+    client: reqwest::Client,
+    endpoint: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    region: String,
+}
+
+impl Ses {
+    #[must_use]
+    pub fn new(config: &config::SesMailer) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: config
+                .endpoint
+                .clone()
+                .unwrap_or_else(|| format!("https://email.{}.amazonaws.com", config.region)),
+            access_key_id: config.access_key_id.clone(),
+            secret_access_key: config.secret_access_key.clone(),
+            session_token: config.session_token.clone(),
+            region: config.region.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl MailerTransport for Ses {
+    async fn send(&self, email: &Email) -> Result<()> {
+        let message = super::message(email)?;
+        // a raw message goes to the recipients of its envelope, which unlike
+        // its headers include the bcc ones
+        let recipients = message
+            .envelope()
+            .to()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let body = json!({
+            "Destination": { "ToAddresses": recipients },
+            "Content": { "Raw": { "Data": STANDARD.encode(message.formatted()) } },
+        })
+        .to_string();
+
+        let url = format!("{}{PATH}", self.endpoint.trim_end_matches('/'));
+        let parsed = reqwest::Url::parse(&url).map_err(Error::wrap)?;
+        let host = match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(Error::Message(format!("invalid SES endpoint `{url}`"))),
+        };
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        let mut headers = vec![
+            ("content-type", "application/json"),
+            ("host", host.as_str()),
+            ("x-amz-date", amz_date.as_str()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.as_str()));
+        }
+        let signing = Signing {
+            access_key_id: &self.access_key_id,
+            secret_access_key: &self.secret_access_key,
+            region: &self.region,
+            service: SERVICE,
+        };
+        let authorization =
+            signing.authorization("POST", PATH, &headers, body.as_bytes(), &amz_date);
+
+        let mut req = self
+            .client
+            .post(&url)
+            .header("authorization", authorization)
+            .body(body);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            req = req.header(*name, *value);
+        }
+        let res = req.send().await.map_err(Error::wrap)?;
+
+        let status = res.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let body = res.text().await.unwrap_or_default();
+            Err(Error::Message(format!(
+                "ses responded with {status}: {body}"
+            )))
+        }
+    }
+}
+
+/// Signs requests with [AWS Signature Version 4](https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html).
+struct Signing<'a> {
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    region: &'a str,
+    service: &'a str,
+}
+
+impl Signing<'_> {
+    /// The `authorization` header of a request without a query string.
+    /// `headers` are the ones to sign, by lowercase name and sorted, with
+    /// `host` and `x-amz-date` among them. `amz_date` is the latter, e.g.
+    /// `20150830T123600Z`.
+    fn authorization(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+        amz_date: &str,
+    ) -> String {
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect::<String>();
+        let canonical_request = format!(
+            "{method}\n{path}\n\n{canonical_headers}\n{signed_headers}\n{:x}",
+            Sha256::digest(body)
+        );
+
+        let date = &amz_date[..8];
+        let scope = format!("{date}/{}/{}/aws4_request", self.region, self.service);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{:x}",
+            Sha256::digest(canonical_request.as_bytes())
+        );
+
+        let key = [date, self.region, self.service, "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_access_key).into_bytes(),
+                |key, part| hmac(&key, part.as_bytes()),
+            );
+        let signature = hmac(&key, string_to_sign.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+             Signature={signature}",
+            self.access_key_id
+        )
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes a key of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `get-vanilla` of the AWS Signature Version 4 test suite
+    #[test]
+    fn can_sign_request() {
+        let signing = Signing {
+            access_key_id: "AKIDEXAMPLE",
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            region: "us-east-1",
+            service: "service",
+        };
+
+        assert_eq!(
+            signing.authorization(
+                "GET",
+                "/",
+                &[
+                    ("host", "example.amazonaws.com"),
+                    ("x-amz-date", "20150830T123600Z")
+                ],
+                b"",
+                "20150830T123600Z",
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+}
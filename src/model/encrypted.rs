@@ -0,0 +1,47 @@
+//! # Encrypted Columns
+//!
+//! Marks values of columns that the application encrypts before they are
+//! stored, such as PII. The wrapper does not encrypt anything by itself, it
+//! keeps ciphertext from being mixed up with plain values and from showing up
+//! in logs.
+
+use std::fmt;
+
+/// A value encrypted by the application.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Encrypted<T>(T);
+
+impl<T> Encrypted<T> {
+    /// Wraps an already encrypted value.
+    pub const fn new(ciphertext: T) -> Self {
+        Self(ciphertext)
+    }
+
+    /// The encrypted value, as stored in the column.
+    pub const fn as_inner(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwraps the encrypted value, as stored in the column.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for Encrypted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Encrypted(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn does_not_debug_print_the_value() {
+        let value = Encrypted::new("ciphertext".to_string());
+        assert_eq!(format!("{value:?}"), "Encrypted(..)");
+        assert_eq!(value.into_inner(), "ciphertext");
+    }
+}
@@ -2,9 +2,15 @@
 //!
 //! Useful when using `sea_orm` and want to propagate errors
 
+mod encrypted;
 pub mod query;
+mod range;
+mod soft_delete;
 use async_trait::async_trait;
+pub use encrypted::Encrypted;
+pub use range::{ParseRangeError, TstzRange};
 use sea_orm::DatabaseConnection;
+pub use soft_delete::SoftDeletable;
 
 use crate::validation::ModelValidationErrors;
 
@@ -0,0 +1,143 @@
+//! # Timestamp Ranges
+//!
+//! Reads the postgres text form of a `tstzrange` column, e.g. the validity
+//! period `["2024-01-01 00:00:00+00","2024-02-01 00:00:00+00")`, to tell
+//! whether a timestamp falls within it.
+
+use std::{ops::Bound, str::FromStr};
+
+use chrono::{DateTime, FixedOffset};
+
+/// A `tstzrange` value, whose bounds are either included, excluded or
+/// missing for an unbounded side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TstzRange {
+    lower: Bound<DateTime<FixedOffset>>,
+    upper: Bound<DateTime<FixedOffset>>,
+    empty: bool,
+}
+
+/// A value that is not a `tstzrange` in the postgres text form.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("`{0}` is not a tstzrange")]
+pub struct ParseRangeError(String);
+
+impl TstzRange {
+    /// The range of the timestamps from `lower`, included, up to `upper`,
+    /// excluded, the default bounds of a postgres range.
+    #[must_use]
+    pub const fn new(lower: DateTime<FixedOffset>, upper: DateTime<FixedOffset>) -> Self {
+        Self {
+            lower: Bound::Included(lower),
+            upper: Bound::Excluded(upper),
+            empty: false,
+        }
+    }
+
+    /// Whether `t` falls within the range
+    #[must_use]
+    pub fn contains(&self, t: DateTime<FixedOffset>) -> bool {
+        let above_lower = match self.lower {
+            Bound::Included(lower) => lower <= t,
+            Bound::Excluded(lower) => lower < t,
+            Bound::Unbounded => true,
+        };
+        let below_upper = match self.upper {
+            Bound::Included(upper) => t <= upper,
+            Bound::Excluded(upper) => t < upper,
+            Bound::Unbounded => true,
+        };
+        !self.empty && above_lower && below_upper
+    }
+}
+
+/// A bound of the range, `None` when the side is unbounded
+fn parse_bound(bound: &str) -> Result<Option<DateTime<FixedOffset>>, ()> {
+    let bound = bound.trim().trim_matches('"');
+    if bound.is_empty() || bound.ends_with("infinity") {
+        return Ok(None);
+    }
+    // postgres leaves out the minutes of whole hour offsets, e.g. `+00`
+    DateTime::parse_from_str(bound, "%Y-%m-%d %H:%M:%S%.f%#z")
+        .or_else(|_| DateTime::parse_from_rfc3339(bound))
+        .map(Some)
+        .map_err(|_| ())
+}
+
+impl FromStr for TstzRange {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseRangeError(s.to_string());
+        let range = s.trim();
+        if range == "empty" {
+            return Ok(Self {
+                lower: Bound::Unbounded,
+                upper: Bound::Unbounded,
+                empty: true,
+            });
+        }
+        let mut chars = range.chars();
+        let (Some(open), Some(close)) = (chars.next(), chars.next_back()) else {
+            return Err(err());
+        };
+        let (lower, upper) = chars.as_str().split_once(',').ok_or_else(err)?;
+        let lower = match (open, parse_bound(lower).map_err(|()| err())?) {
+            (_, None) => Bound::Unbounded,
+            ('[', Some(lower)) => Bound::Included(lower),
+            ('(', Some(lower)) => Bound::Excluded(lower),
+            _ => return Err(err()),
+        };
+        let upper = match (close, parse_bound(upper).map_err(|()| err())?) {
+            (_, None) => Bound::Unbounded,
+            (']', Some(upper)) => Bound::Included(upper),
+            (')', Some(upper)) => Bound::Excluded(upper),
+            _ => return Err(err()),
+        };
+        Ok(Self {
+            lower,
+            upper,
+            empty: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(t: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(t).unwrap()
+    }
+
+    #[test]
+    fn can_check_containment() {
+        let range: TstzRange = r#"["2024-01-01 00:00:00+00","2024-02-01 00:00:00+00")"#
+            .parse()
+            .unwrap();
+        assert!(range.contains(at("2024-01-01T00:00:00Z")));
+        assert!(range.contains(at("2024-01-15T12:30:00+02:00")));
+        assert!(!range.contains(at("2024-02-01T00:00:00Z")));
+        assert!(!range.contains(at("2023-12-31T23:59:59Z")));
+        assert_eq!(
+            range,
+            TstzRange::new(at("2024-01-01T00:00:00Z"), at("2024-02-01T00:00:00Z"))
+        );
+
+        let open_ended: TstzRange = r#"("2024-01-01 00:00:00.5+05:30",)"#.parse().unwrap();
+        assert!(!open_ended.contains(at("2024-01-01T00:00:00.5+05:30")));
+        assert!(open_ended.contains(at("2999-01-01T00:00:00Z")));
+
+        let empty: TstzRange = "empty".parse().unwrap();
+        assert!(!empty.contains(at("2024-01-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn rejects_other_values() {
+        assert_eq!(
+            "2024-01-01".parse::<TstzRange>(),
+            Err(ParseRangeError("2024-01-01".to_string()))
+        );
+        assert!(r#"["yesterday","today")"#.parse::<TstzRange>().is_err());
+    }
+}
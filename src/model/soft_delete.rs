@@ -0,0 +1,170 @@
+//! # Soft Deletes
+//!
+//! Marks rows as deleted with a `deleted_at` timestamp instead of removing
+//! them, so that they can be restored. Models generated with
+//! `--soft-delete` implement [`SoftDeletable`] on their entity.
+
+use async_trait::async_trait;
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, Select, Value,
+};
+
+/// An entity whose rows are soft deleted, by setting their `deleted_at`.
+///
+/// ```rust,ignore
+/// impl SoftDeletable for Entity {
+///     fn deleted_at() -> Column {
+///         Column::DeletedAt
+///     }
+/// }
+///
+/// let post = Entity::soft_delete(&ctx.db, post).await?;
+/// let posts = Entity::find_active().all(&ctx.db).await?;
+/// ```
+#[async_trait]
+pub trait SoftDeletable: EntityTrait {
+    /// The nullable timestamp column marking deleted rows
+    fn deleted_at() -> Self::Column;
+
+    /// Select the rows not soft deleted
+    #[must_use]
+    fn find_active() -> Select<Self> {
+        Self::find().filter(Self::deleted_at().is_null())
+    }
+
+    /// Select the soft deleted rows only
+    #[must_use]
+    fn find_deleted() -> Select<Self> {
+        Self::find().filter(Self::deleted_at().is_not_null())
+    }
+
+    /// Soft delete a row, setting its `deleted_at` to now
+    ///
+    /// # Errors
+    ///
+    /// When the row cannot be updated
+    async fn soft_delete<C>(db: &C, model: Self::Model) -> Result<Self::Model, DbErr>
+    where
+        C: ConnectionTrait,
+        Self::Model: IntoActiveModel<Self::ActiveModel>,
+        Self::ActiveModel: Send,
+    {
+        let now = chrono::Utc::now().fixed_offset();
+        set_deleted_at::<Self, C>(db, model, Some(now).into()).await
+    }
+
+    /// Restore a soft deleted row, clearing its `deleted_at`
+    ///
+    /// # Errors
+    ///
+    /// When the row cannot be updated
+    async fn restore<C>(db: &C, model: Self::Model) -> Result<Self::Model, DbErr>
+    where
+        C: ConnectionTrait,
+        Self::Model: IntoActiveModel<Self::ActiveModel>,
+        Self::ActiveModel: Send,
+    {
+        let none: Option<chrono::DateTime<chrono::FixedOffset>> = None;
+        set_deleted_at::<Self, C>(db, model, none.into()).await
+    }
+}
+
+async fn set_deleted_at<E, C>(db: &C, model: E::Model, value: Value) -> Result<E::Model, DbErr>
+where
+    E: SoftDeletable,
+    C: ConnectionTrait,
+    E::Model: IntoActiveModel<E::ActiveModel>,
+    E::ActiveModel: ActiveModelBehavior + Send,
+{
+    let mut item = model.into_active_model();
+    item.set(E::deleted_at(), value);
+    item.update(db).await
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{entity::prelude::*, ConnectOptions, Database};
+    use sea_orm_migration::SchemaManager;
+
+    use super::*;
+    use crate::schema::{create_table, ColType};
+
+    mod posts {
+        use sea_orm::entity::prelude::*;
+
+        #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+        #[sea_orm(table_name = "posts")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub title: String,
+            pub deleted_at: Option<DateTimeWithTimeZone>,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    impl SoftDeletable for posts::Entity {
+        fn deleted_at() -> posts::Column {
+            posts::Column::DeletedAt
+        }
+    }
+
+    #[tokio::test]
+    async fn can_soft_delete_and_restore() {
+        let mut opt = ConnectOptions::new("sqlite::memory:");
+        opt.max_connections(1);
+        let db = Database::connect(opt).await.unwrap();
+        create_table(
+            &SchemaManager::new(&db),
+            "posts",
+            &[
+                ("id", ColType::PkAuto),
+                ("title", ColType::String),
+                ("deleted_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        for title in ["kept", "removed"] {
+            posts::ActiveModel {
+                title: sea_orm::Set(title.to_string()),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+        let titles =
+            |posts: Vec<posts::Model>| posts.into_iter().map(|post| post.title).collect::<Vec<_>>();
+
+        let post = posts::Entity::find_by_id(2)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        let post = posts::Entity::soft_delete(&db, post).await.unwrap();
+        assert!(post.deleted_at.is_some());
+        assert_eq!(
+            titles(posts::Entity::find_active().all(&db).await.unwrap()),
+            ["kept"]
+        );
+        assert_eq!(
+            titles(posts::Entity::find_deleted().all(&db).await.unwrap()),
+            ["removed"]
+        );
+        assert_eq!(posts::Entity::find().count(&db).await.unwrap(), 2);
+
+        let post = posts::Entity::restore(&db, post).await.unwrap();
+        assert_eq!(post.deleted_at, None);
+        assert_eq!(
+            titles(posts::Entity::find_active().all(&db).await.unwrap()),
+            ["kept", "removed"]
+        );
+    }
+}
@@ -27,10 +27,10 @@ pub use crate::controller::extractor::{
     validate::{JsonValidate, JsonValidateWithMessage},
 };
 #[cfg(feature = "with-db")]
-pub use crate::model::{query, Authenticable, ModelError, ModelResult};
+pub use crate::model::{query, Authenticable, ModelError, ModelResult, SoftDeletable};
 pub use crate::{
     app::{AppContext, Initializer},
-    bgworker::{BackgroundWorker, Queue},
+    bgworker::{BackgroundWorker, Queue, RetryPolicy},
     controller::{
         bad_request, format,
         middleware::{
@@ -5,16 +5,22 @@ use std::{
     collections::HashMap,
     fmt, io,
     path::{Path, PathBuf},
-    sync::OnceLock,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
     time::{Duration, Instant},
 };
 
+use chrono::{DateTime, SubsecRound, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_variant::to_variant_name;
 use tokio_cron_scheduler::{JobScheduler, JobSchedulerError};
 use uuid::Uuid;
 
-use crate::{app::Hooks, environment::Environment, task::Tasks};
+use crate::{app::Hooks, bgworker::Queue, environment::Environment, task::Tasks};
 
 static RE_IS_CRON_SYNTAX: OnceLock<Regex> = OnceLock::new();
 
@@ -40,9 +46,15 @@ pub enum Error {
     #[error("Invalid cron {cron}. err: '{}'", error.as_display())]
     InvalidCronSyntax { cron: String, error: String },
 
+    #[error("the `queue` lock needs a queue provider, with workers in `BackgroundQueue` mode")]
+    QueueNotConfigured,
+
     #[error(transparent)]
     Question(#[from] JobSchedulerError),
 
+    #[error(transparent)]
+    Queue(Box<crate::Error>),
+
     #[error(transparent)]
     IO(#[from] std::io::Error),
 }
@@ -59,6 +71,17 @@ pub struct Config {
     /// The default output setting for the jobs.
     #[serde(default)]
     pub output: Output,
+    /// Where the job runs are coordinated.
+    #[serde(default)]
+    pub lock: Lock,
+    /// Seconds a run can hold the lock of its job with `no_overlap`, past which
+    /// it is considered dead.
+    #[serde(default = "default_lock_timeout")]
+    pub lock_timeout: u64,
+}
+
+const fn default_lock_timeout() -> u64 {
+    60 * 60
 }
 
 /// Representing a single job in the scheduler.
@@ -72,6 +95,9 @@ pub struct Job {
     pub shell: bool,
     #[serde(default)]
     pub run_on_start: bool,
+    /// Skips a run while the previous one is still in progress.
+    #[serde(default)]
+    pub no_overlap: bool,
     #[serde(rename = "schedule")]
     /// The cron expression defining the job's schedule.
     ///
@@ -123,6 +149,9 @@ pub struct Scheduler {
     binary_path: PathBuf,
     default_output: Output,
     environment: Environment,
+    lock: Lock,
+    lock_timeout: Duration,
+    queue: Option<Arc<Queue>>,
 }
 
 /// Specification used to filter all scheduler job with the given Spec.
@@ -144,6 +173,90 @@ pub enum Output {
     STDOUT,
 }
 
+/// Where the scheduler coordinates the job runs.
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Lock {
+    /// Within this instance: every instance runs every job, and runs are not
+    /// recorded.
+    #[default]
+    #[serde(rename = "local")]
+    Local,
+    /// Through the queue provider (Redis, Postgres or `SQLite`): each run of a
+    /// job is claimed by a single instance, and recorded.
+    #[serde(rename = "queue")]
+    Queue,
+}
+
+/// The status of a job run.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RunStatus {
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "succeeded")]
+    Succeeded,
+    #[serde(rename = "failed")]
+    Failed,
+    /// The previous run of a job with `no_overlap` was still in progress.
+    #[serde(rename = "skipped")]
+    Skipped,
+}
+
+impl std::str::FromStr for RunStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(Self::Running),
+            "succeeded" => Ok(Self::Succeeded),
+            "failed" => Ok(Self::Failed),
+            "skipped" => Ok(Self::Skipped),
+            _ => Err(format!("Invalid status: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        to_variant_name(self).expect("only enum supported").fmt(f)
+    }
+}
+
+/// A run of a scheduled job, as recorded with the `queue` lock.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Run {
+    pub id: String,
+    pub job_name: String,
+    /// The tick of the schedule the run is for.
+    pub scheduled_at: DateTime<Utc>,
+    pub status: RunStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+impl Run {
+    /// A run of `job_name` starting now.
+    #[must_use]
+    pub fn new(job_name: &str, scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            job_name: job_name.to_string(),
+            scheduled_at,
+            status: RunStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            error: None,
+        }
+    }
+
+    /// Ends the run with the given status.
+    pub fn finish(&mut self, status: RunStatus, error: Option<String>) {
+        self.status = status;
+        self.finished_at = Some(Utc::now());
+        self.error = error;
+    }
+}
+
 /// Structure representing the job command.
 #[derive(Clone, Debug)]
 pub struct JobDescription {
@@ -259,9 +372,48 @@ impl Scheduler {
             binary_path: std::env::current_exe()?,
             default_output: data.output.clone(),
             environment: environment.clone(),
+            lock: data.lock.clone(),
+            lock_timeout: Duration::from_secs(data.lock_timeout),
+            queue: None,
         })
     }
 
+    /// Sets the queue provider coordinating the job runs with the `queue` lock.
+    #[must_use]
+    pub fn with_queue(self, queue: Arc<Queue>) -> Self {
+        Self {
+            queue: Some(queue),
+            ..self
+        }
+    }
+
+    /// The latest recorded runs of the scheduler's jobs, most recent first.
+    ///
+    /// # Errors
+    ///
+    /// When the scheduler does not use the `queue` lock, or the runs could not
+    /// be read
+    pub async fn history(&self, limit: usize) -> Result<Vec<Run>> {
+        let queue = self.lock_queue()?.ok_or(Error::QueueNotConfigured)?;
+        let mut runs = queue
+            .scheduled_runs(limit)
+            .await
+            .map_err(|err| Error::Queue(Box::new(err)))?;
+        runs.retain(|run| self.jobs.contains_key(&run.job_name));
+        Ok(runs)
+    }
+
+    fn lock_queue(&self) -> Result<Option<Arc<Queue>>> {
+        match self.lock {
+            Lock::Local => Ok(None),
+            Lock::Queue => self
+                .queue
+                .clone()
+                .map(Some)
+                .ok_or(Error::QueueNotConfigured),
+        }
+    }
+
     /// Filters the scheduler's jobs based on the provided specification.
     #[must_use]
     pub fn by_spec(self, include_jobs: &Spec) -> Self {
@@ -292,11 +444,29 @@ impl Scheduler {
     ///
     /// When could not add job to the scheduler
     pub async fn run(self) -> Result<()> {
+        let queue = self.lock_queue()?;
+        if let Some(queue) = &queue {
+            queue
+                .setup()
+                .await
+                .map_err(|err| Error::Queue(Box::new(err)))?;
+        }
+
         let mut sched = JobScheduler::new().await?;
 
         for (job_name, job) in &self.jobs {
-            let job_description =
-                job.prepare_command(&self.binary_path, &self.default_output, &self.environment);
+            let scheduled_job = Arc::new(ScheduledJob {
+                name: job_name.clone(),
+                description: job.prepare_command(
+                    &self.binary_path,
+                    &self.default_output,
+                    &self.environment,
+                ),
+                no_overlap: job.no_overlap,
+                running: AtomicBool::new(false),
+                queue: queue.clone(),
+                lock_timeout: self.lock_timeout,
+            });
 
             let cron_syntax = if get_re_is_cron_syntax().is_match(&job.cron) {
                 job.cron.clone()
@@ -310,32 +480,35 @@ impl Scheduler {
             };
 
             if job.run_on_start {
-                let job_description = job_description.clone();
-                let job_name = job_name.clone();
+                let scheduled_job = scheduled_job.clone();
                 sched
                     .add(tokio_cron_scheduler::Job::new_one_shot_async(
                         Duration::from_secs(0),
                         move |uuid, _l| {
-                            let job_description = job_description.clone();
-                            let job_name = job_name.clone();
-                            Box::pin(async move {
-                                execute_job(job_name.as_str(), uuid, &job_description);
-                            })
+                            Box::pin(
+                                scheduled_job
+                                    .clone()
+                                    .tick(uuid, Utc::now().trunc_subsecs(0)),
+                            )
                         },
                     )?)
                     .await?;
             }
 
-            let job_name = job_name.clone();
+            let schedule =
+                cron::Schedule::from_str(&cron_syntax).map_err(|err| Error::InvalidCronSyntax {
+                    cron: job.cron.clone(),
+                    error: err.to_string(),
+                })?;
             sched
                 .add(tokio_cron_scheduler::Job::new_async(
                     cron_syntax.as_str(),
                     move |uuid, mut _l| {
-                        let job_description = job_description.clone();
-                        let job_name = job_name.clone();
-                        Box::pin(async move {
-                            execute_job(job_name.as_str(), uuid, &job_description);
-                        })
+                        Box::pin(
+                            scheduled_job
+                                .clone()
+                                .tick(uuid, fire_time(&schedule, Utc::now())),
+                        )
                     },
                 )?)
                 .await?;
@@ -350,7 +523,105 @@ impl Scheduler {
     }
 }
 
-fn execute_job(job_name: &str, uuid: Uuid, job_description: &JobDescription) {
+/// How late after its fire time a job may start and still claim it
+const FIRE_TIME_LOOKBACK: chrono::Duration = chrono::Duration::minutes(1);
+
+/// How far ahead of an instance's clock another one may fire a job
+const CLOCK_SKEW: chrono::Duration = chrono::Duration::milliseconds(500);
+
+/// The time of the schedule's tick a job started at `now` runs for: its
+/// latest fire time, so that the instances of the app claim the same tick
+/// however late each starts the job. Falls back to `now`, to the second,
+/// when the job started too late to tell.
+fn fire_time(schedule: &cron::Schedule, now: DateTime<Utc>) -> DateTime<Utc> {
+    schedule
+        .after(&(now - FIRE_TIME_LOOKBACK))
+        .take_while(|time| *time <= now + CLOCK_SKEW)
+        .last()
+        .unwrap_or_else(|| now.trunc_subsecs(0))
+}
+
+/// A job of the running scheduler
+struct ScheduledJob {
+    name: String,
+    description: JobDescription,
+    no_overlap: bool,
+    running: AtomicBool,
+    queue: Option<Arc<Queue>>,
+    lock_timeout: Duration,
+}
+
+impl ScheduledJob {
+    /// Runs the job for the tick of its schedule at `scheduled_at`, unless it
+    /// overlaps a run in progress with `no_overlap`, or another instance
+    /// claimed the tick.
+    async fn tick(self: Arc<Self>, uuid: Uuid, scheduled_at: DateTime<Utc>) {
+        if self.no_overlap && self.running.swap(true, Ordering::AcqRel) {
+            tracing::info!(
+                job_name = self.name,
+                "previous run still in progress, skipping scheduler job"
+            );
+            return;
+        }
+        self.clone().run(uuid, scheduled_at).await;
+        if self.no_overlap {
+            self.running.store(false, Ordering::Release);
+        }
+    }
+
+    async fn run(self: Arc<Self>, uuid: Uuid, scheduled_at: DateTime<Utc>) {
+        let mut run = None;
+        if let Some(queue) = &self.queue {
+            match queue
+                .claim_scheduled_run(&self.name, scheduled_at, self.no_overlap, self.lock_timeout)
+                .await
+            {
+                Ok(Some(claimed)) => run = Some(claimed),
+                Ok(None) => {
+                    tracing::debug!(
+                        job_name = self.name,
+                        "run claimed by another instance or overlapping, skipping scheduler job"
+                    );
+                    return;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        job_name = self.name,
+                        error = %err,
+                        "could not claim scheduler job run"
+                    );
+                    return;
+                }
+            }
+        }
+
+        let job = self.clone();
+        let result =
+            tokio::task::spawn_blocking(move || execute_job(&job.name, uuid, &job.description))
+                .await
+                .unwrap_or_else(|err| Err(err.to_string()));
+
+        if let (Some(queue), Some(mut run)) = (&self.queue, run) {
+            match result {
+                Ok(()) => run.finish(RunStatus::Succeeded, None),
+                Err(err) => run.finish(RunStatus::Failed, Some(err)),
+            }
+            if let Err(err) = queue.finish_scheduled_run(&run).await {
+                tracing::error!(
+                    job_name = self.name,
+                    error = %err,
+                    "could not record scheduler job run"
+                );
+            }
+        }
+    }
+}
+
+fn execute_job(
+    job_name: &str,
+    uuid: Uuid,
+    job_description: &JobDescription,
+) -> std::result::Result<(), String> {
     let task_span = tracing::span!(
         tracing::Level::DEBUG,
         "run_job",
@@ -366,6 +637,11 @@ fn execute_job(job_name: &str, uuid: Uuid, job_description: &JobDescription) {
                 status_code = output.status.code(),
                 "execute scheduler job finished"
             );
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(format!("command exited with {}", output.status))
+            }
         }
         Err(err) => {
             tracing::error!(
@@ -373,6 +649,7 @@ fn execute_job(job_name: &str, uuid: Uuid, job_description: &JobDescription) {
                 error = %err,
                 "failed to execute scheduler job in sub process"
             );
+            Err(err.to_string())
         }
     }
 }
@@ -497,6 +774,7 @@ jobs:
             run: run.to_string(),
             shell,
             run_on_start: false,
+            no_overlap: false,
             cron: "*/5 * * * * *".to_string(),
             tags: None,
             output: None,
@@ -557,6 +835,7 @@ jobs:
                     ),
                     shell: true,
                     run_on_start: false,
+                    no_overlap: false,
                     cron: "run every 1 second".to_string(),
                     tags: None,
                     output: None,
@@ -571,6 +850,7 @@ jobs:
                     ),
                     shell: true,
                     run_on_start: false,
+                    no_overlap: false,
                     cron: "* * * * * ? *".to_string(),
                     tags: None,
                     output: None,
@@ -585,6 +865,7 @@ jobs:
                     ),
                     shell: true,
                     run_on_start: true,
+                    no_overlap: false,
                     cron: "0 0 * * * * *".to_string(),
                     tags: None,
                     output: None,
@@ -621,4 +902,213 @@ jobs:
             1
         );
     }
+
+    fn scheduled_job(
+        run: String,
+        no_overlap: bool,
+        queue: Option<Arc<Queue>>,
+    ) -> Arc<ScheduledJob> {
+        let job = Job {
+            run,
+            shell: true,
+            run_on_start: false,
+            no_overlap,
+            cron: "* * * * * *".to_string(),
+            tags: None,
+            output: None,
+        };
+        Arc::new(ScheduledJob {
+            name: "test".to_string(),
+            description: job.prepare_command(
+                PathBuf::from("[BIN_PATH]").as_path(),
+                &Output::Silent,
+                &Environment::Test,
+            ),
+            no_overlap,
+            running: AtomicBool::new(false),
+            queue,
+            lock_timeout: Duration::from_secs(60),
+        })
+    }
+
+    fn count_lines(path: &Path) -> usize {
+        std::fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[tokio::test]
+    pub async fn can_skip_overlapping_run() {
+        let tree_fs = TreeBuilder::default()
+            .drop(true)
+            .add("scheduler.txt", "")
+            .create()
+            .unwrap();
+        let file = tree_fs.root.join("scheduler.txt");
+        let job = scheduled_job(
+            format!("sleep 1 && echo loco >> {}", file.display()),
+            true,
+            None,
+        );
+
+        let now = Utc::now().trunc_subsecs(0);
+        tokio::join!(
+            job.clone().tick(Uuid::new_v4(), now),
+            job.clone()
+                .tick(Uuid::new_v4(), now + chrono::Duration::seconds(1)),
+        );
+        assert_eq!(count_lines(&file), 1);
+
+        job.clone().tick(Uuid::new_v4(), now).await;
+        assert_eq!(count_lines(&file), 2);
+    }
+
+    #[cfg(feature = "bg_sqlt")]
+    async fn sqlite_queue(root: &Path) -> Arc<Queue> {
+        let queue = crate::bgworker::sqlt::create_provider(&crate::config::SqliteQueueConfig {
+            uri: format!("sqlite://{}?mode=rwc", root.join("queue.sqlite").display()),
+            dangerously_flush: false,
+            enable_logging: false,
+            max_connections: 1,
+            min_connections: 1,
+            connect_timeout: 500,
+            idle_timeout: 500,
+            poll_interval_sec: 1,
+            num_workers: 1,
+        })
+        .await
+        .expect("create sqlite queue");
+        queue.setup().await.expect("setup sqlite queue");
+        Arc::new(queue)
+    }
+
+    #[cfg(feature = "bg_sqlt")]
+    #[tokio::test]
+    pub async fn can_run_tick_once_with_queue_lock() {
+        let tree_fs = TreeBuilder::default()
+            .drop(true)
+            .add("scheduler.txt", "")
+            .create()
+            .unwrap();
+        let file = tree_fs.root.join("scheduler.txt");
+        let queue = sqlite_queue(&tree_fs.root).await;
+        let run = format!("echo loco >> {}", file.display());
+        let instance_1 = scheduled_job(run.clone(), false, Some(queue.clone()));
+        let instance_2 = scheduled_job(run, false, Some(queue.clone()));
+
+        let now = Utc::now().trunc_subsecs(0);
+        tokio::join!(
+            instance_1.tick(Uuid::new_v4(), now),
+            instance_2.tick(Uuid::new_v4(), now),
+        );
+        assert_eq!(count_lines(&file), 1);
+
+        let runs = queue.scheduled_runs(10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].job_name, "test");
+        assert_eq!(runs[0].scheduled_at, now);
+        assert_eq!(runs[0].status, RunStatus::Succeeded);
+        assert!(runs[0].finished_at.is_some());
+    }
+
+    #[cfg(feature = "bg_sqlt")]
+    #[tokio::test]
+    pub async fn can_skip_run_overlapping_another_instance() {
+        let tree_fs = TreeBuilder::default()
+            .drop(true)
+            .add("scheduler.txt", "")
+            .create()
+            .unwrap();
+        let file = tree_fs.root.join("scheduler.txt");
+        let queue = sqlite_queue(&tree_fs.root).await;
+        let run = format!("sleep 1 && echo loco >> {}", file.display());
+        let instance_1 = scheduled_job(run.clone(), true, Some(queue.clone()));
+        let instance_2 = scheduled_job(run, true, Some(queue.clone()));
+
+        let now = Utc::now().trunc_subsecs(0);
+        tokio::join!(instance_1.tick(Uuid::new_v4(), now), async {
+            time::sleep(Duration::from_millis(300)).await;
+            instance_2
+                .tick(Uuid::new_v4(), now + chrono::Duration::seconds(1))
+                .await;
+        });
+        assert_eq!(count_lines(&file), 1);
+
+        let mut statuses: Vec<String> = queue
+            .scheduled_runs(10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|run| run.status.to_string())
+            .collect();
+        statuses.sort();
+        assert_eq!(statuses, vec!["skipped", "succeeded"]);
+    }
+
+    #[test]
+    fn can_take_fire_time() {
+        let schedule = cron::Schedule::from_str("0 */5 * * * *").unwrap();
+        let slot = DateTime::parse_from_rfc3339("2024-01-01T10:05:00Z")
+            .unwrap()
+            .to_utc();
+
+        for late in [0, 20, 350, 4_000] {
+            assert_eq!(
+                fire_time(&schedule, slot + chrono::Duration::milliseconds(late)),
+                slot
+            );
+        }
+        // another instance's clock may be a little behind
+        assert_eq!(
+            fire_time(&schedule, slot - chrono::Duration::milliseconds(200)),
+            slot
+        );
+        let late = slot + chrono::Duration::minutes(2) + chrono::Duration::milliseconds(300);
+        assert_eq!(fire_time(&schedule, late), late.trunc_subsecs(0));
+    }
+
+    #[cfg(feature = "bg_sqlt")]
+    #[tokio::test]
+    pub async fn can_claim_fire_time_once_when_started_apart() {
+        let tree_fs = TreeBuilder::default()
+            .drop(true)
+            .add("scheduler.txt", "")
+            .create()
+            .unwrap();
+        let file = tree_fs.root.join("scheduler.txt");
+        let queue = sqlite_queue(&tree_fs.root).await;
+        let run = format!("echo loco >> {}", file.display());
+        let instance_1 = scheduled_job(run.clone(), false, Some(queue.clone()));
+        let instance_2 = scheduled_job(run, false, Some(queue.clone()));
+
+        // the instances start the job a few hundred ms apart, on either side
+        // of a second
+        let schedule = cron::Schedule::from_str("0 */5 * * * *").unwrap();
+        let slot = DateTime::parse_from_rfc3339("2024-01-01T10:05:00Z")
+            .unwrap()
+            .to_utc();
+        tokio::join!(
+            instance_1.tick(
+                Uuid::new_v4(),
+                fire_time(&schedule, slot - chrono::Duration::milliseconds(200))
+            ),
+            instance_2.tick(
+                Uuid::new_v4(),
+                fire_time(&schedule, slot + chrono::Duration::milliseconds(250))
+            ),
+        );
+        assert_eq!(count_lines(&file), 1);
+
+        let runs = queue.scheduled_runs(10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].scheduled_at, slot);
+    }
+
+    #[tokio::test]
+    pub async fn cannot_run_queue_lock_without_queue() {
+        let (mut scheduler, _tree) = setup_scheduler_config();
+        scheduler.lock = Lock::Queue;
+        assert!(matches!(
+            scheduler.run().await,
+            Err(Error::QueueNotConfigured)
+        ));
+    }
 }
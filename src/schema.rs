@@ -1,8 +1,9 @@
 use heck::ToSnakeCase;
 use sea_orm::{
     sea_query::{
-        Alias, ColumnDef, Expr, Index, IntoIden, PgInterval, Table, TableAlterStatement,
-        TableCreateStatement, TableForeignKey,
+        Alias, ColumnDef, Expr, Index, IndexCreateStatement, IntoIden, PgInterval,
+        PostgresQueryBuilder, SqliteQueryBuilder, Table, TableAlterStatement, TableCreateStatement,
+        TableForeignKey,
     },
     ColumnType, ConnectionTrait, DbErr, ForeignKeyAction,
 };
@@ -154,10 +155,83 @@ async fn check_enum_exists(m: &SchemaManager<'_>, enum_name: &str) -> Result<boo
     }
 }
 
+/// Options of the sequence behind an identity primary key, the unset ones
+/// keep the database defaults.
+/// ```ignore
+/// ColType::PkIdentityWithSequence(IdentitySequence::new().start(1000).increment(10))
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IdentitySequence {
+    start: Option<i64>,
+    increment: Option<i64>,
+    cache: Option<i64>,
+}
+
+impl IdentitySequence {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            start: None,
+            increment: None,
+            cache: None,
+        }
+    }
+
+    /// The first value, `START WITH`
+    #[must_use]
+    pub const fn start(mut self, start: i64) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// The step between values, `INCREMENT BY`
+    #[must_use]
+    pub const fn increment(mut self, increment: i64) -> Self {
+        self.increment = Some(increment);
+        self
+    }
+
+    /// How many values are preallocated, `CACHE`
+    #[must_use]
+    pub const fn cache(mut self, cache: i64) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The sequence options of the identity clause, e.g.
+    /// ` (START WITH 1000 INCREMENT BY 10)`
+    fn clause(&self) -> String {
+        let options = [
+            self.start.map(|v| format!("START WITH {v}")),
+            self.increment.map(|v| format!("INCREMENT BY {v}")),
+            self.cache.map(|v| format!("CACHE {v}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        if options.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", options.join(" "))
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ColType {
     PkAuto,
+    /// Postgres `GENERATED ALWAYS AS IDENTITY` primary key
+    PkIdentity,
+    /// Postgres `GENERATED BY DEFAULT AS IDENTITY` primary key
+    PkIdentityByDefault,
+    /// [`ColType::PkIdentity`] with the options of its sequence
+    PkIdentityWithSequence(IdentitySequence),
+    /// [`ColType::PkIdentityByDefault`] with the options of its sequence
+    PkIdentityByDefaultWithSequence(IdentitySequence),
     PkUuid,
+    /// An integer primary key without a default, e.g. the foreign key of a
+    /// one-to-one sharing the primary key of the referenced table
+    PkInteger,
     CharLen(u32),
     CharLenWithDefault(u32, char),
     CharLenNull(u32),
@@ -180,6 +254,10 @@ pub enum ColType {
     TextUniq,
     Integer,
     IntegerWithDefault(i32),
+    IntegerNullWithDefault(i32),
+    /// An integer defaulting to a database expression, e.g. `nextval('seq')`
+    IntegerWithDefaultExpr(String),
+    IntegerUniqWithDefaultExpr(String),
     IntegerNull,
     IntegerUniq,
     Unsigned,
@@ -200,6 +278,8 @@ pub enum ColType {
     SmallIntegerUniq,
     BigInteger,
     BigIntegerWithDefault(i64),
+    BigIntegerWithDefaultExpr(String),
+    BigIntegerUniqWithDefaultExpr(String),
     BigIntegerNull,
     BigIntegerUniq,
     Decimal,
@@ -247,6 +327,7 @@ pub enum ColType {
     VarBinaryUniq(u32),
     TimestampWithTimeZone,
     TimestampWithTimeZoneWithDefault(String),
+    TimestampWithTimeZoneWithDefaultExpr(String),
     TimestampWithTimeZoneNull,
     Json,
     JsonNull,
@@ -269,7 +350,32 @@ pub enum ColType {
     VarBitLen(u32),
     VarBitLenNull(u32),
     VarBitLenUniq(u32),
+    // Postgres network types
+    Inet,
+    InetNull,
+    InetUniq,
+    Cidr,
+    CidrNull,
+    CidrUniq,
+    MacAddr,
+    MacAddrNull,
+    MacAddrUniq,
+    MacAddr8,
+    MacAddr8Null,
+    MacAddr8Uniq,
+    /// Postgres full text search document
+    TsVector,
+    TsVectorNull,
+    /// Postgres range of timestamps with time zone, e.g. a validity period
+    TstzRange,
+    TstzRangeNull,
+    /// An E.164 phone number, `+` and up to 15 digits
+    Phone,
+    PhoneNull,
+    PhoneUniq,
     Array(ColumnType),
+    /// A not null array defaulting to an empty one, `'{}'`
+    ArrayWithEmptyDefault(ColumnType),
     ArrayNull(ColumnType),
     ArrayUniq(ColumnType),
     // Enum types
@@ -277,6 +383,16 @@ pub enum ColType {
     EnumNull(String, Vec<String>),
     EnumWithDefault(String, Vec<String>, String),
     EnumNullWithDefault(String, Vec<String>, String),
+    /// A Postgres composite type, e.g. one made with
+    /// [`create_composite_type`]
+    Composite(String),
+    CompositeNull(String),
+    /// A column of any type with a collation, see [`ColType::collate`]
+    Collate(Box<ColType>, String),
+    /// A column of any type with a CHECK constraint, see [`ColType::check`]
+    Check(Box<ColType>, String),
+    /// A column of any type with a literal default, see [`ColType::default`]
+    Default(Box<ColType>, sea_query::Value),
 }
 
 pub enum ArrayColType {
@@ -295,6 +411,14 @@ impl ColType {
         Self::Array(Self::array_col_type(&kind))
     }
 
+    /// A not null array, empty unless given, so that an empty array is not
+    /// mistaken for a missing one.
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn array_with_empty_default(kind: ArrayColType) -> Self {
+        Self::ArrayWithEmptyDefault(Self::array_col_type(&kind))
+    }
+
     #[must_use]
     #[allow(clippy::needless_pass_by_value)]
     pub fn array_uniq(kind: ArrayColType) -> Self {
@@ -320,11 +444,89 @@ impl ColType {
 }
 
 impl ColType {
+    /// Sets the collation of a string or text column.
+    /// ```ignore
+    /// ("name", ColType::String.collate("C"))
+    /// ```
+    #[must_use]
+    pub fn collate(self, collation: &str) -> Self {
+        Self::Collate(Box::new(self), collation.to_string())
+    }
+
+    /// Adds a CHECK constraint on the column, given as raw SQL.
+    /// ```ignore
+    /// ("age", ColType::Integer.check(r#""age" >= 0"#))
+    /// ```
+    #[must_use]
+    pub fn check(self, expr: &str) -> Self {
+        Self::Check(Box::new(self), expr.to_string())
+    }
+
+    /// Defaults the column to a literal value.
+    /// ```ignore
+    /// ("status", ColType::String.default("draft"))
+    /// ```
+    #[must_use]
+    pub fn default<V: Into<sea_query::Value>>(self, value: V) -> Self {
+        Self::Default(Box::new(self), value.into())
+    }
+
+    /// Whether the column is the table's primary key.
+    #[must_use]
+    pub fn is_primary_key(&self) -> bool {
+        match self {
+            Self::PkAuto
+            | Self::PkIdentity
+            | Self::PkIdentityByDefault
+            | Self::PkIdentityWithSequence(_)
+            | Self::PkIdentityByDefaultWithSequence(_)
+            | Self::PkUuid
+            | Self::PkInteger => true,
+            Self::Collate(col_type, _) | Self::Check(col_type, _) | Self::Default(col_type, _) => {
+                col_type.is_primary_key()
+            }
+            _ => false,
+        }
+    }
+
+    /// The variants of an enum column, whatever it is wrapped in.
+    fn enum_variants(&self) -> Option<&[String]> {
+        match self {
+            Self::Enum(_, variants)
+            | Self::EnumNull(_, variants)
+            | Self::EnumWithDefault(_, variants, _)
+            | Self::EnumNullWithDefault(_, variants, _) => Some(variants),
+            Self::Collate(col_type, _) | Self::Check(col_type, _) | Self::Default(col_type, _) => {
+                col_type.enum_variants()
+            }
+            _ => None,
+        }
+    }
+
+    /// The column definition on `backend`. Sqlite has no enum types, an enum
+    /// column is a text one whose CHECK constraint only takes its variants.
+    fn to_backend_def(&self, backend: sea_orm::DatabaseBackend, name: &str) -> ColumnDef {
+        let mut def = self.to_def(Alias::new(name));
+        if backend == sea_orm::DatabaseBackend::Sqlite {
+            if let Some(variants) = self.enum_variants() {
+                def.check(Expr::cust(enum_check_sql(name, variants)));
+            }
+        }
+        def
+    }
+
     #[allow(clippy::too_many_lines)]
     fn to_def(&self, name: impl IntoIden) -> ColumnDef {
         match self {
             Self::PkAuto => pk_auto(name),
+            Self::PkIdentity => pk_identity(name, "ALWAYS", &IdentitySequence::new()),
+            Self::PkIdentityByDefault => pk_identity(name, "BY DEFAULT", &IdentitySequence::new()),
+            Self::PkIdentityWithSequence(sequence) => pk_identity(name, "ALWAYS", sequence),
+            Self::PkIdentityByDefaultWithSequence(sequence) => {
+                pk_identity(name, "BY DEFAULT", sequence)
+            }
             Self::PkUuid => pk_uuid(name),
+            Self::PkInteger => integer(name).primary_key().take(),
             Self::CharLen(len) => char_len(name, *len),
             Self::CharLenNull(len) => char_len_null(name, *len),
             Self::CharLenUniq(len) => char_len_uniq(name, *len),
@@ -422,7 +624,38 @@ impl ColType {
             Self::VarBitLen(len) => varbit(name, *len),
             Self::VarBitLenNull(len) => varbit_null(name, *len),
             Self::VarBitLenUniq(len) => varbit_uniq(name, *len),
+            Self::Inet => network(name, &ColumnType::Inet).not_null().take(),
+            Self::InetNull => network(name, &ColumnType::Inet).null().take(),
+            Self::InetUniq => network(name, &ColumnType::Inet)
+                .not_null()
+                .unique_key()
+                .take(),
+            Self::Cidr => network(name, &ColumnType::Cidr).not_null().take(),
+            Self::CidrNull => network(name, &ColumnType::Cidr).null().take(),
+            Self::CidrUniq => network(name, &ColumnType::Cidr)
+                .not_null()
+                .unique_key()
+                .take(),
+            Self::MacAddr => network(name, &ColumnType::MacAddr).not_null().take(),
+            Self::MacAddrNull => network(name, &ColumnType::MacAddr).null().take(),
+            Self::MacAddrUniq => network(name, &ColumnType::MacAddr)
+                .not_null()
+                .unique_key()
+                .take(),
+            Self::MacAddr8 => custom(name, Alias::new("macaddr8")),
+            Self::MacAddr8Null => custom_null(name, Alias::new("macaddr8")),
+            Self::MacAddr8Uniq => custom(name, Alias::new("macaddr8")).unique_key().take(),
+            Self::TsVector => custom(name, Alias::new("tsvector")),
+            Self::TsVectorNull => custom_null(name, Alias::new("tsvector")),
+            Self::TstzRange => custom(name, Alias::new("tstzrange")),
+            Self::TstzRangeNull => custom_null(name, Alias::new("tstzrange")),
+            Self::Phone => string_len(name, 16),
+            Self::PhoneNull => string_len_null(name, 16),
+            Self::PhoneUniq => string_len_uniq(name, 16),
             Self::Array(kind) => array(name, kind.clone()),
+            Self::ArrayWithEmptyDefault(kind) => {
+                array(name, kind.clone()).default(Expr::cust("'{}'")).take()
+            }
             Self::ArrayNull(kind) => array_null(name, kind.clone()),
             Self::ArrayUniq(kind) => array_uniq(name, kind.clone()),
             // Enum types
@@ -434,14 +667,39 @@ impl ColType {
             Self::EnumNullWithDefault(enum_name, _, default_value) => {
                 enum_type_null_with_default(name, enum_name, default_value)
             }
+            Self::Composite(type_name) => ColumnDef::new(name)
+                .custom(Alias::new(type_name))
+                .not_null()
+                .take(),
+            Self::CompositeNull(type_name) => ColumnDef::new(name)
+                .custom(Alias::new(type_name))
+                .null()
+                .take(),
+            Self::Collate(col_type, collation) => col_type
+                .to_def(name)
+                .extra(format!("COLLATE \"{collation}\""))
+                .take(),
+            Self::Check(col_type, expr) => col_type.to_def(name).check(Expr::cust(expr)).take(),
+            Self::Default(col_type, value) => col_type.to_def(name).default(value.clone()).take(),
             // defaults
             Self::MoneyWithDefault(v) => money(name).default(*v).take(),
             Self::IntegerWithDefault(v) => integer(name).default(*v).take(),
+            Self::IntegerNullWithDefault(v) => integer_null(name).default(*v).take(),
+            Self::IntegerWithDefaultExpr(v) => integer(name).default(Expr::cust(v.clone())).take(),
+            Self::IntegerUniqWithDefaultExpr(v) => {
+                integer_uniq(name).default(Expr::cust(v.clone())).take()
+            }
             Self::UnsignedWithDefault(v) => unsigned(name).default(*v).take(),
             Self::SmallUnsignedWithDefault(v) => small_unsigned(name).default(*v).take(),
             Self::BigUnsignedWithDefault(v) => big_unsigned(name).default(*v).take(),
             Self::SmallIntegerWithDefault(v) => small_integer(name).default(*v).take(),
             Self::BigIntegerWithDefault(v) => big_integer(name).default(*v).take(),
+            Self::BigIntegerWithDefaultExpr(v) => {
+                big_integer(name).default(Expr::cust(v.clone())).take()
+            }
+            Self::BigIntegerUniqWithDefaultExpr(v) => {
+                big_integer_uniq(name).default(Expr::cust(v.clone())).take()
+            }
             Self::DecimalWithDefault(v) => decimal(name).default(*v).take(),
             Self::DecimalLenWithDefault(p, s, v) => decimal_len(name, *p, *s).default(*v).take(),
             Self::FloatWithDefault(v) => float(name).default(*v).take(),
@@ -453,6 +711,9 @@ impl ColType {
             Self::TimestampWithTimeZoneWithDefault(v) => {
                 timestamptz(name).default(v.clone()).take()
             }
+            Self::TimestampWithTimeZoneWithDefaultExpr(v) => {
+                timestamptz(name).default(Expr::cust(v.clone())).take()
+            }
             Self::CharWithDefault(v) => char(name).default(*v).take(),
             Self::CharLenWithDefault(len, v) => char_len(name, *len).default(*v).take(),
             Self::StringWithDefault(v) => string(name).default(v.clone()).take(),
@@ -478,6 +739,7 @@ impl ColType {
 /// ```shell
 /// loco g migration CreateMovies title:string user:references
 /// loco g migration CreateMovies title:string user:references:admin_id
+/// loco g migration CreateProjects org:references:tenant.organizations
 /// ```
 /// # Errors
 /// fails when it fails
@@ -559,6 +821,335 @@ pub async fn create_join_table_without_timestamps(
     create_table_impl(m, table, cols, refs, true, false).await
 }
 
+/// Create a Postgres `UNLOGGED` table. Writes to it skip the write-ahead log,
+/// which makes them faster, but the table is truncated after a crash and is
+/// not replicated.
+/// ```ignore
+/// create_unlogged_table(m, "events", vec![
+///     ("payload", ColType::Json)
+/// ],
+/// vec![]
+/// )
+/// .await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn create_unlogged_table(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_unlogged_table_impl(m, table, cols, refs, true).await
+}
+
+/// Create a Postgres `UNLOGGED` table without automatic timestamps.
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn create_unlogged_table_without_timestamps(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_unlogged_table_impl(m, table, cols, refs, false).await
+}
+
+async fn create_unlogged_table_impl(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+    add_timestamps: bool,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "unlogged tables are only supported on postgres, not {backend:?}"
+        )));
+    }
+    let stmt = create_table_stmt(m, table, cols, refs, false, add_timestamps).await?;
+    m.get_connection()
+        .execute_unprepared(&unlogged_sql(&stmt))
+        .await?;
+    Ok(())
+}
+
+/// Create a `SQLite` `WITHOUT ROWID` table, which is stored clustered by its
+/// primary key. Such a table has no `AUTOINCREMENT`: an integer primary key
+/// is a plain column, and the app assigns its values.
+/// ```ignore
+/// create_without_rowid_table(m, "settings", vec![
+///     ("id", ColType::PkAuto),
+///     ("value", ColType::Text)
+/// ],
+/// vec![]
+/// )
+/// .await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, when the database is not `SQLite`, or when there is
+/// no primary key column
+pub async fn create_without_rowid_table(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_without_rowid_table_impl(m, table, cols, refs, true).await
+}
+
+/// Create a `SQLite` `WITHOUT ROWID` table without automatic timestamps.
+///
+/// # Errors
+/// fails when it fails, when the database is not `SQLite`, or when there is
+/// no primary key column
+pub async fn create_without_rowid_table_without_timestamps(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_without_rowid_table_impl(m, table, cols, refs, false).await
+}
+
+async fn create_without_rowid_table_impl(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+    add_timestamps: bool,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Sqlite {
+        return Err(DbErr::Migration(format!(
+            "WITHOUT ROWID tables are only supported on sqlite, not {backend:?}"
+        )));
+    }
+    if !cols.iter().any(|(_, col_type)| col_type.is_primary_key()) {
+        return Err(DbErr::Migration(format!(
+            "WITHOUT ROWID table `{table}` needs a primary key column"
+        )));
+    }
+    let stmt = create_table_stmt(m, table, cols, refs, false, add_timestamps).await?;
+    m.get_connection()
+        .execute_unprepared(&without_rowid_sql(&stmt))
+        .await?;
+    Ok(())
+}
+
+/// sea-query has no notion of `WITHOUT ROWID` tables, so patch the generated
+/// SQL, dropping the `AUTOINCREMENT` `SQLite` does not allow on them
+fn without_rowid_sql(stmt: &TableCreateStatement) -> String {
+    format!(
+        "{} WITHOUT ROWID",
+        stmt.to_string(SqliteQueryBuilder)
+            .replace(" AUTOINCREMENT", "")
+    )
+}
+
+/// Create a `SQLite` `STRICT` table, which rejects values that do not fit
+/// the column types instead of storing them as they come.
+/// ```ignore
+/// create_strict_table(m, "movies", vec![
+///     ("id", ColType::PkAuto),
+///     ("title", ColType::String)
+/// ],
+/// vec![]
+/// )
+/// .await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not `SQLite`
+pub async fn create_strict_table(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_strict_table_impl(m, table, cols, refs, true).await
+}
+
+/// Create a `SQLite` `STRICT` table without automatic timestamps.
+///
+/// # Errors
+/// fails when it fails, or when the database is not `SQLite`
+pub async fn create_strict_table_without_timestamps(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_strict_table_impl(m, table, cols, refs, false).await
+}
+
+async fn create_strict_table_impl(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+    add_timestamps: bool,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Sqlite {
+        return Err(DbErr::Migration(format!(
+            "STRICT tables are only supported on sqlite, not {backend:?}"
+        )));
+    }
+    let stmt = create_table_stmt(m, table, cols, refs, false, add_timestamps).await?;
+    m.get_connection()
+        .execute_unprepared(&strict_sql(&stmt))
+        .await?;
+    Ok(())
+}
+
+/// Create a `SQLite` table that is both `STRICT` and `WITHOUT ROWID`, see
+/// [`create_strict_table`] and [`create_without_rowid_table`].
+/// ```ignore
+/// create_strict_without_rowid_table(m, "settings", vec![
+///     ("id", ColType::PkAuto),
+///     ("value", ColType::Text)
+/// ],
+/// vec![]
+/// )
+/// .await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, when the database is not `SQLite`, or when there is
+/// no primary key column
+pub async fn create_strict_without_rowid_table(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_strict_without_rowid_table_impl(m, table, cols, refs, true).await
+}
+
+/// Create a `SQLite` `STRICT`, `WITHOUT ROWID` table without automatic
+/// timestamps.
+///
+/// # Errors
+/// fails when it fails, when the database is not `SQLite`, or when there is
+/// no primary key column
+pub async fn create_strict_without_rowid_table_without_timestamps(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+) -> Result<(), DbErr> {
+    create_strict_without_rowid_table_impl(m, table, cols, refs, false).await
+}
+
+async fn create_strict_without_rowid_table_impl(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+    add_timestamps: bool,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Sqlite {
+        return Err(DbErr::Migration(format!(
+            "STRICT, WITHOUT ROWID tables are only supported on sqlite, not {backend:?}"
+        )));
+    }
+    if !cols.iter().any(|(_, col_type)| col_type.is_primary_key()) {
+        return Err(DbErr::Migration(format!(
+            "WITHOUT ROWID table `{table}` needs a primary key column"
+        )));
+    }
+    let stmt = create_table_stmt(m, table, cols, refs, false, add_timestamps).await?;
+    m.get_connection()
+        .execute_unprepared(&strict_without_rowid_sql(&stmt))
+        .await?;
+    Ok(())
+}
+
+/// sea-query has no notion of `STRICT` tables, so patch the generated SQL.
+/// A `STRICT` table only takes `INTEGER`, `REAL`, `TEXT`, `BLOB` and `ANY`
+/// columns, each column gets the one of the affinity `SQLite` gives its
+/// declared type.
+fn strict_sql(stmt: &TableCreateStatement) -> String {
+    format!("{} STRICT", strict_columns_sql(stmt))
+}
+
+/// Both table options, comma separated as `SQLite` takes them, see
+/// [`strict_sql`] and [`without_rowid_sql`]
+fn strict_without_rowid_sql(stmt: &TableCreateStatement) -> String {
+    format!(
+        "{} STRICT, WITHOUT ROWID",
+        strict_columns_sql(stmt).replace(" AUTOINCREMENT", "")
+    )
+}
+
+/// The `CREATE TABLE` statement with `STRICT` column types, without the table
+/// option itself
+fn strict_columns_sql(stmt: &TableCreateStatement) -> String {
+    let mut sql = stmt.to_string(SqliteQueryBuilder);
+    for col in stmt.get_columns() {
+        let Some(col_type) = col.get_column_type() else {
+            continue;
+        };
+        let name = col.get_column_name();
+        let declared = Table::create()
+            .table(Alias::new("t"))
+            .col(ColumnDef::new_with_type(
+                Alias::new(&name),
+                col_type.clone(),
+            ))
+            .to_string(SqliteQueryBuilder);
+        let Some(declared) = declared
+            .strip_prefix(r#"CREATE TABLE "t" ( "#)
+            .and_then(|declared| declared.strip_suffix(" )"))
+        else {
+            continue;
+        };
+        let decl_type = declared.trim_start_matches(&format!(r#""{name}" "#));
+        sql = sql.replacen(
+            declared,
+            &format!(r#""{name}" {}"#, strict_type(decl_type)),
+            1,
+        );
+    }
+    sql
+}
+
+/// The `STRICT` column type of the affinity `SQLite` gives a declared type,
+/// see <https://www.sqlite.org/datatype3.html#determination_of_column_affinity>
+fn strict_type(decl_type: &str) -> &'static str {
+    let decl_type = decl_type.to_uppercase();
+    if decl_type.contains("INT") {
+        "INTEGER"
+    } else if ["CHAR", "CLOB", "TEXT"]
+        .iter()
+        .any(|t| decl_type.contains(t))
+    {
+        "TEXT"
+    } else if decl_type.contains("BLOB") {
+        "BLOB"
+    } else if ["REAL", "FLOA", "DOUB"]
+        .iter()
+        .any(|t| decl_type.contains(t))
+    {
+        "REAL"
+    } else {
+        // `NUMERIC` affinity, which `STRICT` tables do not have
+        "ANY"
+    }
+}
+
+/// sea-query has no notion of unlogged tables, so patch the generated SQL
+fn unlogged_sql(stmt: &TableCreateStatement) -> String {
+    stmt.to_string(PostgresQueryBuilder)
+        .replacen("CREATE TABLE", "CREATE UNLOGGED TABLE", 1)
+}
+
 async fn create_table_impl(
     m: &SchemaManager<'_>,
     table: &str,
@@ -567,6 +1158,19 @@ async fn create_table_impl(
     is_join: bool,
     add_timestamps: bool, // New parameter to control timestamp addition
 ) -> Result<(), DbErr> {
+    let stmt = create_table_stmt(m, table, cols, refs, is_join, add_timestamps).await?;
+    m.create_table(stmt).await?;
+    Ok(())
+}
+
+async fn create_table_stmt(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)], // [(from_tbl, to_tbl), ...]
+    is_join: bool,
+    add_timestamps: bool,
+) -> Result<TableCreateStatement, DbErr> {
     let nz_table = normalize_table(table);
 
     // Create enum types automatically if they don't exist
@@ -643,19 +1247,25 @@ async fn create_table_impl(
         stmt.primary_key(&mut idx);
     }
 
+    let backend = m.get_database_backend();
     for (name, atype) in cols {
-        stmt.col(atype.to_def(Alias::new(*name)));
+        stmt.col(atype.to_backend_def(backend, name));
     }
 
     // user, None
     // users, None
     // user, admin_id
+    // tenant.organizations, organization_id
     for (from_tbl, ref_name) in refs {
         // Check for nullable reference
-        let (nz_from_table, is_nullable) = from_tbl.strip_suffix('?').map_or_else(
-            || (normalize_table(from_tbl), false),
-            |stripped| (normalize_table(stripped), true),
-        );
+        let (from_tbl, is_nullable) = from_tbl
+            .strip_suffix('?')
+            .map_or((*from_tbl, false), |stripped| (stripped, true));
+        // A table in another schema
+        let (from_schema, from_tbl) = from_tbl
+            .split_once('.')
+            .map_or((None, from_tbl), |(schema, tbl)| (Some(schema), tbl));
+        let nz_from_table = normalize_table(from_tbl);
         let nz_ref_name = if ref_name.is_empty() {
             reference_id(&nz_from_table)
         } else {
@@ -674,7 +1284,13 @@ async fn create_table_impl(
         let mut fk = sea_query::ForeignKey::create();
         fk.name(format!("fk-{nz_from_table}-{nz_ref_name}-to-{nz_table}"));
         fk.from(Alias::new(&nz_table), Alias::new(&nz_ref_name));
-        fk.to(Alias::new(nz_from_table), Alias::new("id"));
+        match from_schema {
+            Some(schema) => fk.to(
+                (Alias::new(schema), Alias::new(nz_from_table)),
+                Alias::new("id"),
+            ),
+            None => fk.to(Alias::new(nz_from_table), Alias::new("id")),
+        };
         if is_nullable {
             fk.on_delete(ForeignKeyAction::SetNull);
             fk.on_update(ForeignKeyAction::NoAction);
@@ -684,8 +1300,25 @@ async fn create_table_impl(
         }
         stmt.foreign_key(&mut fk);
     }
-    m.create_table(stmt).await?;
-    Ok(())
+    Ok(stmt)
+}
+
+/// a column of one of the postgres network types, nullability is up to the caller
+fn network(name: impl IntoIden, col_type: &ColumnType) -> ColumnDef {
+    ColumnDef::new_with_type(name, col_type.clone())
+}
+
+/// an integer primary key backed by an identity column instead of `serial`
+fn pk_identity(name: impl IntoIden, generated: &str, sequence: &IdentitySequence) -> ColumnDef {
+    ColumnDef::new(name)
+        .integer()
+        .not_null()
+        .primary_key()
+        .extra(format!(
+            "GENERATED {generated} AS IDENTITY{}",
+            sequence.clause()
+        ))
+        .take()
 }
 
 /// person -> people, movies -> movie
@@ -715,7 +1348,7 @@ pub async fn add_column(
     let nz_table = normalize_table(table);
     m.alter_table(
         alter(Alias::new(nz_table))
-            .add_column(atype.to_def(Alias::new(name)))
+            .add_column(atype.to_backend_def(m.get_database_backend(), name))
             .to_owned(),
     )
     .await?;
@@ -741,6 +1374,26 @@ pub async fn remove_column(m: &SchemaManager<'_>, table: &str, name: &str) -> Re
     Ok(())
 }
 
+///
+/// Remove a column when it and its table exist.
+/// ```ignore
+/// remove_column_if_exists(m, "movies", "rating").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn remove_column_if_exists(
+    m: &SchemaManager<'_>,
+    table: &str,
+    name: &str,
+) -> Result<(), DbErr> {
+    let nz_table = normalize_table(table);
+    if m.has_table(&nz_table).await? && m.has_column(&nz_table, name).await? {
+        remove_column(m, table, name).await?;
+    }
+    Ok(())
+}
+
 ///
 /// Adds a reference. Reads "movies belongs-to users":
 /// ```ignore
@@ -767,18 +1420,7 @@ pub async fn add_reference(
     };
     let bk = m.get_database_backend();
     let col = ColType::Integer.to_def(Alias::new(&nz_ref_name));
-    let fk = TableForeignKey::new()
-        // fk-movies-user_id-to-users
-        .name(format!("fk-{nz_fromtbl}-{nz_ref_name}-to-{nz_totbl}"))
-        // from movies#user_id
-        .from_tbl(Alias::new(&nz_fromtbl))
-        .from_col(Alias::new(&nz_ref_name)) // xxx fix
-        // to users#id
-        .to_tbl(Alias::new(nz_totbl))
-        .to_col(Alias::new("id"))
-        .on_delete(ForeignKeyAction::Cascade)
-        .on_update(ForeignKeyAction::Cascade)
-        .to_owned();
+    let fk = reference_fk(&nz_fromtbl, &nz_totbl, &nz_ref_name);
     match bk {
         sea_orm::DatabaseBackend::MySql | sea_orm::DatabaseBackend::Postgres => {
             // from movies to users -> movies#user_id to users#id
@@ -820,9 +1462,69 @@ pub async fn add_reference(
 }
 
 ///
-/// Removes a reference by constructing its name from the table names.
+/// Adds only the foreign key of a reference, for a column that already
+/// exists. Reads "movies belongs-to users" through `movies#user_id`:
 /// ```ignore
-/// remove_reference(m, "movies", "users").await;
+/// add_foreign_key(m, "movies", "users", "").await;
+/// ```
+///
+/// On sqlite this does nothing, as it only applies foreign keys on table
+/// creation.
+///
+/// # Errors
+/// fails when it fails
+pub async fn add_foreign_key(
+    m: &SchemaManager<'_>,
+    fromtbl: &str,
+    totbl: &str,
+    refname: &str,
+) -> Result<(), DbErr> {
+    // movies
+    let nz_fromtbl = normalize_table(fromtbl);
+    // users
+    let nz_totbl = normalize_table(totbl);
+    // user_id
+    let nz_ref_name = if refname.is_empty() {
+        reference_id(totbl)
+    } else {
+        refname.to_string()
+    };
+    match m.get_database_backend() {
+        sea_orm::DatabaseBackend::MySql | sea_orm::DatabaseBackend::Postgres => {
+            m.alter_table(
+                alter(Alias::new(&nz_fromtbl))
+                    .add_foreign_key(&reference_fk(&nz_fromtbl, &nz_totbl, &nz_ref_name))
+                    .to_owned(),
+            )
+            .await?;
+        }
+        sea_orm::DatabaseBackend::Sqlite => {
+            // see `add_reference`
+        }
+    }
+    Ok(())
+}
+
+/// The foreign key of `nz_fromtbl#nz_ref_name` to `nz_totbl#id`
+fn reference_fk(nz_fromtbl: &str, nz_totbl: &str, nz_ref_name: &str) -> TableForeignKey {
+    TableForeignKey::new()
+        // fk-movies-user_id-to-users
+        .name(format!("fk-{nz_fromtbl}-{nz_ref_name}-to-{nz_totbl}"))
+        // from movies#user_id
+        .from_tbl(Alias::new(nz_fromtbl))
+        .from_col(Alias::new(nz_ref_name))
+        // to users#id
+        .to_tbl(Alias::new(nz_totbl))
+        .to_col(Alias::new("id"))
+        .on_delete(ForeignKeyAction::Cascade)
+        .on_update(ForeignKeyAction::Cascade)
+        .to_owned()
+}
+
+///
+/// Removes a reference by constructing its name from the table names.
+/// ```ignore
+/// remove_reference(m, "movies", "users").await;
 /// ```
 ///
 /// # Errors
@@ -867,90 +1569,2132 @@ pub async fn remove_reference(
 }
 
 ///
-/// Drop a table
+/// Removes a reference when its foreign key exists, see [`remove_reference`].
 /// ```ignore
-/// drop_table(m, "movies").await;
+/// remove_reference_if_exists(m, "movies", "users", "").await;
 /// ```
 ///
 /// # Errors
 /// fails when it fails
-pub async fn drop_table(m: &SchemaManager<'_>, table: &str) -> Result<(), DbErr> {
-    let nz_table = normalize_table(table);
-    m.drop_table(Table::drop().table(Alias::new(nz_table)).to_owned())
-        .await
+pub async fn remove_reference_if_exists(
+    m: &SchemaManager<'_>,
+    fromtbl: &str,
+    totbl: &str,
+    refname: &str,
+) -> Result<(), DbErr> {
+    let nz_fromtbl = normalize_table(fromtbl);
+    let nz_totbl = normalize_table(totbl);
+    let nz_ref_name = if refname.is_empty() {
+        reference_id(totbl)
+    } else {
+        refname.to_string()
+    };
+    let fk_name = format!("fk-{nz_fromtbl}-{nz_ref_name}-to-{nz_totbl}");
+    let backend = m.get_database_backend();
+    let conn = m.get_connection();
+    match backend {
+        sea_orm::DatabaseBackend::Postgres => {
+            conn.execute_unprepared(&format!(
+                "ALTER TABLE IF EXISTS {} DROP CONSTRAINT IF EXISTS {}",
+                quote_ident(backend, &nz_fromtbl),
+                quote_ident(backend, &fk_name)
+            ))
+            .await?;
+        }
+        sea_orm::DatabaseBackend::MySql => {
+            // MySQL has no `DROP FOREIGN KEY IF EXISTS`
+            let exists = conn
+                .query_one(sea_orm::Statement::from_sql_and_values(
+                    backend,
+                    "SELECT 1 FROM information_schema.TABLE_CONSTRAINTS WHERE \
+                     CONSTRAINT_SCHEMA = DATABASE() AND TABLE_NAME = ? AND CONSTRAINT_NAME = ? \
+                     AND CONSTRAINT_TYPE = 'FOREIGN KEY'",
+                    [nz_fromtbl.as_str().into(), fk_name.as_str().into()],
+                ))
+                .await?
+                .is_some();
+            if exists {
+                remove_reference(m, fromtbl, totbl, refname).await?;
+            }
+        }
+        sea_orm::DatabaseBackend::Sqlite => {
+            // nothing to remove, see `remove_reference`
+        }
+    }
+    Ok(())
 }
 
 ///
-/// Add enum values to an existing enum type
+/// Create an index without locking the table against writes, with
+/// `CREATE INDEX CONCURRENTLY` on Postgres, and a plain `CREATE INDEX`
+/// elsewhere.
+///
+/// Postgres cannot build an index concurrently inside a transaction, and
+/// migrations run in one. This commits the migrations applied so far, builds
+/// the index outside of a transaction and opens a new one for the rest, which
+/// makes the calling migration non-transactional.
 /// ```ignore
-/// add_enum_values(m, "status_enum", vec!["suspended", "cancelled"]).await;
+/// create_index_concurrently(m, Index::create()
+///     .name("idx-movies-title")
+///     .table(Alias::new("movies"))
+///     .col(Alias::new("title"))
+///     .to_owned(),
+/// )
+/// .await;
 /// ```
 ///
 /// # Errors
 /// fails when it fails
-pub async fn add_enum_values(
+pub async fn create_index_concurrently(
     m: &SchemaManager<'_>,
-    enum_name: &str,
-    new_values: Vec<String>,
+    index: IndexCreateStatement,
 ) -> Result<(), DbErr> {
-    match m.get_database_backend() {
-        sea_orm::DatabaseBackend::Postgres => {
-            for value in new_values {
-                m.get_connection()
-                    .execute(sea_orm::Statement::from_string(
-                        sea_orm::DatabaseBackend::Postgres,
-                        format!("ALTER TYPE {enum_name} ADD VALUE '{value}'"),
-                    ))
-                    .await?;
-            }
-        }
-        sea_orm::DatabaseBackend::Sqlite => {
-            // SQLite doesn't support native enums, values are handled by CHECK constraints
-            tracing::info!(
-                "SQLite: Enum values are handled by CHECK constraints. No action needed."
-            );
-        }
-        sea_orm::DatabaseBackend::MySql => {
-            // MySQL handles enums differently
-            tracing::info!(
-                "MySQL: Enum values are handled by column definition. No action needed."
-            );
-        }
+    if m.get_database_backend() != sea_orm::DatabaseBackend::Postgres {
+        return m.create_index(index).await;
+    }
+    leave_transaction(m).await?;
+    let res = create_index_concurrently_without_transaction(m, index).await;
+    resume_transaction(m).await?;
+    res
+}
+
+///
+/// Create an index like [`create_index_concurrently`], in a migration that
+/// already left the transaction, see [`leave_transaction`].
+///
+/// # Errors
+/// fails when it fails
+pub async fn create_index_concurrently_without_transaction(
+    m: &SchemaManager<'_>,
+    index: IndexCreateStatement,
+) -> Result<(), DbErr> {
+    if m.get_database_backend() != sea_orm::DatabaseBackend::Postgres {
+        return m.create_index(index).await;
     }
+    m.get_connection()
+        .execute_unprepared(&concurrent_index_sql(&index))
+        .await?;
     Ok(())
 }
 
 ///
-/// Drop an enum type completely
+/// Commit the transaction Postgres migrations run in, so that the rest of
+/// the migration runs outside of one, e.g. to build indexes concurrently or
+/// add enum values that are then used. The migrations applied so far are
+/// committed with it. [`resume_transaction`] has to open a new one before
+/// the migration ends, for the migrator to commit. Other backends run
+/// migrations outside of a transaction already, this does nothing there.
 /// ```ignore
-/// drop_enum_type(m, "status_enum").await;
+/// leave_transaction(m).await?;
+/// // ...
+/// resume_transaction(m).await?;
 /// ```
 ///
 /// # Errors
 /// fails when it fails
-pub async fn drop_enum_type(m: &SchemaManager<'_>, enum_name: &str) -> Result<(), DbErr> {
-    match m.get_database_backend() {
-        sea_orm::DatabaseBackend::Postgres => {
-            // First check if the enum type exists
-            let enum_exists = check_enum_exists(m, enum_name).await?;
-            if !enum_exists {
-                tracing::info!("Enum type '{}' does not exist, skipping drop", enum_name);
-                return Ok(());
-            }
+pub async fn leave_transaction(m: &SchemaManager<'_>) -> Result<(), DbErr> {
+    if m.get_database_backend() == sea_orm::DatabaseBackend::Postgres {
+        m.get_connection().execute_unprepared("COMMIT").await?;
+    }
+    Ok(())
+}
 
-            // Try to drop the enum type with CASCADE to handle any remaining references
-            let query = format!("DROP TYPE IF EXISTS {enum_name} CASCADE");
-            m.get_connection()
-                .execute(sea_orm::Statement::from_string(
-                    sea_orm::DatabaseBackend::Postgres,
-                    query,
-                ))
-                .await?;
-        }
-        _ => {
-            // SQLite/MySQL don't have native enum types
-            tracing::info!("No native enum type to drop for this database backend.");
-        }
+///
+/// Open the transaction [`leave_transaction`] committed again, for the rest
+/// of the migrations and the migrator to commit. Postgres only, other
+/// backends skip it.
+///
+/// # Errors
+/// fails when it fails
+pub async fn resume_transaction(m: &SchemaManager<'_>) -> Result<(), DbErr> {
+    if m.get_database_backend() == sea_orm::DatabaseBackend::Postgres {
+        m.get_connection().execute_unprepared("BEGIN").await?;
+    }
+    Ok(())
+}
+
+/// sea-query has no notion of concurrent index builds, so patch the
+/// generated SQL
+fn concurrent_index_sql(index: &IndexCreateStatement) -> String {
+    index
+        .to_string(PostgresQueryBuilder)
+        .replacen(" INDEX ", " INDEX CONCURRENTLY ", 1)
+}
+
+///
+/// Create an index in `tablespace`, e.g. one on faster storage than its
+/// table's. Postgres only, and the tablespace has to exist, see
+/// `CREATE TABLESPACE`.
+/// ```ignore
+/// create_index_in_tablespace(m, Index::create()
+///     .name("idx-movies-title")
+///     .table(Alias::new("movies"))
+///     .col(Alias::new("title"))
+///     .to_owned(),
+///     "fast_ssd",
+/// )
+/// .await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn create_index_in_tablespace(
+    m: &SchemaManager<'_>,
+    index: IndexCreateStatement,
+    tablespace: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "index tablespaces are only supported on postgres, not {backend:?}"
+        )));
+    }
+    m.get_connection()
+        .execute_unprepared(&tablespace_index_sql(&index, tablespace))
+        .await?;
+    Ok(())
+}
+
+///
+/// Create an index in `tablespace` like [`create_index_in_tablespace`], and
+/// build it concurrently like [`create_index_concurrently_without_transaction`],
+/// in a migration that already left the transaction, see
+/// [`leave_transaction`].
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn create_index_concurrently_in_tablespace_without_transaction(
+    m: &SchemaManager<'_>,
+    index: IndexCreateStatement,
+    tablespace: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "index tablespaces are only supported on postgres, not {backend:?}"
+        )));
+    }
+    m.get_connection()
+        .execute_unprepared(&concurrent_tablespace_index_sql(&index, tablespace))
+        .await?;
+    Ok(())
+}
+
+/// `CREATE INDEX CONCURRENTLY .. TABLESPACE`, see [`concurrent_index_sql`]
+/// and [`tablespace_index_sql`]
+fn concurrent_tablespace_index_sql(index: &IndexCreateStatement, tablespace: &str) -> String {
+    tablespace_index_sql(index, tablespace).replacen(" INDEX ", " INDEX CONCURRENTLY ", 1)
+}
+
+/// sea-query has no notion of tablespaces either. The clause goes before the
+/// predicate of a partial index, index expressions cannot have a `WHERE`.
+fn tablespace_index_sql(index: &IndexCreateStatement, tablespace: &str) -> String {
+    let sql = index.to_string(PostgresQueryBuilder);
+    let tablespace = format!(
+        " TABLESPACE {}",
+        quote_ident(sea_orm::DatabaseBackend::Postgres, tablespace)
+    );
+    match sql.find(" WHERE ") {
+        Some(at) => format!("{}{tablespace}{}", &sql[..at], &sql[at..]),
+        None => format!("{sql}{tablespace}"),
+    }
+}
+
+///
+/// Create an index on `column` that sorts by an ICU `collation`, e.g.
+/// `und-x-icu` for a language neutral order, so that `ORDER BY "column"
+/// COLLATE "und-x-icu"` can use it. Postgres only, and the collation has to
+/// exist, see `pg_collation`.
+/// ```ignore
+/// create_collated_index(m, "products", "name", "und-x-icu").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, when the database is not Postgres, or when there is
+/// no ICU collation by that name
+pub async fn create_collated_index(
+    m: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+    collation: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "ICU collations are only supported on postgres, not {backend:?}"
+        )));
+    }
+    let exists = m
+        .get_connection()
+        .query_one(sea_orm::Statement::from_sql_and_values(
+            backend,
+            "SELECT 1 FROM pg_collation WHERE collname = $1 AND collprovider = 'i'",
+            [collation.into()],
+        ))
+        .await?
+        .is_some();
+    if !exists {
+        return Err(DbErr::Migration(format!(
+            "there is no ICU collation `{collation}`, is postgres built with ICU?"
+        )));
+    }
+    m.create_index(collated_index(table, column, collation))
+        .await
+}
+
+///
+/// Set how postgres stores the values of a variable-length `column`, one of
+/// `PLAIN`, `EXTERNAL`, `EXTENDED` or `MAIN`. `EXTERNAL`, for instance, keeps
+/// large values uncompressed out of line, which makes reading substrings of
+/// them faster. Postgres only.
+/// ```ignore
+/// set_column_storage(m, "documents", "body", "EXTERNAL").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, when the database is not Postgres, or when the
+/// storage mode is unknown
+pub async fn set_column_storage(
+    m: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+    storage: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "column storage modes are only supported on postgres, not {backend:?}"
+        )));
+    }
+    let sql = column_storage_sql(table, column, storage)?;
+    m.get_connection().execute_unprepared(&sql).await?;
+    Ok(())
+}
+
+fn column_storage_sql(table: &str, column: &str, storage: &str) -> Result<String, DbErr> {
+    let storage = storage.to_uppercase();
+    if !["PLAIN", "EXTERNAL", "EXTENDED", "MAIN"].contains(&storage.as_str()) {
+        return Err(DbErr::Migration(format!(
+            "`{storage}` is not a storage mode, expected PLAIN, EXTERNAL, EXTENDED or MAIN"
+        )));
+    }
+    let backend = sea_orm::DatabaseBackend::Postgres;
+    Ok(format!(
+        "ALTER TABLE {} ALTER COLUMN {} SET STORAGE {storage}",
+        quote_ident(backend, &normalize_table(table)),
+        quote_ident(backend, column)
+    ))
+}
+
+fn collated_index(table: &str, column: &str, collation: &str) -> IndexCreateStatement {
+    Index::create()
+        .name(format!("idx-{table}-{column}-{collation}"))
+        .table(Alias::new(table))
+        .col(Expr::cust(format!(r#""{column}" COLLATE "{collation}""#)))
+        .to_owned()
+}
+
+///
+/// Create a sequence, optionally in a schema, for a column defaulting to
+/// `nextval('<sequence>')`. Postgres only.
+/// ```ignore
+/// create_sequence(m, "invoice_numbers").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn create_sequence(m: &SchemaManager<'_>, sequence: &str) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "sequences are only supported on postgres, not {backend:?}"
+        )));
+    }
+    m.get_connection()
+        .execute_unprepared(&format!(
+            "CREATE SEQUENCE IF NOT EXISTS {}",
+            sequence_ident(sequence)
+        ))
+        .await?;
+    Ok(())
+}
+
+///
+/// Drop a sequence created with [`create_sequence`], when it exists.
+/// ```ignore
+/// drop_sequence(m, "invoice_numbers").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_sequence(m: &SchemaManager<'_>, sequence: &str) -> Result<(), DbErr> {
+    m.get_connection()
+        .execute_unprepared(&format!(
+            "DROP SEQUENCE IF EXISTS {}",
+            sequence_ident(sequence)
+        ))
+        .await?;
+    Ok(())
+}
+
+/// A quoted sequence name, `billing.numbers` -> `"billing"."numbers"`
+fn sequence_ident(sequence: &str) -> String {
+    sequence
+        .split('.')
+        .map(|part| format!("\"{part}\""))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+///
+/// Adds a counter cache: a `column` on `parent` counting its `child` rows,
+/// kept up to date by triggers on `child` which increment it on insert and
+/// decrement it on delete. Existing rows are counted when it is added.
+/// Moving a child to another parent by updating `fk` is not tracked.
+/// ```ignore
+/// add_counter_cache(m, "posts", "comments_count", "comments", "post_id").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn add_counter_cache(
+    m: &SchemaManager<'_>,
+    parent: &str,
+    column: &str,
+    child: &str,
+    fk: &str,
+) -> Result<(), DbErr> {
+    add_column(m, parent, column, ColType::IntegerWithDefault(0)).await?;
+    let conn = m.get_connection();
+    for sql in counter_cache_sql(m.get_database_backend(), parent, column, child, fk) {
+        conn.execute_unprepared(&sql).await?;
     }
     Ok(())
 }
+
+///
+/// Removes a counter cache added with [`add_counter_cache`], its triggers
+/// and its column.
+/// ```ignore
+/// remove_counter_cache(m, "posts", "comments_count", "comments").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn remove_counter_cache(
+    m: &SchemaManager<'_>,
+    parent: &str,
+    column: &str,
+    child: &str,
+) -> Result<(), DbErr> {
+    let conn = m.get_connection();
+    for sql in drop_counter_cache_sql(m.get_database_backend(), column, child) {
+        conn.execute_unprepared(&sql).await?;
+    }
+    remove_column(m, parent, column).await
+}
+
+///
+/// Removes a counter cache, see [`remove_counter_cache`], skipping the parts
+/// that do not exist.
+///
+/// # Errors
+/// fails when it fails
+pub async fn remove_counter_cache_if_exists(
+    m: &SchemaManager<'_>,
+    parent: &str,
+    column: &str,
+    child: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    // postgres drops triggers `ON` their table, which has to exist
+    if backend != sea_orm::DatabaseBackend::Postgres || m.has_table(normalize_table(child)).await? {
+        let conn = m.get_connection();
+        for sql in drop_counter_cache_sql(backend, column, child) {
+            conn.execute_unprepared(&sql).await?;
+        }
+    }
+    remove_column_if_exists(m, parent, column).await
+}
+
+///
+/// Adds a denormalized `column` on `table` joining its `sources` columns
+/// with a space, e.g. a `full_name` from `first_name` and `last_name`. The
+/// existing rows are backfilled, and triggers keep it up to date on insert
+/// and update. A null source is skipped.
+/// ```ignore
+/// add_denormalized_column(m, "users", "full_name", &["first_name", "last_name"]).await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn add_denormalized_column(
+    m: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+    sources: &[&str],
+) -> Result<(), DbErr> {
+    add_column(m, table, column, ColType::TextNull).await?;
+    let conn = m.get_connection();
+    for sql in denormalized_column_sql(m.get_database_backend(), table, column, sources) {
+        conn.execute_unprepared(&sql).await?;
+    }
+    Ok(())
+}
+
+///
+/// Removes a denormalized column added with [`add_denormalized_column`], its
+/// triggers and the column itself.
+/// ```ignore
+/// remove_denormalized_column(m, "users", "full_name").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn remove_denormalized_column(
+    m: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+) -> Result<(), DbErr> {
+    let conn = m.get_connection();
+    for sql in drop_denormalized_column_sql(m.get_database_backend(), table, column) {
+        conn.execute_unprepared(&sql).await?;
+    }
+    remove_column(m, table, column).await
+}
+
+///
+/// Removes a denormalized column, see [`remove_denormalized_column`],
+/// skipping the parts that do not exist.
+///
+/// # Errors
+/// fails when it fails
+pub async fn remove_denormalized_column_if_exists(
+    m: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+) -> Result<(), DbErr> {
+    // postgres drops triggers `ON` their table, which has to exist
+    if !m.has_table(normalize_table(table)).await? {
+        return Ok(());
+    }
+    let conn = m.get_connection();
+    for sql in drop_denormalized_column_sql(m.get_database_backend(), table, column) {
+        conn.execute_unprepared(&sql).await?;
+    }
+    remove_column_if_exists(m, table, column).await
+}
+
+/// The statements backfilling a denormalized column and creating its
+/// triggers
+fn denormalized_column_sql(
+    backend: sea_orm::DatabaseBackend,
+    table: &str,
+    column: &str,
+    sources: &[&str],
+) -> Vec<String> {
+    let nz_table = normalize_table(table);
+    let q = |ident: &str| quote_ident(backend, ident);
+    let (table_q, column_q) = (q(&nz_table), q(column));
+    let joined = |row: &str| {
+        let sources = sources
+            .iter()
+            .map(|source| format!("{row}{}", q(source)))
+            .collect::<Vec<_>>();
+        format!("concat_ws(' ', {})", sources.join(", "))
+    };
+    let insert = q(&format!("{nz_table}_{column}_insert"));
+    let update = q(&format!("{nz_table}_{column}_update"));
+
+    let mut sql = vec![format!("UPDATE {table_q} SET {column_q} = {}", joined(""))];
+    match backend {
+        sea_orm::DatabaseBackend::Postgres => {
+            let func = q(&format!("{nz_table}_{column}_denormalize"));
+            sql.push(format!(
+                "CREATE OR REPLACE FUNCTION {func}() RETURNS trigger AS $$ BEGIN NEW.{column_q} := \
+                 {}; RETURN NEW; END; $$ LANGUAGE plpgsql",
+                joined("NEW.")
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {insert} BEFORE INSERT ON {table_q} FOR EACH ROW EXECUTE FUNCTION \
+                 {func}()"
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {update} BEFORE UPDATE ON {table_q} FOR EACH ROW EXECUTE FUNCTION \
+                 {func}()"
+            ));
+        }
+        sea_orm::DatabaseBackend::Sqlite => {
+            // sqlite triggers cannot assign `NEW`, the row is updated after
+            let set = format!(
+                "UPDATE {table_q} SET {column_q} = {} WHERE rowid = NEW.rowid",
+                joined("NEW.")
+            );
+            let of = sources.iter().map(|s| q(s)).collect::<Vec<_>>().join(", ");
+            sql.push(format!(
+                "CREATE TRIGGER {insert} AFTER INSERT ON {table_q} BEGIN {set}; END"
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {update} AFTER UPDATE OF {of} ON {table_q} BEGIN {set}; END"
+            ));
+        }
+        sea_orm::DatabaseBackend::MySql => {
+            let set = format!("SET NEW.{column_q} = {}", joined("NEW."));
+            sql.push(format!(
+                "CREATE TRIGGER {insert} BEFORE INSERT ON {table_q} FOR EACH ROW {set}"
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {update} BEFORE UPDATE ON {table_q} FOR EACH ROW {set}"
+            ));
+        }
+    }
+    sql
+}
+
+/// The statements dropping the triggers of a denormalized column
+fn drop_denormalized_column_sql(
+    backend: sea_orm::DatabaseBackend,
+    table: &str,
+    column: &str,
+) -> Vec<String> {
+    let nz_table = normalize_table(table);
+    let q = |ident: &str| quote_ident(backend, ident);
+    let insert = q(&format!("{nz_table}_{column}_insert"));
+    let update = q(&format!("{nz_table}_{column}_update"));
+    match backend {
+        sea_orm::DatabaseBackend::Postgres => {
+            let table_q = q(&nz_table);
+            vec![
+                format!("DROP TRIGGER IF EXISTS {insert} ON {table_q}"),
+                format!("DROP TRIGGER IF EXISTS {update} ON {table_q}"),
+                format!(
+                    "DROP FUNCTION IF EXISTS {}()",
+                    q(&format!("{nz_table}_{column}_denormalize"))
+                ),
+            ]
+        }
+        sea_orm::DatabaseBackend::Sqlite | sea_orm::DatabaseBackend::MySql => vec![
+            format!("DROP TRIGGER IF EXISTS {insert}"),
+            format!("DROP TRIGGER IF EXISTS {update}"),
+        ],
+    }
+}
+
+fn quote_ident(backend: sea_orm::DatabaseBackend, ident: &str) -> String {
+    match backend {
+        sea_orm::DatabaseBackend::MySql => format!("`{ident}`"),
+        sea_orm::DatabaseBackend::Postgres | sea_orm::DatabaseBackend::Sqlite => {
+            format!("\"{ident}\"")
+        }
+    }
+}
+
+/// The CHECK constraint keeping an enum column to its variants where there
+/// are no enum types, e.g. `"status" IN ('draft', 'published')`
+fn enum_check_sql(column: &str, variants: &[String]) -> String {
+    let variants = variants
+        .iter()
+        .map(|variant| format!("'{}'", variant.replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "{} IN ({variants})",
+        quote_ident(sea_orm::DatabaseBackend::Sqlite, column)
+    )
+}
+
+/// The statements counting the existing rows and creating the triggers
+fn counter_cache_sql(
+    backend: sea_orm::DatabaseBackend,
+    parent: &str,
+    column: &str,
+    child: &str,
+    fk: &str,
+) -> Vec<String> {
+    let (nz_parent, nz_child) = (normalize_table(parent), normalize_table(child));
+    let q = |ident: &str| quote_ident(backend, ident);
+    let (parent_q, child_q, column_q, fk_q, id_q) =
+        (q(&nz_parent), q(&nz_child), q(column), q(fk), q("id"));
+    let inc = q(&format!("{nz_child}_{column}_inc"));
+    let dec = q(&format!("{nz_child}_{column}_dec"));
+    let update = |sign: char, row: &str| {
+        format!(
+            "UPDATE {parent_q} SET {column_q} = {column_q} {sign} 1 WHERE {id_q} = {row}.{fk_q}"
+        )
+    };
+
+    let mut sql = vec![format!(
+        "UPDATE {parent_q} SET {column_q} = (SELECT COUNT(*) FROM {child_q} WHERE {child_q}.{fk_q} \
+         = {parent_q}.{id_q})"
+    )];
+    match backend {
+        sea_orm::DatabaseBackend::Postgres => {
+            let func = q(&format!("{nz_child}_{column}_counter"));
+            sql.push(format!(
+                "CREATE OR REPLACE FUNCTION {func}() RETURNS trigger AS $$ BEGIN IF TG_OP = \
+                 'INSERT' THEN {}; ELSE {}; END IF; RETURN NULL; END; $$ LANGUAGE plpgsql",
+                update('+', "NEW"),
+                update('-', "OLD")
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {inc} AFTER INSERT ON {child_q} FOR EACH ROW EXECUTE FUNCTION \
+                 {func}()"
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {dec} AFTER DELETE ON {child_q} FOR EACH ROW EXECUTE FUNCTION \
+                 {func}()"
+            ));
+        }
+        sea_orm::DatabaseBackend::Sqlite => {
+            sql.push(format!(
+                "CREATE TRIGGER {inc} AFTER INSERT ON {child_q} BEGIN {}; END",
+                update('+', "NEW")
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {dec} AFTER DELETE ON {child_q} BEGIN {}; END",
+                update('-', "OLD")
+            ));
+        }
+        sea_orm::DatabaseBackend::MySql => {
+            sql.push(format!(
+                "CREATE TRIGGER {inc} AFTER INSERT ON {child_q} FOR EACH ROW {}",
+                update('+', "NEW")
+            ));
+            sql.push(format!(
+                "CREATE TRIGGER {dec} AFTER DELETE ON {child_q} FOR EACH ROW {}",
+                update('-', "OLD")
+            ));
+        }
+    }
+    sql
+}
+
+/// The statements dropping the triggers of a counter cache
+fn drop_counter_cache_sql(
+    backend: sea_orm::DatabaseBackend,
+    column: &str,
+    child: &str,
+) -> Vec<String> {
+    let nz_child = normalize_table(child);
+    let q = |ident: &str| quote_ident(backend, ident);
+    let inc = q(&format!("{nz_child}_{column}_inc"));
+    let dec = q(&format!("{nz_child}_{column}_dec"));
+    match backend {
+        sea_orm::DatabaseBackend::Postgres => {
+            let child_q = q(&nz_child);
+            vec![
+                format!("DROP TRIGGER IF EXISTS {inc} ON {child_q}"),
+                format!("DROP TRIGGER IF EXISTS {dec} ON {child_q}"),
+                format!(
+                    "DROP FUNCTION IF EXISTS {}()",
+                    q(&format!("{nz_child}_{column}_counter"))
+                ),
+            ]
+        }
+        sea_orm::DatabaseBackend::Sqlite | sea_orm::DatabaseBackend::MySql => vec![
+            format!("DROP TRIGGER IF EXISTS {inc}"),
+            format!("DROP TRIGGER IF EXISTS {dec}"),
+        ],
+    }
+}
+
+///
+/// Create the `<table>_history` table of a system-versioned table, along with
+/// the triggers filling it: each update or delete keeps the previous version
+/// of the row there, valid from its `valid_from` to the change, its
+/// `valid_to`. The versioned table needs a `valid_from` column defaulting to
+/// the current time. `cols` and `refs` are the ones it was created with,
+/// mirrored without their keys, uniqueness, checks or defaults.
+///
+/// A row as of a time `t` is then the current one if its `valid_from <= t`,
+/// else the history one with `valid_from <= t AND t < valid_to`.
+/// ```ignore
+/// create_history_table(m, "movies", &[
+///     ("title", ColType::String),
+///     ("valid_from", ColType::TimestampWithTimeZoneWithDefaultExpr("now()".to_string())),
+/// ], &[("user", "")]).await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn create_history_table(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)],
+) -> Result<(), DbErr> {
+    create_history_table_impl(m, table, cols, refs, true).await
+}
+
+/// Create the history table of a system-versioned table created without
+/// automatic timestamps, see [`create_history_table`].
+///
+/// # Errors
+/// fails when it fails
+pub async fn create_history_table_without_timestamps(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)],
+) -> Result<(), DbErr> {
+    create_history_table_impl(m, table, cols, refs, false).await
+}
+
+async fn create_history_table_impl(
+    m: &SchemaManager<'_>,
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)],
+    add_timestamps: bool,
+) -> Result<(), DbErr> {
+    let nz_table = normalize_table(table);
+    let history = format!("{nz_table}_history");
+    let (stmt, columns) = history_table_stmt(&nz_table, cols, refs, add_timestamps);
+    m.create_table(stmt).await?;
+    m.create_index(
+        Index::create()
+            .name(format!("idx-{history}-id-valid_from"))
+            .table(Alias::new(&history))
+            .col(Alias::new("id"))
+            .col(Alias::new("valid_from"))
+            .to_owned(),
+    )
+    .await?;
+    let conn = m.get_connection();
+    for sql in history_sql(m.get_database_backend(), &nz_table, &columns) {
+        conn.execute_unprepared(&sql).await?;
+    }
+    Ok(())
+}
+
+///
+/// Drop the history table of a system-versioned table and its triggers, see
+/// [`create_history_table`].
+/// ```ignore
+/// drop_history_table(m, "movies").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_history_table(m: &SchemaManager<'_>, table: &str) -> Result<(), DbErr> {
+    let conn = m.get_connection();
+    for sql in drop_history_sql(m.get_database_backend(), table) {
+        conn.execute_unprepared(&sql).await?;
+    }
+    drop_table(m, &format!("{}_history", normalize_table(table))).await
+}
+
+///
+/// Drop the history table of a system-versioned table, see
+/// [`drop_history_table`], skipping the parts that do not exist.
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_history_table_if_exists(m: &SchemaManager<'_>, table: &str) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    // postgres drops triggers `ON` their table, which has to exist
+    if backend != sea_orm::DatabaseBackend::Postgres || m.has_table(normalize_table(table)).await? {
+        let conn = m.get_connection();
+        for sql in drop_history_sql(backend, table) {
+            conn.execute_unprepared(&sql).await?;
+        }
+    }
+    drop_table_if_exists(m, &format!("{}_history", normalize_table(table))).await
+}
+
+/// The history table of `table`, along with the columns copied into it from
+/// a row of `table`.
+fn history_table_stmt(
+    table: &str,
+    cols: &[(&str, ColType)],
+    refs: &[(&str, &str)],
+    add_timestamps: bool,
+) -> (TableCreateStatement, Vec<String>) {
+    let mut stmt = Table::create()
+        .table(Alias::new(format!("{table}_history")))
+        .if_not_exists()
+        .take();
+    stmt.col(pk_auto(Alias::new("history_id")));
+
+    let mut columns = vec![(
+        "id".to_string(),
+        ColumnDef::new(Alias::new("id")).integer().not_null().take(),
+    )];
+    for (name, col_type) in cols {
+        if *name != "valid_from" {
+            columns.push(((*name).to_string(), history_col(name, col_type)));
+        }
+    }
+    for (from_tbl, ref_name) in refs {
+        let (from_tbl, is_nullable) = from_tbl
+            .strip_suffix('?')
+            .map_or((*from_tbl, false), |stripped| (stripped, true));
+        let from_tbl = from_tbl.split_once('.').map_or(from_tbl, |(_, tbl)| tbl);
+        let nz_ref_name = if ref_name.is_empty() {
+            reference_id(&normalize_table(from_tbl))
+        } else {
+            (*ref_name).to_string()
+        };
+        if !columns.iter().any(|(name, _)| *name == nz_ref_name) {
+            let col_type = if is_nullable {
+                ColType::IntegerNull
+            } else {
+                ColType::Integer
+            };
+            columns.push((nz_ref_name.clone(), history_col(&nz_ref_name, &col_type)));
+        }
+    }
+    let mut period = vec!["valid_from"];
+    if add_timestamps {
+        period.splice(0..0, ["created_at", "updated_at"]);
+    }
+    for name in period {
+        columns.push((name.to_string(), timestamp_with_time_zone(Alias::new(name))));
+    }
+
+    for (_, def) in &mut columns {
+        stmt.col(def);
+    }
+    stmt.col(timestamp_with_time_zone(Alias::new("valid_to")));
+    (stmt, columns.into_iter().map(|(name, _)| name).collect())
+}
+
+/// A column mirrored in a history table: its type and nullability only
+fn history_col(name: &str, col_type: &ColType) -> ColumnDef {
+    let def = col_type.to_def(Alias::new(name));
+    let mut col = def.get_column_type().map_or_else(
+        || ColumnDef::new(Alias::new(name)),
+        |col_type| ColumnDef::new_with_type(Alias::new(name), col_type.clone()),
+    );
+    if def
+        .get_column_spec()
+        .iter()
+        .any(|spec| matches!(spec, sea_query::ColumnSpec::NotNull))
+    {
+        col.not_null();
+    } else {
+        col.null();
+    }
+    col
+}
+
+/// The statements creating the triggers that copy the previous version of an
+/// updated or deleted row of `table` into its history table
+fn history_sql(backend: sea_orm::DatabaseBackend, table: &str, columns: &[String]) -> Vec<String> {
+    let q = |ident: &str| quote_ident(backend, ident);
+    let (table_q, history_q) = (q(table), q(&format!("{table}_history")));
+    let (update, delete) = (
+        q(&format!("{table}_history_update")),
+        q(&format!("{table}_history_delete")),
+    );
+    let (valid_from, id) = (q("valid_from"), q("id"));
+    let insert = format!(
+        "INSERT INTO {history_q} ({}, {}) VALUES ({}, CURRENT_TIMESTAMP)",
+        columns.iter().map(|c| q(c)).collect::<Vec<_>>().join(", "),
+        q("valid_to"),
+        columns
+            .iter()
+            .map(|c| format!("OLD.{}", q(c)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    match backend {
+        sea_orm::DatabaseBackend::Postgres => {
+            let func = q(&format!("{table}_history_version"));
+            vec![
+                format!(
+                    "CREATE OR REPLACE FUNCTION {func}() RETURNS trigger AS $$ BEGIN {insert}; IF \
+                     TG_OP = 'UPDATE' THEN NEW.{valid_from} := CURRENT_TIMESTAMP; RETURN NEW; END \
+                     IF; RETURN OLD; END; $$ LANGUAGE plpgsql"
+                ),
+                format!(
+                    "CREATE TRIGGER {update} BEFORE UPDATE ON {table_q} FOR EACH ROW EXECUTE \
+                     FUNCTION {func}()"
+                ),
+                format!(
+                    "CREATE TRIGGER {delete} BEFORE DELETE ON {table_q} FOR EACH ROW EXECUTE \
+                     FUNCTION {func}()"
+                ),
+            ]
+        }
+        sea_orm::DatabaseBackend::Sqlite => vec![
+            // a BEFORE trigger can not set NEW, the new version is stamped after
+            format!(
+                "CREATE TRIGGER {update} AFTER UPDATE ON {table_q} BEGIN {insert}; UPDATE \
+                 {table_q} SET {valid_from} = CURRENT_TIMESTAMP WHERE {id} = NEW.{id}; END"
+            ),
+            format!("CREATE TRIGGER {delete} AFTER DELETE ON {table_q} BEGIN {insert}; END"),
+        ],
+        sea_orm::DatabaseBackend::MySql => vec![
+            format!(
+                "CREATE TRIGGER {update} BEFORE UPDATE ON {table_q} FOR EACH ROW BEGIN {insert}; \
+                 SET NEW.{valid_from} = CURRENT_TIMESTAMP; END"
+            ),
+            format!("CREATE TRIGGER {delete} BEFORE DELETE ON {table_q} FOR EACH ROW {insert}"),
+        ],
+    }
+}
+
+/// The statements dropping the triggers of a history table
+fn drop_history_sql(backend: sea_orm::DatabaseBackend, table: &str) -> Vec<String> {
+    let nz_table = normalize_table(table);
+    let q = |ident: &str| quote_ident(backend, ident);
+    let update = q(&format!("{nz_table}_history_update"));
+    let delete = q(&format!("{nz_table}_history_delete"));
+    match backend {
+        sea_orm::DatabaseBackend::Postgres => {
+            let table_q = q(&nz_table);
+            vec![
+                format!("DROP TRIGGER IF EXISTS {update} ON {table_q}"),
+                format!("DROP TRIGGER IF EXISTS {delete} ON {table_q}"),
+                format!(
+                    "DROP FUNCTION IF EXISTS {}()",
+                    q(&format!("{nz_table}_history_version"))
+                ),
+            ]
+        }
+        sea_orm::DatabaseBackend::Sqlite | sea_orm::DatabaseBackend::MySql => vec![
+            format!("DROP TRIGGER IF EXISTS {update}"),
+            format!("DROP TRIGGER IF EXISTS {delete}"),
+        ],
+    }
+}
+
+///
+/// Drop a table
+/// ```ignore
+/// drop_table(m, "movies").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_table(m: &SchemaManager<'_>, table: &str) -> Result<(), DbErr> {
+    let nz_table = normalize_table(table);
+    m.drop_table(Table::drop().table(Alias::new(nz_table)).to_owned())
+        .await
+}
+
+///
+/// Drop a table when it exists, e.g. in a down migration that has to
+/// survive a partly rolled back schema
+/// ```ignore
+/// drop_table_if_exists(m, "movies").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_table_if_exists(m: &SchemaManager<'_>, table: &str) -> Result<(), DbErr> {
+    let nz_table = normalize_table(table);
+    m.drop_table(
+        Table::drop()
+            .table(Alias::new(nz_table))
+            .if_exists()
+            .to_owned(),
+    )
+    .await
+}
+
+///
+/// Create an enum type unless it exists, in a single `DO` block so that
+/// replaying the migration is safe. Only postgres has enum types, this does
+/// nothing on other backends.
+/// ```ignore
+/// create_enum_type_if_not_exists(m, "status", &["draft", "published"]).await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn create_enum_type_if_not_exists(
+    m: &SchemaManager<'_>,
+    enum_name: &str,
+    variants: &[&str],
+) -> Result<(), DbErr> {
+    if m.get_database_backend() != sea_orm::DatabaseBackend::Postgres {
+        return Ok(());
+    }
+    m.get_connection()
+        .execute_unprepared(&create_enum_type_if_not_exists_sql(enum_name, variants))
+        .await?;
+    Ok(())
+}
+
+fn create_enum_type_if_not_exists_sql(enum_name: &str, variants: &[&str]) -> String {
+    let literal = |value: &str| format!("'{}'", value.replace('\'', "''"));
+    let variants = variants
+        .iter()
+        .map(|variant| literal(variant))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "DO $$ BEGIN IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = {} AND typtype = 'e') \
+         THEN CREATE TYPE {} AS ENUM ({variants}); END IF; END $$",
+        literal(enum_name),
+        quote_ident(sea_orm::DatabaseBackend::Postgres, enum_name)
+    )
+}
+
+///
+/// Add enum values to an existing enum type
+/// ```ignore
+/// add_enum_values(m, "status_enum", vec!["suspended", "cancelled"]).await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn add_enum_values(
+    m: &SchemaManager<'_>,
+    enum_name: &str,
+    new_values: Vec<String>,
+) -> Result<(), DbErr> {
+    match m.get_database_backend() {
+        sea_orm::DatabaseBackend::Postgres => {
+            for value in new_values {
+                m.get_connection()
+                    .execute(sea_orm::Statement::from_string(
+                        sea_orm::DatabaseBackend::Postgres,
+                        format!("ALTER TYPE {enum_name} ADD VALUE '{value}'"),
+                    ))
+                    .await?;
+            }
+        }
+        sea_orm::DatabaseBackend::Sqlite => {
+            // SQLite doesn't support native enums, values are handled by CHECK constraints
+            tracing::info!(
+                "SQLite: Enum values are handled by CHECK constraints. No action needed."
+            );
+        }
+        sea_orm::DatabaseBackend::MySql => {
+            // MySQL handles enums differently
+            tracing::info!(
+                "MySQL: Enum values are handled by column definition. No action needed."
+            );
+        }
+    }
+    Ok(())
+}
+
+///
+/// Drop an enum type completely
+/// ```ignore
+/// drop_enum_type(m, "status_enum").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_enum_type(m: &SchemaManager<'_>, enum_name: &str) -> Result<(), DbErr> {
+    match m.get_database_backend() {
+        sea_orm::DatabaseBackend::Postgres => {
+            // First check if the enum type exists
+            let enum_exists = check_enum_exists(m, enum_name).await?;
+            if !enum_exists {
+                tracing::info!("Enum type '{}' does not exist, skipping drop", enum_name);
+                return Ok(());
+            }
+
+            // Try to drop the enum type with CASCADE to handle any remaining references
+            let query = format!("DROP TYPE IF EXISTS {enum_name} CASCADE");
+            m.get_connection()
+                .execute(sea_orm::Statement::from_string(
+                    sea_orm::DatabaseBackend::Postgres,
+                    query,
+                ))
+                .await?;
+        }
+        _ => {
+            // SQLite/MySQL don't have native enum types
+            tracing::info!("No native enum type to drop for this database backend.");
+        }
+    }
+    Ok(())
+}
+
+///
+/// Create a composite type of `fields`, given as their names and SQL types,
+/// for [`ColType::Composite`] columns. Postgres only.
+/// ```ignore
+/// create_composite_type(m, "address", &[("street", "text"), ("city", "text")]).await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn create_composite_type(
+    m: &SchemaManager<'_>,
+    type_name: &str,
+    fields: &[(&str, &str)],
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "composite types are only supported on postgres, not {backend:?}"
+        )));
+    }
+    m.get_connection()
+        .execute_unprepared(&composite_type_sql(type_name, fields))
+        .await?;
+    Ok(())
+}
+
+fn composite_type_sql(type_name: &str, fields: &[(&str, &str)]) -> String {
+    let backend = sea_orm::DatabaseBackend::Postgres;
+    let fields = fields
+        .iter()
+        .map(|(name, sql_type)| format!("{} {sql_type}", quote_ident(backend, name)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "CREATE TYPE {} AS ({fields})",
+        quote_ident(backend, type_name)
+    )
+}
+
+///
+/// Drop a composite type made with [`create_composite_type`]. The columns
+/// of that type have to be dropped first.
+/// ```ignore
+/// drop_composite_type(m, "address").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_composite_type(m: &SchemaManager<'_>, type_name: &str) -> Result<(), DbErr> {
+    let backend = sea_orm::DatabaseBackend::Postgres;
+    m.get_connection()
+        .execute_unprepared(&format!("DROP TYPE {}", quote_ident(backend, type_name)))
+        .await?;
+    Ok(())
+}
+
+///
+/// Drop a composite type like [`drop_composite_type`], unless it is already
+/// gone.
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_composite_type_if_exists(
+    m: &SchemaManager<'_>,
+    type_name: &str,
+) -> Result<(), DbErr> {
+    let backend = sea_orm::DatabaseBackend::Postgres;
+    m.get_connection()
+        .execute_unprepared(&format!(
+            "DROP TYPE IF EXISTS {}",
+            quote_ident(backend, type_name)
+        ))
+        .await?;
+    Ok(())
+}
+
+///
+/// Comments on an index, e.g. to document the queries it is for. Postgres
+/// only, other backends skip it.
+/// ```ignore
+/// comment_on_index(m, "idx-posts-slug", "looked up by the public urls").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn comment_on_index(
+    m: &SchemaManager<'_>,
+    index: &str,
+    comment: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        tracing::info!("Index comments are not supported on {backend:?}, skipping it");
+        return Ok(());
+    }
+    m.get_connection()
+        .execute_unprepared(&comment_sql(
+            &format!("INDEX {}", quote_ident(backend, index)),
+            comment,
+        ))
+        .await?;
+    Ok(())
+}
+
+///
+/// Comments on the foreign key constraint of a reference column, whatever
+/// name the constraint got. Postgres only, other backends skip it.
+/// ```ignore
+/// comment_on_foreign_key(m, "posts", "user_id", "the author of the post").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when there is no foreign key on the column
+pub async fn comment_on_foreign_key(
+    m: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+    comment: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        tracing::info!("Constraint comments are not supported on {backend:?}, skipping it");
+        return Ok(());
+    }
+    let nz_table = normalize_table(table);
+    let conn = m.get_connection();
+    let constraint = conn
+        .query_one(sea_orm::Statement::from_sql_and_values(
+            backend,
+            "SELECT con.conname FROM pg_constraint con JOIN pg_attribute att ON att.attrelid = \
+             con.conrelid AND att.attnum = ANY(con.conkey) WHERE con.contype = 'f' AND \
+             con.conrelid = to_regclass($1) AND att.attname = $2",
+            [quote_ident(backend, &nz_table).into(), column.into()],
+        ))
+        .await?
+        .ok_or_else(|| {
+            DbErr::Migration(format!(
+                "there is no foreign key on `{nz_table}`.`{column}` to comment on"
+            ))
+        })?
+        .try_get_by_index::<String>(0)?;
+    conn.execute_unprepared(&comment_sql(
+        &format!(
+            "CONSTRAINT {} ON {}",
+            quote_ident(backend, &constraint),
+            quote_ident(backend, &nz_table)
+        ),
+        comment,
+    ))
+    .await?;
+    Ok(())
+}
+
+///
+/// Adds a `CHECK` constraint to a table, e.g. one comparing two of its
+/// columns. `SQLite` cannot add a constraint to an existing table.
+/// ```ignore
+/// add_check(m, "bookings", "chk-bookings-start_date-end_date", "start_date <= end_date").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or on sqlite
+pub async fn add_check(
+    m: &SchemaManager<'_>,
+    table: &str,
+    name: &str,
+    expr: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend == sea_orm::DatabaseBackend::Sqlite {
+        return Err(DbErr::Migration(format!(
+            "check `{name}` cannot be added to an existing table on sqlite"
+        )));
+    }
+    m.get_connection()
+        .execute_unprepared(&add_check_sql(backend, table, name, expr))
+        .await?;
+    Ok(())
+}
+
+///
+/// Adds an exclusion constraint keeping the rows with equal `columns` from
+/// overlapping on the `range` column, e.g. two bookings of a room over
+/// intersecting periods. It enables `btree_gist`, whose operator classes
+/// let the GiST index behind the constraint compare plain columns. Postgres
+/// only.
+/// ```ignore
+/// add_no_overlap(m, "bookings", "excl-bookings-room_id-during", &["room_id"], "during").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn add_no_overlap(
+    m: &SchemaManager<'_>,
+    table: &str,
+    name: &str,
+    columns: &[&str],
+    range: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "exclusion constraints are only supported on postgres, not {backend:?}"
+        )));
+    }
+    let conn = m.get_connection();
+    conn.execute_unprepared("CREATE EXTENSION IF NOT EXISTS btree_gist")
+        .await?;
+    conn.execute_unprepared(&no_overlap_sql(table, name, columns, range))
+        .await?;
+    Ok(())
+}
+
+fn no_overlap_sql(table: &str, name: &str, columns: &[&str], range: &str) -> String {
+    let backend = sea_orm::DatabaseBackend::Postgres;
+    let elements = columns
+        .iter()
+        .map(|column| format!("{} WITH =", quote_ident(backend, column)))
+        .chain(std::iter::once(format!(
+            "{} WITH &&",
+            quote_ident(backend, range)
+        )))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "ALTER TABLE {} ADD CONSTRAINT {} EXCLUDE USING gist ({elements})",
+        quote_ident(backend, &normalize_table(table)),
+        quote_ident(backend, name)
+    )
+}
+
+///
+/// Drops a `CHECK` constraint added with [`add_check`].
+/// ```ignore
+/// drop_check(m, "bookings", "chk-bookings-start_date-end_date").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_check(m: &SchemaManager<'_>, table: &str, name: &str) -> Result<(), DbErr> {
+    m.get_connection()
+        .execute_unprepared(&drop_check_sql(m.get_database_backend(), table, name))
+        .await?;
+    Ok(())
+}
+
+///
+/// Drops a `CHECK` constraint, see [`drop_check`], when it and its table
+/// exist.
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_check_if_exists(
+    m: &SchemaManager<'_>,
+    table: &str,
+    name: &str,
+) -> Result<(), DbErr> {
+    let nz_table = normalize_table(table);
+    if !m.has_table(&nz_table).await? {
+        return Ok(());
+    }
+    let backend = m.get_database_backend();
+    let conn = m.get_connection();
+    let exists = match backend {
+        sea_orm::DatabaseBackend::MySql => conn
+            .query_one(sea_orm::Statement::from_sql_and_values(
+                backend,
+                "SELECT 1 FROM information_schema.table_constraints WHERE table_schema = \
+                 DATABASE() AND table_name = ? AND constraint_name = ?",
+                [nz_table.as_str().into(), name.into()],
+            ))
+            .await?
+            .is_some(),
+        sea_orm::DatabaseBackend::Postgres => conn
+            .query_one(sea_orm::Statement::from_sql_and_values(
+                backend,
+                "SELECT 1 FROM pg_constraint WHERE conrelid = to_regclass($1) AND conname = $2",
+                [quote_ident(backend, &nz_table).into(), name.into()],
+            ))
+            .await?
+            .is_some(),
+        sea_orm::DatabaseBackend::Sqlite => false,
+    };
+    if exists {
+        conn.execute_unprepared(&drop_check_sql(backend, table, name))
+            .await?;
+    }
+    Ok(())
+}
+
+///
+/// Adds a unique constraint that can be deferred, `DEFERRABLE INITIALLY
+/// IMMEDIATE`: it is checked after each statement, unless a transaction
+/// defers it to its commit with `SET CONSTRAINTS ALL DEFERRED`, e.g. to swap
+/// the values of two rows. Postgres only.
+/// ```ignore
+/// add_deferrable_unique(m, "slots", "idx-slots-position", &["position"]).await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn add_deferrable_unique(
+    m: &SchemaManager<'_>,
+    table: &str,
+    name: &str,
+    columns: &[&str],
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "deferrable unique constraints are only supported on postgres, not {backend:?}"
+        )));
+    }
+    m.get_connection()
+        .execute_unprepared(&deferrable_unique_sql(backend, table, name, columns))
+        .await?;
+    Ok(())
+}
+
+///
+/// Create a materialized view over the rows `query` selects. Postgres only.
+/// ```ignore
+/// create_materialized_view(m, "daily_sales", "SELECT day, sum(total) AS total FROM orders GROUP BY day").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails, or when the database is not Postgres
+pub async fn create_materialized_view(
+    m: &SchemaManager<'_>,
+    name: &str,
+    query: &str,
+) -> Result<(), DbErr> {
+    let backend = m.get_database_backend();
+    if backend != sea_orm::DatabaseBackend::Postgres {
+        return Err(DbErr::Migration(format!(
+            "materialized views are only supported on postgres, not {backend:?}"
+        )));
+    }
+    m.get_connection()
+        .execute_unprepared(&create_materialized_view_sql(backend, name, query))
+        .await?;
+    Ok(())
+}
+
+///
+/// Drop a materialized view
+/// ```ignore
+/// drop_materialized_view(m, "daily_sales").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_materialized_view(m: &SchemaManager<'_>, name: &str) -> Result<(), DbErr> {
+    m.get_connection()
+        .execute_unprepared(&drop_materialized_view_sql(
+            m.get_database_backend(),
+            name,
+            false,
+        ))
+        .await?;
+    Ok(())
+}
+
+///
+/// Drop a materialized view when it exists
+/// ```ignore
+/// drop_materialized_view_if_exists(m, "daily_sales").await;
+/// ```
+///
+/// # Errors
+/// fails when it fails
+pub async fn drop_materialized_view_if_exists(
+    m: &SchemaManager<'_>,
+    name: &str,
+) -> Result<(), DbErr> {
+    m.get_connection()
+        .execute_unprepared(&drop_materialized_view_sql(
+            m.get_database_backend(),
+            name,
+            true,
+        ))
+        .await?;
+    Ok(())
+}
+
+fn create_materialized_view_sql(
+    backend: sea_orm::DatabaseBackend,
+    name: &str,
+    query: &str,
+) -> String {
+    format!(
+        "CREATE MATERIALIZED VIEW {} AS {}",
+        quote_ident(backend, &normalize_table(name)),
+        query.trim().trim_end_matches(';')
+    )
+}
+
+fn drop_materialized_view_sql(
+    backend: sea_orm::DatabaseBackend,
+    name: &str,
+    if_exists: bool,
+) -> String {
+    format!(
+        "DROP MATERIALIZED VIEW {}{}",
+        if if_exists { "IF EXISTS " } else { "" },
+        quote_ident(backend, &normalize_table(name))
+    )
+}
+
+fn deferrable_unique_sql(
+    backend: sea_orm::DatabaseBackend,
+    table: &str,
+    name: &str,
+    columns: &[&str],
+) -> String {
+    format!(
+        "ALTER TABLE {} ADD CONSTRAINT {} UNIQUE ({}) DEFERRABLE INITIALLY IMMEDIATE",
+        quote_ident(backend, &normalize_table(table)),
+        quote_ident(backend, name),
+        columns
+            .iter()
+            .map(|column| quote_ident(backend, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+fn add_check_sql(backend: sea_orm::DatabaseBackend, table: &str, name: &str, expr: &str) -> String {
+    format!(
+        "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({expr})",
+        quote_ident(backend, &normalize_table(table)),
+        quote_ident(backend, name)
+    )
+}
+
+/// `MySQL` drops a check as such, postgres as any constraint
+fn drop_check_sql(backend: sea_orm::DatabaseBackend, table: &str, name: &str) -> String {
+    let constraint = if backend == sea_orm::DatabaseBackend::MySql {
+        "CHECK"
+    } else {
+        "CONSTRAINT"
+    };
+    format!(
+        "ALTER TABLE {} DROP {constraint} {}",
+        quote_ident(backend, &normalize_table(table)),
+        quote_ident(backend, name)
+    )
+}
+
+/// `COMMENT ON <target> IS '<comment>'`, with the comment quoted as a string
+fn comment_sql(target: &str, comment: &str) -> String {
+    format!("COMMENT ON {target} IS '{}'", comment.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn managed_timestamps_are_timestamptz() {
+        let stmt = table_auto_tz(Alias::new("users"));
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE IF NOT EXISTS "users" ( "created_at" timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP, "updated_at" timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP )"#
+        );
+    }
+
+    #[test]
+    fn can_build_uuid_with_function_default_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("users"))
+            .col(
+                ColType::UuidUniqWithDefault("gen_random_uuid()".to_string())
+                    .to_def(Alias::new("external_id")),
+            )
+            .to_owned();
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "users" ( "external_id" uuid NOT NULL UNIQUE DEFAULT gen_random_uuid() )"#
+        );
+    }
+
+    #[test]
+    fn can_build_concurrent_index_sql() {
+        let index = Index::create()
+            .name("idx-movies-title")
+            .table(Alias::new("movies"))
+            .col(Alias::new("title"))
+            .unique()
+            .to_owned();
+
+        assert_eq!(
+            concurrent_index_sql(&index),
+            r#"CREATE UNIQUE INDEX CONCURRENTLY "idx-movies-title" ON "movies" ("title")"#
+        );
+    }
+
+    #[test]
+    fn can_build_collated_column_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("users"))
+            .col(ColType::String.collate("C").to_def(Alias::new("name")))
+            .to_owned();
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "users" ( "name" varchar NOT NULL COLLATE "C" )"#
+        );
+    }
+
+    #[test]
+    fn can_build_checked_phone_column_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("users"))
+            .col(
+                ColType::Phone
+                    .check(r#""phone" ~ '^\+[1-9][0-9]{1,14}$'"#)
+                    .to_def(Alias::new("phone")),
+            )
+            .to_owned();
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "users" ( "phone" varchar(16) NOT NULL CHECK ("phone" ~ '^\+[1-9][0-9]{1,14}$') )"#
+        );
+    }
+
+    #[test]
+    fn can_build_without_rowid_table_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("settings"))
+            .col(ColType::PkAuto.to_def(Alias::new("id")))
+            .col(ColType::Text.to_def(Alias::new("value")))
+            .to_owned();
+
+        assert_eq!(
+            without_rowid_sql(&stmt),
+            r#"CREATE TABLE "settings" ( "id" integer NOT NULL PRIMARY KEY, "value" text NOT NULL ) WITHOUT ROWID"#
+        );
+    }
+
+    #[test]
+    fn can_build_nullable_column_with_default_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("projects"))
+            .col(ColType::IntegerNullWithDefault(1).to_def(Alias::new("owner_id")))
+            .to_owned();
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "projects" ( "owner_id" integer NULL DEFAULT 1 )"#
+        );
+    }
+
+    #[test]
+    fn can_build_array_with_empty_default_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("posts"))
+            .col(ColType::array_with_empty_default(ArrayColType::String).to_def(Alias::new("tags")))
+            .to_owned();
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "posts" ( "tags" varchar[] NOT NULL DEFAULT '{}' )"#
+        );
+    }
+
+    #[test]
+    fn can_build_timestamp_with_default_expr_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("events"))
+            .col(
+                ColType::TimestampWithTimeZoneWithDefaultExpr("now()".to_string())
+                    .to_def(Alias::new("created_at")),
+            )
+            .to_owned();
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "events" ( "created_at" timestamp with time zone NOT NULL DEFAULT now() )"#
+        );
+    }
+
+    #[test]
+    fn can_build_strict_table_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("movies"))
+            .col(ColType::PkAuto.to_def(Alias::new("id")))
+            .col(ColType::String.to_def(Alias::new("title")))
+            .col(ColType::BigIntegerNull.to_def(Alias::new("views")))
+            .col(ColType::Boolean.to_def(Alias::new("released")))
+            .col(ColType::Double.to_def(Alias::new("rating")))
+            .col(ColType::BlobNull.to_def(Alias::new("poster")))
+            .col(ColType::TimestampWithTimeZone.to_def(Alias::new("released_at")))
+            .to_owned();
+
+        assert_eq!(
+            strict_sql(&stmt),
+            r#"CREATE TABLE "movies" ( "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT, "title" TEXT NOT NULL, "views" INTEGER NULL, "released" ANY NOT NULL, "rating" REAL NOT NULL, "poster" BLOB NULL, "released_at" TEXT NOT NULL ) STRICT"#
+        );
+    }
+
+    #[test]
+    fn can_build_strict_without_rowid_table_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("settings"))
+            .col(ColType::PkAuto.to_def(Alias::new("id")))
+            .col(ColType::Text.to_def(Alias::new("value")))
+            .to_owned();
+
+        assert_eq!(
+            strict_without_rowid_sql(&stmt),
+            r#"CREATE TABLE "settings" ( "id" INTEGER NOT NULL PRIMARY KEY, "value" TEXT NOT NULL ) STRICT, WITHOUT ROWID"#
+        );
+    }
+
+    #[test]
+    fn can_build_collated_index_sql() {
+        assert_eq!(
+            collated_index("products", "name", "und-x-icu").to_string(PostgresQueryBuilder),
+            r#"CREATE INDEX "idx-products-name-und-x-icu" ON "products" (("name" COLLATE "und-x-icu"))"#
+        );
+    }
+
+    #[test]
+    fn can_build_identity_with_sequence_sql() {
+        let stmt = Table::create()
+            .table(Alias::new("orders"))
+            .col(
+                ColType::PkIdentityWithSequence(IdentitySequence::new().start(1000).cache(20))
+                    .to_def(Alias::new("id")),
+            )
+            .to_owned();
+
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "orders" ( "id" integer NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY (START WITH 1000 CACHE 20) )"#
+        );
+    }
+
+    #[test]
+    fn can_build_drop_table_if_exists_sql() {
+        assert_eq!(
+            Table::drop()
+                .table(Alias::new("movies"))
+                .if_exists()
+                .to_owned()
+                .to_string(PostgresQueryBuilder),
+            r#"DROP TABLE IF EXISTS "movies""#
+        );
+    }
+
+    #[test]
+    fn can_build_comment_sql() {
+        assert_eq!(
+            comment_sql(r#"INDEX "idx-posts-slug""#, "the post's url"),
+            r#"COMMENT ON INDEX "idx-posts-slug" IS 'the post''s url'"#
+        );
+    }
+
+    #[test]
+    fn can_build_check_sql() {
+        let backend = sea_orm::DatabaseBackend::Postgres;
+        assert_eq!(
+            add_check_sql(
+                backend,
+                "bookings",
+                "chk-bookings-start_date-end_date",
+                "start_date <= end_date"
+            ),
+            r#"ALTER TABLE "bookings" ADD CONSTRAINT "chk-bookings-start_date-end_date" CHECK (start_date <= end_date)"#
+        );
+        assert_eq!(
+            drop_check_sql(backend, "bookings", "chk-bookings-start_date-end_date"),
+            r#"ALTER TABLE "bookings" DROP CONSTRAINT "chk-bookings-start_date-end_date""#
+        );
+        assert_eq!(
+            drop_check_sql(
+                sea_orm::DatabaseBackend::MySql,
+                "bookings",
+                "chk-bookings-start_date-end_date"
+            ),
+            "ALTER TABLE `bookings` DROP CHECK `chk-bookings-start_date-end_date`"
+        );
+    }
+
+    #[test]
+    fn can_build_deferrable_unique_sql() {
+        assert_eq!(
+            deferrable_unique_sql(
+                sea_orm::DatabaseBackend::Postgres,
+                "slots",
+                "idx-slots-board_id-position",
+                &["board_id", "position"]
+            ),
+            r#"ALTER TABLE "slots" ADD CONSTRAINT "idx-slots-board_id-position" UNIQUE ("board_id", "position") DEFERRABLE INITIALLY IMMEDIATE"#
+        );
+    }
+
+    #[test]
+    fn can_build_materialized_view_sql() {
+        assert_eq!(
+            create_materialized_view_sql(
+                sea_orm::DatabaseBackend::Postgres,
+                "daily_sales",
+                "SELECT day, sum(total) AS total FROM orders GROUP BY day;\n"
+            ),
+            r#"CREATE MATERIALIZED VIEW "daily_sales" AS SELECT day, sum(total) AS total FROM orders GROUP BY day"#
+        );
+        assert_eq!(
+            drop_materialized_view_sql(sea_orm::DatabaseBackend::Postgres, "daily_sales", true),
+            r#"DROP MATERIALIZED VIEW IF EXISTS "daily_sales""#
+        );
+    }
+
+    #[test]
+    fn can_build_guarded_enum_type_sql() {
+        assert_eq!(
+            create_enum_type_if_not_exists_sql("status", &["draft", "won't"]),
+            r#"DO $$ BEGIN IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'status' AND typtype = 'e') THEN CREATE TYPE "status" AS ENUM ('draft', 'won''t'); END IF; END $$"#
+        );
+    }
+
+    #[test]
+    fn can_default_to_a_literal() {
+        let mut stmt = Table::create().table(Alias::new("posts")).take();
+        stmt.col(
+            ColType::StringNull
+                .default("in 'review'")
+                .to_def(Alias::new("status")),
+        )
+        .col(ColType::Integer.default(0).to_def(Alias::new("views")))
+        .col(
+            ColType::BooleanNull
+                .default(true)
+                .to_def(Alias::new("published")),
+        );
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "posts" ( "status" varchar NULL DEFAULT E'in \'review\'', "views" integer NOT NULL DEFAULT 0, "published" bool NULL DEFAULT TRUE )"#
+        );
+    }
+
+    #[test]
+    fn can_share_the_primary_key_of_a_reference() {
+        let mut stmt = Table::create().table(Alias::new("user_profiles")).take();
+        stmt.col(ColType::PkInteger.to_def(Alias::new("user_id")))
+            .foreign_key(
+                sea_query::ForeignKey::create()
+                    .from(Alias::new("user_profiles"), Alias::new("user_id"))
+                    .to(Alias::new("users"), Alias::new("id")),
+            );
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "user_profiles" ( "user_id" integer NOT NULL PRIMARY KEY, FOREIGN KEY ("user_id") REFERENCES "users" ("id") )"#
+        );
+    }
+
+    #[test]
+    fn can_default_to_a_sequence() {
+        let mut stmt = Table::create().table(Alias::new("invoices")).take();
+        stmt.col(
+            ColType::IntegerUniqWithDefaultExpr("nextval('billing.numbers')".to_string())
+                .to_def(Alias::new("number")),
+        );
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE "invoices" ( "number" integer NOT NULL UNIQUE DEFAULT nextval('billing.numbers') )"#
+        );
+        assert_eq!(sequence_ident("billing.numbers"), r#""billing"."numbers""#);
+    }
+
+    #[test]
+    fn can_build_history_table() {
+        let (stmt, columns) = history_table_stmt(
+            "movies",
+            &[
+                ("title", ColType::StringUniq),
+                ("rating", ColType::IntegerWithDefault(0)),
+                (
+                    "valid_from",
+                    ColType::TimestampWithTimeZoneWithDefaultExpr("now()".to_string()),
+                ),
+            ],
+            &[("user", ""), ("studio?", "")],
+            true,
+        );
+        assert_eq!(
+            stmt.to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE IF NOT EXISTS "movies_history" ( "history_id" serial NOT NULL PRIMARY KEY, "id" integer NOT NULL, "title" varchar NOT NULL, "rating" integer NOT NULL, "user_id" integer NOT NULL, "studio_id" integer NULL, "created_at" timestamp with time zone NOT NULL, "updated_at" timestamp with time zone NOT NULL, "valid_from" timestamp with time zone NOT NULL, "valid_to" timestamp with time zone NOT NULL )"#
+        );
+        assert_eq!(
+            history_sql(sea_orm::DatabaseBackend::Sqlite, "movies", &columns[..2])[1],
+            r#"CREATE TRIGGER "movies_history_delete" AFTER DELETE ON "movies" BEGIN INSERT INTO "movies_history" ("id", "title", "valid_to") VALUES (OLD."id", OLD."title", CURRENT_TIMESTAMP); END"#
+        );
+    }
+
+    #[test]
+    fn can_build_tablespace_index_sql() {
+        let index = Index::create()
+            .name("idx-movies-title")
+            .table(Alias::new("movies"))
+            .col(Alias::new("title"))
+            .to_owned();
+        assert_eq!(
+            tablespace_index_sql(&index, "fast_ssd"),
+            r#"CREATE INDEX "idx-movies-title" ON "movies" ("title") TABLESPACE "fast_ssd""#
+        );
+
+        use sea_orm::sea_query::ConditionalStatement;
+        let partial = index
+            .clone()
+            .and_where(Expr::col(Alias::new("title")).is_not_null())
+            .to_owned();
+        assert_eq!(
+            tablespace_index_sql(&partial, "fast_ssd"),
+            r#"CREATE INDEX "idx-movies-title" ON "movies" ("title") TABLESPACE "fast_ssd" WHERE "title" IS NOT NULL"#
+        );
+    }
+
+    #[test]
+    fn can_build_concurrent_tablespace_index_sql() {
+        let index = Index::create()
+            .name("idx-movies-title")
+            .table(Alias::new("movies"))
+            .col(Alias::new("title"))
+            .unique()
+            .to_owned();
+        assert_eq!(
+            concurrent_tablespace_index_sql(&index, "fast_ssd"),
+            r#"CREATE UNIQUE INDEX CONCURRENTLY "idx-movies-title" ON "movies" ("title") TABLESPACE "fast_ssd""#
+        );
+    }
+
+    #[test]
+    fn can_build_enum_check_sql() {
+        assert_eq!(
+            enum_check_sql("status", &["draft".to_string(), "published".to_string()]),
+            r#""status" IN ('draft', 'published')"#
+        );
+        let col_type = ColType::EnumWithDefault(
+            "status".to_string(),
+            vec!["draft".to_string(), "published".to_string()],
+            "draft".to_string(),
+        )
+        .check(r#""status" <> 'published'"#);
+        let sql = Table::create()
+            .table(Alias::new("posts"))
+            .col(col_type.to_backend_def(sea_orm::DatabaseBackend::Sqlite, "status"))
+            .to_string(sea_query::SqliteQueryBuilder);
+        assert!(
+            sql.contains(r#"CHECK ("status" IN ('draft', 'published'))"#),
+            "{sql}"
+        );
+        let sql = Table::create()
+            .table(Alias::new("posts"))
+            .col(col_type.to_backend_def(sea_orm::DatabaseBackend::Postgres, "status"))
+            .to_string(sea_query::PostgresQueryBuilder);
+        assert!(!sql.contains(" IN ("), "{sql}");
+    }
+
+    #[test]
+    fn can_build_no_overlap_sql() {
+        assert_eq!(
+            no_overlap_sql(
+                "bookings",
+                "excl-bookings-room_id-during",
+                &["room_id"],
+                "during"
+            ),
+            r#"ALTER TABLE "bookings" ADD CONSTRAINT "excl-bookings-room_id-during" EXCLUDE USING gist ("room_id" WITH =, "during" WITH &&)"#
+        );
+    }
+
+    #[test]
+    fn can_build_composite_type_sql() {
+        assert_eq!(
+            composite_type_sql("address", &[("street", "text"), ("zip", "varchar(10)")]),
+            r#"CREATE TYPE "address" AS ("street" text, "zip" varchar(10))"#
+        );
+    }
+
+    #[test]
+    fn can_build_column_storage_sql() {
+        assert_eq!(
+            column_storage_sql("documents", "body", "external").unwrap(),
+            r#"ALTER TABLE "documents" ALTER COLUMN "body" SET STORAGE EXTERNAL"#
+        );
+        assert!(column_storage_sql("documents", "body", "EXTERNAL; DROP").is_err());
+    }
+
+    #[test]
+    fn can_build_denormalized_column_sql() {
+        let sql = denormalized_column_sql(
+            sea_orm::DatabaseBackend::Sqlite,
+            "users",
+            "full_name",
+            &["first_name", "last_name"],
+        );
+        assert_eq!(
+            sql,
+            vec![
+                r#"UPDATE "users" SET "full_name" = concat_ws(' ', "first_name", "last_name")"#,
+                r#"CREATE TRIGGER "users_full_name_insert" AFTER INSERT ON "users" BEGIN UPDATE "users" SET "full_name" = concat_ws(' ', NEW."first_name", NEW."last_name") WHERE rowid = NEW.rowid; END"#,
+                r#"CREATE TRIGGER "users_full_name_update" AFTER UPDATE OF "first_name", "last_name" ON "users" BEGIN UPDATE "users" SET "full_name" = concat_ws(' ', NEW."first_name", NEW."last_name") WHERE rowid = NEW.rowid; END"#,
+            ]
+        );
+
+        let sql = denormalized_column_sql(
+            sea_orm::DatabaseBackend::Postgres,
+            "users",
+            "full_name",
+            &["first_name", "last_name"],
+        );
+        assert_eq!(sql.len(), 4);
+        assert!(sql[1].contains(
+            r#"NEW."full_name" := concat_ws(' ', NEW."first_name", NEW."last_name"); RETURN NEW;"#
+        ));
+        assert_eq!(
+            sql[3],
+            r#"CREATE TRIGGER "users_full_name_update" BEFORE UPDATE ON "users" FOR EACH ROW EXECUTE FUNCTION "users_full_name_denormalize"()"#
+        );
+
+        assert_eq!(
+            drop_denormalized_column_sql(sea_orm::DatabaseBackend::MySql, "users", "full_name"),
+            vec![
+                "DROP TRIGGER IF EXISTS `users_full_name_insert`",
+                "DROP TRIGGER IF EXISTS `users_full_name_update`",
+            ]
+        );
+    }
+
+    #[test]
+    fn can_build_counter_cache_sql() {
+        let sql = counter_cache_sql(
+            sea_orm::DatabaseBackend::Sqlite,
+            "posts",
+            "comments_count",
+            "comments",
+            "post_id",
+        );
+        assert_eq!(
+            sql,
+            vec![
+                r#"UPDATE "posts" SET "comments_count" = (SELECT COUNT(*) FROM "comments" WHERE "comments"."post_id" = "posts"."id")"#,
+                r#"CREATE TRIGGER "comments_comments_count_inc" AFTER INSERT ON "comments" BEGIN UPDATE "posts" SET "comments_count" = "comments_count" + 1 WHERE "id" = NEW."post_id"; END"#,
+                r#"CREATE TRIGGER "comments_comments_count_dec" AFTER DELETE ON "comments" BEGIN UPDATE "posts" SET "comments_count" = "comments_count" - 1 WHERE "id" = OLD."post_id"; END"#,
+            ]
+        );
+
+        let sql = counter_cache_sql(
+            sea_orm::DatabaseBackend::Postgres,
+            "posts",
+            "comments_count",
+            "comments",
+            "post_id",
+        );
+        assert_eq!(sql.len(), 4);
+        assert!(sql[1].contains(r#"UPDATE "posts" SET "comments_count" = "comments_count" + 1 WHERE "id" = NEW."post_id""#));
+        assert!(sql[1].contains(r#"UPDATE "posts" SET "comments_count" = "comments_count" - 1 WHERE "id" = OLD."post_id""#));
+        assert_eq!(
+            sql[2],
+            r#"CREATE TRIGGER "comments_comments_count_inc" AFTER INSERT ON "comments" FOR EACH ROW EXECUTE FUNCTION "comments_comments_count_counter"()"#
+        );
+
+        assert_eq!(
+            drop_counter_cache_sql(
+                sea_orm::DatabaseBackend::MySql,
+                "comments_count",
+                "comments"
+            ),
+            vec![
+                "DROP TRIGGER IF EXISTS `comments_comments_count_inc`",
+                "DROP TRIGGER IF EXISTS `comments_comments_count_dec`",
+            ]
+        );
+    }
+
+    #[test]
+    fn can_build_unlogged_table_sql() {
+        let stmt = table_auto_tz(Alias::new("events"))
+            .col(ColType::PkAuto.to_def(Alias::new("id")))
+            .to_owned();
+
+        assert!(unlogged_sql(&stmt).starts_with("CREATE UNLOGGED TABLE IF NOT EXISTS \"events\""));
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -15,7 +15,7 @@ pub mod mem;
 pub mod null;
 pub mod opendal_adapter;
 
-use super::{stream::BytesStream, StorageResult};
+use super::{stream::BytesStream, StorageError, StorageResult};
 
 #[derive(Debug)]
 pub struct UploadResponse {
@@ -23,6 +23,17 @@ pub struct UploadResponse {
     pub version: Option<String>,
 }
 
+/// A URL granting a client direct access to a file for a limited time, e.g.
+/// to upload it or download it without proxying its bytes through the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedUrl {
+    /// The HTTP method the client has to use, e.g. `GET` or `PUT`
+    pub method: String,
+    pub url: String,
+    /// The headers the client has to send along
+    pub headers: Vec<(String, String)>,
+}
+
 /// TODO: Add more methods to `GetResponse` to read the content in different
 /// ways
 ///
@@ -99,6 +110,17 @@ pub trait StoreDriver: Sync + Send {
     /// Returns a `StorageResult` indicating the success of the copy operation.
     async fn copy(&self, from: &Path, to: &Path) -> StorageResult<()>;
 
+    /// Checks that the store can be reached, for readiness checks. Stores that
+    /// cannot tell, such as the null store, return
+    /// [`StorageError::Unsupported`] and are not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StorageError` when the store cannot be reached.
+    async fn ping(&self) -> StorageResult<()> {
+        Err(StorageError::Unsupported("ping".to_string()))
+    }
+
     /// Checks if the content exists at the specified path in the object store.
     ///
     /// # Errors
@@ -149,4 +171,36 @@ pub trait StoreDriver: Sync + Send {
             .map_err(|e| super::StorageError::Any(Box::new(e)))?;
         self.upload(path, &bytes).await
     }
+
+    /// Presigns a URL to download the file at the specified path directly from
+    /// the store, valid for `expiry`.
+    ///
+    /// # Default Implementation
+    ///
+    /// The default implementation returns [`StorageError::Unsupported`].
+    ///
+    /// # Errors
+    ///
+    /// When the store cannot presign URLs or fails to
+    async fn presigned_url(&self, _path: &Path, _expiry: Duration) -> StorageResult<PresignedUrl> {
+        Err(StorageError::Unsupported("presigned URLs".to_string()))
+    }
+
+    /// Presigns a URL to upload a file at the specified path directly to the
+    /// store, valid for `expiry`.
+    ///
+    /// # Default Implementation
+    ///
+    /// The default implementation returns [`StorageError::Unsupported`].
+    ///
+    /// # Errors
+    ///
+    /// When the store cannot presign URLs or fails to
+    async fn presigned_upload_url(
+        &self,
+        _path: &Path,
+        _expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        Err(StorageError::Unsupported("presigned URLs".to_string()))
+    }
 }
@@ -1,15 +1,20 @@
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures_util::{SinkExt, StreamExt};
-use opendal::{layers::RetryLayer, Operator};
+use opendal::{layers::RetryLayer, raw::PresignedRequest, Operator};
 
-use super::{GetResponse, StoreDriver, UploadResponse};
+use super::{GetResponse, PresignedUrl, StoreDriver, UploadResponse};
 use crate::storage::{stream::BytesStream, StorageError, StorageResult};
 
 pub struct OpendalAdapter {
     opendal_impl: Operator,
+    /// The size of the parts a streamed upload is sent in, the service's
+    /// default when `None`
+    part_size: Option<usize>,
+    /// How many parts of a streamed upload are sent concurrently
+    concurrent_parts: usize,
 }
 
 impl OpendalAdapter {
@@ -19,7 +24,36 @@ impl OpendalAdapter {
         let opendal_impl = opendal_impl
             // Add retry layer with default settings
             .layer(RetryLayer::default().with_jitter());
-        Self { opendal_impl }
+        Self {
+            opendal_impl,
+            part_size: None,
+            concurrent_parts: 1,
+        }
+    }
+
+    /// Streams uploads in parts of `part_size` bytes, sending up to
+    /// `concurrent_parts` of them at once, e.g. as the parts of an S3
+    /// multipart upload. The parts of a stream are buffered in memory until
+    /// sent.
+    #[must_use]
+    pub fn with_multipart(mut self, part_size: usize, concurrent_parts: usize) -> Self {
+        self.part_size = Some(part_size);
+        self.concurrent_parts = concurrent_parts.max(1);
+        self
+    }
+
+    fn presigned(request: &PresignedRequest) -> PresignedUrl {
+        PresignedUrl {
+            method: request.method().to_string(),
+            url: request.uri().to_string(),
+            headers: request
+                .header()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+        }
     }
 }
 
@@ -125,6 +159,15 @@ impl StoreDriver for OpendalAdapter {
         }
     }
 
+    /// Checks that the service can be reached and the store listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StorageError` when the service cannot be reached.
+    async fn ping(&self) -> StorageResult<()> {
+        Ok(self.opendal_impl.check().await?)
+    }
+
     /// Checks if the content exists at the specified path in the object store.
     ///
     /// # Errors
@@ -161,8 +204,13 @@ impl StoreDriver for OpendalAdapter {
     ) -> StorageResult<UploadResponse> {
         let path_str = path.display().to_string();
 
-        // Create writer with OpenDAL's native API
-        let mut writer = self.opendal_impl.writer(&path_str).await?;
+        // Create writer with OpenDAL's native API, which sends the stream as a
+        // multipart upload where the service supports it
+        let mut writer = self.opendal_impl.writer_with(&path_str);
+        if let Some(part_size) = self.part_size {
+            writer = writer.chunk(part_size);
+        }
+        let mut writer = writer.concurrent(self.concurrent_parts).await?;
 
         // Stream data directly to the writer using native write method
         let mut stream = Box::pin(stream);
@@ -179,4 +227,30 @@ impl StoreDriver for OpendalAdapter {
             version: meta.version().map(std::string::ToString::to_string),
         })
     }
+
+    async fn presigned_url(&self, path: &Path, expiry: Duration) -> StorageResult<PresignedUrl> {
+        if !self.opendal_impl.info().full_capability().presign_read {
+            return Err(StorageError::Unsupported("presigned URLs".to_string()));
+        }
+        let request = self
+            .opendal_impl
+            .presign_read(&path.display().to_string(), expiry)
+            .await?;
+        Ok(Self::presigned(&request))
+    }
+
+    async fn presigned_upload_url(
+        &self,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        if !self.opendal_impl.info().full_capability().presign_write {
+            return Err(StorageError::Unsupported("presigned URLs".to_string()));
+        }
+        let request = self
+            .opendal_impl
+            .presign_write(&path.display().to_string(), expiry)
+            .await?;
+        Ok(Self::presigned(&request))
+    }
 }
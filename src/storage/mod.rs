@@ -17,11 +17,15 @@ pub mod stream;
 use std::{
     collections::BTreeMap,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use bytes::Bytes;
 
-use self::{drivers::StoreDriver, stream::BytesStream};
+use self::{
+    drivers::{PresignedUrl, StoreDriver},
+    stream::BytesStream,
+};
 
 #[derive(thiserror::Error, Debug)]
 #[allow(clippy::module_name_repetitions)]
@@ -38,6 +42,9 @@ pub enum StorageError {
     #[error("secondaries errors")]
     Multi(BTreeMap<String, String>),
 
+    #[error("{0} not supported by the store")]
+    Unsupported(String),
+
     #[error(transparent)]
     Any(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
@@ -454,4 +461,37 @@ impl Storage {
     ) -> StorageResult<()> {
         strategy.upload_stream(self, path, stream).await
     }
+
+    /// Presigns a URL for clients to download the given path directly from the
+    /// store, valid for `expiry`. The strategy decides which store signs it.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the store cannot presign URLs, e.g. the
+    /// local and in-memory stores, or if there is an issue with the strategy
+    /// configuration.
+    pub async fn presigned_url(
+        &self,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        self.strategy.presigned_url(self, path, expiry).await
+    }
+
+    /// Presigns a URL for clients to upload to the given path directly, valid
+    /// for `expiry`. The strategy decides which store signs it, so a mirror or
+    /// backup strategy does not copy the file to its secondaries.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the store cannot presign URLs, e.g. the
+    /// local and in-memory stores, or if there is an issue with the strategy
+    /// configuration.
+    pub async fn presigned_upload_url(
+        &self,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        self.strategy.presigned_upload_url(self, path, expiry).await
+    }
 }
@@ -21,11 +21,16 @@
 //!
 //! * `download`: Initiates the download of the given path only from primary
 //!   storage.
-use std::{collections::BTreeMap, path::Path};
+//!
+//! * `presigned_url`/`presigned_upload_url`: Presigns the URL with the primary
+//!   storage only. A file uploaded through it is not backed up.
+use std::{collections::BTreeMap, path::Path, time::Duration};
 
 use bytes::Bytes;
 
-use crate::storage::{strategies::StorageStrategy, Storage, StorageError, StorageResult};
+use crate::storage::{
+    drivers::PresignedUrl, strategies::StorageStrategy, Storage, StorageError, StorageResult,
+};
 
 /// Enum representing the failure mode for the [`BackupStrategy`].
 #[derive(Clone, Debug)]
@@ -256,6 +261,41 @@ impl StorageStrategy for BackupStrategy {
 
         Ok(())
     }
+
+    /// Presigns a download URL from the primary storage
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StorageResult`] with the presigned URL
+    async fn presigned_url(
+        &self,
+        storage: &Storage,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        storage
+            .as_store_err(&self.primary)?
+            .presigned_url(path, expiry)
+            .await
+    }
+
+    /// Presigns an upload URL to the primary storage only,
+    /// which the secondary storages do not back up
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StorageResult`] with the presigned URL
+    async fn presigned_upload_url(
+        &self,
+        storage: &Storage,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        storage
+            .as_store_err(&self.primary)?
+            .presigned_upload_url(path, expiry)
+            .await
+    }
 }
 
 impl BackupStrategy {
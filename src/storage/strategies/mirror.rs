@@ -20,11 +20,16 @@
 //!   primary, it looks for the content in the secondary storages. If the
 //!   content is not found in any storage backend (both primary and secondary),
 //!   it returns an error.
-use std::{collections::BTreeMap, path::Path};
+//!
+//! * `presigned_url`/`presigned_upload_url`: Presigns the URL with the primary
+//!   storage only. A file uploaded through it is not mirrored.
+use std::{collections::BTreeMap, path::Path, time::Duration};
 
 use bytes::Bytes;
 
-use crate::storage::{strategies::StorageStrategy, Storage, StorageError, StorageResult};
+use crate::storage::{
+    drivers::PresignedUrl, strategies::StorageStrategy, Storage, StorageError, StorageResult,
+};
 
 /// Enum representing the failure mode for the [`MirrorStrategy`].
 #[derive(Clone, Debug)]
@@ -288,6 +293,41 @@ impl StorageStrategy for MirrorStrategy {
 
         Ok(())
     }
+
+    /// Presigns a download URL from the primary storage
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StorageResult`] with the presigned URL
+    async fn presigned_url(
+        &self,
+        storage: &Storage,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        storage
+            .as_store_err(&self.primary)?
+            .presigned_url(path, expiry)
+            .await
+    }
+
+    /// Presigns an upload URL to the primary storage only,
+    /// which the secondary storages do not mirror
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StorageResult`] with the presigned URL
+    async fn presigned_upload_url(
+        &self,
+        storage: &Storage,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        storage
+            .as_store_err(&self.primary)?
+            .presigned_upload_url(path, expiry)
+            .await
+    }
 }
 
 impl MirrorStrategy {
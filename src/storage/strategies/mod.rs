@@ -2,11 +2,13 @@ pub mod backup;
 pub mod mirror;
 pub mod single;
 
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
 use bytes::Bytes;
 
-use crate::storage::{stream::BytesStream, Storage, StorageResult};
+use crate::storage::{
+    drivers::PresignedUrl, stream::BytesStream, Storage, StorageError, StorageResult,
+};
 
 #[async_trait::async_trait]
 pub trait StorageStrategy: Sync + Send {
@@ -30,4 +32,28 @@ pub trait StorageStrategy: Sync + Send {
         path: &Path,
         stream: BytesStream,
     ) -> StorageResult<()>;
+
+    /// Presign a URL to download the given path directly from a store.
+    ///
+    /// Strategies not implementing it do not support presigned URLs.
+    async fn presigned_url(
+        &self,
+        _storage: &Storage,
+        _path: &Path,
+        _expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        Err(StorageError::Unsupported("presigned URLs".to_string()))
+    }
+
+    /// Presign a URL to upload to the given path directly to a store.
+    ///
+    /// Strategies not implementing it do not support presigned URLs.
+    async fn presigned_upload_url(
+        &self,
+        _storage: &Storage,
+        _path: &Path,
+        _expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        Err(StorageError::Unsupported("presigned URLs".to_string()))
+    }
 }
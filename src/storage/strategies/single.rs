@@ -2,11 +2,11 @@
 //!
 //! This module provides an implementation of the [`StorageStrategy`] for a
 //! single storage strategy.
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
 use bytes::Bytes;
 
-use crate::storage::{strategies::StorageStrategy, Storage, StorageResult};
+use crate::storage::{drivers::PresignedUrl, strategies::StorageStrategy, Storage, StorageResult};
 
 /// Represents a single storage strategy.
 #[derive(Clone)]
@@ -111,6 +111,40 @@ impl StorageStrategy for SingleStrategy {
             .await?;
         Ok(())
     }
+
+    /// Presigns a download URL from the primary storage
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StorageResult`] with the presigned URL
+    async fn presigned_url(
+        &self,
+        storage: &Storage,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        storage
+            .as_store_err(&self.primary)?
+            .presigned_url(path, expiry)
+            .await
+    }
+
+    /// Presigns an upload URL to the primary storage
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StorageResult`] with the presigned URL
+    async fn presigned_upload_url(
+        &self,
+        storage: &Storage,
+        path: &Path,
+        expiry: Duration,
+    ) -> StorageResult<PresignedUrl> {
+        storage
+            .as_store_err(&self.primary)?
+            .presigned_upload_url(path, expiry)
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -119,7 +153,7 @@ mod tests {
     use std::{collections::BTreeMap, path::PathBuf};
 
     use super::*;
-    use crate::storage::{drivers, Storage};
+    use crate::storage::{drivers, stream::BytesStream, Storage, StorageError};
 
     #[tokio::test]
     async fn can_upload() {
@@ -234,4 +268,50 @@ mod tests {
         assert!(store.exists(orig_path.as_path()).await.unwrap());
         assert!(store.exists(new_path.as_path()).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn can_upload_stream_in_parts() {
+        let store = Box::new(
+            drivers::opendal_adapter::OpendalAdapter::new(
+                opendal::Operator::new(opendal::services::Memory::default())
+                    .unwrap()
+                    .finish(),
+            )
+            .with_multipart(4, 2),
+        ) as Box<dyn drivers::StoreDriver>;
+
+        let strategy = Box::new(SingleStrategy::new("default")) as Box<dyn StorageStrategy>;
+
+        let storage = Storage::new(BTreeMap::from([("default".to_string(), store)]), strategy);
+
+        let path = PathBuf::from("users").join("data").join("1.txt");
+        let chunks = ["file ", "content ", "in parts"].map(|chunk| Ok(Bytes::from(chunk)));
+        let stream = BytesStream::from_body_stream(futures_util::stream::iter(chunks));
+
+        assert!(storage.upload_stream(path.as_path(), stream).await.is_ok());
+
+        let download_file: String = storage.download(path.as_path()).await.unwrap();
+        assert_eq!(download_file, "file content in parts");
+    }
+
+    #[tokio::test]
+    async fn cannot_presign_with_mem_store() {
+        let store = drivers::mem::new();
+
+        let strategy = Box::new(SingleStrategy::new("default")) as Box<dyn StorageStrategy>;
+
+        let storage = Storage::new(BTreeMap::from([("default".to_string(), store)]), strategy);
+
+        let path = PathBuf::from("users").join("data").join("1.txt");
+        let expiry = Duration::from_secs(60);
+
+        assert!(matches!(
+            storage.presigned_url(path.as_path(), expiry).await,
+            Err(StorageError::Unsupported(_))
+        ));
+        assert!(matches!(
+            storage.presigned_upload_url(path.as_path(), expiry).await,
+            Err(StorageError::Unsupported(_))
+        ));
+    }
 }
@@ -0,0 +1,541 @@
+//! # Multi-tenancy
+//!
+//! Serves several tenants from one app. The tenant of a request is resolved
+//! by a [`TenantResolver`], by subdomain, header or JWT claim as configured in
+//! [`config::Tenancy`], and the data of tenants is kept apart as its
+//! `isolation` says:
+//!
+//! * `Row`: tenants share the app's tables, whose rows are stamped with their
+//!   tenant, see `cargo loco generate scaffold --tenant`.
+//! * `Schema`: each tenant has a Postgres schema of its own, `tenant_<id>`,
+//!   which the tenant's connection resolves table names in.
+//! * `Database`: each tenant has a database of its own.
+//!
+//! Handlers take the [`crate::controller::extractor::tenant::Tenant`] of the
+//! request, which comes with the connection to its data. `db migrate`
+//! migrates the tenants [`Hooks::tenants`] lists along with the app's
+//! database, and with schema or database isolation, only those tenants are
+//! served.
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use axum::http::{header, request::Parts};
+use futures_util::future::BoxFuture;
+use sea_orm::{ConnectionTrait, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
+use tracing::{info, warn};
+
+use crate::{
+    app::{AppContext, Hooks},
+    config::{self, TenantIsolation},
+    db, Error, Result,
+};
+
+/// The longest tenant id, which leaves room for the `tenant_` prefix of its
+/// schema within the 63 bytes of a Postgres identifier.
+const MAX_ID_LEN: usize = 48;
+
+/// Resolves the tenant of a request, see [`Hooks::tenant_resolver`].
+#[async_trait]
+pub trait TenantResolver: Send + Sync {
+    /// The id of the tenant the request is for, if it names one.
+    ///
+    /// # Errors
+    ///
+    /// When the tenant cannot be resolved, e.g. the request's token is invalid
+    async fn resolve(&self, parts: &Parts, ctx: &AppContext) -> Result<Option<String>>;
+}
+
+/// Resolves the tenant from the subdomain of the `Host`, e.g. `acme` of
+/// `acme.myapp.com`.
+pub struct Subdomain {
+    pub domain: String,
+}
+
+#[async_trait]
+impl TenantResolver for Subdomain {
+    async fn resolve(&self, parts: &Parts, _ctx: &AppContext) -> Result<Option<String>> {
+        let host = parts
+            .headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .or_else(|| parts.uri.host());
+        Ok(host.and_then(|host| subdomain(host, &self.domain)))
+    }
+}
+
+/// Resolves the tenant from a request header.
+pub struct Header {
+    pub name: String,
+}
+
+#[async_trait]
+impl TenantResolver for Header {
+    async fn resolve(&self, parts: &Parts, _ctx: &AppContext) -> Result<Option<String>> {
+        parts
+            .headers
+            .get(&self.name)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map(|value| value.trim().to_string())
+                    .map_err(|err| Error::BadRequest(err.to_string()))
+            })
+            .transpose()
+    }
+}
+
+/// Resolves the tenant from a claim of the request's JWT, a string or a
+/// number.
+#[cfg(feature = "auth_jwt")]
+pub struct Claim {
+    pub name: String,
+}
+
+#[cfg(feature = "auth_jwt")]
+#[async_trait]
+impl TenantResolver for Claim {
+    async fn resolve(&self, parts: &Parts, ctx: &AppContext) -> Result<Option<String>> {
+        use crate::controller::extractor::auth;
+
+        let jwt_config = auth::get_jwt_from_config(ctx)?;
+        let Ok(token) = auth::extract_token(jwt_config, parts) else {
+            return Ok(None);
+        };
+        let claims = crate::auth::jwt::JWT::new(&jwt_config.secret)
+            .validate(&token)
+            .map_err(|err| Error::Unauthorized(err.to_string()))?
+            .claims;
+        Ok(match claims.claims.get(&self.name) {
+            Some(serde_json::Value::String(tenant)) => Some(tenant.clone()),
+            Some(serde_json::Value::Number(tenant)) => Some(tenant.to_string()),
+            _ => None,
+        })
+    }
+}
+
+/// The resolver `tenancy.resolver` configures.
+#[must_use]
+pub fn resolver(config: &config::TenantResolver) -> Box<dyn TenantResolver> {
+    match config {
+        config::TenantResolver::Subdomain { domain } => Box::new(Subdomain {
+            domain: domain.clone(),
+        }),
+        config::TenantResolver::Header { name } => Box::new(Header { name: name.clone() }),
+        #[cfg(feature = "auth_jwt")]
+        config::TenantResolver::Claim { name } => Box::new(Claim { name: name.clone() }),
+    }
+}
+
+/// Lists the tenants of the app, [`Hooks::tenants`] unless in tests.
+pub type ListTenants = for<'a> fn(&'a AppContext) -> BoxFuture<'a, Result<Vec<String>>>;
+
+/// The tenants [`Hooks::tenants`] lists, as a [`ListTenants`].
+#[must_use]
+pub fn list_tenants<H: Hooks>(ctx: &AppContext) -> BoxFuture<'_, Result<Vec<String>>> {
+    H::tenants(ctx)
+}
+
+/// The tenancy of the app, kept in [`AppContext::shared_store`] when
+/// `tenancy` is configured: how tenants are resolved, and the connections of
+/// the tenants served so far.
+pub struct Tenants {
+    config: config::Tenancy,
+    resolver: Box<dyn TenantResolver>,
+    list: ListTenants,
+    connections: tokio::sync::Mutex<HashMap<String, (DatabaseConnection, Instant)>>,
+}
+
+impl Tenants {
+    #[must_use]
+    pub fn new(
+        config: config::Tenancy,
+        resolver: Box<dyn TenantResolver>,
+        list: ListTenants,
+    ) -> Self {
+        Self {
+            config,
+            resolver,
+            list,
+            connections: tokio::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The tenancy of the app, unless it is not configured.
+    #[must_use]
+    pub fn get(ctx: &AppContext) -> Option<Arc<Self>> {
+        ctx.shared_store.get::<Arc<Self>>()
+    }
+
+    /// Resolves the tenant of a request, if it names one.
+    ///
+    /// # Errors
+    ///
+    /// When the tenant is not a valid id, or not among the configured
+    /// `tenants` when they are given
+    pub async fn resolve(&self, parts: &Parts, ctx: &AppContext) -> Result<Option<String>> {
+        let Some(tenant) = self.resolver.resolve(parts, ctx).await? else {
+            return Ok(None);
+        };
+        if !is_valid_id(&tenant) {
+            return Err(Error::BadRequest(format!("invalid tenant `{tenant}`")));
+        }
+        if !self.config.tenants.is_empty() && !self.config.tenants.contains(&tenant) {
+            return Err(Error::NotFound);
+        }
+        Ok(Some(tenant))
+    }
+
+    /// The connection to the data of `tenant`: the app's one with row
+    /// isolation, or else a pool of its own, opened the first time the tenant
+    /// is served. Only the tenants [`Hooks::tenants`] lists get a pool, and
+    /// at most `max_pools` are kept, closing the least recently used.
+    ///
+    /// # Errors
+    ///
+    /// When the tenant is not listed, or its database cannot be connected to
+    pub async fn connection(&self, ctx: &AppContext, tenant: &str) -> Result<DatabaseConnection> {
+        if matches!(self.config.isolation, TenantIsolation::Row) {
+            return Ok(ctx.db.clone());
+        }
+        if let Some((db, used)) = self.connections.lock().await.get_mut(tenant) {
+            *used = Instant::now();
+            return Ok(db.clone());
+        }
+        // listing and connecting can take a while, other tenants are served
+        // from the cache meanwhile
+        if !(self.list)(ctx)
+            .await?
+            .iter()
+            .any(|listed| listed == tenant)
+        {
+            return Err(Error::NotFound);
+        }
+        let db = connect(&ctx.config.database, &self.config.isolation, tenant).await?;
+        let mut connections = self.connections.lock().await;
+        if !connections.contains_key(tenant) && connections.len() >= self.config.max_pools.max(1) {
+            let unused = connections
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(unused, _)| unused.clone());
+            // requests still holding its pool keep it open until they end
+            if let Some(unused) = unused {
+                connections.remove(&unused);
+            }
+        }
+        let existing = match connections.entry(tenant.to_string()) {
+            // a concurrent request connected first, its pool is kept
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().1 = Instant::now();
+                entry.get().0.clone()
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((db.clone(), Instant::now()));
+                return Ok(db);
+            }
+        };
+        drop(connections);
+        if let Err(err) = db.close().await {
+            warn!(tenant, error = %err, "could not close a redundant tenant pool");
+        }
+        Ok(existing)
+    }
+}
+
+/// Migrates the data of each tenant [`Hooks::tenants`] lists, creating their
+/// schemas with schema isolation. Tenants share the app's tables with row
+/// isolation, which leaves nothing to do.
+///
+/// # Errors
+///
+/// When a tenant id is invalid, or a tenant cannot be migrated
+pub async fn migrate<H: Hooks, M: MigratorTrait>(ctx: &AppContext) -> Result<()> {
+    let Some(tenancy) = &ctx.config.tenancy else {
+        return Ok(());
+    };
+    if matches!(tenancy.isolation, TenantIsolation::Row) {
+        return Ok(());
+    }
+    for tenant in H::tenants(ctx).await? {
+        if !is_valid_id(&tenant) {
+            return Err(Error::Message(format!("invalid tenant `{tenant}`")));
+        }
+        info!(tenant, "migrating tenant");
+        if matches!(tenancy.isolation, TenantIsolation::Schema) {
+            ctx.db
+                .execute_unprepared(&format!(
+                    "CREATE SCHEMA IF NOT EXISTS \"{}\"",
+                    schema_name(&tenant)
+                ))
+                .await?;
+        }
+        let db = connect(&ctx.config.database, &tenancy.isolation, &tenant).await?;
+        db::migrate::<M>(&db).await?;
+        db.close().await?;
+    }
+    Ok(())
+}
+
+/// The Postgres schema of `tenant` with schema isolation.
+#[must_use]
+pub fn schema_name(tenant: &str) -> String {
+    format!("tenant_{tenant}")
+}
+
+/// Whether `tenant` is a valid tenant id: lowercase letters, digits, `-` and
+/// `_`, which name a schema or a database safely.
+#[must_use]
+pub fn is_valid_id(tenant: &str) -> bool {
+    !tenant.is_empty()
+        && tenant.len() <= MAX_ID_LEN
+        && tenant
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+async fn connect(
+    database: &config::Database,
+    isolation: &TenantIsolation,
+    tenant: &str,
+) -> Result<DatabaseConnection> {
+    Ok(match isolation {
+        TenantIsolation::Row => db::connect(database).await?,
+        TenantIsolation::Schema => db::connect_to_schema(database, &schema_name(tenant)).await?,
+        TenantIsolation::Database { uri } => {
+            db::connect(&config::Database {
+                uri: uri.replace("{tenant}", tenant),
+                ..database.clone()
+            })
+            .await?
+        }
+    })
+}
+
+/// The subdomain of `host`, without its port, under `domain`.
+fn subdomain(host: &str, domain: &str) -> Option<String> {
+    let host = host.split(':').next()?.to_ascii_lowercase();
+    let subdomain = host
+        .strip_suffix(&domain.to_ascii_lowercase())?
+        .strip_suffix('.')?;
+    (!subdomain.is_empty() && !subdomain.contains('.')).then(|| subdomain.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+    use sea_orm::Statement;
+    use tree_fs::TreeBuilder;
+
+    use super::*;
+    use crate::tests_cfg;
+
+    fn parts(headers: &[(&str, &str)]) -> Parts {
+        let mut request = Request::builder().uri("/");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn can_take_subdomain() {
+        assert_eq!(
+            subdomain("acme.myapp.com", "myapp.com"),
+            Some("acme".to_string())
+        );
+        assert_eq!(
+            subdomain("ACME.MyApp.com:5150", "myapp.com"),
+            Some("acme".to_string())
+        );
+        assert_eq!(subdomain("myapp.com", "myapp.com"), None);
+        assert_eq!(subdomain("a.b.myapp.com", "myapp.com"), None);
+        assert_eq!(subdomain("acmemyapp.com", "myapp.com"), None);
+        assert_eq!(subdomain("acme.other.com", "myapp.com"), None);
+    }
+
+    #[test]
+    fn can_validate_id() {
+        assert!(is_valid_id("acme"));
+        assert!(is_valid_id("acme-eu_1"));
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("Acme"));
+        assert!(!is_valid_id("acme\"; drop"));
+        assert!(!is_valid_id(&"a".repeat(MAX_ID_LEN + 1)));
+    }
+
+    #[tokio::test]
+    async fn can_resolve_tenant() {
+        let ctx = tests_cfg::app::get_app_context().await;
+        let tenants = Tenants::new(
+            config::Tenancy {
+                resolver: config::TenantResolver::Header {
+                    name: "x-tenant-id".to_string(),
+                },
+                isolation: TenantIsolation::Row,
+                tenants: vec!["acme".to_string()],
+                max_pools: 64,
+            },
+            resolver(&config::TenantResolver::Header {
+                name: "x-tenant-id".to_string(),
+            }),
+            list_tenants::<tests_cfg::db::AppHook>,
+        );
+
+        assert_eq!(
+            tenants
+                .resolve(&parts(&[("x-tenant-id", "acme")]), &ctx)
+                .await
+                .unwrap(),
+            Some("acme".to_string())
+        );
+        assert_eq!(tenants.resolve(&parts(&[]), &ctx).await.unwrap(), None);
+        assert!(matches!(
+            tenants
+                .resolve(&parts(&[("x-tenant-id", "globex")]), &ctx)
+                .await,
+            Err(Error::NotFound)
+        ));
+        assert!(matches!(
+            tenants
+                .resolve(&parts(&[("x-tenant-id", "../acme")]), &ctx)
+                .await,
+            Err(Error::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_connect_to_tenant_database() {
+        let tree = TreeBuilder::default().create().unwrap();
+        let ctx = tests_cfg::app::get_app_context().await;
+        let tenants = Tenants::new(
+            config::Tenancy {
+                resolver: config::TenantResolver::Header {
+                    name: "x-tenant-id".to_string(),
+                },
+                isolation: TenantIsolation::Database {
+                    uri: format!(
+                        "sqlite://{}/{{tenant}}.sqlite?mode=rwc",
+                        tree.root.display()
+                    ),
+                },
+                tenants: vec![],
+                max_pools: 2,
+            },
+            resolver(&config::TenantResolver::Header {
+                name: "x-tenant-id".to_string(),
+            }),
+            |_| {
+                Box::pin(async {
+                    Ok(["acme", "globex", "initech"]
+                        .map(ToString::to_string)
+                        .to_vec())
+                })
+            },
+        );
+
+        let acme = tenants.connection(&ctx, "acme").await.unwrap();
+        acme.execute_unprepared("CREATE TABLE loco (name TEXT)")
+            .await
+            .unwrap();
+        assert!(tree.root.join("acme.sqlite").exists());
+
+        // the connection is kept for the next requests of the tenant
+        let again = tenants.connection(&ctx, "acme").await.unwrap();
+        again
+            .execute_unprepared("INSERT INTO loco (name) VALUES ('loco')")
+            .await
+            .unwrap();
+
+        let globex = tenants.connection(&ctx, "globex").await.unwrap();
+        assert!(globex
+            .query_one(Statement::from_string(
+                globex.get_database_backend(),
+                "SELECT * FROM loco"
+            ))
+            .await
+            .is_err());
+
+        // tenants not listed get no pool, nor a database
+        assert!(matches!(
+            tenants.connection(&ctx, "umbrella").await,
+            Err(Error::NotFound)
+        ));
+        assert!(!tree.root.join("umbrella.sqlite").exists());
+
+        // past `max_pools`, the least recently used pool is closed
+        tenants.connection(&ctx, "acme").await.unwrap();
+        tenants.connection(&ctx, "initech").await.unwrap();
+        let connections = tenants.connections.lock().await;
+        let mut served = connections.keys().cloned().collect::<Vec<_>>();
+        served.sort();
+        assert_eq!(served, ["acme", "initech"]);
+    }
+
+    #[tokio::test]
+    async fn can_serve_cached_tenants_while_connecting() {
+        static LISTING: tokio::sync::Notify = tokio::sync::Notify::const_new();
+        static SLOW: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+        let tree = TreeBuilder::default().create().unwrap();
+        let ctx = tests_cfg::app::get_app_context().await;
+        let tenants = Tenants::new(
+            config::Tenancy {
+                resolver: config::TenantResolver::Header {
+                    name: "x-tenant-id".to_string(),
+                },
+                isolation: TenantIsolation::Database {
+                    uri: format!(
+                        "sqlite://{}/{{tenant}}.sqlite?mode=rwc",
+                        tree.root.display()
+                    ),
+                },
+                tenants: vec![],
+                max_pools: 2,
+            },
+            resolver(&config::TenantResolver::Header {
+                name: "x-tenant-id".to_string(),
+            }),
+            |_| {
+                Box::pin(async {
+                    if SLOW.load(std::sync::atomic::Ordering::SeqCst) {
+                        LISTING.notified().await;
+                    }
+                    Ok(["acme", "globex", "initech"]
+                        .map(ToString::to_string)
+                        .to_vec())
+                })
+            },
+        );
+        tenants.connection(&ctx, "acme").await.unwrap();
+
+        // a cached tenant is served while another one is still being listed
+        SLOW.store(true, std::sync::atomic::Ordering::SeqCst);
+        let (globex, acme) = tokio::join!(tenants.connection(&ctx, "globex"), async {
+            let acme = tokio::time::timeout(
+                std::time::Duration::from_secs(1),
+                tenants.connection(&ctx, "acme"),
+            )
+            .await;
+            LISTING.notify_one();
+            acme
+        });
+        assert!(acme.expect("acme waited on globex's listing").is_ok());
+        assert!(globex.is_ok());
+        SLOW.store(false, std::sync::atomic::Ordering::SeqCst);
+
+        // tenants connected concurrently keep a single pool
+        let (first, second) = tokio::join!(
+            tenants.connection(&ctx, "initech"),
+            tenants.connection(&ctx, "initech")
+        );
+        first.unwrap();
+        second.unwrap();
+        let connections = tenants.connections.lock().await;
+        assert_eq!(connections.len(), 2);
+        assert!(connections.contains_key("initech"));
+    }
+}
@@ -1,13 +1,29 @@
 use crate::{
     app::{AppContext, Hooks},
-    db, hash, Error, Result,
+    boot,
+    config::Config,
+    db,
+    environment::Environment,
+    hash, Error, Result,
 };
+use sha2::{Digest, Sha256};
 use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
 use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::OnceLock;
 use tree_fs::TreeBuilder;
 
+/// The template databases of this test run, by the database URI of the test
+/// config they were created for.
+static TEMPLATES: OnceLock<tokio::sync::Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// Serializes creating the Postgres template database and cloning it, which
+/// fails while another session is connected to the template, across test
+/// processes.
+const TEMPLATE_LOCK_KEY: i64 = 0x6c6f_636f_7465_7374;
+
 /// Seeds data into the database.
 ///
 ///
@@ -52,6 +68,177 @@ pub fn init_test_db_creation(conn_str: &str) -> Result<Box<dyn TestSupport>> {
     }
 }
 
+/// Initializes a test database cloned from a template database, which is
+/// created and migrated, by booting the app against it, once per test run.
+/// Cloning is much faster than migrating, and dropping the clone when the test
+/// ends rolls back whatever it wrote.
+///
+/// `PostgreSQL` clones with `CREATE DATABASE .. TEMPLATE`, `SQLite` copies the
+/// database file. Other databases are used as they are.
+///
+/// # Errors
+/// Returns an error if the template database could not be created.
+pub async fn init_test_db_from_template<H: Hooks>(config: &Config) -> Result<Box<dyn TestSupport>> {
+    let conn_str = &config.database.uri;
+    let template = template_for::<H>(config).await?;
+    if conn_str.starts_with("postgres://") {
+        PostgresTest::new(conn_str)
+            .map(|test| Box::new(test.with_template(&template)) as Box<dyn TestSupport>)
+    } else if conn_str.starts_with("sqlite://") {
+        SqliteTest::new(conn_str)
+            .map(|test| Box::new(test.with_template(&template)) as Box<dyn TestSupport>)
+    } else {
+        Ok(Box::new(Any::new(conn_str)))
+    }
+}
+
+/// The template database for `config`, created on first use: the name of the
+/// `PostgreSQL` database, or the path of the `SQLite` file.
+async fn template_for<H: Hooks>(config: &Config) -> Result<String> {
+    let mut templates = TEMPLATES
+        .get_or_init(|| tokio::sync::Mutex::new(HashMap::new()))
+        .lock()
+        .await;
+    if let Some(template) = templates.get(&config.database.uri) {
+        return Ok(template.clone());
+    }
+
+    let conn_str = &config.database.uri;
+    let template = if conn_str.starts_with("postgres://") {
+        create_postgres_template::<H>(config).await?
+    } else if conn_str.starts_with("sqlite://") {
+        create_sqlite_template::<H>(config).await?
+    } else {
+        String::new()
+    };
+    templates.insert(conn_str.clone(), template.clone());
+    Ok(template)
+}
+
+/// Boots the app against `conn_str` to migrate it, closing its connections
+/// after.
+async fn migrate_template<H: Hooks>(config: &Config, conn_str: &str) -> Result<()> {
+    let mut config = config.clone();
+    config.database.uri = conn_str.to_string();
+    let boot = H::boot(boot::StartMode::ServerOnly, &Environment::Test, config).await?;
+    boot.app_context.db.clone().close().await?;
+    Ok(())
+}
+
+/// Fingerprints the migrations in `dir`, the app's `migration/src` folder, by
+/// the path and content of every file in it, so that the template is rebuilt
+/// only when a migration is added or changed.
+fn migrations_fingerprint(dir: &Path) -> Result<String> {
+    fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                collect(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut hasher = Sha256::new();
+    if dir.exists() {
+        let mut files = Vec::new();
+        collect(dir, &mut files).map_err(Error::wrap)?;
+        files.sort();
+        for file in files {
+            let name = file.strip_prefix(dir).unwrap_or(&file);
+            hasher.update(name.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(std::fs::read(&file).map_err(Error::wrap)?);
+            hasher.update([0]);
+        }
+    }
+    let digest = hasher.finalize();
+    Ok(digest.iter().take(6).map(|b| format!("{b:02x}")).collect())
+}
+
+/// Creates the `<db name>_loco_template_<fingerprint>` database, or reuses it
+/// when an earlier process or test run already did, as every process migrates
+/// it the same. Templates of other fingerprints are dropped, and a new one is
+/// migrated under another name first so an interrupted run never leaves a
+/// half-migrated template to reuse.
+async fn create_postgres_template<H: Hooks>(config: &Config) -> Result<String> {
+    let conn_str = &config.database.uri;
+    let db_name = db::extract_db_name(conn_str)?;
+    let prefix = format!("{db_name}_loco_template");
+    let template = format!(
+        "{prefix}_{}",
+        migrations_fingerprint(Path::new("migration/src"))?
+    );
+    let building = format!("{template}_new");
+    let root_pool = Pool::<Postgres>::connect(&conn_str.replace(db_name, "postgres"))
+        .await
+        .map_err(Error::wrap)?;
+
+    let mut conn = root_pool.acquire().await.map_err(Error::wrap)?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(TEMPLATE_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map_err(Error::wrap)?;
+    let res = async {
+        let existing: Vec<String> =
+            sqlx::query_scalar("SELECT datname FROM pg_database WHERE starts_with(datname, $1)")
+                .bind(&prefix)
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(Error::wrap)?;
+        if existing.contains(&template) {
+            return Ok(());
+        }
+        for stale in existing {
+            if let Err(err) = sqlx::query(&format!("DROP DATABASE IF EXISTS {stale}"))
+                .execute(&mut *conn)
+                .await
+            {
+                tracing::warn!(database = stale, error = %err, "could not drop stale template");
+            }
+        }
+        sqlx::query(&format!("CREATE DATABASE {building}"))
+            .execute(&mut *conn)
+            .await
+            .map_err(Error::wrap)?;
+        migrate_template::<H>(config, &conn_str.replace(db_name, &building)).await?;
+        sqlx::query(&format!("ALTER DATABASE {building} RENAME TO {template}"))
+            .execute(&mut *conn)
+            .await
+            .map_err(Error::wrap)?;
+        Ok(())
+    }
+    .await;
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(TEMPLATE_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map_err(Error::wrap)?;
+    res.map(|()| template)
+}
+
+/// Migrates a `SQLite` file in a temporary folder, which is left for the rest
+/// of the test run.
+async fn create_sqlite_template<H: Hooks>(config: &Config) -> Result<String> {
+    let conn_str = &config.database.uri;
+    let db_name = db::extract_db_name(conn_str)?;
+    let tree = TreeBuilder::default()
+        .add_empty_file("template.sqlite")
+        .drop(false)
+        .create()
+        .map_err(|err| {
+            Error::string(&format!(
+                "could not create template database directory. err: {err}"
+            ))
+        })?;
+    let path = tree.root.join("template.sqlite").display().to_string();
+    migrate_template::<H>(config, &conn_str.replace(db_name, &path)).await?;
+    Ok(path)
+}
+
 pub trait TestSupport: Send + Sync {
     /// Initializes the database.
     fn init_db<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
@@ -65,6 +252,7 @@ pub struct PostgresTest {
     root_connection_string: String,
     connection_string: String,
     schema_name: String,
+    template: Option<String>,
 }
 
 impl PostgresTest {
@@ -83,8 +271,16 @@ impl PostgresTest {
             root_connection_string: conn_str.replace(db_name, "postgres"),
             connection_string: conn_str.replace(db_name, &test_schema_name),
             schema_name: test_schema_name,
+            template: None,
         })
     }
+
+    /// Clones the database from the `template` database.
+    #[must_use]
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_string());
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -98,12 +294,30 @@ impl TestSupport for PostgresTest {
             let pool = Pool::<Postgres>::connect(&self.root_connection_string)
                 .await
                 .expect("db connection should success");
-            let query = format!("CREATE DATABASE {};", self.schema_name);
+            let Some(template) = &self.template else {
+                let query = format!("CREATE DATABASE {};", self.schema_name);
 
-            sqlx::query(&query)
-                .execute(&pool)
+                sqlx::query(&query)
+                    .execute(&pool)
+                    .await
+                    .expect("create DB schema");
+                return;
+            };
+
+            let mut conn = pool.acquire().await.expect("db connection should success");
+            sqlx::query("SELECT pg_advisory_lock($1)")
+                .bind(TEMPLATE_LOCK_KEY)
+                .execute(&mut *conn)
                 .await
-                .expect("create DB schema");
+                .expect("lock template");
+            let query = format!("CREATE DATABASE {} TEMPLATE {template};", self.schema_name);
+            let res = sqlx::query(&query).execute(&mut *conn).await;
+            sqlx::query("SELECT pg_advisory_unlock($1)")
+                .bind(TEMPLATE_LOCK_KEY)
+                .execute(&mut *conn)
+                .await
+                .expect("unlock template");
+            res.expect("clone DB from template");
         })
     }
 
@@ -131,6 +345,7 @@ impl TestSupport for PostgresTest {
 pub struct SqliteTest {
     connection_string: String,
     db_folder: PathBuf,
+    template: Option<PathBuf>,
     _tree: tree_fs::Tree, // Keep the tree alive while the test runs
 }
 
@@ -157,9 +372,17 @@ impl SqliteTest {
                 &tree.root.join("test.sqlite").display().to_string(),
             ),
             db_folder: tree.root.clone(),
+            template: None,
             _tree: tree,
         })
     }
+
+    /// Copies the database from the `template` file.
+    #[must_use]
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.template = Some(PathBuf::from(template));
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -168,7 +391,12 @@ impl TestSupport for SqliteTest {
         &self.connection_string
     }
     fn init_db<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
-        Box::pin(async move {})
+        Box::pin(async move {
+            if let Some(template) = &self.template {
+                std::fs::copy(template, self.db_folder.join("test.sqlite"))
+                    .expect("copy DB from template");
+            }
+        })
     }
 
     fn cleanup_db(&self) {
@@ -230,6 +458,81 @@ mod tests {
         assert!(!sqlite.db_folder.exists());
     }
 
+    #[tokio::test]
+    async fn sqlite_test_support_from_template() {
+        use sea_orm::{ConnectionTrait, Database};
+
+        let mut config = crate::tests_cfg::config::test_config();
+        config.database.uri = "sqlite://loco_template_test.sqlite?mode=rwc".to_string();
+
+        let template = template_for::<crate::tests_cfg::db::AppHook>(&config)
+            .await
+            .expect("create template");
+        let db = Database::connect(format!("sqlite://{template}"))
+            .await
+            .unwrap();
+        db.execute_unprepared("CREATE TABLE loco (name TEXT)")
+            .await
+            .unwrap();
+        db.close().await.unwrap();
+
+        let first = init_test_db_from_template::<crate::tests_cfg::db::AppHook>(&config)
+            .await
+            .expect("create Sqlite test support");
+        first.init_db().await;
+        let db = Database::connect(first.get_connection_str()).await.unwrap();
+        db.execute_unprepared("INSERT INTO loco (name) VALUES ('loco')")
+            .await
+            .unwrap();
+        db.close().await.unwrap();
+
+        let second = init_test_db_from_template::<crate::tests_cfg::db::AppHook>(&config)
+            .await
+            .expect("create Sqlite test support");
+        second.init_db().await;
+        let db = Database::connect(second.get_connection_str())
+            .await
+            .unwrap();
+        assert_eq!(
+            crate::tests_cfg::db::get_value(&db, "SELECT COUNT(*) FROM loco").await,
+            "0"
+        );
+        db.close().await.unwrap();
+
+        first.cleanup_db();
+        second.cleanup_db();
+    }
+
+    #[test]
+    fn can_fingerprint_migrations() {
+        let tree = TreeBuilder::default()
+            .add("lib.rs", "mod m20240101_000001_users;")
+            .add("m20240101_000001_users.rs", "create users")
+            .create()
+            .unwrap();
+        let fingerprint = migrations_fingerprint(&tree.root).unwrap();
+        assert_eq!(fingerprint.len(), 12);
+        assert_eq!(fingerprint, migrations_fingerprint(&tree.root).unwrap());
+
+        std::fs::write(
+            tree.root.join("m20240101_000001_users.rs"),
+            "create users, v2",
+        )
+        .unwrap();
+        let changed = migrations_fingerprint(&tree.root).unwrap();
+        assert_ne!(fingerprint, changed);
+
+        std::fs::write(tree.root.join("m20240102_000001_posts.rs"), "create posts").unwrap();
+        assert_ne!(changed, migrations_fingerprint(&tree.root).unwrap());
+
+        assert_eq!(
+            migrations_fingerprint(&tree.root.join("missing"))
+                .unwrap()
+                .len(),
+            12
+        );
+    }
+
     #[tokio::test]
     async fn postgres_test_support() {
         let (conn, _container) = crate::tests_cfg::postgres::setup_postgres_container().await;
@@ -222,6 +222,42 @@ pub async fn boot_test_with_create_db<H: Hooks>() -> Result<BootResultWrapper> {
     Ok(BootResultWrapper::new(boot, test_db))
 }
 
+/// Bootstraps the test application with a test environment and a database of
+/// its own, cloned from a template database migrated once per test run.
+///
+/// The database is dropped when the returned [`BootResultWrapper`] is, rolling
+/// back whatever the test wrote, so tests do not see each other's data
+/// whatever order they run in. See [`super::db::init_test_db_from_template`].
+///
+/// ```rust,ignore
+/// use myapp::app::App;
+/// use loco_rs::testing::prelude::*;
+///
+/// #[tokio::test]
+/// async fn test_create_user() {
+///     let boot = boot_test_with_rollback::<App>().await;
+/// }
+/// ```
+///
+/// # Errors
+/// when could not bootstrap the test environment
+#[cfg(feature = "with-db")]
+pub async fn boot_test_with_rollback<H: Hooks>() -> Result<BootResultWrapper> {
+    let mut config = H::load_config(&Environment::Test).await?;
+    let test_db = super::db::init_test_db_from_template::<H>(&config).await?;
+    test_db.init_db().await;
+    config.database.uri = test_db.get_connection_str().to_string();
+    let boot = match H::boot(boot::StartMode::ServerOnly, &Environment::Test, config).await {
+        Ok(boot) => boot,
+        Err(err) => {
+            test_db.cleanup_db();
+            return Err(Error::string(&err.to_string()));
+        }
+    };
+
+    Ok(BootResultWrapper::new(boot, test_db))
+}
+
 /// Bootstraps test application with test environment hard coded,
 /// and with a unique port.
 ///
@@ -336,6 +372,38 @@ where
     request_config_with_create_db::<H, F, Fut>(RequestConfig::default(), callback).await;
 }
 
+/// Executes a test server request against a database of the test's own, which
+/// is rolled back when the test ends.
+///
+/// Unlike [`request_with_create_db`], the database is cloned from a template
+/// migrated once per test run instead of being migrated for every test. See
+/// [`boot_test_with_rollback`].
+///
+/// ```rust,ignore
+/// use myapp::app::App;
+///
+/// #[tokio::test]
+/// async fn can_register() {
+///     request_with_rollback::<App, _, _>(|request, ctx| async move {
+///         let response = request.post("/auth/register").json(&serde_json::json!({})).await;
+///     })
+///     .await;
+/// }
+/// ```
+///
+/// # Panics
+/// When could not initialize the test request.this errors can be when could not
+/// initialize the test app
+#[allow(clippy::future_not_send)]
+#[cfg(feature = "with-db")]
+pub async fn request_with_rollback<H: Hooks, F, Fut>(callback: F)
+where
+    F: FnOnce(TestServer, AppContext) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    request_config_with_rollback::<H, F, Fut>(RequestConfig::default(), callback).await;
+}
+
 /// Executes a test server request using a custom [`RequestConfig`].
 ///
 /// This function will boot the test environment without creating a new database.
@@ -387,3 +455,27 @@ where
     let boot_wrapper: BootResultWrapper = boot_test_with_create_db::<H>().await.unwrap();
     request_internal::<F, Fut>(callback, &boot_wrapper.inner, config).await;
 }
+
+/// Executes a test server request against a database rolled back when the
+/// test ends, see [`request_with_rollback`], using a custom [`RequestConfig`].
+///
+/// # Panics
+/// When the test request cannot be initialized, such as when the test app fails to start.
+///
+/// # Example
+/// ```rust,ignore
+/// let config = RequestConfigBuilder::new().save_cookies(true).build();
+/// request_config_with_rollback::<App, _, _>(config, |request, ctx| async move {
+///     let response = request.get("/endpoint").await;
+/// });
+/// ```
+#[allow(clippy::future_not_send)]
+#[cfg(feature = "with-db")]
+pub async fn request_config_with_rollback<H: Hooks, F, Fut>(config: RequestConfig, callback: F)
+where
+    F: FnOnce(TestServer, AppContext) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let boot_wrapper: BootResultWrapper = boot_test_with_rollback::<H>().await.unwrap();
+    request_internal::<F, Fut>(callback, &boot_wrapper.inner, config).await;
+}
@@ -25,6 +25,7 @@ pub fn test_config() -> Config {
             host: "localhost".to_string(),
             ident: None,
             middlewares: middleware::Config::default(),
+            readiness: config::Readiness::default(),
         },
         #[cfg(feature = "with-db")]
         database: get_database_config(),
@@ -35,6 +36,8 @@ pub fn test_config() -> Config {
         },
         mailer: None,
         initializers: None,
+        #[cfg(feature = "with-db")]
+        tenancy: None,
         settings: None,
         scheduler: Some(scheduler::Config {
             jobs: HashMap::from([(
@@ -43,6 +46,7 @@ pub fn test_config() -> Config {
                     run: "echo loco".to_string(),
                     shell: true,
                     run_on_start: false,
+                    no_overlap: false,
                     cron: "*/5 * * * * *".to_string(),
                     tags: Some(vec!["base".to_string()]),
                     output: None,
@@ -50,6 +54,8 @@ pub fn test_config() -> Config {
             )]),
 
             output: scheduler::Output::STDOUT,
+            lock: scheduler::Lock::Local,
+            lock_timeout: 3600,
         }),
         // Always use in-memory cache for tests if available
         #[cfg(feature = "cache_inmem")]
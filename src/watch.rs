@@ -0,0 +1,171 @@
+//! Development watch mode, behind `cargo loco start --watch`.
+//!
+//! Runs the app with `cargo loco start`, and rebuilds and restarts it when
+//! the files under `src/`, `config/`, `assets/` or `migration/` change. The
+//! changes are debounced, so that saving several files restarts it once.
+//!
+//! With server-side views, i.e. an `assets/views` directory, the app is
+//! started with [`livereload`] enabled: the open pages reload once it is
+//! back, and on changes of the views, which the view engine reloads without
+//! a restart.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{
+    event::{EventKind, ModifyKind},
+    RecursiveMode, Watcher,
+};
+
+use crate::{
+    controller::{middleware::livereload, views::engines::DEFAULT_ASSET_FOLDER},
+    Error, Result,
+};
+
+/// The directories whose changes restart the app
+pub const WATCHED_DIRS: &[&str] = &["src", "config", DEFAULT_ASSET_FOLDER, "migration"];
+
+/// How long the files have to stay unchanged before the app restarts
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Runs `cargo loco <args>` and restarts it on each change, until the
+/// process is interrupted.
+///
+/// # Errors
+///
+/// When the directories cannot be watched, or the app cannot be started or
+/// stopped
+pub fn run(args: &[String]) -> Result<()> {
+    let root = std::env::current_dir()?;
+    let views = root.join(DEFAULT_ASSET_FOLDER).join("views");
+    let livereload = views.exists();
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)
+        .map_err(|_| Error::string("error creating file watcher"))?;
+    for dir in WATCHED_DIRS
+        .iter()
+        .map(|dir| root.join(dir))
+        .filter(|dir| dir.exists())
+    {
+        watcher
+            .watch(&dir, RecursiveMode::Recursive)
+            .map_err(|err| Error::Message(format!("error watching `{}`: {err}", dir.display())))?;
+    }
+    // the views are reloaded by the running app
+    let reloaded = livereload.then_some(views.as_path());
+
+    let mut app = spawn(args, livereload)?;
+    while let Ok(event) = events.recv() {
+        let mut changed = changed_paths(event, reloaded);
+        if changed.is_empty() {
+            continue;
+        }
+        while let Ok(event) = events.recv_timeout(DEBOUNCE) {
+            changed.extend(changed_paths(event, reloaded));
+        }
+        tracing::info!(?changed, "files changed, restarting");
+        stop(&mut app)?;
+        app = spawn(args, livereload)?;
+    }
+    stop(&mut app)
+}
+
+fn spawn(args: &[String], livereload: bool) -> Result<Child> {
+    let mut command = Command::new("cargo");
+    command.arg("loco").args(args);
+    if livereload {
+        command.env(livereload::ENV_VAR, "1");
+    }
+    command
+        .spawn()
+        .map_err(|err| Error::Message(format!("failed to run `cargo loco`: {err}")))
+}
+
+/// Stops the app, unless it exited already, e.g. when it did not build
+fn stop(app: &mut Child) -> Result<()> {
+    if app.try_wait()?.is_none() {
+        app.kill()?;
+        app.wait()?;
+    }
+    Ok(())
+}
+
+/// The paths whose content changed, leaving out the `reloaded` directory
+fn changed_paths(event: notify::Result<notify::Event>, reloaded: Option<&Path>) -> Vec<PathBuf> {
+    let Ok(notify::Event { kind, paths, .. }) = event else {
+        return vec![];
+    };
+    if matches!(
+        kind,
+        EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))
+    ) {
+        return vec![];
+    }
+    paths
+        .into_iter()
+        .filter(|path| restarts(path, reloaded))
+        .collect()
+}
+
+/// Whether a change of the file restarts the app. The hidden and backup
+/// files of editors do not, nor the files of the `reloaded` directory.
+fn restarts(path: &Path, reloaded: Option<&Path>) -> bool {
+    let scratch = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') || name.ends_with('~'));
+    !scratch && !reloaded.is_some_and(|dir| path.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_tell_restarting_changes() {
+        let views = Path::new("/app/assets/views");
+        assert!(restarts(Path::new("/app/src/app.rs"), Some(views)));
+        assert!(restarts(
+            Path::new("/app/assets/static/app.css"),
+            Some(views)
+        ));
+        assert!(!restarts(
+            Path::new("/app/assets/views/home.html"),
+            Some(views)
+        ));
+        assert!(restarts(Path::new("/app/assets/views/home.html"), None));
+        assert!(!restarts(Path::new("/app/src/.app.rs.swp"), None));
+        assert!(!restarts(Path::new("/app/src/app.rs~"), None));
+    }
+
+    #[test]
+    fn ignores_access_and_metadata() {
+        let event = |kind| Ok(notify::Event::new(kind).add_path(PathBuf::from("/app/src/app.rs")));
+        assert!(changed_paths(
+            event(EventKind::Access(notify::event::AccessKind::Any)),
+            None
+        )
+        .is_empty());
+        assert!(changed_paths(
+            event(EventKind::Modify(ModifyKind::Metadata(
+                notify::event::MetadataKind::Any
+            ))),
+            None
+        )
+        .is_empty());
+        assert_eq!(
+            changed_paths(
+                event(EventKind::Modify(ModifyKind::Data(
+                    notify::event::DataChange::Content
+                ))),
+                None
+            ),
+            [PathBuf::from("/app/src/app.rs")]
+        );
+    }
+}
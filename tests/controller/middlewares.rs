@@ -148,6 +148,49 @@ async fn timeout(#[case] enable: bool) {
     handle.abort();
 }
 
+#[rstest]
+#[case(true)]
+#[case(false)]
+#[tokio::test]
+async fn rate_limit(#[case] enable: bool) {
+    use loco_rs::controller::middleware::rate_limit::{Quota, RateLimit};
+
+    let mut ctx: AppContext = tests_cfg::app::get_app_context().await;
+
+    ctx.config.server.middlewares.rate_limit = Some(RateLimit {
+        enable,
+        limit: Some(Quota {
+            requests: 1,
+            period: 60,
+        }),
+        ..Default::default()
+    });
+
+    let port = get_available_port().await;
+    let handle = infra_cfg::server::start_from_ctx(ctx, Some(port)).await;
+
+    let first = reqwest::get(get_base_url_port(port))
+        .await
+        .expect("response");
+    let second = reqwest::get(get_base_url_port(port))
+        .await
+        .expect("response");
+
+    assert_eq!(first.status(), StatusCode::OK);
+    if enable {
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        let headers = second.headers();
+        assert_eq!(headers["ratelimit-limit"], "1");
+        assert_eq!(headers["ratelimit-remaining"], "0");
+        assert_eq!(headers["retry-after"], "60");
+    } else {
+        assert_eq!(second.status(), StatusCode::OK);
+        assert!(second.headers().get("ratelimit-limit").is_none());
+    }
+
+    handle.abort();
+}
+
 #[rstest]
 #[case(true, "default", None, None, None)]
 #[case(true, "with_allow_headers", Some(vec!["token".to_string(), "user".to_string()]), None, None)]